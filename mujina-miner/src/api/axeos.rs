@@ -0,0 +1,126 @@
+//! AxeOS-compatible `/api/system/info` for tools written against stock
+//! Bitaxe firmware.
+//!
+//! AxeOS describes one device per host, so the whole miner is reported as
+//! a single device: hashrate, power and share counts are fleet totals,
+//! temperatures are the hottest sensor of each kind, and per-device fields
+//! (model, voltages, fan) come from the first board. Fields mujina does
+//! not measure are reported as zero rather than omitted, because clients
+//! expect every field to be present with its AxeOS type.
+//!
+//! | AxeOS field         | Source                                         |
+//! |---------------------|------------------------------------------------|
+//! | `hashRate`          | sum of thread hashrates, GH/s                  |
+//! | `temp`              | hottest `asic` sensor, Celsius                 |
+//! | `vrTemp`            | hottest `vr` sensor, Celsius                   |
+//! | `power`             | sum of reported power, W                       |
+//! | `voltage`           | first board's `input` voltage, mV              |
+//! | `current`           | first board's `core` current, mA               |
+//! | `coreVoltage`       | first board's `core` voltage, mV               |
+//! | `coreVoltageActual` | same as `coreVoltage`                          |
+//! | `frequency`         | 0; the ASIC clock is not in telemetry          |
+//! | `sharesAccepted`    | shares accepted, summed over sources           |
+//! | `sharesRejected`    | shares rejected, summed over sources           |
+//! | `bestDiff`          | best share difficulty as mujina displays it    |
+//! | `bestSessionDiff`   | same as `bestDiff`; mujina keeps no older best |
+//! | `uptimeSeconds`     | daemon uptime                                  |
+//! | `ASICModel`         | first board's model                            |
+//! | `fanspeed`          | first board's fan duty, percent                |
+//! | `fanrpm`            | first board's fan speed                        |
+//!
+//! Only reads are offered; AxeOS's settings and restart endpoints have no
+//! counterpart here.
+
+use axum::{Json, Router, extract::State, routing};
+use serde::Serialize;
+
+use super::server::SharedState;
+use crate::api_client::summary::fleet_summary;
+use crate::api_client::types::{BoardTelemetry, MinerTelemetry};
+use crate::types::Difficulty;
+
+/// Routes served at the AxeOS paths, outside the versioned API.
+pub(crate) fn routes() -> Router<SharedState> {
+    Router::new().route("/api/system/info", routing::get(get_system_info))
+}
+
+async fn get_system_info(State(state): State<SharedState>) -> Json<SystemInfo> {
+    Json(system_info(&state.miner_telemetry()))
+}
+
+/// The subset of AxeOS's system info that monitoring tools read.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemInfo {
+    hash_rate: f64,
+    temp: f32,
+    vr_temp: f32,
+    power: f32,
+    voltage: f32,
+    current: f32,
+    core_voltage: f32,
+    core_voltage_actual: f32,
+    frequency: f32,
+    shares_accepted: u64,
+    shares_rejected: u64,
+    best_diff: String,
+    best_session_diff: String,
+    uptime_seconds: u64,
+    #[serde(rename = "ASICModel")]
+    asic_model: String,
+    fanspeed: u8,
+    fanrpm: u32,
+    version: String,
+}
+
+fn system_info(telemetry: &MinerTelemetry) -> SystemInfo {
+    let fleet = fleet_summary(telemetry);
+    let first = telemetry.boards.first();
+    let power_rail =
+        |name: &str| first.and_then(|board| board.powers.iter().find(|p| p.name == name));
+    let millis = |value: Option<f32>| value.map_or(0.0, |v| v * 1000.0);
+    let core_voltage = millis(power_rail("core").and_then(|p| p.voltage_v));
+    let best_diff = telemetry
+        .best_share_difficulty
+        .and_then(Difficulty::from_share_difficulty)
+        .map_or_else(|| "0".to_string(), |d| d.to_string());
+
+    SystemInfo {
+        hash_rate: fleet.hashrate.as_gigahashes(),
+        temp: hottest(&telemetry.boards, "asic"),
+        vr_temp: hottest(&telemetry.boards, "vr"),
+        power: fleet.power_w.unwrap_or(0.0),
+        voltage: millis(power_rail("input").and_then(|p| p.voltage_v)),
+        current: millis(power_rail("core").and_then(|p| p.current_a)),
+        core_voltage,
+        core_voltage_actual: core_voltage,
+        frequency: 0.0,
+        shares_accepted: fleet.shares_accepted,
+        shares_rejected: fleet.shares_rejected,
+        best_session_diff: best_diff.clone(),
+        best_diff,
+        uptime_seconds: telemetry.uptime_secs,
+        asic_model: first.map(|b| b.model.clone()).unwrap_or_default(),
+        fanspeed: first
+            .and_then(|b| b.fans.first())
+            .and_then(|f| f.percent)
+            .unwrap_or(0),
+        fanrpm: first
+            .and_then(|b| b.fans.first())
+            .and_then(|f| f.rpm)
+            .unwrap_or(0),
+        version: concat!("mujina-miner ", env!("CARGO_PKG_VERSION")).to_string(),
+    }
+}
+
+/// The hottest reading from sensors named `sensor` on any board, or 0.
+fn hottest(boards: &[BoardTelemetry], sensor: &str) -> f32 {
+    boards
+        .iter()
+        .flat_map(|b| &b.temperatures)
+        .filter(|s| s.name == sensor)
+        .filter_map(|s| s.temperature)
+        .map(|t| t.as_degrees_c())
+        .reduce(f32::max)
+        .unwrap_or(0.0)
+}
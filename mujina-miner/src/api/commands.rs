@@ -4,8 +4,12 @@
 //! await the result and translate it into an HTTP response.
 
 use anyhow::Result;
+use thiserror::Error;
 use tokio::sync::oneshot;
 
+use crate::api_client::types::{SchedulerState, SourceJob};
+use crate::board::cooldown::EnableRefused;
+
 /// Commands from the API to the scheduler.
 pub enum SchedulerCommand {
     /// Pause job distribution to all threads.
@@ -13,6 +17,36 @@ pub enum SchedulerCommand {
 
     /// Resume job distribution after a pause.
     ResumeMining { reply: oneshot::Sender<Result<()>> },
+
+    /// Snapshot the scheduler's share target state for debugging.
+    GetState {
+        reply: oneshot::Sender<SchedulerState>,
+    },
+
+    /// The named source's current job, `None` when there is no such
+    /// source or it has no job.
+    GetJob {
+        source: String,
+        reply: oneshot::Sender<Option<SourceJob>>,
+    },
+
+    /// Move boards without a pool assignment onto the named pool,
+    /// `default` for the default pool.
+    PromotePool {
+        pool: String,
+        reply: oneshot::Sender<Result<(), PromoteError>>,
+    },
+
+    /// Zero the runtime counters: shares, hardware errors and measured
+    /// hashrate. Uptime, best share, blocks found and the pools' share
+    /// counts are kept.
+    ResetStats { reply: oneshot::Sender<()> },
+
+    /// Recompute every thread's share target from the current hashrates
+    /// now, rather than on the next job, replying with the state after.
+    Retarget {
+        reply: oneshot::Sender<SchedulerState>,
+    },
 }
 
 /// Commands from the API to board management.
@@ -23,6 +57,47 @@ pub enum BoardCommand {
         fan: String,
         /// Target duty cycle (0--100), or None for automatic control.
         percent: Option<u8>,
-        reply: oneshot::Sender<Result<()>>,
+        reply: oneshot::Sender<Result<(), FanTargetError>>,
     },
+
+    /// Bring a board that shut itself down back up, once its cooldown
+    /// allows or regardless when `force` is set.
+    Enable {
+        board: String,
+        force: bool,
+        reply: oneshot::Sender<Result<(), EnableError>>,
+    },
+}
+
+/// Why a fan's target was not set.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FanTargetError {
+    #[error("no such board")]
+    NotFound,
+    #[error("no such fan")]
+    NoSuchFan,
+    #[error("board's fan can't be set")]
+    Unsupported,
+    #[error("duty cycle {0} is above 100%")]
+    OutOfRange(u8),
+}
+
+/// Why a pool was not promoted.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PromoteError {
+    #[error("no source for pool {0}")]
+    UnknownPool(String),
+}
+
+/// Why a board was not re-enabled.
+#[derive(Debug, Error)]
+pub enum EnableError {
+    #[error("no such board")]
+    NotFound,
+    #[error("board has not shut down")]
+    NotTripped,
+    #[error(transparent)]
+    Refused(#[from] EnableRefused),
+    #[error("board failed to come back up: {0:#}")]
+    Failed(anyhow::Error),
 }
@@ -0,0 +1,307 @@
+//! Energy each board has used, in kWh.
+//!
+//! Power readings say what a board draws now; running cost depends on
+//! what it has drawn over time. Every sampling interval the board power
+//! is read and integrated with the trapezoidal rule over the time actually
+//! elapsed since the last reading, so a late or skipped sample doesn't
+//! skew the total. A board without a power reading, or not connected,
+//! adds nothing for that stretch: its use is unknown, not zero watts.
+//!
+//! Totals live in the [`BoardRegistry`] and outlast a board's connection.
+//! With `MUJINA_ENERGY_FILE` set they are saved there as JSON once a
+//! minute and at shutdown, and loaded back at startup, so they also
+//! survive restarts.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use super::BoardRegistry;
+use crate::api_client::summary::board_power_w;
+use crate::api_client::types::BoardTelemetry;
+use crate::tracing::prelude::*;
+
+/// Interval between power readings when not configured.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often the totals are saved while running.
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Joules in a kilowatt-hour.
+const JOULES_PER_KWH: f64 = 3.6e6;
+
+/// How often to read power and where to keep the totals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnergyConfig {
+    pub interval: Duration,
+    /// File the totals are saved to, `None` to keep them in memory.
+    pub path: Option<PathBuf>,
+}
+
+impl EnergyConfig {
+    /// Read the file from `MUJINA_ENERGY_FILE` and the interval from
+    /// `MUJINA_ENERGY_INTERVAL_SECS`, warning and using the default on an
+    /// invalid interval.
+    pub fn from_env() -> Self {
+        let interval = match env::var("MUJINA_ENERGY_INTERVAL_SECS") {
+            Err(_) => DEFAULT_INTERVAL,
+            Ok(val) => match val.parse::<u64>() {
+                Ok(secs) if secs > 0 => Duration::from_secs(secs),
+                _ => {
+                    warn!(value = %val, "Invalid MUJINA_ENERGY_INTERVAL_SECS, using default");
+                    DEFAULT_INTERVAL
+                }
+            },
+        };
+        Self {
+            interval,
+            path: env::var_os("MUJINA_ENERGY_FILE")
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from),
+        }
+    }
+}
+
+/// A board's running total.
+#[derive(Debug, Clone, Default)]
+struct BoardEnergy {
+    kwh: f64,
+    /// The latest reading, in watts, and when it was taken. `None` after
+    /// a gap, so the next reading starts a new stretch.
+    last: Option<(Instant, f32)>,
+}
+
+/// The saved form of the totals.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Saved {
+    boards: BTreeMap<String, f64>,
+}
+
+/// Energy totals of every board seen, by name.
+#[derive(Debug, Default)]
+pub struct EnergyMeter {
+    boards: BTreeMap<String, BoardEnergy>,
+}
+
+impl EnergyMeter {
+    /// Add the power of each of `boards`, read at `now`.
+    ///
+    /// Boards missing from `boards` have disconnected; their totals stay
+    /// but stop growing until they are back.
+    pub fn record(&mut self, now: Instant, boards: &[BoardTelemetry]) {
+        for (name, energy) in &mut self.boards {
+            if !boards.iter().any(|b| &b.name == name) {
+                energy.last = None;
+            }
+        }
+        for board in boards {
+            let energy = self.boards.entry(board.name.clone()).or_default();
+            let power = board_power_w(board).filter(|w| w.is_finite() && *w >= 0.0);
+            if let (Some((then, was)), Some(watts)) = (energy.last, power) {
+                let secs = now.duration_since(then).as_secs_f64();
+                energy.kwh += f64::from(was + watts) / 2.0 * secs / JOULES_PER_KWH;
+            }
+            energy.last = power.map(|watts| (now, watts));
+        }
+    }
+
+    /// Energy the board named `name` has used, `None` if it never
+    /// reported power.
+    pub fn board_kwh(&self, name: &str) -> Option<f64> {
+        self.boards
+            .get(name)
+            .filter(|e| e.kwh > 0.0 || e.last.is_some())
+            .map(|e| e.kwh)
+    }
+
+    /// Energy every board seen has used, connected or not.
+    pub fn total_kwh(&self) -> f64 {
+        self.boards.values().map(|e| e.kwh).sum()
+    }
+
+    /// Load totals saved at `path`. A missing file starts from zero; an
+    /// unreadable one does too, with a warning.
+    pub fn load(path: &Path) -> Self {
+        let saved = match fs::read_to_string(path) {
+            Ok(text) => match serde_json::from_str::<Saved>(&text) {
+                Ok(saved) => saved,
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Invalid energy file, starting from zero");
+                    Saved::default()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Saved::default(),
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to read energy file, starting from zero");
+                Saved::default()
+            }
+        };
+        let boards = saved
+            .boards
+            .into_iter()
+            .filter(|(_, kwh)| kwh.is_finite() && *kwh >= 0.0)
+            .map(|(name, kwh)| (name, BoardEnergy { kwh, last: None }))
+            .collect();
+        Self { boards }
+    }
+
+    /// Save the totals to `path`, replacing it whole so a crash never
+    /// leaves half a file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let saved = Saved {
+            boards: self
+                .boards
+                .iter()
+                .map(|(name, e)| (name.clone(), e.kwh))
+                .collect(),
+        };
+        let json = serde_json::to_string_pretty(&saved).map_err(io::Error::other)?;
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".tmp");
+        fs::write(&partial, json + "\n")?;
+        fs::rename(&partial, path)
+    }
+}
+
+/// Read board power every `config.interval` into the registry's meter
+/// until shutdown, saving the totals if configured.
+pub async fn task(
+    config: EnergyConfig,
+    registry: Arc<Mutex<BoardRegistry>>,
+    shutdown: CancellationToken,
+) {
+    let lock = || registry.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(path) = &config.path {
+        let meter = EnergyMeter::load(path);
+        info!(path = %path.display(), kwh = meter.total_kwh(), "Energy totals loaded");
+        *lock().energy_mut() = meter;
+    }
+    let save = |registry: &BoardRegistry| {
+        if let Some(path) = &config.path
+            && let Err(e) = registry.energy().save(path)
+        {
+            warn!(path = %path.display(), error = %e, "Failed to save energy totals");
+        }
+    };
+
+    let mut tick = tokio::time::interval(config.interval);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut saved_at = Instant::now();
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = tick.tick() => {
+                let now = Instant::now();
+                let mut registry = lock();
+                let boards = registry.boards();
+                registry.energy_mut().record(now, &boards);
+                if now.duration_since(saved_at) >= SAVE_INTERVAL {
+                    save(&registry);
+                    saved_at = now;
+                }
+            }
+        }
+    }
+    save(&lock());
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    fn board(name: &str, watts: Option<f32>) -> BoardTelemetry {
+        BoardTelemetry::named(name).with_power("input", watts)
+    }
+
+    #[test]
+    fn integrates_power_over_the_actual_intervals() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut meter = EnergyMeter::default();
+
+        // Board a: 100 W for 10 s, ramping to 200 W over 30 s, then 200 W
+        // for 60 s: 1000 + 4500 + 12000 J. Samples come unevenly.
+        for (secs, watts) in [(0, 100.0), (10, 100.0), (40, 200.0), (100, 200.0)] {
+            meter.record(at(secs), &[board("a", Some(watts)), board("b", Some(50.0))]);
+        }
+        let expected_a = 17_500.0 / JOULES_PER_KWH;
+        assert!((meter.board_kwh("a").unwrap() - expected_a).abs() < 1e-12);
+
+        // Board b loses its power reading for a stretch, then disconnects
+        // for one: neither adds anything, from either side of the gap.
+        meter.record(at(110), &[board("a", Some(200.0)), board("b", None)]);
+        meter.record(at(120), &[board("a", Some(200.0)), board("b", Some(50.0))]);
+        meter.record(at(130), &[board("a", Some(200.0))]);
+        meter.record(at(140), &[board("a", Some(200.0)), board("b", Some(50.0))]);
+        meter.record(at(150), &[board("a", Some(200.0)), board("b", Some(50.0))]);
+        let expected_b = 50.0 * (100.0 + 10.0) / JOULES_PER_KWH;
+        assert!((meter.board_kwh("b").unwrap() - expected_b).abs() < 1e-12);
+
+        let expected_a = expected_a + 200.0 * 50.0 / JOULES_PER_KWH;
+        assert!((meter.total_kwh() - expected_a - expected_b).abs() < 1e-12);
+        assert_eq!(meter.board_kwh("c"), None);
+    }
+
+    #[test]
+    fn totals_survive_a_restart() {
+        let path = env::temp_dir().join(format!("mujina-{}-energy.json", std::process::id()));
+        let start = Instant::now();
+        let mut meter = EnergyMeter::default();
+        meter.record(start, &[board("a", Some(360.0))]);
+        meter.record(start + Duration::from_secs(10), &[board("a", Some(360.0))]);
+        meter.save(&path).unwrap();
+
+        // Loaded again, the total carries on from where it was.
+        let mut meter = EnergyMeter::load(&path);
+        assert!((meter.board_kwh("a").unwrap() - 0.001).abs() < 1e-12);
+        let later = start + Duration::from_secs(3600);
+        meter.record(later, &[board("a", Some(360.0))]);
+        meter.record(later + Duration::from_secs(10), &[board("a", Some(360.0))]);
+        assert!((meter.total_kwh() - 0.002).abs() < 1e-12);
+
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(EnergyMeter::load(&path).total_kwh(), 0.0);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(EnergyMeter::load(&path).total_kwh(), 0.0);
+    }
+
+    #[test]
+    #[serial]
+    fn config_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_ENERGY_FILE");
+            env::remove_var("MUJINA_ENERGY_INTERVAL_SECS");
+            assert_eq!(
+                EnergyConfig::from_env(),
+                EnergyConfig {
+                    interval: DEFAULT_INTERVAL,
+                    path: None,
+                }
+            );
+            env::set_var("MUJINA_ENERGY_FILE", "/var/lib/mujina/energy.json");
+            env::set_var("MUJINA_ENERGY_INTERVAL_SECS", "30");
+            assert_eq!(
+                EnergyConfig::from_env(),
+                EnergyConfig {
+                    interval: Duration::from_secs(30),
+                    path: Some("/var/lib/mujina/energy.json".into()),
+                }
+            );
+            env::set_var("MUJINA_ENERGY_INTERVAL_SECS", "0");
+            assert_eq!(EnergyConfig::from_env().interval, DEFAULT_INTERVAL);
+            env::remove_var("MUJINA_ENERGY_FILE");
+            env::remove_var("MUJINA_ENERGY_INTERVAL_SECS");
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Aggregate health evaluation.
+//!
+//! Reduces a full [`MinerTelemetry`] snapshot to a single [`Health`]
+//! verdict for orchestrators and simple monitors. The criteria are:
+//!
+//! - **Failed:** no source is connected, or (while not paused) no board
+//!   is mining. The miner produces nothing useful in this state.
+//! - **Degraded:** mining continues, but mining is paused, a board is not
+//!   mining, a source is disconnected, a hash thread reports a fault, or
+//!   a temperature sensor reads at or above [`CRITICAL_TEMP_C`].
+//! - **Ok:** none of the above.
+//!
+//! A board counts as mining when it reports at least one active hash
+//! thread.
+
+use crate::api_client::types::{BoardTelemetry, Health, HealthStatus, MinerTelemetry};
+use crate::types::TemperatureUnit;
+
+/// Temperature at or above which a sensor is considered critically hot.
+///
+/// The temperature boards shut themselves down at.
+pub use crate::board::thermal::CRITICAL_TEMP_C;
+
+/// Evaluate overall health from a miner snapshot.
+pub fn assess(telemetry: &MinerTelemetry) -> Health {
+    assess_in(telemetry, TemperatureUnit::configured())
+}
+
+/// [`assess`], with temperatures in the reasons shown in `unit`.
+fn assess_in(telemetry: &MinerTelemetry, unit: TemperatureUnit) -> Health {
+    let mut failures = Vec::new();
+    let mut warnings = Vec::new();
+
+    let connected = telemetry.sources.iter().filter(|s| s.connected).count();
+    if connected == 0 {
+        failures.push("no job source connected".to_string());
+    }
+    for source in telemetry.sources.iter().filter(|s| !s.connected) {
+        match &source.failure {
+            Some(failure) => warnings.push(format!("source {} failed: {failure}", source.name)),
+            None => warnings.push(format!("source {} not connected", source.name)),
+        }
+    }
+
+    if telemetry.paused {
+        warnings.push("mining paused".to_string());
+    } else {
+        let (mining, idle): (Vec<_>, Vec<_>) = telemetry.boards.iter().partition(|b| is_mining(b));
+        if mining.is_empty() {
+            failures.push("no board mining".to_string());
+        }
+        for board in idle {
+            warnings.push(format!("board {} not mining", board.name));
+        }
+    }
+
+    for board in &telemetry.boards {
+        for thread in &board.threads {
+            if let Some(fault) = &thread.fault {
+                warnings.push(format!(
+                    "board {} thread {}: {fault}",
+                    board.name, thread.name
+                ));
+            }
+        }
+        for sensor in &board.temperatures {
+            if let Some(t) = sensor.temperature
+                && t.as_degrees_c() >= CRITICAL_TEMP_C
+            {
+                warnings.push(format!(
+                    "board {} sensor {} critically hot ({})",
+                    board.name,
+                    sensor.name,
+                    t.display(unit)
+                ));
+            }
+        }
+    }
+
+    let status = if !failures.is_empty() {
+        HealthStatus::Failed
+    } else if !warnings.is_empty() {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Ok
+    };
+
+    failures.extend(warnings);
+    Health {
+        status,
+        reasons: failures,
+    }
+}
+
+fn is_mining(board: &BoardTelemetry) -> bool {
+    board.threads.iter().any(|t| t.is_active)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::types::SourceTelemetry;
+
+    fn board(name: &str, active: bool) -> BoardTelemetry {
+        BoardTelemetry::named(name).with_thread(0, active)
+    }
+
+    fn pool(connected: bool) -> SourceTelemetry {
+        SourceTelemetry {
+            name: "pool".into(),
+            connected,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ok_when_all_boards_mine_and_pool_connected() {
+        let telemetry = MinerTelemetry {
+            boards: vec![board("a", true), board("b", true)],
+            sources: vec![pool(true)],
+            ..Default::default()
+        };
+        let health = assess(&telemetry);
+        assert_eq!(health.status, HealthStatus::Ok);
+        assert!(health.reasons.is_empty());
+    }
+
+    #[test]
+    fn degraded_when_one_board_down() {
+        let telemetry = MinerTelemetry {
+            boards: vec![board("a", true), board("b", false)],
+            sources: vec![pool(true)],
+            ..Default::default()
+        };
+        let health = assess(&telemetry);
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert_eq!(health.reasons, vec!["board b not mining"]);
+    }
+
+    #[test]
+    fn failed_when_no_pool() {
+        let telemetry = MinerTelemetry {
+            boards: vec![board("a", true)],
+            sources: vec![pool(false)],
+            ..Default::default()
+        };
+        assert_eq!(assess(&telemetry).status, HealthStatus::Failed);
+
+        let telemetry = MinerTelemetry {
+            boards: vec![board("a", true)],
+            ..Default::default()
+        };
+        assert_eq!(assess(&telemetry).status, HealthStatus::Failed);
+    }
+
+    #[test]
+    fn names_why_a_source_gave_up() {
+        let dead = SourceTelemetry {
+            name: "backup".into(),
+            failure: Some("gave up after 5 failed connection attempts".into()),
+            ..Default::default()
+        };
+        let telemetry = MinerTelemetry {
+            boards: vec![board("a", true)],
+            sources: vec![pool(true), dead],
+            ..Default::default()
+        };
+        let health = assess(&telemetry);
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert_eq!(
+            health.reasons,
+            vec!["source backup failed: gave up after 5 failed connection attempts"]
+        );
+    }
+
+    #[test]
+    fn failed_when_no_board_mining() {
+        let telemetry = MinerTelemetry {
+            boards: vec![board("a", false)],
+            sources: vec![pool(true)],
+            ..Default::default()
+        };
+        assert_eq!(assess(&telemetry).status, HealthStatus::Failed);
+    }
+
+    #[test]
+    fn paused_is_degraded_not_failed() {
+        let telemetry = MinerTelemetry {
+            paused: true,
+            boards: vec![board("a", false)],
+            sources: vec![pool(true)],
+            ..Default::default()
+        };
+        let health = assess(&telemetry);
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert_eq!(health.reasons, vec!["mining paused"]);
+    }
+
+    #[test]
+    fn degraded_when_board_critically_hot() {
+        let hot = board("a", true).with_temperature(CRITICAL_TEMP_C);
+        let telemetry = MinerTelemetry {
+            boards: vec![hot],
+            sources: vec![pool(true)],
+            ..Default::default()
+        };
+        assert_eq!(assess(&telemetry).status, HealthStatus::Degraded);
+    }
+
+    #[test]
+    fn threshold_is_celsius_in_any_display_unit() {
+        let reading = |celsius| MinerTelemetry {
+            boards: vec![board("a", true).with_temperature(celsius)],
+            sources: vec![pool(true)],
+            ..Default::default()
+        };
+
+        for unit in [TemperatureUnit::Celsius, TemperatureUnit::Fahrenheit] {
+            let below = assess_in(&reading(CRITICAL_TEMP_C - 1.0), unit);
+            assert_eq!(below.status, HealthStatus::Ok, "{unit:?}");
+            let at = assess_in(&reading(CRITICAL_TEMP_C), unit);
+            assert_eq!(at.status, HealthStatus::Degraded, "{unit:?}");
+        }
+
+        let reasons = assess_in(&reading(80.0), TemperatureUnit::Fahrenheit).reasons;
+        assert_eq!(reasons, ["board a sensor asic critically hot (176.0 F)"]);
+    }
+}
@@ -0,0 +1,243 @@
+//! Recent per-board readings kept in memory.
+//!
+//! Telemetry endpoints give the current state; this keeps the last few
+//! minutes of it so a client can plot a trend without a metrics stack or
+//! the CSV export. Each sample records a board's hashrate, hottest
+//! temperature and power. Buffers are fixed-size rings: once full, each
+//! new sample evicts the oldest. A board's history is dropped when it
+//! disconnects.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use time::OffsetDateTime;
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use crate::api_client::summary::board_power_w;
+use crate::api_client::types::{BoardSample, BoardTelemetry};
+use crate::clock::Clock;
+use crate::tracing::prelude::*;
+
+/// How far back history reaches when not configured.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Interval between samples when not configured.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often to sample and how many samples to keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryConfig {
+    pub interval: Duration,
+    /// Samples kept per board.
+    pub capacity: usize,
+}
+
+impl HistoryConfig {
+    /// Keep `window` worth of samples taken every `interval`, at least one.
+    pub fn new(window: Duration, interval: Duration) -> Self {
+        let capacity = (window.as_secs() / interval.as_secs().max(1)).max(1) as usize;
+        Self { interval, capacity }
+    }
+
+    /// Read the window from `MUJINA_HISTORY_SECS` and the interval from
+    /// `MUJINA_HISTORY_INTERVAL_SECS`, warning and using the default on
+    /// invalid values.
+    ///
+    /// Returns `None` when the window is 0, which disables history.
+    pub fn from_env() -> Option<Self> {
+        let window = secs_from_env("MUJINA_HISTORY_SECS", DEFAULT_WINDOW, true)?;
+        let interval = secs_from_env("MUJINA_HISTORY_INTERVAL_SECS", DEFAULT_INTERVAL, false)?;
+        Some(Self::new(window, interval))
+    }
+}
+
+/// Parse a whole number of seconds from `var`. 0 gives `None` where
+/// `zero_disables`, and is invalid otherwise.
+fn secs_from_env(var: &str, default: Duration, zero_disables: bool) -> Option<Duration> {
+    let Ok(value) = env::var(var) else {
+        return Some(default);
+    };
+    match value.parse::<u64>() {
+        Ok(0) if zero_disables => None,
+        Ok(secs) if secs > 0 => Some(Duration::from_secs(secs)),
+        _ => {
+            warn!(
+                var,
+                value = %value,
+                default_secs = default.as_secs(),
+                "Invalid history setting, using default"
+            );
+            Some(default)
+        }
+    }
+}
+
+/// Ring buffers of recent samples, one per board.
+#[derive(Debug)]
+pub struct History {
+    capacity: usize,
+    boards: BTreeMap<String, VecDeque<BoardSample>>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(HistoryConfig::new(DEFAULT_WINDOW, DEFAULT_INTERVAL).capacity)
+    }
+}
+
+impl History {
+    /// Empty history keeping `capacity` samples per board.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            boards: BTreeMap::new(),
+        }
+    }
+
+    /// Add a sample of every board in `boards`, taken at `at`.
+    ///
+    /// Boards missing from `boards` have disconnected, so their history
+    /// goes with them.
+    pub fn record(&mut self, at: OffsetDateTime, boards: &[BoardTelemetry]) {
+        self.boards
+            .retain(|name, _| boards.iter().any(|b| &b.name == name));
+        for board in boards {
+            let samples = self.boards.entry(board.name.clone()).or_default();
+            if samples.len() == self.capacity {
+                samples.pop_front();
+            }
+            samples.push_back(sample(at, board));
+        }
+    }
+
+    /// A board's samples, oldest first, or `None` if it has none.
+    pub fn board(&self, name: &str) -> Option<Vec<BoardSample>> {
+        self.boards.get(name).map(|s| s.iter().cloned().collect())
+    }
+}
+
+fn sample(at: OffsetDateTime, board: &BoardTelemetry) -> BoardSample {
+    BoardSample {
+        timestamp: at.unix_timestamp(),
+        hashrate: board.threads.iter().map(|t| t.hashrate).sum(),
+        temperature_c: board
+            .temperatures
+            .iter()
+            .filter_map(|s| s.temperature)
+            .map(|t| t.as_degrees_c())
+            .reduce(f32::max),
+        power_w: board_power_w(board),
+    }
+}
+
+/// Sample every `config.interval` into `history` until shutdown.
+///
+/// `snapshot` is called once per sample for the connected boards.
+pub async fn task(
+    config: HistoryConfig,
+    history: Arc<Mutex<History>>,
+    shutdown: CancellationToken,
+    clock: Arc<dyn Clock>,
+    snapshot: impl Fn() -> Vec<BoardTelemetry>,
+) {
+    let mut tick = tokio::time::interval_at(Instant::now() + config.interval, config.interval);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = tick.tick() => {
+                let boards = snapshot();
+                history
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .record(clock.now_utc(), &boards);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn board(name: &str, hashrate: u64) -> BoardTelemetry {
+        BoardTelemetry::named(name).with_thread(hashrate, true)
+    }
+
+    #[test]
+    fn keeps_capacity_samples_and_evicts_oldest() {
+        let mut history = History::new(3);
+        let start = datetime!(2026-03-01 12:00 UTC);
+
+        for i in 0..5 {
+            let at = start + time::Duration::seconds(10 * i);
+            history.record(at, &[board("a", 100 + i as u64)]);
+        }
+
+        let samples = history.board("a").unwrap();
+        assert_eq!(samples.len(), 3);
+        let hashrates: Vec<_> = samples.iter().map(|s| s.hashrate).collect();
+        assert_eq!(hashrates, [102, 103, 104]);
+        assert_eq!(samples[0].timestamp, start.unix_timestamp() + 20);
+        assert_eq!(samples[2].timestamp, start.unix_timestamp() + 40);
+        assert_eq!(history.board("b"), None);
+    }
+
+    #[test]
+    fn drops_history_of_disconnected_boards() {
+        let mut history = History::new(3);
+        let at = datetime!(2026-03-01 12:00 UTC);
+
+        history.record(at, &[board("a", 1), board("b", 2)]);
+        history.record(at, &[board("b", 2)]);
+
+        assert_eq!(history.board("a"), None);
+        assert_eq!(history.board("b").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn capacity_covers_the_window() {
+        let config = HistoryConfig::new(Duration::from_secs(600), Duration::from_secs(10));
+        assert_eq!(config.capacity, 60);
+        let config = HistoryConfig::new(Duration::from_secs(5), Duration::from_secs(10));
+        assert_eq!(config.capacity, 1);
+    }
+
+    #[test]
+    #[serial]
+    fn config_env_parsing() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_HISTORY_SECS");
+            env::remove_var("MUJINA_HISTORY_INTERVAL_SECS");
+            assert_eq!(
+                HistoryConfig::from_env(),
+                Some(HistoryConfig::new(DEFAULT_WINDOW, DEFAULT_INTERVAL))
+            );
+            env::set_var("MUJINA_HISTORY_SECS", "60");
+            env::set_var("MUJINA_HISTORY_INTERVAL_SECS", "5");
+            assert_eq!(
+                HistoryConfig::from_env(),
+                Some(HistoryConfig {
+                    interval: Duration::from_secs(5),
+                    capacity: 12,
+                })
+            );
+            env::set_var("MUJINA_HISTORY_INTERVAL_SECS", "0");
+            assert_eq!(
+                HistoryConfig::from_env().map(|c| c.interval),
+                Some(DEFAULT_INTERVAL)
+            );
+            env::set_var("MUJINA_HISTORY_SECS", "0");
+            assert_eq!(HistoryConfig::from_env(), None);
+            env::remove_var("MUJINA_HISTORY_SECS");
+            env::remove_var("MUJINA_HISTORY_INTERVAL_SECS");
+        }
+    }
+}
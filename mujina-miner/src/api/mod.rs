@@ -4,10 +4,14 @@
 //! miner. Built on Axum, binds to localhost only by default and does not
 //! require authentication for local access.
 
+mod axeos;
 pub mod commands;
+pub mod energy;
+mod health;
+pub mod history;
 mod registry;
 mod server;
 mod v0;
 
-pub use registry::BoardRegistration;
-pub use server::{ApiConfig, serve};
+pub use registry::{BoardOrder, BoardRegistration, BoardRegistry, collect_boards};
+pub use server::{ApiConfig, BindError, miner_telemetry, serve};
@@ -1,21 +1,98 @@
 //! Dynamic board registration tracking.
 
+use std::cmp::Ordering;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::energy::EnergyMeter;
 use crate::api_client::types::BoardTelemetry;
-use tokio::sync::watch;
+use crate::board::profile::{OperatingGuard, Profile, UnsafePoint};
+use crate::tracing::prelude::*;
+use tokio::sync::{mpsc, watch};
+
+/// Order boards are listed in, independent of the order they were found.
+///
+/// Boards are enumerated as USB devices appear, which varies from one
+/// start to the next, so they are listed by name instead: boards named in
+/// `MUJINA_BOARD_ORDER` first, in the order given, then the others
+/// alphabetically. Everything built from the registry, the API, the CLI,
+/// the summary log and the stats CSV, shows this order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BoardOrder {
+    listed: Vec<String>,
+}
+
+impl BoardOrder {
+    /// List the boards named in `listed` first, in that order.
+    pub fn new(listed: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            listed: listed.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Read `MUJINA_BOARD_ORDER`, a comma-separated list of board names.
+    /// A name listed twice keeps its first place, with a warning.
+    pub fn from_env() -> Self {
+        let Ok(val) = env::var("MUJINA_BOARD_ORDER") else {
+            return Self::default();
+        };
+        let mut listed: Vec<String> = Vec::new();
+        for name in val.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            if listed.iter().any(|n| n == name) {
+                warn!(
+                    board = name,
+                    "Board listed twice in MUJINA_BOARD_ORDER, ignoring"
+                );
+                continue;
+            }
+            listed.push(name.to_string());
+        }
+        Self { listed }
+    }
+
+    /// Put `boards` in this order.
+    pub fn sort(&self, boards: &mut [BoardTelemetry]) {
+        boards.sort_by(|a, b| self.compare(&a.name, &b.name));
+    }
+
+    fn compare(&self, a: &str, b: &str) -> Ordering {
+        let place = |name: &str| {
+            self.listed
+                .iter()
+                .position(|n| n == name)
+                .unwrap_or(self.listed.len())
+        };
+        place(a).cmp(&place(b)).then_with(|| a.cmp(b))
+    }
+}
 
 /// Dynamic collection of board registrations.
 ///
 /// Boards are added via `push()` from a background drain task that
 /// receives registrations as boards connect. The registry cleans up
-/// disconnected boards lazily when `boards()` is called.
+/// disconnected boards lazily when `boards()` is called, and lists them
+/// in its [`BoardOrder`]. It also keeps the boards' energy totals, which
+/// outlast their registrations.
+#[derive(Default)]
 pub struct BoardRegistry {
     boards: Vec<BoardRegistration>,
+    order: BoardOrder,
+    energy: EnergyMeter,
 }
 
 impl BoardRegistry {
-    /// Create an empty registry.
+    /// Create an empty registry, listing boards by name.
     pub fn new() -> Self {
-        Self { boards: Vec::new() }
+        Self::default()
+    }
+
+    /// Create an empty registry listing boards in `order`.
+    pub fn with_order(order: BoardOrder) -> Self {
+        Self {
+            order,
+            ..Self::default()
+        }
     }
 
     /// Add a board registration.
@@ -26,20 +103,122 @@ impl BoardRegistry {
     /// Snapshot all connected boards.
     ///
     /// Removes boards whose sender has been dropped (board disconnected)
-    /// and returns the current state of each.
+    /// and returns the current state of each, in the registry's order.
     pub fn boards(&mut self) -> Vec<BoardTelemetry> {
         self.boards
             .retain(|reg| reg.telemetry_rx.has_changed().is_ok());
-        self.boards
+        let mut boards: Vec<BoardTelemetry> = self
+            .boards
+            .iter()
+            .map(|reg| {
+                let mut telemetry = reg.telemetry_rx.borrow().clone();
+                if let Some(name) = &reg.name {
+                    telemetry.name = name.clone();
+                }
+                if let Some(init) = reg.init_duration {
+                    telemetry.init_secs = Some(init.as_secs_f64());
+                }
+                if let Some(tx) = &reg.profile_tx {
+                    telemetry.profile = Some(*tx.borrow());
+                }
+                telemetry.energy_kwh = self.energy.board_kwh(&telemetry.name);
+                telemetry
+            })
+            .collect();
+        self.order.sort(&mut boards);
+        boards
+    }
+
+    /// Energy totals of every board seen.
+    pub fn energy(&self) -> &EnergyMeter {
+        &self.energy
+    }
+
+    /// Energy totals, for the [`energy::task`](super::energy::task) to
+    /// update.
+    pub fn energy_mut(&mut self) -> &mut EnergyMeter {
+        &mut self.energy
+    }
+
+    /// Select `profile` on the board named `name`, if it is safe to run
+    /// now.
+    pub fn set_profile(&mut self, name: &str, profile: Profile) -> Result<(), SetProfileError> {
+        let reg = self
+            .boards
             .iter()
-            .map(|reg| reg.telemetry_rx.borrow().clone())
-            .collect()
+            .find(|reg| reg.name() == name)
+            .ok_or(SetProfileError::NotFound)?;
+        let tx = reg
+            .profile_tx
+            .as_ref()
+            .ok_or(SetProfileError::Unsupported)?;
+        if let Some(guard) = &reg.profile_guard {
+            guard
+                .validate_profile(profile)
+                .map_err(SetProfileError::Unsafe)?;
+        }
+        tx.send_replace(profile);
+        Ok(())
     }
 }
 
+/// Why a profile could not be selected.
+#[derive(Debug, PartialEq)]
+pub enum SetProfileError {
+    /// No connected board has that name.
+    NotFound,
+    /// The board has no operating profiles.
+    Unsupported,
+    /// The profile's operating point is unsafe for the board right now.
+    Unsafe(UnsafePoint),
+}
+
+/// Start collecting board registrations into a shared registry listing
+/// them in `order`.
+///
+/// Registrations are drained into the registry as they arrive by a
+/// background task, which exits when the sender is dropped (backplane
+/// shutdown).
+pub fn collect_boards(
+    mut board_reg_rx: mpsc::Receiver<BoardRegistration>,
+    order: BoardOrder,
+) -> Arc<Mutex<BoardRegistry>> {
+    let registry = Arc::new(Mutex::new(BoardRegistry::with_order(order)));
+    tokio::spawn({
+        let registry = registry.clone();
+        async move {
+            while let Some(reg) = board_reg_rx.recv().await {
+                registry.lock().unwrap_or_else(|e| e.into_inner()).push(reg);
+            }
+        }
+    });
+    registry
+}
+
 /// A board's registration with the API server.
 pub struct BoardRegistration {
+    /// Name to list the board under instead of the one it reports, when
+    /// another board already reports that one.
+    pub name: Option<String>,
     pub telemetry_rx: watch::Receiver<BoardTelemetry>,
+    /// How long the board took to initialize, reported as
+    /// [`BoardTelemetry::init_secs`].
+    pub init_duration: Option<Duration>,
+    /// Selects the board's operating profile, reported as
+    /// [`BoardTelemetry::profile`]. `None` if the board has no profiles.
+    pub profile_tx: Option<watch::Sender<Profile>>,
+    /// Refuses a profile unsafe for the board as it runs. `None` selects
+    /// any profile.
+    pub profile_guard: Option<OperatingGuard>,
+}
+
+impl BoardRegistration {
+    /// Name the board is listed under.
+    fn name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| self.telemetry_rx.borrow().name.clone())
+    }
 }
 
 #[cfg(test)]
@@ -57,7 +236,16 @@ mod tests {
             ..Default::default()
         };
         let (tx, rx) = watch::channel(telemetry);
-        (tx, BoardRegistration { telemetry_rx: rx })
+        (
+            tx,
+            BoardRegistration {
+                name: None,
+                telemetry_rx: rx,
+                init_duration: None,
+                profile_tx: None,
+                profile_guard: None,
+            },
+        )
     }
 
     #[test]
@@ -75,6 +263,77 @@ mod tests {
         assert_eq!(boards[1].name, "board-b");
     }
 
+    #[test]
+    fn lists_boards_in_order_whatever_order_they_were_found() {
+        let names = ["bitaxe-e2f5", "bitaxe-03c1", "emberone-77aa", "bitaxe-9b10"];
+        for order in [
+            BoardOrder::default(),
+            BoardOrder::new(["emberone-77aa", "bitaxe-9b10", "bitaxe-gone"]),
+        ] {
+            let expected: &[&str] = if order == BoardOrder::default() {
+                &["bitaxe-03c1", "bitaxe-9b10", "bitaxe-e2f5", "emberone-77aa"]
+            } else {
+                // Listed boards first, as listed, then the rest by name.
+                &["emberone-77aa", "bitaxe-9b10", "bitaxe-03c1", "bitaxe-e2f5"]
+            };
+            for discovered in [[0, 1, 2, 3], [3, 2, 1, 0], [2, 0, 3, 1]] {
+                let mut registry = BoardRegistry::with_order(order.clone());
+                let mut keep = Vec::new();
+                for i in discovered {
+                    let (tx, reg) = make_board(names[i]);
+                    keep.push(tx);
+                    registry.push(reg);
+                }
+                let listed: Vec<String> = registry.boards().into_iter().map(|b| b.name).collect();
+                assert_eq!(listed, expected, "found in order {discovered:?}");
+            }
+        }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn board_order_from_env() {
+        let var = "MUJINA_BOARD_ORDER";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(BoardOrder::from_env(), BoardOrder::default());
+            env::set_var(var, " bitaxe-2, bitaxe-1,,bitaxe-2");
+            assert_eq!(
+                BoardOrder::from_env(),
+                BoardOrder::new(["bitaxe-2", "bitaxe-1"])
+            );
+            env::remove_var(var);
+        }
+    }
+
+    #[test]
+    fn refuses_profiles_unsafe_for_the_board() {
+        use crate::board::profile::{self, ProfileSelection};
+
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (profile_tx, selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+        let (_keep, mut reg) = make_board("bitaxe-hot");
+        reg.profile_tx = Some(profile_tx.clone());
+        reg.profile_guard = Some(selection.guard());
+        let mut registry = BoardRegistry::new();
+        registry.push(reg);
+
+        // At its target temperature the board may slow down but not
+        // speed up.
+        selection.applied(gamma.operating_point(Profile::Balanced));
+        selection
+            .conditions()
+            .send_modify(|c| (c.temp_c, c.target_c) = (Some(60.0), Some(60.0)));
+        assert!(matches!(
+            registry.set_profile("bitaxe-hot", Profile::Turbo),
+            Err(SetProfileError::Unsafe(UnsafePoint::Thermal { .. }))
+        ));
+        assert_eq!(*profile_tx.borrow(), Profile::Balanced);
+        assert_eq!(registry.set_profile("bitaxe-hot", Profile::Eco), Ok(()));
+        assert_eq!(*profile_tx.borrow(), Profile::Eco);
+    }
+
     #[test]
     fn removes_disconnected_boards() {
         let mut registry = BoardRegistry::new();
@@ -109,4 +368,44 @@ mod tests {
         tx.send_modify(|s| s.model = "Updated".into());
         assert_eq!(registry.boards()[0].model, "Updated");
     }
+
+    #[test]
+    fn reports_init_duration() {
+        let mut registry = BoardRegistry::new();
+
+        let (_keep, mut reg) = make_board("board-a");
+        reg.init_duration = Some(Duration::from_millis(2500));
+        registry.push(reg);
+
+        assert_eq!(registry.boards()[0].init_secs, Some(2.5));
+    }
+
+    #[test]
+    fn selects_profiles() {
+        let mut registry = BoardRegistry::new();
+
+        let (_keep_a, mut reg_a) = make_board("tunable");
+        let (profile_tx, profile_rx) = watch::channel(Profile::Balanced);
+        reg_a.profile_tx = Some(profile_tx);
+        let (_keep_b, reg_b) = make_board("fixed");
+        registry.push(reg_a);
+        registry.push(reg_b);
+
+        // Listed by name, so "fixed" comes first.
+        assert_eq!(registry.boards()[1].profile, Some(Profile::Balanced));
+        assert_eq!(registry.boards()[0].profile, None);
+
+        assert_eq!(registry.set_profile("tunable", Profile::Eco), Ok(()));
+        assert_eq!(*profile_rx.borrow(), Profile::Eco);
+        assert_eq!(registry.boards()[1].profile, Some(Profile::Eco));
+
+        assert_eq!(
+            registry.set_profile("fixed", Profile::Eco),
+            Err(SetProfileError::Unsupported)
+        );
+        assert_eq!(
+            registry.set_profile("missing", Profile::Eco),
+            Err(SetProfileError::NotFound)
+        );
+    }
 }
@@ -1,5 +1,6 @@
 //! HTTP server lifecycle and router construction.
 
+use std::io;
 use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
@@ -15,17 +16,41 @@ use utoipa_axum::router::OpenApiRouter;
 use utoipa_swagger_ui::SwaggerUi;
 
 use super::{
-    commands::SchedulerCommand,
-    registry::{BoardRegistration, BoardRegistry},
+    axeos,
+    commands::{BoardCommand, SchedulerCommand},
+    history::History,
+    registry::BoardRegistry,
     v0,
 };
 use crate::api_client::types::MinerTelemetry;
 
+/// The API server couldn't listen on its address, typically because
+/// another process holds the port.
+#[derive(Debug, thiserror::Error)]
+#[error("failed to bind the API server to {addr}")]
+pub struct BindError {
+    pub addr: String,
+    #[source]
+    pub source: io::Error,
+}
+
+/// Listen on `addr`.
+async fn bind(addr: &str) -> Result<TcpListener, BindError> {
+    TcpListener::bind(addr).await.map_err(|source| BindError {
+        addr: addr.to_string(),
+        source,
+    })
+}
+
 /// API server configuration.
 #[derive(Debug, Clone)]
 pub struct ApiConfig {
     /// Address and port to bind the API server to.
     pub bind_addr: String,
+
+    /// Also serve AxeOS's `/api/system/info` for tools written against
+    /// stock Bitaxe firmware.
+    pub axeos_compat: bool,
 }
 
 /// Shared application state available to all handlers.
@@ -34,54 +59,63 @@ pub(crate) struct SharedState {
     pub miner_telemetry_rx: watch::Receiver<MinerTelemetry>,
     pub board_registry: Arc<Mutex<BoardRegistry>>,
     pub scheduler_cmd_tx: mpsc::Sender<SchedulerCommand>,
+    pub board_cmd_tx: mpsc::Sender<BoardCommand>,
+    pub history: Arc<Mutex<History>>,
 }
 
 impl SharedState {
     /// Build a complete MinerTelemetry by combining scheduler data with board
     /// snapshots from the registry.
     pub fn miner_telemetry(&self) -> MinerTelemetry {
-        let mut telemetry = self.miner_telemetry_rx.borrow().clone();
-        telemetry.boards = self
-            .board_registry
-            .lock()
-            .unwrap_or_else(|e| e.into_inner())
-            .boards();
-        telemetry
+        miner_telemetry(&self.miner_telemetry_rx, &self.board_registry)
     }
 }
 
+/// Combine the latest scheduler snapshot with the connected boards.
+pub fn miner_telemetry(
+    miner_telemetry_rx: &watch::Receiver<MinerTelemetry>,
+    board_registry: &Mutex<BoardRegistry>,
+) -> MinerTelemetry {
+    let mut telemetry = miner_telemetry_rx.borrow().clone();
+    let mut registry = board_registry.lock().unwrap_or_else(|e| e.into_inner());
+    telemetry.boards = registry.boards();
+    telemetry.energy_kwh = registry.energy().total_kwh();
+    drop(registry);
+    for board in &mut telemetry.boards {
+        board.confirmed = telemetry.confirmed_boards.contains(&board.name);
+    }
+    telemetry
+}
+
 /// Start the API server.
 ///
 /// This function starts the HTTP API server and runs until the provided
 /// cancellation token is triggered. It binds to localhost only by default for
 /// security.
 ///
-/// Board registrations arrive via `board_reg_rx` as boards connect. The
-/// server manages the collection internally and cleans up when boards
-/// disconnect.
+/// Boards come from `board_registry`, filled by
+/// [`collect_boards`](super::collect_boards) as boards connect; the
+/// registry drops boards as they disconnect. Recent readings come from
+/// `history`, filled by [`history::task`](super::history::task).
 pub async fn serve(
     config: ApiConfig,
     shutdown: CancellationToken,
     miner_telemetry_rx: watch::Receiver<MinerTelemetry>,
-    mut board_reg_rx: mpsc::Receiver<BoardRegistration>,
+    board_registry: Arc<Mutex<BoardRegistry>>,
     scheduler_cmd_tx: mpsc::Sender<SchedulerCommand>,
+    board_cmd_tx: mpsc::Sender<BoardCommand>,
+    history: Arc<Mutex<History>>,
 ) -> Result<()> {
-    let board_registry = Arc::new(Mutex::new(BoardRegistry::new()));
-
-    // Drain board registrations into the registry as they arrive.
-    // Exits when the sender is dropped (backplane shutdown).
-    tokio::spawn({
-        let registry = board_registry.clone();
-        async move {
-            while let Some(reg) = board_reg_rx.recv().await {
-                registry.lock().unwrap_or_else(|e| e.into_inner()).push(reg);
-            }
-        }
-    });
-
-    let app = build_router(miner_telemetry_rx, board_registry, scheduler_cmd_tx);
+    let app = build_router(
+        miner_telemetry_rx,
+        board_registry,
+        scheduler_cmd_tx,
+        board_cmd_tx,
+        history,
+        config.axeos_compat,
+    );
 
-    let listener = TcpListener::bind(&config.bind_addr).await?;
+    let listener = bind(&config.bind_addr).await?;
     let actual_addr = listener.local_addr()?;
 
     info!(url = %format!("http://{}", actual_addr), "API server listening.");
@@ -110,19 +144,33 @@ pub(crate) fn build_router(
     miner_telemetry_rx: watch::Receiver<MinerTelemetry>,
     board_registry: Arc<Mutex<BoardRegistry>>,
     scheduler_cmd_tx: mpsc::Sender<SchedulerCommand>,
+    board_cmd_tx: mpsc::Sender<BoardCommand>,
+    history: Arc<Mutex<History>>,
+    axeos_compat: bool,
 ) -> Router {
     let state = SharedState {
         miner_telemetry_rx,
         board_registry,
         scheduler_cmd_tx,
+        board_cmd_tx,
+        history,
     };
 
+    // Compatibility routes mimic other firmware, so they stay out of the
+    // OpenAPI document.
+    let mut compat = Router::new();
+    if axeos_compat {
+        compat = compat.merge(axeos::routes());
+    }
+    let compat = compat.with_state(state.clone());
+
     let (router, api) = OpenApiRouter::new()
         .nest("/api/v0", v0::routes())
         .with_state(state)
         .split_for_parts();
 
     router
+        .merge(compat)
         .route("/", routing::get(Redirect::permanent("/swagger-ui")))
         .route("/api", routing::get(Redirect::permanent("/swagger-ui")))
         .merge(SwaggerUi::new("/swagger-ui").url("/api/v0/openapi.json", api))
@@ -142,7 +190,11 @@ mod tests {
     use super::*;
     use crate::api::commands::SchedulerCommand;
     use crate::api::registry::BoardRegistration;
-    use crate::api_client::types::{BoardTelemetry, SourceTelemetry};
+    use crate::api_client::types::{
+        BoardTelemetry, Fan, Health, HealthStatus, PowerMeasurement, SchedulerState,
+        SchedulerThreadState, SourceTelemetry, TemperatureSensor, ThreadTelemetry,
+    };
+    use crate::types::Temperature;
 
     /// Test fixtures returned by the router builder.
     struct TestFixtures {
@@ -153,11 +205,21 @@ mod tests {
         _miner_tx: watch::Sender<MinerTelemetry>,
         /// Receives commands sent by PATCH handlers.
         _cmd_rx: mpsc::Receiver<SchedulerCommand>,
+        /// Recent readings served by the history endpoint.
+        history: Arc<Mutex<History>>,
     }
 
     fn build_test_router(
         miner_state: MinerTelemetry,
         board_states: Vec<BoardTelemetry>,
+    ) -> TestFixtures {
+        build_test_router_with(miner_state, board_states, false)
+    }
+
+    fn build_test_router_with(
+        miner_state: MinerTelemetry,
+        board_states: Vec<BoardTelemetry>,
+        axeos_compat: bool,
     ) -> TestFixtures {
         let (miner_tx, miner_rx) = watch::channel(miner_state);
         let (cmd_tx, cmd_rx) = mpsc::channel::<SchedulerCommand>(16);
@@ -166,15 +228,30 @@ mod tests {
         let mut board_senders = Vec::new();
         for state in board_states {
             let (tx, rx) = watch::channel(state);
-            registry.push(BoardRegistration { telemetry_rx: rx });
+            registry.push(BoardRegistration {
+                name: None,
+                telemetry_rx: rx,
+                init_duration: None,
+                profile_tx: None,
+                profile_guard: None,
+            });
             board_senders.push(tx);
         }
 
+        let history = Arc::new(Mutex::new(History::default()));
         TestFixtures {
-            router: build_router(miner_rx, Arc::new(Mutex::new(registry)), cmd_tx),
+            router: build_router(
+                miner_rx,
+                Arc::new(Mutex::new(registry)),
+                cmd_tx,
+                mpsc::channel(1).0,
+                history.clone(),
+                axeos_compat,
+            ),
             _board_senders: board_senders,
             _miner_tx: miner_tx,
             _cmd_rx: cmd_rx,
+            history,
         }
     }
 
@@ -189,12 +266,60 @@ mod tests {
         (status, String::from_utf8(body.to_vec()).unwrap())
     }
 
+    fn mining_board(name: &str, active: bool) -> BoardTelemetry {
+        BoardTelemetry {
+            name: name.into(),
+            threads: vec![ThreadTelemetry {
+                name: "t0".into(),
+                hashrate: 1_000_000,
+                is_active: active,
+                fault: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn connected_pool() -> MinerTelemetry {
+        MinerTelemetry {
+            sources: vec![SourceTelemetry {
+                name: "pool".into(),
+                connected: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
     #[tokio::test]
-    async fn health_returns_ok() {
-        let fixtures = build_test_router(MinerTelemetry::default(), vec![]);
+    async fn health_ok_when_mining() {
+        let fixtures = build_test_router(connected_pool(), vec![mining_board("a", true)]);
+        let (status, body) = get(fixtures.router.clone(), "/api/v0/health").await;
+        assert_eq!(status, 200);
+
+        let health: Health = serde_json::from_str(&body).unwrap();
+        assert_eq!(health.status, HealthStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn health_degraded_with_one_board_down() {
+        let boards = vec![mining_board("a", true), mining_board("b", false)];
+        let fixtures = build_test_router(connected_pool(), boards);
         let (status, body) = get(fixtures.router.clone(), "/api/v0/health").await;
         assert_eq!(status, 200);
-        assert_eq!(body, "OK");
+
+        let health: Health = serde_json::from_str(&body).unwrap();
+        assert_eq!(health.status, HealthStatus::Degraded);
+    }
+
+    #[tokio::test]
+    async fn health_failed_without_pool() {
+        let fixtures = build_test_router(MinerTelemetry::default(), vec![mining_board("a", true)]);
+        let (status, body) = get(fixtures.router.clone(), "/api/v0/health").await;
+        assert_eq!(status, 503);
+
+        let health: Health = serde_json::from_str(&body).unwrap();
+        assert_eq!(health.status, HealthStatus::Failed);
+        assert_eq!(health.reasons, vec!["no job source connected"]);
     }
 
     #[tokio::test]
@@ -280,6 +405,40 @@ mod tests {
         assert_eq!(status, 404);
     }
 
+    #[tokio::test]
+    async fn board_history_returns_samples_oldest_first() {
+        let boards = vec![mining_board("board-a", true), mining_board("board-b", true)];
+        let fixtures = build_test_router(MinerTelemetry::default(), boards.clone());
+        {
+            let mut history = fixtures.history.lock().unwrap();
+            let start = time::macros::datetime!(2026-03-01 12:00 UTC);
+            history.record(start, &boards);
+            history.record(start + time::Duration::seconds(10), &boards);
+        }
+
+        let (status, body) = get(fixtures.router.clone(), "/api/v0/boards/board-a/history").await;
+        assert_eq!(status, http::StatusCode::OK);
+        let samples: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0]["timestamp"], 1_772_366_400);
+        assert_eq!(samples[1]["timestamp"], 1_772_366_410);
+        assert!(samples[0]["hashrate"].is_u64());
+
+        let (status, _) = get(fixtures.router, "/api/v0/boards/missing/history").await;
+        assert_eq!(status, http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn board_history_is_empty_before_the_first_sample() {
+        let fixtures = build_test_router(
+            MinerTelemetry::default(),
+            vec![mining_board("board-a", true)],
+        );
+        let (status, body) = get(fixtures.router, "/api/v0/boards/board-a/history").await;
+        assert_eq!(status, http::StatusCode::OK);
+        assert_eq!(body, "[]");
+    }
+
     #[tokio::test]
     async fn sources_returns_list() {
         let miner_state = MinerTelemetry {
@@ -356,6 +515,141 @@ mod tests {
         assert_eq!(source.difficulty, Some(2048.5));
     }
 
+    #[tokio::test]
+    async fn scheduler_state_comes_from_the_scheduler() {
+        let TestFixtures {
+            router,
+            _miner_tx,
+            _cmd_rx: mut cmd_rx,
+            ..
+        } = build_test_router(MinerTelemetry::default(), vec![]);
+        tokio::spawn(async move {
+            let Some(SchedulerCommand::GetState { reply }) = cmd_rx.recv().await else {
+                panic!("expected GetState");
+            };
+            let _ = reply.send(SchedulerState {
+                paused: true,
+                threads: vec![SchedulerThreadState {
+                    name: "bitaxe".into(),
+                    share_difficulty: Some(512.0),
+                    ..Default::default()
+                }],
+                loop_timing: None,
+            });
+        });
+
+        let (status, body) = get(router, "/api/v0/scheduler").await;
+        assert_eq!(status, 200);
+        let state: SchedulerState = serde_json::from_str(&body).unwrap();
+        assert!(state.paused);
+        assert_eq!(state.threads[0].name, "bitaxe");
+        assert_eq!(state.threads[0].share_difficulty, Some(512.0));
+    }
+
+    #[tokio::test]
+    async fn axeos_system_info_uses_axeos_field_names_and_types() {
+        let board = BoardTelemetry {
+            name: "bitaxe-abc123".into(),
+            model: "Bitaxe Gamma".into(),
+            fans: vec![Fan {
+                name: "fan".into(),
+                rpm: Some(4200),
+                percent: Some(60),
+                target_percent: None,
+            }],
+            temperatures: vec![
+                TemperatureSensor {
+                    name: "asic".into(),
+                    temperature: Some(Temperature::from_celsius(58.5)),
+                },
+                TemperatureSensor {
+                    name: "vr".into(),
+                    temperature: Some(Temperature::from_celsius(49.0)),
+                },
+            ],
+            powers: vec![
+                PowerMeasurement {
+                    name: "input".into(),
+                    voltage_v: Some(5.0),
+                    current_a: None,
+                    power_w: None,
+                },
+                PowerMeasurement {
+                    name: "core".into(),
+                    voltage_v: Some(1.25),
+                    current_a: Some(10.0),
+                    power_w: Some(12.5),
+                },
+            ],
+            threads: vec![ThreadTelemetry {
+                name: "t0".into(),
+                hashrate: 1_200_000_000_000,
+                is_active: true,
+                fault: None,
+            }],
+            ..Default::default()
+        };
+        let miner_state = MinerTelemetry {
+            uptime_secs: 600,
+            best_share_difficulty: Some(1_500_000.0),
+            sources: vec![SourceTelemetry {
+                shares_accepted: 42,
+                shares_rejected: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let fixtures = build_test_router_with(miner_state, vec![board], true);
+
+        let (status, body) = get(fixtures.router.clone(), "/api/system/info").await;
+        assert_eq!(status, 200);
+        let info: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        let numbers = [
+            ("hashRate", 1200.0),
+            ("temp", 58.5),
+            ("vrTemp", 49.0),
+            ("power", 12.5),
+            ("voltage", 5000.0),
+            ("current", 10000.0),
+            ("coreVoltage", 1250.0),
+            ("coreVoltageActual", 1250.0),
+            ("frequency", 0.0),
+            ("sharesAccepted", 42.0),
+            ("sharesRejected", 1.0),
+            ("uptimeSeconds", 600.0),
+            ("fanspeed", 60.0),
+            ("fanrpm", 4200.0),
+        ];
+        for (field, expected) in numbers {
+            let value = info[field].as_f64();
+            assert_eq!(value, Some(expected), "{field}: {}", info[field]);
+        }
+        for field in ["sharesAccepted", "sharesRejected", "uptimeSeconds"] {
+            assert!(info[field].is_u64(), "{field} should be an integer");
+        }
+        assert_eq!(info["bestDiff"], "1.50M");
+        assert_eq!(info["bestSessionDiff"], "1.50M");
+        assert_eq!(info["ASICModel"], "Bitaxe Gamma");
+        assert!(info["version"].is_string());
+    }
+
+    #[tokio::test]
+    async fn axeos_system_info_is_off_unless_enabled() {
+        let fixtures = build_test_router(MinerTelemetry::default(), vec![]);
+        let (status, _body) = get(fixtures.router.clone(), "/api/system/info").await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn binding_a_taken_port_names_the_address() {
+        let taken = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = taken.local_addr().unwrap().to_string();
+        let err = bind(&addr).await.unwrap_err();
+        assert_eq!(err.addr, addr);
+        assert_eq!(err.source.kind(), io::ErrorKind::AddrInUse);
+    }
+
     #[tokio::test]
     async fn unknown_route_returns_404() {
         let fixtures = build_test_router(MinerTelemetry::default(), vec![]);
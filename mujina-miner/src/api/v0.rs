@@ -13,34 +13,56 @@ use std::time::Duration;
 use tokio::sync::oneshot;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
-use super::commands::SchedulerCommand;
+use super::commands::{BoardCommand, EnableError, FanTargetError, PromoteError, SchedulerCommand};
+use super::health;
+use super::registry::SetProfileError;
 use super::server::SharedState;
 use crate::api_client::types::{
-    BoardTelemetry, MinerPatchRequest, MinerTelemetry, SourceTelemetry,
+    BoardEnableRequest, BoardPatchRequest, BoardSample, BoardTelemetry, Health, HealthStatus,
+    LogFilter, MinerPatchRequest, MinerTelemetry, SchedulerState, SetFanTargetRequest,
+    SetLogFilterRequest, SourceJob, SourceTelemetry,
 };
+use crate::tracing::{LogFilterError, prelude::*};
 
 /// Build the v0 API routes with OpenAPI metadata.
 pub fn routes() -> OpenApiRouter<SharedState> {
     OpenApiRouter::new()
         .routes(routes!(health))
         .routes(routes!(get_miner, patch_miner))
+        .routes(routes!(reset_miner_stats))
         .routes(routes!(get_boards))
-        .routes(routes!(get_board))
+        .routes(routes!(get_board, patch_board))
+        .routes(routes!(enable_board))
+        .routes(routes!(set_fan_target))
+        .routes(routes!(get_board_history))
         .routes(routes!(get_sources))
         .routes(routes!(get_source))
+        .routes(routes!(get_source_job))
+        .routes(routes!(get_scheduler))
+        .routes(routes!(retarget_scheduler))
+        .routes(routes!(get_log_filter, set_log_filter))
 }
 
-/// Health check endpoint.
+/// Aggregate health check.
+///
+/// Returns 503 when the daemon is failed so orchestrators can restart it;
+/// degraded still returns 200.
 #[utoipa::path(
     get,
     path = "/health",
     tag = "health",
     responses(
-        (status = OK, description = "Server is running", body = String),
+        (status = OK, description = "Healthy or degraded", body = Health),
+        (status = SERVICE_UNAVAILABLE, description = "Failed", body = Health),
     ),
 )]
-async fn health() -> &'static str {
-    "OK"
+async fn health(State(state): State<SharedState>) -> (StatusCode, Json<Health>) {
+    let health = health::assess(&state.miner_telemetry());
+    let code = match health.status {
+        HealthStatus::Ok | HealthStatus::Degraded => StatusCode::OK,
+        HealthStatus::Failed => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (code, Json(health))
 }
 
 /// Return the current miner state snapshot.
@@ -64,6 +86,7 @@ async fn get_miner(State(state): State<SharedState>) -> Json<MinerTelemetry> {
     request_body = MinerPatchRequest,
     responses(
         (status = OK, description = "Updated miner telemetry", body = MinerTelemetry),
+        (status = UNPROCESSABLE_ENTITY, description = "No such pool"),
         (status = INTERNAL_SERVER_ERROR, description = "Command channel error"),
     ),
 )]
@@ -89,6 +112,51 @@ async fn patch_miner(
         };
     }
 
+    if let Some(pool) = req.primary_pool {
+        let (reply, rx) = oneshot::channel();
+        state
+            .scheduler_cmd_tx
+            .send(SchedulerCommand::PromotePool { pool, reply })
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        match tokio::time::timeout(Duration::from_secs(5), rx).await {
+            Ok(Ok(Ok(()))) => {}
+            Ok(Ok(Err(PromoteError::UnknownPool(_)))) => {
+                return Err(StatusCode::UNPROCESSABLE_ENTITY);
+            }
+            _ => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+
+    Ok(Json(state.miner_telemetry()))
+}
+
+/// Zero the runtime counters: shares, hardware errors and measured
+/// hashrate.
+///
+/// Lifetime figures (uptime, best share, blocks found, and the pools'
+/// accepted and rejected counts) are kept.
+#[utoipa::path(
+    post,
+    path = "/miner/stats/reset",
+    tag = "miner",
+    responses(
+        (status = OK, description = "Miner telemetry after the reset", body = MinerTelemetry),
+        (status = INTERNAL_SERVER_ERROR, description = "Command channel error"),
+    ),
+)]
+async fn reset_miner_stats(
+    State(state): State<SharedState>,
+) -> Result<Json<MinerTelemetry>, StatusCode> {
+    let (reply, rx) = oneshot::channel();
+    state
+        .scheduler_cmd_tx
+        .send(SchedulerCommand::ResetStats { reply })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let Ok(Ok(())) = tokio::time::timeout(Duration::from_secs(5), rx).await else {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    };
     Ok(Json(state.miner_telemetry()))
 }
 
@@ -139,6 +207,208 @@ async fn get_board(
         .ok_or(StatusCode::NOT_FOUND)
 }
 
+/// Apply partial updates to a board's configuration.
+///
+/// Changing the profile returns once the new profile is selected; the
+/// board's hash thread then ramps to it in the background.
+#[utoipa::path(
+    patch,
+    path = "/boards/{name}",
+    tag = "boards",
+    params(
+        ("name" = String, Path, description = "Board name"),
+    ),
+    request_body = BoardPatchRequest,
+    responses(
+        (status = OK, description = "Updated board details", body = BoardTelemetry),
+        (status = NOT_FOUND, description = "Board not found"),
+        (status = UNPROCESSABLE_ENTITY, description = "Board has no operating profiles"),
+        (status = CONFLICT, description = "Profile unsafe for the board right now"),
+    ),
+)]
+async fn patch_board(
+    State(state): State<SharedState>,
+    Path(name): Path<String>,
+    Json(req): Json<BoardPatchRequest>,
+) -> Result<Json<BoardTelemetry>, StatusCode> {
+    let mut registry = state
+        .board_registry
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(profile) = req.profile {
+        registry.set_profile(&name, profile).map_err(|e| match e {
+            SetProfileError::NotFound => StatusCode::NOT_FOUND,
+            SetProfileError::Unsupported => StatusCode::UNPROCESSABLE_ENTITY,
+            SetProfileError::Unsafe(e) => {
+                warn!(board = %name, %profile, error = %e, "Refusing unsafe profile");
+                StatusCode::CONFLICT
+            }
+        })?;
+    }
+    registry
+        .boards()
+        .into_iter()
+        .find(|b| b.name == name)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Bring a board that shut itself down back up.
+///
+/// Refused while the board's cooldown after the shutdown runs, unless
+/// `force` is set. On success the board is re-initializing; it appears
+/// again under the same name once it is up.
+#[utoipa::path(
+    post,
+    path = "/boards/{name}/enable",
+    tag = "boards",
+    params(
+        ("name" = String, Path, description = "Board name"),
+    ),
+    request_body = Option<BoardEnableRequest>,
+    responses(
+        (status = NO_CONTENT, description = "Board re-enabled"),
+        (status = NOT_FOUND, description = "Board not found"),
+        (status = CONFLICT, description = "Board has not shut down, or is still cooling down"),
+        (status = INTERNAL_SERVER_ERROR, description = "Board failed to come back up"),
+    ),
+)]
+async fn enable_board(
+    State(state): State<SharedState>,
+    Path(name): Path<String>,
+    req: Option<Json<BoardEnableRequest>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let force = req.is_some_and(|Json(req)| req.force);
+    let (reply, rx) = oneshot::channel();
+    let cmd = BoardCommand::Enable {
+        board: name,
+        force,
+        reply,
+    };
+    let unavailable = || {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "board management unavailable".to_string(),
+        )
+    };
+    state
+        .board_cmd_tx
+        .send(cmd)
+        .await
+        .map_err(|_| unavailable())?;
+    // Bringing a board up can take a while, retries included.
+    let Ok(Ok(result)) = tokio::time::timeout(Duration::from_secs(120), rx).await else {
+        return Err(unavailable());
+    };
+    result.map(|()| StatusCode::NO_CONTENT).map_err(|e| {
+        let status = match e {
+            EnableError::NotFound => StatusCode::NOT_FOUND,
+            EnableError::NotTripped | EnableError::Refused(_) => StatusCode::CONFLICT,
+            EnableError::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, e.to_string())
+    })
+}
+
+/// Hold a board's fan at a fixed duty cycle, or return it to automatic
+/// control with a null target.
+///
+/// The board still runs the fan at full speed while it is hot enough for
+/// its curve to call for that, whatever the target.
+#[utoipa::path(
+    put,
+    path = "/boards/{name}/fans/{fan}",
+    tag = "boards",
+    params(
+        ("name" = String, Path, description = "Board name"),
+        ("fan" = String, Path, description = "Fan name"),
+    ),
+    request_body = SetFanTargetRequest,
+    responses(
+        (status = NO_CONTENT, description = "Fan target set"),
+        (status = NOT_FOUND, description = "Board or fan not found"),
+        (status = UNPROCESSABLE_ENTITY, description = "Fan can't be set, or target above 100"),
+    ),
+)]
+async fn set_fan_target(
+    State(state): State<SharedState>,
+    Path((name, fan)): Path<(String, String)>,
+    Json(req): Json<SetFanTargetRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let (reply, rx) = oneshot::channel();
+    let cmd = BoardCommand::SetFanTarget {
+        board: name,
+        fan,
+        percent: req.target_percent,
+        reply,
+    };
+    let unavailable = || {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "board management unavailable".to_string(),
+        )
+    };
+    state
+        .board_cmd_tx
+        .send(cmd)
+        .await
+        .map_err(|_| unavailable())?;
+    let Ok(result) = rx.await else {
+        return Err(unavailable());
+    };
+    result.map(|()| StatusCode::NO_CONTENT).map_err(|e| {
+        let status = match e {
+            FanTargetError::NotFound | FanTargetError::NoSuchFan => StatusCode::NOT_FOUND,
+            FanTargetError::Unsupported | FanTargetError::OutOfRange(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+        };
+        (status, e.to_string())
+    })
+}
+
+/// Return a board's recent readings, oldest first.
+///
+/// Empty for a board that hasn't been sampled yet or when history is
+/// disabled; 404 if no such board is connected.
+#[utoipa::path(
+    get,
+    path = "/boards/{name}/history",
+    tag = "boards",
+    params(
+        ("name" = String, Path, description = "Board name"),
+    ),
+    responses(
+        (status = OK, description = "Recent samples", body = Vec<BoardSample>),
+        (status = NOT_FOUND, description = "Board not found"),
+    ),
+)]
+async fn get_board_history(
+    State(state): State<SharedState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<BoardSample>>, StatusCode> {
+    let samples = state
+        .history
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .board(&name);
+    if let Some(samples) = samples {
+        return Ok(Json(samples));
+    }
+    let connected = state
+        .board_registry
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .boards()
+        .iter()
+        .any(|b| b.name == name);
+    if connected {
+        Ok(Json(Vec::new()))
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
 /// Return all registered job sources.
 #[utoipa::path(
     get,
@@ -179,3 +449,129 @@ async fn get_source(
         .map(Json)
         .ok_or(StatusCode::NOT_FOUND)
 }
+
+/// Return a source's current job, for debugging share construction.
+#[utoipa::path(
+    get,
+    path = "/sources/{name}/job",
+    tag = "sources",
+    params(
+        ("name" = String, Path, description = "Source name"),
+    ),
+    responses(
+        (status = OK, description = "Current job", body = SourceJob),
+        (status = NOT_FOUND, description = "Source not found or has no job"),
+        (status = INTERNAL_SERVER_ERROR, description = "Command channel error"),
+    ),
+)]
+async fn get_source_job(
+    State(state): State<SharedState>,
+    Path(name): Path<String>,
+) -> Result<Json<SourceJob>, StatusCode> {
+    let (tx, rx) = oneshot::channel();
+    state
+        .scheduler_cmd_tx
+        .send(SchedulerCommand::GetJob {
+            source: name,
+            reply: tx,
+        })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let Ok(Ok(job)) = tokio::time::timeout(Duration::from_secs(5), rx).await else {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    };
+    job.map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Return the scheduler's share target state, for debugging.
+#[utoipa::path(
+    get,
+    path = "/scheduler",
+    tag = "scheduler",
+    responses(
+        (status = OK, description = "Scheduler state snapshot", body = SchedulerState),
+        (status = INTERNAL_SERVER_ERROR, description = "Command channel error"),
+    ),
+)]
+async fn get_scheduler(
+    State(state): State<SharedState>,
+) -> Result<Json<SchedulerState>, StatusCode> {
+    let (tx, rx) = oneshot::channel();
+    state
+        .scheduler_cmd_tx
+        .send(SchedulerCommand::GetState { reply: tx })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let Ok(Ok(snapshot)) = tokio::time::timeout(Duration::from_secs(5), rx).await else {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    };
+    Ok(Json(snapshot))
+}
+
+/// Recompute every thread's share target from its current hashrate now,
+/// rather than when the next job arrives.
+#[utoipa::path(
+    post,
+    path = "/scheduler/retarget",
+    tag = "scheduler",
+    responses(
+        (status = OK, description = "Scheduler state after the retarget", body = SchedulerState),
+        (status = INTERNAL_SERVER_ERROR, description = "Command channel error"),
+    ),
+)]
+async fn retarget_scheduler(
+    State(state): State<SharedState>,
+) -> Result<Json<SchedulerState>, StatusCode> {
+    let (reply, rx) = oneshot::channel();
+    state
+        .scheduler_cmd_tx
+        .send(SchedulerCommand::Retarget { reply })
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let Ok(Ok(snapshot)) = tokio::time::timeout(Duration::from_secs(5), rx).await else {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    };
+    Ok(Json(snapshot))
+}
+
+/// Return the log filter in effect.
+#[utoipa::path(
+    get,
+    path = "/log",
+    tag = "log",
+    responses(
+        (status = OK, description = "Log filter in effect", body = LogFilter),
+        (status = INTERNAL_SERVER_ERROR, description = "Logging not set up"),
+    ),
+)]
+async fn get_log_filter() -> Result<Json<LogFilter>, StatusCode> {
+    crate::tracing::log_filter()
+        .map(|filter| Json(LogFilter { filter }))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Replace the log filter, as if the daemon had been started with
+/// `MUJINA_LOG` set to the given directives.
+#[utoipa::path(
+    put,
+    path = "/log",
+    tag = "log",
+    request_body = SetLogFilterRequest,
+    responses(
+        (status = OK, description = "Log filter now in effect", body = LogFilter),
+        (status = UNPROCESSABLE_ENTITY, description = "Invalid directive"),
+        (status = INTERNAL_SERVER_ERROR, description = "Logging not set up"),
+    ),
+)]
+async fn set_log_filter(
+    Json(req): Json<SetLogFilterRequest>,
+) -> Result<Json<LogFilter>, StatusCode> {
+    match crate::tracing::set_log_filter(&req.filter) {
+        Ok(filter) => Ok(Json(LogFilter { filter })),
+        Err(LogFilterError::Invalid(e)) => {
+            debug!(filter = %req.filter, error = %e, "Log filter refused");
+            Err(StatusCode::UNPROCESSABLE_ENTITY)
+        }
+        Err(LogFilterError::Unavailable(_)) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
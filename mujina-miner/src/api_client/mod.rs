@@ -3,6 +3,8 @@
 //! Provides a Rust client for the miner's HTTP API, shared by the CLI
 //! and TUI binaries.
 
+pub mod bundle;
+pub mod summary;
 pub mod types;
 
 use anyhow::{Context, Result};
@@ -62,6 +64,51 @@ impl Client {
             .context("failed to parse API response")
     }
 
+    /// POST to a v0 API endpoint, without a body, and deserialize the JSON
+    /// response.
+    pub async fn post_json<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let url = format!("{}/api/v0/{}", self.base_url, path);
+        let response = self
+            .http
+            .post(&url)
+            .send()
+            .await
+            .context("failed to connect to miner API")?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("API request failed: {}", status);
+        }
+        response
+            .json()
+            .await
+            .context("failed to parse API response")
+    }
+
+    /// PUT `body` as JSON to a v0 API endpoint and deserialize the JSON
+    /// response.
+    pub async fn put_json<B: serde::Serialize, T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        let url = format!("{}/api/v0/{}", self.base_url, path);
+        let response = self
+            .http
+            .put(&url)
+            .json(body)
+            .send()
+            .await
+            .context("failed to connect to miner API")?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("API request failed: {}", status);
+        }
+        response
+            .json()
+            .await
+            .context("failed to parse API response")
+    }
+
     /// GET a v0 API endpoint and return the raw response body.
     pub async fn get_raw(&self, path: &str) -> Result<String> {
         let url = format!("{}/api/v0/{}", self.base_url, path);
@@ -0,0 +1,268 @@
+//! Fleet-wide totals.
+//!
+//! Reduces a [`MinerTelemetry`] snapshot to the handful of numbers an
+//! operator running several boards wants on one line: how fast, how much
+//! power, how efficiently, how many shares landed, and how hot the hottest
+//! board is.
+//!
+//! Efficiency is total power over total hashrate, restricted to boards that
+//! report power. Averaging each board's J/TH would let a small, inefficient
+//! board count as much as a large one.
+
+use std::fmt;
+
+use super::types::{BoardTelemetry, MinerTelemetry};
+use crate::types::{HashRate, NumberGrouping, Temperature, TemperatureUnit};
+
+/// Aggregate figures across every board and source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FleetSummary {
+    /// Boards in the snapshot.
+    pub boards: usize,
+
+    /// Sum of every hash thread's hashrate.
+    pub hashrate: HashRate,
+
+    /// Sum of board power in watts, `None` when no board reports power.
+    pub power_w: Option<f32>,
+
+    /// Joules per terahash over the boards that report power, `None` when
+    /// none do or they are not hashing.
+    pub efficiency_j_per_th: Option<f64>,
+
+    /// Shares accepted across all sources.
+    pub shares_accepted: u64,
+
+    /// Shares rejected across all sources.
+    pub shares_rejected: u64,
+
+    /// Hottest sensor reading in the fleet.
+    pub hottest: Option<BoardTemperature>,
+}
+
+/// A temperature reading and the board it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoardTemperature {
+    pub board: String,
+    pub temperature: Temperature,
+}
+
+/// Aggregate a telemetry snapshot into fleet totals.
+pub fn fleet_summary(telemetry: &MinerTelemetry) -> FleetSummary {
+    let mut hashrate = HashRate::default();
+    let mut power_w = None;
+    let mut metered_hashrate = HashRate::default();
+    let mut hottest: Option<BoardTemperature> = None;
+
+    for board in &telemetry.boards {
+        let board_hashrate: HashRate = board
+            .threads
+            .iter()
+            .map(|t| HashRate::from(t.hashrate))
+            .sum();
+        hashrate = hashrate + board_hashrate;
+
+        if let Some(watts) = board_power_w(board) {
+            *power_w.get_or_insert(0.0) += watts;
+            metered_hashrate = metered_hashrate + board_hashrate;
+        }
+
+        for temperature in board.temperatures.iter().filter_map(|s| s.temperature) {
+            if hottest.as_ref().is_none_or(|h| temperature > h.temperature) {
+                hottest = Some(BoardTemperature {
+                    board: board.name.clone(),
+                    temperature,
+                });
+            }
+        }
+    }
+
+    let efficiency_j_per_th = power_w
+        .filter(|_| !metered_hashrate.is_zero())
+        .map(|watts| f64::from(watts) / metered_hashrate.as_terahashes());
+
+    FleetSummary {
+        boards: telemetry.boards.len(),
+        hashrate,
+        power_w,
+        efficiency_j_per_th,
+        shares_accepted: telemetry.sources.iter().map(|s| s.shares_accepted).sum(),
+        shares_rejected: telemetry.sources.iter().map(|s| s.shares_rejected).sum(),
+        hottest,
+    }
+}
+
+/// Power drawn by one board.
+///
+/// The input rail covers everything the board draws, so it wins when
+/// measured. Otherwise the measured rails are summed; boards report either
+/// input or per-rail power, and adding both would count the core twice.
+pub(crate) fn board_power_w(board: &BoardTelemetry) -> Option<f32> {
+    let input = board
+        .powers
+        .iter()
+        .find(|p| p.name == "input")
+        .and_then(|p| p.power_w);
+    input.or_else(|| {
+        board
+            .powers
+            .iter()
+            .filter_map(|p| p.power_w)
+            .reduce(|a, b| a + b)
+    })
+}
+
+impl FleetSummary {
+    /// Format with share counts grouped by `grouping`.
+    pub fn display(&self, grouping: NumberGrouping) -> DisplayFleetSummary<'_> {
+        DisplayFleetSummary {
+            summary: self,
+            grouping,
+        }
+    }
+}
+
+/// Share counts grouped as `MUJINA_NUMBER_GROUPING` says.
+impl fmt::Display for FleetSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display(NumberGrouping::configured()).fmt(f)
+    }
+}
+
+/// A fleet summary formatted with a chosen grouping, from
+/// [`FleetSummary::display`].
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayFleetSummary<'a> {
+    summary: &'a FleetSummary,
+    grouping: NumberGrouping,
+}
+
+impl fmt::Display for DisplayFleetSummary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = self.summary;
+        write!(f, "{} boards, {}", summary.boards, summary.hashrate)?;
+        if let Some(watts) = summary.power_w {
+            write!(f, ", {watts:.1} W")?;
+        }
+        if let Some(efficiency) = summary.efficiency_j_per_th {
+            write!(f, ", {efficiency:.1} J/TH")?;
+        }
+        write!(
+            f,
+            ", {} accepted, {} rejected",
+            self.grouping.display(summary.shares_accepted),
+            self.grouping.display(summary.shares_rejected)
+        )?;
+        if let Some(hottest) = &summary.hottest {
+            write!(
+                f,
+                ", hottest {} ({})",
+                hottest.temperature.display(TemperatureUnit::configured()),
+                hottest.board
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::types::SourceTelemetry;
+
+    fn board(name: &str, th: &[f64], power_w: Option<f32>, temp_c: f32) -> BoardTelemetry {
+        th.iter().fold(
+            BoardTelemetry::named(name)
+                .with_temperature(temp_c)
+                .with_power("core", power_w),
+            |board, &th| board.with_thread(HashRate::from_terahashes(th).into(), true),
+        )
+    }
+
+    fn source(accepted: u64, rejected: u64) -> SourceTelemetry {
+        SourceTelemetry {
+            shares_accepted: accepted,
+            shares_rejected: rejected,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn aggregates_several_boards() {
+        let telemetry = MinerTelemetry {
+            boards: vec![
+                // 1 TH/s at 15 J/TH and 9 TH/s at 25 J/TH.
+                board("small", &[1.0], Some(15.0), 55.0),
+                board("big", &[4.0, 5.0], Some(225.0), 71.5),
+                // Hashing, but no power sensor: excluded from efficiency.
+                board("cpu", &[0.5], None, 40.0),
+            ],
+            sources: vec![source(100, 3), source(20, 1)],
+            ..Default::default()
+        };
+
+        let summary = fleet_summary(&telemetry);
+        assert_eq!(summary.boards, 3);
+        assert_eq!(summary.hashrate, HashRate::from_terahashes(10.5));
+        assert_eq!(summary.power_w, Some(240.0));
+
+        // 240 W over the 10 TH/s that is metered. Averaging the two ratios
+        // would give 20 J/TH.
+        let efficiency = summary.efficiency_j_per_th.unwrap();
+        assert!((efficiency - 24.0).abs() < 1e-9, "got {efficiency}");
+
+        assert_eq!(summary.shares_accepted, 120);
+        assert_eq!(summary.shares_rejected, 4);
+        assert_eq!(
+            summary.hottest,
+            Some(BoardTemperature {
+                board: "big".into(),
+                temperature: Temperature::from_celsius(71.5),
+            })
+        );
+    }
+
+    #[test]
+    fn input_power_wins_over_rails() {
+        let b = board("bitaxe", &[1.0], Some(15.0), 50.0).with_power("input", Some(18.0));
+        assert_eq!(board_power_w(&b), Some(18.0));
+    }
+
+    #[test]
+    fn empty_fleet_has_no_derived_figures() {
+        let summary = fleet_summary(&MinerTelemetry::default());
+        assert_eq!(summary.boards, 0);
+        assert!(summary.hashrate.is_zero());
+        assert_eq!(summary.power_w, None);
+        assert_eq!(summary.efficiency_j_per_th, None);
+        assert_eq!(summary.hottest, None);
+        assert_eq!(
+            summary.to_string(),
+            "0 boards, 0 H/s, 0 accepted, 0 rejected"
+        );
+    }
+
+    #[test]
+    fn grouping_reaches_people_not_machines() {
+        let telemetry = MinerTelemetry {
+            sources: vec![source(1_234_567, 2_048)],
+            ..Default::default()
+        };
+        let summary = fleet_summary(&telemetry);
+        assert_eq!(
+            summary.display(NumberGrouping::Comma).to_string(),
+            "0 boards, 0 H/s, 1,234,567 accepted, 2,048 rejected"
+        );
+        assert_eq!(
+            summary.display(NumberGrouping::Off).to_string(),
+            "0 boards, 0 H/s, 1234567 accepted, 2048 rejected"
+        );
+
+        // JSON and CSV carry the counts as plain numbers, whatever the
+        // grouping.
+        let json = serde_json::to_string(&telemetry).unwrap();
+        assert!(json.contains(r#""shares_accepted":1234567"#), "{json}");
+        let rows = crate::stats_csv::rows(time::OffsetDateTime::UNIX_EPOCH, &telemetry);
+        assert!(rows[0].ends_with(",1234567,2048"), "{rows:?}");
+    }
+}
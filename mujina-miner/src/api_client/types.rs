@@ -5,9 +5,12 @@
 //! for the full API contract documentation, including conventions
 //! for null values and units.
 
+use std::collections::BTreeSet;
+
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::board::profile::Profile;
 use crate::types::Temperature;
 
 /// Full miner telemetry snapshot.
@@ -17,9 +20,57 @@ pub struct MinerTelemetry {
     /// Aggregate hashrate in hashes per second.
     pub hashrate: u64,
     pub shares_submitted: u64,
+    /// Shares dropped because a board reported the same nonce twice for
+    /// a job. Counted apart from hardware errors.
+    #[serde(default)]
+    pub duplicate_shares: u64,
+    /// Shares found but never submitted, by reason.
+    #[serde(default)]
+    pub unsubmitted_shares: UnsubmittedShares,
+    /// Highest difficulty of any share found since startup.
+    #[serde(default)]
+    pub best_share_difficulty: Option<f64>,
+    /// Shares found that met the network target.
+    #[serde(default)]
+    pub blocks_found: u64,
+    /// Difficulty of the network target in the latest job.
+    #[serde(default)]
+    pub network_difficulty: Option<f64>,
+    /// Mean time for the current hashrate to find a block at the network
+    /// difficulty. `None` while nothing is hashing or no job has arrived.
+    #[serde(default)]
+    pub expected_time_to_block_secs: Option<f64>,
     pub paused: bool,
+    /// Energy every board has used, in kWh, including boards since
+    /// disconnected.
+    #[serde(default)]
+    pub energy_kwh: f64,
     pub boards: Vec<BoardTelemetry>,
     pub sources: Vec<SourceTelemetry>,
+    /// Names of the boards with a share accepted since startup, from
+    /// which the API sets each board's `confirmed`.
+    #[serde(skip)]
+    pub confirmed_boards: BTreeSet<String>,
+}
+
+/// Shares found but never submitted, by reason. Shares the pool saw and
+/// refused are counted per source as `shares_rejected` instead.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct UnsubmittedShares {
+    /// Found on work that had been replaced or cleared, or withheld by
+    /// the source as too old (see `SourceTelemetry::shares_stale_avoided`).
+    pub stale: u64,
+    /// Below the source's share difficulty, including any
+    /// `MUJINA_POOL_MIN_DIFFICULTY` floor.
+    pub below_target: u64,
+    /// The same nonce reported twice for a job.
+    pub duplicate: u64,
+    /// Nonces whose hash failed the chip's own difficulty when checked
+    /// on the host.
+    pub hardware_error: u64,
+    /// Discarded from a source's submit queue because the pool fell
+    /// behind or stopped answering.
+    pub queue_dropped: u64,
 }
 
 /// Board telemetry snapshot.
@@ -29,10 +80,81 @@ pub struct BoardTelemetry {
     pub name: String,
     pub model: String,
     pub serial: Option<String>,
+    /// Firmware version reported by the board, if it reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firmware_version: Option<String>,
     pub fans: Vec<Fan>,
     pub temperatures: Vec<TemperatureSensor>,
     pub powers: Vec<PowerMeasurement>,
     pub threads: Vec<ThreadTelemetry>,
+    /// Seconds the board took to come up, from the first initialization
+    /// attempt (power-on and chip setup) until its threads were handed
+    /// to the scheduler, retries included. Absent when not measured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub init_secs: Option<f64>,
+    /// Selected operating profile. Absent for boards without profiles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+    /// Why the board shut itself down, such as a thermal emergency.
+    /// Absent while it runs. See `POST /api/v0/boards/{name}/enable`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault: Option<String>,
+    /// Energy the board has used, in kWh, integrated from its power
+    /// readings. Absent while it hasn't reported power.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub energy_kwh: Option<f64>,
+    /// Whether the pool has accepted a share from this board since
+    /// startup, confirming it is really mining.
+    #[serde(default)]
+    pub confirmed: bool,
+}
+
+#[cfg(test)]
+impl BoardTelemetry {
+    /// A board called `name` reporting nothing yet, for tests to fill in.
+    pub(crate) fn named(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the model the board reports.
+    pub(crate) fn with_model(mut self, model: &str) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Add a thread hashing at `hashrate` H/s, named `t0`, `t1` and so on.
+    pub(crate) fn with_thread(mut self, hashrate: u64, is_active: bool) -> Self {
+        self.threads.push(ThreadTelemetry {
+            name: format!("t{}", self.threads.len()),
+            hashrate,
+            is_active,
+            fault: None,
+        });
+        self
+    }
+
+    /// Add an ASIC temperature reading.
+    pub(crate) fn with_temperature(mut self, celsius: f32) -> Self {
+        self.temperatures.push(TemperatureSensor {
+            name: "asic".into(),
+            temperature: Some(Temperature::from_celsius(celsius)),
+        });
+        self
+    }
+
+    /// Add a power measurement point `name` drawing `power_w`.
+    pub(crate) fn with_power(mut self, name: &str, power_w: Option<f32>) -> Self {
+        self.powers.push(PowerMeasurement {
+            name: name.into(),
+            voltage_v: None,
+            current_a: None,
+            power_w,
+        });
+        self
+    }
 }
 
 /// Fan status.
@@ -72,6 +194,23 @@ pub struct ThreadTelemetry {
     /// Hashrate in hashes per second.
     pub hashrate: u64,
     pub is_active: bool,
+    /// Why the thread's results can't be trusted, such as a chip
+    /// returning nonces that don't vary. Absent while it looks healthy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault: Option<String>,
+}
+
+/// One reading from a board's recent history.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct BoardSample {
+    /// When the sample was taken, in Unix seconds.
+    pub timestamp: i64,
+    /// Hashrate in hashes per second.
+    pub hashrate: u64,
+    /// Hottest sensor reading, in degrees Celsius.
+    pub temperature_c: Option<f32>,
+    /// Power drawn by the board, in watts.
+    pub power_w: Option<f32>,
 }
 
 /// Writable fields for `PATCH /api/v0/miner`.
@@ -83,6 +222,29 @@ pub struct ThreadTelemetry {
 pub struct MinerPatchRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub paused: Option<bool>,
+    /// Pool for boards without a `MUJINA_BOARD_POOLS` assignment to mine
+    /// on, by its `MUJINA_POOLS` name or `default`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_pool: Option<String>,
+}
+
+/// Writable fields for `PATCH /api/v0/boards/{name}`.
+///
+/// All fields are optional; only those present in the request body are
+/// applied.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct BoardPatchRequest {
+    /// Operating profile to switch to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+}
+
+/// Request body for `POST /api/v0/boards/{name}/enable`. May be omitted.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct BoardEnableRequest {
+    /// Re-enable without waiting for the cooldown.
+    #[serde(default)]
+    pub force: bool,
 }
 
 /// Request body for setting a fan's target duty cycle.
@@ -92,6 +254,21 @@ pub struct SetFanTargetRequest {
     pub target_percent: Option<u8>,
 }
 
+/// The log filter in effect, from `GET /api/v0/log`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct LogFilter {
+    /// `EnvFilter` directives, such as `mujina_miner=debug,warn`.
+    pub filter: String,
+}
+
+/// Request body for `PUT /api/v0/log`.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct SetLogFilterRequest {
+    /// Directives as `MUJINA_LOG` takes them, such as `debug` or
+    /// `stratum_v1=trace`. Empty goes back to the defaults.
+    pub filter: String,
+}
+
 /// Job source telemetry.
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct SourceTelemetry {
@@ -99,12 +276,178 @@ pub struct SourceTelemetry {
     /// Connection URL (e.g. "stratum+tcp://pool:3333"), if applicable.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
+    /// Whether the source is currently supplying work. For a pool this
+    /// tracks the connection: false until the first job arrives and again
+    /// after a disconnect clears the source's jobs.
+    pub connected: bool,
     /// Current share difficulty set by the source.
     #[serde(
         skip_serializing_if = "Option::is_none",
         serialize_with = "serialize_opt_f64_as_integer_when_whole"
     )]
     pub difficulty: Option<f64>,
+    /// Seconds from pool authorization to the first job on the current
+    /// connection. Absent until that job arrives, or for sources without
+    /// a handshake.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_first_job_secs: Option<f64>,
+    /// Shares discarded because the pool could not keep up with
+    /// submissions. Block solutions are never dropped.
+    #[serde(default)]
+    pub shares_dropped: u64,
+    /// Shares not submitted because their job had been replaced and had
+    /// outlived `MUJINA_POOL_MAX_JOB_AGE_SECS`, or had dropped out of the
+    /// `MUJINA_POOL_JOB_HISTORY` recent jobs, so the pool would have
+    /// rejected them as stale. Block solutions are always submitted.
+    #[serde(default)]
+    pub shares_stale_avoided: u64,
+    /// Shares the source acknowledged as valid.
+    #[serde(default)]
+    pub shares_accepted: u64,
+    /// Shares the source refused.
+    #[serde(default)]
+    pub shares_rejected: u64,
+    /// Shares accepted since the start of the stats day (midnight UTC
+    /// unless `MUJINA_STATS_DAY_OFFSET` says otherwise).
+    #[serde(default)]
+    pub shares_accepted_today: u64,
+    /// Shares refused since the start of the stats day.
+    #[serde(default)]
+    pub shares_rejected_today: u64,
+    /// Why the source gave up on its pool, such as running out of
+    /// connection attempts. Absent while it is still trying.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+    /// Whether the pool's vardiff keeps swinging the difficulty up and
+    /// down by large factors on the current connection.
+    #[serde(default)]
+    pub difficulty_oscillating: bool,
+    /// New blocks on the network seen through the source's jobs: each
+    /// time a job arrived with a different previous block hash.
+    #[serde(default)]
+    pub network_blocks_seen: u64,
+    /// Times the source's connection came back after an outage longer
+    /// than `MUJINA_POOL_RECONNECT_GRACE_MS`.
+    #[serde(default)]
+    pub reconnects: u64,
+}
+
+/// A source's current job, as returned by `GET /api/v0/sources/{name}/job`.
+///
+/// For checking by hand that headers are built from what the pool sent.
+/// Byte strings are hex in the order they arrive over Stratum, except
+/// `prev_blockhash`, which is in the order block explorers show.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SourceJob {
+    pub job_id: String,
+    pub prev_blockhash: String,
+    /// Block version, as 8 hex digits.
+    pub version: String,
+    /// Encoded network target, as 8 hex digits.
+    pub nbits: String,
+    /// Block timestamp, Unix seconds.
+    pub ntime: u32,
+    /// Share difficulty the job was issued at.
+    pub difficulty: f64,
+    /// Coinbase and merkle parts. Absent for jobs with a fixed merkle
+    /// root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coinbase: Option<SourceJobCoinbase>,
+}
+
+/// The parts a job's coinbase transaction and merkle root are built from.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SourceJobCoinbase {
+    pub coinbase1: String,
+    pub extranonce1: String,
+    /// Bytes of extranonce2 rolled between `extranonce1` and `coinbase2`.
+    pub extranonce2_size: u8,
+    pub coinbase2: String,
+    pub merkle_branches: Vec<String>,
+}
+
+/// Scheduler internals, as returned by `GET /api/v0/scheduler`.
+///
+/// For debugging share target selection; fields may change without
+/// notice even within v0.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SchedulerState {
+    pub paused: bool,
+    pub threads: Vec<SchedulerThreadState>,
+    /// How long the scheduler loop spends handling each event, when
+    /// `MUJINA_SCHEDULER_LOOP_TIMING` is set.
+    #[serde(default)]
+    pub loop_timing: Option<LoopTiming>,
+}
+
+/// Histogram of scheduler loop iterations, from when an event woke the
+/// loop to when it was ready for the next.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct LoopTiming {
+    pub iterations: u64,
+    pub mean_us: u64,
+    pub max_us: u64,
+    /// Iterations by duration, fastest first.
+    pub buckets: Vec<LatencyBucket>,
+}
+
+/// Iterations that took at most `le_us` microseconds and longer than
+/// the bucket before; `le_us` is null for the slowest bucket.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct LatencyBucket {
+    pub le_us: Option<u64>,
+    pub count: u64,
+}
+
+/// One thread's share target inputs and outputs.
+///
+/// The share target follows the source's difficulty, clamped to between
+/// `min_difficulty` and `max_difficulty`. Those are computed from the
+/// measured hashrate once it has settled, else the expected one, and are
+/// null while both are unknown.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct SchedulerThreadState {
+    pub name: String,
+    /// Hashrate the thread declared, in hashes per second.
+    pub expected_hashrate: Option<u64>,
+    /// Hashrate measured from the thread's shares, in hashes per second,
+    /// once enough have arrived to trust it.
+    pub measured_hashrate: Option<u64>,
+    /// Shares the measurement is over; `measured_hashrate` is null while
+    /// there are fewer than `MUJINA_HASHRATE_MIN_SHARES`.
+    #[serde(default)]
+    pub hashrate_shares: usize,
+    /// Difficulty of the share target last given to the thread.
+    pub share_difficulty: Option<f64>,
+    /// Easiest allowed difficulty: about 10 shares per second, so the
+    /// thread can't flood the scheduler.
+    pub min_difficulty: Option<f64>,
+    /// Hardest allowed difficulty: about 1 share per second, so the
+    /// hashrate can still be measured.
+    pub max_difficulty: Option<f64>,
+}
+
+/// Overall daemon health, as returned by `GET /api/v0/health`.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct Health {
+    pub status: HealthStatus,
+    /// Human-readable explanations for a non-ok status, empty when ok.
+    pub reasons: Vec<String>,
+}
+
+/// Aggregate health level.
+///
+/// See `docs/api.md` for the criteria behind each level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Every board is mining, every source is connected, nothing is
+    /// overheating.
+    Ok,
+    /// Mining, but something needs attention.
+    Degraded,
+    /// Not mining; a restart or operator action is needed.
+    Failed,
 }
 
 /// Serialize an `Option<f64>` so that whole numbers appear without a
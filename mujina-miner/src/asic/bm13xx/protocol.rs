@@ -15,7 +15,7 @@ use super::crc::{crc5, crc5_is_valid, crc16};
 use super::error::ProtocolError;
 use crate::job_source::GeneralPurposeBits;
 use crate::tracing::prelude::*;
-use crate::types::Difficulty;
+use crate::types::{Difficulty, Target};
 
 /// Wrapper for formatting byte slices as space-separated hex.
 struct HexBytes<'a>(&'a [u8]);
@@ -308,9 +308,12 @@ impl Log2Difficulty {
     /// A nonce that passes the ASIC's difficulty filter represents
     /// this many hashes of work on average.
     pub fn to_work(&self) -> Work {
-        Difficulty::from(1_u64 << self.exponent)
-            .to_target()
-            .to_work()
+        self.to_target().to_work()
+    }
+
+    /// Target every nonce passing the ASIC's difficulty filter meets.
+    pub fn to_target(&self) -> Target {
+        Difficulty::from(1_u64 << self.exponent).to_target()
     }
 }
 
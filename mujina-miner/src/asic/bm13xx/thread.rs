@@ -12,10 +12,11 @@ use std::sync::{Arc, RwLock};
 
 use anyhow::{Context as _, Result, anyhow};
 use async_trait::async_trait;
-use bitcoin::block::Header as BlockHeader;
 use futures::{SinkExt, sink::Sink, stream::Stream};
 use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::Instant;
 use tokio_stream::StreamExt;
+use tracing::Instrument;
 
 use super::protocol::{self, Log2Difficulty, TicketMask};
 use crate::{
@@ -23,6 +24,10 @@ use crate::{
         BoardPeripherals, HashTask, HashThread, HashThreadCapabilities, HashThreadEvent,
         HashThreadStatus, Share, ThreadRemovalSignal,
     },
+    asic::hw_error_reset::{HwErrorMonitor, ResetPolicy, Verdict},
+    asic::nonce_entropy::{NonceEntropyMonitor, Transition},
+    board::profile::{OperatingPoint, ProfileSelection},
+    job_source::{GeneralPurposeBits, header},
     tracing::prelude::*,
     types::{Difficulty, HashRate, ShareRate},
 };
@@ -32,8 +37,13 @@ use crate::{
 /// BM13xx chips use 4-bit job IDs. This tracker maintains snapshots of
 /// HashTasks sent to the chip so we can match nonce responses back to the
 /// correct task context (EN2, ntime, etc.).
+///
+/// Jobs invalidated by a replacement are kept aside until their ID is
+/// reused, so a result the chip still had in flight is recognized as
+/// stale rather than mistaken for garbage.
 struct ChipJobTracker {
     tasks: [Option<HashTask>; 16],
+    flushed: [Option<HashTask>; 16],
     next_id: u8,
 }
 
@@ -41,6 +51,7 @@ impl ChipJobTracker {
     fn new() -> Self {
         Self {
             tasks: Default::default(),
+            flushed: Default::default(),
             next_id: 0,
         }
     }
@@ -48,6 +59,7 @@ impl ChipJobTracker {
     fn insert(&mut self, task: HashTask) -> u8 {
         let chip_job_id = self.next_id;
         self.tasks[chip_job_id as usize] = Some(task);
+        self.flushed[chip_job_id as usize] = None;
         self.next_id = (self.next_id + 1) % (self.tasks.len() as u8);
         chip_job_id
     }
@@ -58,11 +70,44 @@ impl ChipJobTracker {
             .and_then(|t| t.as_ref())
     }
 
+    /// The invalidated job `chip_job_id` last named, if its ID hasn't been
+    /// reused since.
+    fn flushed(&self, chip_job_id: u8) -> Option<&HashTask> {
+        self.flushed
+            .get(chip_job_id as usize)
+            .and_then(|t| t.as_ref())
+    }
+
+    /// Invalidate every job, keeping them aside as flushed.
+    fn flush(&mut self) {
+        for (live, flushed) in self.tasks.iter_mut().zip(&mut self.flushed) {
+            if let Some(task) = live.take() {
+                *flushed = Some(task);
+            }
+        }
+    }
+
+    /// Forget every job, as when the chip itself has.
     fn clear(&mut self) {
         self.tasks = Default::default();
+        self.flushed = Default::default();
     }
 }
 
+/// The version and hash of the header `nonce` completes for `task`, `None`
+/// when the task's merkle root can't be computed.
+fn nonce_hash(
+    task: &HashTask,
+    version: GeneralPurposeBits,
+    nonce: u32,
+) -> Option<(bitcoin::block::Version, bitcoin::BlockHash)> {
+    let template = task.template.as_ref();
+    let full_version = version.apply_to_version(template.version.base());
+    let merkle_root = template.compute_merkle_root(task.en2.as_ref()?).ok()?;
+    let header = header::build(template, merkle_root, full_version, task.ntime, nonce);
+    Some((full_version, header::block_hash(&header)))
+}
+
 /// Command messages sent from scheduler to thread
 #[derive(Debug)]
 enum ThreadCommand {
@@ -125,12 +170,14 @@ impl BM13xxThread {
     /// * `chip_commands` - Sink for sending encoded commands to chips
     /// * `peripherals` - Hardware interfaces from board (enable, regulator, etc.)
     /// * `removal_rx` - Watch channel for board-triggered removal
+    /// * `profile` - Operating profile to run at, and changes to it
     pub fn new<R, W>(
         name: String,
         chip_responses: R,
         chip_commands: W,
         peripherals: BoardPeripherals,
         removal_rx: watch::Receiver<ThreadRemovalSignal>,
+        profile: ProfileSelection,
     ) -> Self
     where
         R: Stream<Item = Result<protocol::Response, std::io::Error>> + Unpin + Send + 'static,
@@ -144,18 +191,22 @@ impl BM13xxThread {
         let status_clone = Arc::clone(&status);
 
         // Spawn the actor task
-        tokio::spawn(async move {
-            bm13xx_thread_actor(
-                cmd_rx,
-                evt_tx,
-                removal_rx,
-                status_clone,
-                chip_responses,
-                chip_commands,
-                peripherals,
-            )
-            .await;
-        });
+        tokio::spawn(
+            async move {
+                bm13xx_thread_actor(
+                    cmd_rx,
+                    evt_tx,
+                    removal_rx,
+                    status_clone,
+                    chip_responses,
+                    chip_commands,
+                    peripherals,
+                    profile,
+                )
+                .await;
+            }
+            .in_current_span(),
+        );
 
         Self {
             name,
@@ -165,6 +216,14 @@ impl BM13xxThread {
             status,
         }
     }
+
+    /// Shared handle to this thread's status.
+    ///
+    /// Lets the board keep reporting thread state in its telemetry after
+    /// the thread itself has been handed to the scheduler.
+    pub fn status_handle(&self) -> Arc<RwLock<HashThreadStatus>> {
+        Arc::clone(&self.status)
+    }
 }
 
 #[async_trait]
@@ -238,13 +297,120 @@ impl HashThread for BM13xxThread {
     }
 }
 
+/// Frequency the PLL ramp starts from at power-on.
+const RAMP_START_MHZ: f32 = 56.25;
+
+/// Frequency change per PLL ramp step.
+const RAMP_STEP_MHZ: f32 = 6.25;
+
+/// Settling time after each PLL ramp step.
+const RAMP_STEP_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Send a register write command, converting the sink error to anyhow.
+async fn send_reg<W>(
+    chip_commands: &mut W,
+    broadcast: bool,
+    register: protocol::Register,
+) -> Result<()>
+where
+    W: Sink<protocol::Command> + Unpin,
+    W::Error: std::fmt::Debug,
+{
+    chip_commands
+        .send(protocol::Command::WriteRegister {
+            broadcast,
+            chip_address: 0x00,
+            register,
+        })
+        .await
+        .map_err(|e| anyhow!("{e:?}"))
+}
+
+/// Step the PLL from `mhz` to `to_mhz`, in either direction.
+///
+/// `mhz` follows each step written, so if one fails it holds the
+/// frequency the chip was left at.
+async fn ramp_frequency<W>(chip_commands: &mut W, mhz: &mut f32, to_mhz: f32) -> Result<()>
+where
+    W: Sink<protocol::Command> + Unpin,
+    W::Error: std::fmt::Debug,
+{
+    debug!("Ramping frequency from {mhz} MHz to {to_mhz} MHz");
+    let frequency_steps = frequency_ramp(*mhz, to_mhz, RAMP_STEP_MHZ);
+
+    for (i, &(step_mhz, pll_config)) in frequency_steps.iter().enumerate() {
+        send_reg(
+            chip_commands,
+            true,
+            protocol::Register::PllDivider(pll_config),
+        )
+        .await
+        .context("PLL ramp failed")?;
+        *mhz = step_mhz;
+
+        tokio::time::sleep(RAMP_STEP_DELAY).await;
+
+        if i % 10 == 0 || i == frequency_steps.len() - 1 {
+            trace!("Frequency ramp step {}/{}", i + 1, frequency_steps.len());
+        }
+    }
+
+    debug!("Frequency ramping complete");
+    Ok(())
+}
+
+/// Move a running chip from the operating point `current` to `to`.
+///
+/// The clock ramps through the same steps as at power-on. Voltage is
+/// raised before a clock increase and lowered after a decrease, so the
+/// chip never runs faster than its current voltage supports. `current`
+/// follows each change that takes effect, so if one fails it holds the
+/// point the chip was left at.
+async fn apply_operating_point<W>(
+    chip_commands: &mut W,
+    peripherals: &mut BoardPeripherals,
+    current: &mut OperatingPoint,
+    to: OperatingPoint,
+) -> Result<()>
+where
+    W: Sink<protocol::Command> + Unpin,
+    W::Error: std::fmt::Debug,
+{
+    let raising = to.frequency_mhz > current.frequency_mhz;
+    if raising {
+        set_core_voltage(peripherals, to.core_voltage_v).await?;
+        current.core_voltage_v = to.core_voltage_v;
+    }
+    if to.frequency_mhz != current.frequency_mhz {
+        ramp_frequency(chip_commands, &mut current.frequency_mhz, to.frequency_mhz).await?;
+    }
+    if !raising {
+        set_core_voltage(peripherals, to.core_voltage_v).await?;
+    }
+    *current = to;
+    Ok(())
+}
+
+/// Set the core voltage, if the board lets the thread control it.
+async fn set_core_voltage(peripherals: &mut BoardPeripherals, volts: f32) -> Result<()> {
+    if let Some(ref mut regulator) = peripherals.voltage_regulator {
+        regulator
+            .set_voltage(volts)
+            .await
+            .context("failed to set core voltage")?;
+    }
+    Ok(())
+}
+
 /// Initialize BM13xx chip for mining.
 ///
-/// Enables chip, configures all registers, and ramps frequency to target.
+/// Enables chip, configures all registers, and ramps frequency to the
+/// operating point's clock.
 async fn initialize_chip<W>(
     chip_commands: &mut W,
     peripherals: &mut BoardPeripherals,
     asic_difficulty: Log2Difficulty,
+    operating_point: OperatingPoint,
 ) -> Result<()>
 where
     W: Sink<protocol::Command> + Unpin,
@@ -252,6 +418,8 @@ where
 {
     use protocol::{Command, Register};
 
+    set_core_voltage(peripherals, operating_point.core_voltage_v).await?;
+
     // Enable the ASIC
     if let Some(ref mut asic_enable) = peripherals.asic_enable {
         debug!("Enabling ASIC");
@@ -263,22 +431,6 @@ where
 
     tokio::time::sleep(std::time::Duration::from_millis(200)).await;
 
-    // Send a register write command, converting the sink error to anyhow.
-    async fn send_reg<W>(chip_commands: &mut W, broadcast: bool, register: Register) -> Result<()>
-    where
-        W: Sink<protocol::Command> + Unpin,
-        W::Error: std::fmt::Debug,
-    {
-        chip_commands
-            .send(Command::WriteRegister {
-                broadcast,
-                chip_address: 0x00,
-                register,
-            })
-            .await
-            .map_err(|e| anyhow!("{e:?}"))
-    }
-
     // Send version mask configuration (3 times)
     debug!("Configuring version mask");
     for _ in 1..=3 {
@@ -435,23 +587,8 @@ where
     )
     .await?;
 
-    // Frequency ramping (56.25 MHz -> 525 MHz)
-    debug!("Ramping frequency from 56.25 MHz to 525 MHz");
-    let frequency_steps = generate_frequency_ramp_steps(56.25, 525.0, 6.25);
-
-    for (i, pll_config) in frequency_steps.iter().enumerate() {
-        send_reg(chip_commands, true, Register::PllDivider(*pll_config))
-            .await
-            .context("PLL ramp failed")?;
-
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-
-        if i % 10 == 0 || i == frequency_steps.len() - 1 {
-            trace!("Frequency ramp step {}/{}", i + 1, frequency_steps.len());
-        }
-    }
-
-    debug!("Frequency ramping complete");
+    let mut mhz = RAMP_START_MHZ;
+    ramp_frequency(chip_commands, &mut mhz, operating_point.frequency_mhz).await?;
 
     // Final configuration
     send_reg(
@@ -472,21 +609,58 @@ where
     Ok(())
 }
 
+/// Reset a chip that is misbehaving and configure it again.
+///
+/// Toggles the reset line, when the board has one, and repeats
+/// initialization at the current operating point. The core voltage stays
+/// up throughout.
+async fn reset_chip<W>(
+    chip_commands: &mut W,
+    peripherals: &mut BoardPeripherals,
+    asic_difficulty: Log2Difficulty,
+    operating_point: OperatingPoint,
+) -> Result<()>
+where
+    W: Sink<protocol::Command> + Unpin,
+    W::Error: std::fmt::Debug,
+{
+    if let Some(ref mut asic_enable) = peripherals.asic_enable {
+        asic_enable
+            .disable()
+            .await
+            .context("failed to hold ASIC in reset")?;
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    initialize_chip(chip_commands, peripherals, asic_difficulty, operating_point).await
+}
+
 /// Generate frequency ramp steps for smooth PLL transitions
-fn generate_frequency_ramp_steps(
+///
+/// Steps run from `start_mhz` to `target_mhz` inclusive, downward if the
+/// target is lower, with the last step shortened to land on the target.
+/// Each PLL setting comes with the frequency it sets.
+fn frequency_ramp(
     start_mhz: f32,
     target_mhz: f32,
     step_mhz: f32,
-) -> Vec<protocol::PllConfig> {
+) -> Vec<(f32, protocol::PllConfig)> {
+    let step = if target_mhz < start_mhz {
+        -step_mhz.abs()
+    } else {
+        step_mhz.abs()
+    };
     let mut configs = Vec::new();
     let mut current = start_mhz;
 
-    while current <= target_mhz {
+    loop {
         if let Some(config) = calculate_pll_for_frequency(current) {
-            configs.push(config);
+            configs.push((current, config));
         }
-        current += step_mhz;
-        if current > target_mhz && (current - step_mhz) < target_mhz {
+        if current == target_mhz {
+            break;
+        }
+        current += step;
+        if (step > 0.0 && current > target_mhz) || (step < 0.0 && current < target_mhz) {
             current = target_mhz;
         }
     }
@@ -602,6 +776,8 @@ fn calculate_pll_for_frequency(target_freq: f32) -> Option<protocol::PllConfig>
 ///
 /// Chip is disabled on startup to establish known state. Chip is enabled and
 /// configured when scheduler assigns first work.
+// Everything the actor owns arrives here once, from `BM13xxThread::new`.
+#[expect(clippy::too_many_arguments)]
 async fn bm13xx_thread_actor<R, W>(
     mut cmd_rx: mpsc::Receiver<ThreadCommand>,
     evt_tx: mpsc::Sender<HashThreadEvent>,
@@ -610,6 +786,7 @@ async fn bm13xx_thread_actor<R, W>(
     mut chip_responses: R,
     mut chip_commands: W,
     mut peripherals: BoardPeripherals,
+    mut profile: ProfileSelection,
 ) where
     R: Stream<Item = Result<protocol::Response, std::io::Error>> + Unpin,
     W: Sink<protocol::Command> + Unpin,
@@ -627,9 +804,12 @@ async fn bm13xx_thread_actor<R, W>(
         ShareRate::per_second(1.0).to_difficulty(HashRate::from_terahashes(1.0)),
     );
 
+    let mut operating_point = profile.current();
     let mut chip_initialized = false;
     let mut current_task: Option<HashTask> = None;
     let mut chip_jobs = ChipJobTracker::new();
+    let mut nonce_entropy = NonceEntropyMonitor::new();
+    let mut hw_errors = HwErrorMonitor::new(ResetPolicy::from_env());
     let mut ntime_ticker = tokio::time::interval(tokio::time::Duration::from_secs(1));
     ntime_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
@@ -655,6 +835,42 @@ async fn bm13xx_thread_actor<R, W>(
                 }
             }
 
+            // Operating profile changes
+            Some(point) = profile.changed() => {
+                // Checked before anything is written; a refused point is
+                // tried again with the next change.
+                if let Err(e) = profile.validate(point) {
+                    warn!(
+                        error = %e,
+                        frequency_mhz = point.frequency_mhz,
+                        core_voltage_v = point.core_voltage_v,
+                        "Refusing unsafe operating point"
+                    );
+                    continue;
+                }
+                if chip_initialized {
+                    match apply_operating_point(&mut chip_commands, &mut peripherals, &mut operating_point, point).await {
+                        Ok(()) => {
+                            info!(
+                                frequency_mhz = point.frequency_mhz,
+                                core_voltage_v = point.core_voltage_v,
+                                "Operating profile applied"
+                            );
+                            profile.applied(point);
+                        }
+                        Err(e) => error!(
+                            error = %e,
+                            frequency_mhz = operating_point.frequency_mhz,
+                            core_voltage_v = operating_point.core_voltage_v,
+                            "Failed to apply operating profile"
+                        ),
+                    }
+                } else {
+                    // Uninitialized chips pick the new point up at power-on.
+                    operating_point = point;
+                }
+            }
+
             // Commands from scheduler
             Some(cmd) = cmd_rx.recv() => {
                 match cmd {
@@ -680,12 +896,13 @@ async fn bm13xx_thread_actor<R, W>(
 
                         if !chip_initialized {
                             trace!("Initializing chip on first assignment.");
-                            if let Err(e) = initialize_chip(&mut chip_commands, &mut peripherals, asic_difficulty).await {
+                            if let Err(e) = initialize_chip(&mut chip_commands, &mut peripherals, asic_difficulty, operating_point).await {
                                 error!(error = %e, "Chip initialization failed");
                                 response_tx.send(Err(e)).ok();
                                 continue;
                             }
                             chip_initialized = true;
+                            profile.applied(operating_point);
                         }
 
                         // Send initial job to chip
@@ -730,16 +947,17 @@ async fn bm13xx_thread_actor<R, W>(
 
                         if !chip_initialized {
                             trace!("Initializing chip on first assignment.");
-                            if let Err(e) = initialize_chip(&mut chip_commands, &mut peripherals, asic_difficulty).await {
+                            if let Err(e) = initialize_chip(&mut chip_commands, &mut peripherals, asic_difficulty, operating_point).await {
                                 error!(error = %e, "Chip initialization failed");
                                 response_tx.send(Err(e)).ok();
                                 continue;
                             }
                             chip_initialized = true;
+                            profile.applied(operating_point);
                         }
 
-                        // Clear old jobs (old shares invalid)
-                        chip_jobs.clear();
+                        // Flush old jobs (old shares invalid)
+                        chip_jobs.flush();
 
                         // Send initial job to chip
                         let chip_job_id = chip_jobs.insert(new_task.clone());
@@ -797,28 +1015,31 @@ async fn bm13xx_thread_actor<R, W>(
                     Ok(response) => {
                         match response {
                             protocol::Response::Nonce { nonce, job_id, version, midstate_num, subcore_id } => {
+                                match nonce_entropy.observe(nonce) {
+                                    Some(Transition::Stuck(stuck)) => {
+                                        error!(%stuck, "Chip nonce stream looks stuck");
+                                        status.write().unwrap().fault = Some(stuck.to_string());
+                                    }
+                                    Some(Transition::Recovered) => {
+                                        info!("Chip nonce stream varying again");
+                                        status.write().unwrap().fault = None;
+                                    }
+                                    None => {}
+                                }
+
                                 // Look up the task for this job_id
+                                let mut verdict = None;
                                 if let Some(task) = chip_jobs.get(job_id) {
-                                    let template = task.template.as_ref();
-
-                                    // Reconstruct full version from rolling field
-                                    let full_version = version.apply_to_version(template.version.base());
-
-                                    // Compute merkle root for this task's EN2
-                                    match task.en2.as_ref().and_then(|en2| template.compute_merkle_root(en2).ok()) {
-                                        Some(merkle_root) => {
-                                            // Build block header
-                                            let header = BlockHeader {
-                                                version: full_version,
-                                                prev_blockhash: template.prev_blockhash,
-                                                merkle_root,
-                                                time: task.ntime,
-                                                bits: template.bits,
-                                                nonce,
-                                            };
-
-                                            // Compute hash
-                                            let hash = header.block_hash();
+                                    // Rebuild the header with the full version
+                                    // and this task's EN2, and hash it
+                                    match nonce_hash(task, version, nonce) {
+                                        Some((full_version, hash)) => {
+                                            // The chip's own filter passed it, so a
+                                            // hash that fails it means the chip
+                                            // computed a different one.
+                                            let hw_error = !task.share_target.is_met_by(hash)
+                                                && !asic_difficulty.to_target().is_met_by(hash);
+                                            verdict = hw_errors.observe(hw_error, Instant::now());
 
                                             // Validate against task share target
                                             if task.share_target.is_met_by(hash) {
@@ -854,6 +1075,15 @@ async fn bm13xx_thread_actor<R, W>(
                                                         "Share found and sent"
                                                     );
                                                 }
+                                            } else if hw_error {
+                                                status.write().unwrap().hardware_errors += 1;
+                                                debug!(
+                                                    chip_job_id = job_id,
+                                                    nonce = format!("{:#x}", nonce),
+                                                    hash = %hash,
+                                                    asic_diff = %asic_difficulty,
+                                                    "Nonce fails the chip's own difficulty (hardware error)"
+                                                );
                                             } else {
                                                 trace!(
                                                     chip_job_id = job_id,
@@ -872,6 +1102,20 @@ async fn bm13xx_thread_actor<R, W>(
                                             );
                                         }
                                     }
+                                } else if let Some(task) = chip_jobs.flushed(job_id) {
+                                    // In flight when its job was replaced: the
+                                    // pool would reject it, so it isn't sent.
+                                    if nonce_hash(task, version, nonce)
+                                        .is_some_and(|(_, hash)| task.share_target.is_met_by(hash))
+                                    {
+                                        status.write().unwrap().flushed_shares += 1;
+                                        debug!(
+                                            chip_job_id = job_id,
+                                            job_id = %task.template.id,
+                                            nonce = format!("{:#x}", nonce),
+                                            "Share for a flushed job (dropped)"
+                                        );
+                                    }
                                 } else {
                                     trace!(
                                         chip_job_id = job_id,
@@ -880,6 +1124,38 @@ async fn bm13xx_thread_actor<R, W>(
                                     );
                                 }
 
+                                match verdict {
+                                    Some(Verdict::Reset(spike)) => {
+                                        warn!(%spike, "Resetting chip after hardware error spike");
+                                        match reset_chip(&mut chip_commands, &mut peripherals, asic_difficulty, operating_point).await {
+                                            Ok(()) => {
+                                                status.write().unwrap().chip_resets += 1;
+                                                info!("Chip reset, resuming work");
+                                            }
+                                            Err(e) => error!(error = %e, "Chip reset failed"),
+                                        }
+                                        hw_errors.reset_done();
+
+                                        // The chip forgot its jobs; give it the
+                                        // current one again.
+                                        chip_jobs.clear();
+                                        if let Some(ref task) = current_task {
+                                            match task_to_job_full(task, chip_jobs.insert(task.clone())) {
+                                                Ok(job_data) => {
+                                                    if let Err(e) = chip_commands.send(protocol::Command::JobFull { job_data }).await {
+                                                        error!(error = ?e, "Failed to send JobFull to chip after reset");
+                                                    }
+                                                }
+                                                Err(e) => error!(error = %e, "Failed to convert task to JobFull"),
+                                            }
+                                        }
+                                    }
+                                    Some(Verdict::RateLimited(spike)) => {
+                                        warn!(%spike, "Hardware error spike, chip reset too recently to reset again");
+                                    }
+                                    None => {}
+                                }
+
                                 let _ = (midstate_num, subcore_id); // Unused for now
                             }
 
@@ -896,12 +1172,13 @@ async fn bm13xx_thread_actor<R, W>(
                 }
             }
 
-            // ntime rolling timer (roll forward every second)
+            // ntime rolling timer (roll forward every second, within the
+            // window the source allows)
             _ = ntime_ticker.tick(), if current_task.is_some() => {
                 let task = current_task.as_mut().unwrap();
-
-                // Increment ntime
-                task.ntime += 1;
+                if !task.roll_ntime() {
+                    continue;
+                }
 
                 // Convert to chip format and send
                 match task_to_job_full(task, chip_jobs.insert(task.clone())) {
@@ -1040,6 +1317,7 @@ mod tests {
             bits: *esp_miner_job::wire_tx::NBITS,
             share_target: crate::types::Difficulty::from(100_u64).to_target(),
             time: *esp_miner_job::wire_tx::NTIME,
+            max_time: *esp_miner_job::wire_tx::NTIME,
             merkle_root: MerkleRootKind::Fixed(*esp_miner_job::wire_tx::MERKLE_ROOT),
         });
 
@@ -1074,4 +1352,511 @@ mod tests {
         );
         assert_eq!(result.merkle_root, *esp_miner_job::wire_tx::MERKLE_ROOT);
     }
+
+    #[test]
+    fn test_frequency_ramp_runs_downward() {
+        let steps = generate_frequency_ramp_steps(575.0, 400.0, 6.25);
+
+        // 575 to 400 in 6.25 MHz steps, landing on 400
+        assert_eq!(steps.len(), 29);
+        assert_eq!(steps.first(), calculate_pll_for_frequency(575.0).as_ref());
+        assert_eq!(steps.last(), calculate_pll_for_frequency(400.0).as_ref());
+    }
+
+    /// What a profile switch did, in order.
+    #[derive(Debug, PartialEq)]
+    enum SwitchStep {
+        Pll(protocol::PllConfig),
+        Voltage(f32),
+    }
+
+    struct RecordingRegulator(Arc<std::sync::Mutex<Vec<SwitchStep>>>);
+
+    #[async_trait]
+    impl crate::asic::hash_thread::VoltageRegulator for RecordingRegulator {
+        async fn set_voltage(&mut self, volts: f32) -> Result<()> {
+            self.0.lock().unwrap().push(SwitchStep::Voltage(volts));
+            Ok(())
+        }
+    }
+
+    /// Switch between two profiles and record the PLL writes and voltage
+    /// changes made on the way.
+    async fn record_switch(
+        from: crate::board::profile::Profile,
+        to: crate::board::profile::Profile,
+    ) -> Vec<SwitchStep> {
+        let gamma = crate::board::profile::for_model("Bitaxe Gamma").unwrap();
+        let steps = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut peripherals = BoardPeripherals {
+            asic_enable: None,
+            voltage_regulator: Some(Box::new(RecordingRegulator(steps.clone()))),
+        };
+        let mut chip_commands = Box::pin(futures::sink::unfold(
+            steps.clone(),
+            |steps, command: protocol::Command| async move {
+                if let protocol::Command::WriteRegister {
+                    register: protocol::Register::PllDivider(config),
+                    ..
+                } = command
+                {
+                    steps.lock().unwrap().push(SwitchStep::Pll(config));
+                }
+                Ok::<_, std::convert::Infallible>(steps)
+            },
+        ));
+
+        apply_operating_point(
+            &mut chip_commands,
+            &mut peripherals,
+            &mut gamma.operating_point(from),
+            gamma.operating_point(to),
+        )
+        .await
+        .unwrap();
+        std::mem::take(&mut *steps.lock().unwrap())
+    }
+
+    /// The PLL settings of a ramp's steps.
+    fn generate_frequency_ramp_steps(
+        start_mhz: f32,
+        target_mhz: f32,
+        step_mhz: f32,
+    ) -> Vec<protocol::PllConfig> {
+        frequency_ramp(start_mhz, target_mhz, step_mhz)
+            .into_iter()
+            .map(|(_, config)| config)
+            .collect()
+    }
+
+    fn pll(mhz: f32) -> SwitchStep {
+        SwitchStep::Pll(calculate_pll_for_frequency(mhz).unwrap())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn switching_profiles_ramps_the_clock() {
+        use crate::board::profile::Profile;
+
+        // Up: voltage first, then the clock ramps 525 -> 575 MHz.
+        let steps = record_switch(Profile::Balanced, Profile::Turbo).await;
+        assert_eq!(steps[0], SwitchStep::Voltage(1.16));
+        assert_eq!(steps[1], pll(525.0));
+        assert_eq!(steps.last(), Some(&pll(575.0)));
+        assert_eq!(steps.len(), 1 + 9);
+
+        // Down: the clock ramps 575 -> 400 MHz, then voltage drops.
+        let steps = record_switch(Profile::Turbo, Profile::Eco).await;
+        assert_eq!(steps[0], pll(575.0));
+        assert_eq!(steps[steps.len() - 2], pll(400.0));
+        assert_eq!(steps.last(), Some(&SwitchStep::Voltage(1.05)));
+        assert_eq!(steps.len(), 29 + 1);
+        assert!(
+            steps[..steps.len() - 1]
+                .iter()
+                .all(|s| matches!(s, SwitchStep::Pll(_)))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_switch_leaves_the_point_it_reached() {
+        use crate::board::profile::{self, Profile};
+
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let turbo = gamma.operating_point(Profile::Turbo);
+        let mut peripherals = BoardPeripherals {
+            asic_enable: None,
+            voltage_regulator: None,
+        };
+        // The chips take three PLL writes, then the link drops.
+        let mut chip_commands = Box::pin(futures::sink::unfold(
+            0,
+            |written, _command: protocol::Command| async move {
+                if written == 3 {
+                    Err("link lost")
+                } else {
+                    Ok(written + 1)
+                }
+            },
+        ));
+
+        let mut current = gamma.operating_point(Profile::Balanced);
+        let result =
+            apply_operating_point(&mut chip_commands, &mut peripherals, &mut current, turbo).await;
+        assert!(result.is_err());
+        // Voltage first, then 525, 531.25 and 537.5 MHz of the ramp to 575.
+        assert_eq!(
+            current,
+            OperatingPoint {
+                frequency_mhz: 537.5,
+                core_voltage_v: turbo.core_voltage_v,
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ntime_rolls_within_the_job_window_and_shares_carry_it() {
+        use crate::board::profile::{self, Profile, ProfileSelection};
+        use crate::job_source::{
+            Extranonce2Range, GeneralPurposeBits, JobTemplate, MerkleRootKind, MerkleRootTemplate,
+            VersionTemplate, test_blocks::block_881423,
+        };
+        use bitcoin::pow::{CompactTarget, Target};
+        use bitcoin::{BlockHash, block::Version, hashes::Hash};
+        use futures::channel::mpsc as chip;
+
+        const NTIME: u32 = 1_700_000_000;
+        // Every nonce makes a share.
+        let anything = Target::from_le_bytes([0xff; 32]);
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (_profile_tx, selection) = ProfileSelection::channel(gamma, Profile::default());
+        let (_removal_tx, removal_rx) = watch::channel(ThreadRemovalSignal::Running);
+        let (commands_tx, mut commands_rx) = chip::unbounded();
+        let (responses_tx, responses_rx) = chip::unbounded();
+        let mut thread = BM13xxThread::new(
+            "t0".into(),
+            responses_rx,
+            commands_tx,
+            BoardPeripherals {
+                asic_enable: None,
+                voltage_regulator: None,
+            },
+            removal_rx,
+            selection,
+        );
+
+        // The source allows three seconds of rolling.
+        let template = Arc::new(JobTemplate {
+            id: "job".into(),
+            prev_blockhash: BlockHash::all_zeros(),
+            version: VersionTemplate::new(
+                Version::from_consensus(0x20000000),
+                GeneralPurposeBits::none(),
+            )
+            .unwrap(),
+            bits: CompactTarget::from_consensus(0x1d00ffff),
+            share_target: anything,
+            time: NTIME,
+            max_time: NTIME + 3,
+            merkle_root: MerkleRootKind::Computed(MerkleRootTemplate {
+                coinbase1: block_881423::coinbase1_bytes().to_vec(),
+                extranonce1: block_881423::extranonce1_bytes().to_vec(),
+                extranonce2_range: Extranonce2Range::new(4).unwrap(),
+                coinbase2: block_881423::coinbase2_bytes().to_vec(),
+                merkle_branches: Vec::new(),
+                cache: Default::default(),
+            }),
+        });
+        let (share_tx, mut share_rx) = mpsc::channel(4);
+        let en2_range = Extranonce2Range::new(4).unwrap();
+        thread
+            .replace_task(HashTask {
+                template,
+                en2: en2_range.iter().next(),
+                en2_range: Some(en2_range),
+                share_target: anything,
+                ntime: NTIME,
+                share_tx,
+            })
+            .await
+            .unwrap();
+
+        // Well past the window: one job per second until it is used up,
+        // then no more.
+        tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+        let mut jobs = Vec::new();
+        while let Ok(command) = commands_rx.try_recv() {
+            if let protocol::Command::JobFull { job_data } = command {
+                jobs.push((job_data.job_id, job_data.ntime));
+            }
+        }
+        let ntimes: Vec<u32> = jobs.iter().map(|(_, ntime)| *ntime).collect();
+        assert_eq!(ntimes, [NTIME, NTIME + 1, NTIME + 2, NTIME + 3]);
+
+        // A nonce against the last rolled job is reported at its ntime.
+        let (job_id, _) = *jobs.last().unwrap();
+        responses_tx
+            .unbounded_send(Ok(protocol::Response::Nonce {
+                nonce: 0x1234,
+                job_id,
+                midstate_num: 0,
+                version: GeneralPurposeBits::none(),
+                subcore_id: 0,
+            }))
+            .unwrap();
+        let share = share_rx.recv().await.unwrap();
+        assert_eq!(share.ntime, NTIME + 3);
+    }
+
+    #[tokio::test]
+    async fn late_shares_for_a_flushed_job_are_dropped_and_counted() {
+        use crate::board::profile::{self, Profile, ProfileSelection};
+        use crate::job_source::{
+            Extranonce2Range, JobTemplate, MerkleRootKind, MerkleRootTemplate, VersionTemplate,
+            test_blocks::block_881423,
+        };
+        use bitcoin::pow::{CompactTarget, Target};
+        use bitcoin::{BlockHash, block::Version, hashes::Hash};
+        use futures::channel::mpsc as chip;
+
+        // Every nonce makes a share.
+        let anything = Target::from_le_bytes([0xff; 32]);
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (_profile_tx, selection) = ProfileSelection::channel(gamma, Profile::default());
+        let (_removal_tx, removal_rx) = watch::channel(ThreadRemovalSignal::Running);
+        let (commands_tx, mut commands_rx) = chip::unbounded();
+        let (responses_tx, responses_rx) = chip::unbounded();
+        let mut thread = BM13xxThread::new(
+            "t0".into(),
+            responses_rx,
+            commands_tx,
+            BoardPeripherals {
+                asic_enable: None,
+                voltage_regulator: None,
+            },
+            removal_rx,
+            selection,
+        );
+
+        let task = |id: &str| {
+            let template = Arc::new(JobTemplate {
+                id: id.into(),
+                prev_blockhash: BlockHash::all_zeros(),
+                version: VersionTemplate::new(
+                    Version::from_consensus(0x20000000),
+                    GeneralPurposeBits::none(),
+                )
+                .unwrap(),
+                bits: CompactTarget::from_consensus(0x1d00ffff),
+                share_target: anything,
+                time: 1_700_000_000,
+                max_time: 1_700_000_000,
+                merkle_root: MerkleRootKind::Computed(MerkleRootTemplate {
+                    coinbase1: block_881423::coinbase1_bytes().to_vec(),
+                    extranonce1: block_881423::extranonce1_bytes().to_vec(),
+                    extranonce2_range: Extranonce2Range::new(4).unwrap(),
+                    coinbase2: block_881423::coinbase2_bytes().to_vec(),
+                    merkle_branches: Vec::new(),
+                    cache: Default::default(),
+                }),
+            });
+            let (share_tx, share_rx) = mpsc::channel(4);
+            let en2_range = Extranonce2Range::new(4).unwrap();
+            let task = HashTask {
+                template,
+                en2: en2_range.iter().next(),
+                en2_range: Some(en2_range),
+                share_target: anything,
+                ntime: 1_700_000_000,
+                share_tx,
+            };
+            (task, share_rx)
+        };
+        let mut sent_job = || loop {
+            if let Ok(protocol::Command::JobFull { job_data }) = commands_rx.try_recv() {
+                return job_data.job_id;
+            }
+        };
+        let nonce = |job_id| {
+            responses_tx
+                .unbounded_send(Ok(protocol::Response::Nonce {
+                    nonce: 0x1234,
+                    job_id,
+                    midstate_num: 0,
+                    version: GeneralPurposeBits::none(),
+                    subcore_id: 0,
+                }))
+                .unwrap();
+        };
+
+        let (old_task, mut old_shares) = task("old");
+        thread.replace_task(old_task).await.unwrap();
+        let old_job = sent_job();
+        let (new_task, mut new_shares) = task("new");
+        thread.replace_task(new_task).await.unwrap();
+        let new_job = sent_job();
+
+        // The chip answers for the flushed job, then the current one.
+        nonce(old_job);
+        nonce(new_job);
+        assert!(new_shares.recv().await.is_some());
+        assert!(old_shares.try_recv().is_err());
+        assert_eq!(thread.status().flushed_shares, 1);
+        assert_eq!(thread.status().hardware_errors, 0);
+    }
+
+    struct RecordingEnable(Arc<std::sync::Mutex<Vec<bool>>>);
+
+    #[async_trait]
+    impl crate::asic::hash_thread::AsicEnable for RecordingEnable {
+        async fn enable(&mut self) -> Result<()> {
+            self.0.lock().unwrap().push(true);
+            Ok(())
+        }
+
+        async fn disable(&mut self) -> Result<()> {
+            self.0.lock().unwrap().push(false);
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    #[serial_test::serial]
+    async fn a_hardware_error_spike_resets_the_chip() {
+        use crate::asic::hw_error_reset::WINDOW;
+        use crate::board::profile::{self, Profile, ProfileSelection};
+        use crate::job_source::{
+            Extranonce2Range, GeneralPurposeBits, JobTemplate, MerkleRootKind, MerkleRootTemplate,
+            VersionTemplate, test_blocks::block_881423,
+        };
+        use bitcoin::pow::CompactTarget;
+        use bitcoin::{BlockHash, block::Version, hashes::Hash};
+        use futures::channel::mpsc as chip;
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { std::env::set_var("MUJINA_ASIC_RESET_INTERVAL_SECS", "0") };
+
+        // No nonce makes a share, and nearly none passes the chip's filter
+        // on recomputation: each is a hardware error.
+        let unreachable = Difficulty::from(u64::MAX).to_target();
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (_profile_tx, selection) = ProfileSelection::channel(gamma, Profile::default());
+        let (_removal_tx, removal_rx) = watch::channel(ThreadRemovalSignal::Running);
+        let (commands_tx, mut commands_rx) = chip::unbounded();
+        let (responses_tx, responses_rx) = chip::unbounded();
+        let enables = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut thread = BM13xxThread::new(
+            "t0".into(),
+            responses_rx,
+            commands_tx,
+            BoardPeripherals {
+                asic_enable: Some(Box::new(RecordingEnable(enables.clone()))),
+                voltage_regulator: None,
+            },
+            removal_rx,
+            selection,
+        );
+
+        let template = Arc::new(JobTemplate {
+            id: "job".into(),
+            prev_blockhash: BlockHash::all_zeros(),
+            version: VersionTemplate::new(
+                Version::from_consensus(0x20000000),
+                GeneralPurposeBits::none(),
+            )
+            .unwrap(),
+            bits: CompactTarget::from_consensus(0x1d00ffff),
+            share_target: unreachable,
+            time: 1_700_000_000,
+            max_time: 1_700_000_000,
+            merkle_root: MerkleRootKind::Computed(MerkleRootTemplate {
+                coinbase1: block_881423::coinbase1_bytes().to_vec(),
+                extranonce1: block_881423::extranonce1_bytes().to_vec(),
+                extranonce2_range: Extranonce2Range::new(4).unwrap(),
+                coinbase2: block_881423::coinbase2_bytes().to_vec(),
+                merkle_branches: Vec::new(),
+                cache: Default::default(),
+            }),
+        });
+        let (share_tx, _share_rx) = mpsc::channel(4);
+        let en2_range = Extranonce2Range::new(4).unwrap();
+        thread
+            .replace_task(HashTask {
+                template,
+                en2: en2_range.iter().next(),
+                en2_range: Some(en2_range),
+                share_target: unreachable,
+                ntime: 1_700_000_000,
+                share_tx,
+            })
+            .await
+            .unwrap();
+        // Held in reset at startup, released by initialization.
+        assert_eq!(*enables.lock().unwrap(), [false, true]);
+
+        // The job the chip is working on.
+        let mut current_job = || {
+            let mut job_id = None;
+            while let Ok(command) = commands_rx.try_recv() {
+                if let protocol::Command::JobFull { job_data } = command {
+                    job_id = Some(job_data.job_id);
+                }
+            }
+            job_id
+        };
+        let send_nonces = |job_id: u8, nonces: std::ops::Range<u32>| {
+            for nonce in nonces {
+                responses_tx
+                    .unbounded_send(Ok(protocol::Response::Nonce {
+                        nonce,
+                        job_id,
+                        midstate_num: 0,
+                        version: GeneralPurposeBits::none(),
+                        subcore_id: 0,
+                    }))
+                    .unwrap();
+            }
+        };
+        // Initialization ramps the clock up again, which takes seconds.
+        let settle = || tokio::time::sleep(std::time::Duration::from_secs(30));
+
+        // A window and a half of errors: one reset, through the reset
+        // line, and the job is handed back to the chip.
+        let job_id = current_job().unwrap();
+        send_nonces(job_id, 0..WINDOW * 3 / 2);
+        settle().await;
+        assert_eq!(*enables.lock().unwrap(), [false, true, false, true]);
+        assert_eq!(thread.status().chip_resets, 1);
+        let job_id = current_job().expect("job not resent after reset");
+
+        // The rest of those nonces were for a job the chip forgot, and
+        // counting started afresh at the reset: it takes a full window on
+        // the resent job to reset again.
+        send_nonces(job_id, 0..WINDOW / 2);
+        settle().await;
+        assert_eq!(thread.status().chip_resets, 1);
+        send_nonces(job_id, WINDOW / 2..WINDOW);
+        settle().await;
+        assert_eq!(thread.status().chip_resets, 2);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { std::env::remove_var("MUJINA_ASIC_RESET_INTERVAL_SECS") };
+    }
+
+    #[tokio::test]
+    async fn actor_logs_carry_the_board_span() {
+        use crate::board::profile::{self, Profile, ProfileSelection};
+
+        let logs = crate::tracing::capture_logs(async {
+            let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+            let (_profile_tx, selection) = ProfileSelection::channel(gamma, Profile::default());
+            let (_removal_tx, removal_rx) = watch::channel(ThreadRemovalSignal::Running);
+            let peripherals = BoardPeripherals {
+                asic_enable: None,
+                voltage_regulator: None,
+            };
+
+            async {
+                let mut thread = BM13xxThread::new(
+                    "t0".into(),
+                    futures::stream::pending::<Result<protocol::Response, std::io::Error>>(),
+                    futures::sink::drain(),
+                    peripherals,
+                    removal_rx,
+                    selection,
+                );
+                thread.go_idle().await.unwrap();
+            }
+            .instrument(crate::board::span("bitaxe-test", "Bitaxe Gamma"))
+            .await;
+        })
+        .await;
+
+        // Logged by the actor task, which the thread spawned.
+        let line = logs
+            .lines()
+            .find(|l| l.contains("Going idle"))
+            .unwrap_or_else(|| panic!("no idle event in:\n{logs}"));
+        assert!(line.contains(r#"board_id="bitaxe-test""#), "{line}");
+        assert!(line.contains(r#"model="Bitaxe Gamma""#), "{line}");
+    }
 }
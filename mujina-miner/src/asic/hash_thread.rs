@@ -37,7 +37,7 @@ use bitcoin::pow::Target;
 use tokio::sync::mpsc;
 
 use crate::job_source::{Extranonce2, Extranonce2Range, JobTemplate};
-use crate::types::HashRate;
+use crate::types::{Difficulty, HashRate};
 use bitcoin::pow::Work;
 
 /// HashThread capabilities reported to scheduler for work assignment decisions.
@@ -69,11 +69,22 @@ pub struct HashThreadStatus {
     /// Number of hardware errors detected
     pub hardware_errors: u64,
 
+    /// Shares found on jobs already replaced by the time the chip
+    /// reported them, dropped rather than submitted
+    pub flushed_shares: u64,
+
+    /// Times the chips were reset after a hardware error spike
+    pub chip_resets: u64,
+
     /// Current chip temperature if available
     pub temperature_c: Option<f32>,
 
     /// Whether thread is actively working
     pub is_active: bool,
+
+    /// Why the thread's results can't be trusted, such as a stuck nonce
+    /// stream. `None` while it looks healthy.
+    pub fault: Option<String>,
 }
 
 /// Events emitted by HashThreads back to the scheduler.
@@ -274,7 +285,8 @@ pub struct HashTask {
 
     /// Current ntime value
     ///
-    /// May be rolled forward during mining. To start, uses the job's time field.
+    /// May be rolled forward during mining, up to the job's `max_time`. To
+    /// start, uses the job's time field.
     pub ntime: u32,
 
     /// Channel for submitting shares back to scheduler.
@@ -284,6 +296,18 @@ pub struct HashTask {
     pub share_tx: mpsc::Sender<Share>,
 }
 
+impl HashTask {
+    /// Roll ntime forward a second, unless that would pass the job's
+    /// `max_time`. Returns whether it rolled.
+    pub fn roll_ntime(&mut self) -> bool {
+        if self.ntime >= self.template.max_time {
+            return false;
+        }
+        self.ntime += 1;
+        true
+    }
+}
+
 impl fmt::Debug for HashTask {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("HashTask")
@@ -330,14 +354,16 @@ pub struct Share {
     pub expected_work: Work,
 }
 
-impl From<(Share, String)> for crate::job_source::Share {
-    fn from((share, job_id): (Share, String)) -> Self {
+impl From<(Share, &JobTemplate)> for crate::job_source::Share {
+    fn from((share, template): (Share, &JobTemplate)) -> Self {
         Self {
-            job_id,
+            job_id: template.id.clone(),
             nonce: share.nonce,
             time: share.ntime,
             version: share.version,
             extranonce2: share.extranonce2,
+            solves_block: template.target().is_met_by(share.hash),
+            difficulty: Difficulty::from_hash(&share.hash),
         }
     }
 }
@@ -0,0 +1,255 @@
+//! Resetting a chip whose hardware error rate spikes.
+//!
+//! A transient upset, such as a voltage dip or a bit flipped in a chip
+//! register, can leave a chip computing wrong hashes while it keeps
+//! returning nonces. Toggling its reset line and configuring it again
+//! usually recovers it, without taking the board's power down. The
+//! monitor judges a chip's nonces in windows of [`WINDOW`], and calls for
+//! a reset when the share of hardware errors in a window exceeds
+//! `MUJINA_ASIC_RESET_HW_PERCENT`. Resets are at least
+//! `MUJINA_ASIC_RESET_INTERVAL_SECS` apart, so a chip a reset doesn't
+//! fix isn't reset over and over.
+
+use std::env;
+use std::fmt;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::tracing::prelude::*;
+
+/// Nonces judged together.
+pub const WINDOW: u32 = 64;
+
+/// Hardware error share above which a chip is reset, when not configured.
+pub const DEFAULT_THRESHOLD: f64 = 0.25;
+
+/// Shortest time between two resets, when not configured.
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(600);
+
+/// When a chip is reset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResetPolicy {
+    /// Share of a window's nonces that may be hardware errors. `None`
+    /// never resets.
+    pub threshold: Option<f64>,
+    /// Shortest time between two resets.
+    pub min_interval: Duration,
+}
+
+impl Default for ResetPolicy {
+    fn default() -> Self {
+        Self {
+            threshold: Some(DEFAULT_THRESHOLD),
+            min_interval: DEFAULT_MIN_INTERVAL,
+        }
+    }
+}
+
+impl ResetPolicy {
+    /// Read `MUJINA_ASIC_RESET_HW_PERCENT` (0 disables resets) and
+    /// `MUJINA_ASIC_RESET_INTERVAL_SECS`, warning and keeping the default
+    /// for each one invalid.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(val) = env::var("MUJINA_ASIC_RESET_HW_PERCENT") {
+            match val.parse::<f64>() {
+                Ok(0.0) => policy.threshold = None,
+                Ok(percent) if percent > 0.0 && percent < 100.0 => {
+                    policy.threshold = Some(percent / 100.0)
+                }
+                _ => warn!(value = %val, "Invalid MUJINA_ASIC_RESET_HW_PERCENT, using default"),
+            }
+        }
+        if let Ok(val) = env::var("MUJINA_ASIC_RESET_INTERVAL_SECS") {
+            match val.parse::<u64>() {
+                Ok(secs) => policy.min_interval = Duration::from_secs(secs),
+                Err(_) => {
+                    warn!(value = %val, "Invalid MUJINA_ASIC_RESET_INTERVAL_SECS, using default")
+                }
+            }
+        }
+        policy
+    }
+}
+
+/// A window with too many hardware errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spike {
+    pub hw_errors: u32,
+    pub nonces: u32,
+}
+
+impl fmt::Display for Spike {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} hardware errors in the last {} nonces",
+            self.hw_errors, self.nonces
+        )
+    }
+}
+
+/// What to do about a spike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Reset the chip now.
+    Reset(Spike),
+    /// The last reset was too recent; keep mining.
+    RateLimited(Spike),
+}
+
+/// Judges a chip's hardware error rate a window at a time.
+#[derive(Debug)]
+pub struct HwErrorMonitor {
+    policy: ResetPolicy,
+    nonces: u32,
+    hw_errors: u32,
+    last_reset: Option<Instant>,
+}
+
+impl HwErrorMonitor {
+    pub fn new(policy: ResetPolicy) -> Self {
+        Self {
+            policy,
+            nonces: 0,
+            hw_errors: 0,
+            last_reset: None,
+        }
+    }
+
+    /// Nonces and hardware errors counted in the window so far.
+    pub fn window(&self) -> (u32, u32) {
+        (self.nonces, self.hw_errors)
+    }
+
+    /// Record a nonce the chip returned for a known job, and whether it
+    /// was a hardware error. Returns a verdict when a completed window
+    /// exceeds the threshold.
+    pub fn observe(&mut self, hw_error: bool, now: Instant) -> Option<Verdict> {
+        self.nonces += 1;
+        self.hw_errors += u32::from(hw_error);
+        if self.nonces < WINDOW {
+            return None;
+        }
+        let spike = Spike {
+            hw_errors: self.hw_errors,
+            nonces: self.nonces,
+        };
+        self.nonces = 0;
+        self.hw_errors = 0;
+
+        let threshold = self.policy.threshold?;
+        if f64::from(spike.hw_errors) / f64::from(spike.nonces) <= threshold {
+            return None;
+        }
+        match self.last_reset {
+            Some(at) if now.duration_since(at) < self.policy.min_interval => {
+                Some(Verdict::RateLimited(spike))
+            }
+            _ => {
+                self.last_reset = Some(now);
+                Some(Verdict::Reset(spike))
+            }
+        }
+    }
+
+    /// Start counting afresh after the chip was reset, so nonces from
+    /// before the reset don't count against it.
+    pub fn reset_done(&mut self) {
+        self.nonces = 0;
+        self.hw_errors = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    /// Feed a window in which `hw_errors` nonces were hardware errors.
+    fn feed_window(monitor: &mut HwErrorMonitor, hw_errors: u32, now: Instant) -> Vec<Verdict> {
+        (0..WINDOW)
+            .filter_map(|i| monitor.observe(i < hw_errors, now))
+            .collect()
+    }
+
+    #[test]
+    fn a_spike_resets_at_most_once_per_interval() {
+        let start = Instant::now();
+        let mut monitor = HwErrorMonitor::new(ResetPolicy::default());
+
+        // A few errors are normal.
+        assert!(feed_window(&mut monitor, 4, start).is_empty());
+
+        let spike = Spike {
+            hw_errors: 32,
+            nonces: WINDOW,
+        };
+        assert_eq!(
+            feed_window(&mut monitor, 32, start),
+            [Verdict::Reset(spike)]
+        );
+        assert_eq!(monitor.window(), (0, 0));
+
+        // Too soon for another.
+        let later = start + Duration::from_secs(60);
+        assert_eq!(
+            feed_window(&mut monitor, 32, later),
+            [Verdict::RateLimited(spike)]
+        );
+
+        let much_later = start + DEFAULT_MIN_INTERVAL;
+        assert_eq!(
+            feed_window(&mut monitor, 32, much_later),
+            [Verdict::Reset(spike)]
+        );
+    }
+
+    #[test]
+    fn errors_before_a_reset_do_not_count_after_it() {
+        let now = Instant::now();
+        let mut monitor = HwErrorMonitor::new(ResetPolicy::default());
+
+        // Half a window of errors, then the chip is reset.
+        for _ in 0..WINDOW / 2 {
+            assert_eq!(monitor.observe(true, now), None);
+        }
+        monitor.reset_done();
+        assert_eq!(monitor.window(), (0, 0));
+
+        // A clean window afterwards is judged clean.
+        assert!(feed_window(&mut monitor, 0, now).is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn policy_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_ASIC_RESET_HW_PERCENT", "10");
+            env::set_var("MUJINA_ASIC_RESET_INTERVAL_SECS", "30");
+        }
+        let policy = ResetPolicy::from_env();
+        assert_eq!(policy.min_interval, Duration::from_secs(30));
+        assert!((policy.threshold.unwrap() - 0.1).abs() < 1e-12);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_ASIC_RESET_HW_PERCENT", "0") };
+        assert_eq!(ResetPolicy::from_env().threshold, None);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_ASIC_RESET_HW_PERCENT", "150");
+            env::set_var("MUJINA_ASIC_RESET_INTERVAL_SECS", "soon");
+        }
+        assert_eq!(ResetPolicy::from_env(), ResetPolicy::default());
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_ASIC_RESET_HW_PERCENT");
+            env::remove_var("MUJINA_ASIC_RESET_INTERVAL_SECS");
+        }
+    }
+}
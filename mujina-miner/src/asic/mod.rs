@@ -1,5 +1,7 @@
 pub mod bm13xx;
 pub mod hash_thread;
+pub mod hw_error_reset;
+pub mod nonce_entropy;
 
 /// Information about a chip
 #[derive(Debug, Clone)]
@@ -0,0 +1,176 @@
+//! Detection of a chip returning nonces that don't vary.
+//!
+//! A healthy chip's nonces spread over the whole 32-bit space: which
+//! nonce wins is down to the hash. A firmware or chip fault can instead
+//! return the same few values over and over, or values clustered in one
+//! small range. No real shares come out, yet unlike a slow board the
+//! nonces keep arriving. The monitor judges nonces in windows of
+//! [`WINDOW`], and counts a window as stuck when fewer than
+//! [`MIN_VARYING_BITS`] bit positions change within it or fewer than half
+//! its nonces are distinct. Arrival rate doesn't enter into it: a slow
+//! board only takes longer to fill a window.
+
+use std::collections::HashSet;
+use std::fmt;
+
+/// Nonces judged together.
+pub const WINDOW: usize = 32;
+
+/// Bit positions that must change somewhere in a window. Among 32 random
+/// nonces a given bit stays put with probability 2^-31, but BM13xx chips
+/// put their chain address in nonce bits 10-15, so on a one-chip board
+/// those six never change.
+pub const MIN_VARYING_BITS: u32 = 20;
+
+/// A window of nonces that don't vary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stuck {
+    pub distinct: usize,
+    pub varying_bits: u32,
+}
+
+impl fmt::Display for Stuck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stuck nonce stream: {} distinct of the last {WINDOW} nonces, {} of 32 bits varying",
+            self.distinct, self.varying_bits
+        )
+    }
+}
+
+/// A change in whether the stream looks stuck.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transition {
+    Stuck(Stuck),
+    Recovered,
+}
+
+/// Judges a chip's nonce stream a window at a time.
+#[derive(Debug, Default)]
+pub struct NonceEntropyMonitor {
+    window: Vec<u32>,
+    stuck: bool,
+}
+
+impl NonceEntropyMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the last full window looked stuck.
+    pub fn is_stuck(&self) -> bool {
+        self.stuck
+    }
+
+    /// Record a nonce the chip returned, whether or not it met any target.
+    /// Returns the transition when a completed window changes the verdict.
+    pub fn observe(&mut self, nonce: u32) -> Option<Transition> {
+        self.window.push(nonce);
+        if self.window.len() < WINDOW {
+            return None;
+        }
+        let verdict = judge(&self.window);
+        self.window.clear();
+        match (verdict, self.stuck) {
+            (Some(stuck), false) => {
+                self.stuck = true;
+                Some(Transition::Stuck(stuck))
+            }
+            (None, true) => {
+                self.stuck = false;
+                Some(Transition::Recovered)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// `Some` when `nonces` vary too little to come from real hashing.
+fn judge(nonces: &[u32]) -> Option<Stuck> {
+    let first = nonces[0];
+    let varying_bits = nonces
+        .iter()
+        .fold(0, |acc, n| acc | (n ^ first))
+        .count_ones();
+    let distinct = nonces.iter().collect::<HashSet<_>>().len();
+    (varying_bits < MIN_VARYING_BITS || distinct < nonces.len() / 2).then_some(Stuck {
+        distinct,
+        varying_bits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A deterministic stand-in for the nonces a lone chip at address 0
+    /// returns: random but for the address bits.
+    fn healthy(count: usize) -> impl Iterator<Item = u32> {
+        let mut x: u32 = 0x9e37_79b9;
+        std::iter::repeat_with(move || {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x & !0x0000_fc00
+        })
+        .take(count)
+    }
+
+    fn feed(
+        monitor: &mut NonceEntropyMonitor,
+        nonces: impl Iterator<Item = u32>,
+    ) -> Vec<Transition> {
+        nonces.filter_map(|n| monitor.observe(n)).collect()
+    }
+
+    #[test]
+    fn only_a_pathological_stream_is_flagged() {
+        let mut monitor = NonceEntropyMonitor::new();
+        assert!(feed(&mut monitor, healthy(10 * WINDOW)).is_empty());
+        assert!(!monitor.is_stuck());
+
+        // One value over and over.
+        let mut repeating = NonceEntropyMonitor::new();
+        let transitions = feed(&mut repeating, std::iter::repeat_n(0x1234_5678, WINDOW));
+        assert_eq!(
+            transitions,
+            [Transition::Stuck(Stuck {
+                distinct: 1,
+                varying_bits: 0
+            })]
+        );
+
+        // All distinct, but always near the same value.
+        let mut clustered = NonceEntropyMonitor::new();
+        let nonces = (0..WINDOW as u32).map(|i| 0x8000_0000 + i * 37);
+        let transitions = feed(&mut clustered, nonces);
+        let [Transition::Stuck(stuck)] = transitions.as_slice() else {
+            panic!("clustered stream not flagged: {transitions:?}");
+        };
+        assert_eq!(stuck.distinct, WINDOW);
+        assert!(stuck.varying_bits < MIN_VARYING_BITS, "{stuck}");
+    }
+
+    #[test]
+    fn flag_clears_once_the_stream_varies_again() {
+        let mut monitor = NonceEntropyMonitor::new();
+        feed(&mut monitor, std::iter::repeat_n(7, WINDOW));
+        assert!(monitor.is_stuck());
+
+        // Reported once, not every window.
+        assert!(feed(&mut monitor, std::iter::repeat_n(7, 3 * WINDOW)).is_empty());
+
+        assert_eq!(feed(&mut monitor, healthy(WINDOW)), [Transition::Recovered]);
+        assert!(!monitor.is_stuck());
+    }
+
+    #[test]
+    fn a_slow_stream_is_judged_on_variety_alone() {
+        // Half a window of healthy nonces, however long they took, says
+        // nothing yet.
+        let mut monitor = NonceEntropyMonitor::new();
+        assert!(feed(&mut monitor, healthy(WINDOW / 2)).is_empty());
+        assert!(!monitor.is_stuck());
+    }
+}
@@ -4,18 +4,60 @@
 //! the scheduler. Like a hardware backplane, it provides connection points for
 //! boards to plug into, routes events between components, and manages board
 //! lifecycle (hotplug, emergency shutdown, etc.).
+//!
+//! Boards are brought up off the event loop, so a slow power-on sequence
+//! doesn't hold up commands, hotplug events or the watchdog. At most
+//! `MUJINA_BOARD_INIT_CONCURRENCY` boards run their power-on sequence at
+//! once, one by default: boards found together at startup then never draw
+//! their inrush current at the same moment, so a supply sized for running
+//! boards isn't tripped by starting them.
+//!
+//! A board that shuts itself down, such as on a thermal emergency, stays
+//! dark until an operator re-enables it through the API, and then only
+//! once its cooldown ([`CooldownPolicy`]) allows unless the request is
+//! forced. Re-enabling brings the board up from scratch on the same
+//! device.
+//!
+//! Every board's monitor publishes telemetry as it polls. A board whose
+//! telemetry stops for [`WatchdogPolicy::timeout`] is taken to be wedged,
+//! and the watchdog bites: the first bites restart the board with its
+//! chips left powered, later ones power-cycle it, and past the ladder it
+//! is marked failed and left off, to be re-enabled through the API like a
+//! board that shut itself down.
+//!
+//! A board that loses the link it is controlled over, such as a USB
+//! serial cable jiggled loose without the device leaving the bus, says so,
+//! and is re-created on its device a bounded number of times
+//! ([`ReconnectPolicy`]) before it too is marked failed.
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use std::collections::{HashMap, HashSet};
-use tokio::sync::mpsc;
+use std::env;
+use std::future::{Future, pending};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::{Semaphore, mpsc, oneshot, watch};
+use tokio::task::{self, AbortHandle, JoinError, JoinSet};
+use tokio::time::{self, Instant};
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::{StreamExt, StreamMap};
 
 use futures::future::BoxFuture;
 
 use crate::{
-    api::BoardRegistration,
-    board::{BackplaneConnector, BoardDescriptor, BoardInfo, VirtualBoardRegistry},
+    api::{
+        BoardRegistration,
+        commands::{BoardCommand, EnableError, FanTargetError},
+    },
+    api_client::types::BoardTelemetry,
+    board::{
+        BackplaneConnector, BoardDescriptor, BoardInfo, BoardShutdown, ShutdownMode,
+        VirtualBoardRegistry,
+        cooldown::{CooldownPolicy, Trip},
+        self_test::SelfTestMode,
+    },
+    peripheral::emc2101::Percent,
     scheduler::ThreadRegistration,
     tracing::prelude::*,
     transport::{
@@ -42,6 +84,339 @@ impl BoardRegistry {
     }
 }
 
+/// How many times to try bringing up a board before giving up on it.
+///
+/// Boards sometimes fail their first initialization for transient reasons,
+/// such as serial ports still enumerating or a PLL slow to lock. Retrying
+/// after a pause gets them up without a replug.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InitRetryPolicy {
+    /// Total attempts, including the first. At least 1.
+    pub attempts: u32,
+    /// Pause between attempts.
+    pub delay: Duration,
+}
+
+impl Default for InitRetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl InitRetryPolicy {
+    /// Read `MUJINA_BOARD_INIT_ATTEMPTS` and `MUJINA_BOARD_INIT_RETRY_MS`,
+    /// keeping the default for each one unset or invalid.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(val) = env::var("MUJINA_BOARD_INIT_ATTEMPTS") {
+            match val.parse::<u32>() {
+                Ok(n) if n >= 1 => policy.attempts = n,
+                _ => warn!(value = %val, "Invalid MUJINA_BOARD_INIT_ATTEMPTS, using default"),
+            }
+        }
+        if let Ok(val) = env::var("MUJINA_BOARD_INIT_RETRY_MS") {
+            match val.parse::<u64>() {
+                Ok(ms) => policy.delay = Duration::from_millis(ms),
+                Err(_) => warn!(value = %val, "Invalid MUJINA_BOARD_INIT_RETRY_MS, using default"),
+            }
+        }
+        policy
+    }
+
+    /// Run `create` until it succeeds or the attempts run out, logging
+    /// each failure. Returns the last error if every attempt fails.
+    async fn run<F, Fut>(&self, board: &str, mut create: F) -> Result<BackplaneConnector>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<BackplaneConnector>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match create().await {
+                Ok(conn) => return Ok(conn),
+                Err(e) if attempt < self.attempts => {
+                    warn!(
+                        board,
+                        attempt,
+                        attempts = self.attempts,
+                        error = %e,
+                        "Board initialization failed, retrying"
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+            time::sleep(self.delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// What to do while no boards are running after startup enumeration.
+///
+/// Hardware can enumerate slowly at boot, and a permissions problem may be
+/// fixed while the daemon runs, so by default the backplane keeps waiting:
+/// hotplugged boards are picked up as they appear, and boards that failed
+/// to initialize are retried periodically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardWaitPolicy {
+    /// Pause between retries of boards that failed to initialize.
+    pub retry_interval: Duration,
+    /// Give up and stop the daemon after this long without a board.
+    /// `None` waits indefinitely.
+    pub give_up_after: Option<Duration>,
+}
+
+impl Default for BoardWaitPolicy {
+    fn default() -> Self {
+        Self {
+            retry_interval: Duration::from_secs(30),
+            give_up_after: None,
+        }
+    }
+}
+
+impl BoardWaitPolicy {
+    /// Read `MUJINA_BOARD_WAIT_SECS`, waiting indefinitely when it is unset
+    /// or invalid.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(val) = env::var("MUJINA_BOARD_WAIT_SECS") {
+            match val.parse::<u64>() {
+                Ok(secs) => policy.give_up_after = Some(Duration::from_secs(secs)),
+                Err(_) => {
+                    warn!(value = %val, "Invalid MUJINA_BOARD_WAIT_SECS, waiting indefinitely")
+                }
+            }
+        }
+        policy
+    }
+}
+
+/// Boards that may run their power-on sequence at once when
+/// `MUJINA_BOARD_INIT_CONCURRENCY` is unset.
+pub const DEFAULT_INIT_CONCURRENCY: usize = 1;
+
+/// Read `MUJINA_BOARD_INIT_CONCURRENCY`, using the default when it is
+/// unset or invalid.
+pub fn init_concurrency_from_env() -> usize {
+    let Ok(val) = env::var("MUJINA_BOARD_INIT_CONCURRENCY") else {
+        return DEFAULT_INIT_CONCURRENCY;
+    };
+    match val.parse::<usize>() {
+        Ok(n) if n >= 1 => n,
+        _ => {
+            warn!(value = %val, "Invalid MUJINA_BOARD_INIT_CONCURRENCY, using default");
+            DEFAULT_INIT_CONCURRENCY
+        }
+    }
+}
+
+/// No board came up within [`BoardWaitPolicy::give_up_after`].
+#[derive(Debug, thiserror::Error)]
+#[error("no hash boards came up within {}s", waited.as_secs())]
+pub struct NoBoardsError {
+    pub waited: Duration,
+}
+
+/// How often boards are checked for silence.
+const WATCHDOG_CHECK: Duration = Duration::from_secs(5);
+
+/// How long a board being replaced, such as a wedged one, may take to
+/// shut down before it is abandoned.
+const BITE_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// How long a power-cycled board is left off, so its supply rails fully
+/// discharge before it is powered up again.
+const POWER_CYCLE_OFF: Duration = Duration::from_secs(5);
+
+/// How the watchdog answers a board that stops reporting.
+///
+/// Bites climb a ladder: the first `restarts` restart the board, the next
+/// `power_cycles` power-cycle it, and any further bite fails it. A board
+/// starts back at the bottom when unplugged or re-enabled through the
+/// API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchdogPolicy {
+    /// Silence after which a board counts as wedged. `None` disables the
+    /// watchdog.
+    pub timeout: Option<Duration>,
+    /// Bites answered by bringing the board up again with its chips left
+    /// powered.
+    pub restarts: u32,
+    /// Further bites answered by powering the board off and on again.
+    pub power_cycles: u32,
+}
+
+/// What the watchdog does about one bite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BiteAction {
+    Restart,
+    PowerCycle,
+    Fail,
+}
+
+impl Default for WatchdogPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(120)),
+            restarts: 1,
+            power_cycles: 1,
+        }
+    }
+}
+
+impl WatchdogPolicy {
+    /// Read `MUJINA_BOARD_WATCHDOG_SECS` (0 disables the watchdog),
+    /// `MUJINA_BOARD_WATCHDOG_RESTARTS` and
+    /// `MUJINA_BOARD_WATCHDOG_POWER_CYCLES`, keeping the default for each
+    /// one unset or invalid.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(val) = env::var("MUJINA_BOARD_WATCHDOG_SECS") {
+            match val.parse::<u64>() {
+                Ok(0) => policy.timeout = None,
+                Ok(secs) => policy.timeout = Some(Duration::from_secs(secs)),
+                Err(_) => warn!(value = %val, "Invalid MUJINA_BOARD_WATCHDOG_SECS, using default"),
+            }
+        }
+        for (var, count) in [
+            ("MUJINA_BOARD_WATCHDOG_RESTARTS", &mut policy.restarts),
+            (
+                "MUJINA_BOARD_WATCHDOG_POWER_CYCLES",
+                &mut policy.power_cycles,
+            ),
+        ] {
+            if let Ok(val) = env::var(var) {
+                match val.parse::<u32>() {
+                    Ok(n) => *count = n,
+                    Err(_) => warn!(value = %val, "Invalid {var}, using default"),
+                }
+            }
+        }
+        policy
+    }
+
+    /// The action for a board's `bite`th bite, counting from 1.
+    pub fn action(&self, bite: u32) -> BiteAction {
+        if bite <= self.restarts {
+            BiteAction::Restart
+        } else if bite - self.restarts <= self.power_cycles {
+            BiteAction::PowerCycle
+        } else {
+            BiteAction::Fail
+        }
+    }
+}
+
+/// How a board that lost its control link is brought back.
+///
+/// The board is re-created on its device, as after a watchdog bite, with
+/// a pause before each attempt so a port that dropped out has time to
+/// return. A board still unreachable after `attempts` tries is marked
+/// failed, to be re-enabled through the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Attempts to re-create the board. 0 fails it straight away.
+    pub attempts: u32,
+    /// Pause before each attempt.
+    pub delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Read `MUJINA_BOARD_RECONNECT_ATTEMPTS` and
+    /// `MUJINA_BOARD_RECONNECT_MS`, keeping the default for each one unset
+    /// or invalid.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(val) = env::var("MUJINA_BOARD_RECONNECT_ATTEMPTS") {
+            match val.parse::<u32>() {
+                Ok(n) => policy.attempts = n,
+                Err(_) => {
+                    warn!(value = %val, "Invalid MUJINA_BOARD_RECONNECT_ATTEMPTS, using default")
+                }
+            }
+        }
+        if let Ok(val) = env::var("MUJINA_BOARD_RECONNECT_MS") {
+            match val.parse::<u64>() {
+                Ok(ms) => policy.delay = Duration::from_millis(ms),
+                Err(_) => warn!(value = %val, "Invalid MUJINA_BOARD_RECONNECT_MS, using default"),
+            }
+        }
+        policy
+    }
+}
+
+/// Factory for a board that failed to initialize, kept for retrying.
+type RetryFactory = Box<dyn FnMut() -> BoxFuture<'static, Result<BackplaneConnector>> + Send>;
+
+/// A recognized board whose initialization failed.
+struct PendingBoard {
+    name: &'static str,
+    /// Path of the USB device, so unplugging it drops the retry.
+    device_path: String,
+    create: RetryFactory,
+    /// When its first attempt began, so the time it takes to come up
+    /// counts every attempt.
+    first_attempt: Instant,
+}
+
+/// A single attempt, for retrying boards that failed to initialize.
+const ONE_ATTEMPT: InitRetryPolicy = InitRetryPolicy {
+    attempts: 1,
+    delay: Duration::ZERO,
+};
+
+/// Why a board is being brought up, and so what to do once it is up or
+/// has failed to come up.
+enum BringUp {
+    /// Plugged in over USB. Kept for retrying if it fails.
+    Plugged,
+    /// The CPU miner. Not retried if it fails.
+    Cpu,
+    /// Retried after failing to initialize.
+    Retry,
+    /// Re-enabled through the API, replying once it is up.
+    Enable {
+        name: String,
+        reply: oneshot::Sender<Result<(), EnableError>>,
+    },
+    /// Restarted by the watchdog, keeping its count of bites.
+    Watchdog { name: String, bites: u32 },
+    /// Re-created after losing its control link. The old board is kept to
+    /// be marked failed if it can't be reached again.
+    Reconnect {
+        board: Box<ActiveBoard>,
+        reason: String,
+    },
+}
+
+/// A board bring-up in progress.
+struct Starting {
+    task: AbortHandle,
+    then: BringUp,
+}
+
+/// What a bring-up task hands back to the event loop.
+struct BroughtUp {
+    board_id: String,
+    restart: Restart,
+    result: Result<BackplaneConnector>,
+    /// When initializing began, not counting the wait for a slot.
+    started: Instant,
+}
+
 /// Backplane that connects boards to the scheduler.
 ///
 /// Acts as the communication substrate between mining boards and the work
@@ -50,7 +425,8 @@ impl BoardRegistry {
 pub struct Backplane {
     registry: BoardRegistry,
     virtual_registry: VirtualBoardRegistry,
-    /// Active boards managed by the backplane
+    /// Active boards, keyed by the transport's identity for the device
+    /// (USB device path, CPU device ID)
     boards: HashMap<String, ActiveBoard>,
     /// One event receiver per transport; the count sets how many initial
     /// enumeration completions to wait for.
@@ -59,6 +435,31 @@ pub struct Backplane {
     scheduler_tx: mpsc::Sender<ThreadRegistration>,
     /// Channel to forward board registrations to the API server
     board_reg_tx: mpsc::Sender<BoardRegistration>,
+    /// Retry policy for board factories
+    init_retry: InitRetryPolicy,
+    /// What to do while no boards are running
+    board_wait: BoardWaitPolicy,
+    /// Recognized boards that failed to initialize
+    pending: Vec<PendingBoard>,
+    /// Commands from the API, taken by `run()`
+    board_cmd_rx: Option<mpsc::Receiver<BoardCommand>>,
+    /// What must pass before a board that shut itself down runs again
+    cooldown: CooldownPolicy,
+    /// What to do about a board that stops reporting
+    watchdog: WatchdogPolicy,
+    /// What to do about a board that fails its startup self-test
+    self_test: SelfTestMode,
+    /// How a board that lost its control link is brought back
+    reconnect: ReconnectPolicy,
+    /// Boards that lost their control link, by board ID, with the reason
+    link_lost_tx: mpsc::Sender<(String, String)>,
+    link_lost_rx: Option<mpsc::Receiver<(String, String)>>,
+    /// One permit per board that may run its power-on sequence at once
+    init_slots: Arc<Semaphore>,
+    /// Tasks bringing boards up, off the event loop
+    bring_ups: JoinSet<BroughtUp>,
+    /// Boards being brought up, by board ID
+    starting: HashMap<String, Starting>,
 }
 
 impl Backplane {
@@ -67,7 +468,9 @@ impl Backplane {
         event_rxs: Vec<mpsc::Receiver<TransportEvent>>,
         scheduler_tx: mpsc::Sender<ThreadRegistration>,
         board_reg_tx: mpsc::Sender<BoardRegistration>,
+        board_cmd_rx: mpsc::Receiver<BoardCommand>,
     ) -> Self {
+        let (link_lost_tx, link_lost_rx) = mpsc::channel(8);
         Self {
             registry: BoardRegistry,
             virtual_registry: VirtualBoardRegistry,
@@ -75,6 +478,19 @@ impl Backplane {
             event_rxs,
             scheduler_tx,
             board_reg_tx,
+            init_retry: InitRetryPolicy::from_env(),
+            board_wait: BoardWaitPolicy::from_env(),
+            pending: Vec::new(),
+            board_cmd_rx: Some(board_cmd_rx),
+            cooldown: CooldownPolicy::from_env(),
+            watchdog: WatchdogPolicy::from_env(),
+            self_test: SelfTestMode::from_env(),
+            reconnect: ReconnectPolicy::from_env(),
+            link_lost_tx,
+            link_lost_rx: Some(link_lost_rx),
+            init_slots: Arc::new(Semaphore::new(init_concurrency_from_env())),
+            bring_ups: JoinSet::new(),
+            starting: HashMap::new(),
         }
     }
 
@@ -96,37 +512,319 @@ impl Backplane {
             completion_sent = true;
         }
 
-        while let Some((transport, event)) = streams.next().await {
-            match event {
-                TransportEvent::Usb(usb_event) => {
-                    self.handle_usb_event(usb_event).await?;
+        // Set once startup enumeration finds no boards, cleared when one
+        // starts: when the wait began and when to next retry.
+        let mut waiting: Option<(Instant, Instant)> = None;
+
+        let mut watchdog_tick = time::interval(WATCHDOG_CHECK);
+        watchdog_tick.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+
+        let mut board_cmd_rx = self.board_cmd_rx.take();
+        let mut link_lost_rx = self.link_lost_rx.take();
+        loop {
+            tokio::select! {
+                _ = watchdog_tick.tick(), if self.watchdog.timeout.is_some() => {
+                    self.check_watchdog();
+                }
+
+                Some((board_id, reason)) = async {
+                    match &mut link_lost_rx {
+                        Some(rx) => rx.recv().await,
+                        None => pending().await,
+                    }
+                } => {
+                    self.reconnect(board_id, reason);
+                }
+
+                Some(cmd) = async {
+                    match &mut board_cmd_rx {
+                        Some(rx) => rx.recv().await,
+                        None => pending().await,
+                    }
+                } => {
+                    self.handle_board_command(cmd);
+                }
+
+                Some(joined) = self.bring_ups.join_next_with_id(), if !self.bring_ups.is_empty() => {
+                    self.finish_bring_up(joined).await;
                 }
-                TransportEvent::Cpu(cpu_event) => {
-                    self.handle_cpu_event(cpu_event).await?;
+
+                // A transport that has said all it will, such as the CPU
+                // miner's, leaves its boards running: the backplane still
+                // answers commands and watches them until shutdown.
+                next = streams.next(), if !streams.is_empty() => {
+                    let Some((transport, event)) = next else { continue };
+                    match event {
+                        TransportEvent::Usb(usb_event) => {
+                            self.handle_usb_event(usb_event).await?;
+                        }
+                        TransportEvent::Cpu(cpu_event) => {
+                            self.handle_cpu_event(cpu_event).await?;
+                        }
+                        TransportEvent::InitialEnumerationComplete => {
+                            completed.insert(transport);
+                        }
+                    }
                 }
-                TransportEvent::InitialEnumerationComplete => {
-                    completed.insert(transport);
-                    if !completion_sent && completed.len() == transport_count {
-                        self.send_enumeration_complete().await;
-                        completion_sent = true;
+
+                _ = time::sleep_until(waiting.map_or_else(Instant::now, |(_, at)| at)),
+                    if waiting.is_some() =>
+                {
+                    let Some((since, _)) = waiting else { continue };
+                    self.retry_pending();
+                    let waited = since.elapsed();
+                    if self.board_wait.give_up_after.is_none_or(|limit| waited < limit) {
+                        warn!(waited_secs = waited.as_secs(), "Still waiting for hash boards");
                     }
+                    waiting = Some((since, Instant::now() + self.board_wait.retry_interval));
+                }
+            }
+
+            // Startup enumeration is over once every transport has said so
+            // and every board it found is up, or has failed to come up.
+            if !completion_sent && completed.len() == transport_count && self.starting.is_empty() {
+                self.send_enumeration_complete().await;
+                completion_sent = true;
+                if self.boards.is_empty() {
+                    self.report_no_boards();
+                    let now = Instant::now();
+                    waiting = Some((now, now + self.board_wait.retry_interval));
                 }
             }
+            if !self.boards.is_empty() {
+                waiting = None;
+            }
+            // Give up only once nothing is left that might still come up.
+            if let Some((since, _)) = waiting
+                && self.starting.is_empty()
+                && let Some(limit) = self.board_wait.give_up_after
+                && since.elapsed() >= limit
+            {
+                return Err(NoBoardsError { waited: limit }.into());
+            }
         }
+    }
 
-        Ok(())
+    /// Explain that no boards were found and what to check.
+    fn report_no_boards(&self) {
+        let failed: Vec<_> = self.pending.iter().map(|p| p.name).collect();
+        let wait = match self.board_wait.give_up_after {
+            Some(limit) => format!("waiting up to {}s", limit.as_secs()),
+            None => "waiting".to_string(),
+        };
+        error!(
+            failed_to_initialize = ?failed,
+            "No hash boards found; {wait} for one to appear. Check that boards \
+             are plugged in and powered, and that this user may open their USB \
+             serial ports (for example via the dialout group or a udev rule). \
+             Set MUJINA_CPUMINER_THREADS to mine on the CPU instead."
+        );
+    }
+
+    /// Try each board that failed to initialize once more.
+    fn retry_pending(&mut self) {
+        for pending in std::mem::take(&mut self.pending) {
+            let restart = Restart {
+                name: pending.name,
+                create: pending.create,
+            };
+            self.spawn_bring_up(
+                pending.device_path,
+                restart,
+                ONE_ATTEMPT,
+                None,
+                Some(pending.first_attempt),
+                BringUp::Retry,
+            );
+        }
+    }
+
+    /// Bring a board up on `board_id` off the event loop.
+    ///
+    /// `before` runs first, such as shutting down the board being
+    /// replaced. The board then waits for an init slot and is created with
+    /// `retry`. Its init time runs from `first_attempt`, for a board tried
+    /// before, else from when it gets the slot. The outcome comes back to
+    /// [`Backplane::finish_bring_up`], which acts on it as `then` says.
+    fn spawn_bring_up(
+        &mut self,
+        board_id: String,
+        mut restart: Restart,
+        retry: InitRetryPolicy,
+        before: Option<BoxFuture<'static, ()>>,
+        first_attempt: Option<Instant>,
+        then: BringUp,
+    ) {
+        let slots = Arc::clone(&self.init_slots);
+        let task = self.bring_ups.spawn({
+            let board_id = board_id.clone();
+            async move {
+                if let Some(before) = before {
+                    before.await;
+                }
+                let _slot = slots.acquire_owned().await.expect("init slots never close");
+                let started = first_attempt.unwrap_or_else(Instant::now);
+                let result = retry.run(restart.name, &mut restart.create).await;
+                BroughtUp {
+                    board_id,
+                    restart,
+                    result,
+                    started,
+                }
+            }
+        });
+        // A board brought up again on the same device replaces the
+        // earlier bring-up, whose result is then thrown away.
+        if let Some(earlier) = self.starting.insert(board_id, Starting { task, then }) {
+            earlier.task.abort();
+        }
     }
 
-    /// Shutdown all boards managed by this backplane.
-    pub async fn shutdown_all_boards(&mut self) {
+    /// Act on a finished bring-up: start the board, or deal with its
+    /// failure as the reason it was brought up says.
+    async fn finish_bring_up(&mut self, joined: Result<(task::Id, BroughtUp), JoinError>) {
+        let (id, brought) = match joined {
+            Ok(joined) => joined,
+            Err(e) => {
+                // Aborted bring-ups were forgotten when they were aborted.
+                let panicked = self
+                    .starting
+                    .iter()
+                    .find(|(_, s)| s.task.id() == e.id())
+                    .map(|(board_id, _)| board_id.clone());
+                if let Some(board_id) = panicked {
+                    self.starting.remove(&board_id);
+                    error!(device = %board_id, error = %e, "Board bring-up failed");
+                }
+                return;
+            }
+        };
+        let BroughtUp {
+            board_id,
+            restart,
+            result,
+            started,
+        } = brought;
+        if self
+            .starting
+            .get(&board_id)
+            .is_none_or(|s| s.task.id() != id)
+        {
+            // Unplugged, or replaced, while it came up.
+            if let Ok(conn) = result
+                && let Some(shutdown) = conn.shutdown
+            {
+                tokio::spawn(shutdown(ShutdownMode::PowerOff));
+            }
+            return;
+        }
+        let Some(Starting { then, .. }) = self.starting.remove(&board_id) else {
+            return;
+        };
+
+        let conn = match result {
+            Ok(conn) => conn,
+            Err(e) => {
+                self.bring_up_failed(board_id, restart, started, then, e);
+                return;
+            }
+        };
+        if let BringUp::Reconnect { board, .. } = &then {
+            info!(board = %board.name, "Board reconnected");
+        }
+        self.start_board(board_id.clone(), conn, started.elapsed(), restart)
+            .await;
+        match then {
+            BringUp::Enable { reply, .. } => {
+                let _ = reply.send(Ok(()));
+            }
+            BringUp::Watchdog { bites, .. } => {
+                if let Some(board) = self.boards.get_mut(&board_id) {
+                    board.bites = bites;
+                }
+            }
+            BringUp::Plugged | BringUp::Cpu | BringUp::Retry | BringUp::Reconnect { .. } => {}
+        }
+    }
+
+    /// Deal with a board that failed to come up, having started trying at
+    /// `started`. Most wait with the other boards that failed to
+    /// initialize.
+    fn bring_up_failed(
+        &mut self,
+        board_id: String,
+        restart: Restart,
+        started: Instant,
+        then: BringUp,
+        error: anyhow::Error,
+    ) {
+        let name = restart.name;
+        match then {
+            BringUp::Plugged => error!(
+                board = name,
+                attempts = self.init_retry.attempts,
+                error = %error,
+                "Failed to create board"
+            ),
+            BringUp::Cpu => {
+                error!(
+                    board = name,
+                    attempts = self.init_retry.attempts,
+                    error = %error,
+                    "Failed to create CPU miner board"
+                );
+                return;
+            }
+            BringUp::Retry => {
+                warn!(board = name, error = %error, "Board initialization retry failed")
+            }
+            BringUp::Enable { name, reply } => {
+                error!(board = %name, error = %error, "Failed to re-enable board");
+                let _ = reply.send(Err(EnableError::Failed(error)));
+            }
+            BringUp::Watchdog { name, .. } => {
+                error!(board = %name, error = %error, "Failed to bring board back after watchdog bite")
+            }
+            BringUp::Reconnect { mut board, reason } => {
+                board.restart = Some(restart);
+                self.fail_reconnect(board_id, *board, &reason, error);
+                return;
+            }
+        }
+        self.pending.push(PendingBoard {
+            name,
+            device_path: board_id,
+            create: restart.create,
+            first_attempt: started,
+        });
+    }
+
+    /// Shutdown all boards managed by this backplane, as far as `mode`
+    /// says.
+    pub async fn shutdown_all_boards(&mut self, mode: ShutdownMode) {
+        // Boards still coming up are abandoned, but any that made it up
+        // are shut down like the rest.
+        self.starting.clear();
+        self.bring_ups.abort_all();
+        while let Some(joined) = self.bring_ups.join_next().await {
+            if let Ok(BroughtUp {
+                result: Ok(conn), ..
+            }) = joined
+                && let Some(shutdown) = conn.shutdown
+            {
+                shutdown(mode).await;
+            }
+        }
+
         let board_ids: Vec<String> = self.boards.keys().cloned().collect();
 
         for board_id in board_ids {
             if let Some(mut board) = self.boards.remove(&board_id) {
-                board.shutdown().await;
+                board.shutdown(mode).await;
                 info!(
                     board = %board.info.model,
-                    serial = %board_id,
+                    serial = ?board.info.serial_number,
+                    ?mode,
                     "Board stopped"
                 );
             }
@@ -134,15 +832,95 @@ impl Backplane {
     }
 
     /// Route a board connection's parts to where they belong.
-    async fn start_board(&mut self, board_id: String, conn: BackplaneConnector) {
+    ///
+    /// `board_id` is the transport's identity for the device, so its
+    /// disconnect event finds the board again. `init_duration` is how long
+    /// the board took to initialize, for telemetry and the log. `restart`
+    /// brings the board up again if it is re-enabled after shutting
+    /// itself down.
+    async fn start_board(
+        &mut self,
+        board_id: String,
+        conn: BackplaneConnector,
+        init_duration: Duration,
+        restart: Restart,
+    ) {
         let BackplaneConnector {
             info,
             threads,
             telemetry_rx,
+            profile_tx,
+            profile_guard,
+            fan_tx,
             shutdown,
+            trip_rx,
+            self_test,
+            link_lost_rx,
         } = conn;
 
-        let registration = BoardRegistration { telemetry_rx };
+        let reported = telemetry_rx.borrow().name.clone();
+        let name = self.distinct_name(&board_id, &reported);
+        // Forwarded with the board's ID. The flag tells the report from
+        // one for an earlier instance of the board on the same device.
+        let link_lost = Arc::new(AtomicBool::new(false));
+        if let Some(rx) = link_lost_rx {
+            let (tx, flag, board_id) = (
+                self.link_lost_tx.clone(),
+                Arc::clone(&link_lost),
+                board_id.clone(),
+            );
+            tokio::spawn(async move {
+                if let Ok(reason) = rx.await {
+                    flag.store(true, Ordering::SeqCst);
+                    let _ = tx.send((board_id, reason)).await;
+                }
+            });
+        }
+        let mut board = ActiveBoard {
+            name: name.clone(),
+            info,
+            shutdown,
+            telemetry_rx,
+            trip_rx,
+            trip: None,
+            restart: Some(restart),
+            fan_tx,
+            last_report: Instant::now(),
+            bites: 0,
+            link_lost,
+        };
+        if let Some(Err(failure)) = self_test {
+            match self.self_test {
+                SelfTestMode::Off => debug!(board = %name, %failure, "Board failed its self-test"),
+                SelfTestMode::Warn => {
+                    warn!(board = %name, %failure, "Board failed its self-test, mining anyway")
+                }
+                SelfTestMode::Enforce => {
+                    error!(
+                        board = %name,
+                        %failure,
+                        "Board failed its self-test, isolating it; re-enable it through the API"
+                    );
+                    board.shutdown(ShutdownMode::PowerOff).await;
+                    board.trip = Some(Trip {
+                        at: Instant::now(),
+                        reason: failure.to_string(),
+                    });
+                    self.boards.insert(board_id, board);
+                    return;
+                }
+            }
+        }
+        let ActiveBoard {
+            info, telemetry_rx, ..
+        } = &board;
+        let registration = BoardRegistration {
+            name: (name != reported).then(|| name.clone()),
+            telemetry_rx: telemetry_rx.clone(),
+            init_duration: Some(init_duration),
+            profile_tx,
+            profile_guard,
+        };
         if let Err(e) = self.board_reg_tx.send(registration).await {
             error!(
                 board = %info.model,
@@ -153,15 +931,19 @@ impl Backplane {
 
         info!(
             board = %info.model,
-            serial = %board_id,
+            serial = ?info.serial_number,
             threads = threads.len(),
+            init_ms = init_duration.as_millis(),
             "Board started."
         );
 
         for thread in threads {
             if let Err(e) = self
                 .scheduler_tx
-                .send(ThreadRegistration::Thread(thread))
+                .send(ThreadRegistration::Thread {
+                    thread,
+                    board: name.clone(),
+                })
                 .await
             {
                 error!(
@@ -173,7 +955,290 @@ impl Backplane {
             }
         }
 
-        self.boards.insert(board_id, ActiveBoard { info, shutdown });
+        self.boards.insert(board_id, board);
+    }
+
+    /// A name for a board reporting `name` that no running board has.
+    ///
+    /// Board names come from serial numbers, and everything about a
+    /// board, down to its stats, is kept by name. Two boards with the same
+    /// serial, from cloned firmware or a blank EEPROM, would be counted as
+    /// one. The later to come up is listed under the name with the first
+    /// free suffix, `-2`, `-3` and so on, with a warning.
+    fn distinct_name(&self, board_id: &str, name: &str) -> String {
+        let taken = |candidate: &str| self.boards.values().any(|b| b.name == candidate);
+        if !taken(name) {
+            return name.to_string();
+        }
+        let distinct = (2..)
+            .map(|suffix| format!("{name}-{suffix}"))
+            .find(|candidate| !taken(candidate))
+            .expect("some suffix is free");
+        warn!(
+            board = name,
+            renamed = %distinct,
+            device = board_id,
+            "DUPLICATE BOARD ID: another board reports the same name, listing this one \
+             under another; give each board a unique serial number"
+        );
+        distinct
+    }
+
+    /// Handle a command from the API.
+    fn handle_board_command(&mut self, cmd: BoardCommand) {
+        match cmd {
+            BoardCommand::Enable {
+                board,
+                force,
+                reply,
+            } => match self.take_tripped(&board, force) {
+                Ok((board_id, mut tripped, restart)) => {
+                    let before = tripped.shut_down_within_grace(ShutdownMode::PowerOff);
+                    let then = BringUp::Enable { name: board, reply };
+                    self.spawn_bring_up(
+                        board_id,
+                        restart,
+                        self.init_retry,
+                        Some(before),
+                        None,
+                        then,
+                    );
+                }
+                Err(e) => {
+                    let _ = reply.send(Err(e));
+                }
+            },
+            BoardCommand::SetFanTarget {
+                board,
+                fan,
+                percent,
+                reply,
+            } => {
+                let _ = reply.send(self.set_fan_target(&board, &fan, percent));
+            }
+        }
+    }
+
+    /// Hold fan `fan` of the board named `name` at `percent`, or hand it
+    /// back to automatic control when `None`.
+    fn set_fan_target(
+        &self,
+        name: &str,
+        fan: &str,
+        percent: Option<u8>,
+    ) -> Result<(), FanTargetError> {
+        let board = self
+            .boards
+            .values()
+            .find(|b| b.name == name)
+            .ok_or(FanTargetError::NotFound)?;
+        if !board
+            .telemetry_rx
+            .borrow()
+            .fans
+            .iter()
+            .any(|f| f.name == fan)
+        {
+            return Err(FanTargetError::NoSuchFan);
+        }
+        let fan_tx = board.fan_tx.as_ref().ok_or(FanTargetError::Unsupported)?;
+        let target = percent
+            .map(|p| Percent::new(p).ok_or(FanTargetError::OutOfRange(p)))
+            .transpose()?;
+        match percent {
+            Some(percent) => info!(board = name, fan, percent, "Fan set to a manual duty cycle"),
+            None => info!(board = name, fan, "Fan returned to automatic control"),
+        }
+        fan_tx.send_replace(target);
+        Ok(())
+    }
+
+    /// Take the board named `name` out of the running ones to bring it
+    /// back up after it shut itself down, with how to restart it.
+    ///
+    /// Refused while the cooldown runs unless `force` is set. The board
+    /// is re-created on its device; if that fails, it waits with the
+    /// other boards that failed to initialize.
+    fn take_tripped(
+        &mut self,
+        name: &str,
+        force: bool,
+    ) -> Result<(String, ActiveBoard, Restart), EnableError> {
+        let Some((board_id, board)) = self.boards.iter_mut().find(|(_, b)| b.name == name) else {
+            return Err(EnableError::NotFound);
+        };
+        let Some(trip) = board.trip().cloned() else {
+            return Err(EnableError::NotTripped);
+        };
+        let cooled_secs = trip.at.elapsed().as_secs();
+        if force {
+            warn!(
+                board = name,
+                fault = %trip.reason,
+                cooled_secs,
+                "Re-enabling board without waiting for cooldown"
+            );
+        } else {
+            self.cooldown
+                .check(&trip, Instant::now(), board.hottest_c())
+                .inspect_err(|e| {
+                    info!(board = name, reason = %e, "Board re-enable refused");
+                })?;
+        }
+        let board_id = board_id.clone();
+        let Some(mut board) = self.boards.remove(&board_id) else {
+            return Err(EnableError::NotFound);
+        };
+        let Some(restart) = board.restart.take() else {
+            self.boards.insert(board_id, board);
+            return Err(EnableError::Failed(anyhow!("board cannot be restarted")));
+        };
+        info!(board = name, fault = %trip.reason, cooled_secs, "Re-enabling board");
+        Ok((board_id, board, restart))
+    }
+
+    /// Bite every running board whose telemetry has been silent past the
+    /// watchdog timeout. Boards that shut themselves down, or were failed
+    /// by the watchdog, are quiet on purpose and left alone.
+    fn check_watchdog(&mut self) {
+        let Some(timeout) = self.watchdog.timeout else {
+            return;
+        };
+        let now = Instant::now();
+        let mut wedged = Vec::new();
+        for (board_id, board) in &mut self.boards {
+            if board.trip().is_some() {
+                continue;
+            }
+            if board.telemetry_rx.has_changed().unwrap_or(false) {
+                board.telemetry_rx.mark_unchanged();
+                board.last_report = now;
+            } else if now.duration_since(board.last_report) >= timeout {
+                wedged.push(board_id.clone());
+            }
+        }
+        for board_id in wedged {
+            self.bite(board_id);
+        }
+    }
+
+    /// Answer a wedged board with the next action on the watchdog ladder.
+    fn bite(&mut self, board_id: String) {
+        let Some(mut board) = self.boards.remove(&board_id) else {
+            return;
+        };
+        board.bites += 1;
+        let bites = board.bites;
+        let silent_secs = board.last_report.elapsed().as_secs();
+        let restart = board.restart.take();
+        let action = match restart {
+            Some(_) => self.watchdog.action(bites),
+            None => BiteAction::Fail,
+        };
+        warn!(board = %board.name, silent_secs, bites, ?action, "Board stopped reporting, watchdog bit");
+
+        let mode = match action {
+            BiteAction::Restart => ShutdownMode::Idle,
+            BiteAction::PowerCycle | BiteAction::Fail => ShutdownMode::PowerOff,
+        };
+        let shutdown = board.shut_down_within_grace(mode);
+
+        let restart = match (action, restart) {
+            (BiteAction::Restart | BiteAction::PowerCycle, Some(restart)) => restart,
+            (_, restart) => {
+                tokio::spawn(shutdown);
+                error!(
+                    board = %board.name,
+                    bites,
+                    "Board failed after repeated watchdog bites; re-enable it through the API"
+                );
+                board.restart = restart;
+                board.trip = Some(Trip {
+                    at: Instant::now(),
+                    reason: format!(
+                        "watchdog: no telemetry for {silent_secs}s after {bites} bites"
+                    ),
+                });
+                self.boards.insert(board_id, board);
+                return;
+            }
+        };
+        let before = Box::pin(async move {
+            shutdown.await;
+            if action == BiteAction::PowerCycle {
+                time::sleep(POWER_CYCLE_OFF).await;
+            }
+        });
+        let then = BringUp::Watchdog {
+            name: board.name,
+            bites,
+        };
+        self.spawn_bring_up(board_id, restart, self.init_retry, Some(before), None, then);
+    }
+
+    /// Re-create a board that lost its control link, failing it if it
+    /// can't be reached again within the reconnect policy.
+    fn reconnect(&mut self, board_id: String, reason: String) {
+        if !self
+            .boards
+            .get(&board_id)
+            .is_some_and(|b| b.link_lost.load(Ordering::SeqCst))
+        {
+            return;
+        }
+        let Some(mut board) = self.boards.remove(&board_id) else {
+            return;
+        };
+        let attempts = self.reconnect.attempts;
+        warn!(board = %board.name, %reason, attempts, "Board lost its control link, reconnecting");
+        let shutdown = board.shut_down_within_grace(ShutdownMode::PowerOff);
+
+        let restart = match board.restart.take() {
+            Some(restart) if attempts > 0 => restart,
+            restart => {
+                tokio::spawn(shutdown);
+                let error = match restart {
+                    Some(_) => anyhow!("reconnecting is disabled"),
+                    None => anyhow!("board cannot be restarted"),
+                };
+                board.restart = restart;
+                self.fail_reconnect(board_id, board, &reason, error);
+                return;
+            }
+        };
+        let delay = self.reconnect.delay;
+        let before = Box::pin(async move {
+            shutdown.await;
+            time::sleep(delay).await;
+        });
+        let retry = InitRetryPolicy { attempts, delay };
+        let then = BringUp::Reconnect {
+            board: Box::new(board),
+            reason,
+        };
+        self.spawn_bring_up(board_id, restart, retry, Some(before), None, then);
+    }
+
+    /// Mark a board that couldn't be reconnected failed, to be re-enabled
+    /// through the API.
+    fn fail_reconnect(
+        &mut self,
+        board_id: String,
+        mut board: ActiveBoard,
+        reason: &str,
+        error: anyhow::Error,
+    ) {
+        error!(
+            board = %board.name,
+            attempts = self.reconnect.attempts,
+            %error,
+            "Board could not be reconnected; re-enable it through the API"
+        );
+        board.trip = Some(Trip {
+            at: Instant::now(),
+            reason: format!("{reason}, not reconnected: {error}"),
+        });
+        self.boards.insert(board_id, board);
     }
 
     /// Tell the scheduler that startup enumeration across all transports is
@@ -190,6 +1255,20 @@ impl Backplane {
         }
     }
 
+    /// Abandon the bring-up of the board on `board_id`, whose device has
+    /// gone. Returns whether one was in progress.
+    fn abandon_bring_up(&mut self, board_id: &str) -> bool {
+        let Some(Starting { task, then }) = self.starting.remove(board_id) else {
+            return false;
+        };
+        task.abort();
+        info!(device = board_id, "Board disconnected before it came up");
+        if let BringUp::Enable { reply, .. } = then {
+            let _ = reply.send(Err(EnableError::NotFound));
+        }
+        true
+    }
+
     /// Handle USB transport events.
     async fn handle_usb_event(&mut self, event: UsbTransportEvent) -> Result<()> {
         match event {
@@ -208,42 +1287,35 @@ impl Backplane {
                     "Hash board connected via USB."
                 );
 
-                let conn = match (descriptor.create_fn)(device_info).await {
-                    Ok(conn) => conn,
-                    Err(e) => {
-                        error!(
-                            board = descriptor.name,
-                            error = %e,
-                            "Failed to create board"
-                        );
-                        return Ok(());
-                    }
+                let device_path = device_info.device_path.clone();
+                let restart = Restart {
+                    name: descriptor.name,
+                    create: Box::new(move || (descriptor.create_fn)(device_info.clone())),
                 };
+                self.spawn_bring_up(
+                    device_path,
+                    restart,
+                    self.init_retry,
+                    None,
+                    None,
+                    BringUp::Plugged,
+                );
+            }
+            UsbTransportEvent::UsbDeviceDisconnected { device_path } => {
+                // A board that never came up has nothing to shut down.
+                let pending = self.pending.len();
+                self.pending.retain(|p| p.device_path != device_path);
+                if self.pending.len() < pending || self.abandon_bring_up(&device_path) {
+                    return Ok(());
+                }
 
-                let board_id = conn
-                    .info
-                    .serial_number
-                    .clone()
-                    .unwrap_or_else(|| "unknown".to_string());
-
-                self.start_board(board_id, conn).await;
-            }
-            UsbTransportEvent::UsbDeviceDisconnected { device_path: _ } => {
-                // Find and shutdown the board
-                // Note: Current design uses serial number as key, but we get device_path
-                // in disconnect event. For single-board setups this works fine.
-                // TODO: Maintain device_path -> board_id mapping for multi-board support
-                let board_ids: Vec<String> = self.boards.keys().cloned().collect();
-                for board_id in board_ids {
-                    if let Some(mut board) = self.boards.remove(&board_id) {
-                        board.shutdown().await;
-                        info!(
-                            board = %board.info.model,
-                            serial = %board_id,
-                            "Board disconnected"
-                        );
-                        break; // For now, assume one board per device
-                    }
+                if let Some(mut board) = self.boards.remove(&device_path) {
+                    board.shutdown(ShutdownMode::PowerOff).await;
+                    info!(
+                        board = %board.info.model,
+                        serial = ?board.info.serial_number,
+                        "Board disconnected"
+                    );
                 }
             }
         }
@@ -262,30 +1334,29 @@ impl Backplane {
 
                 info!(
                     board = descriptor.name,
-                    threads = device_info.thread_count,
-                    duty = device_info.duty_percent,
+                    device = %device_info.device_id,
                     "CPU miner board connected."
                 );
 
-                let conn = match (descriptor.create_fn)().await {
-                    Ok(conn) => conn,
-                    Err(e) => {
-                        error!(
-                            board = descriptor.name,
-                            error = %e,
-                            "Failed to create CPU miner board"
-                        );
-                        return Ok(());
-                    }
-                };
-
                 let board_id = device_info.device_id.clone();
-                self.start_board(board_id, conn).await;
+                let create_fn = descriptor.create_fn;
+                let restart = Restart {
+                    name: descriptor.name,
+                    create: Box::new(move || create_fn(device_info.clone())),
+                };
+                self.spawn_bring_up(board_id, restart, self.init_retry, None, None, BringUp::Cpu);
             }
             CpuTransportEvent::CpuDeviceDisconnected { device_id } => {
+                if self.abandon_bring_up(&device_id) {
+                    return Ok(());
+                }
                 if let Some(mut board) = self.boards.remove(&device_id) {
-                    board.shutdown().await;
-                    info!(board = %board.info.model, serial = %device_id, "Board disconnected");
+                    board.shutdown(ShutdownMode::PowerOff).await;
+                    info!(
+                        board = %board.info.model,
+                        serial = ?board.info.serial_number,
+                        "Board disconnected"
+                    );
                 }
             }
         }
@@ -294,16 +1365,1114 @@ impl Backplane {
     }
 }
 
+/// How to bring a board up again on the same device.
+struct Restart {
+    name: &'static str,
+    create: RetryFactory,
+}
+
 /// Per-board state the backplane keeps for lifecycle management.
 struct ActiveBoard {
+    /// The board's name in telemetry and the API.
+    name: String,
     info: BoardInfo,
-    shutdown: Option<BoxFuture<'static, ()>>,
+    shutdown: Option<BoardShutdown>,
+    telemetry_rx: watch::Receiver<BoardTelemetry>,
+    trip_rx: Option<oneshot::Receiver<Trip>>,
+    /// Set once the board has shut itself down, or the watchdog failed
+    /// it.
+    trip: Option<Trip>,
+    restart: Option<Restart>,
+    /// Sets the board's fan by hand. `None` if it can't be.
+    fan_tx: Option<watch::Sender<Option<Percent>>>,
+    /// When the board's telemetry last changed, as the watchdog saw it.
+    last_report: Instant,
+    /// Watchdog bites since the board was plugged in or re-enabled.
+    bites: u32,
+    /// Set once this instance of the board reports its control link lost.
+    link_lost: Arc<AtomicBool>,
 }
 
 impl ActiveBoard {
-    async fn shutdown(&mut self) {
-        if let Some(fut) = self.shutdown.take() {
-            fut.await;
+    /// The board's shutdown, if it has shut itself down.
+    fn trip(&mut self) -> Option<&Trip> {
+        if self.trip.is_none()
+            && let Some(rx) = &mut self.trip_rx
+            && let Ok(trip) = rx.try_recv()
+        {
+            self.trip = Some(trip);
+        }
+        self.trip.as_ref()
+    }
+
+    /// The board's hottest sensor reading, in degrees Celsius.
+    fn hottest_c(&self) -> Option<f32> {
+        self.telemetry_rx
+            .borrow()
+            .temperatures
+            .iter()
+            .filter_map(|s| s.temperature)
+            .map(|t| t.as_degrees_c())
+            .reduce(f32::max)
+    }
+
+    async fn shutdown(&mut self, mode: ShutdownMode) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown(mode).await;
+        }
+    }
+
+    /// The board's shutdown as `mode` says, to run off the event loop. A
+    /// board that doesn't finish within [`BITE_SHUTDOWN_GRACE`] is
+    /// abandoned.
+    fn shut_down_within_grace(&mut self, mode: ShutdownMode) -> BoxFuture<'static, ()> {
+        let shutdown = self.shutdown.take();
+        let name = self.name.clone();
+        Box::pin(async move {
+            let Some(shutdown) = shutdown else { return };
+            if time::timeout(BITE_SHUTDOWN_GRACE, shutdown(mode))
+                .await
+                .is_err()
+            {
+                warn!(board = %name, "Board did not shut down in time, abandoning it");
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use anyhow::bail;
+    use serial_test::serial;
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::api_client::types::BoardTelemetry;
+    use crate::board::pattern::{Match, StringMatch};
+    use crate::board::self_test::SelfTestFailure;
+    use crate::daemon::{ShutdownProfiles, StopSignal};
+    use crate::hw_trait::gpio::{Gpio, GpioPin};
+    use crate::mgmt_protocol::BitaxeRawGpioController;
+    use crate::mgmt_protocol::bitaxe_raw::ResponseFormat;
+    use crate::mgmt_protocol::bitaxe_raw::transport::MockFirmware;
+
+    fn connector() -> BackplaneConnector {
+        BackplaneConnector {
+            info: BoardInfo {
+                model: "flaky".into(),
+                firmware_version: None,
+                serial_number: None,
+            },
+            threads: Vec::new(),
+            telemetry_rx: tokio::sync::watch::channel(BoardTelemetry::default()).1,
+            profile_tx: None,
+            profile_guard: None,
+            fan_tx: None,
+            self_test: None,
+            shutdown: None,
+            trip_rx: None,
+            link_lost_rx: None,
+        }
+    }
+
+    /// A factory that fails its first `failures` calls, counting calls.
+    fn flaky(
+        failures: u32,
+        calls: &Arc<AtomicU32>,
+    ) -> impl FnMut() -> BoxFuture<'static, Result<BackplaneConnector>> + Send + use<> {
+        let calls = Arc::clone(calls);
+        move || {
+            let calls = Arc::clone(&calls);
+            Box::pin(async move {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                if call < failures {
+                    bail!("PLL did not lock (attempt {})", call + 1);
+                }
+                Ok(connector())
+            })
+        }
+    }
+
+    const POLICY: InitRetryPolicy = InitRetryPolicy {
+        attempts: 4,
+        delay: Duration::from_millis(500),
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn board_comes_up_within_retry_budget() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let start = Instant::now();
+
+        let conn = POLICY.run("flaky", flaky(3, &calls)).await.unwrap();
+        assert_eq!(conn.info.model, "flaky");
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert_eq!(start.elapsed(), POLICY.delay * 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn board_fails_once_budget_is_spent() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let err = match POLICY.run("flaky", flaky(10, &calls)).await {
+            Ok(_) => panic!("board came up past its retry budget"),
+            Err(e) => e,
+        };
+        assert_eq!(calls.load(Ordering::SeqCst), POLICY.attempts);
+        assert!(err.to_string().contains("attempt 4"), "{err}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn healthy_board_is_not_delayed() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let start = Instant::now();
+        POLICY.run("flaky", flaky(0, &calls)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    /// A backplane over one transport, returning the transport sender and
+    /// the receiver boards register on.
+    fn backplane(
+        board_wait: BoardWaitPolicy,
+    ) -> (
+        Backplane,
+        mpsc::Sender<TransportEvent>,
+        mpsc::Receiver<BoardRegistration>,
+    ) {
+        let (transport_tx, transport_rx) = mpsc::channel(4);
+        // No scheduler: the backplane only logs when it can't reach one.
+        let (thread_tx, _) = mpsc::channel(4);
+        let (board_reg_tx, board_reg_rx) = mpsc::channel(4);
+        // No API: nothing sends board commands.
+        let (_, board_cmd_rx) = mpsc::channel(1);
+        let mut backplane =
+            Backplane::new(vec![transport_rx], thread_tx, board_reg_tx, board_cmd_rx);
+        backplane.board_wait = board_wait;
+        // Test boards don't report telemetry.
+        backplane.watchdog.timeout = None;
+        (backplane, transport_tx, board_reg_rx)
+    }
+
+    const WAIT: BoardWaitPolicy = BoardWaitPolicy {
+        retry_interval: Duration::from_secs(30),
+        give_up_after: None,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn board_missing_at_startup_is_picked_up_on_retry() {
+        let (mut backplane, transport_tx, mut board_reg_rx) = backplane(WAIT);
+
+        // Found at enumeration, but its ports weren't openable yet: it
+        // fails at startup and on the first retry, then comes up.
+        let calls = Arc::new(AtomicU32::new(0));
+        let mut create = flaky(2, &calls);
+        assert!(create().await.is_err());
+        backplane.pending.push(PendingBoard {
+            name: "flaky",
+            device_path: "/sys/devices/usb1".into(),
+            create: Box::new(create),
+            first_attempt: Instant::now(),
+        });
+
+        let start = Instant::now();
+        transport_tx
+            .send(TransportEvent::InitialEnumerationComplete)
+            .await
+            .unwrap();
+        tokio::spawn(async move { backplane.run().await });
+
+        board_reg_rx.recv().await.expect("board registered");
+        assert_eq!(start.elapsed(), WAIT.retry_interval * 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_wait_limit() {
+        let limit = Duration::from_secs(90);
+        let (mut backplane, transport_tx, _board_reg_rx) = backplane(BoardWaitPolicy {
+            give_up_after: Some(limit),
+            ..WAIT
+        });
+
+        let start = Instant::now();
+        transport_tx
+            .send(TransportEvent::InitialEnumerationComplete)
+            .await
+            .unwrap();
+        let err = backplane.run().await.unwrap_err();
+        assert_eq!(err.to_string(), "no hash boards came up within 90s");
+        assert_eq!(start.elapsed(), limit);
+        assert!(matches!(
+            crate::daemon::DaemonError::from(err),
+            crate::daemon::DaemonError::NoBoards(NoBoardsError { waited }) if waited == limit
+        ));
+    }
+
+    // Every test board is one descriptor; the USB product string picks
+    // how the board behaves, in `test_board`.
+    inventory::submit! {
+        BoardDescriptor {
+            pattern: crate::board::pattern::BoardPattern {
+                vid: Match::Any,
+                pid: Match::Any,
+                bcd_device: Match::Any,
+                manufacturer: Match::Specific(StringMatch::Exact("Mujina Test")),
+                product: Match::Any,
+                serial_pattern: Match::Any,
+            },
+            name: "Mujina Test",
+            create_fn: |device| Box::pin(test_board(device.product.unwrap_or_default())),
+        }
+    }
+
+    /// Products each test board factory call was for, in order.
+    static CALLS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+    /// Names of the test boards shut down, with how each was.
+    static SHUTDOWNS: std::sync::Mutex<Vec<(String, ShutdownMode)>> =
+        std::sync::Mutex::new(Vec::new());
+
+    /// Times the factory was called for test boards of `product`.
+    fn calls(product: &str) -> usize {
+        CALLS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|p| *p == product)
+            .count()
+    }
+
+    /// How each test board named `name` was shut down.
+    fn shutdowns(name: &str) -> Vec<ShutdownMode> {
+        SHUTDOWNS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(n, _)| n == name)
+            .map(|(_, mode)| *mode)
+            .collect()
+    }
+
+    /// How long the slow-init test board takes to come up.
+    const SLOW_INIT: Duration = Duration::from_millis(3200);
+
+    /// Bring up the test board `product`:
+    ///
+    /// - Hotplug: comes up at once and stays up.
+    /// - Slow Init: takes [`SLOW_INIT`] to come up.
+    /// - Late Init: fails on its first call, then comes up like Slow Init.
+    /// - Inrush: takes half a second to power on, counted in
+    ///   [`POWERING_ON`].
+    /// - Tripping: shuts itself down as soon as it comes up.
+    /// - Failing: fails its self-test.
+    /// - Fanned: has one fan that can be set by hand.
+    /// - Wedging: its monitor never reports, as if wedged from the start.
+    /// - Reconnecting: polled over an in-memory control channel, reporting
+    ///   the link lost when the far end goes away. Its port is openable on
+    ///   the first and third calls only, so one reconnect succeeds on its
+    ///   second try and any later one fails.
+    async fn test_board(product: String) -> Result<BackplaneConnector> {
+        CALLS.lock().unwrap().push(product.clone());
+        let named = BoardTelemetry {
+            name: product.to_lowercase(),
+            ..Default::default()
+        };
+        match product.as_str() {
+            "Hotplug" | "Wedging" => Ok(test_connector(named)),
+            "Slow Init" => {
+                time::sleep(SLOW_INIT).await;
+                Ok(test_connector(named))
+            }
+            "Late Init" => {
+                if calls("Late Init") == 1 {
+                    bail!("no control port yet");
+                }
+                time::sleep(SLOW_INIT).await;
+                Ok(test_connector(named))
+            }
+            "Inrush" => {
+                let now = POWERING_ON.fetch_add(1, Ordering::SeqCst) + 1;
+                MAX_POWERING_ON.fetch_max(now, Ordering::SeqCst);
+                time::sleep(Duration::from_millis(500)).await;
+                POWERING_ON.fetch_sub(1, Ordering::SeqCst);
+                Ok(test_connector(named))
+            }
+            "Tripping" => {
+                let (trip_tx, trip_rx) = oneshot::channel();
+                trip_tx
+                    .send(Trip {
+                        at: Instant::now(),
+                        reason: "thermal emergency".into(),
+                    })
+                    .unwrap();
+                Ok(BackplaneConnector {
+                    trip_rx: Some(trip_rx),
+                    ..test_connector(named)
+                })
+            }
+            "Failing" => Ok(BackplaneConnector {
+                self_test: Some(SelfTestFailure::outcome(vec![
+                    "fan at 0 RPM at full duty".into(),
+                ])),
+                ..test_connector(named)
+            }),
+            "Fanned" => {
+                let (fan_tx, fan_rx) = watch::channel(None);
+                *FANNED_FAN.lock().unwrap() = Some(fan_rx);
+                let fans = vec![crate::api_client::types::Fan {
+                    name: "fan".into(),
+                    rpm: None,
+                    percent: None,
+                    target_percent: None,
+                }];
+                Ok(BackplaneConnector {
+                    fan_tx: Some(fan_tx),
+                    ..test_connector(BoardTelemetry { fans, ..named })
+                })
+            }
+            "Reconnecting" => {
+                let call = calls("Reconnecting");
+                if call != 1 && call != 3 {
+                    bail!("no control port (call {call})");
+                }
+                let (channel, firmware) = MockFirmware::pair(ResponseFormat::V1);
+                RECONNECTING_FIRMWARE.lock().unwrap().push(firmware);
+                let (link_lost_tx, link_lost_rx) = oneshot::channel();
+                tokio::spawn(async move {
+                    let mut pin = BitaxeRawGpioController::new(channel.clone())
+                        .pin(0)
+                        .await
+                        .unwrap();
+                    while !channel.link_lost() {
+                        // The firmware never answers; each poll times out.
+                        let _ = pin.read().await;
+                    }
+                    let _ = link_lost_tx.send("control link lost".into());
+                });
+                Ok(BackplaneConnector {
+                    link_lost_rx: Some(link_lost_rx),
+                    ..test_connector(named)
+                })
+            }
+            other => bail!("no test board {other:?}"),
+        }
+    }
+
+    /// A test board reporting `telemetry`. The telemetry sender lives until
+    /// the board is shut down, so a closed receiver shows the teardown ran,
+    /// and the shutdown is recorded in [`SHUTDOWNS`].
+    fn test_connector(telemetry: BoardTelemetry) -> BackplaneConnector {
+        let name = telemetry.name.clone();
+        let (telemetry_tx, telemetry_rx) = watch::channel(telemetry);
+        BackplaneConnector {
+            info: BoardInfo {
+                model: "Mujina Test".into(),
+                firmware_version: None,
+                // Unserialized boards must not collide.
+                serial_number: None,
+            },
+            telemetry_rx,
+            shutdown: Some(Box::new(move |mode| {
+                Box::pin(async move {
+                    SHUTDOWNS.lock().unwrap().push((name, mode));
+                    drop(telemetry_tx);
+                })
+            })),
+            ..connector()
+        }
+    }
+
+    fn hotplug_device(path: &str) -> TransportEvent {
+        usb_device("Hotplug", path)
+    }
+
+    fn usb_device(product: &str, path: &str) -> TransportEvent {
+        TransportEvent::Usb(UsbTransportEvent::UsbDeviceConnected(UsbDeviceInfo {
+            manufacturer: Some("Mujina Test".into()),
+            product: Some(product.into()),
+            device_path: path.into(),
+            ..Default::default()
+        }))
+    }
+
+    fn unplug(path: &str) -> TransportEvent {
+        TransportEvent::Usb(UsbTransportEvent::UsbDeviceDisconnected {
+            device_path: path.into(),
+        })
+    }
+
+    #[tokio::test]
+    async fn boards_attach_and_detach_after_startup() {
+        let (mut backplane, transport_tx, mut board_reg_rx) = backplane(WAIT);
+        tokio::spawn(async move { backplane.run().await });
+        transport_tx
+            .send(TransportEvent::InitialEnumerationComplete)
+            .await
+            .unwrap();
+
+        transport_tx.send(hotplug_device("/usb/1")).await.unwrap();
+        transport_tx.send(hotplug_device("/usb/2")).await.unwrap();
+        let mut first = board_reg_rx.recv().await.unwrap().telemetry_rx;
+        let second = board_reg_rx.recv().await.unwrap().telemetry_rx;
+
+        // Unplugging one board tears down that board only.
+        transport_tx.send(unplug("/usb/1")).await.unwrap();
+        assert!(first.changed().await.is_err(), "first board not shut down");
+        assert!(second.has_changed().is_ok(), "second board shut down too");
+
+        // Unplugging an unknown device touches nothing.
+        transport_tx.send(unplug("/usb/9")).await.unwrap();
+        transport_tx.send(hotplug_device("/usb/3")).await.unwrap();
+        board_reg_rx.recv().await.unwrap();
+        assert!(second.has_changed().is_ok());
+    }
+
+    #[tokio::test]
+    async fn boards_reporting_the_same_name_are_told_apart() {
+        // Kept so the registry sees the boards as connected.
+        let mut senders = Vec::new();
+        let mut named = |name: &str| {
+            let (tx, telemetry_rx) = tokio::sync::watch::channel(BoardTelemetry {
+                name: name.into(),
+                ..Default::default()
+            });
+            senders.push(tx);
+            BackplaneConnector {
+                telemetry_rx,
+                ..connector()
+            }
+        };
+        let restart = || Restart {
+            name: "cloned",
+            create: Box::new(|| Box::pin(async { Ok(connector()) })),
+        };
+        let (mut backplane, _transport_tx, mut board_reg_rx) = backplane(WAIT);
+
+        // Three boards with one cloned serial, and one of their own.
+        let logs = crate::tracing::capture_logs(async {
+            for (path, name) in [
+                ("/usb/1", "bitaxe-0001"),
+                ("/usb/2", "bitaxe-0001"),
+                ("/usb/3", "bitaxe-0002"),
+                ("/usb/4", "bitaxe-0001"),
+            ] {
+                backplane
+                    .start_board(path.into(), named(name), Duration::ZERO, restart())
+                    .await;
+            }
+        })
+        .await;
+        let mut names: Vec<&str> = backplane.boards.values().map(|b| b.name.as_str()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "bitaxe-0001",
+                "bitaxe-0001-2",
+                "bitaxe-0001-3",
+                "bitaxe-0002"
+            ]
+        );
+        assert_eq!(backplane.boards["/usb/2"].name, "bitaxe-0001-2");
+        assert_eq!(logs.matches("DUPLICATE BOARD ID").count(), 2, "{logs}");
+        assert!(logs.contains("renamed=bitaxe-0001-3"), "{logs}");
+
+        // The API lists the renamed boards under their new names.
+        let mut registry = crate::api::BoardRegistry::new();
+        while let Ok(reg) = board_reg_rx.try_recv() {
+            registry.push(reg);
+        }
+        let listed: Vec<String> = registry.boards().into_iter().map(|b| b.name).collect();
+        assert_eq!(
+            listed,
+            [
+                "bitaxe-0001",
+                "bitaxe-0001-2",
+                "bitaxe-0001-3",
+                "bitaxe-0002"
+            ]
+        );
+
+        // Once the original is gone, its name is free for the next.
+        backplane.boards.remove("/usb/1");
+        backplane
+            .start_board(
+                "/usb/5".into(),
+                named("bitaxe-0001"),
+                Duration::ZERO,
+                restart(),
+            )
+            .await;
+        assert_eq!(backplane.boards["/usb/5"].name, "bitaxe-0001");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn init_duration_is_recorded() {
+        // A board that had to be retried, failing at once and coming up a
+        // wait later, counts from its first attempt.
+        for (product, init_duration) in [
+            ("Slow Init", SLOW_INIT),
+            ("Late Init", WAIT.retry_interval + SLOW_INIT),
+        ] {
+            let (mut backplane, transport_tx, mut board_reg_rx) = backplane(WAIT);
+            backplane.init_retry = ONE_ATTEMPT;
+            tokio::spawn(async move { backplane.run().await });
+            transport_tx
+                .send(usb_device(product, "/usb/1"))
+                .await
+                .unwrap();
+            transport_tx
+                .send(TransportEvent::InitialEnumerationComplete)
+                .await
+                .unwrap();
+
+            let registration = board_reg_rx.recv().await.unwrap();
+            assert_eq!(registration.init_duration, Some(init_duration), "{product}");
+        }
+    }
+
+    /// Boards currently in the inrush test board's power-on sequence, and
+    /// the most seen at once.
+    static POWERING_ON: AtomicU32 = AtomicU32::new(0);
+    static MAX_POWERING_ON: AtomicU32 = AtomicU32::new(0);
+
+    #[tokio::test(start_paused = true)]
+    async fn no_more_boards_than_the_init_concurrency_power_on_at_once() {
+        const BOARDS: usize = 5;
+        // One at a time is fully sequential; three at a time takes two
+        // rounds of half a second.
+        for (concurrency, rounds) in [(1, 5), (3, 2)] {
+            MAX_POWERING_ON.store(0, Ordering::SeqCst);
+            let (mut backplane, transport_tx, mut board_reg_rx) = backplane(WAIT);
+            backplane.init_slots = Arc::new(Semaphore::new(concurrency));
+            tokio::spawn(async move { backplane.run().await });
+
+            let start = Instant::now();
+            for i in 0..BOARDS {
+                transport_tx
+                    .send(usb_device("Inrush", &format!("/usb/{i}")))
+                    .await
+                    .unwrap();
+            }
+            for _ in 0..BOARDS {
+                board_reg_rx.recv().await.expect("board registered");
+            }
+
+            assert_eq!(
+                MAX_POWERING_ON.load(Ordering::SeqCst),
+                concurrency as u32,
+                "concurrency {concurrency}"
+            );
+            assert_eq!(start.elapsed(), Duration::from_millis(500) * rounds);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backplane_keeps_answering_while_a_board_comes_up() {
+        let (mut backplane, transport_tx, mut board_reg_rx) = backplane(WAIT);
+        let (board_cmd_tx, board_cmd_rx) = mpsc::channel(1);
+        backplane.board_cmd_rx = Some(board_cmd_rx);
+        tokio::spawn(async move { backplane.run().await });
+
+        transport_tx
+            .send(usb_device("Slow Init", "/usb/1"))
+            .await
+            .unwrap();
+        time::sleep(Duration::from_millis(100)).await;
+
+        // Commands are answered in the middle of its power-on sequence.
+        let start = Instant::now();
+        let (reply, rx) = oneshot::channel();
+        board_cmd_tx
+            .send(BoardCommand::SetFanTarget {
+                board: "slow init".into(),
+                fan: "fan".into(),
+                percent: None,
+                reply,
+            })
+            .await
+            .unwrap();
+        assert!(matches!(rx.await.unwrap(), Err(FanTargetError::NotFound)));
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // Unplugged before it is up, it is dropped, and its init slot goes
+        // to the next board straight away.
+        transport_tx.send(unplug("/usb/1")).await.unwrap();
+        transport_tx.send(hotplug_device("/usb/2")).await.unwrap();
+        let registration = board_reg_rx.recv().await.unwrap();
+        assert_eq!(registration.telemetry_rx.borrow().name, "hotplug");
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        time::sleep(SLOW_INIT).await;
+        assert!(board_reg_rx.try_recv().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tripped_board_is_re_enabled_only_after_cooldown_or_by_force() {
+        let cooldown = Duration::from_secs(300);
+        let (transport_tx, transport_rx) = mpsc::channel(4);
+        let (thread_tx, _) = mpsc::channel(4);
+        let (board_reg_tx, mut board_reg_rx) = mpsc::channel(4);
+        let (board_cmd_tx, board_cmd_rx) = mpsc::channel(4);
+        let mut backplane =
+            Backplane::new(vec![transport_rx], thread_tx, board_reg_tx, board_cmd_rx);
+        backplane.cooldown = CooldownPolicy {
+            period: cooldown,
+            resume_below_c: None,
+        };
+        backplane.watchdog.timeout = None;
+        tokio::spawn(async move { backplane.run().await });
+
+        let enable = |board: &str, force| {
+            let board_cmd_tx = board_cmd_tx.clone();
+            let board = board.to_string();
+            async move {
+                let (reply, rx) = oneshot::channel();
+                board_cmd_tx
+                    .send(BoardCommand::Enable {
+                        board,
+                        force,
+                        reply,
+                    })
+                    .await
+                    .unwrap();
+                rx.await.unwrap()
+            }
+        };
+
+        transport_tx
+            .send(usb_device("Tripping", "/usb/1"))
+            .await
+            .unwrap();
+        transport_tx.send(hotplug_device("/usb/2")).await.unwrap();
+        let mut tripped = board_reg_rx.recv().await.unwrap().telemetry_rx;
+        board_reg_rx.recv().await.unwrap();
+        assert_eq!(calls("Tripping"), 1);
+
+        // Still cooling: refused, and the board is left alone.
+        time::advance(cooldown / 2).await;
+        let refused = enable("tripping", false).await.unwrap_err();
+        assert!(matches!(refused, EnableError::Refused(_)), "{refused:?}");
+        assert!(tripped.has_changed().is_ok());
+        assert_eq!(calls("Tripping"), 1);
+
+        assert!(matches!(
+            enable("missing", false).await,
+            Err(EnableError::NotFound)
+        ));
+        // The hotplug test board never trips.
+        assert!(matches!(
+            enable("hotplug", false).await,
+            Err(EnableError::NotTripped)
+        ));
+
+        // Forcing skips the cooldown: the board is torn down and brought
+        // up again, tripping straight away.
+        enable("tripping", true).await.unwrap();
+        assert!(tripped.changed().await.is_err(), "old board not shut down");
+        board_reg_rx.recv().await.unwrap();
+        assert_eq!(calls("Tripping"), 2);
+        assert!(matches!(
+            enable("tripping", false).await,
+            Err(EnableError::Refused(_))
+        ));
+
+        // Once the cooldown has passed, no force is needed.
+        time::advance(cooldown).await;
+        enable("tripping", false).await.unwrap();
+        board_reg_rx.recv().await.unwrap();
+        assert_eq!(calls("Tripping"), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn self_test_failures_isolate_the_board_only_when_enforced() {
+        let start = |mode| {
+            let (transport_tx, transport_rx) = mpsc::channel(4);
+            let (thread_tx, _) = mpsc::channel(4);
+            let (board_reg_tx, board_reg_rx) = mpsc::channel(4);
+            let (board_cmd_tx, board_cmd_rx) = mpsc::channel(4);
+            let mut backplane =
+                Backplane::new(vec![transport_rx], thread_tx, board_reg_tx, board_cmd_rx);
+            backplane.self_test = mode;
+            backplane.watchdog.timeout = None;
+            tokio::spawn(async move { backplane.run().await });
+            (transport_tx, board_reg_rx, board_cmd_tx)
+        };
+        let enable = |board_cmd_tx: &mpsc::Sender<BoardCommand>, force| {
+            let board_cmd_tx = board_cmd_tx.clone();
+            async move {
+                let (reply, rx) = oneshot::channel();
+                board_cmd_tx
+                    .send(BoardCommand::Enable {
+                        board: "failing".into(),
+                        force,
+                        reply,
+                    })
+                    .await
+                    .unwrap();
+                rx.await.unwrap()
+            }
+        };
+
+        // Warned about, the board mines as usual.
+        let (transport_tx, mut board_reg_rx, _board_cmd_tx) = start(SelfTestMode::Warn);
+        transport_tx
+            .send(usb_device("Failing", "/usb/1"))
+            .await
+            .unwrap();
+        let running = board_reg_rx.recv().await.unwrap();
+        assert_eq!(running.telemetry_rx.borrow().name, "failing");
+        assert!(shutdowns("failing").is_empty());
+
+        // Enforced, it is powered off before it mines and never shows up
+        // in the API, while other boards still start.
+        let (transport_tx, mut board_reg_rx, board_cmd_tx) = start(SelfTestMode::Enforce);
+        transport_tx
+            .send(usb_device("Failing", "/usb/1"))
+            .await
+            .unwrap();
+        transport_tx.send(hotplug_device("/usb/2")).await.unwrap();
+        let started = board_reg_rx.recv().await.unwrap();
+        assert_ne!(started.telemetry_rx.borrow().name, "failing");
+        assert_eq!(shutdowns("failing"), [ShutdownMode::PowerOff]);
+
+        // It is held as shut down, and re-enabling it tests it again.
+        assert!(matches!(
+            enable(&board_cmd_tx, false).await,
+            Err(EnableError::Refused(_))
+        ));
+        enable(&board_cmd_tx, true).await.unwrap();
+        assert_eq!(shutdowns("failing").len(), 2);
+        assert!(board_reg_rx.try_recv().is_err());
+    }
+
+    /// The fan setting of the latest fanned test board.
+    static FANNED_FAN: std::sync::Mutex<Option<watch::Receiver<Option<Percent>>>> =
+        std::sync::Mutex::new(None);
+
+    #[tokio::test]
+    async fn fan_targets_reach_the_named_boards_fan() {
+        let (transport_tx, transport_rx) = mpsc::channel(4);
+        let (thread_tx, _) = mpsc::channel(4);
+        let (board_reg_tx, mut board_reg_rx) = mpsc::channel(4);
+        let (board_cmd_tx, board_cmd_rx) = mpsc::channel(4);
+        let mut backplane =
+            Backplane::new(vec![transport_rx], thread_tx, board_reg_tx, board_cmd_rx);
+        backplane.watchdog.timeout = None;
+        tokio::spawn(async move { backplane.run().await });
+
+        let set = |board: &str, fan: &str, percent| {
+            let board_cmd_tx = board_cmd_tx.clone();
+            let (board, fan) = (board.to_string(), fan.to_string());
+            async move {
+                let (reply, rx) = oneshot::channel();
+                board_cmd_tx
+                    .send(BoardCommand::SetFanTarget {
+                        board,
+                        fan,
+                        percent,
+                        reply,
+                    })
+                    .await
+                    .unwrap();
+                rx.await.unwrap()
+            }
+        };
+
+        transport_tx
+            .send(usb_device("Fanned", "/usb/1"))
+            .await
+            .unwrap();
+        transport_tx.send(hotplug_device("/usb/2")).await.unwrap();
+        board_reg_rx.recv().await.unwrap();
+        board_reg_rx.recv().await.unwrap();
+        let fan = FANNED_FAN.lock().unwrap().clone().unwrap();
+
+        set("fanned", "fan", Some(40)).await.unwrap();
+        assert_eq!(*fan.borrow(), Some(Percent::new_clamped(40)));
+
+        assert_eq!(
+            set("fanned", "fan", Some(140)).await,
+            Err(FanTargetError::OutOfRange(140))
+        );
+        assert_eq!(
+            set("fanned", "pump", Some(40)).await,
+            Err(FanTargetError::NoSuchFan)
+        );
+        assert_eq!(
+            set("missing", "fan", None).await,
+            Err(FanTargetError::NotFound)
+        );
+        // A rejected request leaves the manual duty in place.
+        assert_eq!(*fan.borrow(), Some(Percent::new_clamped(40)));
+
+        set("fanned", "fan", None).await.unwrap();
+        assert_eq!(*fan.borrow(), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn watchdog_escalates_through_the_ladder_then_fails_the_board() {
+        let (transport_tx, transport_rx) = mpsc::channel(4);
+        let (thread_tx, _) = mpsc::channel(4);
+        let (board_reg_tx, mut board_reg_rx) = mpsc::channel(16);
+        let (board_cmd_tx, board_cmd_rx) = mpsc::channel(4);
+        let mut backplane =
+            Backplane::new(vec![transport_rx], thread_tx, board_reg_tx, board_cmd_rx);
+        backplane.watchdog = WatchdogPolicy {
+            timeout: Some(Duration::from_secs(60)),
+            restarts: 2,
+            power_cycles: 1,
+        };
+        tokio::spawn(async move { backplane.run().await });
+
+        transport_tx
+            .send(usb_device("Wedging", "/usb/1"))
+            .await
+            .unwrap();
+        board_reg_rx.recv().await.unwrap();
+
+        // Two restarts, one power cycle, then the board is failed.
+        time::sleep(Duration::from_secs(600)).await;
+        assert_eq!(
+            shutdowns("wedging"),
+            [
+                ShutdownMode::Idle,
+                ShutdownMode::Idle,
+                ShutdownMode::PowerOff,
+                ShutdownMode::PowerOff,
+            ]
+        );
+        assert_eq!(calls("Wedging"), 4);
+
+        // A failed board stays off.
+        time::sleep(Duration::from_secs(600)).await;
+        assert_eq!(calls("Wedging"), 4);
+        assert_eq!(shutdowns("wedging").len(), 4);
+
+        // Re-enabling it starts the ladder over: the next bite restarts.
+        let (reply, rx) = oneshot::channel();
+        board_cmd_tx
+            .send(BoardCommand::Enable {
+                board: "wedging".into(),
+                force: false,
+                reply,
+            })
+            .await
+            .unwrap();
+        rx.await.unwrap().unwrap();
+        assert_eq!(calls("Wedging"), 5);
+        time::sleep(Duration::from_secs(70)).await;
+        assert_eq!(shutdowns("wedging").last(), Some(&ShutdownMode::Idle));
+        assert_eq!(calls("Wedging"), 6);
+    }
+
+    #[test]
+    fn watchdog_ladder_climbs_by_the_configured_counts() {
+        let policy = WatchdogPolicy {
+            timeout: Some(Duration::from_secs(60)),
+            restarts: 1,
+            power_cycles: 2,
+        };
+        let actions: Vec<_> = (1..=5).map(|bite| policy.action(bite)).collect();
+        assert_eq!(
+            actions,
+            [
+                BiteAction::Restart,
+                BiteAction::PowerCycle,
+                BiteAction::PowerCycle,
+                BiteAction::Fail,
+                BiteAction::Fail,
+            ]
+        );
+
+        // No rungs: the first bite fails the board.
+        let fail_fast = WatchdogPolicy {
+            restarts: 0,
+            power_cycles: 0,
+            ..policy
+        };
+        assert_eq!(fail_fast.action(1), BiteAction::Fail);
+    }
+
+    #[test]
+    #[serial]
+    fn watchdog_policy_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_BOARD_WATCHDOG_SECS", "30");
+            env::set_var("MUJINA_BOARD_WATCHDOG_RESTARTS", "3");
+            env::set_var("MUJINA_BOARD_WATCHDOG_POWER_CYCLES", "0");
+        }
+        assert_eq!(
+            WatchdogPolicy::from_env(),
+            WatchdogPolicy {
+                timeout: Some(Duration::from_secs(30)),
+                restarts: 3,
+                power_cycles: 0,
+            }
+        );
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_BOARD_WATCHDOG_SECS", "0");
+            env::set_var("MUJINA_BOARD_WATCHDOG_RESTARTS", "many");
+            env::remove_var("MUJINA_BOARD_WATCHDOG_POWER_CYCLES");
+        }
+        assert_eq!(
+            WatchdogPolicy::from_env(),
+            WatchdogPolicy {
+                timeout: None,
+                ..WatchdogPolicy::default()
+            }
+        );
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_BOARD_WATCHDOG_SECS");
+            env::remove_var("MUJINA_BOARD_WATCHDOG_RESTARTS");
+        }
+    }
+
+    /// Firmware ends of the reconnecting board's control channels. Clearing
+    /// it pulls the cable.
+    static RECONNECTING_FIRMWARE: std::sync::Mutex<Vec<MockFirmware>> =
+        std::sync::Mutex::new(Vec::new());
+
+    #[tokio::test(start_paused = true)]
+    async fn board_that_loses_its_link_is_reconnected_a_bounded_number_of_times() {
+        let (mut backplane, transport_tx, mut board_reg_rx) = backplane(WAIT);
+        backplane.reconnect = ReconnectPolicy {
+            attempts: 3,
+            delay: Duration::from_secs(1),
+        };
+        tokio::spawn(async move { backplane.run().await });
+
+        transport_tx
+            .send(usb_device("Reconnecting", "/usb/1"))
+            .await
+            .unwrap();
+        board_reg_rx.recv().await.unwrap();
+
+        // The cable is jiggled: the port is gone for the first try and
+        // back for the second, and the board registers again to mine.
+        let start = Instant::now();
+        RECONNECTING_FIRMWARE.lock().unwrap().clear();
+        board_reg_rx.recv().await.unwrap();
+        assert_eq!(calls("Reconnecting"), 3);
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+
+        // Pulled for good: every attempt fails and the board is left off.
+        RECONNECTING_FIRMWARE.lock().unwrap().clear();
+        time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(calls("Reconnecting"), 6);
+        assert!(board_reg_rx.try_recv().is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn reconnect_policy_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_BOARD_RECONNECT_ATTEMPTS", "0");
+            env::set_var("MUJINA_BOARD_RECONNECT_MS", "soon");
+        }
+        assert_eq!(
+            ReconnectPolicy::from_env(),
+            ReconnectPolicy {
+                attempts: 0,
+                ..ReconnectPolicy::default()
+            }
+        );
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_BOARD_RECONNECT_ATTEMPTS");
+            env::remove_var("MUJINA_BOARD_RECONNECT_MS");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn retry_policy_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_BOARD_INIT_ATTEMPTS", "5");
+            env::set_var("MUJINA_BOARD_INIT_RETRY_MS", "250");
+        }
+        assert_eq!(
+            InitRetryPolicy::from_env(),
+            InitRetryPolicy {
+                attempts: 5,
+                delay: Duration::from_millis(250),
+            }
+        );
+
+        // Zero attempts would never try at all.
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_BOARD_INIT_ATTEMPTS", "0");
+            env::remove_var("MUJINA_BOARD_INIT_RETRY_MS");
+        }
+        assert_eq!(InitRetryPolicy::from_env(), InitRetryPolicy::default());
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_BOARD_INIT_ATTEMPTS") };
+    }
+
+    #[test]
+    #[serial]
+    fn init_concurrency_from_env() {
+        for (value, expected) in [
+            (None, DEFAULT_INIT_CONCURRENCY),
+            (Some("4"), 4),
+            // No slots would never bring a board up.
+            (Some("0"), DEFAULT_INIT_CONCURRENCY),
+            (Some("many"), DEFAULT_INIT_CONCURRENCY),
+        ] {
+            // SAFETY: Test runs serially, no concurrent env access
+            unsafe {
+                match value {
+                    Some(v) => env::set_var("MUJINA_BOARD_INIT_CONCURRENCY", v),
+                    None => env::remove_var("MUJINA_BOARD_INIT_CONCURRENCY"),
+                }
+            }
+            assert_eq!(super::init_concurrency_from_env(), expected, "{value:?}");
+        }
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_BOARD_INIT_CONCURRENCY") };
+    }
+
+    #[tokio::test]
+    async fn each_stop_signal_runs_its_shutdown_profile() {
+        let profiles = ShutdownProfiles {
+            sigint: ShutdownMode::Idle,
+            sigterm: ShutdownMode::PowerOff,
+        };
+        for (signal, expected) in [
+            (StopSignal::Terminate, ShutdownMode::PowerOff),
+            (StopSignal::Interrupt, ShutdownMode::Idle),
+        ] {
+            let (mut backplane, _transport_tx, _board_reg_rx) = backplane(WAIT);
+            let (mode_tx, mut mode_rx) = oneshot::channel();
+            let mut conn = connector();
+            conn.shutdown = Some(Box::new(move |mode| {
+                Box::pin(async move {
+                    let _ = mode_tx.send(mode);
+                })
+            }));
+            let restart = Restart {
+                name: "flaky",
+                create: Box::new(|| Box::pin(async { Ok(connector()) })),
+            };
+            backplane
+                .start_board("/usb/1".into(), conn, Duration::ZERO, restart)
+                .await;
+
+            backplane
+                .shutdown_all_boards(profiles.for_signal(signal))
+                .await;
+            assert_eq!(mode_rx.try_recv(), Ok(expected), "{signal:?}");
+            assert!(backplane.boards.is_empty());
         }
     }
 }
@@ -4,10 +4,16 @@
 //! daemon via the HTTP API.
 
 use std::env;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use time::OffsetDateTime;
 
 use mujina_miner::api_client;
+use mujina_miner::api_client::bundle::{self, Sections};
+use mujina_miner::api_client::summary::fleet_summary;
+use mujina_miner::api_client::types::{LogFilter, SchedulerState, SetLogFilterRequest};
+use mujina_miner::types::{Difficulty, HumanDuration, NumberGrouping};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -19,6 +25,9 @@ async fn main() -> Result<()> {
         eprintln!("Commands:");
         eprintln!("  status          Show miner status");
         eprintln!("  api <endpoint>  Raw API call (e.g. \"api miner\")");
+        eprintln!("  bundle [file]   Write a redacted support bundle for bug reports");
+        eprintln!("  retarget        Recompute share targets now");
+        eprintln!("  log [filter]    Show the log filter, or set it as MUJINA_LOG takes it");
         eprintln!();
         eprintln!("Environment:");
         eprintln!("  MUJINA_API_URL    API base URL (default: http://127.0.0.1:7785)");
@@ -33,6 +42,9 @@ async fn main() -> Result<()> {
             let endpoint = args.get(2).map_or("", String::as_str);
             cmd_api(endpoint).await?;
         }
+        "bundle" => cmd_bundle(args.get(2).map(String::as_str)).await?,
+        "retarget" => cmd_retarget().await?,
+        "log" => cmd_log(args.get(2).map(String::as_str)).await?,
         _ => {
             eprintln!("Unknown command: {}", command);
             eprintln!("Run without arguments to see usage.");
@@ -65,14 +77,91 @@ async fn cmd_api(endpoint: &str) -> Result<()> {
     Ok(())
 }
 
+/// Write a support bundle to `path`, by default a file named for the
+/// time in the current directory.
+async fn cmd_bundle(path: Option<&str>) -> Result<()> {
+    let client = make_client();
+    let now = OffsetDateTime::now_utc();
+    let bundle = bundle::assemble(now, env::vars(), Sections::fetch(&client).await);
+    let path = path.map_or_else(
+        || format!("mujina-bundle-{}.json", now.unix_timestamp()),
+        str::to_string,
+    );
+    std::fs::write(&path, serde_json::to_string_pretty(&bundle)?)
+        .with_context(|| format!("failed to write {path}"))?;
+    println!("Support bundle written to {path}");
+    println!("Secrets are redacted, but look it over before sharing.");
+    Ok(())
+}
+
+/// Recompute share targets and print each thread's new difficulty.
+async fn cmd_retarget() -> Result<()> {
+    let client = make_client();
+    let state: SchedulerState = client.post_json("scheduler/retarget").await?;
+    for thread in &state.threads {
+        let difficulty = thread
+            .share_difficulty
+            .and_then(Difficulty::from_share_difficulty)
+            .map_or_else(|| "none".to_string(), |d| d.to_string());
+        println!("{}: {difficulty}", thread.name);
+    }
+    Ok(())
+}
+
+/// Print the daemon's log filter, after setting it to `filter` if given.
+async fn cmd_log(filter: Option<&str>) -> Result<()> {
+    let client = make_client();
+    let current: LogFilter = match filter {
+        Some(filter) => {
+            let request = SetLogFilterRequest {
+                filter: filter.to_string(),
+            };
+            client.put_json("log", &request).await?
+        }
+        None => client.get_json("log").await?,
+    };
+    println!("{}", current.filter);
+    Ok(())
+}
+
 /// Print a summary of the current miner state.
 async fn cmd_status() -> Result<()> {
     let client = make_client();
     let state = client.get_miner().await?;
-
-    println!("Uptime:  {} s", state.uptime_secs);
-    println!("Hashrate: {} H/s", state.hashrate);
-    println!("Shares:  {}", state.shares_submitted);
+    let n = |value| NumberGrouping::configured().display(value);
+
+    println!("Uptime:  {} s", n(state.uptime_secs));
+    println!("Hashrate: {} H/s", n(state.hashrate));
+    println!("Shares:  {}", n(state.shares_submitted));
+    let unsubmitted = &state.unsubmitted_shares;
+    if *unsubmitted != Default::default() {
+        println!(
+            "Not submitted: {} stale, {} below target, {} duplicate, \
+             {} hardware error, {} queue dropped",
+            n(unsubmitted.stale),
+            n(unsubmitted.below_target),
+            n(unsubmitted.duplicate),
+            n(unsubmitted.hardware_error),
+            n(unsubmitted.queue_dropped)
+        );
+    }
+    if let Some(best) = state.best_share_difficulty {
+        let best = Difficulty::from_share_difficulty(best)
+            .map_or_else(|| "?".to_string(), |d| d.to_string());
+        println!("Best share: {best}");
+    }
+    if state.blocks_found > 0 {
+        println!("Blocks found: {}", n(state.blocks_found));
+    }
+    if state.network_difficulty.is_some() {
+        match state.expected_time_to_block_secs {
+            Some(secs) => {
+                let mean = Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX);
+                println!("Expected time to block: {}", HumanDuration(mean));
+            }
+            None => println!("Expected time to block: - (not hashing)"),
+        }
+    }
 
     if state.sources.is_empty() {
         println!("Sources: (none)");
@@ -84,9 +173,13 @@ async fn cmd_status() -> Result<()> {
     }
 
     if !state.boards.is_empty() {
+        println!("Fleet:   {}", fleet_summary(&state));
         println!("Boards:");
         for board in &state.boards {
-            println!("  - {}", board.model);
+            match &board.firmware_version {
+                Some(firmware) => println!("  - {} ({firmware})", board.model),
+                None => println!("  - {}", board.model),
+            }
         }
     }
 
@@ -2,10 +2,12 @@
 
 use clap::Command;
 
-use mujina_miner::{daemon::Daemon, env_help, tracing};
+use mujina_miner::{
+    daemon::{Daemon, RuntimeConfig},
+    env_help, tracing,
+};
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
     // The daemon takes no positional arguments; clap is here to handle
     // --help (documenting the control environment variables) and --version.
     Command::new("mujina-minerd")
@@ -16,6 +18,9 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::init();
 
-    let daemon = Daemon::new();
-    daemon.run().await
+    // Built by hand rather than with #[tokio::main] so the worker count can
+    // be sized for small hosts.
+    let runtime = RuntimeConfig::from_env().build()?;
+    runtime.block_on(Daemon::new().run())?;
+    Ok(())
 }
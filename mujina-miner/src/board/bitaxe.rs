@@ -3,39 +3,40 @@ use async_trait::async_trait;
 use futures::sink::SinkExt;
 use std::{
     pin::Pin,
-    sync::{Arc, Mutex as StdMutex},
+    sync::{Arc, Mutex as StdMutex, RwLock},
     task::{Context, Poll},
     time::Duration,
 };
 use tokio::{
     io::{AsyncRead, ReadBuf},
-    sync::{Mutex, watch},
-    time::{self, Instant, MissedTickBehavior},
+    sync::{Mutex, oneshot, watch},
+    time::{self, Instant},
 };
-use tokio_serial::SerialPortBuilderExt;
 use tokio_stream::StreamExt;
-use tokio_util::{
-    codec::{FramedRead, FramedWrite},
-    sync::CancellationToken,
-};
+use tokio_util::codec::{FramedRead, FramedWrite};
+use tracing::Instrument;
 
 use crate::{
-    api_client::types::{BoardTelemetry, Fan, PowerMeasurement, TemperatureSensor},
+    api_client::types::{BoardTelemetry, Fan, PowerMeasurement},
     asic::{
         ChipInfo,
         bm13xx::{self, BM13xxProtocol, protocol::Command, thread::BM13xxThread},
-        hash_thread::{AsicEnable, BoardPeripherals, HashThread, ThreadRemovalSignal},
+        hash_thread::{
+            AsicEnable, BoardPeripherals, HashThread, HashThreadStatus, ThreadRemovalSignal,
+            VoltageRegulator,
+        },
     },
+    clock,
     hw_trait::{
-        gpio::{Gpio, GpioPin, PinValue},
-        i2c::I2c,
+        gpio::{Gpio, GpioPin, PanicSafeState, PinValue},
+        i2c::{I2c, I2cSpeed, SpeedFallbackI2c, speed_fallback_from_env},
     },
     mgmt_protocol::{
-        ControlChannel,
+        ControlChannel, ControlTransport,
         bitaxe_raw::{
-            ResponseFormat,
+            DeviceVersion,
             gpio::{BitaxeRawGpioController, BitaxeRawGpioPin},
-            i2c::BitaxeRawI2c,
+            i2c::{self as raw_i2c, BitaxeRawI2c},
         },
     },
     peripheral::{
@@ -47,14 +48,35 @@ use crate::{
         UsbDeviceInfo,
         serial::{SerialReader, SerialStream, SerialWriter},
     },
-    types::Temperature,
+    types::{Temperature, TemperatureUnit},
 };
 
 use super::{
-    BackplaneConnector, BoardInfo,
+    BackplaneConnector, BoardInfo, BoardShutdown, ShutdownMode,
+    brownout::BrownoutGuard,
+    cooldown::Trip,
+    fan_stall::{self, StallDetector},
     pattern::{Match, StringMatch},
+    plausibility::{ImplausibleAction, RegulatorChecks, RegulatorReadings},
+    poll::{self, Due, PollSchedule},
+    power_clamp::PowerClamp,
+    profile::{self, OperatingConditions, Profile, ProfileSelection},
+    quiet_hours::{QuietHours, QuietSchedule},
+    self_test::SelfTestFailure,
+    thermal::{
+        self, BoardTemps, FanControl, FanFloor, TargetTemps, ThermalSource, ThermalThrottle,
+    },
+    thread_telemetry,
+    warmup::ProfileWarmup,
 };
 
+/// The board's I2C bus: bitaxe-raw passthrough, falling back to standard
+/// speed on failure when `MUJINA_I2C_SPEED_FALLBACK` is set.
+type BoardI2c = SpeedFallbackI2c<BitaxeRawI2c>;
+
+/// Routine sensor polling interval when not configured.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 // Register this board type with the inventory system
 inventory::submit! {
     crate::board::BoardDescriptor {
@@ -67,7 +89,10 @@ inventory::submit! {
             serial_pattern: Match::Any,
         },
         name: "Bitaxe Gamma",
-        create_fn: |device| Box::pin(create_from_usb(device)),
+        create_fn: |device| {
+            let span = super::span(&super::usb_board_name("bitaxe", &device), "Bitaxe Gamma");
+            Box::pin(create_from_usb(device).instrument(span))
+        },
     }
 }
 
@@ -83,9 +108,14 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
     );
 
     // Open control port, create management channel and I2C bus
-    let control_port = tokio_serial::new(&serial_ports[0], 115200).open_native_async()?;
-    let control_channel = ControlChannel::new(control_port, ResponseFormat::V0);
-    let mut i2c = BitaxeRawI2c::new(control_channel.clone());
+    let version = DeviceVersion::from_bcd(device.bcd_device);
+    let control_channel = ControlTransport::serial(&serial_ports[0])
+        .connect(&version)
+        .await?;
+    let mut i2c = BoardI2c::new(
+        BitaxeRawI2c::new(control_channel.clone()).with_timeout(raw_i2c::timeout_from_env()),
+        speed_fallback_from_env(),
+    );
 
     // Open data port for chip communication
     let data_stream =
@@ -97,6 +127,7 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
 
     // Get reset pin
     const ASIC_RESET_PIN: u8 = 0;
+    let control = control_channel.clone();
     let mut gpio_controller = BitaxeRawGpioController::new(control_channel);
     let mut reset_pin = gpio_controller.pin(ASIC_RESET_PIN).await?;
 
@@ -104,13 +135,54 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
     reset_pin.write(PinValue::Low).await?;
 
     // Initialize peripherals
-    i2c.set_frequency(100_000).await?;
-
-    let emc2101 = init_fan_controller(i2c.clone()).await?;
-    let regulator = Arc::new(Mutex::new(init_power_controller(i2c.clone()).await?));
+    let i2c_speed = I2cSpeed::from_env().unwrap_or_default();
+    i2c.set_frequency(i2c_speed.hz()).await?;
+
+    let profiles = profile::for_model("Bitaxe Gamma").expect("Bitaxe Gamma has profiles");
+    let startup_profile = Profile::from_env();
+    debug!(profile = %startup_profile, "Operating profile selected");
+    let warmup_period = ProfileWarmup::period_from_env();
+    let (profile_tx, profile_selection) = ProfileSelection::channel(
+        profiles,
+        ProfileWarmup::initial(startup_profile, warmup_period),
+    );
+    let warmup = ProfileWarmup::start(Instant::now(), startup_profile, warmup_period, &profile_tx);
+    let brownout = BrownoutGuard::new(
+        BrownoutGuard::threshold_from_env(),
+        profile_selection.clock_scale(),
+    );
+    let max_power_w = PowerClamp::max_from_env();
+    let power_clamp = PowerClamp::new(max_power_w, profile_selection.power_scale());
+    let target_c = TargetTemps::from_env().for_board(&super::usb_board_name("bitaxe", &device));
+    debug!(?target_c, "Target temperature selected");
+    let throttle = ThermalThrottle::new(target_c, profile_selection.thermal_scale());
+    let conditions = profile_selection.conditions();
+    conditions.send_modify(|c| (c.max_power_w, c.target_c) = (max_power_w, target_c));
+    let profile_guard = profile_selection.guard();
+    let (fan_tx, mut fan) = FanControl::channel(target_c);
+    fan.set_floor(FanFloor::from_env());
+
+    let mut emc2101 = init_fan_controller(i2c.clone()).await?;
+    let regulator = Arc::new(Mutex::new(
+        init_power_controller(i2c.clone(), profile_selection.current().core_voltage_v).await?,
+    ));
 
     time::sleep(Duration::from_millis(500)).await;
 
+    // With the chip still in reset, its diode reads clean.
+    let asic_absent = !has_asic_diode(&mut emc2101).await;
+    if asic_absent {
+        warn!(
+            fan_percent = u8::from(thermal::SAFE_FAN_DUTY),
+            "No ASIC temperature sensor, fan control and thermal throttle disabled"
+        );
+        fan.fix(thermal::SAFE_FAN_DUTY);
+        emc2101
+            .set_fan_speed(thermal::SAFE_FAN_DUTY)
+            .await
+            .context("failed to set safe fan speed")?;
+    }
+
     // Release ASIC from reset for discovery
     debug!("De-asserting ASIC nRST");
     reset_pin.write(PinValue::High).await?;
@@ -158,6 +230,14 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
     // Put chip back in reset before handing off to hash thread
     reset_pin.write(PinValue::Low).await?;
 
+    let self_test = self_test(
+        &mut emc2101,
+        &regulator,
+        chip_infos.len(),
+        profile_selection.current().core_voltage_v,
+    )
+    .await;
+
     // Create hash thread
     let (thread_shutdown_tx, thread_shutdown_rx) = watch::channel(ThreadRemovalSignal::Running);
 
@@ -166,6 +246,7 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
         None => "Bitaxe-Gamma".to_string(),
     };
 
+    let reset_guard = PanicSafeState::new(reset_pin.clone(), PinValue::Low, "asic-nrst");
     let asic_enable = BitaxeAsicEnable {
         nrst_pin: reset_pin,
         enabled_since: Arc::new(StdMutex::new(None)),
@@ -173,62 +254,103 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
     let asic_enable_monitor = asic_enable.clone();
     let peripherals = BoardPeripherals {
         asic_enable: Some(Box::new(asic_enable)),
-        voltage_regulator: None,
+        voltage_regulator: Some(Box::new(BitaxeCoreVoltage(regulator.clone()))),
     };
 
     let thread = BM13xxThread::new(
-        thread_name,
+        thread_name.clone(),
         data_reader,
         data_writer,
         peripherals,
         thread_shutdown_rx,
+        profile_selection,
     );
+    let thread_status = thread.status_handle();
     let threads: Vec<Box<dyn HashThread>> = vec![Box::new(thread)];
 
     debug!("Bitaxe board initialized with {} chips", chip_infos.len());
 
     // Telemetry channel seeded with board identity
     let serial = device.serial_number.clone();
-    let board_name = format!("bitaxe-{}", serial.as_deref().unwrap_or("unknown"));
+    let board_name = super::usb_board_name("bitaxe", &device);
+    let firmware = format!("bitaxe-raw {version}");
     let initial_state = BoardTelemetry {
         name: board_name.clone(),
         model: "Bitaxe Gamma".into(),
         serial: serial.clone(),
+        firmware_version: Some(firmware.clone()),
         ..Default::default()
     };
     let (telemetry_tx, telemetry_rx) = watch::channel(initial_state);
 
     let info = BoardInfo {
         model: "Bitaxe Gamma".to_string(),
-        firmware_version: Some("bitaxe-raw".to_string()),
+        firmware_version: Some(firmware.clone()),
         serial_number: device.serial_number.clone(),
     };
 
     // Assemble internal state and spawn the board monitor
+    let (trip_tx, trip_rx) = oneshot::channel();
+    let (link_lost_tx, link_lost_rx) = oneshot::channel();
     let bitaxe = Bitaxe {
+        control,
+        link_lost_tx: Some(link_lost_tx),
         emc2101,
         regulator,
         thread_shutdown: thread_shutdown_tx,
         board_name,
         board_model: "Bitaxe Gamma",
         board_serial: serial,
+        board_firmware: firmware,
         bad_thermal_count: 0,
+        temps: BoardTemps {
+            asic_absent,
+            ..Default::default()
+        },
+        thermal_source: ThermalSource::from_env(),
+        fan,
+        fan_speed: if asic_absent {
+            thermal::SAFE_FAN_DUTY
+        } else {
+            Percent::FULL
+        },
+        throttle,
         asic_enable: asic_enable_monitor,
+        thread_name,
+        thread_status,
+        _reset_guard: reset_guard,
+        trip_tx: Some(trip_tx),
+        fault: None,
+        brownout,
+        power_clamp,
+        fan_stall: StallDetector::new(),
+        plausibility: RegulatorChecks::new(ImplausibleAction::from_env()),
+        quiet_hours: QuietHours::from_env().map(|hours| QuietSchedule::new(hours, clock::system())),
+        warmup,
+        conditions,
     };
 
-    let cancel = CancellationToken::new();
-    let monitor_handle = tokio::spawn(bitaxe.run_monitor(telemetry_tx, cancel.clone()));
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let monitor_handle = tokio::spawn(bitaxe.run_monitor(telemetry_tx, stop_rx).in_current_span());
 
-    let shutdown = Box::pin(async move {
-        cancel.cancel();
-        let _ = monitor_handle.await;
+    let shutdown: BoardShutdown = Box::new(move |mode| {
+        Box::pin(async move {
+            let _ = stop_tx.send(mode);
+            let _ = monitor_handle.await;
+        })
     });
 
     Ok(BackplaneConnector {
         info,
         threads,
         telemetry_rx,
+        profile_tx: Some(profile_tx),
+        profile_guard: Some(profile_guard),
+        fan_tx: Some(fan_tx),
+        self_test: Some(self_test),
         shutdown: Some(shutdown),
+        trip_rx: Some(trip_rx),
+        link_lost_rx: Some(link_lost_rx),
     })
 }
 
@@ -236,41 +358,118 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
 ///
 /// The factory assembles this and moves it into `run_monitor()`.
 struct Bitaxe {
-    emc2101: Emc2101<BitaxeRawI2c>,
-    regulator: Arc<Mutex<Tps546<BitaxeRawI2c>>>,
+    /// The control channel every peripheral is reached through, watched
+    /// for the link dropping.
+    control: ControlChannel,
+    /// Tells the backplane the control link dropped.
+    link_lost_tx: Option<oneshot::Sender<String>>,
+    emc2101: Emc2101<BoardI2c>,
+    regulator: Arc<Mutex<Tps546<BoardI2c>>>,
     thread_shutdown: watch::Sender<ThreadRemovalSignal>,
     board_name: String,
     board_model: &'static str,
     board_serial: Option<String>,
+    board_firmware: String,
     /// Consecutive bad thermal readings (I2C error, out-of-range, or
     /// above emergency threshold). Triggers emergency shutdown.
     bad_thermal_count: u32,
+    /// The watchdog's latest usable ASIC temperature and the regulator's
+    /// from the routine poll.
+    temps: BoardTemps,
+    /// Which of them fan control and the throttle act on.
+    thermal_source: ThermalSource,
+    /// Picks the fan speed, from the curve or as set through the API.
+    fan: FanControl,
+    /// Fan speed last set, so only changes are written.
+    fan_speed: Percent,
+    /// Holds the clock down while the fan can't keep up.
+    throttle: ThermalThrottle,
     asic_enable: BitaxeAsicEnable,
+    thread_name: String,
+    /// Status shared with the hash thread, reported in telemetry.
+    thread_status: Arc<RwLock<HashThreadStatus>>,
+    /// Holds the chip in reset if the monitor panics before `shutdown()`.
+    _reset_guard: PanicSafeState<BitaxeRawGpioPin>,
+    /// Tells the backplane the board shut itself down.
+    trip_tx: Option<oneshot::Sender<Trip>>,
+    /// Why the board shut itself down. Once set, the monitor keeps
+    /// reporting sensors with the chips dark until the board is
+    /// re-created.
+    fault: Option<String>,
+    /// Holds the clock down while the input supply sags.
+    brownout: BrownoutGuard,
+    /// Holds the clock down while the board draws more than its cap.
+    power_clamp: PowerClamp,
+    /// Shuts the board down if the fan stops while driven.
+    fan_stall: StallDetector,
+    /// Rejects regulator readings that can't be true.
+    plausibility: RegulatorChecks,
+    /// When the fan is capped and the clock lowered for quiet. `None`
+    /// without quiet hours.
+    quiet_hours: Option<QuietSchedule>,
+    /// The startup profile waiting out the warm-up, if any.
+    warmup: Option<ProfileWarmup>,
+    /// Readings a new operating point is checked against.
+    conditions: watch::Sender<OperatingConditions>,
 }
 
 impl Bitaxe {
     async fn run_monitor(
         mut self,
         telemetry_tx: watch::Sender<BoardTelemetry>,
-        cancel: CancellationToken,
+        mut stop_rx: oneshot::Receiver<ShutdownMode>,
     ) {
-        let mut tick = time::interval(Duration::from_secs(2));
-        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut schedule = PollSchedule::new(poll::interval_from_env(POLL_INTERVAL));
         let mut last_log = Instant::now();
 
         loop {
             tokio::select! {
-                _ = tick.tick() => {
-                    if let Err(e) = self.monitor_tick(&telemetry_tx, &mut last_log).await {
-                        error!(error = %e, "Board monitor failed");
-                        self.shutdown().await;
-                        return;
+                due = schedule.next() => {
+                    // Nothing can be read without the link; the backplane
+                    // re-creates the board once it hears.
+                    if self.link_lost_tx.is_none() {
+                        continue;
+                    }
+                    let result = match due {
+                        Due::Watchdog => self.watch_temperature().await,
+                        Due::Routine => {
+                            self.poll_sensors(&telemetry_tx, &mut last_log).await;
+                            Ok(())
+                        }
+                    };
+                    // Failed reads over a dropped link say nothing about
+                    // the board's temperature.
+                    if self.control.link_lost() {
+                        self.report_link_lost().await;
+                        continue;
+                    }
+                    match result {
+                        Err(e) if self.fault.is_none() => {
+                            error!(error = %e, "Board monitor failed");
+                            self.shutdown().await;
+                            self.trip(e.to_string());
+                        }
+                        Err(e) => debug!(error = %e, "Monitoring tripped board"),
+                        Ok(()) => {}
                     }
                 }
-                _ = cancel.cancelled() => {
-                    self.shutdown().await;
-                    if let Err(e) = self.emc2101.set_fan_speed(Percent::new_clamped(25)).await {
-                        warn!("Failed to reduce fan speed: {}", e);
+                mode = &mut stop_rx => {
+                    // A tripped board is already dark, and may still be
+                    // hot, so its fan stays up. So does an idled one's.
+                    // A board without its link can't be told anything.
+                    if self.fault.is_none() && !self.control.link_lost() {
+                        match mode.unwrap_or_default() {
+                            ShutdownMode::PowerOff => {
+                                self.shutdown().await;
+                                if let Err(e) = self.emc2101.set_fan_speed(Percent::new_clamped(25)).await {
+                                    warn!("Failed to reduce fan speed: {}", e);
+                                }
+                            }
+                            ShutdownMode::Idle => {
+                                self.stop_threads().await;
+                                info!("Chips left powered and idle");
+                            }
+                        }
                     }
                     return;
                 }
@@ -278,10 +477,29 @@ impl Bitaxe {
         }
     }
 
-    /// Run one monitoring cycle. Returns `Err` on thermal emergency.
-    ///
-    /// Reads all sensors, classifies the temperature reading, publishes
-    /// telemetry, and logs a periodic summary.
+    /// Record that the board shut itself down for `reason`.
+    fn trip(&mut self, reason: String) {
+        if let Some(tx) = self.trip_tx.take() {
+            let _ = tx.send(Trip {
+                at: Instant::now(),
+                reason: reason.clone(),
+            });
+        }
+        self.fault = Some(reason);
+    }
+
+    /// Stop hashing and tell the backplane the control link dropped.
+    async fn report_link_lost(&mut self) {
+        let Some(tx) = self.link_lost_tx.take() else {
+            return;
+        };
+        error!("Control link to board lost, stopping");
+        self.stop_threads().await;
+        let _ = tx.send("control link lost".into());
+    }
+
+    /// Read and classify the ASIC temperature, the thermal watchdog's
+    /// poll. Returns `Err` on thermal emergency.
     ///
     /// Temperature readings fall into four categories:
     /// - I2C errors or diode faults: no temperature available.
@@ -293,38 +511,11 @@ impl Bitaxe {
     /// Any category except normal increments a consecutive
     /// bad-reading counter. After BAD_READING_LIMIT consecutive
     /// bad readings, the board shuts down.
-    async fn monitor_tick(
-        &mut self,
-        tx: &watch::Sender<BoardTelemetry>,
-        last_log: &mut Instant,
-    ) -> Result<()> {
-        // Read all sensors in one pass
+    async fn watch_temperature(&mut self) -> Result<()> {
         let raw_temp = self.emc2101.get_external_temperature().await;
-        let fan_percent = self.emc2101.get_fan_speed().await.ok().map(u8::from);
-        let fan_rpm = self.emc2101.get_rpm().await.ok();
-
-        let (vin_mv, vout_mv, iout_ma, power_mw, vr_temp) = {
-            let mut reg = self.regulator.lock().await;
-
-            if let Err(e) = reg.check_status().await {
-                error!("Power controller fault: {}", e);
-                if let Err(e) = reg.clear_faults().await {
-                    error!("Failed to clear faults: {}", e);
-                }
-            }
-
-            (
-                reg.get_vin().await.ok(),
-                reg.get_vout().await.ok(),
-                reg.get_iout().await.ok(),
-                reg.get_power().await.ok(),
-                reg.get_temperature().await.ok(),
-            )
-        };
 
         const EXPECTED_MIN_C: f32 = 0.0;
         const EXPECTED_MAX_C: f32 = 120.0;
-        const EMERGENCY_TEMP_C: f32 = 80.0;
         const BAD_READING_LIMIT: u32 = 3;
         // The EMC2101 measures temperature via a diode on the ASIC
         // die. When the ASIC comes out of reset, the resulting
@@ -336,17 +527,19 @@ impl Bitaxe {
             .enabled_since()
             .context("failed to read ASIC enable state")?
             .is_some_and(|since| since.elapsed() >= DIODE_SETTLE);
-        let asic_temp = if diode_ready {
+        let asic_temp = if self.temps.asic_absent {
+            None
+        } else if diode_ready {
             match raw_temp {
                 Ok(t) if !(EXPECTED_MIN_C..=EXPECTED_MAX_C).contains(&t) => {
                     self.bad_thermal_count += 1;
                     trace!(temp_c = t, "Discarding out-of-range temperature reading");
                     None
                 }
-                Ok(t) if t >= EMERGENCY_TEMP_C => {
+                Ok(t) if t >= thermal::CRITICAL_TEMP_C => {
                     self.bad_thermal_count += 1;
                     warn!(
-                        temp_c = t,
+                        temp = %Temperature::from_celsius(t).display(TemperatureUnit::configured()),
                         consecutive = self.bad_thermal_count,
                         "Temperature above emergency threshold"
                     );
@@ -366,6 +559,19 @@ impl Bitaxe {
             self.bad_thermal_count = 0;
             None
         };
+        self.temps.asic_c = asic_temp;
+        let control_temp = self.temps.for_control(self.thermal_source);
+        self.update_quiet();
+        if self
+            .warmup
+            .as_mut()
+            .is_some_and(|warmup| warmup.poll(Instant::now()))
+        {
+            self.warmup = None;
+        }
+        self.conditions.send_modify(|c| c.temp_c = control_temp);
+        self.throttle.observe(control_temp);
+        self.update_fan(control_temp).await;
 
         // Without reliable temperature readings we cannot operate
         // safely. Shut down the board.
@@ -377,78 +583,186 @@ impl Bitaxe {
             if let Err(e) = self.emc2101.set_fan_speed(Percent::FULL).await {
                 error!("Failed to set fan speed: {}", e);
             }
+            self.fan_speed = Percent::FULL;
             bail!(
                 "thermal emergency after {} consecutive bad readings",
                 self.bad_thermal_count
             );
         }
 
+        Ok(())
+    }
+
+    /// Start or end quiet hours as the clock says.
+    fn update_quiet(&mut self) {
+        let Some(schedule) = &mut self.quiet_hours else {
+            return;
+        };
+        let Some(quiet) = schedule.check() else {
+            return;
+        };
+        let quiet_hours = *schedule.hours();
+        if quiet {
+            info!(
+                max_fan_percent = u8::from(quiet_hours.max_fan),
+                clock_percent = (quiet_hours.clock * 100.0).round(),
+                "Quiet hours started"
+            );
+            self.fan.set_quiet(Some(quiet_hours.max_fan));
+            self.throttle.set_quiet(Some(quiet_hours.clock));
+        } else {
+            info!("Quiet hours ended");
+            self.fan.set_quiet(None);
+            self.throttle.set_quiet(None);
+        }
+    }
+
+    /// Set the fan for a temperature reading, if its control calls for a
+    /// change.
+    async fn update_fan(&mut self, temp_c: Option<f32>) {
+        let Some(speed) = self.fan.speed(temp_c).filter(|&s| s != self.fan_speed) else {
+            return;
+        };
+        match self.emc2101.set_fan_speed(speed).await {
+            Ok(()) => {
+                if speed == Percent::FULL && self.fan.manual().is_some_and(|manual| manual != speed)
+                {
+                    warn!(
+                        temp_c,
+                        "Board too hot for the manual fan duty, running fan at full speed"
+                    );
+                } else {
+                    trace!(temp_c, fan_percent = u8::from(speed), "Fan speed adjusted");
+                }
+                self.fan_speed = speed;
+            }
+            Err(e) => warn!("Failed to set fan speed: {}", e),
+        }
+    }
+
+    /// Read the fan and regulator, publish telemetry with the watchdog's
+    /// latest temperature, and log a periodic summary.
+    async fn poll_sensors(&mut self, tx: &watch::Sender<BoardTelemetry>, last_log: &mut Instant) {
+        let fan_duty = self.emc2101.get_fan_speed().await.ok();
+        let fan_percent = fan_duty.map(u8::from);
+        let fan_rpm = self.emc2101.get_rpm().await.ok();
+
+        let raw = {
+            let mut reg = self.regulator.lock().await;
+
+            if let Err(e) = reg.check_status().await {
+                error!("Power controller fault: {}", e);
+                if let Err(e) = reg.clear_faults().await {
+                    error!("Failed to clear faults: {}", e);
+                }
+            }
+
+            let milli = |m: u32| m as f32 / 1000.0;
+            RegulatorReadings {
+                input_v: reg.get_vin().await.ok().map(milli),
+                core_v: reg.get_vout().await.ok().map(milli),
+                core_a: reg.get_iout().await.ok().map(milli),
+                core_w: reg.get_power().await.ok().map(milli),
+                temp_c: reg.get_temperature().await.ok().map(|t| t as f32),
+            }
+        };
+        let readings = self.plausibility.check(raw);
+
+        self.brownout.observe(readings.input_v);
+        self.power_clamp.observe(readings.core_w);
+        self.conditions.send_modify(|c| c.power_w = readings.core_w);
+        if let Some(stall) = self.fan_stall.observe(fan_duty, fan_rpm)
+            && self.fault.is_none()
+        {
+            error!(
+                rpm = stall.rpm,
+                duty = u8::from(stall.duty),
+                "FAN FAILURE: shutting down board"
+            );
+            self.shutdown().await;
+            self.trip(stall.to_string());
+        }
+        if let Some(broken) = self.plausibility.fault()
+            && self.fault.is_none()
+        {
+            error!(
+                channel = broken.bounds.channel,
+                value = broken.value,
+                polls = broken.polls,
+                "IMPLAUSIBLE SENSOR READINGS: shutting down board"
+            );
+            self.shutdown().await;
+            self.trip(broken.to_string());
+        }
+        self.temps.vr_c = readings.temp_c;
+        let asic_temp = self.temps.asic_c;
+
         // Publish telemetry
         let _ = tx.send(BoardTelemetry {
             name: self.board_name.clone(),
             model: self.board_model.into(),
             serial: self.board_serial.clone(),
+            firmware_version: Some(self.board_firmware.clone()),
             fans: vec![Fan {
                 name: "fan".into(),
                 rpm: fan_rpm,
                 percent: fan_percent,
-                target_percent: None,
+                target_percent: self.fan.manual().map(u8::from),
             }],
-            temperatures: vec![
-                TemperatureSensor {
-                    name: "asic".into(),
-                    temperature: asic_temp.map(Temperature::from_celsius),
-                },
-                TemperatureSensor {
-                    name: "vr".into(),
-                    temperature: vr_temp.map(|t| Temperature::from_celsius(t as f32)),
-                },
-            ],
+            temperatures: self.temps.sensors(),
             powers: vec![
                 PowerMeasurement {
                     name: "input".into(),
-                    voltage_v: vin_mv.map(|mv| mv as f32 / 1000.0),
+                    voltage_v: readings.input_v,
                     current_a: None,
                     power_w: None,
                 },
                 PowerMeasurement {
                     name: "core".into(),
-                    voltage_v: vout_mv.map(|mv| mv as f32 / 1000.0),
-                    current_a: iout_ma.map(|ma| ma as f32 / 1000.0),
-                    power_w: power_mw.map(|mw| mw as f32 / 1000.0),
+                    voltage_v: readings.core_v,
+                    current_a: readings.core_a,
+                    power_w: readings.core_w,
                 },
             ],
-            threads: Vec::new(), // TODO: populate from hash thread telemetry
+            threads: vec![thread_telemetry(&self.thread_name, &self.thread_status)],
+            // Known to the backplane, which fills it in at registration.
+            init_secs: None,
+            // Filled in by the API registry from the selection channel.
+            profile: None,
+            fault: self.fault.clone(),
+            // Integrated by the API registry's energy meter.
+            energy_kwh: None,
+            // Known to the scheduler, and filled in by the API.
+            confirmed: false,
         });
 
         // Periodic log
         const LOG_INTERVAL: Duration = Duration::from_secs(30);
         if last_log.elapsed() >= LOG_INTERVAL {
             *last_log = Instant::now();
+            let shown = |c: f32| {
+                Temperature::from_celsius(c)
+                    .display(TemperatureUnit::configured())
+                    .to_string()
+            };
             info!(
                 board = %self.board_model,
                 serial = ?self.board_serial,
-                asic_temp_c = ?asic_temp,
+                asic_temp = ?asic_temp.map(shown),
                 fan_percent = ?fan_percent,
                 fan_rpm = ?fan_rpm,
-                vr_temp_c = ?vr_temp,
-                power_w = ?power_mw.map(|mw| mw as f32 / 1000.0),
-                current_a = ?iout_ma.map(|ma| ma as f32 / 1000.0),
-                vin_v = ?vin_mv.map(|mv| mv as f32 / 1000.0),
-                vout_v = ?vout_mv.map(|mv| mv as f32 / 1000.0),
+                vr_temp = ?readings.temp_c.map(shown),
+                power_w = ?readings.core_w,
+                current_a = ?readings.core_a,
+                vin_v = ?readings.input_v,
+                vout_v = ?readings.core_v,
                 "Board status"
             );
         }
-
-        Ok(())
     }
 
     async fn shutdown(&mut self) {
-        if let Err(e) = self.thread_shutdown.send(ThreadRemovalSignal::Shutdown) {
-            warn!("Failed to send shutdown signal to threads: {}", e);
-        } else {
-            time::sleep(Duration::from_millis(200)).await;
-        }
+        self.stop_threads().await;
 
         if let Err(e) = self.asic_enable.disable().await {
             warn!("Failed to hold chips in reset: {}", e);
@@ -459,9 +773,37 @@ impl Bitaxe {
             Err(e) => warn!("Failed to turn off core voltage: {}", e),
         }
     }
+
+    /// Tell the hash threads to stop and give them a moment to do so.
+    async fn stop_threads(&mut self) {
+        if let Err(e) = self.thread_shutdown.send(ThreadRemovalSignal::Shutdown) {
+            warn!("Failed to send shutdown signal to threads: {}", e);
+        } else {
+            time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+}
+
+/// Whether the fan controller has the ASIC's diode to read. Only counted
+/// missing when every one of a few reads says so, so a board whose probe
+/// fails on the bus keeps its thermal watchdog.
+async fn has_asic_diode(fan: &mut Emc2101<BoardI2c>) -> bool {
+    const PROBES: usize = 3;
+    for _ in 0..PROBES {
+        match fan.has_external_diode().await {
+            Ok(false) => {}
+            Ok(true) => return true,
+            Err(e) => {
+                debug!(error = %e, "ASIC diode probe failed");
+                return true;
+            }
+        }
+        time::sleep(Duration::from_millis(100)).await;
+    }
+    false
 }
 
-async fn init_fan_controller(i2c: BitaxeRawI2c) -> Result<Emc2101<BitaxeRawI2c>> {
+async fn init_fan_controller(i2c: BoardI2c) -> Result<Emc2101<BoardI2c>> {
     let mut fan = Emc2101::new(i2c);
     fan.init().await.context("EMC2101 init failed")?;
     fan.set_fan_speed(Percent::FULL)
@@ -471,7 +813,52 @@ async fn init_fan_controller(i2c: BitaxeRawI2c) -> Result<Emc2101<BitaxeRawI2c>>
     Ok(fan)
 }
 
-async fn init_power_controller(i2c: BitaxeRawI2c) -> Result<Tps546<BitaxeRawI2c>> {
+/// Check the board's hardware once it is up: one chip answered, the fan
+/// turns at the full speed it was started at, and the regulator sees a
+/// 5 V input and holds `core_voltage` volts.
+async fn self_test(
+    fan: &mut Emc2101<BoardI2c>,
+    regulator: &Mutex<Tps546<BoardI2c>>,
+    chips: usize,
+    core_voltage: f32,
+) -> Result<(), SelfTestFailure> {
+    const VIN_RANGE_V: std::ops::RangeInclusive<f32> = 4.5..=5.5;
+    const VOUT_TOLERANCE: f32 = 0.05;
+
+    let mut failed = Vec::new();
+    if chips != 1 {
+        failed.push(format!("expected 1 chip, {chips} answered"));
+    }
+    match fan.get_rpm().await {
+        Ok(rpm) if rpm < fan_stall::STALL_RPM => {
+            failed.push(format!("fan at {rpm} RPM at full duty"))
+        }
+        Ok(_) => {}
+        Err(e) => failed.push(format!("fan speed unreadable: {e}")),
+    }
+    let mut reg = regulator.lock().await;
+    match reg.get_vin().await {
+        Ok(mv) if !VIN_RANGE_V.contains(&(mv as f32 / 1000.0)) => {
+            failed.push(format!("input at {:.2} V", mv as f32 / 1000.0))
+        }
+        Ok(_) => {}
+        Err(e) => failed.push(format!("input voltage unreadable: {e}")),
+    }
+    match reg.get_vout().await {
+        Ok(mv) if ((mv as f32 / 1000.0) - core_voltage).abs() > core_voltage * VOUT_TOLERANCE => {
+            failed.push(format!(
+                "core at {:.3} V, set to {core_voltage:.3} V",
+                mv as f32 / 1000.0
+            ))
+        }
+        Ok(_) => {}
+        Err(e) => failed.push(format!("core voltage unreadable: {e}")),
+    }
+    SelfTestFailure::outcome(failed)
+}
+
+/// Bring up the core regulator and set it to `core_voltage` volts.
+async fn init_power_controller(i2c: BoardI2c, core_voltage: f32) -> Result<Tps546<BoardI2c>> {
     let config = Tps546Config {
         phase: 0x00,
         frequency_switch_khz: 650,
@@ -521,12 +908,11 @@ async fn init_power_controller(i2c: BitaxeRawI2c) -> Result<Tps546<BitaxeRawI2c>
 
     time::sleep(Duration::from_millis(100)).await;
 
-    const DEFAULT_VOUT: f32 = 1.15;
     tps546
-        .set_vout(DEFAULT_VOUT)
+        .set_vout(core_voltage)
         .await
         .context("failed to set core voltage")?;
-    debug!("Core voltage set to {DEFAULT_VOUT}V");
+    debug!("Core voltage set to {core_voltage}V");
 
     time::sleep(Duration::from_millis(500)).await;
 
@@ -643,6 +1029,17 @@ impl AsicEnable for BitaxeAsicEnable {
     }
 }
 
+/// Core voltage control handed to the hash thread, so it can follow
+/// operating profile changes.
+struct BitaxeCoreVoltage(Arc<Mutex<Tps546<BoardI2c>>>);
+
+#[async_trait]
+impl VoltageRegulator for BitaxeCoreVoltage {
+    async fn set_voltage(&mut self, volts: f32) -> Result<()> {
+        self.0.lock().await.set_vout(volts).await
+    }
+}
+
 /// A wrapper around AsyncRead that traces raw bytes as they're read.
 struct TracingReader<R> {
     inner: R,
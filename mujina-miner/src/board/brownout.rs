@@ -0,0 +1,167 @@
+//! Clock reduction while the input supply sags.
+//!
+//! A marginal power supply can dip well below its rating under full load.
+//! Hashing on at full clock through the dip risks errors and, on a weak
+//! enough supply, the regulator dropping out. With `MUJINA_BROWNOUT_V`
+//! set, a board whose input voltage reads below it is cut to
+//! [`REDUCED_CLOCK`] of the profile's clock, no lower than the model's
+//! minimum, to draw less current, and restored once a reading is back
+//! above the threshold by [`HYSTERESIS_V`]. Unset, the guard is off.
+//!
+//! The guard acts per poll: the input is read on the board's routine poll
+//! (`MUJINA_BOARD_POLL_MS`), so it responds within a poll interval, and a
+//! dip that ends between two polls goes unseen. It rides out a supply that
+//! sags under sustained load, not a transient.
+
+use std::env;
+
+use tokio::sync::watch;
+
+use crate::tracing::prelude::*;
+
+/// How far above the threshold the input must recover before the clock is
+/// restored, so a supply hovering at the threshold doesn't cycle it.
+pub const HYSTERESIS_V: f32 = 0.1;
+
+/// Fraction of the profile's clock to run at during a sag.
+pub const REDUCED_CLOCK: f32 = 0.75;
+
+/// Watches a board's input voltage and scales its clock through a sag.
+#[derive(Debug)]
+pub struct BrownoutGuard {
+    /// `None` when disabled.
+    sag_below_v: Option<f32>,
+    clock_scale: watch::Sender<f32>,
+    sagging: bool,
+}
+
+impl BrownoutGuard {
+    /// Guard the clock behind `clock_scale`, reducing it below
+    /// `sag_below_v`, or never when `None`.
+    pub fn new(sag_below_v: Option<f32>, clock_scale: watch::Sender<f32>) -> Self {
+        Self {
+            sag_below_v,
+            clock_scale,
+            sagging: false,
+        }
+    }
+
+    /// The threshold from `MUJINA_BROWNOUT_V`, `None` when unset or 0,
+    /// warning and leaving the guard off on invalid values.
+    pub fn threshold_from_env() -> Option<f32> {
+        let val = env::var("MUJINA_BROWNOUT_V").ok()?;
+        match val.parse::<f32>() {
+            Ok(0.0) => None,
+            Ok(v) if v > 0.0 && v.is_finite() => Some(v),
+            _ => {
+                warn!(value = %val, "Invalid MUJINA_BROWNOUT_V, leaving brownout guard off");
+                None
+            }
+        }
+    }
+
+    /// Whether the clock is currently reduced.
+    pub fn sagging(&self) -> bool {
+        self.sagging
+    }
+
+    /// Act on an input voltage reading, taken once per poll. A missing
+    /// reading changes nothing.
+    pub fn observe(&mut self, input_v: Option<f32>) {
+        let (Some(threshold), Some(input_v)) = (self.sag_below_v, input_v) else {
+            return;
+        };
+        if !self.sagging && input_v < threshold {
+            self.sagging = true;
+            warn!(
+                input_v,
+                threshold_v = threshold,
+                "Input voltage sagging, reducing clock"
+            );
+            self.clock_scale.send_replace(REDUCED_CLOCK);
+        } else if self.sagging && input_v >= threshold + HYSTERESIS_V {
+            self.sagging = false;
+            info!(input_v, "Input voltage recovered, restoring clock");
+            self.clock_scale.send_replace(1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::board::profile::{self, Profile, ProfileSelection};
+
+    #[tokio::test]
+    async fn clock_is_reduced_through_a_sag_and_restored_after() {
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (_profile_tx, mut selection) = ProfileSelection::channel(gamma, Profile::Turbo);
+        let mut guard = BrownoutGuard::new(Some(4.75), selection.clock_scale());
+        let full = selection.current();
+        assert_eq!(full.frequency_mhz, 575.0);
+
+        guard.observe(Some(5.02));
+        guard.observe(None);
+        assert!(!guard.sagging());
+
+        // The supply sags under load: the hash thread is told to slow to
+        // three quarters of the clock, at the same voltage.
+        guard.observe(Some(4.61));
+        let reduced = selection.changed().await.unwrap();
+        assert_eq!(reduced.frequency_mhz, 575.0 * REDUCED_CLOCK);
+        assert_eq!(reduced.core_voltage_v, full.core_voltage_v);
+
+        // Back above the threshold, but not by the hysteresis.
+        guard.observe(Some(4.8));
+        assert!(guard.sagging());
+        assert_eq!(selection.current(), reduced);
+
+        guard.observe(Some(4.98));
+        assert!(!guard.sagging());
+        assert_eq!(selection.changed().await, Some(full));
+    }
+
+    #[test]
+    fn reduced_clock_stays_within_the_model_limits() {
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (_profile_tx, selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+        let mut guard = BrownoutGuard::new(Some(4.75), selection.clock_scale());
+
+        guard.observe(Some(4.5));
+        assert_eq!(
+            selection.current().frequency_mhz,
+            gamma.limits.min_frequency_mhz
+        );
+    }
+
+    #[test]
+    fn disabled_guard_never_reduces_the_clock() {
+        let (clock_scale, scale) = watch::channel(1.0);
+        let mut guard = BrownoutGuard::new(None, clock_scale);
+        guard.observe(Some(3.0));
+        assert!(!guard.sagging());
+        assert_eq!(*scale.borrow(), 1.0);
+    }
+
+    #[test]
+    #[serial]
+    fn threshold_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_BROWNOUT_V", "11.4") };
+        assert_eq!(BrownoutGuard::threshold_from_env(), Some(11.4));
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_BROWNOUT_V", "0") };
+        assert_eq!(BrownoutGuard::threshold_from_env(), None);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_BROWNOUT_V", "low") };
+        assert_eq!(BrownoutGuard::threshold_from_env(), None);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_BROWNOUT_V") };
+        assert_eq!(BrownoutGuard::threshold_from_env(), None);
+    }
+}
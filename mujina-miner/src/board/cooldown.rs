@@ -0,0 +1,191 @@
+//! Cooldown before a board that shut itself down may run again.
+//!
+//! A board that trips its thermal emergency shutdown is usually still hot,
+//! and enabling it straight away only heats it back to the limit: the
+//! board cycles between shutdown and full power. After a trip, a board
+//! may be re-enabled only once `MUJINA_THERMAL_COOLDOWN_SECS` have passed
+//! and, when `MUJINA_THERMAL_RESUME_C` is set, its hottest sensor has
+//! dropped below that temperature. An operator can override both with an
+//! explicit force.
+
+use std::env;
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::time::Instant;
+
+use crate::tracing::prelude::*;
+
+/// Cooldown when not configured.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// A board shutting itself down.
+#[derive(Debug, Clone)]
+pub struct Trip {
+    pub at: Instant,
+    /// What made the board shut down, as logged.
+    pub reason: String,
+}
+
+/// What has to happen after a trip before the board may run again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CooldownPolicy {
+    /// Time since the trip.
+    pub period: Duration,
+    /// Hottest sensor reading to stay below, in degrees Celsius. `None`
+    /// waits for the period only.
+    pub resume_below_c: Option<f32>,
+}
+
+impl Default for CooldownPolicy {
+    fn default() -> Self {
+        Self {
+            period: DEFAULT_COOLDOWN,
+            resume_below_c: None,
+        }
+    }
+}
+
+/// Why a tripped board may not be enabled yet.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum EnableRefused {
+    #[error("cooling down, {}s remaining", remaining.as_secs().max(1))]
+    Cooling { remaining: Duration },
+    #[error("still at {temp_c:.1} C, resumes below {limit_c:.1} C")]
+    TooHot { temp_c: f32, limit_c: f32 },
+    #[error("no temperature reading to check against {limit_c:.1} C")]
+    TemperatureUnknown { limit_c: f32 },
+}
+
+impl CooldownPolicy {
+    /// Read `MUJINA_THERMAL_COOLDOWN_SECS` and `MUJINA_THERMAL_RESUME_C`,
+    /// warning and keeping the default for each one invalid.
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        if let Ok(val) = env::var("MUJINA_THERMAL_COOLDOWN_SECS") {
+            match val.parse::<u64>() {
+                Ok(secs) => policy.period = Duration::from_secs(secs),
+                Err(_) => {
+                    warn!(value = %val, "Invalid MUJINA_THERMAL_COOLDOWN_SECS, using default")
+                }
+            }
+        }
+        if let Ok(val) = env::var("MUJINA_THERMAL_RESUME_C") {
+            match val.parse::<f32>() {
+                Ok(c) if c.is_finite() => policy.resume_below_c = Some(c),
+                _ => warn!(value = %val, "Invalid MUJINA_THERMAL_RESUME_C, ignoring"),
+            }
+        }
+        policy
+    }
+
+    /// Whether a board that tripped at `trip` may be enabled at `now`,
+    /// its hottest sensor reading `hottest_c`.
+    pub fn check(
+        &self,
+        trip: &Trip,
+        now: Instant,
+        hottest_c: Option<f32>,
+    ) -> Result<(), EnableRefused> {
+        let cooled = now.saturating_duration_since(trip.at);
+        if cooled < self.period {
+            return Err(EnableRefused::Cooling {
+                remaining: self.period - cooled,
+            });
+        }
+        match (self.resume_below_c, hottest_c) {
+            (None, _) => Ok(()),
+            (Some(limit_c), None) => Err(EnableRefused::TemperatureUnknown { limit_c }),
+            (Some(limit_c), Some(temp_c)) if temp_c >= limit_c => {
+                Err(EnableRefused::TooHot { temp_c, limit_c })
+            }
+            (Some(_), Some(_)) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    fn trip() -> Trip {
+        Trip {
+            at: Instant::now(),
+            reason: "thermal emergency".into(),
+        }
+    }
+
+    #[test]
+    fn refuses_until_the_period_has_passed() {
+        let policy = CooldownPolicy {
+            period: Duration::from_secs(300),
+            resume_below_c: None,
+        };
+        let trip = trip();
+
+        assert_eq!(
+            policy.check(&trip, trip.at + Duration::from_secs(100), Some(90.0)),
+            Err(EnableRefused::Cooling {
+                remaining: Duration::from_secs(200)
+            })
+        );
+        assert_eq!(
+            policy.check(&trip, trip.at + Duration::from_secs(300), Some(90.0)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn refuses_until_the_board_is_below_the_resume_temperature() {
+        let policy = CooldownPolicy {
+            period: Duration::ZERO,
+            resume_below_c: Some(60.0),
+        };
+        let trip = trip();
+
+        assert_eq!(
+            policy.check(&trip, trip.at, Some(60.0)),
+            Err(EnableRefused::TooHot {
+                temp_c: 60.0,
+                limit_c: 60.0
+            })
+        );
+        assert_eq!(
+            policy.check(&trip, trip.at, None),
+            Err(EnableRefused::TemperatureUnknown { limit_c: 60.0 })
+        );
+        assert_eq!(policy.check(&trip, trip.at, Some(59.5)), Ok(()));
+    }
+
+    #[test]
+    #[serial]
+    fn policy_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_THERMAL_COOLDOWN_SECS", "120");
+            env::set_var("MUJINA_THERMAL_RESUME_C", "55");
+        }
+        assert_eq!(
+            CooldownPolicy::from_env(),
+            CooldownPolicy {
+                period: Duration::from_secs(120),
+                resume_below_c: Some(55.0),
+            }
+        );
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_THERMAL_COOLDOWN_SECS", "soon");
+            env::set_var("MUJINA_THERMAL_RESUME_C", "cold");
+        }
+        assert_eq!(CooldownPolicy::from_env(), CooldownPolicy::default());
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_THERMAL_COOLDOWN_SECS");
+            env::remove_var("MUJINA_THERMAL_RESUME_C");
+        }
+    }
+}
@@ -1,15 +1,27 @@
 //! CPU hashboard implementation.
 //!
 //! Provides a virtual board that uses CPU cores for SHA-256 hashing.
-//! See [`CpuMinerConfig`] for environment variable configuration.
+//! The daemon passes the [`CpuMinerConfig`] in with the virtual device.
 
-use anyhow::{Result, anyhow};
-use tokio::sync::watch;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::Result;
+use tokio::{
+    sync::watch,
+    time::{self, MissedTickBehavior},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
-use super::{BackplaneConnector, BoardInfo, VirtualBoardDescriptor};
+use super::{
+    BackplaneConnector, BoardInfo, BoardShutdown, VirtualBoardDescriptor, thread_telemetry,
+};
 use crate::{
     api_client::types::BoardTelemetry,
-    asic::hash_thread::HashThread,
+    asic::hash_thread::{HashThread, HashThreadStatus},
     cpu_miner::{CpuHashThread, CpuMinerConfig},
 };
 
@@ -17,14 +29,11 @@ inventory::submit! {
     VirtualBoardDescriptor {
         device_type: "cpu_miner",
         name: "CPU Miner",
-        create_fn: || Box::pin(create_cpu_board()),
+        create_fn: |device| Box::pin(create_cpu_board(device.config)),
     }
 }
 
-async fn create_cpu_board() -> Result<BackplaneConnector> {
-    let config = CpuMinerConfig::from_env()
-        .ok_or_else(|| anyhow!("cpu miner not configured (MUJINA_CPUMINER_THREADS not set)"))?;
-
+async fn create_cpu_board(config: CpuMinerConfig) -> Result<BackplaneConnector> {
     let info = BoardInfo {
         model: "CPU Miner".into(),
         firmware_version: None,
@@ -34,27 +43,82 @@ async fn create_cpu_board() -> Result<BackplaneConnector> {
         )),
     };
 
+    // No awaits follow, so the span is simply entered; the threads and
+    // monitor started below carry it along.
+    let board_id = info.serial_number.clone().unwrap();
+    let _span = super::span(&board_id, &info.model).entered();
+
     let initial_state = BoardTelemetry {
-        name: info.serial_number.clone().unwrap(),
+        name: board_id,
         model: info.model.clone(),
         serial: info.serial_number.clone(),
         ..Default::default()
     };
-    let (_telemetry_tx, telemetry_rx) = watch::channel(initial_state);
+    let (telemetry_tx, telemetry_rx) = watch::channel(initial_state);
 
-    let threads: Vec<Box<dyn HashThread>> = (0..config.thread_count)
+    let cpu_threads: Vec<CpuHashThread> = (0..config.thread_count)
         .map(|i| {
-            Box::new(CpuHashThread::new(
+            CpuHashThread::new(
                 format!("CPU Core {i}"),
                 config.duty_percent,
-            )) as _
+                Arc::clone(&config.strategy),
+            )
         })
         .collect();
+    let statuses = cpu_threads
+        .iter()
+        .map(|t| (t.name().to_string(), t.status_handle()))
+        .collect();
+    let threads: Vec<Box<dyn HashThread>> =
+        cpu_threads.into_iter().map(|t| Box::new(t) as _).collect();
+
+    let cancel = CancellationToken::new();
+    let monitor =
+        tokio::spawn(run_monitor(telemetry_tx, statuses, cancel.clone()).in_current_span());
+    // Nothing to power off: both modes just stop the monitor.
+    let shutdown: BoardShutdown = Box::new(move |_mode| {
+        Box::pin(async move {
+            cancel.cancel();
+            let _ = monitor.await;
+        })
+    });
 
     Ok(BackplaneConnector {
         info,
         threads,
         telemetry_rx,
-        shutdown: None,
+        profile_tx: None,
+        profile_guard: None,
+        fan_tx: None,
+        self_test: None,
+        shutdown: Some(shutdown),
+        trip_rx: None,
+        link_lost_rx: None,
     })
 }
+
+/// Periodically publish thread state. The CPU board has no sensors, so
+/// its telemetry is just its threads.
+async fn run_monitor(
+    telemetry_tx: watch::Sender<BoardTelemetry>,
+    statuses: Vec<(String, Arc<RwLock<HashThreadStatus>>)>,
+    cancel: CancellationToken,
+) {
+    const INTERVAL: Duration = Duration::from_secs(5);
+    let mut ticker = time::interval(INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+
+        telemetry_tx.send_modify(|t| {
+            t.threads = statuses
+                .iter()
+                .map(|(name, status)| thread_telemetry(name, status))
+                .collect();
+        });
+    }
+}
@@ -10,22 +10,27 @@ use anyhow::{Context, Result};
 use tokio::sync::watch;
 use tokio::task::JoinHandle;
 use tokio::time::{self, MissedTickBehavior};
-use tokio_serial::SerialPortBuilderExt;
 use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
 use super::{
-    BackplaneConnector, BoardDescriptor, BoardInfo,
+    BackplaneConnector, BoardDescriptor, BoardInfo, BoardShutdown, ShutdownMode,
+    firmware::FirmwarePolicy,
     pattern::{BoardPattern, Match, StringMatch},
+    poll,
 };
 use crate::{
     api_client::types::{BoardTelemetry, TemperatureSensor},
-    hw_trait::gpio::{Gpio, GpioPin, PinValue},
+    hw_trait::{
+        gpio::{Gpio, GpioPin, PanicSafeState, PinValue},
+        i2c::{I2c, I2cSpeed, SpeedFallbackI2c, speed_fallback_from_env},
+    },
     mgmt_protocol::{
-        ControlChannel,
+        ControlChannel, ControlTransport,
         bitaxe_raw::{
-            DeviceVersion, ResponseFormat,
+            DeviceVersion,
             gpio::{BitaxeRawGpioController, BitaxeRawGpioPin},
-            i2c::BitaxeRawI2c,
+            i2c::{self as raw_i2c, BitaxeRawI2c},
             led::BitaxeRawLed,
             system,
         },
@@ -50,10 +55,17 @@ inventory::submit! {
             serial_pattern: Match::Any,
         },
         name: "emberOne/00",
-        create_fn: |device| Box::pin(create_from_usb(device)),
+        create_fn: |device| {
+            let span = super::span(&super::usb_board_name("emberone00", &device), "emberOne/00");
+            Box::pin(create_from_usb(device).instrument(span))
+        },
     }
 }
 
+/// The board's I2C bus: bitaxe-raw passthrough, falling back to standard
+/// speed on failure when `MUJINA_I2C_SPEED_FALLBACK` is set.
+type BoardI2c = SpeedFallbackI2c<BitaxeRawI2c>;
+
 /// I2C device addresses on the emberOne/00 board.
 mod i2c_addr {
     pub const TMP1075_LEFT: u8 = 0x4A;
@@ -69,17 +81,12 @@ mod gpio_cmd {
     pub const VDDIO_EN: u8 = 0x02;
 }
 
-/// Select response format based on firmware version.
+/// Oldest firmware (minor, patch) known to work correctly.
 ///
-/// Firmware minor >= 1 uses v1 response format with explicit
-/// status byte. Older firmware uses v0.
-fn response_format(version: &DeviceVersion) -> ResponseFormat {
-    if version.firmware_minor() >= 1 {
-        ResponseFormat::V1
-    } else {
-        ResponseFormat::V0
-    }
-}
+/// Earlier firmware answers in the v0 response format, where an error
+/// can't be told apart from a response whose first byte is 0xFF, so
+/// failed peripheral reads may go unnoticed.
+const MIN_FIRMWARE: (u8, u8) = (1, 0);
 
 async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
     let serial_ports = device.get_serial_ports(2).await?;
@@ -91,14 +98,21 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
         "Opening emberOne/00 serial ports"
     );
 
-    let control_port = tokio_serial::new(&serial_ports[0], 115200)
-        .open_native_async()
-        .context("failed to open control port")?;
     let version = DeviceVersion::from_bcd(device.bcd_device);
-    let format = response_format(&version);
-    let control = ControlChannel::new(control_port, format);
+    FirmwarePolicy::from_env().check("emberOne/00", &version, MIN_FIRMWARE)?;
+    let control = ControlTransport::serial(&serial_ports[0])
+        .connect(&version)
+        .await
+        .context("failed to open control port")?;
 
-    let i2c = BitaxeRawI2c::new(control.clone());
+    let mut i2c = BoardI2c::new(
+        BitaxeRawI2c::new(control.clone()).with_timeout(raw_i2c::timeout_from_env()),
+        speed_fallback_from_env(),
+    );
+    // Left at the firmware's clock unless asked otherwise.
+    if let Some(speed) = I2cSpeed::from_env() {
+        i2c.set_frequency(speed.hz()).await?;
+    }
 
     let mut gpio = BitaxeRawGpioController::new(control.clone());
     let mut vddio_en = gpio.pin(gpio_cmd::VDDIO_EN).await?;
@@ -134,24 +148,29 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
 
     let info = BoardInfo {
         model: "emberOne/00".to_string(),
-        firmware_version: None,
+        firmware_version: Some(version.to_string()),
         serial_number: device.serial_number.clone(),
     };
 
-    let board_name = format!(
-        "emberone00-{}",
-        info.serial_number.as_deref().unwrap_or("unknown")
-    );
+    let board_name = super::usb_board_name("emberone00", &device);
     let initial_telemetry = BoardTelemetry {
         name: board_name,
         model: info.model.clone(),
         serial: info.serial_number.clone(),
+        firmware_version: info.firmware_version.clone(),
         ..Default::default()
     };
     let (telemetry_tx, telemetry_rx) = watch::channel(initial_telemetry);
 
     let cancel = CancellationToken::new();
-    let monitor_task = spawn_monitor(temp_left, temp_right, telemetry_tx, cancel.clone());
+    let vddio_guard = PanicSafeState::new(vddio_en.clone(), PinValue::Low, "vddio-en");
+    let monitor_task = spawn_monitor(
+        temp_left,
+        temp_right,
+        telemetry_tx,
+        vddio_guard,
+        cancel.clone(),
+    );
 
     let mut board = EmberOne00 {
         control,
@@ -161,9 +180,8 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
         monitor_task,
     };
 
-    let shutdown = Box::pin(async move {
-        board.shutdown().await;
-    });
+    let shutdown: BoardShutdown =
+        Box::new(move |mode| Box::pin(async move { board.shutdown(mode).await }));
 
     warn!("emberOne/00 hash threads not yet implemented");
 
@@ -171,76 +189,92 @@ async fn create_from_usb(device: UsbDeviceInfo) -> Result<BackplaneConnector> {
         info,
         threads: Vec::new(),
         telemetry_rx,
+        profile_tx: None,
+        profile_guard: None,
+        fan_tx: None,
+        self_test: None,
         shutdown: Some(shutdown),
+        trip_rx: None,
+        link_lost_rx: None,
     })
 }
 
 /// Spawn a task that periodically reads sensors and publishes telemetry.
+///
+/// The task owns a guard on VDDIO_EN, so a panic while sensing turns the
+/// rail off rather than leaving it up with nobody watching temperatures.
 fn spawn_monitor(
-    mut temp_left: Tmp1075<BitaxeRawI2c>,
-    mut temp_right: Tmp451<BitaxeRawI2c>,
+    mut temp_left: Tmp1075<BoardI2c>,
+    mut temp_right: Tmp451<BoardI2c>,
     telemetry_tx: watch::Sender<BoardTelemetry>,
+    vddio_guard: PanicSafeState<BitaxeRawGpioPin>,
     cancel: CancellationToken,
 ) -> JoinHandle<()> {
-    tokio::spawn(async move {
-        const INTERVAL: Duration = Duration::from_secs(5);
-        let mut ticker = time::interval(INTERVAL);
-        ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
-
-        // Discard first tick (fires immediately)
-        ticker.tick().await;
-
-        loop {
-            tokio::select! {
-                _ = cancel.cancelled() => break,
-                _ = ticker.tick() => {}
-            }
-
-            let t_left = match temp_left.read().await {
-                Ok(reading) => Some(reading.into()),
-                Err(e) => {
-                    warn!("TMP1075 (left) read failed: {}", e);
-                    None
+    tokio::spawn(
+        async move {
+            // Cuts VDDIO if this task panics; orderly shutdown does it otherwise.
+            let _vddio_guard = vddio_guard;
+
+            const INTERVAL: Duration = Duration::from_secs(5);
+            let mut ticker = time::interval(poll::interval_from_env(INTERVAL));
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            // Discard first tick (fires immediately)
+            ticker.tick().await;
+
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = ticker.tick() => {}
                 }
-            };
 
-            let t_right = match temp_right.read_local().await {
-                Ok(reading) => Some(reading.into()),
-                Err(e) => {
-                    warn!("TMP451 (right) local read failed: {}", e);
-                    None
-                }
-            };
-
-            // TODO: attribute to the chip's hash thread once implemented
-            let t_chip0 = match temp_right.read_remote().await {
-                Ok(reading) => Some(reading.into()),
-                Err(e) => {
-                    if !matches!(e, crate::peripheral::tmp451::Error::RemoteDiodeOpen) {
-                        warn!("TMP451 remote read failed: {}", e);
+                let t_left = match temp_left.read().await {
+                    Ok(reading) => Some(reading.into()),
+                    Err(e) => {
+                        warn!("TMP1075 (left) read failed: {}", e);
+                        None
                     }
-                    None
-                }
-            };
-
-            telemetry_tx.send_modify(|t| {
-                t.temperatures = vec![
-                    TemperatureSensor {
-                        name: "pcb-left".into(),
-                        temperature: t_left,
-                    },
-                    TemperatureSensor {
-                        name: "pcb-right".into(),
-                        temperature: t_right,
-                    },
-                    TemperatureSensor {
-                        name: "chip-0".into(),
-                        temperature: t_chip0,
-                    },
-                ];
-            });
+                };
+
+                let t_right = match temp_right.read_local().await {
+                    Ok(reading) => Some(reading.into()),
+                    Err(e) => {
+                        warn!("TMP451 (right) local read failed: {}", e);
+                        None
+                    }
+                };
+
+                // TODO: attribute to the chip's hash thread once implemented
+                let t_chip0 = match temp_right.read_remote().await {
+                    Ok(reading) => Some(reading.into()),
+                    Err(e) => {
+                        if !matches!(e, crate::peripheral::tmp451::Error::RemoteDiodeOpen) {
+                            warn!("TMP451 remote read failed: {}", e);
+                        }
+                        None
+                    }
+                };
+
+                telemetry_tx.send_modify(|t| {
+                    t.temperatures = vec![
+                        TemperatureSensor {
+                            name: "pcb-left".into(),
+                            temperature: t_left,
+                        },
+                        TemperatureSensor {
+                            name: "pcb-right".into(),
+                            temperature: t_right,
+                        },
+                        TemperatureSensor {
+                            name: "chip-0".into(),
+                            temperature: t_chip0,
+                        },
+                    ];
+                });
+            }
         }
-    })
+        .in_current_span(),
+    )
 }
 
 /// emberOne/00 hash board state.
@@ -256,9 +290,12 @@ struct EmberOne00 {
 }
 
 impl EmberOne00 {
-    async fn shutdown(&mut self) {
+    async fn shutdown(&mut self, mode: ShutdownMode) {
         self.monitor_cancel.cancel();
         let _ = (&mut self.monitor_task).await;
+        if mode == ShutdownMode::Idle {
+            return;
+        }
         let _ = self.vddio_en.write(PinValue::Low).await;
         self.status_led.off().await;
         let _ = system::reboot(&self.control).await;
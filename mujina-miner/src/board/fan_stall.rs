@@ -0,0 +1,119 @@
+//! Detecting a fan that has stopped while it is meant to be running.
+//!
+//! A fan that seizes or comes unplugged leaves the fan curve driving a
+//! duty cycle into nothing, and the ASIC heats until the thermal
+//! emergency catches it, if it does. Where the board reads its fan's
+//! tachometer, the detector compares the RPM with the duty cycle the fan
+//! is set to: at [`MIN_JUDGED_DUTY`] or more, a fan below [`STALL_RPM`]
+//! for [`STALL_POLLS`] polls in a row has stalled, and the board shuts
+//! itself down. Lower duties aren't judged, since some fans don't turn at
+//! all that slowly, nor are polls whose RPM couldn't be read.
+
+use std::fmt;
+
+use crate::peripheral::emc2101::Percent;
+
+/// Least duty cycle at which a still fan counts as stalled.
+pub const MIN_JUDGED_DUTY: Percent = Percent::new_clamped(20);
+
+/// RPM below which a fan counts as still.
+pub const STALL_RPM: u32 = 300;
+
+/// Consecutive still polls before a stall is declared, so a fan spinning
+/// up from a lower duty isn't.
+pub const STALL_POLLS: u32 = 3;
+
+/// A fan found stalled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanStall {
+    pub rpm: u32,
+    pub duty: Percent,
+}
+
+impl fmt::Display for FanStall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fan stalled: {} RPM at {}% duty",
+            self.rpm,
+            u8::from(self.duty)
+        )
+    }
+}
+
+/// Watches one fan's tachometer against its duty cycle.
+#[derive(Debug, Default)]
+pub struct StallDetector {
+    still_polls: u32,
+}
+
+impl StallDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a poll of the fan's duty cycle and RPM. Returns the stall
+    /// once the fan has been still for [`STALL_POLLS`] polls, and on each
+    /// poll after that until it turns again.
+    pub fn observe(&mut self, duty: Option<Percent>, rpm: Option<u32>) -> Option<FanStall> {
+        let (Some(duty), Some(rpm)) = (duty, rpm) else {
+            return None;
+        };
+        if duty < MIN_JUDGED_DUTY || rpm >= STALL_RPM {
+            self.still_polls = 0;
+            return None;
+        }
+        self.still_polls += 1;
+        (self.still_polls >= STALL_POLLS).then_some(FanStall { rpm, duty })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_fan_driven_but_not_turning_is_a_stall() {
+        let mut fan = StallDetector::new();
+        let full = Some(Percent::FULL);
+        assert_eq!(fan.observe(full, Some(5200)), None);
+
+        // The fan stops while still driven at full duty.
+        assert_eq!(fan.observe(full, Some(0)), None);
+        assert_eq!(fan.observe(full, Some(0)), None);
+        assert_eq!(
+            fan.observe(full, Some(0)),
+            Some(FanStall {
+                rpm: 0,
+                duty: Percent::FULL
+            })
+        );
+        assert_eq!(
+            fan.observe(full, Some(0)).unwrap().to_string(),
+            "fan stalled: 0 RPM at 100% duty"
+        );
+    }
+
+    #[test]
+    fn spin_up_low_duty_and_failed_reads_are_not_stalls() {
+        let mut fan = StallDetector::new();
+        let full = Some(Percent::FULL);
+
+        // Spinning up: still for a poll or two, then turning.
+        for rpm in [0, 120, 2400, 0, 0, 4800] {
+            assert_eq!(fan.observe(full, Some(rpm)), None, "{rpm}");
+        }
+
+        // A fan set below the judged duty may stand still.
+        for _ in 0..STALL_POLLS * 2 {
+            assert_eq!(fan.observe(Some(Percent::new_clamped(10)), Some(0)), None);
+        }
+
+        // Unreadable polls neither count nor reset the count.
+        assert_eq!(fan.observe(full, Some(0)), None);
+        assert_eq!(fan.observe(full, Some(0)), None);
+        assert_eq!(fan.observe(full, None), None);
+        assert_eq!(fan.observe(None, Some(0)), None);
+        assert!(fan.observe(full, Some(0)).is_some());
+    }
+}
@@ -0,0 +1,116 @@
+//! What to do about boards running firmware older than supported.
+//!
+//! By default an old-firmware board is used anyway, with a warning, so an
+//! upgrade doesn't strand working hardware. Operators who would rather not
+//! mine on firmware with known problems can have such boards refused.
+
+use std::env;
+
+use anyhow::{Result, bail};
+
+use crate::mgmt_protocol::bitaxe_raw::DeviceVersion;
+use crate::tracing::prelude::*;
+
+/// Policy for boards whose firmware is below the supported minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FirmwarePolicy {
+    /// Log a warning and use the board.
+    #[default]
+    Warn,
+
+    /// Fail the board's initialization.
+    Enforce,
+}
+
+impl FirmwarePolicy {
+    /// Read the policy from `MUJINA_FIRMWARE_POLICY` (`warn` or
+    /// `enforce`), warning and falling back to `warn` on other values.
+    pub fn from_env() -> Self {
+        match env::var("MUJINA_FIRMWARE_POLICY").as_deref() {
+            Err(_) | Ok("warn") => Self::Warn,
+            Ok("enforce") => Self::Enforce,
+            Ok(other) => {
+                warn!(value = %other, "Invalid MUJINA_FIRMWARE_POLICY, using warn");
+                Self::Warn
+            }
+        }
+    }
+
+    /// Apply the policy to a board's firmware.
+    ///
+    /// `minimum` is the board's oldest supported `(minor, patch)`. Errors
+    /// only under [`Enforce`](Self::Enforce), with a message naming the
+    /// firmware found and the version to update to.
+    pub fn check(self, board: &str, version: &DeviceVersion, minimum: (u8, u8)) -> Result<()> {
+        let Err(e) = version.require_firmware(minimum) else {
+            return Ok(());
+        };
+        match self {
+            Self::Warn => {
+                warn!(
+                    board,
+                    version = %version,
+                    error = %e,
+                    "Board firmware is older than supported; update it"
+                );
+                Ok(())
+            }
+            Self::Enforce => bail!(
+                "{board}: {e}; update the firmware or set \
+                 MUJINA_FIRMWARE_POLICY=warn to use it anyway"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    const MINIMUM: (u8, u8) = (1, 0);
+
+    fn old() -> DeviceVersion {
+        DeviceVersion::from_bcd(0x0509)
+    }
+
+    fn current() -> DeviceVersion {
+        DeviceVersion::from_bcd(0x0510)
+    }
+
+    #[test]
+    fn warn_mode_accepts_old_firmware() {
+        let policy = FirmwarePolicy::Warn;
+        assert!(policy.check("board", &old(), MINIMUM).is_ok());
+        assert!(policy.check("board", &current(), MINIMUM).is_ok());
+    }
+
+    #[test]
+    fn enforce_mode_refuses_old_firmware() {
+        let policy = FirmwarePolicy::Enforce;
+        let err = policy.check("emberOne/00", &old(), MINIMUM).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "emberOne/00: firmware 0.9 is older than the minimum supported 1.0; \
+             update the firmware or set MUJINA_FIRMWARE_POLICY=warn to use it anyway"
+        );
+        assert!(policy.check("emberOne/00", &current(), MINIMUM).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn policy_from_env() {
+        let var = "MUJINA_FIRMWARE_POLICY";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(FirmwarePolicy::from_env(), FirmwarePolicy::Warn);
+            env::set_var(var, "enforce");
+            assert_eq!(FirmwarePolicy::from_env(), FirmwarePolicy::Enforce);
+            env::set_var(var, "strict");
+            assert_eq!(FirmwarePolicy::from_env(), FirmwarePolicy::Warn);
+            env::remove_var(var);
+        }
+    }
+}
@@ -1,14 +1,32 @@
 pub(crate) mod bitaxe;
+pub mod brownout;
+pub mod cooldown;
+#[cfg(feature = "cpu-miner")]
 pub(crate) mod cpu;
 pub(crate) mod emberone00;
+pub mod fan_stall;
+pub mod firmware;
 pub mod pattern;
+pub mod plausibility;
+pub(crate) mod poll;
+pub mod power_clamp;
+pub mod profile;
+pub mod quiet_hours;
+pub mod self_test;
+pub mod thermal;
+pub mod warmup;
+
+use std::sync::RwLock;
 
 use anyhow::Result;
 use futures::future::BoxFuture;
-use tokio::sync::watch;
+use tokio::sync::{oneshot, watch};
 
 use crate::{
-    api_client::types::BoardTelemetry, asic::hash_thread::HashThread, transport::UsbDeviceInfo,
+    api_client::types::{BoardTelemetry, ThreadTelemetry},
+    asic::hash_thread::{HashThread, HashThreadStatus},
+    peripheral::emc2101::Percent,
+    transport::{CpuDeviceInfo, UsbDeviceInfo},
 };
 
 /// Returned by board factory functions with everything the backplane
@@ -23,11 +41,51 @@ pub struct BackplaneConnector {
     /// Watch receiver for the board's telemetry stream.
     pub telemetry_rx: watch::Receiver<BoardTelemetry>,
 
-    /// Shuts down the board when awaited. `None` if the board has
-    /// no shutdown work to do.
-    pub shutdown: Option<BoxFuture<'static, ()>>,
+    /// Selects the board's operating profile. `None` if the board has no
+    /// profiles.
+    pub profile_tx: Option<watch::Sender<profile::Profile>>,
+
+    /// Checks a profile is safe for the board before it is selected.
+    /// `None` if the board has no profiles.
+    pub profile_guard: Option<profile::OperatingGuard>,
+
+    /// Holds the board's fan at a fixed duty cycle, or hands it back to
+    /// automatic control with `None`. `None` if the fan can't be set.
+    pub fan_tx: Option<watch::Sender<Option<Percent>>>,
+
+    /// Outcome of the board's startup self-test. `None` for boards that
+    /// don't test themselves.
+    pub self_test: Option<Result<(), self_test::SelfTestFailure>>,
+
+    /// Shuts down the board in the given mode when called and awaited.
+    /// `None` if the board has no shutdown work to do.
+    pub shutdown: Option<BoardShutdown>,
+
+    /// Fires when the board shuts itself down, such as on a thermal
+    /// emergency. `None` for boards that never do.
+    pub trip_rx: Option<oneshot::Receiver<cooldown::Trip>>,
+
+    /// Fires, with the reason, when the board loses the link it is
+    /// controlled over, such as a USB cable jiggled loose. The backplane
+    /// re-creates the board. `None` for boards whose link can't drop.
+    pub link_lost_rx: Option<oneshot::Receiver<String>>,
+}
+
+/// How far a board shuts down when the daemon stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShutdownMode {
+    /// Stop hashing and turn off the chips' power.
+    #[default]
+    PowerOff,
+    /// Stop hashing but leave the chips powered, for a fast restart.
+    /// Nothing watches the board's temperature once the daemon has
+    /// exited.
+    Idle,
 }
 
+/// A board's shutdown, run once in the mode given.
+pub type BoardShutdown = Box<dyn FnOnce(ShutdownMode) -> BoxFuture<'static, ()> + Send>;
+
 /// Information about a board.
 #[derive(Debug, Clone)]
 pub struct BoardInfo {
@@ -80,10 +138,10 @@ inventory::collect!(BoardDescriptor);
 
 /// Factory function signature for creating a virtual board.
 ///
-/// Same contract as [`BoardFactoryFn`], but virtual boards don't
-/// receive USB device info. They are configured via environment
-/// variables or other means.
-pub type VirtualBoardFactoryFn = fn() -> BoxFuture<'static, Result<BackplaneConnector>>;
+/// Same contract as [`BoardFactoryFn`], but virtual boards receive the
+/// virtual device they were connected for rather than USB device info.
+pub type VirtualBoardFactoryFn =
+    fn(CpuDeviceInfo) -> BoxFuture<'static, Result<BackplaneConnector>>;
 
 /// Descriptor for virtual boards (CPU miner, test boards, etc.).
 ///
@@ -109,3 +167,33 @@ impl VirtualBoardRegistry {
         inventory::iter::<VirtualBoardDescriptor>().find(|desc| desc.device_type == device_type)
     }
 }
+
+/// Span that tags everything one board logs.
+///
+/// The board's factory runs inside it, and the tasks and threads the
+/// factory starts carry it along. That way each event from the board's
+/// initialization, monitor and hash threads has a `board_id` field (the
+/// board's name in telemetry and the API) and a `model` field, which
+/// journald keeps for filtering.
+pub(crate) fn span(board_id: &str, model: &str) -> tracing::Span {
+    tracing::info_span!("board", board_id, model)
+}
+
+/// Name of a USB board: `prefix`, then its serial number.
+pub(crate) fn usb_board_name(prefix: &str, device: &UsbDeviceInfo) -> String {
+    format!(
+        "{prefix}-{}",
+        device.serial_number.as_deref().unwrap_or("unknown")
+    )
+}
+
+/// Snapshot a hash thread's shared status for board telemetry.
+pub(crate) fn thread_telemetry(name: &str, status: &RwLock<HashThreadStatus>) -> ThreadTelemetry {
+    let status = status.read().unwrap_or_else(|e| e.into_inner());
+    ThreadTelemetry {
+        name: name.to_string(),
+        hashrate: u64::from(status.hashrate),
+        is_active: status.is_active,
+        fault: status.fault.clone(),
+    }
+}
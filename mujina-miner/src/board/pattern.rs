@@ -575,7 +575,6 @@ mod tests {
             manufacturer: manufacturer.map(|s| s.to_string()),
             product: product.map(|s| s.to_string()),
             device_path: "/sys/devices/test".to_string(),
-            ..Default::default()
         }
     }
 
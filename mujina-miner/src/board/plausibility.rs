@@ -0,0 +1,340 @@
+//! Rejecting sensor readings that can't be true.
+//!
+//! A regulator whose telemetry glitches, or an I2C transfer that returns
+//! garbage without an error, can report a negative temperature or a core
+//! voltage the chip would never survive. Fed to the brownout guard, the
+//! power clamp or the fan curve, such a reading drives the board as if it
+//! were real. Each channel the regulator reports has plausible
+//! [`Bounds`]; a reading outside them is logged and rejected, the last good
+//! reading standing in for it. After [`IMPLAUSIBLE_POLLS`] rejected polls
+//! in a row the sensor is taken to be broken, and by default the board
+//! shuts itself down; with `MUJINA_IMPLAUSIBLE_READINGS=hold` it keeps
+//! running on the last good values instead.
+//!
+//! The ASIC temperature has its own range check in the thermal watchdog,
+//! which already shuts the board down after a few bad readings.
+
+use std::{env, fmt};
+
+use crate::tracing::prelude::*;
+
+/// Consecutive rejected polls of a channel before its sensor counts as
+/// broken.
+pub const IMPLAUSIBLE_POLLS: u32 = 3;
+
+/// The range of readings a channel can plausibly report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub channel: &'static str,
+    pub unit: &'static str,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Bounds {
+    fn contains(&self, value: f32) -> bool {
+        (self.min..=self.max).contains(&value)
+    }
+}
+
+/// The regulator's input, rated to 18 V.
+pub const INPUT_V: Bounds = Bounds {
+    channel: "input voltage",
+    unit: "V",
+    min: 0.0,
+    max: 20.0,
+};
+
+/// The ASIC core rail, well under 2 V on any chip the regulator feeds.
+pub const CORE_V: Bounds = Bounds {
+    channel: "core voltage",
+    unit: "V",
+    min: 0.0,
+    max: 2.0,
+};
+
+/// Core current, within the regulator's 40 A rating.
+pub const CORE_A: Bounds = Bounds {
+    channel: "core current",
+    unit: "A",
+    min: 0.0,
+    max: 40.0,
+};
+
+/// Core power, 40 A at the highest plausible core voltage.
+pub const CORE_W: Bounds = Bounds {
+    channel: "core power",
+    unit: "W",
+    min: 0.0,
+    max: 80.0,
+};
+
+/// The regulator's temperature, over its rated junction range.
+pub const VR_C: Bounds = Bounds {
+    channel: "regulator temperature",
+    unit: "C",
+    min: -40.0,
+    max: 150.0,
+};
+
+/// What a broken sensor leads to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImplausibleAction {
+    /// Shut the board down.
+    #[default]
+    Fault,
+    /// Keep running on the last good reading.
+    Hold,
+}
+
+impl ImplausibleAction {
+    /// Read the action from `MUJINA_IMPLAUSIBLE_READINGS`, `fault` or
+    /// `hold`, warning and faulting on an unrecognized value.
+    pub fn from_env() -> Self {
+        let Ok(val) = env::var("MUJINA_IMPLAUSIBLE_READINGS") else {
+            return Self::default();
+        };
+        match val.to_ascii_lowercase().as_str() {
+            "fault" => Self::Fault,
+            "hold" => Self::Hold,
+            _ => {
+                warn!(value = %val, "Invalid MUJINA_IMPLAUSIBLE_READINGS, using fault");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// A channel rejected for [`IMPLAUSIBLE_POLLS`] polls or more.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Implausible {
+    pub bounds: Bounds,
+    /// The latest rejected reading.
+    pub value: f32,
+    pub polls: u32,
+}
+
+impl fmt::Display for Implausible {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "implausible {}: {} {} for {} polls",
+            self.bounds.channel, self.value, self.bounds.unit, self.polls
+        )
+    }
+}
+
+/// Checks one channel's readings against its bounds.
+#[derive(Debug)]
+pub struct PlausibilityCheck {
+    bounds: Bounds,
+    last_good: Option<f32>,
+    /// Rejected polls in a row, with the latest rejected reading.
+    rejected: Option<(u32, f32)>,
+}
+
+impl PlausibilityCheck {
+    pub fn new(bounds: Bounds) -> Self {
+        Self {
+            bounds,
+            last_good: None,
+            rejected: None,
+        }
+    }
+
+    /// The reading to act on for `reading`: itself when plausible, else
+    /// the last good one. A missing reading stays missing and neither
+    /// counts against the channel nor clears it.
+    pub fn check(&mut self, reading: Option<f32>) -> Option<f32> {
+        let value = reading?;
+        if self.bounds.contains(value) {
+            if let Some((polls, _)) = self.rejected.take() {
+                info!(
+                    channel = self.bounds.channel,
+                    value, polls, "Sensor readings plausible again"
+                );
+            }
+            self.last_good = Some(value);
+            return Some(value);
+        }
+        let polls = self.rejected.map_or(0, |(polls, _)| polls) + 1;
+        self.rejected = Some((polls, value));
+        if polls == 1 {
+            warn!(
+                channel = self.bounds.channel,
+                value,
+                min = self.bounds.min,
+                max = self.bounds.max,
+                last_good = ?self.last_good,
+                "Rejecting implausible sensor reading"
+            );
+        } else {
+            trace!(
+                channel = self.bounds.channel,
+                value, polls, "Still implausible"
+            );
+        }
+        self.last_good
+    }
+
+    /// The channel's trouble, once rejected for [`IMPLAUSIBLE_POLLS`]
+    /// polls in a row.
+    pub fn sustained(&self) -> Option<Implausible> {
+        let (polls, value) = self.rejected?;
+        (polls >= IMPLAUSIBLE_POLLS).then_some(Implausible {
+            bounds: self.bounds,
+            value,
+            polls,
+        })
+    }
+}
+
+/// One poll of the regulator's telemetry, in volts, amps, watts and
+/// degrees Celsius.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RegulatorReadings {
+    pub input_v: Option<f32>,
+    pub core_v: Option<f32>,
+    pub core_a: Option<f32>,
+    pub core_w: Option<f32>,
+    pub temp_c: Option<f32>,
+}
+
+/// Checks every channel of a board's regulator.
+#[derive(Debug)]
+pub struct RegulatorChecks {
+    action: ImplausibleAction,
+    input_v: PlausibilityCheck,
+    core_v: PlausibilityCheck,
+    core_a: PlausibilityCheck,
+    core_w: PlausibilityCheck,
+    temp_c: PlausibilityCheck,
+}
+
+impl RegulatorChecks {
+    /// Check readings, taking `action` on a broken sensor.
+    pub fn new(action: ImplausibleAction) -> Self {
+        Self {
+            action,
+            input_v: PlausibilityCheck::new(INPUT_V),
+            core_v: PlausibilityCheck::new(CORE_V),
+            core_a: PlausibilityCheck::new(CORE_A),
+            core_w: PlausibilityCheck::new(CORE_W),
+            temp_c: PlausibilityCheck::new(VR_C),
+        }
+    }
+
+    /// The readings to act on for a poll, implausible ones replaced.
+    pub fn check(&mut self, readings: RegulatorReadings) -> RegulatorReadings {
+        RegulatorReadings {
+            input_v: self.input_v.check(readings.input_v),
+            core_v: self.core_v.check(readings.core_v),
+            core_a: self.core_a.check(readings.core_a),
+            core_w: self.core_w.check(readings.core_w),
+            temp_c: self.temp_c.check(readings.temp_c),
+        }
+    }
+
+    /// The broken sensor the board should fault on, if any. Always `None`
+    /// when told to hold.
+    pub fn fault(&self) -> Option<Implausible> {
+        if self.action == ImplausibleAction::Hold {
+            return None;
+        }
+        [
+            &self.input_v,
+            &self.core_v,
+            &self.core_a,
+            &self.core_w,
+            &self.temp_c,
+        ]
+        .into_iter()
+        .find_map(PlausibilityCheck::sustained)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    fn readings(core_v: f32, temp_c: f32) -> RegulatorReadings {
+        RegulatorReadings {
+            input_v: Some(5.1),
+            core_v: Some(core_v),
+            core_a: Some(12.0),
+            core_w: Some(14.4),
+            temp_c: Some(temp_c),
+        }
+    }
+
+    #[test]
+    fn implausible_readings_are_rejected_then_fault() {
+        let mut checks = RegulatorChecks::new(ImplausibleAction::Fault);
+        let good = readings(1.2, 48.0);
+        assert_eq!(checks.check(good), good);
+
+        // A glitch reads a negative temperature: the last good one stands
+        // in for it, and the other channels pass.
+        assert_eq!(checks.check(readings(1.21, -273.0)), readings(1.21, 48.0));
+        assert_eq!(checks.fault(), None);
+        // A missing reading is no evidence either way.
+        let unread = RegulatorReadings {
+            temp_c: None,
+            ..good
+        };
+        assert_eq!(checks.check(unread), unread);
+
+        // Kept up, the glitch is a broken sensor.
+        assert_eq!(checks.check(readings(1.2, -273.0)), good);
+        assert_eq!(checks.fault(), None);
+        assert_eq!(checks.check(readings(1.2, f32::NAN)).temp_c, Some(48.0));
+        let fault = checks.fault().unwrap();
+        assert_eq!(fault.bounds, VR_C);
+        assert_eq!(fault.polls, IMPLAUSIBLE_POLLS);
+        assert_eq!(
+            checks.check(readings(7.5, f32::NAN)).core_v,
+            Some(1.2),
+            "core voltage rejected as well"
+        );
+        assert_eq!(
+            checks.fault().unwrap().to_string(),
+            "implausible regulator temperature: NaN C for 4 polls"
+        );
+
+        // A good reading clears the channel.
+        checks.check(good);
+        assert_eq!(checks.fault(), None);
+
+        // Without a good reading yet, there is nothing to stand in.
+        let mut checks = RegulatorChecks::new(ImplausibleAction::Fault);
+        assert_eq!(checks.check(readings(9.0, 48.0)).core_v, None);
+    }
+
+    #[test]
+    fn hold_keeps_the_board_running() {
+        let mut checks = RegulatorChecks::new(ImplausibleAction::Hold);
+        checks.check(readings(1.2, 48.0));
+        for _ in 0..IMPLAUSIBLE_POLLS * 2 {
+            assert_eq!(checks.check(readings(1.2, 400.0)).temp_c, Some(48.0));
+        }
+        assert_eq!(checks.fault(), None);
+    }
+
+    #[test]
+    #[serial]
+    fn action_from_env() {
+        let var = "MUJINA_IMPLAUSIBLE_READINGS";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(ImplausibleAction::from_env(), ImplausibleAction::Fault);
+            env::set_var(var, "Hold");
+            assert_eq!(ImplausibleAction::from_env(), ImplausibleAction::Hold);
+            env::set_var(var, "ignore");
+            assert_eq!(ImplausibleAction::from_env(), ImplausibleAction::Fault);
+            env::remove_var(var);
+        }
+    }
+}
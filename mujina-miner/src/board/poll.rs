@@ -0,0 +1,137 @@
+//! Sensor polling cadence for board monitors.
+//!
+//! Routine polling (fans, regulator readings, telemetry, the periodic log)
+//! runs at `MUJINA_BOARD_POLL_MS`, or each board's own default. A slower
+//! cadence saves I2C traffic and CPU on small hosts; a faster one gives
+//! fresher telemetry. Whatever it is set to, a board with a thermal
+//! watchdog keeps checking its temperature every [`WATCHDOG_INTERVAL`].
+
+use std::env;
+use std::time::Duration;
+
+use tokio::time::{self, Interval, MissedTickBehavior};
+
+use crate::tracing::prelude::*;
+
+/// How often the thermal watchdog reads the temperature.
+pub(crate) const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The routine polling interval from `MUJINA_BOARD_POLL_MS`, or `default`
+/// when unset, warning and using `default` when invalid.
+pub(crate) fn interval_from_env(default: Duration) -> Duration {
+    let Ok(val) = env::var("MUJINA_BOARD_POLL_MS") else {
+        return default;
+    };
+    match val.parse::<u64>() {
+        Ok(ms) if ms > 0 => Duration::from_millis(ms),
+        _ => {
+            warn!(
+                value = %val,
+                default_ms = default.as_millis() as u64,
+                "Invalid MUJINA_BOARD_POLL_MS, using default"
+            );
+            default
+        }
+    }
+}
+
+/// Which poll is due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Due {
+    Watchdog,
+    Routine,
+}
+
+/// Interleaves the routine poll with the watchdog's.
+pub(crate) struct PollSchedule {
+    watchdog: Interval,
+    routine: Interval,
+}
+
+impl PollSchedule {
+    /// Both polls are due at once; missed ones are skipped, not made up.
+    pub(crate) fn new(routine: Duration) -> Self {
+        let interval = |period| {
+            let mut interval = time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            interval
+        };
+        Self {
+            watchdog: interval(WATCHDOG_INTERVAL),
+            routine: interval(routine),
+        }
+    }
+
+    /// Wait for the next poll. When both are due the watchdog goes
+    /// first, so routine telemetry carries its latest reading.
+    pub(crate) async fn next(&mut self) -> Due {
+        tokio::select! {
+            biased;
+            _ = self.watchdog.tick() => Due::Watchdog,
+            _ = self.routine.tick() => Due::Routine,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use tokio::time::Instant;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn routine_polls_follow_the_interval_and_the_watchdog_its_own() {
+        let start = Instant::now();
+        let mut schedule = PollSchedule::new(Duration::from_secs(10));
+        let mut watchdog = Vec::new();
+        let mut routine = Vec::new();
+        while routine.len() < 4 {
+            match schedule.next().await {
+                Due::Watchdog => watchdog.push(start.elapsed().as_secs()),
+                Due::Routine => routine.push(start.elapsed().as_secs()),
+            }
+        }
+
+        assert_eq!(routine, [0, 10, 20, 30]);
+        assert_eq!(watchdog, (0..=30).collect::<Vec<_>>());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn watchdog_keeps_its_rate_when_routine_polling_is_faster() {
+        let start = Instant::now();
+        let mut schedule = PollSchedule::new(Duration::from_millis(250));
+        let mut watchdog = 0;
+        let mut routine = 0;
+        while routine < 8 {
+            match schedule.next().await {
+                Due::Watchdog => watchdog += 1,
+                Due::Routine => routine += 1,
+            }
+        }
+
+        assert_eq!(start.elapsed(), Duration::from_millis(1750));
+        assert_eq!(watchdog, 2);
+    }
+
+    #[test]
+    #[serial]
+    fn interval_from_env_overrides_the_board_default() {
+        let default = Duration::from_secs(2);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_BOARD_POLL_MS") };
+        assert_eq!(interval_from_env(default), default);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_BOARD_POLL_MS", "10000") };
+        assert_eq!(interval_from_env(default), Duration::from_secs(10));
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_BOARD_POLL_MS", "0") };
+        assert_eq!(interval_from_env(default), default);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_BOARD_POLL_MS") };
+    }
+}
@@ -0,0 +1,176 @@
+//! A hard cap on a board's power draw.
+//!
+//! A safety net apart from profiles: whichever operating point is
+//! selected, through `MUJINA_PROFILE` or by hand through the API, a
+//! reading of the board's core power above `MUJINA_BOARD_MAX_POWER_W`
+//! cuts the clock at once. The cut is in proportion to the excess, and
+//! [`MARGIN`] deeper, since at a fixed voltage power follows the clock.
+//! Every reading still over the cap cuts again. The clock is given back
+//! [`RECOVERY_STEP`] at a time, and only while the power that step
+//! implies stays within [`MARGIN`] of the cap, so it settles below the
+//! cap instead of cycling across it.
+
+use std::env;
+
+use tokio::sync::watch;
+
+use crate::tracing::prelude::*;
+
+/// Fraction below the cap the clock is cut to, and must stay under to
+/// recover.
+pub const MARGIN: f32 = 0.05;
+
+/// Fraction of the profile's clock given back per reading once the
+/// power allows.
+pub const RECOVERY_STEP: f32 = 0.05;
+
+/// Least clock scale the clamp asks for. The model's minimum clock is
+/// the real floor; this only keeps the arithmetic away from zero.
+const MIN_SCALE: f32 = 0.1;
+
+/// Holds a board's clock down while its power is over the cap.
+#[derive(Debug)]
+pub struct PowerClamp {
+    /// `None` when disabled.
+    max_w: Option<f32>,
+    power_scale: watch::Sender<f32>,
+    scale: f32,
+}
+
+impl PowerClamp {
+    /// Clamp the clock behind `power_scale` to keep power at or below
+    /// `max_w`, or never when `None`.
+    pub fn new(max_w: Option<f32>, power_scale: watch::Sender<f32>) -> Self {
+        Self {
+            max_w,
+            power_scale,
+            scale: 1.0,
+        }
+    }
+
+    /// The cap from `MUJINA_BOARD_MAX_POWER_W`, unset or 0 to disable,
+    /// warning and disabling on invalid values.
+    pub fn max_from_env() -> Option<f32> {
+        let val = env::var("MUJINA_BOARD_MAX_POWER_W").ok()?;
+        match val.parse::<f32>() {
+            Ok(0.0) => None,
+            Ok(w) if w > 0.0 && w.is_finite() => Some(w),
+            _ => {
+                warn!(value = %val, "Invalid MUJINA_BOARD_MAX_POWER_W, no power cap");
+                None
+            }
+        }
+    }
+
+    /// Whether the clock is currently held down.
+    pub fn clamping(&self) -> bool {
+        self.scale < 1.0
+    }
+
+    /// Act on a power reading. A missing reading changes nothing.
+    pub fn observe(&mut self, power_w: Option<f32>) {
+        let (Some(max_w), Some(power_w)) = (self.max_w, power_w) else {
+            return;
+        };
+        if power_w > max_w {
+            let scale = (self.scale * max_w / power_w * (1.0 - MARGIN)).max(MIN_SCALE);
+            if scale >= self.scale {
+                return;
+            }
+            warn!(
+                power_w,
+                max_w,
+                clock_percent = (scale * 100.0).round(),
+                "Board power over its maximum, cutting clock"
+            );
+            self.set_scale(scale);
+        } else if self.clamping() {
+            let scale = (self.scale + RECOVERY_STEP).min(1.0);
+            if power_w * scale / self.scale > max_w * (1.0 - MARGIN) {
+                return;
+            }
+            if scale == 1.0 {
+                info!(power_w, max_w, "Board power back under its maximum");
+            }
+            self.set_scale(scale);
+        }
+    }
+
+    fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+        self.power_scale.send_replace(scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::board::profile::{self, Profile, ProfileSelection};
+
+    #[tokio::test]
+    async fn power_over_the_cap_cuts_the_clock_whatever_the_profile() {
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (profile_tx, mut selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+        let mut clamp = PowerClamp::new(Some(20.0), selection.power_scale());
+
+        // Turbo is picked by hand and draws more than the cap allows.
+        profile_tx.send_replace(Profile::Turbo);
+        assert_eq!(selection.changed().await.unwrap().frequency_mhz, 575.0);
+        clamp.observe(Some(19.0));
+        assert!(!clamp.clamping());
+
+        // One reading over: the clock drops at once, at turbo's voltage.
+        clamp.observe(Some(25.0));
+        let cut = selection.changed().await.unwrap();
+        let expected = 575.0 * 20.0 / 25.0 * (1.0 - MARGIN);
+        assert!((cut.frequency_mhz - expected).abs() < 0.01, "{cut:?}");
+        assert_eq!(
+            cut.core_voltage_v,
+            gamma.operating_point(Profile::Turbo).core_voltage_v
+        );
+
+        // Picking turbo again doesn't lift the cap.
+        profile_tx.send_replace(Profile::Turbo);
+        assert_eq!(selection.changed().await, Some(cut));
+
+        // Within the cap, the clock comes back a step at a time, and not
+        // while the next step would go over it.
+        clamp.observe(Some(18.9));
+        assert_eq!(selection.current(), cut);
+        clamp.observe(Some(17.0));
+        assert!(selection.current().frequency_mhz > cut.frequency_mhz);
+        while clamp.clamping() {
+            clamp.observe(Some(10.0));
+        }
+        assert_eq!(selection.current().frequency_mhz, 575.0);
+    }
+
+    #[test]
+    fn disabled_clamp_never_cuts_the_clock() {
+        let (power_scale, scale) = watch::channel(1.0);
+        let mut clamp = PowerClamp::new(None, power_scale);
+        clamp.observe(Some(500.0));
+        assert!(!clamp.clamping());
+        assert_eq!(*scale.borrow(), 1.0);
+    }
+
+    #[test]
+    #[serial]
+    fn max_from_env() {
+        let var = "MUJINA_BOARD_MAX_POWER_W";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(PowerClamp::max_from_env(), None);
+            env::set_var(var, "22.5");
+            assert_eq!(PowerClamp::max_from_env(), Some(22.5));
+            env::set_var(var, "0");
+            assert_eq!(PowerClamp::max_from_env(), None);
+            env::set_var(var, "lots");
+            assert_eq!(PowerClamp::max_from_env(), None);
+            env::remove_var(var);
+        }
+    }
+}
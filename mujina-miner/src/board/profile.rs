@@ -0,0 +1,576 @@
+//! Named operating profiles: eco, balanced and turbo.
+//!
+//! A profile is a preset ASIC clock and core voltage for a board model,
+//! for operators who want less power or more hashrate without tuning the
+//! two by hand. Each model lists its three operating points together with
+//! the limits they must stay within; a model without a table (including
+//! boards whose clock mujina doesn't control) simply has no profiles.
+//!
+//! The startup profile comes from `MUJINA_PROFILE`, applied after a
+//! warm-up at balanced when `MUJINA_PROFILE_WARMUP_SECS` is set (see
+//! [`warmup`](super::warmup)), and a board's profile can be changed while
+//! it runs through `PATCH /api/v0/boards/{name}`.
+//! Hash threads apply a change by ramping the clock through the same
+//! stepped PLL ramp used at power-on.
+//!
+//! Before anything is written, a new point is checked against the model's
+//! limits and the board's [`OperatingConditions`]: the power it would
+//! draw, scaled from the power measured at the running point, against the
+//! board's cap, and whether a board already at its target temperature has
+//! headroom for more power. The API checks a profile this way before
+//! selecting it, and the hash thread checks every point again before it
+//! applies it; see [`ModelProfiles::validate_operating_point`].
+
+use std::env;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use utoipa::ToSchema;
+
+use crate::tracing::prelude::*;
+
+/// Which operating point of a board model to run at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Lowest power: a reduced clock at a reduced core voltage.
+    Eco,
+    /// The model's stock clock and voltage.
+    #[default]
+    Balanced,
+    /// The highest hashrate the model's limits allow.
+    Turbo,
+}
+
+impl Profile {
+    /// Every profile, from lowest to highest power.
+    pub const ALL: [Self; 3] = [Self::Eco, Self::Balanced, Self::Turbo];
+
+    /// The profile's name as written in configuration and the API.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Eco => "eco",
+            Self::Balanced => "balanced",
+            Self::Turbo => "turbo",
+        }
+    }
+
+    /// Look up a profile by name, in any case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Read the startup profile from `MUJINA_PROFILE`, warning and falling
+    /// back to balanced on invalid values.
+    ///
+    /// A burn-in runs boards at turbo whatever `MUJINA_PROFILE` says.
+    pub fn from_env() -> Self {
+        if crate::burn_in::requested() {
+            return Self::Turbo;
+        }
+        let Ok(value) = env::var("MUJINA_PROFILE") else {
+            return Self::default();
+        };
+        Self::from_name(&value).unwrap_or_else(|| {
+            warn!(value = %value, "Invalid MUJINA_PROFILE, using balanced");
+            Self::default()
+        })
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An ASIC clock and the core voltage to run it at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OperatingPoint {
+    pub frequency_mhz: f32,
+    pub core_voltage_v: f32,
+}
+
+impl OperatingPoint {
+    /// Dynamic power in arbitrary units: CMOS power follows the clock and
+    /// the square of the voltage. Only ratios between points mean anything.
+    pub fn relative_power(&self) -> f32 {
+        self.frequency_mhz * self.core_voltage_v.powi(2)
+    }
+}
+
+/// How a board is running, as far as it bears on moving to another
+/// operating point.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OperatingConditions {
+    /// The point the chips run at, once one is applied.
+    pub running: Option<OperatingPoint>,
+    /// Core power measured since `running` was applied.
+    pub power_w: Option<f32>,
+    /// The board's power cap, if it has one.
+    pub max_power_w: Option<f32>,
+    /// The temperature thermal control acts on.
+    pub temp_c: Option<f32>,
+    /// The temperature thermal control holds the board toward.
+    pub target_c: Option<f32>,
+}
+
+/// Why an operating point is unsafe to apply.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+pub enum UnsafePoint {
+    #[error("{frequency_mhz} MHz is outside the model's {min_mhz}-{max_mhz} MHz")]
+    Frequency {
+        frequency_mhz: f32,
+        min_mhz: f32,
+        max_mhz: f32,
+    },
+    #[error("{core_voltage_v} V is outside the model's {min_v}-{max_v} V")]
+    Voltage {
+        core_voltage_v: f32,
+        min_v: f32,
+        max_v: f32,
+    },
+    #[error("would draw about {estimated_w:.1} W, over the board's {max_w} W cap")]
+    Power { estimated_w: f32, max_w: f32 },
+    #[error("no thermal headroom for more power: {temp_c:.1} °C, target {target_c} °C")]
+    Thermal { temp_c: f32, target_c: f32 },
+}
+
+/// Ranges a model's operating points must stay inside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OperatingLimits {
+    pub min_frequency_mhz: f32,
+    pub max_frequency_mhz: f32,
+    pub min_core_voltage_v: f32,
+    pub max_core_voltage_v: f32,
+}
+
+impl OperatingLimits {
+    /// Whether `point` is inside these limits.
+    pub fn contain(&self, point: OperatingPoint) -> bool {
+        (self.min_frequency_mhz..=self.max_frequency_mhz).contains(&point.frequency_mhz)
+            && (self.min_core_voltage_v..=self.max_core_voltage_v).contains(&point.core_voltage_v)
+    }
+}
+
+/// The profiles of one board model.
+#[derive(Debug)]
+pub struct ModelProfiles {
+    /// Board model, as in [`BoardInfo::model`](super::BoardInfo::model).
+    pub model: &'static str,
+    pub limits: OperatingLimits,
+    eco: OperatingPoint,
+    balanced: OperatingPoint,
+    turbo: OperatingPoint,
+}
+
+impl ModelProfiles {
+    /// The operating point `profile` selects on this model.
+    pub fn operating_point(&self, profile: Profile) -> OperatingPoint {
+        match profile {
+            Profile::Eco => self.eco,
+            Profile::Balanced => self.balanced,
+            Profile::Turbo => self.turbo,
+        }
+    }
+
+    /// Check that `point` is safe to apply under `conditions`, without
+    /// applying it.
+    ///
+    /// The point must be inside the model's limits. A point drawing more
+    /// than the running one must also keep the power, estimated by
+    /// [`OperatingPoint::relative_power`] from the power measured at the
+    /// running point, within the board's cap, and is refused while the
+    /// board is at or above its target temperature. Whatever can't be
+    /// judged for want of a reading passes; a point drawing less always
+    /// does.
+    pub fn validate_operating_point(
+        &self,
+        point: OperatingPoint,
+        conditions: &OperatingConditions,
+    ) -> Result<(), UnsafePoint> {
+        let limits = &self.limits;
+        if !(limits.min_frequency_mhz..=limits.max_frequency_mhz).contains(&point.frequency_mhz) {
+            return Err(UnsafePoint::Frequency {
+                frequency_mhz: point.frequency_mhz,
+                min_mhz: limits.min_frequency_mhz,
+                max_mhz: limits.max_frequency_mhz,
+            });
+        }
+        if !(limits.min_core_voltage_v..=limits.max_core_voltage_v).contains(&point.core_voltage_v)
+        {
+            return Err(UnsafePoint::Voltage {
+                core_voltage_v: point.core_voltage_v,
+                min_v: limits.min_core_voltage_v,
+                max_v: limits.max_core_voltage_v,
+            });
+        }
+
+        let Some(running) = conditions.running else {
+            return Ok(());
+        };
+        let ratio = point.relative_power() / running.relative_power();
+        if ratio <= 1.0 {
+            return Ok(());
+        }
+        if let (Some(power_w), Some(max_w)) = (conditions.power_w, conditions.max_power_w) {
+            let estimated_w = power_w * ratio;
+            if estimated_w > max_w {
+                return Err(UnsafePoint::Power { estimated_w, max_w });
+            }
+        }
+        if let (Some(temp_c), Some(target_c)) = (conditions.temp_c, conditions.target_c)
+            && temp_c >= target_c
+        {
+            return Err(UnsafePoint::Thermal { temp_c, target_c });
+        }
+        Ok(())
+    }
+}
+
+/// Checks operating points against one board's model and conditions.
+///
+/// Cloned from a [`ProfileSelection`] for whatever selects profiles from
+/// outside the hash thread, such as the API.
+#[derive(Debug, Clone)]
+pub struct OperatingGuard {
+    profiles: &'static ModelProfiles,
+    conditions: watch::Receiver<OperatingConditions>,
+}
+
+impl OperatingGuard {
+    /// Check that `point` is safe to apply now.
+    pub fn validate_operating_point(&self, point: OperatingPoint) -> Result<(), UnsafePoint> {
+        self.profiles
+            .validate_operating_point(point, &self.conditions.borrow())
+    }
+
+    /// Check that `profile`'s operating point is safe to apply now.
+    pub fn validate_profile(&self, profile: Profile) -> Result<(), UnsafePoint> {
+        self.validate_operating_point(self.profiles.operating_point(profile))
+    }
+}
+
+/// A model's profiles and the profile currently selected for one board.
+///
+/// Held by the board's hash thread. The selection is changed through the
+/// [`watch::Sender`] returned by [`channel`](Self::channel), which the
+/// board registers with the API. The board itself can hold the clock
+/// below the profile's for a while, through [`clock_scale`](Self::clock_scale)
+/// while its supply sags, [`thermal_scale`](Self::thermal_scale) while
+/// it runs hot and [`power_scale`](Self::power_scale) while it draws too
+/// much; the three multiply. The board reports its readings through
+/// [`conditions`](Self::conditions) for [`validate`](Self::validate).
+pub struct ProfileSelection {
+    profiles: &'static ModelProfiles,
+    conditions_tx: watch::Sender<OperatingConditions>,
+    selected: watch::Receiver<Profile>,
+    scale_tx: watch::Sender<f32>,
+    scale: watch::Receiver<f32>,
+    thermal_tx: watch::Sender<f32>,
+    thermal: watch::Receiver<f32>,
+    power_tx: watch::Sender<f32>,
+    power: watch::Receiver<f32>,
+}
+
+impl ProfileSelection {
+    /// A selection starting at `initial`, and the sender that changes it.
+    pub fn channel(
+        profiles: &'static ModelProfiles,
+        initial: Profile,
+    ) -> (watch::Sender<Profile>, Self) {
+        let (tx, selected) = watch::channel(initial);
+        let (scale_tx, scale) = watch::channel(1.0);
+        let (thermal_tx, thermal) = watch::channel(1.0);
+        let (power_tx, power) = watch::channel(1.0);
+        (
+            tx,
+            Self {
+                profiles,
+                conditions_tx: watch::Sender::new(OperatingConditions::default()),
+                selected,
+                scale_tx,
+                scale,
+                thermal_tx,
+                thermal,
+                power_tx,
+                power,
+            },
+        )
+    }
+
+    /// A sender for the fraction of the profile's clock to run at, 1.0
+    /// unless the board is holding it down. The core voltage stays the
+    /// profile's.
+    pub fn clock_scale(&self) -> watch::Sender<f32> {
+        self.scale_tx.clone()
+    }
+
+    /// Like [`clock_scale`](Self::clock_scale), for the thermal throttle.
+    pub fn thermal_scale(&self) -> watch::Sender<f32> {
+        self.thermal_tx.clone()
+    }
+
+    /// Like [`clock_scale`](Self::clock_scale), for the power cap.
+    pub fn power_scale(&self) -> watch::Sender<f32> {
+        self.power_tx.clone()
+    }
+
+    /// A sender for the board's readings and limits. The running point is
+    /// recorded through [`applied`](Self::applied) instead.
+    pub fn conditions(&self) -> watch::Sender<OperatingConditions> {
+        self.conditions_tx.clone()
+    }
+
+    /// A guard checking points against this board, for use elsewhere.
+    pub fn guard(&self) -> OperatingGuard {
+        OperatingGuard {
+            profiles: self.profiles,
+            conditions: self.conditions_tx.subscribe(),
+        }
+    }
+
+    /// Check that `point` is safe to apply now, without applying it.
+    pub fn validate(&self, point: OperatingPoint) -> Result<(), UnsafePoint> {
+        self.profiles
+            .validate_operating_point(point, &self.conditions_tx.borrow())
+    }
+
+    /// Record that the chips now run at `point`. The power measured
+    /// before no longer applies.
+    pub fn applied(&self, point: OperatingPoint) {
+        self.conditions_tx.send_modify(|conditions| {
+            conditions.running = Some(point);
+            conditions.power_w = None;
+        });
+    }
+
+    /// The operating point of the selected profile, at the current clock
+    /// scales but no slower than the model allows.
+    pub fn current(&self) -> OperatingPoint {
+        let mut point = self.profiles.operating_point(*self.selected.borrow());
+        let scaled = point.frequency_mhz
+            * *self.scale.borrow()
+            * *self.thermal.borrow()
+            * *self.power.borrow();
+        if scaled < point.frequency_mhz {
+            point.frequency_mhz = scaled.max(self.profiles.limits.min_frequency_mhz);
+        }
+        point
+    }
+
+    /// Wait for a new selection or clock scale and return the operating
+    /// point, or `None` once the selection sender is gone.
+    pub async fn changed(&mut self) -> Option<OperatingPoint> {
+        tokio::select! {
+            changed = self.selected.changed() => changed.ok()?,
+            // Never closes: the selection holds a sender.
+            Ok(()) = self.scale.changed() => {}
+            Ok(()) = self.thermal.changed() => {}
+            Ok(()) = self.power.changed() => {}
+        }
+        Some(self.current())
+    }
+}
+
+/// Bitaxe Gamma (single BM1370).
+///
+/// Balanced is the clock and voltage the board has always run at. The
+/// voltage ceiling is the TPS546's over-voltage warning limit, so turbo
+/// never trips the regulator's protection; the floor is its `VOUT_MIN`.
+const BITAXE_GAMMA: ModelProfiles = ModelProfiles {
+    model: "Bitaxe Gamma",
+    limits: OperatingLimits {
+        min_frequency_mhz: 400.0,
+        max_frequency_mhz: 600.0,
+        min_core_voltage_v: 1.00,
+        max_core_voltage_v: 1.16,
+    },
+    eco: OperatingPoint {
+        frequency_mhz: 400.0,
+        core_voltage_v: 1.05,
+    },
+    balanced: OperatingPoint {
+        frequency_mhz: 525.0,
+        core_voltage_v: 1.15,
+    },
+    turbo: OperatingPoint {
+        frequency_mhz: 575.0,
+        core_voltage_v: 1.16,
+    },
+};
+
+static MODELS: &[ModelProfiles] = &[BITAXE_GAMMA];
+
+/// The profile table for a board model, if it has one.
+pub fn for_model(model: &str) -> Option<&'static ModelProfiles> {
+    MODELS.iter().find(|m| m.model == model)
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    fn every_profile_is_within_the_model_limits() {
+        for model in MODELS {
+            for profile in Profile::ALL {
+                let point = model.operating_point(profile);
+                assert!(
+                    model.limits.contain(point),
+                    "{} {profile}: {point:?} outside {:?}",
+                    model.model,
+                    model.limits
+                );
+            }
+            let power = |p| model.operating_point(p).relative_power();
+            assert!(power(Profile::Eco) < power(Profile::Balanced));
+            assert!(power(Profile::Balanced) < power(Profile::Turbo));
+        }
+    }
+
+    #[test]
+    fn bitaxe_gamma_profiles() {
+        let gamma = for_model("Bitaxe Gamma").unwrap();
+        let point = |frequency_mhz, core_voltage_v| OperatingPoint {
+            frequency_mhz,
+            core_voltage_v,
+        };
+        assert_eq!(gamma.operating_point(Profile::Eco), point(400.0, 1.05));
+        assert_eq!(gamma.operating_point(Profile::Balanced), point(525.0, 1.15));
+        assert_eq!(gamma.operating_point(Profile::Turbo), point(575.0, 1.16));
+        assert!(for_model("emberOne/00").is_none());
+    }
+
+    #[test]
+    fn unsafe_points_are_refused_before_anything_is_written() {
+        let gamma = for_model("Bitaxe Gamma").unwrap();
+        let point = |frequency_mhz, core_voltage_v| OperatingPoint {
+            frequency_mhz,
+            core_voltage_v,
+        };
+        let balanced = gamma.operating_point(Profile::Balanced);
+        let turbo = gamma.operating_point(Profile::Turbo);
+        let eco = gamma.operating_point(Profile::Eco);
+        // Running balanced at 15 W against an 18 W cap, well under the
+        // 60 °C target: turbo draws 15 * 1.113 ~ 16.7 W and fits.
+        let cool = OperatingConditions {
+            running: Some(balanced),
+            power_w: Some(15.0),
+            max_power_w: Some(18.0),
+            temp_c: Some(52.0),
+            target_c: Some(60.0),
+        };
+        for profile in Profile::ALL {
+            let point = gamma.operating_point(profile);
+            assert_eq!(gamma.validate_operating_point(point, &cool), Ok(()));
+        }
+        // With nothing known yet, only the limits are checked.
+        let unknown = OperatingConditions::default();
+        assert_eq!(gamma.validate_operating_point(turbo, &unknown), Ok(()));
+
+        assert_eq!(
+            gamma.validate_operating_point(point(650.0, 1.15), &unknown),
+            Err(UnsafePoint::Frequency {
+                frequency_mhz: 650.0,
+                min_mhz: 400.0,
+                max_mhz: 600.0,
+            })
+        );
+        assert_eq!(
+            gamma.validate_operating_point(point(525.0, 1.3), &unknown),
+            Err(UnsafePoint::Voltage {
+                core_voltage_v: 1.3,
+                min_v: 1.0,
+                max_v: 1.16,
+            })
+        );
+
+        let drawing = OperatingConditions {
+            power_w: Some(17.0),
+            ..cool
+        };
+        let Err(UnsafePoint::Power { estimated_w, max_w }) =
+            gamma.validate_operating_point(turbo, &drawing)
+        else {
+            panic!("turbo over the cap allowed");
+        };
+        assert!(
+            (estimated_w - 17.0 * turbo.relative_power() / balanced.relative_power()).abs() < 1e-3
+        );
+        assert_eq!(max_w, 18.0);
+
+        let hot = OperatingConditions {
+            temp_c: Some(61.5),
+            ..cool
+        };
+        assert_eq!(
+            gamma.validate_operating_point(turbo, &hot),
+            Err(UnsafePoint::Thermal {
+                temp_c: 61.5,
+                target_c: 60.0,
+            })
+        );
+        assert_eq!(
+            gamma
+                .validate_operating_point(turbo, &hot)
+                .unwrap_err()
+                .to_string(),
+            "no thermal headroom for more power: 61.5 °C, target 60 °C"
+        );
+        // Drawing less is always allowed, hot or over the cap.
+        for conditions in [drawing, hot] {
+            assert_eq!(gamma.validate_operating_point(eco, &conditions), Ok(()));
+            assert_eq!(
+                gamma.validate_operating_point(balanced, &conditions),
+                Ok(())
+            );
+        }
+
+        // The selection and its guard judge by the conditions the board
+        // reports.
+        let (_profile_tx, selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+        let guard = selection.guard();
+        selection.applied(balanced);
+        selection
+            .conditions()
+            .send_modify(|c| (c.temp_c, c.target_c) = (Some(61.5), Some(60.0)));
+        assert!(matches!(
+            guard.validate_profile(Profile::Turbo),
+            Err(UnsafePoint::Thermal { .. })
+        ));
+        assert!(selection.validate(turbo).is_err());
+        assert_eq!(guard.validate_profile(Profile::Eco), Ok(()));
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(Profile::from_name("eco"), Some(Profile::Eco));
+        assert_eq!(Profile::from_name("Turbo"), Some(Profile::Turbo));
+        assert_eq!(Profile::from_name("max"), None);
+        for profile in Profile::ALL {
+            assert_eq!(Profile::from_name(&profile.to_string()), Some(profile));
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn profile_from_env() {
+        let var = "MUJINA_PROFILE";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(Profile::from_env(), Profile::Balanced);
+            env::set_var(var, "eco");
+            assert_eq!(Profile::from_env(), Profile::Eco);
+            env::set_var(var, "ludicrous");
+            assert_eq!(Profile::from_env(), Profile::Balanced);
+            env::remove_var(var);
+        }
+    }
+}
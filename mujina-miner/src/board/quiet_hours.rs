@@ -0,0 +1,286 @@
+//! Quieter fans during scheduled hours.
+//!
+//! A miner at home is heard at night. `MUJINA_QUIET_HOURS` names a daily
+//! window, such as `22:00-07:00`, during which the fan curve is capped at
+//! `MUJINA_QUIET_FAN_MAX_PERCENT` and, since the capped fan cools less,
+//! the clock runs at `MUJINA_QUIET_CLOCK_PERCENT` of the profile's. Times
+//! are at the fixed offset `MUJINA_QUIET_HOURS_OFFSET` from UTC, UTC
+//! unless set, so the window doesn't follow daylight saving changes.
+//!
+//! A board follows the window through a [`QuietSchedule`], which reads the
+//! time of day from a [`Clock`].
+//!
+//! Quiet never outranks thermal safety. A board that heats past what the
+//! curve answers with full speed gets full speed, cap or not, and the
+//! thermal throttle cuts its clock further, exactly as outside quiet
+//! hours.
+
+use std::env;
+use std::sync::Arc;
+
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::clock::Clock;
+use crate::peripheral::emc2101::Percent;
+use crate::tracing::prelude::*;
+use crate::types::parse_utc_offset;
+
+use super::thermal::MIN_FAN;
+
+/// Fan cap during quiet hours when not configured.
+pub const DEFAULT_MAX_FAN: Percent = Percent::new_clamped(40);
+
+/// Fraction of the profile's clock run at during quiet hours when not
+/// configured.
+pub const DEFAULT_CLOCK: f32 = 0.8;
+
+/// A daily quiet window and what it holds the board to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    /// Start of the window, in minutes after midnight.
+    start: u16,
+    /// End of the window, in minutes after midnight; before `start` when
+    /// the window spans midnight.
+    end: u16,
+    offset: UtcOffset,
+    /// Most the fan curve may call for.
+    pub max_fan: Percent,
+    /// Fraction of the profile's clock to run at.
+    pub clock: f32,
+}
+
+impl QuietHours {
+    /// Quiet from `start` to `end`, each written `HH:MM`, at `offset`,
+    /// with the default fan cap and clock.
+    pub fn new(start: &str, end: &str, offset: UtcOffset) -> Option<Self> {
+        Some(Self {
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+            offset,
+            max_fan: DEFAULT_MAX_FAN,
+            clock: DEFAULT_CLOCK,
+        })
+    }
+
+    /// Read `MUJINA_QUIET_HOURS` and the settings beside it. `None` when
+    /// unset, or invalid with a warning; an invalid fan cap, clock or
+    /// offset keeps its default with a warning.
+    pub fn from_env() -> Option<Self> {
+        let window = env::var("MUJINA_QUIET_HOURS").ok()?;
+        let Some(mut quiet) = window
+            .split_once('-')
+            .and_then(|(start, end)| Self::new(start, end, UtcOffset::UTC))
+        else {
+            warn!(value = %window, "Invalid MUJINA_QUIET_HOURS, no quiet hours");
+            return None;
+        };
+        if let Ok(val) = env::var("MUJINA_QUIET_HOURS_OFFSET") {
+            match parse_utc_offset(&val) {
+                Some(offset) => quiet.offset = offset,
+                None => warn!(value = %val, "Invalid MUJINA_QUIET_HOURS_OFFSET, using UTC"),
+            }
+        }
+        if let Ok(val) = env::var("MUJINA_QUIET_FAN_MAX_PERCENT") {
+            match val.parse::<u8>().ok().and_then(Percent::new) {
+                Some(cap) if cap >= MIN_FAN => quiet.max_fan = cap,
+                _ => warn!(value = %val, "Invalid MUJINA_QUIET_FAN_MAX_PERCENT, using default"),
+            }
+        }
+        if let Ok(val) = env::var("MUJINA_QUIET_CLOCK_PERCENT") {
+            match val.parse::<u8>() {
+                Ok(percent @ 1..=100) => quiet.clock = f32::from(percent) / 100.0,
+                _ => warn!(value = %val, "Invalid MUJINA_QUIET_CLOCK_PERCENT, using default"),
+            }
+        }
+        Some(quiet)
+    }
+
+    /// Whether `at` falls in the window.
+    pub fn active_at(&self, at: OffsetDateTime) -> bool {
+        let (hour, minute, _) = at.to_offset(self.offset).to_hms();
+        let now = u16::from(hour) * 60 + u16::from(minute);
+        if self.start <= self.end {
+            (self.start..self.end).contains(&now)
+        } else {
+            now >= self.start || now < self.end
+        }
+    }
+}
+
+/// A board's quiet hours, and whether they are in force by its clock.
+#[derive(Debug)]
+pub struct QuietSchedule {
+    hours: QuietHours,
+    clock: Arc<dyn Clock>,
+    quiet: bool,
+}
+
+impl QuietSchedule {
+    /// Follow `hours` by the time of day `clock` reads, outside them to
+    /// begin with.
+    pub fn new(hours: QuietHours, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            hours,
+            clock,
+            quiet: false,
+        }
+    }
+
+    /// The window followed and what it holds the board to.
+    pub fn hours(&self) -> &QuietHours {
+        &self.hours
+    }
+
+    /// `Some(true)` when quiet hours have started since the last check,
+    /// `Some(false)` when they have ended, `None` when nothing changed.
+    pub fn check(&mut self) -> Option<bool> {
+        let quiet = self.hours.active_at(self.clock.now_utc());
+        if quiet == self.quiet {
+            return None;
+        }
+        self.quiet = quiet;
+        Some(quiet)
+    }
+}
+
+/// Minutes after midnight of a time written `HH:MM`.
+fn parse_time(text: &str) -> Option<u16> {
+    let (hours, minutes) = text.trim().split_once(':')?;
+    let hours: u16 = hours.parse().ok().filter(|h| *h < 24)?;
+    let minutes: u16 = minutes.parse().ok().filter(|m| *m < 60)?;
+    Some(hours * 60 + minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serial_test::serial;
+    use time::macros::{datetime, offset};
+
+    use super::*;
+    use crate::board::profile::{self, Profile, ProfileSelection};
+    use crate::board::thermal::{FanControl, ThermalThrottle, fan_speed};
+    use crate::clock::FakeClock;
+
+    #[test]
+    fn window_spans_midnight_at_its_offset() {
+        let quiet = QuietHours::new("22:00", "07:00", offset!(+2)).unwrap();
+        assert!(!quiet.active_at(datetime!(2026-06-01 19:59 UTC)));
+        assert!(quiet.active_at(datetime!(2026-06-01 20:00 UTC)));
+        assert!(quiet.active_at(datetime!(2026-06-02 04:59 UTC)));
+        assert!(!quiet.active_at(datetime!(2026-06-02 05:00 UTC)));
+
+        let afternoon = QuietHours::new("13:00", "15:30", UtcOffset::UTC).unwrap();
+        assert!(afternoon.active_at(datetime!(2026-06-01 15:29 UTC)));
+        assert!(!afternoon.active_at(datetime!(2026-06-01 15:30 UTC)));
+        assert!(!afternoon.active_at(datetime!(2026-06-01 12:00 UTC)));
+
+        assert_eq!(QuietHours::new("24:00", "07:00", UtcOffset::UTC), None);
+        assert_eq!(QuietHours::new("22", "07:00", UtcOffset::UTC), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn schedule_follows_the_boards_clock() {
+        let clock = FakeClock::new(datetime!(2026-06-01 21:59 UTC));
+        let hours = QuietHours::new("22:00", "07:00", UtcOffset::UTC).unwrap();
+        let mut schedule = QuietSchedule::new(hours, clock.clone());
+        assert_eq!(schedule.check(), None);
+
+        clock.advance(Duration::from_secs(60)).await;
+        assert_eq!(schedule.check(), Some(true));
+        assert_eq!(schedule.check(), None);
+
+        clock.advance(Duration::from_secs(9 * 3600)).await;
+        assert_eq!(schedule.check(), Some(false));
+        assert_eq!(schedule.check(), None);
+    }
+
+    #[tokio::test]
+    async fn quiet_hours_cap_the_fan_until_safety_needs_it() {
+        let target_c = 60.0;
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (_profile_tx, mut selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+        let (_manual_tx, mut fan) = FanControl::channel(Some(target_c));
+        let mut throttle = ThermalThrottle::new(Some(target_c), selection.thermal_scale());
+
+        // Quiet hours begin: the fan is held under the cap where the
+        // curve would run it faster, and the clock drops to match.
+        fan.set_quiet(Some(DEFAULT_MAX_FAN));
+        throttle.set_quiet(Some(DEFAULT_CLOCK));
+        assert!(fan_speed(58.0, target_c) > DEFAULT_MAX_FAN);
+        assert_eq!(fan.speed(Some(58.0)), Some(DEFAULT_MAX_FAN));
+        assert_eq!(fan.speed(Some(45.0)), Some(fan_speed(45.0, target_c)));
+        let quiet = selection.changed().await.unwrap();
+        assert_eq!(quiet.frequency_mhz, 525.0 * DEFAULT_CLOCK);
+
+        // The capped fan can't hold the board even at the lower clock:
+        // safety takes the fan to full speed and cuts the clock further,
+        // as far as the model allows.
+        throttle.observe(Some(66.0));
+        assert_eq!(fan.speed(Some(66.0)), Some(Percent::FULL));
+        let hot = selection.changed().await.unwrap();
+        assert!(hot.frequency_mhz < quiet.frequency_mhz);
+        // 525 MHz at 80%, then 85% of that, is below the Gamma's floor.
+        assert_eq!(hot.frequency_mhz, 400.0);
+
+        // Back at the target, quiet resumes.
+        throttle.observe(Some(60.0));
+        assert_eq!(fan.speed(Some(60.0)), Some(DEFAULT_MAX_FAN));
+        assert_eq!(selection.changed().await, Some(quiet));
+
+        // Quiet hours end: the curve and clock are the profile's again.
+        fan.set_quiet(None);
+        throttle.set_quiet(None);
+        assert_eq!(fan.speed(Some(58.0)), Some(fan_speed(58.0, target_c)));
+        assert_eq!(selection.changed().await.unwrap().frequency_mhz, 525.0);
+    }
+
+    #[test]
+    #[serial]
+    fn settings_from_env() {
+        let vars = [
+            "MUJINA_QUIET_HOURS",
+            "MUJINA_QUIET_HOURS_OFFSET",
+            "MUJINA_QUIET_FAN_MAX_PERCENT",
+            "MUJINA_QUIET_CLOCK_PERCENT",
+        ];
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            for var in vars {
+                env::remove_var(var);
+            }
+            assert_eq!(QuietHours::from_env(), None);
+
+            env::set_var("MUJINA_QUIET_HOURS", "23:30-06:15");
+            env::set_var("MUJINA_QUIET_HOURS_OFFSET", "-05:00");
+            env::set_var("MUJINA_QUIET_FAN_MAX_PERCENT", "30");
+            env::set_var("MUJINA_QUIET_CLOCK_PERCENT", "70");
+        }
+        let quiet = QuietHours::from_env().unwrap();
+        assert_eq!(quiet.max_fan, Percent::new_clamped(30));
+        assert_eq!(quiet.clock, 0.7);
+        assert!(quiet.active_at(datetime!(2026-06-02 04:30 UTC)));
+
+        // A cap the fan can't run at while hashing keeps the default.
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_QUIET_FAN_MAX_PERCENT", "10");
+            env::set_var("MUJINA_QUIET_CLOCK_PERCENT", "0");
+        }
+        let quiet = QuietHours::from_env().unwrap();
+        assert_eq!(
+            (quiet.max_fan, quiet.clock),
+            (DEFAULT_MAX_FAN, DEFAULT_CLOCK)
+        );
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_QUIET_HOURS", "nights");
+            assert_eq!(QuietHours::from_env(), None);
+            for var in vars {
+                env::remove_var(var);
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Startup self-test.
+//!
+//! A board checks its own hardware as it comes up, such as whether the
+//! expected chips answered and the fan turns, and hands the outcome to the
+//! backplane with its connection. `MUJINA_SELF_TEST_ON_START` sets what a
+//! failure does: nothing beyond a debug log (`off`, the default), a
+//! warning while the board mines anyway (`warn`), or isolation
+//! (`enforce`): the board is powered off without mining and held as shut
+//! down until re-enabled through the API, which tests it again.
+
+use std::env;
+use std::fmt;
+
+use crate::tracing::prelude::*;
+
+/// What a failed self-test does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelfTestMode {
+    /// Log at debug level and mine.
+    #[default]
+    Off,
+    /// Warn and mine.
+    Warn,
+    /// Power the board off and keep it from mining.
+    Enforce,
+}
+
+impl SelfTestMode {
+    /// Read `MUJINA_SELF_TEST_ON_START`, warning and keeping the default
+    /// when invalid.
+    pub fn from_env() -> Self {
+        let Ok(value) = env::var("MUJINA_SELF_TEST_ON_START") else {
+            return Self::default();
+        };
+        match value.to_ascii_lowercase().as_str() {
+            "off" => Self::Off,
+            "warn" => Self::Warn,
+            "enforce" => Self::Enforce,
+            _ => {
+                warn!(value = %value, "Invalid MUJINA_SELF_TEST_ON_START, using off");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// The checks a board failed, in the order it ran them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestFailure {
+    pub failed: Vec<String>,
+}
+
+impl SelfTestFailure {
+    /// The outcome of a self-test that failed the checks described by
+    /// `failed`, a pass when there are none.
+    pub fn outcome(failed: Vec<String>) -> Result<(), Self> {
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(Self { failed })
+        }
+    }
+}
+
+impl fmt::Display for SelfTestFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "self-test failed: {}", self.failed.join("; "))
+    }
+}
+
+impl std::error::Error for SelfTestFailure {}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    fn failures_are_listed_in_order() {
+        assert_eq!(SelfTestFailure::outcome(Vec::new()), Ok(()));
+        let failure =
+            SelfTestFailure::outcome(vec!["fan at 0 RPM".into(), "input at 4.1 V".into()])
+                .unwrap_err();
+        assert_eq!(
+            failure.to_string(),
+            "self-test failed: fan at 0 RPM; input at 4.1 V"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn mode_env_parsing() {
+        let cases = [
+            (None, SelfTestMode::Off),
+            (Some("warn"), SelfTestMode::Warn),
+            (Some("ENFORCE"), SelfTestMode::Enforce),
+            (Some("off"), SelfTestMode::Off),
+            (Some("strict"), SelfTestMode::Off),
+        ];
+        for (value, expected) in cases {
+            // SAFETY: Test runs serially, no concurrent env access
+            unsafe {
+                match value {
+                    Some(v) => env::set_var("MUJINA_SELF_TEST_ON_START", v),
+                    None => env::remove_var("MUJINA_SELF_TEST_ON_START"),
+                }
+            }
+            assert_eq!(SelfTestMode::from_env(), expected, "value {value:?}");
+        }
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_SELF_TEST_ON_START") };
+    }
+}
@@ -0,0 +1,747 @@
+//! Per-board target temperature for fan control and thermal throttling.
+//!
+//! A board given a target temperature is cooled toward it. The fan speeds
+//! up as the ASIC warms toward the target, reaching full speed
+//! [`THROTTLE_ABOVE_C`] above it. When full speed can't hold the board
+//! there, the clock is cut to [`THROTTLED_CLOCK`] of the profile's until
+//! the board is back at its target.
+//!
+//! A board's target comes from `MUJINA_BOARD_TARGET_TEMPS` (`board=°C`
+//! pairs, boards named as in telemetry), else `MUJINA_TARGET_TEMP_C`. A
+//! board with neither has no target: its fan runs at full speed and its
+//! clock is never throttled. A target must sit at least [`MIN_MARGIN_C`]
+//! below [`CRITICAL_TEMP_C`], where boards shut themselves down, so the
+//! throttle always acts first; a closer one is rejected.
+//!
+//! Boards read both the ASIC die and the core voltage regulator, and
+//! either can be the one near its limit. Fan control and the throttle act
+//! on the hotter of the two unless `MUJINA_THERMAL_SOURCE` picks one; the
+//! emergency shutdown at [`CRITICAL_TEMP_C`] stays on the ASIC reading.
+//!
+//! An operator can set a board's fan to a fixed duty cycle instead, for
+//! maintenance or quiet. The curve takes back over when the board gets
+//! hot enough for it to call for full speed, or on a board without a
+//! target, as hot as the curve of the highest allowed target would. During quiet hours
+//! ([`super::quiet_hours`]) the curve is capped and the clock lowered to
+//! match, with the same exception.
+//!
+//! Whatever sets it, the fan never runs below its [`FanFloor`]: at very
+//! low duty a fan can stall, or turn without moving enough air. The floor
+//! is [`MIN_FAN`] unless `MUJINA_FAN_MIN_PERCENT` raises or lowers it, and
+//! a duty below it, from the curve, the quiet-hours cap or a duty set by
+//! hand, is raised to it. A fan is switched fully off only with
+//! `MUJINA_FAN_ALLOW_OFF` set and a duty of 0 asked for by hand.
+//!
+//! A board built without the ASIC's temperature diode can't be cooled
+//! toward a target at all. Such a board runs with fan control and the
+//! throttle off and the fan fixed at [`SAFE_FAN_DUTY`], rather than
+//! failing the thermal watchdog and shutting down.
+
+use std::collections::HashMap;
+use std::env;
+
+use thiserror::Error;
+use tokio::sync::watch;
+
+use crate::api_client::types::TemperatureSensor;
+use crate::peripheral::emc2101::Percent;
+use crate::tracing::prelude::*;
+use crate::types::Temperature;
+
+/// ASIC temperature at which a board shuts itself down.
+pub const CRITICAL_TEMP_C: f32 = 80.0;
+
+/// Fan duty for a board without an ASIC temperature sensor. With no way
+/// to tell how hot the chip runs, the fan runs flat out.
+pub const SAFE_FAN_DUTY: Percent = Percent::FULL;
+
+/// Least distance between a target and [`CRITICAL_TEMP_C`].
+pub const MIN_MARGIN_C: f32 = 10.0;
+
+/// How far below the target the fan runs at its slowest.
+pub const FAN_BAND_C: f32 = 10.0;
+
+/// Slowest fan speed while hashing.
+pub const MIN_FAN: Percent = Percent::new_clamped(25);
+
+/// The least duty a fan runs at, short of off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanFloor {
+    pub min: Percent,
+    /// Whether a duty of 0 switches the fan off rather than running it
+    /// at `min`.
+    pub allow_off: bool,
+}
+
+impl Default for FanFloor {
+    fn default() -> Self {
+        Self {
+            min: MIN_FAN,
+            allow_off: false,
+        }
+    }
+}
+
+impl FanFloor {
+    /// Read `MUJINA_FAN_MIN_PERCENT` (1 to 100) and `MUJINA_FAN_ALLOW_OFF`
+    /// (`true` or `false`), warning and keeping the default for either
+    /// when invalid.
+    pub fn from_env() -> Self {
+        let mut floor = Self::default();
+        if let Ok(val) = env::var("MUJINA_FAN_MIN_PERCENT") {
+            match val.parse::<u8>().ok().and_then(Percent::new) {
+                Some(min) if min > Percent::ZERO => floor.min = min,
+                _ => warn!(value = %val, "Invalid MUJINA_FAN_MIN_PERCENT, using default"),
+            }
+        }
+        if let Ok(val) = env::var("MUJINA_FAN_ALLOW_OFF") {
+            match val.to_ascii_lowercase().as_str() {
+                "true" | "1" => floor.allow_off = true,
+                "false" | "0" => floor.allow_off = false,
+                _ => warn!(value = %val, "Invalid MUJINA_FAN_ALLOW_OFF, using false"),
+            }
+        }
+        floor
+    }
+
+    /// The duty to run at when `duty` is asked for.
+    pub fn apply(&self, duty: Percent) -> Percent {
+        if duty == Percent::ZERO && self.allow_off {
+            duty
+        } else {
+            duty.max(self.min)
+        }
+    }
+}
+
+/// How far above the target the fan reaches full speed, and the clock is
+/// cut if the board keeps heating.
+pub const THROTTLE_ABOVE_C: f32 = 5.0;
+
+/// Fraction of the profile's clock to run at while throttled.
+pub const THROTTLED_CLOCK: f32 = 0.85;
+
+/// A target temperature too close to the shutdown temperature, or not a
+/// temperature at all.
+#[derive(Debug, Clone, Copy, PartialEq, Error)]
+#[error(
+    "target temperature {target_c} °C must be above 0 °C and at least \
+     {MIN_MARGIN_C} °C below the {CRITICAL_TEMP_C} °C shutdown"
+)]
+pub struct UnsafeTarget {
+    pub target_c: f32,
+}
+
+/// Accept `target_c` as a target temperature if it is safe.
+pub fn validate(target_c: f32) -> Result<f32, UnsafeTarget> {
+    if target_c > 0.0 && target_c <= CRITICAL_TEMP_C - MIN_MARGIN_C {
+        Ok(target_c)
+    } else {
+        Err(UnsafeTarget { target_c })
+    }
+}
+
+/// Every board's target temperature, if it has one.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TargetTemps {
+    default_c: Option<f32>,
+    boards: HashMap<String, f32>,
+}
+
+impl TargetTemps {
+    /// Targets of `default_c`, and of their own for the `(board, °C)`
+    /// pairs given, rejecting the first unsafe one.
+    pub fn new(
+        default_c: Option<f32>,
+        boards: impl IntoIterator<Item = (String, f32)>,
+    ) -> Result<Self, UnsafeTarget> {
+        Ok(Self {
+            default_c: default_c.map(validate).transpose()?,
+            boards: boards
+                .into_iter()
+                .map(|(board, target_c)| Ok((board, validate(target_c)?)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Read `MUJINA_TARGET_TEMP_C` and `MUJINA_BOARD_TARGET_TEMPS`,
+    /// warning about and ignoring malformed or unsafe values.
+    pub fn from_env() -> Self {
+        let mut targets = Self::default();
+        if let Ok(val) = env::var("MUJINA_TARGET_TEMP_C") {
+            match val.parse::<f32>().map(validate) {
+                Ok(Ok(target_c)) => targets.default_c = Some(target_c),
+                Ok(Err(e)) => warn!(error = %e, "Unsafe MUJINA_TARGET_TEMP_C, ignoring"),
+                Err(_) => warn!(value = %val, "Invalid MUJINA_TARGET_TEMP_C, ignoring"),
+            }
+        }
+        let Ok(value) = env::var("MUJINA_BOARD_TARGET_TEMPS") else {
+            return targets;
+        };
+        for entry in value.split(',').filter(|e| !e.trim().is_empty()) {
+            let parsed = entry
+                .split_once('=')
+                .filter(|(board, _)| !board.trim().is_empty())
+                .and_then(|(board, t)| Some((board.trim(), t.trim().parse::<f32>().ok()?)));
+            match parsed.map(|(board, t)| (board, validate(t))) {
+                Some((board, Ok(target_c))) => {
+                    targets.boards.insert(board.to_string(), target_c);
+                }
+                Some((board, Err(e))) => {
+                    warn!(board, error = %e, "Unsafe MUJINA_BOARD_TARGET_TEMPS entry, ignoring")
+                }
+                None => warn!(entry = %entry, "Invalid MUJINA_BOARD_TARGET_TEMPS entry, ignoring"),
+            }
+        }
+        targets
+    }
+
+    /// The target of `board`, named as in telemetry, if it has one.
+    pub fn for_board(&self, board: &str) -> Option<f32> {
+        self.boards.get(board).copied().or(self.default_c)
+    }
+}
+
+/// Fan speed for an ASIC at `temp_c` cooled toward `target_c`: the
+/// slowest [`MIN_FAN`] up to [`FAN_BAND_C`] below the target, rising
+/// linearly to full speed [`THROTTLE_ABOVE_C`] above it.
+pub fn fan_speed(temp_c: f32, target_c: f32) -> Percent {
+    let low = target_c - FAN_BAND_C;
+    let high = target_c + THROTTLE_ABOVE_C;
+    let fraction = ((temp_c - low) / (high - low)).clamp(0.0, 1.0);
+    let min = f32::from(u8::from(MIN_FAN));
+    Percent::new_clamped((min + fraction * (100.0 - min)).round() as u8)
+}
+
+/// A board's fan speed: its curve, or a duty cycle set by hand. Without a
+/// target the curve is full speed throughout.
+///
+/// A manual duty holds until the ASIC is hot enough for the curve to call
+/// for full speed, taking the highest allowed target for a board without
+/// one. The fan then runs at full speed, whatever was set, until the board
+/// cools below that point.
+#[derive(Debug)]
+pub struct FanControl {
+    target_c: Option<f32>,
+    manual: watch::Receiver<Option<Percent>>,
+    /// Most the curve may run the fan at, during quiet hours.
+    quiet_cap: Option<Percent>,
+    /// Duty held whatever the reading or a manual duty says.
+    fixed: Option<Percent>,
+    floor: FanFloor,
+}
+
+impl FanControl {
+    /// Control toward `target_c`, or at full speed without one, and the
+    /// sender that sets a manual duty or, with `None`, returns to the
+    /// curve.
+    pub fn channel(target_c: Option<f32>) -> (watch::Sender<Option<Percent>>, Self) {
+        let (tx, manual) = watch::channel(None);
+        (
+            tx,
+            Self {
+                target_c,
+                manual,
+                quiet_cap: None,
+                fixed: None,
+                floor: FanFloor::default(),
+            },
+        )
+    }
+
+    /// Cap the curve at `cap` for quiet hours, or lift the cap with
+    /// `None`. A duty set by hand isn't capped.
+    pub fn set_quiet(&mut self, cap: Option<Percent>) {
+        self.quiet_cap = cap;
+    }
+
+    /// Never run the fan below `floor`.
+    pub fn set_floor(&mut self, floor: FanFloor) {
+        self.floor = floor;
+    }
+
+    /// Hold the fan at `duty` from now on, for a board whose temperature
+    /// can't be read.
+    pub fn fix(&mut self, duty: Percent) {
+        self.fixed = Some(duty);
+    }
+
+    /// The duty cycle set by hand, if any.
+    pub fn manual(&self) -> Option<Percent> {
+        *self.manual.borrow()
+    }
+
+    /// The speed to run the fan at for a temperature reading, or `None` to
+    /// leave it as it is.
+    pub fn speed(&self, temp_c: Option<f32>) -> Option<Percent> {
+        if self.fixed.is_some() {
+            return self.fixed;
+        }
+        let (curve, too_hot) = match self.target_c {
+            Some(target_c) => {
+                let curve = temp_c.map(|t| fan_speed(t, target_c));
+                (curve, curve == Some(Percent::FULL))
+            }
+            None => (
+                Some(Percent::FULL),
+                temp_c
+                    .is_some_and(|t| fan_speed(t, CRITICAL_TEMP_C - MIN_MARGIN_C) == Percent::FULL),
+            ),
+        };
+        let speed = match (self.manual(), curve) {
+            _ if too_hot => Some(Percent::FULL),
+            (Some(manual), _) => Some(manual),
+            (None, curve) => match self.quiet_cap {
+                Some(cap) => curve.map(|speed| speed.min(cap)),
+                None => curve,
+            },
+        };
+        speed.map(|speed| self.floor.apply(speed))
+    }
+}
+
+/// Cuts a board's clock while it runs hotter than its fan can handle,
+/// and while quiet hours hold the fan down.
+#[derive(Debug)]
+pub struct ThermalThrottle {
+    target_c: Option<f32>,
+    clock_scale: watch::Sender<f32>,
+    throttled: bool,
+    /// Fraction of the clock to run at for quiet hours, 1.0 outside them.
+    quiet_clock: f32,
+}
+
+impl ThermalThrottle {
+    /// Hold a board toward `target_c` through `clock_scale`. Without a
+    /// target only quiet hours change the clock.
+    pub fn new(target_c: Option<f32>, clock_scale: watch::Sender<f32>) -> Self {
+        Self {
+            target_c,
+            clock_scale,
+            throttled: false,
+            quiet_clock: 1.0,
+        }
+    }
+
+    /// Run at `clock` of the profile's clock for quiet hours, or at the
+    /// full clock again with `None`. A throttle on top still cuts.
+    pub fn set_quiet(&mut self, clock: Option<f32>) {
+        self.quiet_clock = clock.unwrap_or(1.0);
+        self.send_scale();
+    }
+
+    fn send_scale(&self) {
+        let throttle = if self.throttled { THROTTLED_CLOCK } else { 1.0 };
+        self.clock_scale.send_replace(self.quiet_clock * throttle);
+    }
+
+    /// Whether the clock is currently cut.
+    pub fn throttled(&self) -> bool {
+        self.throttled
+    }
+
+    /// Act on a temperature reading. A missing reading, or a missing
+    /// target, changes nothing.
+    pub fn observe(&mut self, temp_c: Option<f32>) {
+        let (Some(temp_c), Some(target_c)) = (temp_c, self.target_c) else {
+            return;
+        };
+        if !self.throttled && temp_c > target_c + THROTTLE_ABOVE_C {
+            self.throttled = true;
+            warn!(
+                temp_c,
+                target_c, "Board above its target temperature at full fan, reducing clock"
+            );
+            self.send_scale();
+        } else if self.throttled && temp_c <= target_c {
+            self.throttled = false;
+            info!(
+                temp_c,
+                "Board back at its target temperature, restoring clock"
+            );
+            self.send_scale();
+        }
+    }
+}
+
+/// Which reading fan control and the throttle act on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThermalSource {
+    /// The ASIC die.
+    Asic,
+    /// The core voltage regulator.
+    Vr,
+    /// Whichever of the two is hotter.
+    #[default]
+    Max,
+}
+
+impl ThermalSource {
+    /// Read `MUJINA_THERMAL_SOURCE`: `asic`, `vr` or `max`, in any case.
+    /// Warns and uses the hotter reading when invalid.
+    pub fn from_env() -> Self {
+        let Ok(val) = env::var("MUJINA_THERMAL_SOURCE") else {
+            return Self::default();
+        };
+        match val.trim().to_ascii_lowercase().as_str() {
+            "asic" => Self::Asic,
+            "vr" => Self::Vr,
+            "max" => Self::Max,
+            _ => {
+                warn!(value = %val, "Invalid MUJINA_THERMAL_SOURCE, using max");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// A board's latest temperature readings, in degrees Celsius.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BoardTemps {
+    /// ASIC die, from the diode the fan controller reads.
+    pub asic_c: Option<f32>,
+    /// Core voltage regulator.
+    pub vr_c: Option<f32>,
+    /// The board has no ASIC diode, so nothing is controlled on
+    /// temperature.
+    pub asic_absent: bool,
+}
+
+impl BoardTemps {
+    /// The reading `source` picks. Without an ASIC reading there is none
+    /// for `Max` either, so a failing diode isn't masked by a cooler
+    /// regulator. A board without a diode has none for any source.
+    pub fn for_control(&self, source: ThermalSource) -> Option<f32> {
+        if self.asic_absent {
+            return None;
+        }
+        match source {
+            ThermalSource::Asic => self.asic_c,
+            ThermalSource::Vr => self.vr_c,
+            ThermalSource::Max => self
+                .asic_c
+                .map(|asic| self.vr_c.map_or(asic, |vr| asic.max(vr))),
+        }
+    }
+
+    /// Both readings as telemetry sensors, `asic` and `vr`.
+    pub fn sensors(&self) -> Vec<TemperatureSensor> {
+        [("asic", self.asic_c), ("vr", self.vr_c)]
+            .into_iter()
+            .map(|(name, temp_c)| TemperatureSensor {
+                name: name.into(),
+                temperature: temp_c.map(Temperature::from_celsius),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::board::profile::{self, Profile, ProfileSelection};
+
+    #[test]
+    fn throttle_follows_the_chosen_reading() {
+        // A cool die beside a regulator running hot.
+        let temps = BoardTemps {
+            asic_c: Some(55.0),
+            vr_c: Some(70.0),
+            ..Default::default()
+        };
+        let sensors: Vec<_> = temps
+            .sensors()
+            .into_iter()
+            .map(|s| (s.name, s.temperature.map(Temperature::as_degrees_c)))
+            .collect();
+        assert_eq!(
+            sensors,
+            [("asic".into(), Some(55.0)), ("vr".into(), Some(70.0))]
+        );
+
+        assert_eq!(temps.for_control(ThermalSource::Asic), Some(55.0));
+        assert_eq!(temps.for_control(ThermalSource::Vr), Some(70.0));
+        assert_eq!(temps.for_control(ThermalSource::default()), Some(70.0));
+        // No die reading, no reading to act on, however cool the
+        // regulator.
+        let no_diode = BoardTemps {
+            asic_c: None,
+            vr_c: Some(40.0),
+            ..Default::default()
+        };
+        assert_eq!(no_diode.for_control(ThermalSource::Max), None);
+
+        // By default the hot regulator cuts the clock; told to watch the
+        // ASIC only, the throttle leaves it.
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        for (source, throttled) in [
+            (ThermalSource::default(), true),
+            (ThermalSource::Asic, false),
+        ] {
+            let (_profile_tx, selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+            let mut throttle = ThermalThrottle::new(Some(60.0), selection.thermal_scale());
+            throttle.observe(temps.for_control(source));
+            assert_eq!(throttle.throttled(), throttled, "{source:?}");
+            let expected = if throttled {
+                525.0 * THROTTLED_CLOCK
+            } else {
+                525.0
+            };
+            assert_eq!(selection.current().frequency_mhz, expected);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn thermal_source_from_env() {
+        let var = "MUJINA_THERMAL_SOURCE";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(ThermalSource::from_env(), ThermalSource::Max);
+            env::set_var(var, "ASIC");
+            assert_eq!(ThermalSource::from_env(), ThermalSource::Asic);
+            env::set_var(var, "vr");
+            assert_eq!(ThermalSource::from_env(), ThermalSource::Vr);
+            env::set_var(var, "board");
+            assert_eq!(ThermalSource::from_env(), ThermalSource::Max);
+            env::remove_var(var);
+        }
+    }
+
+    #[tokio::test]
+    async fn each_board_is_controlled_toward_its_own_target() {
+        let targets = TargetTemps::new(
+            Some(60.0),
+            [
+                ("bitaxe-cool".to_string(), 50.0),
+                ("bitaxe-warm".to_string(), 68.0),
+            ],
+        )
+        .unwrap();
+        let cool = targets.for_board("bitaxe-cool").unwrap();
+        let warm = targets.for_board("bitaxe-warm").unwrap();
+        assert_eq!((cool, warm), (50.0, 68.0));
+        assert_eq!(targets.for_board("bitaxe-other"), Some(60.0));
+
+        // The same reading calls for full fan on one board and the
+        // slowest on the other.
+        assert_eq!(fan_speed(58.0, cool), Percent::FULL);
+        assert_eq!(fan_speed(58.0, warm), MIN_FAN);
+        assert_eq!(fan_speed(warm, warm), Percent::new_clamped(75));
+
+        // And cuts only the cooler board's clock.
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (_cool_tx, mut cool_selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+        let (_warm_tx, warm_selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+        let mut cool_throttle = ThermalThrottle::new(Some(cool), cool_selection.thermal_scale());
+        let mut warm_throttle = ThermalThrottle::new(Some(warm), warm_selection.thermal_scale());
+        cool_throttle.observe(Some(58.0));
+        warm_throttle.observe(Some(58.0));
+        assert!(cool_throttle.throttled());
+        assert!(!warm_throttle.throttled());
+        let throttled = cool_selection.changed().await.unwrap();
+        assert_eq!(throttled.frequency_mhz, 525.0 * THROTTLED_CLOCK);
+        assert_eq!(warm_selection.current().frequency_mhz, 525.0);
+
+        // The clock comes back only once the board is at its target.
+        cool_throttle.observe(Some(52.0));
+        assert!(cool_throttle.throttled());
+        cool_throttle.observe(Some(50.0));
+        assert_eq!(cool_selection.changed().await.unwrap().frequency_mhz, 525.0);
+    }
+
+    #[test]
+    fn manual_fan_duty_holds_until_the_board_is_dangerously_hot() {
+        let (manual_tx, fan) = FanControl::channel(Some(60.0));
+        assert_eq!(fan.speed(Some(55.0)), Some(fan_speed(55.0, 60.0)));
+        assert_eq!(fan.speed(None), None);
+
+        // A manual duty is applied whatever the temperature, or without a
+        // reading at all.
+        let quiet = Percent::new_clamped(30);
+        manual_tx.send_replace(Some(quiet));
+        assert_eq!(fan.manual(), Some(quiet));
+        for temp_c in [Some(40.0), Some(55.0), None, Some(64.0)] {
+            assert_eq!(fan.speed(temp_c), Some(quiet), "{temp_c:?}");
+        }
+
+        // A critical reading still forces full speed, and the manual duty
+        // returns once the board cools.
+        assert_eq!(fan.speed(Some(CRITICAL_TEMP_C)), Some(Percent::FULL));
+        assert_eq!(fan.speed(Some(65.0)), Some(Percent::FULL));
+        assert_eq!(fan.speed(Some(62.0)), Some(quiet));
+
+        // Clearing it hands the fan back to the curve.
+        manual_tx.send_replace(None);
+        assert_eq!(fan.manual(), None);
+        assert_eq!(fan.speed(Some(40.0)), Some(MIN_FAN));
+    }
+
+    #[test]
+    fn fan_never_runs_between_off_and_the_floor() {
+        let floor = Percent::new_clamped(40);
+        let (manual_tx, mut fan) = FanControl::channel(Some(60.0));
+        fan.set_floor(FanFloor {
+            min: floor,
+            allow_off: false,
+        });
+        fan.set_quiet(Some(Percent::new_clamped(30)));
+
+        // Neither the curve's slowest, nor a quiet cap or a manual duty
+        // under the floor, nor off, goes below it.
+        let mut asked = vec![None];
+        asked.extend((0..=100).step_by(5).map(|d| Some(Percent::new_clamped(d))));
+        for manual in asked {
+            manual_tx.send_replace(manual);
+            for temp_c in (30..=80).map(|t| Some(t as f32)) {
+                let speed = fan.speed(temp_c).unwrap();
+                assert!(speed >= floor, "{manual:?} at {temp_c:?}: {speed:?}");
+            }
+        }
+        // The curve above the floor, and full speed when hot, stand.
+        manual_tx.send_replace(None);
+        fan.set_quiet(None);
+        assert_eq!(fan.speed(Some(40.0)), Some(floor));
+        assert_eq!(fan.speed(Some(62.0)), Some(fan_speed(62.0, 60.0)));
+        assert_eq!(fan.speed(Some(CRITICAL_TEMP_C)), Some(Percent::FULL));
+
+        // Allowed off, 0 by hand switches the fan off, but nothing else
+        // lands under the floor.
+        fan.set_floor(FanFloor {
+            min: floor,
+            allow_off: true,
+        });
+        for (asked, runs) in [(0, 0), (1, 40), (39, 40), (40, 40), (55, 55)] {
+            manual_tx.send_replace(Some(Percent::new_clamped(asked)));
+            assert_eq!(
+                fan.speed(Some(45.0)),
+                Some(Percent::new_clamped(runs)),
+                "{asked}"
+            );
+        }
+        // A hot board still gets full speed.
+        manual_tx.send_replace(Some(Percent::ZERO));
+        assert_eq!(fan.speed(Some(70.0)), Some(Percent::FULL));
+    }
+
+    #[test]
+    #[serial]
+    fn fan_floor_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_FAN_MIN_PERCENT");
+            env::remove_var("MUJINA_FAN_ALLOW_OFF");
+            assert_eq!(FanFloor::from_env(), FanFloor::default());
+            env::set_var("MUJINA_FAN_MIN_PERCENT", "35");
+            env::set_var("MUJINA_FAN_ALLOW_OFF", "true");
+            assert_eq!(
+                FanFloor::from_env(),
+                FanFloor {
+                    min: Percent::new_clamped(35),
+                    allow_off: true,
+                }
+            );
+            for invalid in ["0", "101", "quiet"] {
+                env::set_var("MUJINA_FAN_MIN_PERCENT", invalid);
+                env::set_var("MUJINA_FAN_ALLOW_OFF", invalid);
+                assert_eq!(FanFloor::from_env(), FanFloor::default(), "{invalid}");
+            }
+            env::remove_var("MUJINA_FAN_MIN_PERCENT");
+            env::remove_var("MUJINA_FAN_ALLOW_OFF");
+        }
+    }
+
+    #[test]
+    fn board_without_a_diode_runs_at_a_fixed_duty() {
+        // A hot regulator would cut the clock and speed the fan on any
+        // source, were there a diode.
+        let temps = BoardTemps {
+            asic_c: None,
+            vr_c: Some(80.0),
+            asic_absent: true,
+        };
+        for source in [ThermalSource::Asic, ThermalSource::Vr, ThermalSource::Max] {
+            assert_eq!(temps.for_control(source), None, "{source:?}");
+        }
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (_profile_tx, selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+        let mut throttle = ThermalThrottle::new(Some(60.0), selection.thermal_scale());
+        throttle.observe(temps.for_control(ThermalSource::Vr));
+        assert!(!throttle.throttled());
+        assert_eq!(selection.current().frequency_mhz, 525.0);
+
+        // The fan holds the safe duty, whatever is asked of it.
+        let (manual_tx, mut fan) = FanControl::channel(Some(60.0));
+        fan.fix(SAFE_FAN_DUTY);
+        fan.set_quiet(Some(Percent::new_clamped(20)));
+        manual_tx.send_replace(Some(Percent::new_clamped(30)));
+        for temp_c in [None, Some(30.0)] {
+            assert_eq!(fan.speed(temp_c), Some(SAFE_FAN_DUTY), "{temp_c:?}");
+        }
+    }
+
+    #[test]
+    fn board_without_a_target_runs_full_fan_unthrottled() {
+        assert_eq!(TargetTemps::default().for_board("bitaxe-1"), None);
+
+        let (manual_tx, fan) = FanControl::channel(None);
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let (_profile_tx, selection) = ProfileSelection::channel(gamma, Profile::Balanced);
+        let mut throttle = ThermalThrottle::new(None, selection.thermal_scale());
+        for temp_c in [None, Some(30.0), Some(60.0), Some(78.0)] {
+            assert_eq!(fan.speed(temp_c), Some(Percent::FULL), "{temp_c:?}");
+            throttle.observe(temp_c);
+            assert!(!throttle.throttled(), "{temp_c:?}");
+        }
+        assert_eq!(selection.current().frequency_mhz, 525.0);
+
+        // A manual duty holds until the board is as hot as the highest
+        // target allows.
+        let quiet = Percent::new_clamped(30);
+        manual_tx.send_replace(Some(quiet));
+        assert_eq!(fan.speed(Some(70.0)), Some(quiet));
+        assert_eq!(fan.speed(Some(75.0)), Some(Percent::FULL));
+    }
+
+    #[test]
+    fn targets_near_the_shutdown_are_rejected() {
+        assert_eq!(validate(70.0), Ok(70.0));
+        assert_eq!(validate(72.0), Err(UnsafeTarget { target_c: 72.0 }));
+        assert!(validate(0.0).is_err());
+        assert!(validate(f32::NAN).is_err());
+        assert_eq!(
+            TargetTemps::new(Some(60.0), [("bitaxe-1".to_string(), 75.0)]),
+            Err(UnsafeTarget { target_c: 75.0 })
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn unsafe_targets_are_ignored_at_load() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_TARGET_TEMP_C", "78");
+            env::set_var(
+                "MUJINA_BOARD_TARGET_TEMPS",
+                "bitaxe-1=72, bitaxe-2=65,bitaxe-3=hot,=55",
+            );
+        }
+        let targets = TargetTemps::from_env();
+        assert_eq!(targets.for_board("bitaxe-1"), None);
+        assert_eq!(targets.for_board("bitaxe-2"), Some(65.0));
+        assert_eq!(targets.for_board("bitaxe-3"), None);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_TARGET_TEMP_C", "55") };
+        assert_eq!(TargetTemps::from_env().for_board("bitaxe-1"), Some(55.0));
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_TARGET_TEMP_C");
+            env::remove_var("MUJINA_BOARD_TARGET_TEMPS");
+        }
+    }
+}
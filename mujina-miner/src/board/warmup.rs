@@ -0,0 +1,150 @@
+//! Warm-up at the balanced profile before the startup profile applies.
+//!
+//! A cold board reads differently from one that has run a while: the
+//! chips and regulator heat toward their working temperature and the
+//! hashrate estimate is still climbing, so a board clocked straight to
+//! turbo, by `MUJINA_PROFILE` or a burn-in, is pushed hardest before its
+//! conditions have settled. With `MUJINA_PROFILE_WARMUP_SECS` set, a board
+//! whose startup profile isn't balanced starts at balanced and moves to
+//! its profile once the warm-up has passed. A profile chosen through the
+//! API during the warm-up ends it and is kept.
+
+use std::env;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+use super::profile::Profile;
+use crate::tracing::prelude::*;
+
+/// A board's pending move from balanced to its startup profile.
+#[derive(Debug)]
+pub struct ProfileWarmup {
+    until: Instant,
+    target: Profile,
+    /// Notices a profile set through the API in the meantime.
+    seen: watch::Receiver<Profile>,
+    profile_tx: watch::Sender<Profile>,
+}
+
+impl ProfileWarmup {
+    /// Read `MUJINA_PROFILE_WARMUP_SECS`. `None`, no warm-up, when unset
+    /// or 0, or invalid with a warning.
+    pub fn period_from_env() -> Option<Duration> {
+        let val = env::var("MUJINA_PROFILE_WARMUP_SECS").ok()?;
+        match val.parse::<u64>() {
+            Ok(0) => None,
+            Ok(secs) => Some(Duration::from_secs(secs)),
+            Err(_) => {
+                warn!(value = %val, "Invalid MUJINA_PROFILE_WARMUP_SECS, no warm-up");
+                None
+            }
+        }
+    }
+
+    /// The profile a board should start at to reach `target` after a
+    /// warm-up of `period`: balanced while warming up, else `target`.
+    pub fn initial(target: Profile, period: Option<Duration>) -> Profile {
+        match period {
+            Some(_) => Profile::Balanced,
+            None => target,
+        }
+    }
+
+    /// Warm up a board from `now`, moving it to `target` through
+    /// `profile_tx` after `period`. `None` when there is nothing to wait
+    /// for: no period, or a balanced target.
+    pub fn start(
+        now: Instant,
+        target: Profile,
+        period: Option<Duration>,
+        profile_tx: &watch::Sender<Profile>,
+    ) -> Option<Self> {
+        let period = period.filter(|_| target != Profile::Balanced)?;
+        debug!(profile = %target, secs = period.as_secs(), "Warming up at balanced");
+        Some(Self {
+            until: now + period,
+            target,
+            seen: profile_tx.subscribe(),
+            profile_tx: profile_tx.clone(),
+        })
+    }
+
+    /// Apply the startup profile if the warm-up has passed by `now`.
+    /// Returns whether the warm-up is over.
+    pub fn poll(&mut self, now: Instant) -> bool {
+        if self.seen.has_changed().unwrap_or(true) {
+            debug!("Profile chosen during warm-up, keeping it");
+            return true;
+        }
+        if now < self.until {
+            return false;
+        }
+        info!(profile = %self.target, "Warm-up done, applying profile");
+        self.profile_tx.send_replace(self.target);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::board::profile::{self, ProfileSelection};
+
+    #[test]
+    fn turbo_waits_for_the_warm_up() {
+        let gamma = profile::for_model("Bitaxe Gamma").unwrap();
+        let period = Some(Duration::from_secs(300));
+        let start = Instant::now();
+
+        let initial = ProfileWarmup::initial(Profile::Turbo, period);
+        let (profile_tx, selection) = ProfileSelection::channel(gamma, initial);
+        let mut warmup = ProfileWarmup::start(start, Profile::Turbo, period, &profile_tx).unwrap();
+        let balanced = gamma.operating_point(Profile::Balanced);
+        assert_eq!(selection.current(), balanced);
+
+        // Still balanced just short of the warm-up...
+        assert!(!warmup.poll(start + Duration::from_secs(299)));
+        assert_eq!(selection.current(), balanced);
+        // ...and turbo once it has passed.
+        assert!(warmup.poll(start + Duration::from_secs(300)));
+        assert_eq!(selection.current(), gamma.operating_point(Profile::Turbo));
+
+        // A profile set through the API in the meantime wins.
+        let (profile_tx, selection) = ProfileSelection::channel(gamma, initial);
+        let mut warmup = ProfileWarmup::start(start, Profile::Turbo, period, &profile_tx).unwrap();
+        profile_tx.send_replace(Profile::Eco);
+        assert!(warmup.poll(start + Duration::from_secs(10)));
+        assert!(warmup.poll(start + Duration::from_secs(600)));
+        assert_eq!(selection.current(), gamma.operating_point(Profile::Eco));
+
+        // Nothing to wait for without a period, or for balanced itself.
+        assert_eq!(ProfileWarmup::initial(Profile::Turbo, None), Profile::Turbo);
+        assert!(ProfileWarmup::start(start, Profile::Turbo, None, &profile_tx).is_none());
+        assert!(ProfileWarmup::start(start, Profile::Balanced, period, &profile_tx).is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn period_from_env() {
+        let var = "MUJINA_PROFILE_WARMUP_SECS";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(ProfileWarmup::period_from_env(), None);
+            env::set_var(var, "120");
+            assert_eq!(
+                ProfileWarmup::period_from_env(),
+                Some(Duration::from_secs(120))
+            );
+            for none in ["0", "soon"] {
+                env::set_var(var, none);
+                assert_eq!(ProfileWarmup::period_from_env(), None);
+            }
+            env::remove_var(var);
+        }
+    }
+}
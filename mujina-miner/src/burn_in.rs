@@ -0,0 +1,884 @@
+//! Burn-in: run the boards hard for a set time and report how they held up.
+//!
+//! Before a board is deployed it can be run for hours at its highest
+//! operating point to shake out marginal chips, regulators and cooling.
+//! Setting `MUJINA_BURN_IN_HOURS` starts every board at the turbo profile,
+//! the fastest point inside its model's limits, and samples telemetry
+//! while it runs. When the time is up the daemon logs a pass/fail report,
+//! writes it as JSON to `MUJINA_BURN_IN_REPORT` if set, and exits: with
+//! success if every board passed, with an error otherwise.
+//!
+//! Measurement waits for the boards to settle: a board that has just
+//! powered up is still warming and its hashrate estimate still climbing,
+//! which would pass for a dip against its later mean. Until every board's
+//! hottest sensor and hashrate have held within a tolerance for
+//! [`SETTLE_SAMPLES`] samples in a row, or `MUJINA_BURN_IN_SETTLE_MINS`
+//! pass, samples are taken but not counted, and the clock doesn't run.
+//!
+//! Thermal protection stays in force throughout. A board that shuts itself
+//! down ends the burn-in there, failed, rather than waiting out the clock.
+//!
+//! A board passes when it never faulted, hashed, and its hashrate never
+//! fell below [`HASHRATE_DIP_LIMIT`] of its mean once it started; the run
+//! passes when every board did and the pool rejected no more than
+//! [`MAX_REJECT_RATIO`] of the shares.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::api_client::summary::fleet_summary;
+use crate::api_client::types::{BoardTelemetry, MinerTelemetry};
+use crate::board::profile::Profile;
+use crate::daemon::DaemonError;
+use crate::tracing::prelude::*;
+use crate::types::HashRate;
+
+/// How often telemetry is sampled.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Lowest hashrate sample, as a share of the board's mean, that passes.
+pub(crate) const HASHRATE_DIP_LIMIT: f64 = 0.5;
+
+/// Highest share of pool answers that may be rejects.
+pub(crate) const MAX_REJECT_RATIO: f64 = 0.02;
+
+/// Consecutive samples that must agree for the boards to count as
+/// settled.
+pub(crate) const SETTLE_SAMPLES: usize = 3;
+
+/// How long to burn in, and where to write the report.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BurnInConfig {
+    pub duration: Duration,
+    pub report_path: Option<PathBuf>,
+    /// When measurement may start. `None` measures from the first sample.
+    pub settle: Option<SettlePolicy>,
+}
+
+/// When the boards count as settled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SettlePolicy {
+    /// Longest wait, after which measurement starts regardless.
+    pub max_wait: Duration,
+    /// How far each board's hottest sensor may move across the samples.
+    pub temperature_c: f32,
+    /// How far each board's hashrate may move across the samples, as a
+    /// share of the highest.
+    pub hashrate_ratio: f64,
+}
+
+impl Default for SettlePolicy {
+    fn default() -> Self {
+        Self {
+            max_wait: Duration::from_secs(600),
+            temperature_c: 1.0,
+            hashrate_ratio: 0.05,
+        }
+    }
+}
+
+impl SettlePolicy {
+    /// Read `MUJINA_BURN_IN_SETTLE_MINS` (0 disables settling),
+    /// `MUJINA_BURN_IN_SETTLE_C` and `MUJINA_BURN_IN_SETTLE_PERCENT`,
+    /// warning and keeping the default for each one invalid.
+    fn from_env() -> Option<Self> {
+        let mut policy = Self::default();
+        if let Ok(val) = env::var("MUJINA_BURN_IN_SETTLE_MINS") {
+            match val.parse::<f64>() {
+                Ok(0.0) => return None,
+                Ok(mins) if mins > 0.0 && mins.is_finite() => {
+                    policy.max_wait = Duration::from_secs_f64(mins * 60.0)
+                }
+                _ => warn!(value = %val, "Invalid MUJINA_BURN_IN_SETTLE_MINS, using default"),
+            }
+        }
+        if let Ok(val) = env::var("MUJINA_BURN_IN_SETTLE_C") {
+            match val.parse::<f32>() {
+                Ok(c) if c >= 0.0 && c.is_finite() => policy.temperature_c = c,
+                _ => warn!(value = %val, "Invalid MUJINA_BURN_IN_SETTLE_C, using default"),
+            }
+        }
+        if let Ok(val) = env::var("MUJINA_BURN_IN_SETTLE_PERCENT") {
+            match val.parse::<f64>() {
+                Ok(percent) if (0.0..100.0).contains(&percent) => {
+                    policy.hashrate_ratio = percent / 100.0
+                }
+                _ => warn!(value = %val, "Invalid MUJINA_BURN_IN_SETTLE_PERCENT, using default"),
+            }
+        }
+        Some(policy)
+    }
+}
+
+/// Whether a burn-in was asked for, valid or not.
+///
+/// Boards check this at startup to pick their operating point, before the
+/// daemon has read the full configuration.
+pub(crate) fn requested() -> bool {
+    env::var_os("MUJINA_BURN_IN_HOURS").is_some()
+}
+
+/// Read `MUJINA_BURN_IN_HOURS`, `MUJINA_BURN_IN_REPORT` and the settling
+/// variables.
+///
+/// Returns `None`, with a warning if it was set, when no positive number
+/// of hours is configured.
+pub(crate) fn config_from_env() -> Option<BurnInConfig> {
+    let value = env::var("MUJINA_BURN_IN_HOURS").ok()?;
+    let hours = match value.parse::<f64>() {
+        Ok(hours) if hours > 0.0 && hours.is_finite() => hours,
+        _ => {
+            warn!(value = %value, "Invalid MUJINA_BURN_IN_HOURS, not burning in");
+            return None;
+        }
+    };
+    Some(BurnInConfig {
+        duration: Duration::from_secs_f64(hours * 3600.0),
+        report_path: env::var_os("MUJINA_BURN_IN_REPORT")
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from),
+        settle: SettlePolicy::from_env(),
+    })
+}
+
+/// One board as seen by one sample, for judging whether it has settled.
+#[derive(Debug, Clone, PartialEq)]
+struct SettleReading {
+    name: String,
+    hottest_c: Option<f32>,
+    hashrate: u64,
+    faulted: bool,
+}
+
+/// Watches samples until the boards have settled.
+#[derive(Debug)]
+struct Settling {
+    policy: SettlePolicy,
+    started: Instant,
+    /// The latest samples, oldest first, each sorted by board name.
+    recent: Vec<Vec<SettleReading>>,
+}
+
+impl Settling {
+    fn new(policy: SettlePolicy, now: Instant) -> Self {
+        Self {
+            policy,
+            started: now,
+            recent: Vec::new(),
+        }
+    }
+
+    /// Take a sample. Returns whether measurement should start: the
+    /// boards have settled, the wait is over, or a board faulted, which
+    /// measurement must see at once.
+    fn observe(&mut self, telemetry: &MinerTelemetry, now: Instant) -> bool {
+        let mut sample: Vec<SettleReading> = telemetry
+            .boards
+            .iter()
+            .map(|board| SettleReading {
+                name: board.name.clone(),
+                hottest_c: board
+                    .temperatures
+                    .iter()
+                    .filter_map(|s| s.temperature)
+                    .map(|t| t.as_degrees_c())
+                    .reduce(f32::max),
+                hashrate: board.threads.iter().map(|t| t.hashrate).sum(),
+                faulted: board.fault.is_some() || board.threads.iter().any(|t| t.fault.is_some()),
+            })
+            .collect();
+        sample.sort_by(|a, b| a.name.cmp(&b.name));
+        let faulted = sample.iter().any(|r| r.faulted);
+        self.recent.push(sample);
+        if self.recent.len() > SETTLE_SAMPLES {
+            self.recent.remove(0);
+        }
+
+        faulted
+            || now.saturating_duration_since(self.started) >= self.policy.max_wait
+            || self.is_settled()
+    }
+
+    /// Whether the board readings of the last [`SETTLE_SAMPLES`] samples
+    /// agree within the policy's tolerances.
+    fn is_settled(&self) -> bool {
+        let [first, ..] = self.recent.as_slice() else {
+            return false;
+        };
+        if self.recent.len() < SETTLE_SAMPLES || first.is_empty() {
+            return false;
+        }
+        let same_boards = self.recent.iter().all(|sample| {
+            sample.len() == first.len() && sample.iter().zip(first).all(|(a, b)| a.name == b.name)
+        });
+        if !same_boards {
+            return false;
+        }
+
+        (0..first.len()).all(|i| {
+            let readings = || self.recent.iter().map(move |sample| &sample[i]);
+            let hashrates = readings().map(|r| r.hashrate);
+            let (lo, hi) = (hashrates.clone().min(), hashrates.max());
+            let (Some(lo), Some(hi)) = (lo, hi) else {
+                return false;
+            };
+            let hashrate_settled =
+                lo > 0 && (hi - lo) as f64 <= hi as f64 * self.policy.hashrate_ratio;
+
+            let temperatures: Option<Vec<f32>> = readings().map(|r| r.hottest_c).collect();
+            let temperature_settled = match temperatures {
+                Some(temps) => {
+                    let lo = temps.iter().copied().fold(f32::INFINITY, f32::min);
+                    let hi = temps.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                    hi - lo <= self.policy.temperature_c
+                }
+                // A board without a sensor settles on hashrate alone.
+                None => readings().all(|r| r.hottest_c.is_none()),
+            };
+            hashrate_settled && temperature_settled
+        })
+    }
+}
+
+/// The outcome of a burn-in.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct BurnInReport {
+    pub passed: bool,
+    pub planned_secs: u64,
+    /// Time spent waiting for the boards to settle, before the clock ran.
+    pub settle_secs: u64,
+    pub elapsed_secs: u64,
+    /// Why the run ended early, if it did.
+    pub aborted: Option<String>,
+    pub shares_accepted: u64,
+    pub shares_rejected: u64,
+    pub reject_percent: Option<f64>,
+    /// Reasons the run as a whole failed, beyond its boards'.
+    pub failures: Vec<String>,
+    pub boards: Vec<BoardReport>,
+}
+
+/// How one board held up.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct BoardReport {
+    pub name: String,
+    pub model: String,
+    pub profile: Option<Profile>,
+    pub passed: bool,
+    /// Samples taken since the board started hashing.
+    pub samples: u64,
+    pub mean_hashrate: u64,
+    pub min_hashrate: u64,
+    pub peak_temperature_c: Option<f32>,
+    pub peak_power_w: Option<f32>,
+    pub fault: Option<String>,
+    pub failures: Vec<String>,
+}
+
+/// What has been seen of one board.
+#[derive(Debug, Default)]
+struct BoardRecord {
+    name: String,
+    model: String,
+    profile: Option<Profile>,
+    present: bool,
+    samples: u64,
+    hashrate_sum: u128,
+    min_hashrate: Option<u64>,
+    peak_temperature_c: Option<f32>,
+    peak_power_w: Option<f32>,
+    fault: Option<String>,
+}
+
+impl BoardRecord {
+    fn record(&mut self, board: &BoardTelemetry) {
+        self.model.clone_from(&board.model);
+        self.profile = board.profile;
+        self.present = true;
+        if self.fault.is_none() {
+            self.fault = board
+                .fault
+                .clone()
+                .or_else(|| board.threads.iter().find_map(|t| t.fault.clone()));
+        }
+
+        for c in board
+            .temperatures
+            .iter()
+            .filter_map(|s| s.temperature)
+            .map(|t| t.as_degrees_c())
+        {
+            self.peak_temperature_c = Some(self.peak_temperature_c.map_or(c, |p| p.max(c)));
+        }
+        if let Some(w) = crate::api_client::summary::board_power_w(board) {
+            self.peak_power_w = Some(self.peak_power_w.map_or(w, |p| p.max(w)));
+        }
+
+        // Hashrate counts from the first nonzero estimate, so the time
+        // spent initializing isn't taken for a dip.
+        let hashrate: u64 = board.threads.iter().map(|t| t.hashrate).sum();
+        if hashrate > 0 || self.samples > 0 {
+            self.samples += 1;
+            self.hashrate_sum += u128::from(hashrate);
+            self.min_hashrate = Some(self.min_hashrate.map_or(hashrate, |m| m.min(hashrate)));
+        }
+    }
+
+    fn report(&self) -> BoardReport {
+        let mean_hashrate = if self.samples == 0 {
+            0
+        } else {
+            (self.hashrate_sum / u128::from(self.samples)) as u64
+        };
+        let min_hashrate = self.min_hashrate.unwrap_or(0);
+
+        let mut failures = Vec::new();
+        if let Some(fault) = &self.fault {
+            failures.push(format!("faulted: {fault}"));
+        }
+        if !self.present {
+            failures.push("went away".into());
+        }
+        if mean_hashrate == 0 {
+            failures.push("never hashed".into());
+        } else if (min_hashrate as f64) < mean_hashrate as f64 * HASHRATE_DIP_LIMIT {
+            failures.push(format!(
+                "hashrate dipped to {} against a mean of {}",
+                HashRate(min_hashrate),
+                HashRate(mean_hashrate)
+            ));
+        }
+
+        BoardReport {
+            name: self.name.clone(),
+            model: self.model.clone(),
+            profile: self.profile,
+            passed: failures.is_empty(),
+            samples: self.samples,
+            mean_hashrate,
+            min_hashrate,
+            peak_temperature_c: self.peak_temperature_c,
+            peak_power_w: self.peak_power_w,
+            fault: self.fault.clone(),
+            failures,
+        }
+    }
+}
+
+/// Collects telemetry over a burn-in.
+///
+/// Time is passed in rather than read, so a run can be tested without
+/// waiting it out.
+#[derive(Debug)]
+pub(crate) struct BurnIn {
+    duration: Duration,
+    started: Instant,
+    boards: Vec<BoardRecord>,
+    /// Share totals at the start, so earlier shares don't count.
+    shares_at_start: Option<(u64, u64)>,
+    shares: (u64, u64),
+    settle: Duration,
+}
+
+impl BurnIn {
+    pub(crate) fn new(duration: Duration, now: Instant) -> Self {
+        Self {
+            duration,
+            started: now,
+            boards: Vec::new(),
+            shares_at_start: None,
+            shares: (0, 0),
+            settle: Duration::ZERO,
+        }
+    }
+
+    /// When the burn-in is over.
+    pub(crate) fn ends_at(&self) -> Instant {
+        self.started + self.duration
+    }
+
+    /// Record a telemetry snapshot. Returns why the run must stop now if a
+    /// board has shut itself down.
+    pub(crate) fn record(&mut self, telemetry: &MinerTelemetry) -> Option<String> {
+        let fleet = fleet_summary(telemetry);
+        let start = *self
+            .shares_at_start
+            .get_or_insert((fleet.shares_accepted, fleet.shares_rejected));
+        self.shares = (
+            fleet.shares_accepted.saturating_sub(start.0),
+            fleet.shares_rejected.saturating_sub(start.1),
+        );
+
+        for record in &mut self.boards {
+            record.present = false;
+        }
+        for board in &telemetry.boards {
+            let index = match self.boards.iter().position(|r| r.name == board.name) {
+                Some(index) => index,
+                None => {
+                    self.boards.push(BoardRecord {
+                        name: board.name.clone(),
+                        ..Default::default()
+                    });
+                    self.boards.len() - 1
+                }
+            };
+            self.boards[index].record(board);
+        }
+
+        self.boards.iter().find_map(|r| {
+            r.fault
+                .as_ref()
+                .map(|f| format!("{} shut down: {f}", r.name))
+        })
+    }
+
+    /// The report as of `now`, `aborted` saying why if the run ended early.
+    pub(crate) fn report(&self, now: Instant, aborted: Option<String>) -> BurnInReport {
+        let (accepted, rejected) = self.shares;
+        let answered = accepted + rejected;
+        let reject_ratio = (answered > 0).then(|| rejected as f64 / answered as f64);
+        let boards: Vec<BoardReport> = self.boards.iter().map(BoardRecord::report).collect();
+
+        let mut failures = Vec::new();
+        if boards.is_empty() {
+            failures.push("no boards".into());
+        }
+        if let Some(ratio) = reject_ratio.filter(|r| *r > MAX_REJECT_RATIO) {
+            failures.push(format!(
+                "{:.2}% of shares rejected, limit {:.2}%",
+                ratio * 100.0,
+                MAX_REJECT_RATIO * 100.0
+            ));
+        }
+
+        BurnInReport {
+            passed: aborted.is_none() && failures.is_empty() && boards.iter().all(|b| b.passed),
+            planned_secs: self.duration.as_secs(),
+            settle_secs: self.settle.as_secs(),
+            elapsed_secs: now.saturating_duration_since(self.started).as_secs(),
+            aborted,
+            shares_accepted: accepted,
+            shares_rejected: rejected,
+            reject_percent: reject_ratio.map(|r| r * 100.0),
+            failures,
+            boards,
+        }
+    }
+}
+
+impl BurnInReport {
+    fn log(&self) {
+        for board in &self.boards {
+            let failures = board.failures.join("; ");
+            if board.passed {
+                info!(
+                    board = %board.name,
+                    mean_hashrate = %HashRate(board.mean_hashrate),
+                    min_hashrate = %HashRate(board.min_hashrate),
+                    peak_temperature_c = board.peak_temperature_c,
+                    peak_power_w = board.peak_power_w,
+                    "Burn-in: board passed."
+                );
+            } else {
+                warn!(
+                    board = %board.name,
+                    mean_hashrate = %HashRate(board.mean_hashrate),
+                    min_hashrate = %HashRate(board.min_hashrate),
+                    peak_temperature_c = board.peak_temperature_c,
+                    peak_power_w = board.peak_power_w,
+                    failures = %failures,
+                    "Burn-in: board failed."
+                );
+            }
+        }
+        let summary = format!(
+            "Burn-in {} after {}s of {}s.",
+            if self.passed { "passed" } else { "failed" },
+            self.elapsed_secs,
+            self.planned_secs
+        );
+        if self.passed {
+            info!(
+                accepted = self.shares_accepted,
+                rejected = self.shares_rejected,
+                "{summary}"
+            );
+        } else {
+            warn!(
+                accepted = self.shares_accepted,
+                rejected = self.shares_rejected,
+                aborted = self.aborted.as_deref(),
+                failures = %self.failures.join("; "),
+                "{summary}"
+            );
+        }
+    }
+}
+
+/// Run a burn-in, then stop the daemon.
+///
+/// `snapshot` is called once per sample for the current telemetry. On a pass
+/// the daemon is shut down through `shutdown`; on a failure an error is sent
+/// to `fatal_tx`, so the daemon exits with it. Returns the report, or `None`
+/// if the daemon shut down first.
+pub(crate) async fn task(
+    config: BurnInConfig,
+    shutdown: CancellationToken,
+    fatal_tx: mpsc::Sender<DaemonError>,
+    snapshot: impl Fn() -> MinerTelemetry,
+) -> Option<BurnInReport> {
+    info!(
+        hours = format!("{:.2}", config.duration.as_secs_f64() / 3600.0),
+        "Burn-in started, boards run at the turbo profile"
+    );
+
+    // Until the boards settle, samples are only watched.
+    let mut first = None;
+    let mut settle = Duration::ZERO;
+    if let Some(policy) = config.settle {
+        let mut settling = Settling::new(policy, Instant::now());
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => return None,
+                _ = tokio::time::sleep(SAMPLE_INTERVAL) => {}
+            }
+            let telemetry = snapshot();
+            if settling.observe(&telemetry, Instant::now()) {
+                first = Some(telemetry);
+                break;
+            }
+        }
+        settle = settling.started.elapsed();
+        if settling.is_settled() {
+            info!(
+                secs = settle.as_secs(),
+                "Burn-in: boards settled, measuring"
+            );
+        } else {
+            warn!(
+                secs = settle.as_secs(),
+                "Burn-in: boards not settled, measuring anyway"
+            );
+        }
+    }
+
+    let mut burn_in = BurnIn::new(config.duration, Instant::now());
+    burn_in.settle = settle;
+    let mut aborted = None;
+    if let Some(telemetry) = first {
+        aborted = burn_in.record(&telemetry);
+    }
+    while aborted.is_none() && Instant::now() < burn_in.ends_at() {
+        let next = (Instant::now() + SAMPLE_INTERVAL).min(burn_in.ends_at());
+        tokio::select! {
+            _ = shutdown.cancelled() => return None,
+            _ = tokio::time::sleep_until(next) => aborted = burn_in.record(&snapshot()),
+        }
+    }
+
+    let report = burn_in.report(Instant::now(), aborted);
+    report.log();
+    if let Some(path) = &config.report_path {
+        let written = serde_json::to_string_pretty(&report)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(fs::write(path, json + "\n")?));
+        if let Err(e) = written {
+            error!(path = %path.display(), error = %e, "Failed to write burn-in report");
+        }
+    }
+
+    if report.passed {
+        shutdown.cancel();
+    } else {
+        let _ = fatal_tx
+            .send(DaemonError::Other(anyhow::anyhow!("Burn-in failed")))
+            .await;
+    }
+    Some(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::api_client::types::SourceTelemetry;
+
+    fn board(hashrate: u64, temperature_c: f32) -> BoardTelemetry {
+        BoardTelemetry {
+            profile: Some(Profile::Turbo),
+            ..BoardTelemetry::named("bitaxe-1")
+                .with_model("Bitaxe Gamma")
+                .with_temperature(temperature_c)
+                .with_thread(hashrate, true)
+        }
+    }
+
+    fn telemetry(board: BoardTelemetry, accepted: u64, rejected: u64) -> MinerTelemetry {
+        MinerTelemetry {
+            boards: vec![board],
+            sources: vec![SourceTelemetry {
+                shares_accepted: accepted,
+                shares_rejected: rejected,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_the_run_when_time_is_up() {
+        let path = env::temp_dir().join(format!("mujina-{}-burn-in.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let config = BurnInConfig {
+            duration: Duration::from_secs(60),
+            report_path: Some(path.clone()),
+            settle: None,
+        };
+        let shutdown = CancellationToken::new();
+        let (fatal_tx, mut fatal_rx) = mpsc::channel(1);
+
+        // Initializing at first, then hashing and finding shares.
+        let samples = Arc::new(Mutex::new(0u64));
+        let snapshot = move || {
+            let mut n = samples.lock().unwrap();
+            *n += 1;
+            let hashrate = if *n == 1 { 0 } else { 1_000_000_000_000 + *n };
+            telemetry(board(hashrate, 60.0 + *n as f32), 10 * *n, 0)
+        };
+
+        let report = task(config, shutdown.clone(), fatal_tx, snapshot)
+            .await
+            .unwrap();
+
+        assert!(report.passed, "{report:?}");
+        assert!(shutdown.is_cancelled());
+        assert!(fatal_rx.try_recv().is_err());
+        assert_eq!(report.planned_secs, 60);
+        assert_eq!(report.elapsed_secs, 60);
+        assert_eq!(report.aborted, None);
+        // Six samples; the first sets the share baseline.
+        assert_eq!((report.shares_accepted, report.shares_rejected), (50, 0));
+        assert_eq!(report.reject_percent, Some(0.0));
+
+        let board = &report.boards[0];
+        assert_eq!(board.name, "bitaxe-1");
+        assert_eq!(board.profile, Some(Profile::Turbo));
+        assert_eq!(board.samples, 5);
+        assert_eq!(board.min_hashrate, 1_000_000_000_002);
+        assert_eq!(board.mean_hashrate, 1_000_000_000_004);
+        assert_eq!(board.peak_temperature_c, Some(66.0));
+        assert!(board.failures.is_empty());
+
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["passed"], true);
+        assert_eq!(written["boards"][0]["min_hashrate"], 1_000_000_000_002u64);
+        assert_eq!(written["boards"][0]["peak_temperature_c"], 66.0);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_thermal_shutdown_aborts_the_run() {
+        let config = BurnInConfig {
+            duration: Duration::from_secs(3600),
+            report_path: None,
+            settle: None,
+        };
+        let shutdown = CancellationToken::new();
+        let (fatal_tx, mut fatal_rx) = mpsc::channel(1);
+
+        // The board overheats on the third sample and shuts itself down.
+        let samples = Arc::new(Mutex::new(0u64));
+        let snapshot = move || {
+            let mut n = samples.lock().unwrap();
+            *n += 1;
+            let mut board = board(1_000_000_000_000, 70.0 + 10.0 * *n as f32);
+            if *n == 3 {
+                board.threads[0].hashrate = 0;
+                board.fault = Some("thermal emergency: ASIC at 100.0 C".into());
+            }
+            telemetry(board, 0, 0)
+        };
+        let started = Instant::now();
+
+        let report = task(config, shutdown.clone(), fatal_tx, snapshot)
+            .await
+            .unwrap();
+
+        assert_eq!(started.elapsed(), SAMPLE_INTERVAL * 3);
+        assert!(!report.passed);
+        assert!(!shutdown.is_cancelled());
+        assert!(fatal_rx.try_recv().is_ok());
+        assert_eq!(
+            report.aborted.as_deref(),
+            Some("bitaxe-1 shut down: thermal emergency: ASIC at 100.0 C")
+        );
+        assert_eq!(report.elapsed_secs, 30);
+        assert_eq!(report.boards[0].peak_temperature_c, Some(100.0));
+        assert_eq!(
+            report.boards[0].fault.as_deref(),
+            Some("thermal emergency: ASIC at 100.0 C")
+        );
+    }
+
+    /// Run a one-minute burn-in that waits up to a minute for the board to
+    /// settle, the board reading `temperature_c` at the nth sample.
+    async fn settled_run(temperature_c: impl Fn(u64) -> f32 + 'static) -> BurnInReport {
+        let config = BurnInConfig {
+            duration: Duration::from_secs(60),
+            report_path: None,
+            settle: Some(SettlePolicy {
+                max_wait: Duration::from_secs(60),
+                ..Default::default()
+            }),
+        };
+        let (fatal_tx, _fatal_rx) = mpsc::channel(1);
+        let samples = Arc::new(Mutex::new(0u64));
+        let snapshot = move || {
+            let mut n = samples.lock().unwrap();
+            *n += 1;
+            telemetry(board(1_000_000_000_000, temperature_c(*n)), 10 * *n, 0)
+        };
+        task(config, CancellationToken::new(), fatal_tx, snapshot)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn measurement_waits_for_the_boards_to_settle() {
+        // Warming 10 C a sample, then holding at 80 C from the third.
+        let started = Instant::now();
+        let report = settled_run(|n| (50.0 + 10.0 * n as f32).min(80.0)).await;
+
+        // The third to fifth samples agree; measuring starts with the
+        // fifth and runs the full minute after it.
+        assert_eq!(report.settle_secs, 50);
+        assert_eq!(report.elapsed_secs, 60);
+        assert_eq!(started.elapsed(), Duration::from_secs(110));
+        assert_eq!(report.boards[0].samples, 7);
+        assert_eq!((report.shares_accepted, report.shares_rejected), (60, 0));
+        assert!(report.passed, "{report:?}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn measurement_starts_after_the_max_wait_regardless() {
+        // Never holds still.
+        let report = settled_run(|n| 60.0 + 2.0 * n as f32).await;
+
+        assert_eq!(report.settle_secs, 60);
+        assert_eq!(report.elapsed_secs, 60);
+        assert_eq!(report.boards[0].samples, 7);
+        assert_eq!(report.boards[0].peak_temperature_c, Some(84.0));
+    }
+
+    #[test]
+    fn settling_needs_every_board_steady_and_hashing() {
+        let now = Instant::now();
+        let mut settling = Settling::new(SettlePolicy::default(), now);
+        let sample = |hashrate, temperature_c| telemetry(board(hashrate, temperature_c), 0, 0);
+
+        // Not hashing yet, however steady.
+        for _ in 0..SETTLE_SAMPLES {
+            assert!(!settling.observe(&sample(0, 60.0), now));
+        }
+        // Hashrate still climbing past the tolerance.
+        for hashrate in [900, 960, 1_000] {
+            assert!(!settling.observe(&sample(hashrate, 60.0), now));
+        }
+        assert!(settling.observe(&sample(990, 60.5), now));
+
+        // A fault starts measurement at once, so it is seen.
+        let mut settling = Settling::new(SettlePolicy::default(), now);
+        let mut faulted = board(1_000, 60.0);
+        faulted.fault = Some("thermal emergency".into());
+        assert!(settling.observe(&telemetry(faulted, 0, 0), now));
+        assert!(!settling.is_settled());
+    }
+
+    #[test]
+    fn fails_boards_that_dip_and_runs_with_many_rejects() {
+        let start = Instant::now();
+        let mut burn_in = BurnIn::new(Duration::from_secs(60), start);
+        burn_in.record(&telemetry(board(1_000, 60.0), 0, 0));
+        burn_in.record(&telemetry(board(1_000, 60.0), 90, 5));
+        burn_in.record(&telemetry(board(100, 60.0), 95, 10));
+
+        let report = burn_in.report(start + Duration::from_secs(60), None);
+        assert!(!report.passed);
+        assert_eq!(report.reject_percent, Some(10.0 / 105.0 * 100.0));
+        assert_eq!(report.failures, ["9.52% of shares rejected, limit 2.00%"]);
+        assert_eq!(
+            report.boards[0].failures,
+            ["hashrate dipped to 100 H/s against a mean of 700 H/s"]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn config_from_env_reads_hours_and_report_path() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_BURN_IN_HOURS");
+            env::remove_var("MUJINA_BURN_IN_REPORT");
+        }
+        assert_eq!(config_from_env(), None);
+        assert!(!requested());
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_BURN_IN_HOURS", "0.5");
+            env::set_var("MUJINA_BURN_IN_REPORT", "/tmp/burn-in.json");
+        }
+        assert_eq!(
+            config_from_env(),
+            Some(BurnInConfig {
+                duration: Duration::from_secs(1800),
+                report_path: Some("/tmp/burn-in.json".into()),
+                settle: Some(SettlePolicy::default()),
+            })
+        );
+        assert!(requested());
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_BURN_IN_SETTLE_MINS", "0") };
+        assert_eq!(config_from_env().unwrap().settle, None);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_BURN_IN_SETTLE_MINS", "2");
+            env::set_var("MUJINA_BURN_IN_SETTLE_C", "0.5");
+            env::set_var("MUJINA_BURN_IN_SETTLE_PERCENT", "lots");
+        }
+        assert_eq!(
+            config_from_env().unwrap().settle,
+            Some(SettlePolicy {
+                max_wait: Duration::from_secs(120),
+                temperature_c: 0.5,
+                ..Default::default()
+            })
+        );
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_BURN_IN_HOURS", "-1") };
+        assert_eq!(config_from_env(), None);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_BURN_IN_HOURS");
+            env::remove_var("MUJINA_BURN_IN_REPORT");
+            env::remove_var("MUJINA_BURN_IN_SETTLE_MINS");
+            env::remove_var("MUJINA_BURN_IN_SETTLE_C");
+            env::remove_var("MUJINA_BURN_IN_SETTLE_PERCENT");
+        }
+    }
+}
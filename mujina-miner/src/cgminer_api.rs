@@ -0,0 +1,434 @@
+//! cgminer-compatible `summary` and `devs` for existing dashboards.
+//!
+//! Many monitoring tools poll miners over cgminer's API: connect to TCP
+//! port 4028, send `{"command":"summary"}`, read one JSON reply, and the
+//! miner closes the connection. When `MUJINA_CGMINER_LISTEN` is set the
+//! daemon answers the two commands those tools rely on, mapped from the
+//! same telemetry the REST API serves. Anything else gets cgminer's
+//! "Invalid command" error. This is an interop convenience, not API
+//! parity: nothing can be changed through it.
+//!
+//! Field mapping for `summary` (one entry):
+//!
+//! | cgminer field      | Source                                        |
+//! |--------------------|-----------------------------------------------|
+//! | `Elapsed`          | daemon uptime, seconds                        |
+//! | `MHS av`           | sum of thread hashrates, MH/s                 |
+//! | `Accepted`         | shares accepted, summed over sources          |
+//! | `Rejected`         | shares rejected, summed over sources          |
+//! | `Best Share`       | best share difficulty, 0 before the first     |
+//! | `Found Blocks`     | shares that met the network target            |
+//! | `Pool Rejected%`   | rejected over accepted plus rejected, percent |
+//!
+//! Field mapping for `devs` (one entry per board, as an ASC):
+//!
+//! | cgminer field | Source                                             |
+//! |---------------|----------------------------------------------------|
+//! | `ASC`, `ID`   | board position in the board list                   |
+//! | `Name`        | board model                                        |
+//! | `Enabled`     | always `Y`                                         |
+//! | `Status`      | `Alive` when a thread is active, else `Dead`       |
+//! | `Temperature` | hottest sensor on the board, Celsius, 0 if none    |
+//! | `MHS av`      | sum of the board's thread hashrates, MH/s          |
+//!
+//! Hashrates are mujina's windowed estimates, not averages since start as
+//! in cgminer; dashboards plot them the same way.
+
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde_json::{Value, json};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+
+use crate::api_client::summary::fleet_summary;
+use crate::api_client::types::{BoardTelemetry, MinerTelemetry};
+use crate::tracing::prelude::*;
+use crate::types::HashRate;
+
+/// cgminer's API port.
+const CGMINER_PORT: u16 = 4028;
+
+/// Largest request accepted; real requests are a few dozen bytes.
+const MAX_REQUEST: usize = 4096;
+
+/// How long a client has to send its request and take the reply.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read the listen address from `MUJINA_CGMINER_LISTEN`.
+///
+/// Returns `None` when unset, which leaves the interface off. A bare IPv4
+/// or IPv6 address, bracketed or not, gets cgminer's port 4028. Anything
+/// that isn't an address warns and leaves the interface off too.
+pub(crate) fn listen_from_env() -> Option<SocketAddr> {
+    let value = env::var("MUJINA_CGMINER_LISTEN").ok()?;
+    let addr = parse_listen(&value);
+    if addr.is_none() {
+        warn!(value = %value, "Invalid MUJINA_CGMINER_LISTEN, cgminer API disabled");
+    }
+    addr
+}
+
+/// Parse an address with or without a port, supplying cgminer's.
+fn parse_listen(value: &str) -> Option<SocketAddr> {
+    let value = value.trim();
+    if let Ok(addr) = value.parse() {
+        return Some(addr);
+    }
+    let bare = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    let ip: IpAddr = bare.parse().ok()?;
+    Some(SocketAddr::new(ip, CGMINER_PORT))
+}
+
+/// Answer cgminer API requests on `bind_addr` until shutdown.
+///
+/// `snapshot` is called once per request for the current telemetry.
+pub(crate) async fn task(
+    bind_addr: SocketAddr,
+    shutdown: CancellationToken,
+    snapshot: impl Fn() -> MinerTelemetry + Send + Sync + 'static,
+) {
+    let listener = match TcpListener::bind(bind_addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(addr = %bind_addr, error = %e, "cgminer API failed to bind");
+            return;
+        }
+    };
+    info!(addr = %bind_addr, "cgminer API listening.");
+    serve(listener, shutdown, snapshot).await;
+}
+
+/// Accept connections on `listener`, one request each, until shutdown.
+///
+/// Each connection is answered in its own task, so a client that stalls
+/// holds up no one else; it is dropped after [`REQUEST_TIMEOUT`].
+async fn serve(
+    listener: TcpListener,
+    shutdown: CancellationToken,
+    snapshot: impl Fn() -> MinerTelemetry + Send + Sync + 'static,
+) {
+    let snapshot = Arc::new(snapshot);
+    loop {
+        let stream = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!(error = %e, "cgminer API accept failed");
+                    continue;
+                }
+            },
+        };
+        let snapshot = Arc::clone(&snapshot);
+        tokio::spawn(async move {
+            let exchange = handle_connection(stream, snapshot.as_ref());
+            match tokio::time::timeout(REQUEST_TIMEOUT, exchange).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => debug!(error = %e, "cgminer API request failed"),
+                Err(_) => debug!("cgminer API client too slow, dropped"),
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    snapshot: &impl Fn() -> MinerTelemetry,
+) -> Result<()> {
+    let request = read_request(&mut stream).await?;
+    let reply = respond(&request, &snapshot(), unix_now());
+    let mut bytes = serde_json::to_vec(&reply)?;
+    // cgminer terminates every reply with a NUL, and clients expect it.
+    bytes.push(0);
+    stream.write_all(&bytes).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Read until the request is complete: a whole JSON value, a line, a NUL,
+/// or the client closing its side.
+async fn read_request(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 512];
+    loop {
+        let n = stream.read(&mut buf).await?;
+        request.extend_from_slice(&buf[..n]);
+        let complete = n == 0
+            || request.contains(&b'\n')
+            || request.contains(&0)
+            || serde_json::from_slice::<Value>(&request).is_ok();
+        if complete || request.len() >= MAX_REQUEST {
+            return Ok(request);
+        }
+    }
+}
+
+/// The reply to one request, as cgminer would shape it.
+///
+/// Accepts the JSON form (`{"command":"summary"}`) and the bare command
+/// name some tools send; both get a JSON reply.
+fn respond(request: &[u8], telemetry: &MinerTelemetry, when: u64) -> Value {
+    let text = String::from_utf8_lossy(request);
+    let text = text.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    let command = match serde_json::from_str::<Value>(text) {
+        Ok(value) => value["command"].as_str().unwrap_or_default().to_string(),
+        Err(_) => text.to_string(),
+    };
+    match command.as_str() {
+        "summary" => json!({
+            "STATUS": [status("S", 11, "Summary", when)],
+            "SUMMARY": [summary(telemetry)],
+            "id": 1,
+        }),
+        "devs" => json!({
+            "STATUS": [status(
+                "S",
+                9,
+                &format!("{} ASC(s)", telemetry.boards.len()),
+                when,
+            )],
+            "DEVS": telemetry
+                .boards
+                .iter()
+                .enumerate()
+                .map(|(i, board)| device(i, board))
+                .collect::<Vec<_>>(),
+            "id": 1,
+        }),
+        _ => json!({
+            "STATUS": [status("E", 14, "Invalid command", when)],
+            "id": 1,
+        }),
+    }
+}
+
+fn status(code: &str, number: u32, msg: &str, when: u64) -> Value {
+    json!({
+        "STATUS": code,
+        "When": when,
+        "Code": number,
+        "Msg": msg,
+        "Description": concat!("mujina-miner ", env!("CARGO_PKG_VERSION")),
+    })
+}
+
+fn summary(telemetry: &MinerTelemetry) -> Value {
+    let fleet = fleet_summary(telemetry);
+    let answered = fleet.shares_accepted + fleet.shares_rejected;
+    let rejected_pct = if answered > 0 {
+        fleet.shares_rejected as f64 * 100.0 / answered as f64
+    } else {
+        0.0
+    };
+    json!({
+        "Elapsed": telemetry.uptime_secs,
+        "MHS av": fleet.hashrate.as_megahashes(),
+        "Accepted": fleet.shares_accepted,
+        "Rejected": fleet.shares_rejected,
+        "Best Share": telemetry.best_share_difficulty.unwrap_or(0.0),
+        "Found Blocks": telemetry.blocks_found,
+        "Pool Rejected%": rejected_pct,
+    })
+}
+
+fn device(index: usize, board: &BoardTelemetry) -> Value {
+    let hashrate: HashRate = board
+        .threads
+        .iter()
+        .map(|t| HashRate::from(t.hashrate))
+        .sum();
+    let hottest = board
+        .temperatures
+        .iter()
+        .filter_map(|s| s.temperature)
+        .map(|t| t.as_degrees_c())
+        .reduce(f32::max);
+    let alive = board.threads.iter().any(|t| t.is_active);
+    json!({
+        "ASC": index,
+        "Name": board.model,
+        "ID": index,
+        "Enabled": "Y",
+        "Status": if alive { "Alive" } else { "Dead" },
+        "Temperature": hottest.unwrap_or(0.0),
+        "MHS av": hashrate.as_megahashes(),
+    })
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::api_client::types::SourceTelemetry;
+
+    const WHEN: u64 = 1_760_000_000;
+
+    fn board(model: &str, mh: &[f64], temps_c: &[f32], active: bool) -> BoardTelemetry {
+        let board = BoardTelemetry::named(format!("{model}-0")).with_model(model);
+        let board = temps_c.iter().fold(board, |b, &c| b.with_temperature(c));
+        mh.iter().fold(board, |b, &mh| {
+            b.with_thread(HashRate::from_megahashes(mh).into(), active)
+        })
+    }
+
+    fn fleet() -> MinerTelemetry {
+        MinerTelemetry {
+            uptime_secs: 3600,
+            best_share_difficulty: Some(1.5e6),
+            blocks_found: 0,
+            boards: vec![
+                board("Bitaxe Gamma", &[1_000_000.0], &[55.0, 61.5], true),
+                board("EmberOne", &[2_000_000.0, 3_000_000.0], &[], false),
+            ],
+            sources: vec![SourceTelemetry {
+                shares_accepted: 95,
+                shares_rejected: 5,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn summary_matches_cgminer_shape() {
+        let reply = respond(br#"{"command":"summary"}"#, &fleet(), WHEN);
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["STATUS"][0]["STATUS"], "S");
+        assert_eq!(reply["STATUS"][0]["When"], WHEN);
+        assert_eq!(reply["STATUS"][0]["Code"], 11);
+
+        let summary = &reply["SUMMARY"][0];
+        assert_eq!(summary["Elapsed"], 3600);
+        assert_eq!(summary["MHS av"], 6_000_000.0);
+        assert_eq!(summary["Accepted"], 95);
+        assert_eq!(summary["Rejected"], 5);
+        assert_eq!(summary["Best Share"], 1.5e6);
+        assert_eq!(summary["Found Blocks"], 0);
+        assert_eq!(summary["Pool Rejected%"], 5.0);
+    }
+
+    #[test]
+    fn devs_lists_each_board_as_an_asc() {
+        let reply = respond(b"devs", &fleet(), WHEN);
+        assert_eq!(reply["STATUS"][0]["Msg"], "2 ASC(s)");
+
+        let devs = reply["DEVS"].as_array().unwrap();
+        assert_eq!(devs.len(), 2);
+        assert_eq!(devs[0]["ASC"], 0);
+        assert_eq!(devs[0]["Name"], "Bitaxe Gamma");
+        assert_eq!(devs[0]["Enabled"], "Y");
+        assert_eq!(devs[0]["Status"], "Alive");
+        assert_eq!(devs[0]["Temperature"], 61.5);
+        assert_eq!(devs[0]["MHS av"], 1_000_000.0);
+
+        assert_eq!(devs[1]["ID"], 1);
+        assert_eq!(devs[1]["Status"], "Dead");
+        assert_eq!(devs[1]["Temperature"], 0.0);
+        assert_eq!(devs[1]["MHS av"], 5_000_000.0);
+    }
+
+    #[test]
+    fn unknown_commands_are_refused() {
+        for request in [&br#"{"command":"addpool"}"#[..], b"", b"not json"] {
+            let reply = respond(request, &fleet(), WHEN);
+            assert_eq!(reply["STATUS"][0]["STATUS"], "E");
+            assert_eq!(reply["STATUS"][0]["Code"], 14);
+        }
+    }
+
+    #[tokio::test]
+    async fn answers_over_tcp_with_nul_terminator() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(serve(listener, shutdown.clone(), fleet));
+
+        // Like real clients, send the request and wait, without closing.
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(br#"{"command":"summary"}"#).await.unwrap();
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).await.unwrap();
+
+        assert_eq!(reply.pop(), Some(0));
+        let reply: Value = serde_json::from_slice(&reply).unwrap();
+        assert_eq!(reply["SUMMARY"][0]["Accepted"], 95);
+
+        shutdown.cancel();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stalled_client_does_not_hold_up_others() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(serve(listener, shutdown.clone(), fleet));
+
+        // Connects and never sends anything.
+        let _stalled = TcpStream::connect(addr).await.unwrap();
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"summary").await.unwrap();
+        stream.shutdown().await.unwrap();
+        let mut reply = Vec::new();
+        tokio::time::timeout(REQUEST_TIMEOUT / 5, stream.read_to_end(&mut reply))
+            .await
+            .expect("reply held up by the stalled client")
+            .unwrap();
+        assert_eq!(reply.pop(), Some(0));
+
+        shutdown.cancel();
+        server.await.unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn listen_address_from_env() {
+        let var = "MUJINA_CGMINER_LISTEN";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(listen_from_env(), None);
+            env::set_var(var, "0.0.0.0");
+            assert_eq!(listen_from_env(), Some("0.0.0.0:4028".parse().unwrap()));
+            env::set_var(var, "not an address");
+            assert_eq!(listen_from_env(), None);
+            env::remove_var(var);
+        }
+    }
+
+    #[test]
+    fn listen_addresses_get_cgminer_port_when_bare() {
+        for (value, expected) in [
+            ("0.0.0.0", "0.0.0.0:4028"),
+            ("127.0.0.1:14028", "127.0.0.1:14028"),
+            ("::", "[::]:4028"),
+            ("::1", "[::1]:4028"),
+            ("[::1]", "[::1]:4028"),
+            ("[::1]:14028", "[::1]:14028"),
+        ] {
+            assert_eq!(
+                parse_listen(value),
+                Some(expected.parse().unwrap()),
+                "{value}"
+            );
+        }
+        assert_eq!(parse_listen("localhost"), None);
+        assert_eq!(parse_listen("::1:99999"), None);
+    }
+}
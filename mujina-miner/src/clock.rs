@@ -0,0 +1,93 @@
+//! The time of day, from a clock tests can control.
+//!
+//! Elapsed time (backoff, timeouts, rate windows, uptime) is measured with
+//! `tokio::time::Instant`, which a test pauses and steps with
+//! `tokio::time::advance`. The time of day is read from the host instead,
+//! and daily share counts, history samples and CSV rows are stamped with
+//! it. Components that stamp the time of day take a [`Clock`], so a test
+//! can choose what time it is.
+
+use std::fmt;
+use std::sync::Arc;
+
+use time::OffsetDateTime;
+
+/// A source of the time of day.
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// The current time of day, in UTC.
+    fn now_utc(&self) -> OffsetDateTime;
+}
+
+/// The host's clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_utc(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// The clock components use unless given another.
+pub fn system() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// A clock that moves only when a test moves it.
+///
+/// [`FakeClock::advance`] steps the time of day and Tokio's paused clock
+/// together, so one call drives both timers and daily rollover.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct FakeClock {
+    now: std::sync::Mutex<OffsetDateTime>,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    /// A clock reading `start`.
+    pub fn new(start: OffsetDateTime) -> Arc<Self> {
+        Arc::new(Self {
+            now: std::sync::Mutex::new(start),
+        })
+    }
+
+    /// Move the time of day and Tokio's clock forward by `by`. The runtime
+    /// must have its clock paused.
+    pub async fn advance(&self, by: std::time::Duration) {
+        *self.now.lock().unwrap() += by;
+        tokio::time::advance(by).await;
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now_utc(&self) -> OffsetDateTime {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use time::macros::datetime;
+    use tokio::time::Instant;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn fake_clock_moves_time_of_day_and_timers_together() {
+        let clock = FakeClock::new(datetime!(2026-03-01 23:59:30 UTC));
+        let started = Instant::now();
+        let timer = tokio::spawn(tokio::time::sleep(Duration::from_secs(45)));
+
+        clock.advance(Duration::from_secs(44)).await;
+        assert!(!timer.is_finished());
+        clock.advance(Duration::from_secs(1)).await;
+        timer.await.unwrap();
+
+        assert_eq!(started.elapsed(), Duration::from_secs(45));
+        assert_eq!(clock.now_utc(), datetime!(2026-03-02 00:00:15 UTC));
+    }
+}
@@ -2,6 +2,11 @@
 //!
 //! Parses environment variables to configure the CPU mining backend.
 
+use std::sync::Arc;
+
+use super::strategy::{Sequential, WorkStrategy, strategy_by_name};
+use crate::tracing::prelude::*;
+
 /// CPU miner configuration parsed from environment variables.
 #[derive(Debug, Clone)]
 pub struct CpuMinerConfig {
@@ -14,6 +19,9 @@ pub struct CpuMinerConfig {
     /// sleeps for 200ms per second. Useful for avoiding alerts on cloud
     /// instances that monitor for sustained CPU usage.
     pub duty_percent: u8,
+
+    /// Order in which each thread walks the nonce and version space.
+    pub strategy: Arc<dyn WorkStrategy>,
 }
 
 impl CpuMinerConfig {
@@ -26,6 +34,8 @@ impl CpuMinerConfig {
     ///
     /// - `MUJINA_CPUMINER_THREADS`: Number of threads (presence enables CPU mining)
     /// - `MUJINA_CPUMINER_DUTY`: Duty cycle % (default: 50, clamped to 1-100)
+    /// - `MUJINA_CPUMINER_STRATEGY`: `sequential` (default), `version-first`,
+    ///   or `interleaved`
     pub fn from_env() -> Option<Self> {
         let thread_count = std::env::var("MUJINA_CPUMINER_THREADS")
             .ok()
@@ -37,9 +47,18 @@ impl CpuMinerConfig {
             .unwrap_or(50)
             .clamp(1, 100);
 
+        let strategy = match std::env::var("MUJINA_CPUMINER_STRATEGY") {
+            Ok(name) => strategy_by_name(&name).unwrap_or_else(|| {
+                warn!(value = %name, "Invalid MUJINA_CPUMINER_STRATEGY, using sequential");
+                Arc::new(Sequential)
+            }),
+            Err(_) => Arc::new(Sequential),
+        };
+
         Some(Self {
             thread_count,
             duty_percent,
+            strategy,
         })
     }
 }
@@ -31,9 +31,10 @@ use anyhow::Result;
 use bitcoin::block::Header as BlockHeader;
 use bitcoin::hashes::Hash;
 
+use super::strategy::{SearchPoint, SearchSpace, WorkStrategy};
 use crate::{
     asic::hash_thread::{HashTask, HashThreadStatus, Share},
-    job_source::MerkleRootKind,
+    job_source::{MerkleRootKind, header},
     tracing::prelude::*,
     types::HashRate,
 };
@@ -73,12 +74,14 @@ pub enum MinerCommand {
 /// * `cmd_rx` - Channel for receiving commands
 /// * `status` - Shared status for queries
 /// * `duty_percent` - Target CPU duty cycle (1-100)
+/// * `strategy` - Order in which to walk each task's nonce and version space
 /// * `shutdown` - Atomic flag for graceful shutdown
 pub fn run_mining_loop(
     thread_name: String,
     cmd_rx: mpsc::Receiver<MinerCommand>,
     status: Arc<RwLock<HashThreadStatus>>,
     duty_percent: u8,
+    strategy: Arc<dyn WorkStrategy>,
     shutdown: Arc<AtomicBool>,
 ) {
     // Calculate duty cycle timing
@@ -87,7 +90,8 @@ pub fn run_mining_loop(
 
     let mut current_task: Option<HashTask> = None;
     let mut cached_merkle_root: Option<bitcoin::TxMerkleNode> = None;
-    let mut nonce: u32 = 0;
+    let mut space = SearchSpace::new(crate::job_source::GeneralPurposeBits::none());
+    let mut position: u64 = 0;
     let mut last_ntime_tick = Instant::now();
     let mut shares_found: u64 = 0;
     let mut hashes_computed: u64 = 0;
@@ -105,15 +109,17 @@ pub fn run_mining_loop(
                 Ok(cmd) => match cmd {
                     MinerCommand::UpdateTask { task, response_tx } => {
                         cached_merkle_root = compute_merkle_root(&task);
+                        space = SearchSpace::new(task.template.version.gp_bits_mask());
                         let old = current_task.replace(task);
-                        nonce = 0;
+                        position = 0;
                         update_status(&status, true, shares_found);
                         let _ = response_tx.send(Ok(old));
                     }
                     MinerCommand::ReplaceTask { task, response_tx } => {
                         cached_merkle_root = compute_merkle_root(&task);
+                        space = SearchSpace::new(task.template.version.gp_bits_mask());
                         let old = current_task.replace(task);
-                        nonce = 0;
+                        position = 0;
                         update_status(&status, true, shares_found);
                         let _ = response_tx.send(Ok(old));
                     }
@@ -155,8 +161,9 @@ pub fn run_mining_loop(
                     match cmd {
                         MinerCommand::ReplaceTask { task, response_tx } => {
                             cached_merkle_root = compute_merkle_root(&task);
+                            space = SearchSpace::new(task.template.version.gp_bits_mask());
                             let old = current_task.replace(task);
-                            nonce = 0;
+                            position = 0;
                             update_status(&status, true, shares_found);
                             let _ = response_tx.send(Ok(old));
                             // Continue with new task in next iteration
@@ -164,8 +171,9 @@ pub fn run_mining_loop(
                         }
                         MinerCommand::UpdateTask { task, response_tx } => {
                             cached_merkle_root = compute_merkle_root(&task);
+                            space = SearchSpace::new(task.template.version.gp_bits_mask());
                             let old = current_task.replace(task);
-                            nonce = 0;
+                            position = 0;
                             update_status(&status, true, shares_found);
                             let _ = response_tx.send(Ok(old));
                             break;
@@ -181,9 +189,10 @@ pub fn run_mining_loop(
                     }
                 }
 
-                // Try this nonce
+                // Try the next point in the search order
+                let point = strategy.point(position, &space);
                 if let (Some(task), Some(merkle_root)) = (&current_task, cached_merkle_root)
-                    && let Some(share) = try_nonce(task, merkle_root, nonce)
+                    && let Some(share) = try_point(task, merkle_root, point)
                 {
                     shares_found += 1;
                     debug!(
@@ -195,7 +204,9 @@ pub fn run_mining_loop(
                     let _ = task.share_tx.blocking_send(share);
                 }
 
-                nonce = nonce.wrapping_add(1);
+                // Past the end, start over; ntime rolls meanwhile, so the
+                // headers are new.
+                position = (position + 1) % space.size();
                 hashes_computed += 1;
             }
 
@@ -271,7 +282,7 @@ fn probe_peak_hashrate_for(window: Duration) -> HashRate {
             break;
         }
         header.nonce = header.nonce.wrapping_add(1);
-        std::hint::black_box(header.block_hash());
+        std::hint::black_box(header::block_hash(&header));
         hashes += 1;
     }
 
@@ -291,26 +302,24 @@ fn compute_merkle_root(task: &HashTask) -> Option<bitcoin::TxMerkleNode> {
     }
 }
 
-/// Try a single nonce and return a share if it meets the task's share target.
-fn try_nonce(task: &HashTask, merkle_root: bitcoin::TxMerkleNode, nonce: u32) -> Option<Share> {
+/// Try a single point and return a share if it meets the task's share target.
+fn try_point(
+    task: &HashTask,
+    merkle_root: bitcoin::TxMerkleNode,
+    point: SearchPoint,
+) -> Option<Share> {
     let template = task.template.as_ref();
+    let version = point.version_bits.apply_to_version(template.version.base());
 
     // Build block header and compute double-SHA256
-    let header = BlockHeader {
-        version: template.version.base(),
-        prev_blockhash: template.prev_blockhash,
-        merkle_root,
-        time: task.ntime,
-        bits: template.bits,
-        nonce,
-    };
-    let hash = header.block_hash();
+    let header = header::build(template, merkle_root, version, task.ntime, point.nonce);
+    let hash = header::block_hash(&header);
 
     if task.share_target.is_met_by(hash) {
         Some(Share {
-            nonce,
+            nonce: point.nonce,
             hash,
-            version: template.version.base(),
+            version,
             ntime: task.ntime,
             extranonce2: task.en2,
             expected_work: task.share_target.to_work(),
@@ -336,6 +345,14 @@ mod tests {
     use std::sync::Arc;
     use tokio::sync::mpsc as tokio_mpsc;
 
+    /// A point at `nonce` with no version bits rolled.
+    fn nonce_point(nonce: u32) -> SearchPoint {
+        SearchPoint {
+            nonce,
+            version_bits: GeneralPurposeBits::none(),
+        }
+    }
+
     /// Create a test task with a very easy target (high difficulty threshold).
     fn make_test_task() -> HashTask {
         // Use a very easy target so we find shares quickly
@@ -357,6 +374,7 @@ mod tests {
             bits: bitcoin::pow::CompactTarget::from_consensus(0x1d00ffff),
             share_target: easy_target,
             time: 1234567890,
+            max_time: 1234567890,
             merkle_root: MerkleRootKind::Fixed(bitcoin::TxMerkleNode::all_zeros()),
         });
 
@@ -386,7 +404,7 @@ mod tests {
         // With such an easy target, we should find a share within a few attempts
         let mut found = false;
         for nonce in 0..1000 {
-            if try_nonce(&task, merkle_root, nonce).is_some() {
+            if try_point(&task, merkle_root, nonce_point(nonce)).is_some() {
                 found = true;
                 break;
             }
@@ -402,7 +420,7 @@ mod tests {
 
         // Find a valid share
         let share = (0..10000)
-            .find_map(|nonce| try_nonce(&task, merkle_root, nonce))
+            .find_map(|nonce| try_point(&task, merkle_root, nonce_point(nonce)))
             .expect("Should find a share");
 
         // Verify share fields match task
@@ -411,6 +429,44 @@ mod tests {
         assert!(task.share_target.is_met_by(share.hash));
     }
 
+    #[test]
+    fn try_point_rolls_version_bits() {
+        let mut task = make_test_task();
+        let mut template = (*task.template).clone();
+        template.version = VersionTemplate::new(
+            bitcoin::block::Version::from_consensus(0x20000000),
+            GeneralPurposeBits::full(),
+        )
+        .unwrap();
+        task.template = Arc::new(template);
+        let merkle_root = compute_merkle_root(&task).unwrap();
+
+        let bits = GeneralPurposeBits::new([0x00, 0x01]);
+        let share = (0..10000)
+            .find_map(|nonce| {
+                try_point(
+                    &task,
+                    merkle_root,
+                    SearchPoint {
+                        nonce,
+                        version_bits: bits,
+                    },
+                )
+            })
+            .expect("Should find a share");
+
+        assert_eq!(share.version.to_consensus(), 0x20002000);
+        let header = BlockHeader {
+            version: share.version,
+            prev_blockhash: task.template.prev_blockhash,
+            merkle_root,
+            time: share.ntime,
+            bits: task.template.bits,
+            nonce: share.nonce,
+        };
+        assert_eq!(header.block_hash(), share.hash);
+    }
+
     #[test]
     fn test_try_nonce_with_computed_merkle_root() {
         use crate::job_source::{
@@ -452,12 +508,14 @@ mod tests {
             bits: *block_881423::BITS,
             share_target: easy_target,
             time: block_881423::TIME,
+            max_time: block_881423::TIME,
             merkle_root: MerkleRootKind::Computed(MerkleRootTemplate {
                 coinbase1: block_881423::coinbase1_bytes().to_vec(),
                 extranonce1: block_881423::extranonce1_bytes().to_vec(),
                 extranonce2_range,
                 coinbase2: block_881423::coinbase2_bytes().to_vec(),
                 merkle_branches: block_881423::MERKLE_BRANCHES.clone(),
+                cache: Default::default(),
             }),
         });
 
@@ -476,7 +534,7 @@ mod tests {
         let merkle_root = compute_merkle_root(&task).unwrap();
         let mut found = false;
         for nonce in 0..10000 {
-            if try_nonce(&task, merkle_root, nonce).is_some() {
+            if try_point(&task, merkle_root, nonce_point(nonce)).is_some() {
                 found = true;
                 break;
             }
@@ -484,4 +542,34 @@ mod tests {
 
         assert!(found, "Should find a share with computed merkle root");
     }
+
+    #[test]
+    fn reported_hashrate_matches_the_simulated_rate() {
+        use crate::types::{HashRate, HashrateEstimator};
+
+        // Hash as the thread does, each hash stamped as if the thread ran
+        // at exactly 1 kH/s, and estimate from the shares it sends the way
+        // the scheduler does. The target is easy enough to give thousands
+        // of shares, so chance keeps the estimate within a few percent.
+        let rate = 1000;
+        let hashes = 100_000;
+        let mut task = make_test_task();
+        let mut easy = [0xff; 32];
+        easy[0] = 0x0f;
+        task.share_target = Target::from_be_bytes(easy);
+        let merkle_root = compute_merkle_root(&task).unwrap();
+
+        let start = std::time::Instant::now();
+        let at = |hash: u32| start + Duration::from_micros(u64::from(hash) * 1_000_000 / rate);
+        let mut estimator =
+            HashrateEstimator::with_limits(Duration::from_secs(300), 1, hashes as usize);
+        for nonce in 0..hashes {
+            if let Some(share) = try_point(&task, merkle_root, nonce_point(nonce)) {
+                estimator.record_at(at(nonce), share.expected_work);
+            }
+        }
+        let reported = estimator.hashrate_at(at(hashes));
+        let error = (reported.0 as f64 / rate as f64 - 1.0).abs();
+        assert!(error < 0.05, "{reported} reported for {}", HashRate(rate));
+    }
 }
@@ -9,10 +9,17 @@
 //!
 //! - `MUJINA_CPUMINER_THREADS=N` - Number of mining threads (presence enables)
 //! - `MUJINA_CPUMINER_DUTY=P` - Duty cycle percentage (default: 50)
+//! - `MUJINA_CPUMINER_STRATEGY=S` - Search order (default: sequential)
+//!
+//! Built only with the `cpu-miner` feature (on by default).
 
 mod config;
 mod hasher;
+mod strategy;
 mod thread;
 
 pub use config::CpuMinerConfig;
+pub use strategy::{
+    Interleaved, SearchPoint, SearchSpace, Sequential, VersionFirst, WorkStrategy, strategy_by_name,
+};
 pub use thread::CpuHashThread;
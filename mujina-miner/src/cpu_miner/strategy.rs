@@ -0,0 +1,247 @@
+//! Search-space traversal order.
+//!
+//! For a fixed extranonce2 and ntime, a job's search space is every nonce
+//! crossed with every version the pool lets us roll. ASIC chips walk this
+//! space in silicon; the CPU miner walks it in software and a
+//! [`WorkStrategy`] decides the order. Each strategy maps positions
+//! `0..space.size()` onto the space one-to-one, so whatever the order, the
+//! whole space is covered exactly once before the walk repeats.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::job_source::GeneralPurposeBits;
+
+/// Nonces per version in a real header.
+const NONCES: u64 = 1 << 32;
+
+/// Nonces hashed per version before [`Interleaved`] moves to the next.
+const INTERLEAVE_RUN: u64 = 1 << 12;
+
+/// The nonce and version space of one task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchSpace {
+    nonces: u64,
+    version_mask: u16,
+}
+
+impl SearchSpace {
+    /// Full nonce range crossed with every version the mask allows.
+    pub fn new(version_mask: GeneralPurposeBits) -> Self {
+        Self {
+            nonces: NONCES,
+            version_mask: u16::from_be_bytes(*version_mask.as_bytes()),
+        }
+    }
+
+    /// A reduced nonce range, for exhaustive tests.
+    #[cfg(test)]
+    fn with_nonces(nonces: u64, version_mask: GeneralPurposeBits) -> Self {
+        Self {
+            nonces,
+            version_mask: u16::from_be_bytes(*version_mask.as_bytes()),
+        }
+    }
+
+    /// Number of nonces per version.
+    pub fn nonces(&self) -> u64 {
+        self.nonces
+    }
+
+    /// Number of distinct rolled versions, 1 when rolling is not allowed.
+    pub fn versions(&self) -> u64 {
+        1 << self.version_mask.count_ones()
+    }
+
+    /// Total points in the space.
+    pub fn size(&self) -> u64 {
+        self.nonces * self.versions()
+    }
+
+    /// Build the point for a nonce index and version index.
+    ///
+    /// The version index is spread across the mask's set bits, lowest
+    /// first, so indices `0..versions()` give every allowed bit pattern.
+    fn point(&self, nonce: u64, version: u64) -> SearchPoint {
+        let mut bits = 0u16;
+        let mut remaining = self.version_mask;
+        let mut index = version;
+        while remaining != 0 {
+            let lowest = remaining & remaining.wrapping_neg();
+            if index & 1 == 1 {
+                bits |= lowest;
+            }
+            index >>= 1;
+            remaining &= !lowest;
+        }
+        SearchPoint {
+            nonce: nonce as u32,
+            version_bits: GeneralPurposeBits::new(bits.to_be_bytes()),
+        }
+    }
+}
+
+/// One header to hash: a nonce and the version bits to roll in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchPoint {
+    pub nonce: u32,
+    pub version_bits: GeneralPurposeBits,
+}
+
+/// Order in which a hashing thread walks a task's search space.
+pub trait WorkStrategy: Send + Sync + fmt::Debug {
+    /// Map `position` (in `0..space.size()`) to a point in the space.
+    ///
+    /// Must be a bijection over `0..space.size()`.
+    fn point(&self, position: u64, space: &SearchSpace) -> SearchPoint;
+}
+
+/// Every nonce for one version, then the next version.
+///
+/// With no version rolling this is a plain nonce counter, which is how
+/// the CPU miner has always searched.
+#[derive(Debug, Default)]
+pub struct Sequential;
+
+impl WorkStrategy for Sequential {
+    fn point(&self, position: u64, space: &SearchSpace) -> SearchPoint {
+        space.point(position % space.nonces(), position / space.nonces())
+    }
+}
+
+/// Every version for one nonce, then the next nonce.
+#[derive(Debug, Default)]
+pub struct VersionFirst;
+
+impl WorkStrategy for VersionFirst {
+    fn point(&self, position: u64, space: &SearchSpace) -> SearchPoint {
+        space.point(position / space.versions(), position % space.versions())
+    }
+}
+
+/// Runs of nonces, rotating to the next version after each run.
+#[derive(Debug)]
+pub struct Interleaved {
+    run: u64,
+}
+
+impl Default for Interleaved {
+    fn default() -> Self {
+        Self {
+            run: INTERLEAVE_RUN,
+        }
+    }
+}
+
+impl WorkStrategy for Interleaved {
+    fn point(&self, position: u64, space: &SearchSpace) -> SearchPoint {
+        // A run never spans more nonces than exist, so the mapping stays
+        // one-to-one on small test spaces.
+        let run = self.run.min(space.nonces());
+        let block = position / run;
+        let versions = space.versions();
+        let nonce = (block / versions) * run + position % run;
+        space.point(nonce, block % versions)
+    }
+}
+
+/// Look up a strategy by its configuration name.
+pub fn strategy_by_name(name: &str) -> Option<Arc<dyn WorkStrategy>> {
+    match name {
+        "sequential" => Some(Arc::new(Sequential)),
+        "version-first" => Some(Arc::new(VersionFirst)),
+        "interleaved" => Some(Arc::new(Interleaved::default())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    /// Walk the whole space and check every point appears exactly once.
+    fn assert_covers_without_overlap(strategy: &dyn WorkStrategy, space: SearchSpace) {
+        let mut seen = HashSet::new();
+        for position in 0..space.size() {
+            let point = strategy.point(position, &space);
+            assert!(u64::from(point.nonce) < space.nonces());
+            assert!(
+                space_mask(&space).contains(&point.version_bits),
+                "{strategy:?}: bits outside mask"
+            );
+            assert!(
+                seen.insert((point.nonce, *point.version_bits.as_bytes())),
+                "{strategy:?}: {point:?} repeated at {position}"
+            );
+        }
+        assert_eq!(seen.len() as u64, space.size());
+    }
+
+    fn space_mask(space: &SearchSpace) -> GeneralPurposeBits {
+        GeneralPurposeBits::new(space.version_mask.to_be_bytes())
+    }
+
+    fn strategies() -> Vec<Arc<dyn WorkStrategy>> {
+        vec![
+            Arc::new(Sequential),
+            Arc::new(VersionFirst),
+            Arc::new(Interleaved { run: 8 }),
+        ]
+    }
+
+    #[test]
+    fn each_strategy_covers_space_exactly_once() {
+        // Sparse mask (bits 1, 4 and 9) makes the bit spreading non-trivial.
+        let mask = GeneralPurposeBits::new(0b0000_0010_0001_0010u16.to_be_bytes());
+        let space = SearchSpace::with_nonces(64, mask);
+        assert_eq!(space.size(), 64 * 8);
+
+        for strategy in strategies() {
+            assert_covers_without_overlap(strategy.as_ref(), space);
+        }
+    }
+
+    #[test]
+    fn each_strategy_covers_space_without_rolling() {
+        let space = SearchSpace::with_nonces(100, GeneralPurposeBits::none());
+        for strategy in strategies() {
+            assert_covers_without_overlap(strategy.as_ref(), space);
+        }
+    }
+
+    #[test]
+    fn sequential_without_rolling_is_a_nonce_counter() {
+        let space = SearchSpace::new(GeneralPurposeBits::none());
+        assert_eq!(space.size(), NONCES);
+        for position in [0, 1, 12345, NONCES - 1] {
+            let point = Sequential.point(position, &space);
+            assert_eq!(u64::from(point.nonce), position);
+            assert_eq!(point.version_bits, GeneralPurposeBits::none());
+        }
+    }
+
+    #[test]
+    fn orders_differ() {
+        let space = SearchSpace::with_nonces(64, GeneralPurposeBits::full());
+        let walk = |s: &dyn WorkStrategy| (0..16).map(|p| s.point(p, &space)).collect::<Vec<_>>();
+
+        let sequential = walk(&Sequential);
+        let version_first = walk(&VersionFirst);
+        assert!(
+            sequential
+                .iter()
+                .all(|p| p.version_bits == GeneralPurposeBits::none())
+        );
+        assert!(version_first.iter().all(|p| p.nonce == 0));
+    }
+
+    #[test]
+    fn names_resolve() {
+        for name in ["sequential", "version-first", "interleaved"] {
+            assert!(strategy_by_name(name).is_some(), "{name}");
+        }
+        assert!(strategy_by_name("random").is_none());
+    }
+}
@@ -14,6 +14,7 @@ use async_trait::async_trait;
 use tokio::sync::mpsc as tokio_mpsc;
 
 use super::hasher::{self, MinerCommand};
+use super::strategy::WorkStrategy;
 use crate::{
     asic::hash_thread::{
         HashTask, HashThread, HashThreadCapabilities, HashThreadEvent, HashThreadStatus,
@@ -63,7 +64,8 @@ impl CpuHashThread {
     ///
     /// * `name` - Human-readable name for logging
     /// * `duty_percent` - Target CPU duty cycle (1-100)
-    pub fn new(name: String, duty_percent: u8) -> Self {
+    /// * `strategy` - Order in which to walk the search space
+    pub fn new(name: String, duty_percent: u8, strategy: Arc<dyn WorkStrategy>) -> Self {
         let (cmd_tx, cmd_rx) = mpsc::channel();
         let (evt_tx, evt_rx) = tokio_mpsc::channel(100);
 
@@ -74,16 +76,19 @@ impl CpuHashThread {
         let status_clone = Arc::clone(&status);
         let shutdown_clone = Arc::clone(&shutdown);
         let thread_name = name.clone();
+        let span = tracing::Span::current();
 
         // Spawn the mining thread
         let handle = std::thread::Builder::new()
             .name(format!("cpu-miner-{}", name))
             .spawn(move || {
+                let _span = span.entered();
                 hasher::run_mining_loop(
                     thread_name,
                     cmd_rx,
                     status_clone,
                     duty_percent,
+                    strategy,
                     shutdown_clone,
                 );
             })
@@ -102,6 +107,14 @@ impl CpuHashThread {
         }
     }
 
+    /// Shared handle to this thread's status.
+    ///
+    /// Lets the board keep reporting thread state in its telemetry after
+    /// the thread itself has been handed to the scheduler.
+    pub fn status_handle(&self) -> Arc<RwLock<HashThreadStatus>> {
+        Arc::clone(&self.status)
+    }
+
     /// Signal the mining thread to shut down.
     pub fn shutdown(&self) {
         self.shutdown.store(true, Ordering::Relaxed);
@@ -189,3 +202,67 @@ impl HashThread for CpuHashThread {
         self.status.read().unwrap().clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bitcoin::block::{Header, Version};
+    use bitcoin::hashes::Hash;
+    use bitcoin::pow::CompactTarget;
+
+    use super::*;
+    use crate::cpu_miner::Sequential;
+    use crate::job_source::{GeneralPurposeBits, JobTemplate, MerkleRootKind, VersionTemplate};
+    use crate::types::Difficulty;
+
+    /// Run the real thread against a trivially easy target and check the
+    /// share it reports is a valid header hash meeting that target.
+    #[tokio::test]
+    async fn finds_valid_share_at_easy_target() {
+        let easy_target = Difficulty::from_f64(0.0001).to_target();
+        let merkle_root = bitcoin::TxMerkleNode::all_zeros();
+        let template = Arc::new(JobTemplate {
+            id: "easy".into(),
+            prev_blockhash: bitcoin::BlockHash::all_zeros(),
+            version: VersionTemplate::new(
+                Version::from_consensus(0x20000000),
+                GeneralPurposeBits::none(),
+            )
+            .unwrap(),
+            bits: CompactTarget::from_consensus(0x1d00ffff),
+            share_target: easy_target,
+            time: 1234567890,
+            max_time: 1234567890,
+            merkle_root: MerkleRootKind::Fixed(merkle_root),
+        });
+        let (share_tx, mut share_rx) = tokio_mpsc::channel(100);
+        let task = HashTask {
+            template: Arc::clone(&template),
+            en2_range: None,
+            en2: None,
+            share_target: easy_target,
+            ntime: template.time,
+            share_tx,
+        };
+
+        let mut thread = CpuHashThread::new("test".into(), 100, Arc::new(Sequential));
+        thread.update_task(task).await.unwrap();
+
+        let share = tokio::time::timeout(Duration::from_secs(10), share_rx.recv())
+            .await
+            .expect("no share within timeout")
+            .expect("share channel closed");
+
+        let header = Header {
+            version: share.version,
+            prev_blockhash: template.prev_blockhash,
+            merkle_root,
+            time: share.ntime,
+            bits: template.bits,
+            nonce: share.nonce,
+        };
+        assert_eq!(header.block_hash(), share.hash);
+        assert!(easy_target.is_met_by(share.hash));
+    }
+}
@@ -3,46 +3,285 @@
 //! This module handles the core daemon functionality including initialization,
 //! task management, signal handling, and graceful shutdown.
 
-use std::env;
+use std::{
+    env, io,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    thread,
+};
 
+use anyhow::Context;
+use tokio::runtime::{self, Runtime};
 use tokio::signal::unix::{self, SignalKind};
 use tokio::sync::{mpsc, watch};
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
 use crate::api_client::types::MinerTelemetry;
+#[cfg(feature = "socks5")]
+use crate::stratum_v1::Socks5Proxy;
 use crate::tracing::prelude::*;
 use crate::{
-    api::{self, ApiConfig, commands::SchedulerCommand},
-    backplane::Backplane,
-    cpu_miner::CpuMinerConfig,
+    api::{
+        self, ApiConfig, BindError,
+        commands::SchedulerCommand,
+        energy::EnergyConfig,
+        history::{History, HistoryConfig},
+    },
+    backplane::{Backplane, NoBoardsError},
+    board::ShutdownMode,
+    burn_in, cgminer_api,
+    clock::{self, Clock},
+    host_load,
     job_source::{
         SourceCommand, SourceEvent,
         dummy::DummySource,
         forced_rate::{ForcedRateConfig, ForcedRateSource},
+        stale_ratio::StaleFailoverPolicy,
         stratum_v1::StratumV1Source,
     },
-    scheduler::{self, SourceRegistration, ThreadRegistration},
-    stratum_v1::{PoolConfig as StratumPoolConfig, TcpConnector},
-    transport::{CpuDeviceInfo, TransportEvent, UsbTransport, cpu as cpu_transport},
+    network, payout,
+    scheduler::{
+        self, HighDifficultyAction, MiningMode, PoolAssignment, PoolOutagePolicy,
+        SourceRegistration, TelemetryCadence, ThreadRegistration,
+    },
+    stats_csv,
+    stratum_v1::{
+        Connector, DEFAULT_MAX_LINE, PoolConfig as StratumPoolConfig, SocketOptions, StratumError,
+        SuggestDifficulty, TcpConnector, env_setting,
+    },
+    summary_log,
+    transport::{TransportEvent, UsbTransport},
+    types::Network,
 };
 
-/// The main daemon.
-pub struct Daemon {
+/// Why the daemon stopped, or couldn't start.
+///
+/// The failures an operator can act on have their own variants, so a
+/// supervisor or test can tell them apart; anything else is `Other`.
+#[derive(Debug, thiserror::Error)]
+pub enum DaemonError {
+    /// A setting the daemon can't run with.
+    #[error("invalid {var}: {reason}")]
+    ConfigInvalid { var: &'static str, reason: String },
+
+    /// No hash board came up within `MUJINA_BOARD_WAIT_SECS`.
+    #[error(transparent)]
+    NoBoards(#[from] NoBoardsError),
+
+    /// The API server couldn't listen on its address.
+    #[error(transparent)]
+    BindFailed(#[from] BindError),
+
+    /// A pool rejected the worker's credentials. Reconnecting won't help.
+    #[error("pool {pool} rejected the worker's credentials: {reason}")]
+    PoolAuthFailed { pool: String, reason: String },
+
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl From<anyhow::Error> for DaemonError {
+    /// Classify an error from one of the daemon's components by its cause.
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<NoBoardsError>() {
+            Ok(e) => return e.into(),
+            Err(e) => e,
+        };
+        match e.downcast::<BindError>() {
+            Ok(e) => e.into(),
+            Err(e) => Self::Other(e),
+        }
+    }
+}
+
+/// The error stopping the daemon when a pool source fails, if the failure
+/// is one that should: rejected credentials won't fix themselves.
+fn pool_failure(pool: &str, e: &anyhow::Error) -> Option<DaemonError> {
+    match e.downcast_ref() {
+        Some(StratumError::AuthorizationFailed(reason)) => Some(DaemonError::PoolAuthFailed {
+            pool: pool.to_string(),
+            reason: reason.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// Run a Stratum v1 source until it ends, stopping the daemon through
+/// `fatal_tx` if the pool rejects its credentials.
+///
+/// Named pools run without a `fatal_tx`: one rejecting the credentials
+/// is left failed, so failover passes it over, and the other pools mine
+/// on.
+async fn run_stratum_source(
+    source: StratumV1Source,
+    pool: String,
+    fatal_tx: Option<mpsc::Sender<DaemonError>>,
+) {
+    if let Err(e) = source.run().await {
+        error!("Stratum v1 source error: {}", e);
+        if let Some(fatal) = pool_failure(&pool, &e) {
+            match fatal_tx {
+                Some(fatal_tx) => {
+                    let _ = fatal_tx.send(fatal).await;
+                }
+                None => warn!(error = %fatal, "Named pool failed, mining on the others"),
+            }
+        }
+    }
+}
+
+/// The API server's port when `MUJINA_API_LISTEN` doesn't give one.
+/// ASCII 'M' (77) + 'U' (85) = 7785.
+const API_PORT: u16 = 7785;
+
+/// The API server's address from `MUJINA_API_LISTEN`, which may omit the
+/// port.
+fn api_listen_from_env() -> String {
+    match env::var("MUJINA_API_LISTEN") {
+        Ok(addr) if addr.contains(':') => addr,
+        Ok(addr) => format!("{addr}:{API_PORT}"),
+        Err(_) => format!("127.0.0.1:{API_PORT}"),
+    }
+}
+
+/// Builds a [`Daemon`], for embedding it or running it in a test.
+///
+/// [`DaemonBuilder::from_env`] starts from what the environment asks for,
+/// [`DaemonBuilder::new`] from a daemon with no boards at all; the methods
+/// then choose where boards come from, where the API listens, who can stop
+/// the daemon, and the clock it reads the time of day from. Everything else
+/// is still read from the environment when the daemon runs. Elapsed time
+/// comes from the Tokio runtime, so a test can pause it.
+#[derive(Debug, Clone)]
+pub struct DaemonBuilder {
+    usb: bool,
+    #[cfg(feature = "cpu-miner")]
+    cpu_miner: Option<crate::cpu_miner::CpuMinerConfig>,
+    api_listen: String,
     shutdown: CancellationToken,
-    tracker: TaskTracker,
+    clock: Arc<dyn Clock>,
 }
 
-impl Daemon {
-    /// Create a new daemon instance.
+impl DaemonBuilder {
+    /// A daemon without boards, with its API on the default address.
     pub fn new() -> Self {
         Self {
+            usb: false,
+            #[cfg(feature = "cpu-miner")]
+            cpu_miner: None,
+            api_listen: format!("127.0.0.1:{API_PORT}"),
             shutdown: CancellationToken::new(),
+            clock: clock::system(),
+        }
+    }
+
+    /// The daemon the environment configures: USB boards unless
+    /// `MUJINA_USB_DISABLE` is set, the CPU miner when
+    /// `MUJINA_CPUMINER_THREADS` is, and the API on `MUJINA_API_LISTEN`.
+    pub fn from_env() -> Self {
+        Self {
+            usb: env::var("MUJINA_USB_DISABLE").is_err(),
+            #[cfg(feature = "cpu-miner")]
+            cpu_miner: crate::cpu_miner::CpuMinerConfig::from_env(),
+            api_listen: api_listen_from_env(),
+            shutdown: CancellationToken::new(),
+            clock: clock::system(),
+        }
+    }
+
+    /// Whether to discover hash boards on USB.
+    pub fn usb(mut self, enabled: bool) -> Self {
+        self.usb = enabled;
+        self
+    }
+
+    /// Hash on the CPU as a virtual board, or not with `None`.
+    #[cfg(feature = "cpu-miner")]
+    pub fn cpu_miner(mut self, config: Option<crate::cpu_miner::CpuMinerConfig>) -> Self {
+        self.cpu_miner = config;
+        self
+    }
+
+    /// Where the API server listens. Port 0 picks a free one.
+    pub fn api_listen(mut self, addr: impl Into<String>) -> Self {
+        self.api_listen = addr.into();
+        self
+    }
+
+    /// Stop the daemon when `token` is cancelled, as a signal would. The
+    /// daemon cancels it too when it stops for any other reason.
+    pub fn shutdown(mut self, token: CancellationToken) -> Self {
+        self.shutdown = token;
+        self
+    }
+
+    /// Read the time of day from `clock`, for daily share counts and
+    /// timestamps.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn build(self) -> Daemon {
+        Daemon {
+            shutdown: self.shutdown,
             tracker: TaskTracker::new(),
+            usb: self.usb,
+            #[cfg(feature = "cpu-miner")]
+            cpu_miner: self.cpu_miner,
+            api_listen: self.api_listen,
+            clock: self.clock,
         }
     }
+}
+
+impl Default for DaemonBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The main daemon.
+pub struct Daemon {
+    shutdown: CancellationToken,
+    tracker: TaskTracker,
+    usb: bool,
+    #[cfg(feature = "cpu-miner")]
+    cpu_miner: Option<crate::cpu_miner::CpuMinerConfig>,
+    api_listen: String,
+    clock: Arc<dyn Clock>,
+}
+
+impl Daemon {
+    /// Create the daemon the environment configures.
+    pub fn new() -> Self {
+        DaemonBuilder::from_env().build()
+    }
 
     /// Run the daemon until shutdown is requested.
-    pub async fn run(self) -> anyhow::Result<()> {
+    pub async fn run(self) -> Result<(), DaemonError> {
+        // Checked before anything starts: a bad payout address should stop
+        // the daemon, not surface when a block is found.
+        let mining_mode = MiningMode::from_env();
+        let network = network::from_env();
+        if network != Network::Bitcoin {
+            info!(network = network::name(network), "Mining a test network");
+        }
+        if mining_mode == MiningMode::Lottery {
+            info!("Lottery mode: mining for a block rather than steady shares");
+            if env::var("MUJINA_POOL_URL").is_ok() {
+                let user = env::var("MUJINA_POOL_USER").unwrap_or_default();
+                let address =
+                    payout::payout_address_from_username(&user, network).map_err(|e| {
+                        DaemonError::ConfigInvalid {
+                            var: "MUJINA_POOL_USER",
+                            reason: format!("must be the solo payout address in lottery mode: {e}"),
+                        }
+                    })?;
+                info!(address = %address, "Block rewards pay to this address");
+            }
+        }
+
         // Create channels for component communication. Each transport gets its
         // own event channel; the backplane waits for one enumeration completion
         // per channel.
@@ -51,7 +290,7 @@ impl Daemon {
         let mut transport_rxs: Vec<mpsc::Receiver<TransportEvent>> = Vec::new();
 
         // Create and start USB transport discovery
-        if std::env::var("MUJINA_USB_DISABLE").is_err() {
+        if self.usb {
             let (usb_tx, usb_rx) = mpsc::channel::<TransportEvent>(100);
             let usb_transport = UsbTransport::new(usb_tx);
             if let Err(e) = usb_transport.start_discovery(self.shutdown.clone()).await {
@@ -63,7 +302,10 @@ impl Daemon {
         }
 
         // Inject CPU miner virtual device if configured
-        if let Some(config) = CpuMinerConfig::from_env() {
+        #[cfg(feature = "cpu-miner")]
+        if let Some(config) = self.cpu_miner.clone() {
+            use crate::transport::{CpuDeviceInfo, cpu as cpu_transport};
+
             info!(
                 threads = config.thread_count,
                 duty = config.duty_percent,
@@ -73,8 +315,7 @@ impl Daemon {
             let device = TransportEvent::Cpu(cpu_transport::TransportEvent::CpuDeviceConnected(
                 CpuDeviceInfo {
                     device_id: format!("cpu-{}x{}%", config.thread_count, config.duty_percent),
-                    thread_count: config.thread_count,
-                    duty_percent: config.duty_percent,
+                    config,
                 },
             ));
             // Send the device and its enumeration completion, then drop the
@@ -87,26 +328,42 @@ impl Daemon {
                 .await;
             transport_rxs.push(cpu_rx);
         }
+        #[cfg(not(feature = "cpu-miner"))]
+        if env::var_os("MUJINA_CPUMINER_THREADS").is_some() {
+            warn!("MUJINA_CPUMINER_THREADS set, but built without the cpu-miner feature");
+        }
 
         // Board registration channel: backplane forwards board
         // registrations here, the API server collects and serves them.
         let (board_reg_tx, board_reg_rx) = mpsc::channel(10);
+        // Board commands: the API server sends, the backplane handles.
+        let (board_cmd_tx, board_cmd_rx) = mpsc::channel(10);
 
         // Create and start backplane
-        let mut backplane = Backplane::new(transport_rxs, thread_tx, board_reg_tx);
+        // An error from the backplane (e.g. giving up waiting for boards)
+        // stops the daemon.
+        let (fatal_tx, mut fatal_rx) = mpsc::channel::<DaemonError>(1);
+        // How far boards shut down, set from the signal that stops the
+        // daemon. Anything else stopping it powers them off.
+        let shutdown_profiles = ShutdownProfiles::from_env();
+        let (shutdown_mode_tx, shutdown_mode_rx) = watch::channel(ShutdownMode::PowerOff);
+        let mut backplane = Backplane::new(transport_rxs, thread_tx, board_reg_tx, board_cmd_rx);
         self.tracker.spawn({
             let shutdown = self.shutdown.clone();
+            let fatal_tx = fatal_tx.clone();
             async move {
                 tokio::select! {
                     result = backplane.run() => {
                         if let Err(e) = result {
                             error!("Backplane error: {}", e);
+                            let _ = fatal_tx.send(e.into()).await;
                         }
                     }
                     _ = shutdown.cancelled() => {}
                 }
 
-                backplane.shutdown_all_boards().await;
+                let mode = *shutdown_mode_rx.borrow();
+                backplane.shutdown_all_boards(mode).await;
             }
         });
 
@@ -115,21 +372,100 @@ impl Daemon {
         // - MUJINA_POOL_URL: Pool address (e.g., stratum+tcp://localhost:3333)
         // - MUJINA_POOL_USER: Worker username (optional, defaults to "mujina-testing")
         // - MUJINA_POOL_PASS: Worker password (optional, defaults to "x")
+        // - MUJINA_POOL_SUGGEST_DIFFICULTY: auto, off, or a fixed difficulty
+        // - MUJINA_POOL_MIN_DIFFICULTY: local share difficulty floor (optional)
+        // - MUJINA_POOL_JOB_DEBOUNCE_MS: window for coalescing job updates
+        // - MUJINA_POOL_MAX_JOBS_PER_SEC: job notifications taken in per second
+        // - MUJINA_POOL_ACK_SLA_MS: submit ack latency to warn beyond
+        // - MUJINA_LOG_SHARE_DIFFICULTY: accepted-share log threshold (optional)
+        // - MUJINA_NETWORK: network the pool should be mining
+        // - MUJINA_POOL_SUBMIT_AHEAD: shares that may await an answer at once
+        // - MUJINA_POOL_SUBMIT_BATCH_MS: window for gathering shares into one write
+        // - MUJINA_STATS_DAY_OFFSET: UTC offset at which daily share counts restart
+        // - MUJINA_POOL_MAX_JOB_AGE_SECS: job age beyond which shares are withheld
+        // - MUJINA_POOL_NTIME_ROLL_SECS: how far ntime may roll past a job's
+        // - MUJINA_POOL_FLUSH_GRACE_MS: how long shutdown waits on queued shares
+        // - MUJINA_POOL_JOB_HISTORY: recent jobs whose shares are still sent
+        // - MUJINA_POOL_RECONNECT_GRACE_MS: outage below which a reconnect isn't counted
+        // - MUJINA_POOLS: further named pools, for boards assigned to them
+        // - MUJINA_BOARD_POOLS: which named pool each board mines on
         let (source_event_tx, source_event_rx) = mpsc::channel::<SourceEvent>(100);
         let (source_cmd_tx, source_cmd_rx) = mpsc::channel(10);
+        let pool_assignment = PoolAssignment::from_env();
 
         if let Ok(pool_url) = env::var("MUJINA_POOL_URL") {
             // Use Stratum v1 source
-            let pool_user =
-                env::var("MUJINA_POOL_USER").unwrap_or_else(|_| "mujina-testing".to_string());
-            let pool_pass = env::var("MUJINA_POOL_PASS").unwrap_or_else(|_| "x".to_string());
-
-            let stratum_config = StratumPoolConfig {
-                url: pool_url.clone(),
-                username: pool_user,
-                password: pool_pass,
-                user_agent: "mujina-miner/0.1.0-alpha".to_string(),
+            let mut stratum_config = StratumPoolConfig {
+                network,
+                ..StratumPoolConfig::from_env(pool_url.clone())
             };
+            // Re-suggesting as the hashrate estimate moves only churns
+            // difficulty; a block is a block at any share difficulty.
+            if mining_mode == MiningMode::Lottery
+                && stratum_config.suggest_difficulty == SuggestDifficulty::Auto
+            {
+                stratum_config.suggest_difficulty = SuggestDifficulty::Off;
+            }
+
+            let bind_address = env::var("MUJINA_POOL_BIND_ADDRESS").ok();
+            let max_line = env_setting(
+                "MUJINA_POOL_MAX_LINE_BYTES",
+                DEFAULT_MAX_LINE,
+                "using default",
+                |val| val.parse().ok().filter(|&bytes| bytes > 0),
+            );
+            let socket_options = SocketOptions::from_env();
+            #[cfg_attr(not(feature = "socks5"), expect(unused_variables))]
+            let proxy = pool_proxy_from_env()?;
+            let pool_connector = |url: &str| -> Box<dyn Connector> {
+                let mut connector = TcpConnector::new(url.to_string())
+                    .with_max_line(max_line)
+                    .with_socket_options(socket_options);
+                if let Some(bind) = &bind_address {
+                    connector = connector.with_bind_address(bind.clone());
+                }
+                #[cfg(feature = "socks5")]
+                if let Some(proxy) = &proxy {
+                    connector = connector.with_proxy(proxy.clone());
+                }
+                Box::new(connector)
+            };
+
+            // Named pools share the default pool's settings but for the
+            // address, and mine only for the boards assigned to them.
+            let named_pools = named_pools_from_env();
+            for pool in pool_assignment.pools() {
+                if !named_pools.iter().any(|(name, _)| name == pool) {
+                    warn!(pool = %pool, "MUJINA_BOARD_POOLS names a pool not in MUJINA_POOLS, its boards stay idle");
+                }
+            }
+            for (name, url) in named_pools {
+                let (event_tx, event_rx) = mpsc::channel::<SourceEvent>(100);
+                let (cmd_tx, cmd_rx) = mpsc::channel(10);
+                let source = StratumV1Source::new(
+                    StratumPoolConfig {
+                        url: url.clone(),
+                        ..stratum_config.clone()
+                    },
+                    cmd_rx,
+                    event_tx,
+                    self.shutdown.clone(),
+                    pool_connector(&url),
+                )
+                .with_clock(self.clock.clone());
+                source_reg_tx
+                    .send(SourceRegistration {
+                        name: format!("{} ({name})", source.name()),
+                        url: Some(url.clone()),
+                        event_rx,
+                        command_tx: cmd_tx,
+                        stats_rx: Some(source.stats()),
+                        pool: Some(name),
+                    })
+                    .await
+                    .context("Scheduler stopped before the source registered")?;
+                self.tracker.spawn(run_stratum_source(source, url, None));
+            }
 
             // Optionally wrap with ForcedRateSource for testing
             if let Some(forced_rate_config) = ForcedRateConfig::from_env() {
@@ -147,16 +483,18 @@ impl Daemon {
                     inner_cmd_rx,
                     inner_event_tx,
                     self.shutdown.clone(),
-                    Box::new(TcpConnector::new(pool_url.clone())),
-                );
+                    pool_connector(&pool_url),
+                )
+                .with_clock(self.clock.clone());
                 let stratum_name = stratum_source.name();
+                let stratum_stats = stratum_source.stats();
 
                 // Spawn stratum source
-                self.tracker.spawn(async move {
-                    if let Err(e) = stratum_source.run().await {
-                        error!("Stratum v1 source error: {}", e);
-                    }
-                });
+                self.tracker.spawn(run_stratum_source(
+                    stratum_source,
+                    pool_url.clone(),
+                    Some(fatal_tx.clone()),
+                ));
 
                 // Create and spawn wrapper (uses outer channels from above)
                 let forced_rate = ForcedRateSource::new(
@@ -174,8 +512,11 @@ impl Daemon {
                         url: Some(pool_url.clone()),
                         event_rx: source_event_rx,
                         command_tx: source_cmd_tx,
+                        stats_rx: Some(stratum_stats),
+                        pool: None,
                     })
-                    .await?;
+                    .await
+                    .context("Scheduler stopped before the source registered")?;
 
                 self.tracker.spawn(async move {
                     if let Err(e) = forced_rate.run().await {
@@ -189,23 +530,27 @@ impl Daemon {
                     source_cmd_rx,
                     source_event_tx,
                     self.shutdown.clone(),
-                    Box::new(TcpConnector::new(pool_url.clone())),
-                );
+                    pool_connector(&pool_url),
+                )
+                .with_clock(self.clock.clone());
 
                 source_reg_tx
                     .send(SourceRegistration {
                         name: stratum_source.name(),
-                        url: Some(pool_url),
+                        url: Some(pool_url.clone()),
                         event_rx: source_event_rx,
                         command_tx: source_cmd_tx,
+                        stats_rx: Some(stratum_source.stats()),
+                        pool: None,
                     })
-                    .await?;
+                    .await
+                    .context("Scheduler stopped before the source registered")?;
 
-                self.tracker.spawn(async move {
-                    if let Err(e) = stratum_source.run().await {
-                        error!("Stratum v1 source error: {}", e);
-                    }
-                });
+                self.tracker.spawn(run_stratum_source(
+                    stratum_source,
+                    pool_url.clone(),
+                    Some(fatal_tx.clone()),
+                ));
             }
         } else {
             // Use DummySource
@@ -224,8 +569,11 @@ impl Daemon {
                     url: None,
                     event_rx: source_event_rx,
                     command_tx: source_cmd_tx,
+                    stats_rx: None,
+                    pool: None,
                 })
-                .await?;
+                .await
+                .context("Scheduler stopped before the source registered")?;
 
             self.tracker.spawn(async move {
                 if let Err(e) = dummy_source.run().await {
@@ -240,6 +588,16 @@ impl Daemon {
         // Command channel: API sends commands, scheduler processes them.
         let (scheduler_cmd_tx, scheduler_cmd_rx) = mpsc::channel::<SchedulerCommand>(16);
 
+        // Host load, for telemetry to back off while the host is busy.
+        let (host_loaded_tx, host_loaded_rx) = watch::channel(false);
+        if let Some(per_cpu) = host_load::threshold_from_env() {
+            self.tracker.spawn(host_load::task(
+                per_cpu,
+                host_loaded_tx,
+                self.shutdown.clone(),
+            ));
+        }
+
         // Start the scheduler
         self.tracker.spawn(scheduler::task(
             self.shutdown.clone(),
@@ -247,31 +605,117 @@ impl Daemon {
             source_reg_rx,
             miner_telemetry_tx,
             scheduler_cmd_rx,
+            mining_mode,
+            PoolOutagePolicy::from_env(),
+            HighDifficultyAction::from_env(),
+            pool_assignment,
+            StaleFailoverPolicy::from_env(),
+            TelemetryCadence::from_env(),
+            host_loaded_rx,
+            self.clock.clone(),
+        ));
+
+        let board_registry = api::collect_boards(board_reg_rx, api::BoardOrder::from_env());
+
+        if let Some(interval) = summary_log::interval_from_env() {
+            self.tracker
+                .spawn(summary_log::task(interval, self.shutdown.clone(), {
+                    let miner_telemetry_rx = miner_telemetry_rx.clone();
+                    let board_registry = board_registry.clone();
+                    move || api::miner_telemetry(&miner_telemetry_rx, &board_registry)
+                }));
+        }
+
+        if let Some(config) = stats_csv::config_from_env() {
+            self.tracker.spawn(stats_csv::task(
+                config,
+                self.shutdown.clone(),
+                self.clock.clone(),
+                {
+                    let miner_telemetry_rx = miner_telemetry_rx.clone();
+                    let board_registry = board_registry.clone();
+                    move || api::miner_telemetry(&miner_telemetry_rx, &board_registry)
+                },
+            ));
+        }
+
+        let history_config = HistoryConfig::from_env();
+        let history = Arc::new(Mutex::new(
+            history_config.map_or_else(History::default, |c| History::new(c.capacity)),
+        ));
+        if let Some(config) = history_config {
+            self.tracker.spawn(api::history::task(
+                config,
+                history.clone(),
+                self.shutdown.clone(),
+                self.clock.clone(),
+                {
+                    let board_registry = board_registry.clone();
+                    move || {
+                        board_registry
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .boards()
+                    }
+                },
+            ));
+        }
+
+        self.tracker.spawn(api::energy::task(
+            EnergyConfig::from_env(),
+            board_registry.clone(),
+            self.shutdown.clone(),
         ));
 
+        if let Some(config) = burn_in::config_from_env() {
+            self.tracker.spawn(burn_in::task(
+                config,
+                self.shutdown.clone(),
+                fatal_tx.clone(),
+                {
+                    let miner_telemetry_rx = miner_telemetry_rx.clone();
+                    let board_registry = board_registry.clone();
+                    move || api::miner_telemetry(&miner_telemetry_rx, &board_registry)
+                },
+            ));
+        }
+
+        if let Some(bind_addr) = cgminer_api::listen_from_env() {
+            self.tracker
+                .spawn(cgminer_api::task(bind_addr, self.shutdown.clone(), {
+                    let miner_telemetry_rx = miner_telemetry_rx.clone();
+                    let board_registry = board_registry.clone();
+                    move || api::miner_telemetry(&miner_telemetry_rx, &board_registry)
+                }));
+        }
+
         // Start the API server
         self.tracker.spawn({
             let shutdown = self.shutdown.clone();
+            let bind_addr = self.api_listen.clone();
             async move {
-                // ASCII 'M' (77) + 'U' (85) = 7785
-                const API_PORT: u16 = 7785;
-
-                let bind_addr = match env::var("MUJINA_API_LISTEN") {
-                    Ok(addr) if addr.contains(':') => addr,
-                    Ok(addr) => format!("{addr}:{API_PORT}"),
-                    Err(_) => format!("127.0.0.1:{API_PORT}"),
+                let config = ApiConfig {
+                    bind_addr,
+                    axeos_compat: env::var_os("MUJINA_AXEOS_COMPAT").is_some(),
                 };
-                let config = ApiConfig { bind_addr };
                 if let Err(e) = api::serve(
                     config,
                     shutdown,
                     miner_telemetry_rx,
-                    board_reg_rx,
+                    board_registry,
                     scheduler_cmd_tx,
+                    board_cmd_tx,
+                    history,
                 )
                 .await
                 {
+                    // Without the API the miner can't be watched or
+                    // controlled, so failing to listen stops the daemon.
+                    let e = DaemonError::from(e);
                     error!("API server error: {}", e);
+                    if matches!(e, DaemonError::BindFailed(_)) {
+                        let _ = fatal_tx.send(e).await;
+                    }
                 }
             }
         });
@@ -282,18 +726,29 @@ impl Daemon {
         info!("For debugging, set MUJINA_LOG=debug or trace.");
 
         // Install signal handlers
-        let mut sigint = unix::signal(SignalKind::interrupt())?;
-        let mut sigterm = unix::signal(SignalKind::terminate())?;
+        let mut sigint =
+            unix::signal(SignalKind::interrupt()).context("Failed to install SIGINT handler")?;
+        let mut sigterm =
+            unix::signal(SignalKind::terminate()).context("Failed to install SIGTERM handler")?;
 
-        // Wait for shutdown signal
-        tokio::select! {
+        // Wait for shutdown signal or a fatal error
+        let fatal = tokio::select! {
             _ = sigint.recv() => {
-                info!("Received SIGINT.");
+                let mode = shutdown_profiles.for_signal(StopSignal::Interrupt);
+                info!(?mode, "Received SIGINT.");
+                shutdown_mode_tx.send_replace(mode);
+                None
             },
             _ = sigterm.recv() => {
-                info!("Received SIGTERM.");
+                let mode = shutdown_profiles.for_signal(StopSignal::Terminate);
+                info!(?mode, "Received SIGTERM.");
+                shutdown_mode_tx.send_replace(mode);
+                None
             },
-        }
+            Some(e) = fatal_rx.recv() => Some(e),
+            // A task finished what the daemon was run for.
+            _ = self.shutdown.cancelled() => None,
+        };
 
         // Initiate shutdown
         self.shutdown.cancel();
@@ -302,7 +757,10 @@ impl Daemon {
         self.tracker.wait().await;
         info!("Exiting.");
 
-        Ok(())
+        match fatal {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
     }
 }
 
@@ -311,3 +769,497 @@ impl Default for Daemon {
         Self::new()
     }
 }
+
+/// A signal asking the daemon to stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopSignal {
+    /// SIGINT, as from Ctrl-C at a terminal.
+    Interrupt,
+    /// SIGTERM, as from `systemctl stop`.
+    Terminate,
+}
+
+/// How far boards shut down for each stop signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ShutdownProfiles {
+    pub sigint: ShutdownMode,
+    pub sigterm: ShutdownMode,
+}
+
+impl ShutdownProfiles {
+    /// Read `MUJINA_SIGINT_SHUTDOWN` and `MUJINA_SIGTERM_SHUTDOWN`
+    /// (`power-off` or `idle`), warning and powering off on other values.
+    pub fn from_env() -> Self {
+        Self {
+            sigint: shutdown_mode_from_env("MUJINA_SIGINT_SHUTDOWN"),
+            sigterm: shutdown_mode_from_env("MUJINA_SIGTERM_SHUTDOWN"),
+        }
+    }
+
+    /// The shutdown profile `signal` runs.
+    pub fn for_signal(&self, signal: StopSignal) -> ShutdownMode {
+        match signal {
+            StopSignal::Interrupt => self.sigint,
+            StopSignal::Terminate => self.sigterm,
+        }
+    }
+}
+
+fn shutdown_mode_from_env(var: &str) -> ShutdownMode {
+    match env::var(var).as_deref() {
+        Err(_) | Ok("power-off") => ShutdownMode::PowerOff,
+        Ok("idle") => ShutdownMode::Idle,
+        Ok(other) => {
+            warn!(value = %other, "Invalid {var}, using power-off");
+            ShutdownMode::PowerOff
+        }
+    }
+}
+
+/// The SOCKS5 proxy `MUJINA_POOL_PROXY` names for pool connections.
+///
+/// A proxy that can't be used stops the daemon rather than letting it
+/// connect directly, which may be exactly what the proxy was to prevent.
+#[cfg(feature = "socks5")]
+fn pool_proxy_from_env() -> Result<Option<Socks5Proxy>, DaemonError> {
+    let Ok(value) = env::var("MUJINA_POOL_PROXY") else {
+        return Ok(None);
+    };
+    Socks5Proxy::parse(&value)
+        .map(Some)
+        .map_err(|e| DaemonError::ConfigInvalid {
+            var: "MUJINA_POOL_PROXY",
+            reason: e.to_string(),
+        })
+}
+
+#[cfg(not(feature = "socks5"))]
+fn pool_proxy_from_env() -> Result<Option<()>, DaemonError> {
+    match env::var_os("MUJINA_POOL_PROXY") {
+        Some(_) => Err(DaemonError::ConfigInvalid {
+            var: "MUJINA_POOL_PROXY",
+            reason: "this build has no SOCKS5 support (the socks5 feature)".into(),
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Read `MUJINA_POOLS`, a comma-separated list of `name=url` pairs naming
+/// pools beyond the default, warning about and skipping malformed or
+/// repeated entries.
+fn named_pools_from_env() -> Vec<(String, String)> {
+    let Ok(value) = env::var("MUJINA_POOLS") else {
+        return Vec::new();
+    };
+    let mut pools: Vec<(String, String)> = Vec::new();
+    for entry in value.split(',').filter(|e| !e.trim().is_empty()) {
+        match entry.split_once('=') {
+            Some((name, url)) if !name.trim().is_empty() && !url.trim().is_empty() => {
+                let name = name.trim();
+                if pools.iter().any(|(n, _)| n == name) {
+                    warn!(pool = %name, "Pool named twice in MUJINA_POOLS, using the first");
+                } else {
+                    pools.push((name.to_string(), url.trim().to_string()));
+                }
+            }
+            _ => warn!(entry = %entry, "Invalid MUJINA_POOLS entry, ignoring"),
+        }
+    }
+    pools
+}
+
+/// Settings for the Tokio runtime the daemon runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    /// Number of async worker threads.
+    pub worker_threads: NonZeroUsize,
+}
+
+impl RuntimeConfig {
+    /// Read `MUJINA_WORKER_THREADS`, falling back to one worker per core.
+    ///
+    /// Invalid values (zero, non-numeric) warn and use the default. Because
+    /// this runs before the runtime exists, call it after tracing is set up
+    /// so the warning is visible.
+    pub fn from_env() -> Self {
+        let worker_threads = match env::var("MUJINA_WORKER_THREADS") {
+            Ok(val) => match val.parse::<NonZeroUsize>() {
+                Ok(n) => n,
+                Err(_) => {
+                    warn!(value = %val, "Invalid MUJINA_WORKER_THREADS, using core count");
+                    default_worker_threads()
+                }
+            },
+            Err(_) => default_worker_threads(),
+        };
+        Self { worker_threads }
+    }
+
+    /// Build a multi-threaded runtime with these settings.
+    pub fn build(&self) -> io::Result<Runtime> {
+        runtime::Builder::new_multi_thread()
+            .worker_threads(self.worker_threads.get())
+            .enable_all()
+            .build()
+    }
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            worker_threads: default_worker_threads(),
+        }
+    }
+}
+
+/// One worker per available core, as `#[tokio::main]` would choose.
+fn default_worker_threads() -> NonZeroUsize {
+    thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+    use std::time::Duration;
+
+    #[test]
+    fn runtime_uses_configured_worker_threads() {
+        let config = RuntimeConfig {
+            worker_threads: NonZeroUsize::new(2).unwrap(),
+        };
+        let rt = config.build().unwrap();
+        assert_eq!(rt.metrics().num_workers(), 2);
+    }
+
+    #[test]
+    #[serial]
+    fn worker_threads_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_WORKER_THREADS", "3") };
+        assert_eq!(RuntimeConfig::from_env().worker_threads.get(), 3);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_WORKER_THREADS", "0") };
+        assert_eq!(RuntimeConfig::from_env(), RuntimeConfig::default());
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_WORKER_THREADS") };
+        assert_eq!(RuntimeConfig::from_env(), RuntimeConfig::default());
+    }
+
+    #[test]
+    #[serial]
+    fn named_pools_from_env_skips_bad_entries() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var(
+                "MUJINA_POOLS",
+                "test=stratum+tcp://a:3333, nourl=,test=stratum+tcp://b:3333,other=stratum+tcp://c:3333",
+            )
+        };
+        assert_eq!(
+            named_pools_from_env(),
+            [
+                ("test".to_string(), "stratum+tcp://a:3333".to_string()),
+                ("other".to_string(), "stratum+tcp://c:3333".to_string()),
+            ]
+        );
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_POOLS") };
+        assert!(named_pools_from_env().is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn shutdown_profiles_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_SIGINT_SHUTDOWN") };
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_SIGTERM_SHUTDOWN") };
+        assert_eq!(ShutdownProfiles::from_env(), ShutdownProfiles::default());
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_SIGINT_SHUTDOWN", "idle") };
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_SIGTERM_SHUTDOWN", "power-off") };
+        let profiles = ShutdownProfiles::from_env();
+        assert_eq!(
+            profiles.for_signal(StopSignal::Interrupt),
+            ShutdownMode::Idle
+        );
+        assert_eq!(
+            profiles.for_signal(StopSignal::Terminate),
+            ShutdownMode::PowerOff
+        );
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_SIGTERM_SHUTDOWN", "halt") };
+        assert_eq!(ShutdownProfiles::from_env().sigterm, ShutdownMode::PowerOff);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_SIGINT_SHUTDOWN") };
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_SIGTERM_SHUTDOWN") };
+    }
+
+    #[test]
+    fn component_failures_are_classified_by_cause() {
+        let no_boards = anyhow::Error::from(NoBoardsError {
+            waited: Duration::from_secs(90),
+        });
+        assert!(matches!(
+            DaemonError::from(no_boards),
+            DaemonError::NoBoards(_)
+        ));
+
+        let bind = anyhow::Error::from(BindError {
+            addr: "127.0.0.1:7785".into(),
+            source: io::ErrorKind::AddrInUse.into(),
+        });
+        assert!(matches!(
+            DaemonError::from(bind),
+            DaemonError::BindFailed(BindError { addr, .. }) if addr == "127.0.0.1:7785"
+        ));
+
+        let other = anyhow::anyhow!("Burn-in failed");
+        assert!(matches!(DaemonError::from(other), DaemonError::Other(_)));
+    }
+
+    #[test]
+    fn only_rejected_credentials_stop_the_daemon() {
+        let pool = "stratum+tcp://pool.example:3333";
+        let rejected =
+            anyhow::Error::from(StratumError::AuthorizationFailed("unknown worker".into()));
+        match pool_failure(pool, &rejected) {
+            Some(DaemonError::PoolAuthFailed { pool: p, reason }) => {
+                assert_eq!(p, pool);
+                assert_eq!(reason, "unknown worker");
+            }
+            other => panic!("expected PoolAuthFailed, got {other:?}"),
+        }
+
+        let refused = anyhow::Error::from(StratumError::ConnectionFailed("refused".into()));
+        assert!(pool_failure(pool, &refused).is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn lottery_mode_refuses_to_start_without_a_payout_address() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_MINING_MODE", "lottery");
+            env::set_var("MUJINA_POOL_URL", "stratum+tcp://solo.example:3333");
+            env::set_var("MUJINA_POOL_USER", "not-an-address");
+        }
+        let result = Daemon::new().run().await;
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_MINING_MODE");
+            env::remove_var("MUJINA_POOL_URL");
+            env::remove_var("MUJINA_POOL_USER");
+        }
+        assert!(matches!(
+            result,
+            Err(DaemonError::ConfigInvalid {
+                var: "MUJINA_POOL_USER",
+                ..
+            })
+        ));
+    }
+
+    #[cfg(feature = "cpu-miner")]
+    #[tokio::test]
+    #[serial]
+    async fn built_daemon_mines_on_the_cpu_until_stopped() {
+        use crate::cpu_miner::{CpuMinerConfig, Sequential};
+
+        // A free port for the API, so the test can watch the daemon.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let shutdown = CancellationToken::new();
+        let daemon = DaemonBuilder::new()
+            .cpu_miner(Some(CpuMinerConfig {
+                thread_count: 1,
+                duty_percent: 10,
+                strategy: Arc::new(Sequential),
+            }))
+            .api_listen(addr.to_string())
+            .shutdown(shutdown.clone())
+            .build();
+        let running = tokio::spawn(daemon.run());
+
+        let client = crate::api_client::Client::with_base_url(format!("http://{addr}"));
+        let boards = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Ok(miner) = client.get_miner().await
+                    && !miner.boards.is_empty()
+                {
+                    return miner.boards;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("CPU board registered");
+        assert_eq!(boards.len(), 1);
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(10), running)
+            .await
+            .expect("daemon stopped")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[cfg(feature = "cpu-miner")]
+    #[tokio::test]
+    #[serial]
+    async fn daemon_mines_shares_the_pool_accepts() {
+        use crate::asic::bm13xx::test_data::stratum_json::MINING_NOTIFY;
+        use crate::cpu_miner::{CpuMinerConfig, Sequential};
+        use crate::stratum_v1::test_pool::{TestPool, TestPoolConfig};
+
+        // A difficulty the CPU meets every few thousand hashes, so shares
+        // come quickly without the pool taking whatever it gets.
+        const DIFFICULTY: f64 = 1e-6;
+        let mut pool = TestPool::start(TestPoolConfig::default()).await;
+        pool.set_difficulty(DIFFICULTY);
+        let notify: serde_json::Value = serde_json::from_str(MINING_NOTIFY).unwrap();
+        pool.notify(notify["params"].clone());
+
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_POOL_URL", pool.url());
+        }
+        let shutdown = CancellationToken::new();
+        let daemon = DaemonBuilder::new()
+            .cpu_miner(Some(CpuMinerConfig {
+                thread_count: 1,
+                duty_percent: 50,
+                strategy: Arc::new(Sequential),
+            }))
+            .api_listen(addr.to_string())
+            .shutdown(shutdown.clone())
+            .build();
+        let running = tokio::spawn(daemon.run());
+
+        tokio::time::timeout(Duration::from_secs(10), pool.authorized())
+            .await
+            .expect("daemon connected to the pool");
+        for _ in 0..3 {
+            let share = tokio::time::timeout(Duration::from_secs(30), pool.next_submit())
+                .await
+                .expect("share submitted");
+            assert!(share.accepted, "{share:?}");
+            assert_eq!(share.params.username, "mujina-testing");
+            assert!(share.hash_difficulty.unwrap().as_f64() >= DIFFICULTY);
+        }
+
+        // The daemon counts what the pool accepted.
+        let client = crate::api_client::Client::with_base_url(format!("http://{addr}"));
+        let accepted = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Ok(miner) = client.get_miner().await {
+                    let accepted: u64 = miner.sources.iter().map(|s| s.shares_accepted).sum();
+                    if accepted >= 3 {
+                        return accepted;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("accepted shares reported");
+        assert!(accepted >= 3);
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(10), running)
+            .await
+            .expect("daemon stopped")
+            .unwrap()
+            .unwrap();
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_POOL_URL");
+        }
+    }
+
+    #[cfg(feature = "cpu-miner")]
+    #[tokio::test]
+    #[serial]
+    async fn named_pool_rejecting_credentials_leaves_the_daemon_mining() {
+        use crate::cpu_miner::{CpuMinerConfig, Sequential};
+        use crate::stratum_v1::test_pool::{TestPool, TestPoolConfig};
+
+        let pool = TestPool::start(TestPoolConfig::default()).await;
+        let backup = TestPool::start(TestPoolConfig {
+            authorize: false,
+            ..Default::default()
+        })
+        .await;
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_POOL_URL", pool.url());
+            env::set_var("MUJINA_POOLS", format!("backup={}", backup.url()));
+            // A pool only connects once it has boards to mine for.
+            env::set_var("MUJINA_BOARD_POOLS", "cpu-1x10%=backup");
+            env::set_var("MUJINA_TELEMETRY_INTERVAL_SECS", "1");
+        }
+        let shutdown = CancellationToken::new();
+        let daemon = DaemonBuilder::new()
+            .cpu_miner(Some(CpuMinerConfig {
+                thread_count: 1,
+                duty_percent: 10,
+                strategy: Arc::new(Sequential),
+            }))
+            .api_listen(addr.to_string())
+            .shutdown(shutdown.clone())
+            .build();
+        let running = tokio::spawn(daemon.run());
+
+        let client = crate::api_client::Client::with_base_url(format!("http://{addr}"));
+        let failed = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Ok(miner) = client.get_miner().await
+                    && let Some(failed) = miner.sources.into_iter().find(|s| s.failure.is_some())
+                {
+                    return failed;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("backup pool reported failed");
+        assert!(failed.name.contains("backup"), "{}", failed.name);
+        assert_eq!(failed.failure.as_deref(), Some("authentication failed"));
+
+        // The rejection stopped only the backup's source.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!running.is_finished());
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(10), running)
+            .await
+            .expect("daemon stopped")
+            .unwrap()
+            .unwrap();
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_POOL_URL");
+            env::remove_var("MUJINA_POOLS");
+            env::remove_var("MUJINA_BOARD_POOLS");
+            env::remove_var("MUJINA_TELEMETRY_INTERVAL_SECS");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Whether the host is busy enough for background work to back off.
+//!
+//! On a single-core controller busy feeding chips and talking to pools,
+//! work nobody is waiting on, such as refreshing telemetry, competes with
+//! the hot path. This samples the one-minute load average from
+//! `/proc/loadavg` and publishes whether it exceeds
+//! `MUJINA_HOST_LOAD_PER_CPU` per core, so such work can run less often
+//! while it does. Hosts without `/proc/loadavg` never count as loaded.
+
+use std::env;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::tracing::prelude::*;
+
+/// Load per core above which the host counts as loaded, when not
+/// configured.
+pub const DEFAULT_LOAD_PER_CPU: f64 = 1.5;
+
+/// Interval between load samples. The load average itself moves slowly.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+const LOADAVG_PATH: &str = "/proc/loadavg";
+
+/// Read the per-core threshold from `MUJINA_HOST_LOAD_PER_CPU`, warning
+/// and using the default when invalid.
+///
+/// Returns `None` when set to 0, which disables load detection.
+pub fn threshold_from_env() -> Option<f64> {
+    let Ok(value) = env::var("MUJINA_HOST_LOAD_PER_CPU") else {
+        return Some(DEFAULT_LOAD_PER_CPU);
+    };
+    match value.parse::<f64>() {
+        Ok(0.0) => None,
+        Ok(per_cpu) if per_cpu.is_finite() && per_cpu > 0.0 => Some(per_cpu),
+        _ => {
+            warn!(
+                value = %value,
+                default = DEFAULT_LOAD_PER_CPU,
+                "Invalid MUJINA_HOST_LOAD_PER_CPU, using default"
+            );
+            Some(DEFAULT_LOAD_PER_CPU)
+        }
+    }
+}
+
+/// The one-minute load average from the contents of `/proc/loadavg`.
+fn parse_load1(loadavg: &str) -> Option<f64> {
+    loadavg.split_whitespace().next()?.parse().ok()
+}
+
+/// Whether `load1` across `cpus` cores is over `per_cpu` each.
+fn is_loaded(load1: f64, cpus: NonZeroUsize, per_cpu: f64) -> bool {
+    load1 > per_cpu * cpus.get() as f64
+}
+
+/// Sample the load average into `loaded_tx` until shutdown.
+pub async fn task(per_cpu: f64, loaded_tx: watch::Sender<bool>, shutdown: CancellationToken) {
+    let cpus = std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let mut tick = tokio::time::interval(SAMPLE_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = tick.tick() => {}
+            _ = shutdown.cancelled() => return,
+        }
+        let load1 = match tokio::fs::read_to_string(LOADAVG_PATH).await {
+            Ok(text) => parse_load1(&text),
+            Err(e) => {
+                debug!(error = %e, "No load average on this host, not watching load");
+                return;
+            }
+        };
+        let Some(load1) = load1 else {
+            warn!("Unreadable {LOADAVG_PATH}, not watching load");
+            return;
+        };
+        let loaded = is_loaded(load1, cpus, per_cpu);
+        if loaded_tx.send_if_modified(|was| std::mem::replace(was, loaded) != loaded) {
+            if loaded {
+                info!(
+                    load1,
+                    cpus = cpus.get(),
+                    "Host under load, backing off background work"
+                );
+            } else {
+                info!(load1, cpus = cpus.get(), "Host load back to normal");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    fn load_is_judged_per_core() {
+        let load1 = parse_load1("3.42 2.10 1.05 3/412 12345\n").unwrap();
+        assert_eq!(load1, 3.42);
+        let cpus = |n| NonZeroUsize::new(n).unwrap();
+        assert!(is_loaded(load1, cpus(2), 1.5));
+        assert!(!is_loaded(load1, cpus(4), 1.5));
+        assert_eq!(parse_load1(""), None);
+    }
+
+    #[test]
+    #[serial]
+    fn threshold_env_parsing() {
+        let cases = [
+            (None, Some(DEFAULT_LOAD_PER_CPU)),
+            (Some("2.5"), Some(2.5)),
+            (Some("0"), None),
+            (Some("-1"), Some(DEFAULT_LOAD_PER_CPU)),
+            (Some("busy"), Some(DEFAULT_LOAD_PER_CPU)),
+        ];
+        for (value, expected) in cases {
+            // SAFETY: Test runs serially, no concurrent env access
+            unsafe {
+                match value {
+                    Some(v) => env::set_var("MUJINA_HOST_LOAD_PER_CPU", v),
+                    None => env::remove_var("MUJINA_HOST_LOAD_PER_CPU"),
+                }
+            }
+            assert_eq!(threshold_from_env(), expected, "value {value:?}");
+        }
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_HOST_LOAD_PER_CPU") };
+    }
+}
@@ -1,6 +1,7 @@
 //! GPIO hardware abstraction trait.
 
 use super::Result;
+use crate::tracing::prelude::*;
 use async_trait::async_trait;
 
 /// GPIO pin value
@@ -51,3 +52,109 @@ pub trait Gpio: Send + Sync {
     /// Get a reference to a specific GPIO pin.
     async fn pin(&mut self, number: u8) -> Result<Self::Pin>;
 }
+
+/// Drives a pin to its safe value if the owning task panics.
+///
+/// A backstop for pins such as power enables and ASIC resets, whose normal
+/// shutdown path never runs if the task holding them unwinds. Keep the
+/// guard in the state the task owns; when it is dropped during a panic, a
+/// write of the safe value is spawned on the current runtime, since `Drop`
+/// can't await. An ordinary drop does nothing, as orderly shutdown has
+/// already put the pin where it belongs.
+pub struct PanicSafeState<P: GpioPin + 'static> {
+    pin: Option<P>,
+    safe: PinValue,
+    name: &'static str,
+}
+
+impl<P: GpioPin + 'static> PanicSafeState<P> {
+    /// Guard `pin`, naming it for the log.
+    pub fn new(pin: P, safe: PinValue, name: &'static str) -> Self {
+        Self {
+            pin: Some(pin),
+            safe,
+            name,
+        }
+    }
+}
+
+impl<P: GpioPin + 'static> Drop for PanicSafeState<P> {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            return;
+        }
+        let Some(mut pin) = self.pin.take() else {
+            return;
+        };
+        let (safe, name) = (self.safe, self.name);
+
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            error!(pin = name, "Task panicked with no runtime to make pin safe");
+            return;
+        };
+        runtime.spawn(async move {
+            match pin.write(safe).await {
+                Ok(()) => {
+                    error!(pin = name, value = ?safe, "Task panicked; pin driven to safe state")
+                }
+                Err(e) => error!(pin = name, error = %e, "Task panicked; failed to make pin safe"),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Pin that records its last written value.
+    #[derive(Clone)]
+    struct RecordingPin(Arc<Mutex<PinValue>>);
+
+    #[async_trait]
+    impl GpioPin for RecordingPin {
+        async fn set_mode(&mut self, _mode: PinMode) -> Result<()> {
+            Ok(())
+        }
+
+        async fn write(&mut self, value: PinValue) -> Result<()> {
+            *self.0.lock().unwrap() = value;
+            Ok(())
+        }
+
+        async fn read(&mut self) -> Result<PinValue> {
+            Ok(*self.0.lock().unwrap())
+        }
+    }
+
+    #[tokio::test]
+    async fn panicking_task_leaves_power_enable_safe() {
+        let power_enable = RecordingPin(Arc::new(Mutex::new(PinValue::Low)));
+        let mut task_pin = power_enable.clone();
+
+        let task = tokio::spawn(async move {
+            let _guard = PanicSafeState::new(task_pin.clone(), PinValue::Low, "power-enable");
+            task_pin.write(PinValue::High).await.unwrap();
+            panic!("board task failed");
+        });
+        assert!(task.await.unwrap_err().is_panic());
+
+        // The safe-state write runs as its own task.
+        tokio::task::yield_now().await;
+        assert_eq!(*power_enable.0.lock().unwrap(), PinValue::Low);
+    }
+
+    #[tokio::test]
+    async fn orderly_drop_leaves_pin_alone() {
+        let power_enable = RecordingPin(Arc::new(Mutex::new(PinValue::High)));
+        drop(PanicSafeState::new(
+            power_enable.clone(),
+            PinValue::Low,
+            "power-enable",
+        ));
+        tokio::task::yield_now().await;
+        assert_eq!(*power_enable.0.lock().unwrap(), PinValue::High);
+    }
+}
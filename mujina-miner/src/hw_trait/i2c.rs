@@ -1,6 +1,11 @@
 //! I2C hardware abstraction trait.
 
-use super::Result;
+use std::env;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::{HwError, Result};
+use crate::tracing::prelude::*;
 use async_trait::async_trait;
 
 /// I2C-specific errors
@@ -10,6 +15,11 @@ pub enum I2cError {
     #[error("No acknowledgment from device at address 0x{0:02x}")]
     NoAck(u8),
 
+    /// Device acknowledged but held the bus past the timeout, usually by
+    /// stretching the clock
+    #[error("Timed out waiting for device at address 0x{0:02x}")]
+    Timeout(u8),
+
     /// Bus arbitration lost
     #[error("Bus arbitration lost")]
     ArbitrationLost,
@@ -23,6 +33,51 @@ pub enum I2cError {
     Other(String),
 }
 
+/// Standard I2C bus clock rates.
+///
+/// Slower is more forgiving of long wiring and marginal devices; a sensor
+/// that NACKs in fast mode often works in standard mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum I2cSpeed {
+    /// Standard mode, 100 kHz.
+    #[default]
+    Standard,
+    /// Fast mode, 400 kHz.
+    Fast,
+}
+
+impl I2cSpeed {
+    /// Bus clock in Hz.
+    pub fn hz(self) -> u32 {
+        match self {
+            Self::Standard => 100_000,
+            Self::Fast => 400_000,
+        }
+    }
+
+    /// Look up a speed by mode name or rate: `standard` or `100k`, `fast`
+    /// or `400k`, in any case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "standard" | "100k" => Some(Self::Standard),
+            "fast" | "400k" => Some(Self::Fast),
+            _ => None,
+        }
+    }
+
+    /// Read the speed from `MUJINA_I2C_SPEED`, `None` when unset so each
+    /// board keeps its own default. Unrecognized values warn and count as
+    /// unset.
+    pub fn from_env() -> Option<Self> {
+        let value = env::var("MUJINA_I2C_SPEED").ok()?;
+        let speed = Self::from_name(&value);
+        if speed.is_none() {
+            warn!(value = %value, "Invalid MUJINA_I2C_SPEED, using board default");
+        }
+        speed
+    }
+}
+
 /// I2C bus abstraction
 #[async_trait]
 pub trait I2c: Send + Sync {
@@ -38,3 +93,278 @@ pub trait I2c: Send + Sync {
     /// Set the I2C bus frequency in Hz.
     async fn set_frequency(&mut self, hz: u32) -> Result<()>;
 }
+
+/// Whether `MUJINA_I2C_SPEED_FALLBACK` is set, enabling
+/// [`SpeedFallbackI2c`] on board buses.
+pub fn speed_fallback_from_env() -> bool {
+    env::var_os("MUJINA_I2C_SPEED_FALLBACK").is_some()
+}
+
+/// One I2C transaction, so a failed one can be run again.
+enum Transaction<'a> {
+    Write(&'a [u8]),
+    Read(&'a mut [u8]),
+    WriteRead(&'a [u8], &'a mut [u8]),
+}
+
+impl Transaction<'_> {
+    async fn run(&mut self, bus: &mut impl I2c, addr: u8) -> Result<()> {
+        match self {
+            Self::Write(data) => bus.write(addr, data).await,
+            Self::Read(buffer) => bus.read(addr, buffer).await,
+            Self::WriteRead(write, read) => bus.write_read(addr, write, read).await,
+        }
+    }
+}
+
+/// An I2C bus that drops to standard speed when a transaction fails above
+/// it, then retries the transaction once.
+///
+/// Marginal sensors and long wiring often work at 100 kHz when they NACK
+/// or time out at 400 kHz. The fallback happens at most once per bus:
+/// after it, the bus stays at standard speed, shared by every clone, and
+/// failures there are returned as they are.
+#[derive(Clone)]
+pub struct SpeedFallbackI2c<I> {
+    inner: I,
+    enabled: bool,
+    /// Clock last set on the bus, 0 until set.
+    hz: Arc<AtomicU32>,
+}
+
+impl<I: I2c> SpeedFallbackI2c<I> {
+    /// Wrap `inner`, falling back only if `enabled`; otherwise every call
+    /// passes straight through. Fallback applies once a speed above
+    /// standard has been set through the wrapper.
+    pub fn new(inner: I, enabled: bool) -> Self {
+        Self {
+            inner,
+            enabled,
+            hz: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Whether a failure with `error` should drop the bus to standard
+    /// speed and retry. Only a missing acknowledgment or a timeout can be
+    /// the clock's fault; a bus error or lost arbitration would fail again.
+    fn should_fall_back(&self, error: &HwError) -> bool {
+        self.enabled
+            && matches!(
+                error,
+                HwError::I2c(I2cError::NoAck(_) | I2cError::Timeout(_)) | HwError::Timeout
+            )
+            && self.hz.load(Ordering::Relaxed) > I2cSpeed::Standard.hz()
+    }
+
+    /// Run `transaction`, falling back to standard speed and running it
+    /// once more if it fails as [`Self::should_fall_back`] says.
+    async fn transact(&mut self, addr: u8, mut transaction: Transaction<'_>) -> Result<()> {
+        match transaction.run(&mut self.inner, addr).await {
+            Err(e) if self.should_fall_back(&e) => {
+                self.fall_back(addr, &e).await?;
+                let result = transaction.run(&mut self.inner, addr).await;
+                Self::report_retry(addr, &result);
+                result
+            }
+            result => result,
+        }
+    }
+
+    /// Drop the bus to standard speed after `error` at `addr`.
+    async fn fall_back(&mut self, addr: u8, error: &HwError) -> Result<()> {
+        let hz = self.hz.load(Ordering::Relaxed);
+        warn!(
+            addr = format!("{addr:#04x}"),
+            hz,
+            error = %error,
+            "I2C transaction failed, retrying at standard speed"
+        );
+        self.set_frequency(I2cSpeed::Standard.hz()).await
+    }
+
+    /// Log how the retry after a fallback went.
+    fn report_retry(addr: u8, result: &Result<()>) {
+        let addr = format!("{addr:#04x}");
+        match result {
+            Ok(_) => info!(
+                addr,
+                hz = I2cSpeed::Standard.hz(),
+                "I2C transaction succeeded at standard speed; bus stays there"
+            ),
+            Err(e) => warn!(addr, error = %e, "I2C transaction failed at standard speed too"),
+        }
+    }
+}
+
+#[async_trait]
+impl<I: I2c> I2c for SpeedFallbackI2c<I> {
+    async fn write(&mut self, addr: u8, data: &[u8]) -> Result<()> {
+        self.transact(addr, Transaction::Write(data)).await
+    }
+
+    async fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<()> {
+        self.transact(addr, Transaction::Read(buffer)).await
+    }
+
+    async fn write_read(&mut self, addr: u8, write: &[u8], read: &mut [u8]) -> Result<()> {
+        self.transact(addr, Transaction::WriteRead(write, read))
+            .await
+    }
+
+    async fn set_frequency(&mut self, hz: u32) -> Result<()> {
+        self.inner.set_frequency(hz).await?;
+        self.hz.store(hz, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    fn speed_names() {
+        assert_eq!(I2cSpeed::from_name("Fast"), Some(I2cSpeed::Fast));
+        assert_eq!(I2cSpeed::from_name("400k"), Some(I2cSpeed::Fast));
+        assert_eq!(I2cSpeed::from_name("standard"), Some(I2cSpeed::Standard));
+        assert_eq!(I2cSpeed::from_name("1M"), None);
+        assert_eq!(I2cSpeed::Fast.hz(), 400_000);
+    }
+
+    /// A sensor that only answers at standard speed.
+    #[derive(Clone, Default)]
+    struct MarginalSensor {
+        hz: Arc<AtomicU32>,
+        reads: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl I2c for MarginalSensor {
+        async fn write(&mut self, _addr: u8, _data: &[u8]) -> Result<()> {
+            Ok(())
+        }
+
+        async fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<()> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            if self.hz.load(Ordering::Relaxed) > I2cSpeed::Standard.hz() {
+                return Err(HwError::I2c(I2cError::NoAck(addr)));
+            }
+            buffer.fill(0x42);
+            Ok(())
+        }
+
+        async fn write_read(&mut self, addr: u8, _write: &[u8], read: &mut [u8]) -> Result<()> {
+            self.read(addr, read).await
+        }
+
+        async fn set_frequency(&mut self, hz: u32) -> Result<()> {
+            self.hz.store(hz, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_read_at_fast_speed_succeeds_at_standard() {
+        let sensor = MarginalSensor::default();
+        let mut bus = SpeedFallbackI2c::new(sensor.clone(), true);
+        bus.set_frequency(I2cSpeed::Fast.hz()).await.unwrap();
+
+        let mut buffer = [0; 2];
+        let logs = crate::tracing::capture_logs(async {
+            bus.read(0x4c, &mut buffer).await.unwrap();
+        })
+        .await;
+
+        assert_eq!(buffer, [0x42; 2]);
+        assert_eq!(sensor.reads.load(Ordering::Relaxed), 2);
+        assert_eq!(sensor.hz.load(Ordering::Relaxed), I2cSpeed::Standard.hz());
+        assert!(logs.contains("retrying at standard speed"), "{logs}");
+        assert!(logs.contains("succeeded at standard speed"), "{logs}");
+
+        // Clones share the bus, so they stay at standard speed too.
+        let mut other = bus.clone();
+        other.read(0x4c, &mut buffer).await.unwrap();
+        assert_eq!(sensor.reads.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn failures_at_standard_speed_are_not_retried() {
+        let sensor = MarginalSensor::default();
+        let mut bus = SpeedFallbackI2c::new(sensor.clone(), true);
+        sensor.hz.store(I2cSpeed::Fast.hz(), Ordering::Relaxed);
+
+        // The wrapper didn't set a fast clock, so it has nothing to drop.
+        let mut buffer = [0; 1];
+        assert!(bus.read(0x4c, &mut buffer).await.is_err());
+        assert_eq!(sensor.reads.load(Ordering::Relaxed), 1);
+    }
+
+    /// A bus that fails every transaction with a bus error.
+    #[derive(Clone, Default)]
+    struct BrokenBus {
+        hz: Arc<AtomicU32>,
+        writes: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl I2c for BrokenBus {
+        async fn write(&mut self, _addr: u8, _data: &[u8]) -> Result<()> {
+            self.writes.fetch_add(1, Ordering::Relaxed);
+            Err(HwError::I2c(I2cError::BusError))
+        }
+
+        async fn read(&mut self, _addr: u8, _buffer: &mut [u8]) -> Result<()> {
+            Err(HwError::I2c(I2cError::BusError))
+        }
+
+        async fn write_read(&mut self, _addr: u8, _write: &[u8], _read: &mut [u8]) -> Result<()> {
+            Err(HwError::I2c(I2cError::BusError))
+        }
+
+        async fn set_frequency(&mut self, hz: u32) -> Result<()> {
+            self.hz.store(hz, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn bus_errors_keep_the_speed() {
+        let broken = BrokenBus::default();
+        let mut bus = SpeedFallbackI2c::new(broken.clone(), true);
+        bus.set_frequency(I2cSpeed::Fast.hz()).await.unwrap();
+
+        // Slowing the clock wouldn't fix the bus, so it isn't tried.
+        assert!(bus.write(0x4c, &[0x01]).await.is_err());
+        assert_eq!(broken.writes.load(Ordering::Relaxed), 1);
+        assert_eq!(broken.hz.load(Ordering::Relaxed), I2cSpeed::Fast.hz());
+    }
+
+    #[tokio::test]
+    async fn fallback_can_be_disabled() {
+        let sensor = MarginalSensor::default();
+        let mut bus = SpeedFallbackI2c::new(sensor.clone(), false);
+        bus.set_frequency(I2cSpeed::Fast.hz()).await.unwrap();
+
+        let mut buffer = [0; 1];
+        assert!(bus.read(0x4c, &mut buffer).await.is_err());
+        assert_eq!(sensor.hz.load(Ordering::Relaxed), I2cSpeed::Fast.hz());
+    }
+
+    #[test]
+    #[serial]
+    fn speed_from_env() {
+        let var = "MUJINA_I2C_SPEED";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(I2cSpeed::from_env(), None);
+            env::set_var(var, "fast");
+            assert_eq!(I2cSpeed::from_env(), Some(I2cSpeed::Fast));
+            env::set_var(var, "ludicrous");
+            assert_eq!(I2cSpeed::from_env(), None);
+            env::remove_var(var);
+        }
+    }
+}
@@ -12,8 +12,8 @@ pub mod rgb_led;
 
 // Re-export traits
 pub use adc::{Adc, AdcChannel};
-pub use gpio::{Gpio, GpioPin, PinMode, PinValue};
-pub use i2c::{I2c, I2cError};
+pub use gpio::{Gpio, GpioPin, PanicSafeState, PinMode, PinValue};
+pub use i2c::{I2c, I2cError, I2cSpeed, SpeedFallbackI2c};
 pub use rgb_led::{RgbColor, RgbLed};
 
 /// Common error type for hardware operations
@@ -91,6 +91,9 @@ impl DummySource {
 
             time: block_881423::TIME,
 
+            // Nothing downstream to reject a rolled share
+            max_time: u32::MAX,
+
             // Use computed merkle root with authentic coinbase parts
             merkle_root: MerkleRootKind::Computed(MerkleRootTemplate {
                 coinbase1: block_881423::coinbase1_bytes().to_vec(),
@@ -98,6 +101,7 @@ impl DummySource {
                 extranonce2_range,
                 coinbase2: block_881423::coinbase2_bytes().to_vec(),
                 merkle_branches,
+                cache: Default::default(),
             }),
         };
 
@@ -137,7 +141,7 @@ impl DummySource {
                                 "Share received"
                             );
                         }
-                        SourceCommand::UpdateHashRate(_) => {
+                        SourceCommand::UpdateHashRate(_) | SourceCommand::LowerDifficulty => {
                             // Ignored in dummy source
                         }
                     }
@@ -157,6 +161,7 @@ impl DummySource {
 mod tests {
     use super::*;
     use crate::job_source::Share;
+    use crate::types::Difficulty;
 
     #[tokio::test(start_paused = true)]
     async fn test_dummy_source_communication() {
@@ -192,6 +197,8 @@ mod tests {
             time: block_881423::TIME,
             version: *block_881423::VERSION,
             extranonce2: None,
+            solves_block: true,
+            difficulty: Difficulty::from_hash(&block_881423::BLOCK_HASH),
         };
         command_tx
             .send(SourceCommand::SubmitShare(share))
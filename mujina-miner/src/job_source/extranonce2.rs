@@ -193,11 +193,17 @@ impl Extranonce2Range {
         for i in 0..n {
             // Distribute remainder among first few chunks
             let size = chunk_size + if (i as u64) < remainder { 1 } else { 0 };
-            let end = start + size - 1;
+            // The last chunk runs to the top: for a full 8-byte range
+            // `len` saturates one short of the true count.
+            let end = if i == n - 1 {
+                self.max
+            } else {
+                start + size - 1
+            };
 
             ranges.push(Self::new_range(start, end, self.size).expect("sub-range should be valid"));
 
-            start = end + 1;
+            start = end.saturating_add(1);
         }
 
         Some(ranges)
@@ -212,6 +218,13 @@ impl Extranonce2Range {
     }
 }
 
+impl fmt::Display for Extranonce2Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.size as usize * 2;
+        write!(f, "{:0width$x}..={:0width$x}", self.min, self.max)
+    }
+}
+
 /// Iterator that generates `Extranonce2` values from a range.
 ///
 /// Created via `Extranonce2Range::iter()`. Implements Rust's `Iterator` trait
@@ -156,7 +156,7 @@ impl ForcedRateSource {
                         SourceEvent::ReplaceJob(job) => {
                             SourceEvent::ReplaceJob(self.modify_job(job))
                         }
-                        SourceEvent::ClearJobs => SourceEvent::ClearJobs,
+                        other => other,
                     };
                     self.outer_event_tx.send(modified).await?;
                 }
@@ -248,6 +248,7 @@ mod tests {
             bits: CompactTarget::from_consensus(0x1d00ffff),
             share_target,
             time: 0,
+            max_time: 0,
             merkle_root: MerkleRootKind::Fixed(bitcoin::TxMerkleNode::all_zeros()),
         }
     }
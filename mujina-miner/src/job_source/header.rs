@@ -0,0 +1,157 @@
+//! Block header construction and serialization.
+//!
+//! Hardware validation and share building both turn a job plus the rolled
+//! fields into an 80-byte header and hash it. They go through these helpers
+//! so the byte order is decided in exactly one place.
+//!
+//! Every header field is little-endian on the wire. For the two hashes that
+//! means the internal byte order, which is the reverse of the hex shown by
+//! block explorers: a block hash with leading zeros in its display form has
+//! trailing zero bytes here.
+
+use bitcoin::block::{Header as BlockHeader, Version};
+use bitcoin::hash_types::{BlockHash, TxMerkleNode};
+use bitcoin::hashes::{Hash, sha256d};
+
+use super::JobTemplate;
+
+/// Size of a serialized block header.
+pub const HEADER_LEN: usize = 80;
+
+/// Build the header for a job with the given rolled fields.
+///
+/// The previous block hash and bits come from the job; everything a miner
+/// rolls is passed in.
+pub fn build(
+    template: &JobTemplate,
+    merkle_root: TxMerkleNode,
+    version: Version,
+    time: u32,
+    nonce: u32,
+) -> BlockHeader {
+    BlockHeader {
+        version,
+        prev_blockhash: template.prev_blockhash,
+        merkle_root,
+        time,
+        bits: template.bits,
+        nonce,
+    }
+}
+
+/// Serialize a header to its 80-byte wire form.
+pub fn to_bytes(header: &BlockHeader) -> [u8; HEADER_LEN] {
+    let mut bytes = [0u8; HEADER_LEN];
+    bytes[0..4].copy_from_slice(&header.version.to_consensus().to_le_bytes());
+    bytes[4..36].copy_from_slice(header.prev_blockhash.as_byte_array());
+    bytes[36..68].copy_from_slice(header.merkle_root.as_byte_array());
+    bytes[68..72].copy_from_slice(&header.time.to_le_bytes());
+    bytes[72..76].copy_from_slice(&header.bits.to_consensus().to_le_bytes());
+    bytes[76..80].copy_from_slice(&header.nonce.to_le_bytes());
+    bytes
+}
+
+/// Double-SHA256 of the serialized header.
+pub fn block_hash(header: &BlockHeader) -> BlockHash {
+    BlockHash::from_raw_hash(sha256d::Hash::hash(&to_bytes(header)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::consensus::serialize;
+    use bitcoin::pow::{CompactTarget, Target};
+
+    use super::*;
+    use crate::job_source::test_blocks::block_881423;
+    use crate::job_source::{GeneralPurposeBits, MerkleRootKind, VersionTemplate};
+
+    /// Bitcoin's genesis block header.
+    const GENESIS_HEADER_HEX: &str = "01000000\
+        0000000000000000000000000000000000000000000000000000000000000000\
+        3ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a\
+        29ab5f49\
+        ffff001d\
+        1dac2b7c";
+
+    fn genesis() -> BlockHeader {
+        BlockHeader {
+            version: Version::ONE,
+            prev_blockhash: BlockHash::all_zeros(),
+            merkle_root: TxMerkleNode::from_str(
+                "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+            )
+            .unwrap(),
+            time: 1231006505,
+            bits: CompactTarget::from_consensus(0x1d00ffff),
+            nonce: 2083236893,
+        }
+    }
+
+    #[test]
+    fn serializes_block_881423() {
+        assert_eq!(to_bytes(&block_881423::HEADER), block_881423::HEADER_BYTES);
+        assert_eq!(block_hash(&block_881423::HEADER), *block_881423::BLOCK_HASH);
+    }
+
+    #[test]
+    fn serializes_genesis() {
+        let header = genesis();
+        let expected: Vec<u8> = (0..GENESIS_HEADER_HEX.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&GENESIS_HEADER_HEX[i..i + 2], 16).unwrap())
+            .collect();
+        assert_eq!(to_bytes(&header).as_slice(), expected.as_slice());
+        assert_eq!(
+            block_hash(&header).to_string(),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+    }
+
+    #[test]
+    fn hash_byte_order_matches_target_comparison() {
+        // Display order has the leading zeros; internal order has them last,
+        // which is what the little-endian target comparison expects.
+        let hash = block_hash(&block_881423::HEADER);
+        assert_eq!(hash.as_byte_array()[24..], [0u8; 8]);
+        assert!(Target::from(*block_881423::BITS).is_met_by(hash));
+    }
+
+    #[test]
+    fn matches_consensus_encoding() {
+        for header in [genesis(), *block_881423::HEADER] {
+            assert_eq!(to_bytes(&header).as_slice(), serialize(&header).as_slice());
+            assert_eq!(block_hash(&header), header.block_hash());
+        }
+    }
+
+    #[test]
+    fn build_combines_job_and_rolled_fields() {
+        // Only the job's prev hash and bits reach the header; the version
+        // is whatever the miner rolled.
+        let template = JobTemplate {
+            id: "881423".into(),
+            prev_blockhash: *block_881423::PREV_BLOCKHASH,
+            version: VersionTemplate::new(
+                Version::from_consensus(0x2000_0000),
+                GeneralPurposeBits::full(),
+            )
+            .unwrap(),
+            bits: *block_881423::BITS,
+            share_target: Target::MAX,
+            time: 0,
+            max_time: 0,
+            merkle_root: MerkleRootKind::Fixed(*block_881423::MERKLE_ROOT),
+        };
+
+        let header = build(
+            &template,
+            *block_881423::MERKLE_ROOT,
+            *block_881423::VERSION,
+            block_881423::TIME,
+            block_881423::NONCE,
+        );
+        assert_eq!(header, *block_881423::HEADER);
+    }
+}
@@ -2,9 +2,12 @@
 
 use bitcoin::block::Version;
 use bitcoin::hash_types::BlockHash;
+use bitcoin::hashes::Hash;
 use bitcoin::pow::{CompactTarget, Target};
 
 use super::{Extranonce2, MerkleRootKind, VersionTemplate};
+use crate::api_client::types::{SourceJob, SourceJobCoinbase};
+use crate::types::Difficulty;
 
 /// Template for mining jobs from any source.
 ///
@@ -35,11 +38,20 @@ pub struct JobTemplate {
     /// Shares are submitted if hash meets this target. Set by Stratum's
     /// mining.set_difficulty (converted to Target). Independent from network
     /// difficulty (bits field).
+    ///
+    /// Snapshotted when the job is issued. A difficulty change takes effect
+    /// with the next job; shares from work already handed out are validated
+    /// against the value in effect when that work was issued.
     pub share_target: Target,
 
     /// Block timestamp
     pub time: u32,
 
+    /// Latest block timestamp the source accepts in a share for this job.
+    /// Threads may roll ntime forward from `time` up to this, never past
+    /// it; equal to `time` when the source allows no rolling.
+    pub max_time: u32,
+
     /// Specifies how to obtain the merkle root for this job.
     pub merkle_root: MerkleRootKind,
 }
@@ -67,6 +79,34 @@ impl JobTemplate {
     }
 }
 
+impl From<&JobTemplate> for SourceJob {
+    fn from(job: &JobTemplate) -> Self {
+        let coinbase = match &job.merkle_root {
+            MerkleRootKind::Computed(template) => Some(SourceJobCoinbase {
+                coinbase1: hex::encode(&template.coinbase1),
+                extranonce1: hex::encode(&template.extranonce1),
+                extranonce2_size: template.extranonce2_range.size,
+                coinbase2: hex::encode(&template.coinbase2),
+                merkle_branches: template
+                    .merkle_branches
+                    .iter()
+                    .map(|branch| hex::encode(branch.as_byte_array()))
+                    .collect(),
+            }),
+            MerkleRootKind::Fixed(_) => None,
+        };
+        Self {
+            job_id: job.id.clone(),
+            prev_blockhash: job.prev_blockhash.to_string(),
+            version: format!("{:08x}", job.version.base().to_consensus()),
+            nbits: format!("{:08x}", job.bits.to_consensus()),
+            ntime: job.time,
+            difficulty: Difficulty::from_target(job.share_target).as_f64(),
+            coinbase,
+        }
+    }
+}
+
 /// Represents a share submission (solved work).
 #[derive(Debug, Clone)]
 pub struct Share {
@@ -84,4 +124,12 @@ pub struct Share {
 
     /// Extranonce2
     pub extranonce2: Option<Extranonce2>,
+
+    /// Whether the hash also meets the network target. A source must never
+    /// discard such a share, even under backpressure.
+    pub solves_block: bool,
+
+    /// Difficulty the share's hash achieved, which may be far above the
+    /// target it was found against.
+    pub difficulty: Difficulty,
 }
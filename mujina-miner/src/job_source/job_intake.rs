@@ -0,0 +1,125 @@
+//! Capping how fast a pool's jobs are taken in.
+//!
+//! A pool sends new work every 30 seconds or so, a few times more around a
+//! block. One that floods `mining.notify`, broken or hostile, would have
+//! every board switch work many times a second and waste the time each
+//! switch costs, which the debounce window only partly absorbs: clean jobs
+//! pass straight through it, and it can be turned off. Past the limit in
+//! any second, jobs that leave old work valid are dropped unheard; the
+//! boards keep mining the job before, which the pool still accepts. Jobs
+//! that invalidate old work always apply.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Span the limit counts jobs over.
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// What to do with a job that arrived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intake {
+    /// Take the job.
+    Accept,
+    /// Take the job, the first after a quiet second ended a flood that
+    /// dropped `dropped` jobs.
+    AcceptAfterFlood { dropped: u64 },
+    /// Drop the job; `first` when it starts a flood.
+    Drop { first: bool },
+}
+
+/// Counts one connection's jobs against a per-second limit.
+#[derive(Debug)]
+pub struct JobIntake {
+    limit: u32,
+    window_start: Option<Instant>,
+    /// Jobs taken in the current window.
+    accepted: u32,
+    /// Whether the current window dropped any.
+    dropping: bool,
+    /// Jobs dropped since the flood began.
+    dropped: u64,
+}
+
+impl JobIntake {
+    /// Take at most `limit` jobs a second, not counting clean ones beyond
+    /// it.
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            window_start: None,
+            accepted: 0,
+            dropping: false,
+            dropped: 0,
+        }
+    }
+
+    /// Judge a job arriving at `now`. A `clean` one, invalidating old
+    /// work, is always taken.
+    pub fn admit(&mut self, now: Instant, clean: bool) -> Intake {
+        let mut flood_over = None;
+        let since_start = self.window_start.map(|start| now.duration_since(start));
+        if since_start.is_none_or(|since| since >= WINDOW) {
+            // A window without drops ends a flood, as does a silent one
+            // between the last and this.
+            let quiet = !self.dropping || since_start.is_some_and(|since| since >= WINDOW * 2);
+            if quiet && self.dropped > 0 {
+                flood_over = Some(std::mem::take(&mut self.dropped));
+            }
+            self.window_start = Some(now);
+            self.accepted = 0;
+            self.dropping = false;
+        }
+
+        if !clean && self.accepted >= self.limit {
+            self.dropping = true;
+            self.dropped += 1;
+            return Intake::Drop {
+                first: self.dropped == 1,
+            };
+        }
+        self.accepted = self.accepted.saturating_add(1);
+        match flood_over {
+            Some(dropped) => Intake::AcceptAfterFlood { dropped },
+            None => Intake::Accept,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flood_is_capped_until_a_quiet_second() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut intake = JobIntake::new(2);
+
+        assert_eq!(intake.admit(at(0), false), Intake::Accept);
+        assert_eq!(intake.admit(at(10), false), Intake::Accept);
+        assert_eq!(intake.admit(at(20), false), Intake::Drop { first: true });
+        assert_eq!(intake.admit(at(30), true), Intake::Accept);
+        assert_eq!(intake.admit(at(40), false), Intake::Drop { first: false });
+
+        // The next second still drops, so the flood goes on.
+        assert_eq!(intake.admit(at(1000), false), Intake::Accept);
+        assert_eq!(intake.admit(at(1001), false), Intake::Accept);
+        assert_eq!(intake.admit(at(1002), false), Intake::Drop { first: false });
+
+        // A second that kept under the limit ends it.
+        assert_eq!(intake.admit(at(2000), false), Intake::Accept);
+        assert_eq!(
+            intake.admit(at(3000), false),
+            Intake::AcceptAfterFlood { dropped: 3 }
+        );
+        assert_eq!(intake.admit(at(3001), false), Intake::Accept);
+
+        // So does a silent second after one that dropped.
+        assert_eq!(intake.admit(at(3002), false), Intake::Drop { first: true });
+        assert_eq!(
+            intake.admit(at(5000), false),
+            Intake::AcceptAfterFlood { dropped: 1 }
+        );
+    }
+}
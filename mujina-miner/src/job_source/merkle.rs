@@ -1,10 +1,12 @@
 //! Merkle root specification for mining jobs.
 
+use std::sync::OnceLock;
+
 use anyhow::Result;
 use bitcoin::Transaction;
-use bitcoin::consensus::deserialize;
+use bitcoin::consensus::{deserialize, serialize};
 use bitcoin::hash_types::TxMerkleNode;
-use bitcoin::hashes::{Hash, sha256d};
+use bitcoin::hashes::{Hash, HashEngine, sha256, sha256d};
 
 use super::{Extranonce2, Extranonce2Range};
 
@@ -63,18 +65,50 @@ pub struct MerkleRootTemplate {
     /// After hashing the coinbase transaction, these branches are used to climb
     /// the merkle tree to compute the final merkle root for the block header.
     pub merkle_branches: Vec<TxMerkleNode>,
+
+    /// Per-job cache of the work shared by every extranonce2.
+    ///
+    /// Filled on first use and only used while the coinbase fields still
+    /// match what it was built from; a template changed afterwards
+    /// computes from scratch rather than from a stale cache.
+    pub cache: MerkleCache,
 }
 
 impl MerkleRootTemplate {
     /// Compute merkle root for a specific extranonce2 value.
     ///
-    /// Builds the complete coinbase transaction by concatenating parts with the
-    /// given extranonce2, computes its txid, then climbs the merkle tree using
-    /// the branches to produce the final merkle root.
+    /// The first call validates the coinbase and hashes the part before
+    /// extranonce2 once; later calls only hash the extranonce2 and coinbase2
+    /// onto that state and climb the branches. Falls back to
+    /// [`compute_merkle_root_uncached`](Self::compute_merkle_root_uncached)
+    /// when the cache is disabled, the coinbase can't take the fast path,
+    /// or the template has changed since the cache was filled.
     ///
-    /// This is a pure function - it doesn't modify the template. Callers manage
+    /// This is a pure function as far as callers can observe. Callers manage
     /// extranonce2 iteration externally via `Extranonce2Iter`.
     pub fn compute_merkle_root(&self, extranonce2: &Extranonce2) -> Result<TxMerkleNode> {
+        if self.cache.disabled {
+            return self.compute_merkle_root_uncached(extranonce2);
+        }
+
+        match self
+            .cache
+            .path
+            .get_or_init(|| MerklePath::new(self).map(Box::new))
+        {
+            Some(path) if path.built_from(self, extranonce2) => {
+                Ok(path.merkle_root(self, extranonce2))
+            }
+            _ => self.compute_merkle_root_uncached(extranonce2),
+        }
+    }
+
+    /// Compute merkle root from scratch, ignoring the cache.
+    ///
+    /// Builds the complete coinbase transaction by concatenating parts with the
+    /// given extranonce2, computes its txid, then climbs the merkle tree using
+    /// the branches to produce the final merkle root.
+    pub fn compute_merkle_root_uncached(&self, extranonce2: &Extranonce2) -> Result<TxMerkleNode> {
         // Build complete coinbase transaction
         let mut coinbase_bytes = Vec::new();
         coinbase_bytes.extend_from_slice(&self.coinbase1);
@@ -100,6 +134,150 @@ impl MerkleRootTemplate {
     }
 }
 
+/// Lazily built [`MerklePath`] for one job's coinbase.
+///
+/// Each clone of a template that has already been used carries the built
+/// path with it. [`MerkleCache::disabled`] forces every computation down
+/// the from-scratch path, which is mostly useful for comparing the two.
+#[derive(Debug, Clone, Default)]
+pub struct MerkleCache {
+    disabled: bool,
+    // Boxed to keep `MerkleRootKind` variants similar in size.
+    path: OnceLock<Option<Box<MerklePath>>>,
+}
+
+impl MerkleCache {
+    /// A cache that is never filled.
+    pub fn disabled() -> Self {
+        Self {
+            disabled: true,
+            path: OnceLock::new(),
+        }
+    }
+}
+
+/// Hashing state shared by every extranonce2 of one coinbase.
+#[derive(Clone)]
+struct MerklePath {
+    /// Double-SHA256 engine after the txid bytes preceding extranonce2.
+    prefix: sha256::HashEngine,
+
+    /// Txid bytes following extranonce2.
+    suffix: Vec<u8>,
+
+    /// Extranonce2 size the coinbase was validated with.
+    extranonce2_size: u8,
+
+    /// The coinbase parts the path was built from, to notice a template
+    /// changed after the cache was filled.
+    coinbase1: Vec<u8>,
+    extranonce1: Vec<u8>,
+    coinbase2: Vec<u8>,
+}
+
+impl std::fmt::Debug for MerklePath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MerklePath")
+            .field("suffix_len", &self.suffix.len())
+            .field("extranonce2_size", &self.extranonce2_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MerklePath {
+    /// Validate the coinbase once and capture the state around extranonce2.
+    ///
+    /// The txid hashes the legacy serialization, so when the pool sends the
+    /// coinbase in SegWit format the marker, flag and witness are cut out of
+    /// the cached parts. The result is checked against a full parse; a
+    /// coinbase the split can't handle gets `None`, and every share then
+    /// uses the from-scratch path.
+    fn new(template: &MerkleRootTemplate) -> Option<Self> {
+        let extranonce2_size = template.extranonce2_range.size;
+        let probe = Extranonce2::new(0, extranonce2_size).ok()?;
+        let probe_bytes: Vec<u8> = probe.into();
+
+        let mut coinbase_bytes = Vec::new();
+        coinbase_bytes.extend_from_slice(&template.coinbase1);
+        coinbase_bytes.extend_from_slice(&template.extranonce1);
+        coinbase_bytes.extend_from_slice(&probe_bytes);
+        coinbase_bytes.extend_from_slice(&template.coinbase2);
+        let mut coinbase_tx: Transaction = deserialize(&coinbase_bytes).ok()?;
+        let txid = coinbase_tx.compute_txid().to_byte_array();
+
+        let witness = coinbase_tx
+            .input
+            .iter()
+            .any(|input| !input.witness.is_empty());
+        coinbase_tx
+            .input
+            .iter_mut()
+            .for_each(|input| input.witness.clear());
+        let legacy = serialize(&coinbase_tx);
+
+        // Version, then the two-byte marker and flag if present.
+        let mut head = template.coinbase1.clone();
+        if witness {
+            if head.get(4..6) != Some(&[0x00, 0x01]) {
+                return None;
+            }
+            head.drain(4..6);
+        }
+        head.extend_from_slice(&template.extranonce1);
+        head.extend_from_slice(&probe_bytes);
+        if !legacy.starts_with(&head) {
+            return None;
+        }
+        head.truncate(head.len() - probe_bytes.len());
+
+        let mut prefix = sha256d::Hash::engine();
+        prefix.input(&head);
+        let path = Self {
+            prefix,
+            suffix: legacy[head.len() + probe_bytes.len()..].to_vec(),
+            extranonce2_size,
+            coinbase1: template.coinbase1.clone(),
+            extranonce1: template.extranonce1.clone(),
+            coinbase2: template.coinbase2.clone(),
+        };
+        (path.coinbase_hash(&probe) == txid).then_some(path)
+    }
+
+    /// Whether this path is for `template`'s coinbase as it is now, with
+    /// an extranonce2 the size of `extranonce2`. Branches aren't cached, so
+    /// they needn't match.
+    fn built_from(&self, template: &MerkleRootTemplate, extranonce2: &Extranonce2) -> bool {
+        self.extranonce2_size == extranonce2.size()
+            && self.coinbase1 == template.coinbase1
+            && self.extranonce1 == template.extranonce1
+            && self.coinbase2 == template.coinbase2
+    }
+
+    /// Hash the coinbase for `extranonce2` onto the cached prefix.
+    fn coinbase_hash(&self, extranonce2: &Extranonce2) -> [u8; 32] {
+        let mut engine = self.prefix.clone();
+        engine.input(&extranonce2.value().to_le_bytes()[..extranonce2.size() as usize]);
+        engine.input(&self.suffix);
+        sha256d::Hash::from_engine(engine).to_byte_array()
+    }
+
+    /// Merkle root for `extranonce2`, without intermediate allocations.
+    fn merkle_root(
+        &self,
+        template: &MerkleRootTemplate,
+        extranonce2: &Extranonce2,
+    ) -> TxMerkleNode {
+        let mut current_hash = self.coinbase_hash(extranonce2);
+        for branch in &template.merkle_branches {
+            let mut engine = sha256d::Hash::engine();
+            engine.input(&current_hash);
+            engine.input(branch.as_byte_array());
+            current_hash = sha256d::Hash::from_engine(engine).to_byte_array();
+        }
+        TxMerkleNode::from_byte_array(current_hash)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,6 +294,7 @@ mod tests {
             extranonce2_range: Extranonce2Range::new(extranonce2.size()).unwrap(),
             coinbase2: block_881423::coinbase2_bytes().to_vec(),
             merkle_branches: block_881423::MERKLE_BRANCHES.clone(),
+            cache: MerkleCache::default(),
         };
 
         // Compute merkle root
@@ -130,4 +309,88 @@ mod tests {
             "Computed merkle root doesn't match block 881,423"
         );
     }
+
+    fn template_881423(cache: MerkleCache) -> MerkleRootTemplate {
+        MerkleRootTemplate {
+            coinbase1: block_881423::coinbase1_bytes().to_vec(),
+            extranonce1: block_881423::extranonce1_bytes().to_vec(),
+            extranonce2_range: Extranonce2Range::new(block_881423::EXTRANONCE2.size()).unwrap(),
+            coinbase2: block_881423::coinbase2_bytes().to_vec(),
+            merkle_branches: block_881423::MERKLE_BRANCHES.clone(),
+            cache,
+        }
+    }
+
+    #[test]
+    fn cached_path_matches_from_scratch() {
+        let size = block_881423::EXTRANONCE2.size();
+        let cached = template_881423(MerkleCache::default());
+
+        // Vary the coinbase through several extranonce2 values, including the
+        // golden one, and through a different extranonce1.
+        let mut other_en1 = template_881423(MerkleCache::default());
+        other_en1.extranonce1.iter_mut().for_each(|b| *b ^= 0xa5);
+
+        let values = [0, 1, 0xff, 0x1234_5678, block_881423::EXTRANONCE2.value()];
+        for template in [&cached, &other_en1] {
+            for value in values {
+                let en2 = Extranonce2::new(value, size).unwrap();
+                assert_eq!(
+                    template.compute_merkle_root(&en2).unwrap(),
+                    template.compute_merkle_root_uncached(&en2).unwrap(),
+                    "extranonce2 {en2}"
+                );
+            }
+        }
+
+        assert_eq!(
+            cached
+                .compute_merkle_root(&block_881423::EXTRANONCE2)
+                .unwrap(),
+            *block_881423::MERKLE_ROOT
+        );
+        assert!(cached.cache.path.get().unwrap().is_some());
+    }
+
+    #[test]
+    fn template_changed_after_first_use_ignores_the_cache() {
+        let en2 = *block_881423::EXTRANONCE2;
+        let mut template = template_881423(MerkleCache::default());
+        assert_eq!(
+            template.compute_merkle_root(&en2).unwrap(),
+            *block_881423::MERKLE_ROOT
+        );
+
+        // The cache now holds the original coinbase; each change must
+        // still give the root of the coinbase as it is.
+        template.extranonce1.iter_mut().for_each(|b| *b ^= 0xa5);
+        let root = template.compute_merkle_root(&en2).unwrap();
+        assert_ne!(root, *block_881423::MERKLE_ROOT);
+        assert_eq!(root, template.compute_merkle_root_uncached(&en2).unwrap());
+
+        template.coinbase1[0] ^= 0x01;
+        assert_eq!(
+            template.compute_merkle_root(&en2).unwrap(),
+            template.compute_merkle_root_uncached(&en2).unwrap()
+        );
+    }
+
+    #[test]
+    fn disabled_cache_stays_empty() {
+        let template = template_881423(MerkleCache::disabled());
+        let root = template
+            .compute_merkle_root(&block_881423::EXTRANONCE2)
+            .unwrap();
+        assert_eq!(root, *block_881423::MERKLE_ROOT);
+        assert!(template.cache.path.get().is_none());
+    }
+
+    #[test]
+    fn unparseable_coinbase_errors_like_from_scratch() {
+        let mut template = template_881423(MerkleCache::default());
+        template.coinbase2.truncate(3);
+        let en2 = *block_881423::EXTRANONCE2;
+        assert!(template.compute_merkle_root_uncached(&en2).is_err());
+        assert!(template.compute_merkle_root(&en2).is_err());
+    }
 }
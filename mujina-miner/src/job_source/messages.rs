@@ -43,12 +43,13 @@
 
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use tokio::sync::mpsc;
 
 use super::{JobTemplate, Share};
-use crate::types::HashRate;
+use crate::types::{DailyCount, HashRate};
 
 /// Handle to a job source (identity + communication).
 ///
@@ -139,6 +140,10 @@ pub enum SourceEvent {
     /// Scheduler should cancel all work from this source and wait for new job.
     /// Used during pool disconnection or when awaiting new block.
     ClearJobs,
+
+    /// The pool accepted a submitted share, identified by its job and
+    /// nonce. Lets the scheduler credit the board that found it.
+    ShareAccepted { job_id: String, nonce: u32 },
 }
 
 /// Commands to sources (pull, coordinator-initiated).
@@ -152,4 +157,58 @@ pub enum SourceCommand {
 
     /// Update the source with expected hashrate (an estimate, not a measurement).
     UpdateHashRate(HashRate),
+
+    /// The current share difficulty is too high for the expected hashrate;
+    /// ask upstream for one it can meet, if the protocol allows.
+    LowerDifficulty,
+}
+
+/// Measurements a source publishes about its upstream connection.
+///
+/// Sources that track these hand the scheduler a watch receiver at
+/// registration; the scheduler folds the latest values into source
+/// telemetry.
+#[derive(Debug, Clone, Default)]
+pub struct SourceStats {
+    /// Time from authorization to the first job on the current
+    /// connection, `None` until that job arrives.
+    pub time_to_first_job: Option<Duration>,
+
+    /// Shares discarded because the submission queue was full.
+    pub shares_dropped: u64,
+
+    /// Shares not submitted because their job was older than the
+    /// configured maximum age or no longer in the job history.
+    pub shares_stale_avoided: u64,
+
+    /// Shares the upstream acknowledged as valid.
+    pub shares_accepted: u64,
+
+    /// Shares the upstream refused.
+    pub shares_rejected: u64,
+
+    /// Of those, shares refused as stale.
+    pub shares_rejected_stale: u64,
+
+    /// Shares accepted since the start of the current stats day.
+    pub accepted_today: DailyCount,
+
+    /// Shares refused since the start of the current stats day.
+    pub rejected_today: DailyCount,
+
+    /// Why the source stopped trying to reach its upstream, `None` while
+    /// it is still trying.
+    pub failure: Option<String>,
+
+    /// Whether the upstream's share difficulty keeps swinging back and
+    /// forth on the current connection.
+    pub difficulty_oscillating: bool,
+
+    /// Times the upstream's jobs moved to a new previous block hash, each
+    /// a block found on the network.
+    pub network_blocks_seen: u64,
+
+    /// Times the connection came back after an outage longer than the
+    /// reconnect grace. Brief reconnects aren't counted.
+    pub reconnects: u64,
 }
@@ -42,9 +42,10 @@
 //! ## Share Difficulty
 //!
 //! Sources receive share difficulty from their upstream (pool, node, etc.)
-//! and report it directly in [`JobTemplate::share_target`]. We avoid
-//! suggesting difficulty to pools because some (notably Ocean) disconnect
-//! clients that suggest inappropriately low values.
+//! and report it directly in [`JobTemplate::share_target`]. The Stratum
+//! source may suggest a starting difficulty (see `SuggestDifficulty`), but
+//! the pool's `set_difficulty` remains authoritative; pools that ignore the
+//! suggestion are fine.
 //!
 //! Rate limiting to prevent share flooding is the scheduler's responsibility.
 //! Sources declare their maximum share rate at registration time, and the
@@ -54,18 +55,28 @@
 pub mod dummy;
 mod extranonce2;
 pub mod forced_rate;
+pub mod header;
 pub(crate) mod job;
+mod job_intake;
 mod merkle;
 mod messages;
+mod reconnects;
+mod share_queue;
+pub mod stale_ratio;
 pub mod stratum_v1;
 pub mod test_blocks;
+mod vardiff_swings;
 mod version;
 
 // Re-export types from submodules
 pub use extranonce2::{Extranonce2, Extranonce2Error, Extranonce2Iter, Extranonce2Range};
 pub use job::{JobTemplate, Share};
-pub use merkle::{MerkleRootKind, MerkleRootTemplate};
-pub use messages::{SourceCommand, SourceEvent, SourceHandle};
+pub use job_intake::{Intake, JobIntake};
+pub use merkle::{MerkleCache, MerkleRootKind, MerkleRootTemplate};
+pub use messages::{SourceCommand, SourceEvent, SourceHandle, SourceStats};
+pub use reconnects::{Reconnect, ReconnectGrace};
+pub use share_queue::ShareQueue;
+pub use vardiff_swings::SwingTracker;
 pub use version::{GeneralPurposeBits, VersionTemplate, VersionTemplateError};
 
 // TODO: Add HeaderTemplate type (Level 2 in the hierarchy)
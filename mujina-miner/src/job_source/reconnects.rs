@@ -0,0 +1,99 @@
+//! Telling brief reconnects from outages.
+//!
+//! Some pools drop idle connections, or restart a frontend behind a load
+//! balancer, and the miner is back on within a moment having lost
+//! nothing. Counted like an outage, such reconnects make a healthy pool
+//! look unreliable. When the connection comes back, the time since it went
+//! down decides: within the grace it was a blip, logged at debug and not
+//! counted; longer, it was a disruption and counts in `reconnects`.
+//! Failed attempts in between extend the one outage rather than each
+//! starting another.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// How a connection coming back is counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reconnect {
+    /// Back within the grace, after being down for the given time.
+    Brief(Duration),
+    /// Back after an outage longer than the grace.
+    Disruptive(Duration),
+}
+
+/// Times each outage of one source's connection.
+#[derive(Debug)]
+pub struct ReconnectGrace {
+    grace: Duration,
+    /// When the connection went down, `None` while it is up or before
+    /// it first came up.
+    down_since: Option<Instant>,
+}
+
+impl ReconnectGrace {
+    /// Count reconnects after an outage up to `grace` as brief. A zero
+    /// grace counts every one.
+    pub fn new(grace: Duration) -> Self {
+        Self {
+            grace,
+            down_since: None,
+        }
+    }
+
+    /// The connection went down at `now`. Later losses before it comes
+    /// back belong to the same outage.
+    pub fn lost(&mut self, now: Instant) {
+        self.down_since.get_or_insert(now);
+    }
+
+    /// The connection came back at `now`: how to count it, `None` for
+    /// the first connection.
+    pub fn restored(&mut self, now: Instant) -> Option<Reconnect> {
+        let outage = now.duration_since(self.down_since.take()?);
+        Some(if outage < self.grace {
+            Reconnect::Brief(outage)
+        } else {
+            Reconnect::Disruptive(outage)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_outages_past_the_grace_count() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut reconnects = ReconnectGrace::new(Duration::from_secs(1));
+
+        // The first connection is no reconnect at all.
+        assert_eq!(reconnects.restored(at(0)), None);
+
+        // Back within the grace: a blip.
+        reconnects.lost(at(1000));
+        assert_eq!(
+            reconnects.restored(at(1300)),
+            Some(Reconnect::Brief(Duration::from_millis(300)))
+        );
+
+        // A failed attempt in between doesn't restart the outage, which
+        // runs past the grace.
+        reconnects.lost(at(2000));
+        reconnects.lost(at(2600));
+        assert_eq!(
+            reconnects.restored(at(3500)),
+            Some(Reconnect::Disruptive(Duration::from_millis(1500)))
+        );
+
+        // Without a grace, every reconnect counts.
+        let mut reconnects = ReconnectGrace::new(Duration::ZERO);
+        reconnects.lost(at(0));
+        assert_eq!(
+            reconnects.restored(at(0)),
+            Some(Reconnect::Disruptive(Duration::ZERO))
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Bounded queue for shares awaiting submission.
+//!
+//! A slow pool must not let shares pile up without limit on a small
+//! device. The queue holds at most `capacity` ordinary shares; when full,
+//! the oldest ordinary share (the most likely to be stale) is dropped to
+//! make room and the drop is counted. Shares that solve a block are never
+//! dropped, so the queue may exceed its capacity by however many of those
+//! are waiting, which in practice is none or one.
+
+use std::collections::VecDeque;
+
+/// FIFO of pending submissions with a drop-oldest overflow policy.
+#[derive(Debug)]
+pub struct ShareQueue<T> {
+    items: VecDeque<Queued<T>>,
+    capacity: usize,
+    dropped: u64,
+}
+
+#[derive(Debug)]
+struct Queued<T> {
+    item: T,
+    solves_block: bool,
+}
+
+impl<T> ShareQueue<T> {
+    /// Create an empty queue holding up to `capacity` ordinary shares.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+            dropped: 0,
+        }
+    }
+
+    /// Enqueue a share, dropping the oldest ordinary share if full.
+    ///
+    /// Returns `true` if a share was dropped to stay within capacity.
+    pub fn push(&mut self, item: T, solves_block: bool) -> bool {
+        let mut dropped = false;
+        if self.items.len() >= self.capacity {
+            match self.items.iter().position(|q| !q.solves_block) {
+                Some(oldest) => {
+                    self.items.remove(oldest);
+                    self.dropped += 1;
+                    dropped = true;
+                }
+                None if !solves_block => {
+                    // Full of block solutions; the newcomer is the one to go.
+                    self.dropped += 1;
+                    return true;
+                }
+                None => {}
+            }
+        }
+        self.items.push_back(Queued { item, solves_block });
+        dropped
+    }
+
+    /// Take the oldest queued share.
+    pub fn pop(&mut self) -> Option<T> {
+        self.items.pop_front().map(|q| q.item)
+    }
+
+    /// Take the oldest queued share and whether it solves a block.
+    pub fn pop_marked(&mut self) -> Option<(T, bool)> {
+        self.items.pop_front().map(|q| (q.item, q.solves_block))
+    }
+
+    /// Number of shares waiting.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether no shares are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Total shares dropped since the queue was created.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_below_capacity() {
+        let mut queue = ShareQueue::new(4);
+        for i in 0..3 {
+            assert!(!queue.push(i, false));
+        }
+        assert_eq!(queue.pop(), Some(0));
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.dropped(), 0);
+    }
+
+    #[test]
+    fn flood_stays_bounded_and_counts_drops() {
+        let mut queue = ShareQueue::new(8);
+        for i in 0..1000 {
+            queue.push(i, false);
+            assert!(queue.len() <= 8);
+        }
+        assert_eq!(queue.dropped(), 992);
+
+        // The newest shares survive.
+        let kept: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(kept, (992..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn block_shares_survive_a_flood() {
+        let mut queue = ShareQueue::new(4);
+        queue.push(-1, true);
+        for i in 0..100 {
+            queue.push(i, false);
+        }
+        queue.push(-2, true);
+        for i in 100..200 {
+            queue.push(i, false);
+        }
+        // Block shares are at most one over capacity each.
+        assert!(queue.len() <= 5);
+
+        let kept: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+        assert!(kept.contains(&-1));
+        assert!(kept.contains(&-2));
+    }
+
+    #[test]
+    fn full_of_block_shares_drops_newcomer() {
+        let mut queue = ShareQueue::new(2);
+        queue.push(1, true);
+        queue.push(2, true);
+        assert!(queue.push(3, false));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.dropped(), 1);
+
+        // Another block share is kept regardless.
+        assert!(!queue.push(4, true));
+        assert_eq!(queue.len(), 3);
+    }
+}
@@ -0,0 +1,226 @@
+//! Judging a pool by how many of its shares go stale.
+//!
+//! A share is stale when it arrives for work the pool has moved on from.
+//! A few are unavoidable around each new block, but a pool far away, or
+//! slow to answer, loses a steady fraction of every board's work this
+//! way, so a pool's stale ratio is a good proxy for how well it suits the
+//! miner. Counted stale are the shares withheld because their job had
+//! expired and those the pool rejected as stale, out of every share found
+//! for it.
+//!
+//! With `MUJINA_POOL_MAX_STALE_PERCENT` set, the scheduler moves the
+//! boards off a primary pool whose ratio stays above it for a whole
+//! `MUJINA_POOL_STALE_WINDOW_SECS`, as if promoted through the API.
+
+use std::collections::VecDeque;
+use std::env;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use super::SourceStats;
+use crate::tracing::prelude::*;
+
+/// Window the stale ratio is measured over when not configured.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Shares a window must hold before its ratio means anything.
+pub const MIN_SHARES: u64 = 20;
+
+/// When to fail over from a pool whose shares go stale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StaleFailoverPolicy {
+    /// Stale fraction of shares above which the pool is left.
+    pub max_ratio: f64,
+    /// How long the ratio must stay above it.
+    pub window: Duration,
+}
+
+impl StaleFailoverPolicy {
+    /// Read `MUJINA_POOL_MAX_STALE_PERCENT` and
+    /// `MUJINA_POOL_STALE_WINDOW_SECS`. `None`, never failing over, when
+    /// the maximum is unset, 0 or invalid, with a warning for invalid
+    /// values; an invalid window keeps the default with a warning.
+    pub fn from_env() -> Option<Self> {
+        let val = env::var("MUJINA_POOL_MAX_STALE_PERCENT").ok()?;
+        let max_ratio = match val.parse::<f64>() {
+            Ok(0.0) => return None,
+            Ok(percent) if percent > 0.0 && percent < 100.0 => percent / 100.0,
+            _ => {
+                warn!(value = %val, "Invalid MUJINA_POOL_MAX_STALE_PERCENT, no stale failover");
+                return None;
+            }
+        };
+        let window = match env::var("MUJINA_POOL_STALE_WINDOW_SECS") {
+            Err(_) => DEFAULT_WINDOW,
+            Ok(val) => match val.parse::<u64>() {
+                Ok(secs) if secs > 0 => Duration::from_secs(secs),
+                _ => {
+                    warn!(
+                        value = %val,
+                        default_secs = DEFAULT_WINDOW.as_secs(),
+                        "Invalid MUJINA_POOL_STALE_WINDOW_SECS, using default"
+                    );
+                    DEFAULT_WINDOW
+                }
+            },
+        };
+        Some(Self { max_ratio, window })
+    }
+}
+
+/// Whether a pool's reason for rejecting a share means it was stale.
+///
+/// Pools word this differently: "Stale", "stale-prevblk", "Job not
+/// found" for a job they have already dropped.
+pub fn is_stale_reason(reason: &str) -> bool {
+    let reason = reason.to_ascii_lowercase();
+    reason.contains("stale") || reason.contains("job not found") || reason.contains("unknown job")
+}
+
+/// Running share totals of one pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StaleCounts {
+    /// Shares that went stale.
+    pub stale: u64,
+    /// Every share found for the pool, stale or not.
+    pub total: u64,
+}
+
+impl StaleCounts {
+    /// The totals a source's stats show.
+    pub fn from_stats(stats: &SourceStats) -> Self {
+        Self {
+            stale: stats.shares_stale_avoided + stats.shares_rejected_stale,
+            total: stats.shares_accepted + stats.shares_rejected + stats.shares_stale_avoided,
+        }
+    }
+}
+
+impl std::ops::AddAssign for StaleCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.stale += other.stale;
+        self.total += other.total;
+    }
+}
+
+/// One pool's stale ratio over a sliding window, from totals sampled now
+/// and then.
+#[derive(Debug)]
+pub struct StaleWindow {
+    window: Duration,
+    /// Samples, oldest first. The oldest is the latest at or before the
+    /// start of the window, so differences span all of it.
+    samples: VecDeque<(Instant, StaleCounts)>,
+}
+
+impl StaleWindow {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Record the pool's totals at `now`.
+    pub fn observe(&mut self, now: Instant, counts: StaleCounts) {
+        let start = now.checked_sub(self.window).unwrap_or(now);
+        while self.samples.get(1).is_some_and(|&(at, _)| at <= start) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, counts));
+    }
+
+    /// Stale fraction of the shares over the last full window. `None`
+    /// until samples span a window, or when it holds too few shares to
+    /// judge.
+    pub fn ratio(&self) -> Option<f64> {
+        let (&(first_at, first), &(last_at, last)) = (self.samples.front()?, self.samples.back()?);
+        if last_at.duration_since(first_at) < self.window {
+            return None;
+        }
+        // Totals shrink when a source restarts; judge nothing across that.
+        let total = last.total.checked_sub(first.total)?;
+        let stale = last.stale.checked_sub(first.stale)?;
+        (total >= MIN_SHARES).then(|| stale as f64 / total as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    fn ratio_covers_a_full_window_of_enough_shares() {
+        let window = Duration::from_secs(600);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let counts = |stale, total| StaleCounts { stale, total };
+
+        let mut pool = StaleWindow::new(window);
+        pool.observe(at(0), counts(0, 0));
+        pool.observe(at(300), counts(10, 50));
+        // Not a full window yet.
+        assert_eq!(pool.ratio(), None);
+        pool.observe(at(600), counts(20, 100));
+        assert_eq!(pool.ratio(), Some(0.2));
+        // Slides: the last 600 s hold 30 stale out of 100.
+        pool.observe(at(900), counts(40, 150));
+        pool.observe(at(1200), counts(50, 200));
+        assert_eq!(pool.ratio(), Some(0.3));
+        // A quiet window has too few shares to judge.
+        pool.observe(at(1800), counts(52, 210));
+        assert_eq!(pool.ratio(), None);
+    }
+
+    #[test]
+    fn stale_rejections_are_recognized() {
+        for reason in ["Stale", "stale-prevblk", "Job not found"] {
+            assert!(is_stale_reason(reason), "{reason}");
+        }
+        for reason in ["Low difficulty share", "Duplicate share"] {
+            assert!(!is_stale_reason(reason), "{reason}");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn policy_from_env() {
+        let (max, window) = (
+            "MUJINA_POOL_MAX_STALE_PERCENT",
+            "MUJINA_POOL_STALE_WINDOW_SECS",
+        );
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(max);
+            env::remove_var(window);
+            assert_eq!(StaleFailoverPolicy::from_env(), None);
+            env::set_var(max, "5");
+            assert_eq!(
+                StaleFailoverPolicy::from_env(),
+                Some(StaleFailoverPolicy {
+                    max_ratio: 0.05,
+                    window: DEFAULT_WINDOW
+                })
+            );
+            env::set_var(window, "120");
+            assert_eq!(
+                StaleFailoverPolicy::from_env().unwrap().window,
+                Duration::from_secs(120)
+            );
+            env::set_var(window, "0");
+            assert_eq!(
+                StaleFailoverPolicy::from_env().unwrap().window,
+                DEFAULT_WINDOW
+            );
+            for bad in ["0", "100", "lots"] {
+                env::set_var(max, bad);
+                assert_eq!(StaleFailoverPolicy::from_env(), None, "{bad}");
+            }
+            env::remove_var(max);
+            env::remove_var(window);
+        }
+    }
+}
@@ -5,24 +5,32 @@
 //! the internal JobTemplate/Share types used by the scheduler.
 
 use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, VecDeque};
 use std::future;
 use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
-use tokio::sync::mpsc;
+use bitcoin::BlockHash;
+use bitcoin::pow::Target;
+use tokio::sync::{mpsc, watch};
 use tokio::time::{self, Instant};
 use tokio_util::sync::CancellationToken;
 
+use crate::clock::{self, Clock};
+use crate::network;
 use crate::stratum_v1::{
-    ClientCommand, ClientEvent, Connector, JobNotification, PoolConfig, StratumV1Client,
+    ClientCommand, ClientEvent, Connector, JobNotification, PoolConfig, StratumError,
+    StratumV1Client, SubmitParams, SuggestDifficulty,
 };
 use crate::tracing::prelude::*;
-use crate::types::{Difficulty, HashRate, ShareRate};
+use crate::types::{DailyCount, Difficulty, HashRate, ShareRate};
 
 use super::{
-    Extranonce2Range, GeneralPurposeBits, JobTemplate, MerkleRootKind, MerkleRootTemplate, Share,
-    SourceCommand, SourceEvent, VersionTemplate,
+    Extranonce2Range, GeneralPurposeBits, Intake, JobIntake, JobTemplate, MerkleRootKind,
+    MerkleRootTemplate, Reconnect, ReconnectGrace, Share, ShareQueue, SourceCommand, SourceEvent,
+    SourceStats, SwingTracker, VersionTemplate, stale_ratio,
 };
 
 /// Target share rate for suggest_difficulty: 20 shares/min (one every 3 sec).
@@ -45,6 +53,23 @@ const UP_FACTOR: f64 = 1.5;
 /// costs at most one message per interval. A starting value, tune with use.
 const SUGGEST_MIN_INTERVAL: Duration = Duration::from_secs(5);
 
+/// Time from authorize to first job beyond which the pool is flagged as slow.
+///
+/// Pools normally send `mining.notify` immediately after authorizing, so a
+/// long wait suggests a misbehaving or overloaded pool. The warning comes
+/// once this has passed without a job, and again with the wait once the
+/// job arrives.
+const SLOW_FIRST_JOB_THRESHOLD: Duration = Duration::from_secs(10);
+
+/// Shares held for submission while the pool is slow to respond. Beyond
+/// this the oldest are dropped; at the one-every-few-seconds rate the
+/// scheduler aims for, this is minutes of backlog.
+const SUBMIT_QUEUE_CAPACITY: usize = 64;
+
+/// Shares whose difficulty is remembered while awaiting the pool's answer.
+/// Well above what the submit queue can hold in flight.
+const MAX_UNANSWERED_SHARES: usize = 4 * SUBMIT_QUEUE_CAPACITY;
+
 /// Minimum connection duration before backoff resets on disconnect.
 ///
 /// If a connection was alive for at least this long, the next reconnect
@@ -104,6 +129,16 @@ impl ExponentialBackoff {
     }
 }
 
+/// Whether `event` is the pool's last word on a submitted share.
+fn answers_share(event: &ClientEvent) -> bool {
+    matches!(
+        event,
+        ClientEvent::ShareAccepted { .. }
+            | ClientEvent::ShareRejected { .. }
+            | ClientEvent::ShareUnanswered { .. }
+    )
+}
+
 /// Outcome of a single connection attempt.
 enum ConnectOutcome {
     /// Graceful shutdown requested.
@@ -149,6 +184,57 @@ pub struct StratumV1Source {
 
     /// Factory for creating transport connections.
     connector: Box<dyn Connector>,
+
+    /// When the current connection was authorized.
+    authorized_at: Option<Instant>,
+
+    /// Whether the current connection has delivered a job yet.
+    first_job_seen: bool,
+
+    /// When to warn that the pool still hasn't sent a job since
+    /// authorize, `None` once one arrives or the warning is given.
+    first_job_due: Option<Instant>,
+
+    /// Connection measurements published to the scheduler.
+    stats_tx: watch::Sender<SourceStats>,
+
+    /// Latest non-clean job held back by the debounce.
+    pending_job: Option<JobTemplate>,
+
+    /// When the job debounce window ends, `None` when no window is open.
+    job_window_until: Option<Instant>,
+
+    /// Whether a job from another network has been warned about on the
+    /// current connection.
+    network_mismatch_warned: bool,
+
+    /// Achieved difficulty of shares awaiting the pool's answer, keyed by
+    /// job ID and nonce. Only kept when a log threshold is configured.
+    unanswered_shares: HashMap<(String, u32), Difficulty>,
+
+    /// Recent jobs from the pool and when each first arrived, least
+    /// recently sent first, so the last is the pool's latest job. Holds at
+    /// most [`PoolConfig::job_history`] jobs; shares on any other job are
+    /// stale.
+    job_arrivals: VecDeque<(String, Instant)>,
+
+    /// The current connection's share difficulty changes.
+    difficulty_swings: SwingTracker,
+
+    /// The current connection's jobs counted against
+    /// [`PoolConfig::max_jobs_per_sec`], when limited.
+    job_intake: Option<JobIntake>,
+
+    /// Times the connection's outages, to tell brief reconnects from
+    /// disruptions.
+    reconnects: ReconnectGrace,
+
+    /// Previous block hash of the latest job. Kept across reconnects, so
+    /// a block found while disconnected still counts.
+    prev_hash: Option<BlockHash>,
+
+    /// Time of day for the daily share counts.
+    clock: Arc<dyn Clock>,
 }
 
 /// Protocol state after successful subscription.
@@ -176,6 +262,9 @@ impl StratumV1Source {
         shutdown: CancellationToken,
         connector: Box<dyn Connector>,
     ) -> Self {
+        let day_boundary = config.day_boundary;
+        let job_intake = config.max_jobs_per_sec.map(JobIntake::new);
+        let reconnects = ReconnectGrace::new(config.reconnect_grace);
         Self {
             config,
             event_tx,
@@ -187,9 +276,38 @@ impl StratumV1Source {
             last_suggested_difficulty: None,
             cooldown_until: None,
             connector,
+            authorized_at: None,
+            first_job_seen: false,
+            first_job_due: None,
+            stats_tx: watch::Sender::new(SourceStats {
+                accepted_today: DailyCount::new(day_boundary),
+                rejected_today: DailyCount::new(day_boundary),
+                ..Default::default()
+            }),
+            pending_job: None,
+            job_window_until: None,
+            network_mismatch_warned: false,
+            unanswered_shares: HashMap::new(),
+            job_arrivals: VecDeque::new(),
+            difficulty_swings: SwingTracker::new(),
+            job_intake,
+            reconnects,
+            prev_hash: None,
+            clock: clock::system(),
         }
     }
 
+    /// Count shares on the days of `clock` rather than the host's.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Subscribe to this source's connection measurements.
+    pub fn stats(&self) -> watch::Receiver<SourceStats> {
+        self.stats_tx.subscribe()
+    }
+
     /// Human-readable name derived from pool URL (e.g., "solo.ckpool.org:3333").
     pub fn name(&self) -> String {
         self.config
@@ -220,9 +338,17 @@ impl StratumV1Source {
 
         let version_template = VersionTemplate::new(job.version, gp_bits_mask)?;
 
-        // Use pool's share difficulty directly (scheduler handles rate limiting)
+        // Use pool's share difficulty directly (scheduler handles rate limiting),
+        // raised to the local floor if one is configured. A share never
+        // needs to beat a block: on test networks easier than difficulty 1,
+        // the block target is the share target.
         let share_difficulty = state.share_difficulty.unwrap_or(Difficulty::from(1));
-        let share_target = share_difficulty.to_target();
+        let network_target = Target::from_compact(job.nbits);
+        let share_target = self
+            .apply_difficulty_floor(share_difficulty.to_target(), network_target)
+            .max(network_target);
+
+        let ntime_roll = self.config.ntime_roll.min(PoolConfig::MAX_NTIME_ROLL);
 
         Ok(JobTemplate {
             id: job.job_id,
@@ -231,16 +357,169 @@ impl StratumV1Source {
             bits: job.nbits,
             share_target,
             time: job.ntime,
+            max_time: job.ntime.saturating_add(ntime_roll.as_secs() as u32),
             merkle_root: MerkleRootKind::Computed(MerkleRootTemplate {
                 coinbase1: job.coinbase1,
                 extranonce1: state.extranonce1.clone(),
                 extranonce2_range,
                 coinbase2: job.coinbase2,
                 merkle_branches: job.merkle_branches,
+                cache: Default::default(),
             }),
         })
     }
 
+    /// Warn, once per connection, about a job whose block target the
+    /// configured network can't require.
+    ///
+    /// The job is still mined: the pool decides what it pays for. But a
+    /// block found on it belongs to another chain.
+    fn check_network(&mut self, network_target: Target) {
+        if self.network_mismatch_warned
+            || network::allows_target(self.config.network, network_target)
+        {
+            return;
+        }
+        self.network_mismatch_warned = true;
+        warn!(
+            pool = %self.config.url,
+            network = network::name(self.config.network),
+            network_difficulty = %Difficulty::from_target(network_target),
+            "Pool's jobs are easier than the configured network allows; \
+             is it mining another network? Check MUJINA_NETWORK"
+        );
+    }
+
+    /// Count a share dropped from the submission queue.
+    ///
+    /// Warns once per connection; a pool that falls behind tends to stay
+    /// behind, and a warning per share would flood the log.
+    fn record_share_dropped(&mut self, warned: &mut bool) {
+        self.stats_tx.send_modify(|stats| stats.shares_dropped += 1);
+        let dropped = self.stats_tx.borrow().shares_dropped;
+        if !*warned {
+            *warned = true;
+            warn!(
+                pool = %self.config.url,
+                dropped,
+                "Pool not keeping up with share submissions, dropping oldest shares"
+            );
+        } else {
+            trace!(dropped, "Dropped queued share");
+        }
+    }
+
+    /// Remember a share's achieved difficulty until the pool answers, so
+    /// the acceptance can be checked against the log threshold.
+    fn track_unanswered(&mut self, share: &Share) {
+        if self.config.log_share_difficulty.is_none() {
+            return;
+        }
+        // Shares dropped before submission are never answered; bound the
+        // map so a pool that falls behind can't grow it forever.
+        if self.unanswered_shares.len() >= MAX_UNANSWERED_SHARES {
+            self.unanswered_shares.clear();
+        }
+        self.unanswered_shares
+            .insert((share.job_id.clone(), share.nonce), share.difficulty);
+    }
+
+    /// Note the previous block hash of a job from the pool. Returns
+    /// whether it differs from the last job's, meaning a block was found
+    /// on the network (or the chain reorganized) since.
+    fn observe_prev_hash(&mut self, prev_hash: BlockHash) -> bool {
+        let Some(last) = self.prev_hash.replace(prev_hash) else {
+            return false;
+        };
+        if last == prev_hash {
+            return false;
+        }
+        let mut blocks_seen = 0;
+        self.stats_tx.send_modify(|stats| {
+            stats.network_blocks_seen += 1;
+            blocks_seen = stats.network_blocks_seen;
+        });
+        info!(
+            pool = %self.config.url,
+            prev_hash = %prev_hash,
+            blocks_seen,
+            "New block on the network."
+        );
+        true
+    }
+
+    /// Remember a job and when it arrived, forgetting the oldest once
+    /// the history is full.
+    ///
+    /// A job the pool sends again becomes the latest but keeps the time
+    /// it first arrived, so resending it doesn't reset its age.
+    fn record_job_arrival(&mut self, job_id: &str) {
+        let arrived = match self.job_arrivals.iter().position(|(id, _)| id == job_id) {
+            Some(pos) => self.job_arrivals.remove(pos).map(|(_, arrived)| arrived),
+            None => {
+                if self.job_arrivals.len() >= self.config.job_history.max(1) {
+                    self.job_arrivals.pop_front();
+                }
+                None
+            }
+        };
+        self.job_arrivals
+            .push_back((job_id.to_string(), arrived.unwrap_or_else(Instant::now)));
+    }
+
+    /// Whether `share` is on a job the pool would reject as stale: one
+    /// forgotten from the job history, or a replaced one older than the
+    /// configured maximum age.
+    ///
+    /// The pool's latest job is never stale, however long ago it came: with
+    /// nothing newer it is still the pool's work. A share that solves a
+    /// block is never stale here either: the block may still win, and the
+    /// pool, not the guess, should decide.
+    fn is_stale(&self, share: &Share) -> bool {
+        if share.solves_block {
+            return false;
+        }
+        let latest = self.job_arrivals.len().checked_sub(1);
+        match self
+            .job_arrivals
+            .iter()
+            .position(|(id, _)| *id == share.job_id)
+        {
+            None => true,
+            Some(pos) if Some(pos) == latest => false,
+            Some(pos) => self
+                .config
+                .max_job_age
+                .is_some_and(|max_age| self.job_arrivals[pos].1.elapsed() > max_age),
+        }
+    }
+
+    /// Count a share withheld because its job was too old.
+    fn record_stale_avoided(&mut self, share: &Share) {
+        self.stats_tx
+            .send_modify(|stats| stats.shares_stale_avoided += 1);
+        debug!(
+            pool = %self.name(),
+            job_id = %share.job_id,
+            nonce = format!("{:#x}", share.nonce),
+            "Share on an expired or forgotten job, not submitting"
+        );
+    }
+
+    /// Tighten the pool's share target to the configured minimum difficulty.
+    ///
+    /// The floor itself is capped at the network target, so a share that
+    /// solves a block always passes no matter how high the floor is set.
+    fn apply_difficulty_floor(&self, pool_target: Target, network_target: Target) -> Target {
+        let Some(min_difficulty) = self.config.min_difficulty else {
+            return pool_target;
+        };
+        let floor_target = Difficulty::from_f64(min_difficulty)
+            .to_target()
+            .max(network_target);
+        pool_target.min(floor_target)
+    }
+
     /// Handle a client event.
     async fn handle_client_event(&mut self, event: ClientEvent) -> Result<()> {
         match event {
@@ -294,18 +573,46 @@ impl StratumV1Source {
                 }
             }
 
+            ClientEvent::Authorized => {
+                let now = Instant::now();
+                self.authorized_at = Some(now);
+                self.record_reconnect(now);
+                // Some pools send the first job while authorize is in flight.
+                if self.first_job_seen {
+                    self.record_time_to_first_job(Duration::ZERO);
+                } else {
+                    self.first_job_due = Some(now + SLOW_FIRST_JOB_THRESHOLD);
+                }
+            }
+
             ClientEvent::NewJob(job) => {
                 debug!(job_id = %job.job_id, clean_jobs = job.clean_jobs, "Received job from pool");
 
-                let clean_jobs = job.clean_jobs;
+                if !self.first_job_seen {
+                    self.first_job_seen = true;
+                    self.first_job_due = None;
+                    if let Some(authorized_at) = self.authorized_at {
+                        self.record_time_to_first_job(authorized_at.elapsed());
+                    }
+                }
+
+                // A new previous block hash means the network moved on, so
+                // old work is worthless whether or not the pool says so.
+                let new_block = self.observe_prev_hash(job.prev_hash);
+                let clean_jobs = job.clean_jobs || new_block;
+                if !self.admit_job(&job.job_id, clean_jobs) {
+                    return Ok(());
+                }
+                self.record_job_arrival(&job.job_id);
+                self.check_network(Target::from_compact(job.nbits));
                 let template = self.job_to_template(job)?;
-                let event = if clean_jobs {
-                    SourceEvent::ReplaceJob(template)
+                if clean_jobs {
+                    // Old work is invalid; anything held is superseded.
+                    self.pending_job = None;
+                    self.send_job(SourceEvent::ReplaceJob(template)).await?;
                 } else {
-                    SourceEvent::UpdateJob(template)
-                };
-
-                self.event_tx.send(event).await?;
+                    self.debounce_job_update(template).await?;
+                }
             }
 
             ClientEvent::DifficultyChanged(diff) => {
@@ -314,6 +621,21 @@ impl StratumV1Source {
                 if let Some(state) = &mut self.state {
                     state.share_difficulty = Some(difficulty);
                 }
+                let oscillating = self.difficulty_swings.observe(Instant::now(), diff);
+                if self.stats_tx.send_if_modified(|stats| {
+                    std::mem::replace(&mut stats.difficulty_oscillating, oscillating) != oscillating
+                }) {
+                    if oscillating {
+                        warn!(
+                            pool = %self.config.url,
+                            reversals = self.difficulty_swings.reversals(),
+                            "Pool difficulty oscillating; its vardiff may be costing \
+                             efficiency, consider another pool"
+                        );
+                    } else {
+                        info!(pool = %self.config.url, "Pool difficulty settled");
+                    }
+                }
             }
 
             ClientEvent::VersionMaskSet(mask) => {
@@ -324,6 +646,20 @@ impl StratumV1Source {
             }
 
             ClientEvent::ShareAccepted { job_id, nonce } => {
+                let now = self.clock.now_utc();
+                self.stats_tx.send_modify(|stats| {
+                    stats.shares_accepted += 1;
+                    stats.accepted_today.increment(now);
+                });
+                let difficulty = self.unanswered_shares.remove(&(job_id.clone(), nonce));
+                // Only informs the scheduler; not worth holding up the
+                // connection for when its queue is full.
+                if let Err(e) = self.event_tx.try_send(SourceEvent::ShareAccepted {
+                    job_id: job_id.clone(),
+                    nonce,
+                }) {
+                    trace!(error = %e, "Accepted share not passed to scheduler");
+                }
                 if !self.first_share_logged {
                     self.first_share_logged = true;
                     info!(
@@ -333,6 +669,17 @@ impl StratumV1Source {
                         job_id = %job_id,
                         "First share accepted."
                     );
+                } else if let Some(threshold) = self.config.log_share_difficulty {
+                    // Below the threshold the share is only counted.
+                    if let Some(difficulty) = difficulty.filter(|&d| d >= threshold) {
+                        info!(
+                            pool = %self.config.url,
+                            nonce = format!("{:#x}", nonce),
+                            job_id = %job_id,
+                            difficulty = %difficulty,
+                            "Share accepted."
+                        );
+                    }
                 } else {
                     debug!(
                         pool = %self.config.url,
@@ -344,10 +691,27 @@ impl StratumV1Source {
                 }
             }
 
-            ClientEvent::ShareRejected { job_id, reason } => {
+            ClientEvent::ShareRejected {
+                job_id,
+                nonce,
+                reason,
+            } => {
+                self.unanswered_shares.remove(&(job_id.clone(), nonce));
+                let now = self.clock.now_utc();
+                let stale = stale_ratio::is_stale_reason(&reason);
+                self.stats_tx.send_modify(|stats| {
+                    stats.shares_rejected += 1;
+                    stats.shares_rejected_stale += u64::from(stale);
+                    stats.rejected_today.increment(now);
+                });
                 warn!(job_id = %job_id, reason = %reason, "Share rejected by pool");
             }
 
+            ClientEvent::ShareUnanswered { job_id, nonce } => {
+                // The client has already warned.
+                self.unanswered_shares.remove(&(job_id, nonce));
+            }
+
             ClientEvent::Disconnected => {
                 warn!("Disconnected from pool");
                 // ClearJobs is sent by the reconnection loop after
@@ -364,6 +728,123 @@ impl StratumV1Source {
         Ok(())
     }
 
+    /// Count a job against the intake limit, returning whether to take it.
+    fn admit_job(&mut self, job_id: &str, clean: bool) -> bool {
+        let Some(intake) = &mut self.job_intake else {
+            return true;
+        };
+        match intake.admit(Instant::now(), clean) {
+            Intake::Accept => true,
+            Intake::AcceptAfterFlood { dropped } => {
+                info!(
+                    pool = %self.config.url,
+                    dropped,
+                    "Pool job notifications back under the limit"
+                );
+                true
+            }
+            Intake::Drop { first } => {
+                if first {
+                    warn!(
+                        pool = %self.config.url,
+                        limit = self.config.max_jobs_per_sec,
+                        "Pool flooding job notifications; dropping updates past the \
+                         limit per second"
+                    );
+                }
+                trace!(job_id = %job_id, "Dropped job over the intake limit");
+                false
+            }
+        }
+    }
+
+    /// Forward a non-clean job, or hold it if a job went out within the
+    /// debounce window.
+    ///
+    /// A held job is replaced by any newer one and flushed by
+    /// `flush_job_update` when the window ends, so a burst of notifies
+    /// costs the boards at most one work change per window. The pool's
+    /// old work stays valid throughout, so holding loses nothing.
+    async fn debounce_job_update(&mut self, template: JobTemplate) -> Result<()> {
+        if self
+            .job_window_until
+            .is_some_and(|until| Instant::now() < until)
+        {
+            if let Some(replaced) = self.pending_job.replace(template) {
+                trace!(job_id = %replaced.id, "Coalesced job update");
+            }
+            return Ok(());
+        }
+        self.send_job(SourceEvent::UpdateJob(template)).await
+    }
+
+    /// Send the job held during the debounce window, or else close the
+    /// window.
+    async fn flush_job_update(&mut self) -> Result<()> {
+        match self.pending_job.take() {
+            Some(template) => self.send_job(SourceEvent::UpdateJob(template)).await,
+            None => {
+                self.job_window_until = None;
+                Ok(())
+            }
+        }
+    }
+
+    /// Send a job event to the scheduler and open a debounce window.
+    async fn send_job(&mut self, event: SourceEvent) -> Result<()> {
+        if !self.config.job_debounce.is_zero() {
+            self.job_window_until = Some(Instant::now() + self.config.job_debounce);
+        }
+        self.event_tx.send(event).await?;
+        Ok(())
+    }
+
+    /// Publish the time-to-first-job measurement, warning if the pool was
+    /// slow. Returns whether the warning fired.
+    fn record_time_to_first_job(&mut self, latency: Duration) -> bool {
+        self.stats_tx
+            .send_modify(|stats| stats.time_to_first_job = Some(latency));
+
+        let slow = latency >= SLOW_FIRST_JOB_THRESHOLD;
+        if slow {
+            warn!(
+                pool = %self.config.url,
+                latency_secs = latency.as_secs_f64(),
+                "Pool slow to send first job after authorize"
+            );
+        } else {
+            debug!(latency_secs = latency.as_secs_f64(), "First job received");
+        }
+        slow
+    }
+
+    /// Count the connection coming back at `now` if it was down past the
+    /// reconnect grace.
+    fn record_reconnect(&mut self, now: Instant) {
+        match self.reconnects.restored(now) {
+            None => {}
+            Some(Reconnect::Brief(outage)) => {
+                debug!(
+                    outage_ms = outage.as_millis() as u64,
+                    "Pool reconnected within the grace, not a disruption"
+                );
+            }
+            Some(Reconnect::Disruptive(outage)) => {
+                let mut reconnects = 0;
+                self.stats_tx.send_modify(|stats| {
+                    stats.reconnects += 1;
+                    reconnects = stats.reconnects;
+                });
+                info!(
+                    pool = %self.config.url,
+                    outage_secs = outage.as_secs_f64(),
+                    reconnects,
+                    "Pool connection restored after an outage"
+                );
+            }
+        }
+    }
+
     /// Convert Share to SubmitParams.
     fn share_to_submit_params(&self, share: Share) -> Result<crate::stratum_v1::SubmitParams> {
         let state = self
@@ -409,6 +890,18 @@ impl StratumV1Source {
         Some(Difficulty::from_target(target).as_f64())
     }
 
+    /// The difficulty to suggest under the configured policy, if any.
+    ///
+    /// A fixed value never changes, so the deadband suppresses every
+    /// re-suggestion after the one sent at connect.
+    fn suggested_difficulty(&self) -> Option<f64> {
+        match self.config.suggest_difficulty {
+            SuggestDifficulty::Auto => Self::compute_suggested_difficulty(self.expected_hashrate),
+            SuggestDifficulty::Fixed(difficulty) => Some(difficulty),
+            SuggestDifficulty::Off => None,
+        }
+    }
+
     /// Whether `new_diff` differs enough from the last suggestion to re-suggest.
     ///
     /// The deadband is asymmetric, measured from the last suggestion (which the
@@ -433,7 +926,7 @@ impl StratumV1Source {
     /// once the cooldown expires, so a burst collapses to one message per
     /// interval.
     async fn maybe_suggest_difficulty(&mut self, client_command_tx: &mpsc::Sender<ClientCommand>) {
-        let Some(new_diff) = Self::compute_suggested_difficulty(self.expected_hashrate) else {
+        let Some(new_diff) = self.suggested_difficulty() else {
             return;
         };
         if !Self::is_material_change(new_diff, self.last_suggested_difficulty) {
@@ -448,7 +941,8 @@ impl StratumV1Source {
     /// Re-test the latest hashrate when the cooldown expires, sending a still-
     /// material suggestion or else ending the throttle.
     async fn flush_suggest(&mut self, client_command_tx: &mpsc::Sender<ClientCommand>) {
-        let pending = Self::compute_suggested_difficulty(self.expected_hashrate)
+        let pending = self
+            .suggested_difficulty()
             .filter(|diff| Self::is_material_change(*diff, self.last_suggested_difficulty));
         match pending {
             Some(diff) => self.send_suggest(diff, client_command_tx).await,
@@ -479,6 +973,19 @@ impl StratumV1Source {
         }
     }
 
+    /// Suggest a difficulty the expected hashrate can meet, after the
+    /// scheduler found the pool's too high for it.
+    ///
+    /// Sent whatever the configured policy and the deadband: the pool is
+    /// already above any floor suggested, so the last suggestion either
+    /// never went out or was overridden.
+    async fn suggest_lower_difficulty(&mut self, client_command_tx: &mpsc::Sender<ClientCommand>) {
+        let Some(diff) = Self::compute_suggested_difficulty(self.expected_hashrate) else {
+            return;
+        };
+        self.send_suggest(diff, client_command_tx).await;
+    }
+
     /// Whether the suggest cooldown is still active at `now`.
     fn in_cooldown(&self, now: Instant) -> bool {
         self.cooldown_until.is_some_and(|until| now < until)
@@ -506,7 +1013,7 @@ impl StratumV1Source {
                                 break;
                             }
                         }
-                        SourceCommand::SubmitShare(_) => {
+                        SourceCommand::SubmitShare(_) | SourceCommand::LowerDifficulty => {
                             // No connection yet, drop silently.
                         }
                     }
@@ -519,11 +1026,23 @@ impl StratumV1Source {
 
         // Phase 2: connect with automatic reconnection.
         let mut backoff = ExponentialBackoff::new(Duration::from_secs(1), Duration::from_secs(60));
+        let mut failed_attempts: u32 = 0;
 
         loop {
             // Reset per-connection state so a fresh handshake starts clean.
             self.state = None;
             self.first_share_logged = false;
+            self.authorized_at = None;
+            self.first_job_seen = false;
+            self.first_job_due = None;
+            self.unanswered_shares.clear();
+            self.job_arrivals.clear();
+            self.difficulty_swings = SwingTracker::new();
+            self.job_intake = self.config.max_jobs_per_sec.map(JobIntake::new);
+            self.stats_tx.send_modify(|stats| {
+                stats.time_to_first_job = None;
+                stats.difficulty_oscillating = false;
+            });
 
             info!(pool = %self.config.url, "Connecting to pool");
 
@@ -531,16 +1050,49 @@ impl StratumV1Source {
             match self.connect_and_run().await {
                 ConnectOutcome::Shutdown => return Ok(()),
                 ConnectOutcome::Fatal(e) => {
-                    error!(error = %e, "Fatal pool error, not reconnecting");
+                    // A job can arrive before authorization; don't leave it
+                    // behind as if the pool were still there.
+                    if let Err(e) = self.event_tx.send(SourceEvent::ClearJobs).await {
+                        warn!(error = %e, "Failed to send ClearJobs");
+                    }
+                    if let Some(StratumError::AuthorizationFailed(detail)) = e.downcast_ref() {
+                        error!(
+                            pool = %self.config.url,
+                            user = %self.config.username,
+                            detail = %detail,
+                            "Pool rejected the worker's credentials, not reconnecting"
+                        );
+                        self.record_failure("authentication failed");
+                    } else {
+                        error!(error = %e, "Fatal pool error, not reconnecting");
+                        self.record_failure(&e.to_string());
+                    }
                     return Err(e);
                 }
                 ConnectOutcome::Disconnected => {
+                    self.reconnects.lost(Instant::now());
                     // Invalidate stale work from the dead connection.
                     if let Err(e) = self.event_tx.send(SourceEvent::ClearJobs).await {
                         warn!(error = %e, "Failed to send ClearJobs");
                     }
                     if connected_at.elapsed() >= STABLE_CONNECTION_THRESHOLD {
                         backoff.reset();
+                        failed_attempts = 0;
+                    } else {
+                        failed_attempts += 1;
+                    }
+                    if let Some(max) = self.config.max_failed_attempts
+                        && failed_attempts >= max
+                    {
+                        let reason =
+                            format!("gave up after {failed_attempts} failed connection attempts");
+                        error!(
+                            pool = %self.config.url,
+                            attempts = failed_attempts,
+                            "Pool unreachable, giving up"
+                        );
+                        self.record_failure(&reason);
+                        return Err(anyhow::anyhow!("pool {}: {reason}", self.config.url));
                     }
                     let delay = backoff.next_delay();
                     info!(
@@ -562,19 +1114,35 @@ impl StratumV1Source {
     /// until disconnect or shutdown, and returns the outcome.
     async fn connect_and_run(&mut self) -> ConnectOutcome {
         let (client_event_tx, mut client_event_rx) = mpsc::channel(100);
-        let (client_command_tx, client_command_rx) = mpsc::channel(100);
+        // Kept small: shares wait in `submit_queue`, where overflow is
+        // handled, rather than in the channel, where it is not.
+        let (client_command_tx, client_command_rx) = mpsc::channel(4);
+        let mut submit_queue = ShareQueue::new(SUBMIT_QUEUE_CAPACITY);
+        let mut drop_warned = false;
+        // Shares handed to the client that the pool hasn't answered.
+        let mut in_flight: usize = 0;
+
+        // A job held from a previous connection is not valid on this one.
+        self.pending_job = None;
+        self.job_window_until = None;
+        // A pool on the wrong network is warned about on each connection.
+        self.network_mismatch_warned = false;
 
         // Compute initial difficulty so the client can send it inline
         // during the handshake, before the first job arrives.
-        let initial_difficulty = Self::compute_suggested_difficulty(self.expected_hashrate);
+        let initial_difficulty = self.suggested_difficulty();
         self.last_suggested_difficulty = initial_difficulty;
         self.cooldown_until = None;
 
+        // The client outlives the daemon's shutdown signal so the flush
+        // can still submit through it; it stops when this attempt ends.
+        let client_shutdown = CancellationToken::new();
+        let _stop_client = client_shutdown.clone().drop_guard();
         let client = StratumV1Client::with_commands(
             self.config.clone(),
             client_event_tx,
             client_command_rx,
-            self.shutdown.clone(),
+            client_shutdown,
             initial_difficulty,
         );
 
@@ -598,21 +1166,26 @@ impl StratumV1Source {
 
         let client_handle = tokio::spawn(async move { client.run_with_transport(transport).await });
 
-        // Main event loop
-        loop {
-            // Copied out so the cooldown branch captures the value, not `self`.
+        // Main event loop, until the client exits or shutdown is requested
+        let shutdown = loop {
+            // Copied out so the timer branches capture the values, not `self`.
             let cooldown_until = self.cooldown_until;
+            let job_window_until = self.job_window_until;
+            let first_job_due = self.first_job_due;
             tokio::select! {
                 event_opt = client_event_rx.recv() => {
                     match event_opt {
                         Some(event) => {
+                            if answers_share(&event) {
+                                in_flight = in_flight.saturating_sub(1);
+                            }
                             if let Err(e) = self.handle_client_event(event).await {
                                 warn!(error = %e, "Error handling client event");
                             }
                         }
                         None => {
                             // Client task exited; check why below.
-                            break;
+                            break false;
                         }
                     }
                 }
@@ -620,31 +1193,30 @@ impl StratumV1Source {
                 Some(cmd) = self.command_rx.recv() => {
                     match cmd {
                         SourceCommand::SubmitShare(share) => {
-                            debug!(
-                                pool = %self.name(),
-                                job_id = %share.job_id,
-                                nonce = format!("{:#x}", share.nonce),
-                                "Submitting share"
-                            );
-
-                            match self.share_to_submit_params(share) {
-                                Ok(submit_params) => {
-                                    if let Err(e) = client_command_tx.send(
-                                        ClientCommand::SubmitShare(submit_params)
-                                    ).await {
-                                        warn!(error = %e, "Failed to send share to client");
-                                    }
-                                }
-                                Err(e) => {
-                                    warn!(error = %e, "Failed to convert share");
-                                }
-                            }
+                            self.queue_share(share, &mut submit_queue, &mut drop_warned);
                         }
 
                         SourceCommand::UpdateHashRate(rate) => {
                             self.expected_hashrate = rate;
                             self.maybe_suggest_difficulty(&client_command_tx).await;
                         }
+
+                        SourceCommand::LowerDifficulty => {
+                            self.suggest_lower_difficulty(&client_command_tx).await;
+                        }
+                    }
+                }
+
+                // Feed queued shares to the client as it makes room.
+                permit = client_command_tx.reserve(), if !submit_queue.is_empty() => {
+                    match permit {
+                        Ok(permit) => {
+                            if let Some((params, solves_block)) = submit_queue.pop_marked() {
+                                permit.send(ClientCommand::SubmitShare { params, solves_block });
+                                in_flight += 1;
+                            }
+                        }
+                        Err(e) => warn!(error = %e, "Failed to send share to client"),
                     }
                 }
 
@@ -658,10 +1230,48 @@ impl StratumV1Source {
                     self.flush_suggest(&client_command_tx).await;
                 }
 
-                _ = self.shutdown.cancelled() => {
-                    return ConnectOutcome::Shutdown;
+                // Debounce window end: forward the latest held job.
+                _ = async {
+                    match job_window_until {
+                        Some(until) => time::sleep_until(until).await,
+                        None => future::pending().await,
+                    }
+                }, if job_window_until.is_some() => {
+                    if let Err(e) = self.flush_job_update().await {
+                        warn!(error = %e, "Error forwarding held job");
+                    }
+                }
+
+                // Authorized, but still no job to mine: say so while
+                // waiting, not only once one turns up.
+                _ = async {
+                    match first_job_due {
+                        Some(due) => time::sleep_until(due).await,
+                        None => future::pending().await,
+                    }
+                }, if first_job_due.is_some() => {
+                    self.first_job_due = None;
+                    warn!(
+                        pool = %self.config.url,
+                        waited_secs = SLOW_FIRST_JOB_THRESHOLD.as_secs(),
+                        "Pool has sent no job since authorize"
+                    );
                 }
+
+                _ = self.shutdown.cancelled() => break true,
             }
+        };
+
+        if shutdown {
+            self.flush_shares(
+                submit_queue,
+                client_command_tx,
+                &mut client_event_rx,
+                in_flight,
+                drop_warned,
+            )
+            .await;
+            return ConnectOutcome::Shutdown;
         }
 
         // Client task exited -- determine outcome from its return value.
@@ -681,6 +1291,136 @@ impl StratumV1Source {
         }
     }
 
+    /// Convert a share from the scheduler and queue it for the client,
+    /// unless its job is too old to be accepted.
+    fn queue_share(
+        &mut self,
+        share: Share,
+        submit_queue: &mut ShareQueue<SubmitParams>,
+        drop_warned: &mut bool,
+    ) {
+        if self.is_stale(&share) {
+            self.record_stale_avoided(&share);
+            return;
+        }
+        debug!(
+            pool = %self.name(),
+            job_id = %share.job_id,
+            nonce = format!("{:#x}", share.nonce),
+            "Submitting share"
+        );
+
+        self.track_unanswered(&share);
+        let solves_block = share.solves_block;
+        match self.share_to_submit_params(share) {
+            Ok(submit_params) => {
+                if submit_queue.push(submit_params, solves_block) {
+                    self.record_share_dropped(drop_warned);
+                }
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to convert share");
+            }
+        }
+    }
+
+    /// Give the pool up to [`PoolConfig::shutdown_flush`] to take the
+    /// shares still queued or awaiting its answer, then count what is
+    /// left as dropped.
+    ///
+    /// Closing the client's command channel once the queue is empty lets
+    /// the client stop as soon as its last share is answered, which ends
+    /// the flush early.
+    async fn flush_shares(
+        &mut self,
+        mut submit_queue: ShareQueue<SubmitParams>,
+        client_command_tx: mpsc::Sender<ClientCommand>,
+        client_event_rx: &mut mpsc::Receiver<ClientEvent>,
+        mut in_flight: usize,
+        mut drop_warned: bool,
+    ) {
+        // Shares the scheduler sent before it stopped.
+        while let Ok(cmd) = self.command_rx.try_recv() {
+            if let SourceCommand::SubmitShare(share) = cmd {
+                self.queue_share(share, &mut submit_queue, &mut drop_warned);
+            }
+        }
+        if submit_queue.is_empty() && in_flight == 0 {
+            return;
+        }
+
+        let grace = self.config.shutdown_flush;
+        debug!(
+            pool = %self.config.url,
+            queued = submit_queue.len(),
+            in_flight,
+            grace_ms = grace.as_millis() as u64,
+            "Flushing shares before disconnecting"
+        );
+        let deadline = time::sleep(grace);
+        tokio::pin!(deadline);
+        let mut client_command_tx = Some(client_command_tx);
+        let mut timed_out = false;
+        loop {
+            if submit_queue.is_empty() {
+                client_command_tx = None;
+            }
+            tokio::select! {
+                event = client_event_rx.recv() => {
+                    // Closed: the client is done, or the pool went away.
+                    let Some(event) = event else { break };
+                    if answers_share(&event) {
+                        in_flight = in_flight.saturating_sub(1);
+                    }
+                    if let Err(e) = self.handle_client_event(event).await {
+                        warn!(error = %e, "Error handling client event");
+                    }
+                }
+                permit = async {
+                    match &client_command_tx {
+                        Some(tx) => tx.reserve().await,
+                        None => future::pending().await,
+                    }
+                }, if !submit_queue.is_empty() => {
+                    match permit {
+                        Ok(permit) => {
+                            if let Some((params, solves_block)) = submit_queue.pop_marked() {
+                                permit.send(ClientCommand::SubmitShare { params, solves_block });
+                                in_flight += 1;
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                }
+                _ = &mut deadline => {
+                    timed_out = true;
+                    break;
+                }
+            }
+        }
+
+        let dropped = submit_queue.len() + in_flight;
+        if dropped == 0 {
+            debug!(pool = %self.config.url, "Shares flushed");
+            return;
+        }
+        self.stats_tx
+            .send_modify(|stats| stats.shares_dropped += dropped as u64);
+        warn!(
+            pool = %self.config.url,
+            dropped,
+            timed_out,
+            grace_ms = grace.as_millis() as u64,
+            "Shares not taken by the pool before shutdown, dropping"
+        );
+    }
+
+    /// Publish why the source stopped trying to reach the pool.
+    fn record_failure(&self, reason: &str) {
+        self.stats_tx
+            .send_modify(|stats| stats.failure = Some(reason.to_string()));
+    }
+
     /// Wait for the given duration, draining commands in the meantime.
     ///
     /// Returns `true` if shutdown was requested during the wait.
@@ -698,7 +1438,7 @@ impl StratumV1Source {
                         SourceCommand::UpdateHashRate(rate) => {
                             self.expected_hashrate = rate;
                         }
-                        SourceCommand::SubmitShare(_) => {
+                        SourceCommand::SubmitShare(_) | SourceCommand::LowerDifficulty => {
                             // No connection, drop silently.
                         }
                     }
@@ -714,16 +1454,19 @@ impl StratumV1Source {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api_client::types::SourceJob;
     use crate::asic::bm13xx::test_data::esp_miner_job::{
         POOL_SHARE_DIFFICULTY_INT, STRATUM_EXTRANONCE1, STRATUM_EXTRANONCE2_SIZE, VERSION_MASK,
         notify, submit,
     };
     use crate::asic::bm13xx::test_data::stratum_json;
+    use crate::clock::FakeClock;
     use crate::job_source::Extranonce2;
     use crate::stratum_v1::{
         JobNotification, JsonRpcMessage, MockConnector, MockTransport, MockTransportHandle,
         StratumResult, Transport,
     };
+    use bitcoin::Network;
     use bitcoin::block::Version;
     use serde_json::json;
 
@@ -784,6 +1527,68 @@ mod tests {
         source
     }
 
+    /// The job dump shows what the pool sent, field for field.
+    #[test]
+    fn job_dump_matches_the_notify() {
+        let extranonce1 = hex::decode(STRATUM_EXTRANONCE1).unwrap();
+        let source = source_with_state(
+            extranonce1,
+            STRATUM_EXTRANONCE2_SIZE,
+            Some(POOL_SHARE_DIFFICULTY_INT),
+            Some(VERSION_MASK),
+        );
+        let json: serde_json::Value = serde_json::from_str(stratum_json::MINING_NOTIFY).unwrap();
+        let params = json["params"].as_array().unwrap();
+        let job = JobNotification::from_stratum_params(params).unwrap();
+        let dump = SourceJob::from(&source.job_to_template(job).unwrap());
+
+        let param = |i: usize| params[i].as_str().unwrap();
+        assert_eq!(dump.job_id, param(0));
+        assert_eq!(dump.prev_blockhash, notify::PREV_BLOCKHASH.to_string());
+        assert_eq!(dump.version, param(5));
+        assert_eq!(dump.nbits, param(6));
+        assert_eq!(dump.ntime, u32::from_str_radix(param(7), 16).unwrap());
+        assert_eq!(dump.difficulty, POOL_SHARE_DIFFICULTY_INT as f64);
+
+        let coinbase = dump.coinbase.expect("pool jobs have coinbase parts");
+        assert_eq!(coinbase.coinbase1, param(2));
+        assert_eq!(coinbase.extranonce1, STRATUM_EXTRANONCE1);
+        assert_eq!(coinbase.extranonce2_size as usize, STRATUM_EXTRANONCE2_SIZE);
+        assert_eq!(coinbase.coinbase2, param(3));
+        let branches: Vec<&str> = params[4]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b.as_str().unwrap())
+            .collect();
+        assert!(!branches.is_empty());
+        assert_eq!(coinbase.merkle_branches, branches);
+    }
+
+    /// Jobs allow ntime rolling up to the configured window, which is
+    /// never wider than the pool's.
+    #[test]
+    fn job_rolling_window_follows_the_config_within_the_pool_limit() {
+        let max_time = |ntime_roll: Duration| {
+            let mut source = source_with_state(
+                hex::decode(STRATUM_EXTRANONCE1).unwrap(),
+                STRATUM_EXTRANONCE2_SIZE,
+                Some(POOL_SHARE_DIFFICULTY_INT),
+                Some(VERSION_MASK),
+            );
+            source.config.ntime_roll = ntime_roll;
+            let params: serde_json::Value = job_params("job");
+            let job = JobNotification::from_stratum_params(params.as_array().unwrap()).unwrap();
+            let template = source.job_to_template(job).unwrap();
+            template.max_time - template.time
+        };
+
+        assert_eq!(max_time(PoolConfig::MAX_NTIME_ROLL), 7000);
+        assert_eq!(max_time(Duration::from_secs(600)), 600);
+        assert_eq!(max_time(Duration::ZERO), 0);
+        assert_eq!(max_time(Duration::from_secs(86_400)), 7000);
+    }
+
     /// Test job_to_template with real capture data from esp-miner.
     ///
     /// Uses the Bitaxe Gamma capture that produced an accepted share at
@@ -914,6 +1719,32 @@ mod tests {
         );
     }
 
+    /// The template's EN2 range is exactly the space the pool negotiated.
+    #[test]
+    fn job_to_template_rolls_within_the_negotiated_extranonce2_size() {
+        let source = source_with_state(vec![0x08, 0x00, 0x00, 0x02], 2, None, None);
+        let params = json!([
+            "jobid",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "aa",
+            "bb",
+            [],
+            "20000000",
+            "1d00ffff",
+            "5a5a5a5a",
+            false
+        ]);
+
+        let job = JobNotification::from_stratum_params(params.as_array().unwrap()).unwrap();
+        let template = source.job_to_template(job).unwrap();
+
+        let MerkleRootKind::Computed(merkle) = &template.merkle_root else {
+            panic!("expected a computed merkle root");
+        };
+        assert_eq!(merkle.extranonce2_range, Extranonce2Range::new(2).unwrap());
+        assert_eq!(merkle.extranonce2_range.max, 0xffff);
+    }
+
     /// Test job_to_template uses default difficulty when not set.
     #[test]
     fn test_job_to_template_default_difficulty() {
@@ -949,14 +1780,176 @@ mod tests {
         );
     }
 
-    /// Test share_to_submit_params with real capture data.
-    ///
-    /// Converts the share found by the Bitaxe Gamma back to Stratum format
-    /// and validates against the actual mining.submit from the capture.
-    #[test]
-    fn test_share_to_submit_params_with_capture_data() {
-        let extranonce1 = hex::decode(STRATUM_EXTRANONCE1).unwrap();
-        let source = source_with_state(
+    /// Build a notify with the given nbits, for difficulty-floor tests.
+    fn job_with_bits(nbits: &str) -> JobNotification {
+        let params = json!([
+            "jobid",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "aa",
+            "bb",
+            [],
+            "20000000",
+            nbits,
+            "5a5a5a5a",
+            false
+        ]);
+        JobNotification::from_stratum_params(params.as_array().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn regtest_jobs_mine_against_trivial_targets() {
+        let extranonce1 = hex::decode(STRATUM_EXTRANONCE1).unwrap();
+        let mut source = source_with_state(extranonce1, STRATUM_EXTRANONCE2_SIZE, Some(1), None);
+        source.config.network = Network::Regtest;
+
+        let job = job_with_bits("207fffff");
+        source.check_network(Target::from_compact(job.nbits));
+        assert!(!source.network_mismatch_warned);
+        let template = source.job_to_template(job).unwrap();
+
+        // Far below difficulty 1: almost any hash solves a regtest block.
+        let network_difficulty = Difficulty::from_target(template.target());
+        assert!(network_difficulty.as_f64() < 1e-9, "{network_difficulty}");
+        let easy_hash = hash_at(Difficulty::from_f64(1e-6).to_target());
+        assert!(template.target().is_met_by(easy_hash));
+        // The pool's difficulty-1 shares are harder than a block here;
+        // block solutions must still count as shares.
+        assert_eq!(template.share_target, template.target());
+        assert!(template.share_target.is_met_by(easy_hash));
+    }
+
+    #[test]
+    fn jobs_from_another_network_are_flagged() {
+        let extranonce1 = hex::decode(STRATUM_EXTRANONCE1).unwrap();
+        let mut source = source_with_state(extranonce1, STRATUM_EXTRANONCE2_SIZE, Some(1), None);
+        assert_eq!(source.config.network, Network::Bitcoin);
+
+        // Mainnet jobs are fine on mainnet.
+        source.check_network(Target::from_compact(job_with_bits("1703a30c").nbits));
+        assert!(!source.network_mismatch_warned);
+
+        // A regtest job can't be from mainnet.
+        source.check_network(Target::from_compact(job_with_bits("207fffff").nbits));
+        assert!(source.network_mismatch_warned);
+    }
+
+    #[tokio::test]
+    async fn share_log_threshold_counts_small_shares_without_logging() {
+        let extranonce1 = hex::decode(STRATUM_EXTRANONCE1).unwrap();
+        let mut source = source_with_state(extranonce1, STRATUM_EXTRANONCE2_SIZE, Some(100), None);
+        source.config.log_share_difficulty = Difficulty::from_si("1M");
+        // Past the first share, which is always logged.
+        source.first_share_logged = true;
+        let stats = source.stats();
+
+        let share = |nonce, difficulty: u64| Share {
+            job_id: "job-1".into(),
+            nonce,
+            time: 0,
+            version: Version::from_consensus(0x20000000),
+            extranonce2: None,
+            solves_block: false,
+            difficulty: Difficulty::from(difficulty),
+        };
+        source.track_unanswered(&share(0x11, 2_048));
+        source.track_unanswered(&share(0x22, 5_000_000));
+
+        let small = crate::tracing::capture_logs(async {
+            source
+                .handle_client_event(ClientEvent::ShareAccepted {
+                    job_id: "job-1".into(),
+                    nonce: 0x11,
+                })
+                .await
+                .unwrap();
+        })
+        .await;
+        assert!(!small.contains("Share accepted"), "logged: {small}");
+        assert_eq!(stats.borrow().shares_accepted, 1);
+
+        let big = crate::tracing::capture_logs(async {
+            source
+                .handle_client_event(ClientEvent::ShareAccepted {
+                    job_id: "job-1".into(),
+                    nonce: 0x22,
+                })
+                .await
+                .unwrap();
+        })
+        .await;
+        assert!(big.contains("Share accepted"), "not logged: {big}");
+        assert!(big.contains("difficulty=5M"), "{big}");
+        assert_eq!(stats.borrow().shares_accepted, 2);
+        assert!(source.unanswered_shares.is_empty());
+    }
+
+    /// A hash sitting exactly on `target`.
+    fn hash_at(target: Target) -> bitcoin::BlockHash {
+        use bitcoin::hashes::Hash;
+        bitcoin::BlockHash::from_byte_array(target.to_le_bytes())
+    }
+
+    #[test]
+    fn difficulty_floor_withholds_sub_floor_shares() {
+        let extranonce1 = hex::decode(STRATUM_EXTRANONCE1).unwrap();
+        let mut source = source_with_state(extranonce1, STRATUM_EXTRANONCE2_SIZE, Some(100), None);
+        source.config.min_difficulty = Some(1000.0);
+
+        // Mainnet-scale nbits: network difficulty far above the floor.
+        let job = job_with_bits("1703a30c");
+        let network_target = Target::from_compact(job.nbits);
+        let template = source.job_to_template(job).unwrap();
+
+        assert_eq!(template.share_target, Difficulty::from(1000).to_target());
+        // Meets the pool's 100 but not the floor: withheld.
+        assert!(
+            !template
+                .share_target
+                .is_met_by(hash_at(Difficulty::from(500).to_target()))
+        );
+        // Meets the floor: submitted.
+        assert!(
+            template
+                .share_target
+                .is_met_by(hash_at(Difficulty::from(1000).to_target()))
+        );
+        // A block-solving share: submitted.
+        assert!(template.share_target.is_met_by(hash_at(network_target)));
+    }
+
+    #[test]
+    fn difficulty_floor_never_exceeds_network_difficulty() {
+        let extranonce1 = hex::decode(STRATUM_EXTRANONCE1).unwrap();
+        let mut source = source_with_state(extranonce1, STRATUM_EXTRANONCE2_SIZE, None, None);
+        source.config.min_difficulty = Some(1_000_000.0);
+
+        // Network difficulty 1 (regtest-like), well below the floor.
+        let job = job_with_bits("1d00ffff");
+        let network_target = Target::from_compact(job.nbits);
+        let template = source.job_to_template(job).unwrap();
+
+        assert_eq!(template.share_target, network_target);
+        assert!(template.share_target.is_met_by(hash_at(network_target)));
+    }
+
+    #[test]
+    fn difficulty_floor_leaves_higher_pool_difficulty_alone() {
+        let extranonce1 = hex::decode(STRATUM_EXTRANONCE1).unwrap();
+        let mut source = source_with_state(extranonce1, STRATUM_EXTRANONCE2_SIZE, Some(5000), None);
+        source.config.min_difficulty = Some(1000.0);
+
+        let template = source.job_to_template(job_with_bits("1703a30c")).unwrap();
+        assert_eq!(template.share_target, Difficulty::from(5000).to_target());
+    }
+
+    /// Test share_to_submit_params with real capture data.
+    ///
+    /// Converts the share found by the Bitaxe Gamma back to Stratum format
+    /// and validates against the actual mining.submit from the capture.
+    #[test]
+    fn test_share_to_submit_params_with_capture_data() {
+        let extranonce1 = hex::decode(STRATUM_EXTRANONCE1).unwrap();
+        let source = source_with_state(
             extranonce1,
             STRATUM_EXTRANONCE2_SIZE,
             Some(POOL_SHARE_DIFFICULTY_INT),
@@ -973,6 +1966,8 @@ mod tests {
             time: *submit::NTIME,
             version: full_version,
             extranonce2: Some(extranonce2_from_bytes(&*submit::EXTRANONCE2)),
+            solves_block: false,
+            difficulty: Difficulty::from(1_u64),
         };
 
         // Convert to SubmitParams
@@ -1027,6 +2022,8 @@ mod tests {
             time: 0x65432100,
             version: Version::from_consensus(0x20000000),
             extranonce2: Some(extranonce2_from_bytes(&[0xde, 0xad, 0xbe, 0xef])),
+            solves_block: false,
+            difficulty: Difficulty::from(1_u64),
         };
 
         let params = source.share_to_submit_params(share).unwrap();
@@ -1055,6 +2052,8 @@ mod tests {
             time: 0x65432100,
             version: Version::from_consensus(0x20000000),
             extranonce2: None, // Not provided
+            solves_block: false,
+            difficulty: Difficulty::from(1_u64),
         };
 
         let params = source.share_to_submit_params(share).unwrap();
@@ -1090,6 +2089,8 @@ mod tests {
             time: *submit::NTIME,
             version: full_version,
             extranonce2: Some(extranonce2_from_bytes(&*submit::EXTRANONCE2)),
+            solves_block: false,
+            difficulty: Difficulty::from(1_u64),
         };
 
         // Convert to SubmitParams and then to JSON
@@ -1554,22 +2555,31 @@ mod tests {
         });
     }
 
+    /// Params of a minimal mining.notify.
+    fn job_params(job_id: &str) -> serde_json::Value {
+        json!([
+            job_id,
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "aa",
+            "bb",
+            [],
+            "20000000",
+            "1d00ffff",
+            "5a5a5a5a",
+            true
+        ])
+    }
+
     /// Build a minimal mining.notify notification.
     fn job_notification(job_id: &str) -> JsonRpcMessage {
-        JsonRpcMessage::notification(
-            "mining.notify",
-            json!([
-                job_id,
-                "0000000000000000000000000000000000000000000000000000000000000000",
-                "aa",
-                "bb",
-                [],
-                "20000000",
-                "1d00ffff",
-                "5a5a5a5a",
-                true
-            ]),
-        )
+        JsonRpcMessage::notification("mining.notify", job_params(job_id))
+    }
+
+    /// Like [`job_notification`], with `clean_jobs` unset.
+    fn update_notification(job_id: &str) -> JsonRpcMessage {
+        let mut params = job_params(job_id);
+        params[8] = json!(false);
+        JsonRpcMessage::notification("mining.notify", params)
     }
 
     /// Create a StratumV1Source wired to a mock transport channel.
@@ -1581,6 +2591,19 @@ mod tests {
         mpsc::Sender<SourceCommand>,
         mpsc::Sender<MockTransport>,
         CancellationToken,
+    ) {
+        source_with_suggest_policy(SuggestDifficulty::Auto)
+    }
+
+    /// Like [`source_with_mock_transports`], with a chosen suggest policy.
+    fn source_with_suggest_policy(
+        suggest_difficulty: SuggestDifficulty,
+    ) -> (
+        StratumV1Source,
+        mpsc::Receiver<SourceEvent>,
+        mpsc::Sender<SourceCommand>,
+        mpsc::Sender<MockTransport>,
+        CancellationToken,
     ) {
         let (event_tx, event_rx) = mpsc::channel(100);
         let (command_tx, command_rx) = mpsc::channel(100);
@@ -1592,6 +2615,7 @@ mod tests {
             username: "testworker".to_string(),
             password: "x".to_string(),
             user_agent: "test".to_string(),
+            suggest_difficulty,
             ..Default::default()
         };
 
@@ -1606,146 +2630,1112 @@ mod tests {
         (source, event_rx, command_tx, mock_tx, shutdown)
     }
 
+    /// Respond to mining.authorize with success.
+    async fn do_authorize(handle: &mut MockTransportHandle) {
+        let msg = handle.recv().await;
+        assert_eq!(msg.method(), Some("mining.authorize"));
+        handle.send(JsonRpcMessage::Response {
+            id: msg.id().unwrap(),
+            result: Some(json!(true)),
+            error: None,
+        });
+    }
+
     #[tokio::test(start_paused = true)]
-    async fn reconnects_after_disconnect() {
-        let (source, mut event_rx, command_tx, mock_tx, shutdown) = source_with_mock_transports();
+    async fn slow_pool_drops_oldest_shares_but_keeps_block_solutions() {
+        const FLOOD: u32 = 200;
+        const BLOCK_NONCE: u32 = 0xb10c;
 
-        let (transport1, mut handle1) = MockTransport::pair();
-        let (transport2, mut handle2) = MockTransport::pair();
-        mock_tx.send(transport1).await.unwrap();
-        mock_tx.send(transport2).await.unwrap();
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) =
+            source_with_suggest_policy(SuggestDifficulty::Off);
+        let stats = source.stats();
 
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
         let source_handle = tokio::spawn(source.run());
-
-        // Trigger Phase 1 -> Phase 2 with a positive hashrate.
         command_tx
-            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
-                500.0,
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                1.0,
             )))
             .await
             .unwrap();
 
-        // First connection: handshake, receive one job, then disconnect.
-        do_handshake(&mut handle1).await;
-        handle1.send(job_notification("job-1"));
+        do_configure_and_subscribe(&mut handle).await;
+        do_authorize(&mut handle).await;
+        handle.send(job_notification("job-1"));
+        assert!(matches!(
+            event_rx.recv().await,
+            Some(SourceEvent::ReplaceJob(_))
+        ));
+
+        let share = |nonce, solves_block| Share {
+            job_id: "job-1".into(),
+            nonce,
+            time: 0,
+            version: Version::from_consensus(0x20000000),
+            extranonce2: None,
+            solves_block,
+            difficulty: Difficulty::from(1_u64),
+        };
 
-        let event = event_rx.recv().await.unwrap();
-        assert!(
-            matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == "job-1"),
-            "expected ReplaceJob(job-1), got {event:?}",
-        );
+        // The pool sits on the first submit and never answers. Flood with
+        // ordinary shares, with a block solution in the middle.
+        for nonce in 0..FLOOD {
+            if nonce == FLOOD / 2 {
+                command_tx
+                    .send(SourceCommand::SubmitShare(share(BLOCK_NONCE, true)))
+                    .await
+                    .unwrap();
+            }
+            command_tx
+                .send(SourceCommand::SubmitShare(share(nonce, false)))
+                .await
+                .unwrap();
+        }
+        time::sleep(Duration::from_millis(100)).await;
+
+        // Everything not in flight, in the client channel, or in the queue
+        // was dropped, and the count says so.
+        let dropped = stats.borrow().shares_dropped;
+        let held = u64::from(FLOOD) + 1 - dropped;
+        assert!(held <= SUBMIT_QUEUE_CAPACITY as u64 + 5, "held {held}");
+
+        // Let the pool catch up and collect what actually reaches it.
+        let mut submitted = Vec::new();
+        while let Ok(msg) = time::timeout(Duration::from_secs(1), handle.recv()).await {
+            let JsonRpcMessage::Request {
+                id: Some(id),
+                method,
+                params,
+            } = msg
+            else {
+                panic!("expected request, got {msg:?}");
+            };
+            assert_eq!(method, "mining.submit");
+            let nonce = u32::from_str_radix(params[4].as_str().unwrap(), 16).unwrap();
+            submitted.push(nonce);
+            handle.send(JsonRpcMessage::Response {
+                id,
+                result: Some(json!(true)),
+                error: None,
+            });
+        }
 
-        // Drop the handle to simulate pool going away.
-        drop(handle1);
+        assert_eq!(submitted.len() as u64, held);
+        assert!(submitted.contains(&BLOCK_NONCE), "block share dropped");
+        assert_eq!(submitted.last(), Some(&(FLOOD - 1)), "newest share kept");
 
-        let event = event_rx.recv().await.unwrap();
-        assert!(
-            matches!(event, SourceEvent::ClearJobs),
-            "expected ClearJobs after disconnect, got {event:?}",
-        );
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
 
-        // Advance past the backoff (max initial is 1s).
-        time::advance(Duration::from_secs(2)).await;
+    /// A source connected through the handshake with a job from the mock
+    /// pool, flushing for `grace` at shutdown.
+    async fn source_with_job_and_flush_grace(
+        grace: Duration,
+    ) -> (
+        tokio::task::JoinHandle<Result<()>>,
+        watch::Receiver<SourceStats>,
+        mpsc::Sender<SourceCommand>,
+        MockTransportHandle,
+        CancellationToken,
+        mpsc::Receiver<SourceEvent>,
+    ) {
+        let (mut source, mut event_rx, command_tx, mock_tx, shutdown) =
+            source_with_suggest_policy(SuggestDifficulty::Off);
+        source.config.shutdown_flush = grace;
+        let stats = source.stats();
 
-        // Second connection: handshake, receive a job.
-        do_handshake(&mut handle2).await;
-        handle2.send(job_notification("job-2"));
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+        let source_handle = tokio::spawn(source.run());
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                1.0,
+            )))
+            .await
+            .unwrap();
 
-        let event = event_rx.recv().await.unwrap();
-        assert!(
-            matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == "job-2"),
-            "expected ReplaceJob(job-2), got {event:?}",
-        );
+        do_configure_and_subscribe(&mut handle).await;
+        do_authorize(&mut handle).await;
+        handle.send(job_notification("job-1"));
+        assert!(matches!(
+            event_rx.recv().await,
+            Some(SourceEvent::ReplaceJob(_))
+        ));
+        (source_handle, stats, command_tx, handle, shutdown, event_rx)
+    }
+
+    fn share_on_job_1(nonce: u32) -> SourceCommand {
+        SourceCommand::SubmitShare(Share {
+            job_id: "job-1".into(),
+            nonce,
+            time: 0,
+            version: Version::from_consensus(0x20000000),
+            extranonce2: None,
+            solves_block: false,
+            difficulty: Difficulty::from(1_u64),
+        })
+    }
 
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_flush_gives_up_on_an_unresponsive_pool_at_the_grace_period() {
+        const GRACE: Duration = Duration::from_secs(2);
+        let (source_handle, stats, command_tx, mut handle, shutdown, _event_rx) =
+            source_with_job_and_flush_grace(GRACE).await;
+
+        for nonce in 1..=3 {
+            command_tx.send(share_on_job_1(nonce)).await.unwrap();
+        }
+        // The pool takes the first submit and never answers it.
+        assert_eq!(handle.recv().await.method(), Some("mining.submit"));
+
+        let stopping = Instant::now();
         shutdown.cancel();
         source_handle.await.unwrap().unwrap();
+
+        assert_eq!(stopping.elapsed(), GRACE);
+        let stats = stats.borrow();
+        assert_eq!(stats.shares_dropped, 3);
+        assert_eq!(stats.shares_accepted, 0);
     }
 
     #[tokio::test(start_paused = true)]
-    async fn backoff_escalates_across_disconnects() {
-        let (source, mut event_rx, command_tx, mock_tx, shutdown) = source_with_mock_transports();
+    async fn shutdown_flush_submits_queued_shares_to_a_responsive_pool() {
+        const GRACE: Duration = Duration::from_secs(2);
+        let (source_handle, stats, command_tx, mut handle, shutdown, _event_rx) =
+            source_with_job_and_flush_grace(GRACE).await;
 
-        // Four transports that fail immediately (handle dropped).
-        for _ in 0..4 {
-            let (transport, handle) = MockTransport::pair();
-            drop(handle);
-            mock_tx.send(transport).await.unwrap();
+        for nonce in 1..=3 {
+            command_tx.send(share_on_job_1(nonce)).await.unwrap();
         }
+        let stopping = Instant::now();
+        shutdown.cancel();
 
-        let source_handle = tokio::spawn(source.run());
+        // Every share still reaches the pool after shutdown was asked for.
+        let mut submitted = Vec::new();
+        for _ in 0..3 {
+            let JsonRpcMessage::Request {
+                id: Some(id),
+                method,
+                params,
+            } = handle.recv().await
+            else {
+                panic!("expected a request");
+            };
+            assert_eq!(method, "mining.submit");
+            submitted.push(u32::from_str_radix(params[4].as_str().unwrap(), 16).unwrap());
+            handle.send(JsonRpcMessage::Response {
+                id,
+                result: Some(json!(true)),
+                error: None,
+            });
+        }
+        source_handle.await.unwrap().unwrap();
 
+        assert_eq!(submitted, [1, 2, 3]);
+        assert!(stopping.elapsed() < GRACE);
+        let stats = stats.borrow();
+        assert_eq!(stats.shares_accepted, 3);
+        assert_eq!(stats.shares_dropped, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shares_on_expired_jobs_are_withheld_except_blocks() {
+        const MAX_AGE: Duration = Duration::from_secs(60);
+
+        let (mut source, mut event_rx, command_tx, mock_tx, shutdown) =
+            source_with_suggest_policy(SuggestDifficulty::Off);
+        source.config.max_job_age = Some(MAX_AGE);
+        let stats = source.stats();
+
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+        let source_handle = tokio::spawn(source.run());
         command_tx
-            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
-                500.0,
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                1.0,
             )))
             .await
             .unwrap();
 
-        // 1st disconnect: nominal 1s, jittered to [0.5s, 1.0s).
-        let event = event_rx.recv().await.unwrap();
-        assert!(matches!(event, SourceEvent::ClearJobs));
-
-        // Advancing 0.4s is below the minimum (0.5s); no reconnect yet.
-        time::advance(Duration::from_millis(400)).await;
-        tokio::task::yield_now().await;
-        assert!(
-            event_rx.try_recv().is_err(),
-            "reconnected too soon after 1st disconnect",
-        );
-
-        // Advance past the maximum (1.0s total) to trigger reconnect.
-        time::advance(Duration::from_millis(600)).await;
-
-        // 2nd disconnect: nominal 2s, jittered to [1.0s, 2.0s).
-        let event = event_rx.recv().await.unwrap();
-        assert!(matches!(event, SourceEvent::ClearJobs));
+        do_configure_and_subscribe(&mut handle).await;
+        do_authorize(&mut handle).await;
+        handle.send(job_notification("job-1"));
+        assert!(matches!(
+            event_rx.recv().await,
+            Some(SourceEvent::ReplaceJob(_))
+        ));
+
+        let share = |nonce, solves_block| Share {
+            job_id: "job-1".into(),
+            nonce,
+            time: 0,
+            version: Version::from_consensus(0x20000000),
+            extranonce2: None,
+            solves_block,
+            difficulty: Difficulty::from(1_u64),
+        };
+        let submitted_nonce = async |handle: &mut MockTransportHandle| {
+            let msg = handle.recv().await;
+            assert_eq!(msg.method(), Some("mining.submit"));
+            handle.send(JsonRpcMessage::Response {
+                id: msg.id().unwrap(),
+                result: Some(json!(true)),
+                error: None,
+            });
+            let JsonRpcMessage::Request { params, .. } = msg else {
+                unreachable!()
+            };
+            u32::from_str_radix(params[4].as_str().unwrap(), 16).unwrap()
+        };
 
-        // Advancing 0.9s is below the minimum (1.0s); no reconnect yet.
-        time::advance(Duration::from_millis(900)).await;
-        tokio::task::yield_now().await;
-        assert!(
-            event_rx.try_recv().is_err(),
-            "reconnected too soon after 2nd disconnect",
-        );
+        // Within the window, shares go out.
+        command_tx
+            .send(SourceCommand::SubmitShare(share(1, false)))
+            .await
+            .unwrap();
+        assert_eq!(submitted_nonce(&mut handle).await, 1);
+
+        // Once job-1 is replaced and past it, ordinary shares are counted
+        // instead; the block share still goes out, and is the next thing
+        // the pool sees.
+        time::sleep(MAX_AGE + Duration::from_secs(1)).await;
+        handle.send(job_notification("job-2"));
+        assert!(matches!(
+            event_rx.recv().await,
+            Some(SourceEvent::ShareAccepted { .. })
+        ));
+        assert!(matches!(
+            event_rx.recv().await,
+            Some(SourceEvent::ReplaceJob(_))
+        ));
+        for (nonce, solves_block) in [(2, false), (3, true), (4, false)] {
+            command_tx
+                .send(SourceCommand::SubmitShare(share(nonce, solves_block)))
+                .await
+                .unwrap();
+        }
+        assert_eq!(submitted_nonce(&mut handle).await, 3);
+        time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(stats.borrow().shares_stale_avoided, 2);
 
-        // Advance past the maximum (2.0s total).
-        time::advance(Duration::from_millis(1100)).await;
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
 
-        // 3rd disconnect: nominal 4s, jittered to [2.0s, 4.0s).
-        let event = event_rx.recv().await.unwrap();
-        assert!(matches!(event, SourceEvent::ClearJobs));
+    fn share_on(job_id: &str) -> Share {
+        Share {
+            job_id: job_id.into(),
+            nonce: 0,
+            time: 0,
+            version: Version::from_consensus(0x20000000),
+            extranonce2: None,
+            solves_block: false,
+            difficulty: Difficulty::from(1_u64),
+        }
+    }
 
-        // Advancing 1.9s is below the minimum (2.0s); proves escalation.
-        time::advance(Duration::from_millis(1900)).await;
-        tokio::task::yield_now().await;
-        assert!(
-            event_rx.try_recv().is_err(),
-            "reconnected too soon after 3rd disconnect",
-        );
+    #[tokio::test(start_paused = true)]
+    async fn latest_job_never_expires() {
+        let mut source = throttle_test_source();
+        source.config.max_job_age = Some(Duration::from_secs(60));
+
+        // The pool sends nothing new for ten minutes: job-1 is still its
+        // work, and shares on it go out.
+        source.record_job_arrival("job-1");
+        time::advance(Duration::from_secs(600)).await;
+        assert!(!source.is_stale(&share_on("job-1")));
+
+        // Once replaced, its age counts against it.
+        source.record_job_arrival("job-2");
+        assert!(source.is_stale(&share_on("job-1")));
+        assert!(!source.is_stale(&share_on("job-2")));
+    }
 
-        shutdown.cancel();
-        source_handle.await.unwrap().unwrap();
+    #[tokio::test(start_paused = true)]
+    async fn resent_job_keeps_its_arrival_time() {
+        let mut source = throttle_test_source();
+        source.config.max_job_age = Some(Duration::from_secs(60));
+
+        // job-1 arrives, is replaced, then sent again 50 s later.
+        source.record_job_arrival("job-1");
+        time::advance(Duration::from_secs(45)).await;
+        source.record_job_arrival("job-2");
+        time::advance(Duration::from_secs(5)).await;
+        source.record_job_arrival("job-1");
+        assert!(!source.is_stale(&share_on("job-1")));
+
+        // Replaced again at 70 s, it counts its age from the first
+        // arrival and has expired.
+        time::advance(Duration::from_secs(20)).await;
+        source.record_job_arrival("job-3");
+        assert!(source.is_stale(&share_on("job-1")));
+        assert!(!source.is_stale(&share_on("job-2")));
     }
 
     #[tokio::test(start_paused = true)]
-    async fn fatal_error_stops_retrying() {
-        let (source, _event_rx, command_tx, mock_tx, _shutdown) = source_with_mock_transports();
+    async fn shares_on_jobs_evicted_from_the_history_are_stale() {
+        let (mut source, mut event_rx, command_tx, mock_tx, shutdown) =
+            source_with_suggest_policy(SuggestDifficulty::Off);
+        source.config.job_history = 2;
+        let stats = source.stats();
 
         let (transport, mut handle) = MockTransport::pair();
         mock_tx.send(transport).await.unwrap();
-
         let source_handle = tokio::spawn(source.run());
-
         command_tx
-            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
-                500.0,
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                1.0,
             )))
             .await
             .unwrap();
 
         do_configure_and_subscribe(&mut handle).await;
+        do_authorize(&mut handle).await;
+        for job_id in ["job-1", "job-2", "job-3"] {
+            handle.send(job_notification(job_id));
+            assert!(matches!(
+                event_rx.recv().await,
+                Some(SourceEvent::ReplaceJob(_))
+            ));
+        }
 
-        // mining.authorize -- reject
+        let share = |job_id: &str, nonce| Share {
+            job_id: job_id.into(),
+            nonce,
+            time: 0,
+            version: Version::from_consensus(0x20000000),
+            extranonce2: None,
+            solves_block: false,
+            difficulty: Difficulty::from(1_u64),
+        };
+
+        // job-1 fell out when job-3 arrived. Its share and one on a job
+        // never seen are counted, not submitted; a share on job-2 still
+        // goes out.
+        for share in [share("job-1", 1), share("job-0", 2), share("job-2", 3)] {
+            command_tx
+                .send(SourceCommand::SubmitShare(share))
+                .await
+                .unwrap();
+        }
+        let msg = handle.recv().await;
+        assert_eq!(msg.method(), Some("mining.submit"));
+        let JsonRpcMessage::Request { params, .. } = msg else {
+            unreachable!()
+        };
+        assert_eq!(params[1], "job-2");
+        time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(stats.borrow().shares_stale_avoided, 2);
+        assert!(handle.try_recv().is_none());
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn job_updates_coalesce_but_clean_jobs_preempt() {
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) =
+            source_with_suggest_policy(SuggestDifficulty::Off);
+        let debounce = PoolConfig::DEFAULT_JOB_DEBOUNCE;
+
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+        let source_handle = tokio::spawn(source.run());
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                1.0,
+            )))
+            .await
+            .unwrap();
+
+        do_configure_and_subscribe(&mut handle).await;
+        do_authorize(&mut handle).await;
+        handle.send(job_notification("job-1"));
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == "job-1"));
+
+        // A burst of updates inside the window is held...
+        for id in ["job-2", "job-3", "job-4"] {
+            handle.send(update_notification(id));
+        }
+        assert!(
+            time::timeout(debounce / 2, event_rx.recv()).await.is_err(),
+            "update forwarded inside the debounce window"
+        );
+
+        // ...and collapses to the latest once the window ends.
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::UpdateJob(ref t) if t.id == "job-4"));
+        assert!(time::timeout(debounce * 4, event_rx.recv()).await.is_err());
+
+        // With the window closed, the next update goes straight through.
+        handle.send(update_notification("job-5"));
+        let event = time::timeout(Duration::from_millis(1), event_rx.recv())
+            .await
+            .expect("update after a quiet period is not held")
+            .unwrap();
+        assert!(matches!(event, SourceEvent::UpdateJob(ref t) if t.id == "job-5"));
+
+        // A clean job preempts both the window and a held update, which is
+        // discarded rather than delivered after it.
+        handle.send(update_notification("job-6"));
+        handle.send(job_notification("job-7"));
+        let event = time::timeout(Duration::from_millis(1), event_rx.recv())
+            .await
+            .expect("clean job held by debounce")
+            .unwrap();
+        assert!(matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == "job-7"));
+        assert!(time::timeout(debounce * 4, event_rx.recv()).await.is_err());
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn notify_flood_is_capped_but_clean_jobs_apply() {
+        const LIMIT: u32 = 5;
+        let (event_tx, mut event_rx) = mpsc::channel(100);
+        let (command_tx, command_rx) = mpsc::channel(100);
+        let shutdown = CancellationToken::new();
+        let (mock_tx, mock_rx) = mpsc::channel(10);
+        let config = PoolConfig {
+            url: "stratum+tcp://test:3333".to_string(),
+            suggest_difficulty: SuggestDifficulty::Off,
+            // Forward every job taken in, so only the cap holds any back.
+            job_debounce: Duration::ZERO,
+            max_jobs_per_sec: Some(LIMIT),
+            ..Default::default()
+        };
+        let source = StratumV1Source::new(
+            config,
+            command_rx,
+            event_tx,
+            shutdown.clone(),
+            Box::new(MockConnector::new(mock_rx)),
+        );
+
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+        let logs = crate::tracing::capture_logs(async {
+            let source_handle = tokio::spawn(source.run());
+            command_tx
+                .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                    1.0,
+                )))
+                .await
+                .unwrap();
+            do_configure_and_subscribe(&mut handle).await;
+            do_authorize(&mut handle).await;
+
+            // A flood within one second: the clean job that opens it and
+            // four updates are taken, the rest dropped...
+            handle.send(job_notification("job-0"));
+            for i in 1..=100 {
+                handle.send(update_notification(&format!("job-{i}")));
+            }
+            // ...but a clean job in the middle of it still applies.
+            handle.send(job_notification("clean"));
+            time::sleep(Duration::from_millis(100)).await;
+            let mut ids = Vec::new();
+            while let Ok(event) = event_rx.try_recv() {
+                match event {
+                    SourceEvent::UpdateJob(t) | SourceEvent::ReplaceJob(t) => ids.push(t.id),
+                    _ => {}
+                }
+            }
+            assert_eq!(ids, ["job-0", "job-1", "job-2", "job-3", "job-4", "clean"]);
+
+            // After a quiet second, updates are taken again.
+            time::sleep(Duration::from_secs(2)).await;
+            handle.send(update_notification("after"));
+            let event = event_rx.recv().await.unwrap();
+            assert!(matches!(event, SourceEvent::UpdateJob(ref t) if t.id == "after"));
+
+            shutdown.cancel();
+            source_handle.await.unwrap().unwrap();
+        })
+        .await;
+        assert_eq!(logs.matches("Pool flooding job notifications").count(), 1);
+        assert!(logs.contains("back under the limit"), "{logs}");
+        assert!(logs.contains("dropped=96"), "{logs}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn new_prev_hash_counts_a_block_and_flushes_work() {
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) =
+            source_with_suggest_policy(SuggestDifficulty::Off);
+        let stats = source.stats();
+        let debounce = PoolConfig::DEFAULT_JOB_DEBOUNCE;
+
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+        let source_handle = tokio::spawn(source.run());
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                1.0,
+            )))
+            .await
+            .unwrap();
+
+        do_configure_and_subscribe(&mut handle).await;
+        do_authorize(&mut handle).await;
+        handle.send(job_notification("job-1"));
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == "job-1"));
+
+        // More work on the same block is an update, and no new block.
+        handle.send(update_notification("job-2"));
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::UpdateJob(ref t) if t.id == "job-2"));
+        assert_eq!(stats.borrow().network_blocks_seen, 0);
+        assert!(time::timeout(debounce * 4, event_rx.recv()).await.is_err());
+
+        // Each new previous block hash replaces work at once, even without
+        // clean_jobs, and an update held from the block before is dropped
+        // rather than sent after.
+        let notify = |job_id: &str, prev_hash: &str| {
+            let mut params = job_params(job_id);
+            params[1] = json!(prev_hash.repeat(32));
+            params[8] = json!(false);
+            JsonRpcMessage::notification("mining.notify", params)
+        };
+        let mut current = "00";
+        for (blocks, prev_hash) in [(1, "11"), (2, "22"), (3, "11")] {
+            // The first update goes straight through and opens the window.
+            handle.send(notify("update", current));
+            let event = event_rx.recv().await.unwrap();
+            assert!(matches!(event, SourceEvent::UpdateJob(ref t) if t.id == "update"));
+            handle.send(notify("held", current));
+            handle.send(notify(&format!("block-{blocks}"), prev_hash));
+            current = prev_hash;
+            let event = time::timeout(Duration::from_millis(1), event_rx.recv())
+                .await
+                .expect("job on a new block held by debounce")
+                .unwrap();
+            assert!(
+                matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == format!("block-{blocks}")),
+                "{event:?}"
+            );
+            assert_eq!(stats.borrow().network_blocks_seen, blocks);
+            assert!(time::timeout(debounce * 4, event_rx.recv()).await.is_err());
+        }
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fixed_suggestion_sent_and_ignored_by_pool() {
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) =
+            source_with_suggest_policy(SuggestDifficulty::Fixed(512.0));
+
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+        let source_handle = tokio::spawn(source.run());
+
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                1.0,
+            )))
+            .await
+            .unwrap();
+
+        do_configure_and_subscribe(&mut handle).await;
+        do_authorize(&mut handle).await;
+
+        // The configured value goes out regardless of hashrate.
+        let msg = handle.recv().await;
+        assert_eq!(msg.method(), Some("mining.suggest_difficulty"));
+        let JsonRpcMessage::Request { params, .. } = msg else {
+            panic!("expected request, got {msg:?}");
+        };
+        assert_eq!(params, json!([512]));
+
+        // The pool never replies or sets difficulty; the session carries on
+        // at the pool's default difficulty.
+        handle.send(job_notification("job-1"));
+        let event = event_rx.recv().await.unwrap();
+        let SourceEvent::ReplaceJob(template) = event else {
+            panic!("expected ReplaceJob, got {event:?}");
+        };
+        assert_eq!(template.id, "job-1");
+        assert_eq!(template.share_target, Difficulty::from(1).to_target());
+
+        // A hashrate change does not re-suggest a fixed value.
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                10.0,
+            )))
+            .await
+            .unwrap();
+        handle.send(job_notification("job-2"));
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == "job-2"));
+        assert!(handle.try_recv().is_none(), "unexpected re-suggestion");
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn suggestion_off_skips_suggest_difficulty() {
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) =
+            source_with_suggest_policy(SuggestDifficulty::Off);
+
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+        let source_handle = tokio::spawn(source.run());
+
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                1.0,
+            )))
+            .await
+            .unwrap();
+
+        do_configure_and_subscribe(&mut handle).await;
+        do_authorize(&mut handle).await;
+
+        handle.send(job_notification("job-1"));
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == "job-1"));
+        assert!(handle.try_recv().is_none(), "unexpected suggest_difficulty");
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn measures_time_to_first_job() {
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) = source_with_mock_transports();
+        let stats = source.stats();
+
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+        let source_handle = tokio::spawn(source.run());
+
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                1.0,
+            )))
+            .await
+            .unwrap();
+
+        do_configure_and_subscribe(&mut handle).await;
+        do_authorize(&mut handle).await;
+        let authorized_at = Instant::now();
+
+        // mining.suggest_difficulty, answered so the handshake completes.
+        let msg = handle.recv().await;
+        assert_eq!(msg.method(), Some("mining.suggest_difficulty"));
+        handle.send(JsonRpcMessage::Response {
+            id: msg.id().unwrap(),
+            result: Some(json!(true)),
+            error: None,
+        });
+
+        assert_eq!(stats.borrow().time_to_first_job, None);
+
+        time::sleep_until(authorized_at + Duration::from_secs(4)).await;
+        handle.send(job_notification("job-1"));
+        event_rx.recv().await.unwrap();
+
+        let measured = stats.borrow().time_to_first_job.unwrap();
+        assert!(
+            (Duration::from_secs(4)..Duration::from_millis(4100)).contains(&measured),
+            "measured {measured:?}"
+        );
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn missing_first_job_is_warned_about_while_waiting() {
+        let (source, _event_rx, command_tx, mock_tx, shutdown) = source_with_mock_transports();
+
+        let logs = crate::tracing::capture_logs(async {
+            let (transport, mut handle) = MockTransport::pair();
+            mock_tx.send(transport).await.unwrap();
+            let source_handle = tokio::spawn(source.run());
+            command_tx
+                .send(SourceCommand::UpdateHashRate(HashRate::from_terahashes(
+                    1.0,
+                )))
+                .await
+                .unwrap();
+
+            do_configure_and_subscribe(&mut handle).await;
+            do_authorize(&mut handle).await;
+            let msg = handle.recv().await;
+            assert_eq!(msg.method(), Some("mining.suggest_difficulty"));
+            handle.send(JsonRpcMessage::Response {
+                id: msg.id().unwrap(),
+                result: Some(json!(true)),
+                error: None,
+            });
+
+            // The pool never sends a job.
+            time::sleep(SLOW_FIRST_JOB_THRESHOLD * 3).await;
+            shutdown.cancel();
+            source_handle.await.unwrap().unwrap();
+        })
+        .await;
+
+        assert_eq!(
+            logs.matches("Pool has sent no job since authorize").count(),
+            1,
+            "{logs}"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_first_job_warns_past_threshold() {
+        let mut source = throttle_test_source();
+        let stats = source.stats();
+
+        assert!(!source.record_time_to_first_job(Duration::from_secs(1)));
+        assert_eq!(
+            stats.borrow().time_to_first_job,
+            Some(Duration::from_secs(1))
+        );
+
+        assert!(source.record_time_to_first_job(SLOW_FIRST_JOB_THRESHOLD));
+        assert_eq!(
+            stats.borrow().time_to_first_job,
+            Some(SLOW_FIRST_JOB_THRESHOLD)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn first_job_before_authorize_counts_as_immediate() {
+        let mut source = throttle_test_source();
+        let stats = source.stats();
+
+        // Job arrives while authorize is in flight; conversion fails for lack
+        // of protocol state, which doesn't matter here.
+        let _ = source
+            .handle_client_event(ClientEvent::NewJob(
+                JobNotification::from_stratum_params(job_params("early").as_array().unwrap())
+                    .unwrap(),
+            ))
+            .await;
+        assert_eq!(stats.borrow().time_to_first_job, None);
+
+        time::advance(Duration::from_secs(30)).await;
+        source
+            .handle_client_event(ClientEvent::Authorized)
+            .await
+            .unwrap();
+        assert_eq!(stats.borrow().time_to_first_job, Some(Duration::ZERO));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reconnects_after_disconnect() {
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) = source_with_mock_transports();
+
+        let (transport1, mut handle1) = MockTransport::pair();
+        let (transport2, mut handle2) = MockTransport::pair();
+        mock_tx.send(transport1).await.unwrap();
+        mock_tx.send(transport2).await.unwrap();
+
+        let source_handle = tokio::spawn(source.run());
+
+        // Trigger Phase 1 -> Phase 2 with a positive hashrate.
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
+                500.0,
+            )))
+            .await
+            .unwrap();
+
+        // First connection: handshake, receive one job, then disconnect.
+        do_handshake(&mut handle1).await;
+        handle1.send(job_notification("job-1"));
+
+        let event = event_rx.recv().await.unwrap();
+        assert!(
+            matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == "job-1"),
+            "expected ReplaceJob(job-1), got {event:?}",
+        );
+
+        // Drop the handle to simulate pool going away.
+        drop(handle1);
+
+        let event = event_rx.recv().await.unwrap();
+        assert!(
+            matches!(event, SourceEvent::ClearJobs),
+            "expected ClearJobs after disconnect, got {event:?}",
+        );
+
+        // Advance past the backoff (max initial is 1s).
+        time::advance(Duration::from_secs(2)).await;
+
+        // Second connection: handshake, receive a job.
+        do_handshake(&mut handle2).await;
+        handle2.send(job_notification("job-2"));
+
+        let event = event_rx.recv().await.unwrap();
+        assert!(
+            matches!(event, SourceEvent::ReplaceJob(ref t) if t.id == "job-2"),
+            "expected ReplaceJob(job-2), got {event:?}",
+        );
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn network_mismatch_is_warned_again_after_reconnecting() {
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) = source_with_mock_transports();
+        let (transport1, mut handle1) = MockTransport::pair();
+        let (transport2, mut handle2) = MockTransport::pair();
+        mock_tx.send(transport1).await.unwrap();
+        mock_tx.send(transport2).await.unwrap();
+        // A regtest target, which no mainnet job can have.
+        let regtest_job = |job_id: &str| {
+            let mut params = job_params(job_id);
+            params[6] = json!("207fffff");
+            JsonRpcMessage::notification("mining.notify", params)
+        };
+
+        let logs = crate::tracing::capture_logs(async {
+            let source_handle = tokio::spawn(source.run());
+            command_tx
+                .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
+                    500.0,
+                )))
+                .await
+                .unwrap();
+
+            // Two such jobs on the first connection, warned about once.
+            do_handshake(&mut handle1).await;
+            handle1.send(regtest_job("job-1"));
+            handle1.send(regtest_job("job-2"));
+            for _ in 0..2 {
+                let event = event_rx.recv().await.unwrap();
+                assert!(matches!(event, SourceEvent::ReplaceJob(_)), "{event:?}");
+            }
+            drop(handle1);
+            let event = event_rx.recv().await.unwrap();
+            assert!(matches!(event, SourceEvent::ClearJobs), "{event:?}");
+
+            // The next connection warns again.
+            time::advance(Duration::from_secs(2)).await;
+            do_handshake(&mut handle2).await;
+            handle2.send(regtest_job("job-3"));
+            let event = event_rx.recv().await.unwrap();
+            assert!(matches!(event, SourceEvent::ReplaceJob(_)), "{event:?}");
+
+            shutdown.cancel();
+            source_handle.await.unwrap().unwrap();
+        })
+        .await;
+        assert_eq!(logs.matches("Check MUJINA_NETWORK").count(), 2, "{logs}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn only_reconnects_past_the_grace_count() {
+        let (mut source, mut event_rx, command_tx, mock_tx, shutdown) =
+            source_with_mock_transports();
+        source.reconnects = ReconnectGrace::new(Duration::from_secs(5));
+        let stats = source.stats();
+        let (transport1, mut handle1) = MockTransport::pair();
+        let (transport2, mut handle2) = MockTransport::pair();
+        let (transport3, mut handle3) = MockTransport::pair();
+        mock_tx.send(transport1).await.unwrap();
+        mock_tx.send(transport2).await.unwrap();
+
+        let source_handle = tokio::spawn(source.run());
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
+                500.0,
+            )))
+            .await
+            .unwrap();
+        do_handshake(&mut handle1).await;
+
+        // Dropped and back on the first retry, well inside the grace.
+        drop(handle1);
+        assert!(matches!(
+            event_rx.recv().await.unwrap(),
+            SourceEvent::ClearJobs
+        ));
+        time::advance(Duration::from_secs(1)).await;
+        do_handshake(&mut handle2).await;
+        handle2.send(job_notification("job-2"));
+        event_rx.recv().await.unwrap();
+        assert_eq!(stats.borrow().reconnects, 0);
+
+        // Dropped again, and the pool is gone for ten seconds.
+        drop(handle2);
+        assert!(matches!(
+            event_rx.recv().await.unwrap(),
+            SourceEvent::ClearJobs
+        ));
+        time::advance(Duration::from_secs(10)).await;
+        mock_tx.send(transport3).await.unwrap();
+        do_handshake(&mut handle3).await;
+        handle3.send(job_notification("job-3"));
+        event_rx.recv().await.unwrap();
+        assert_eq!(stats.borrow().reconnects, 1);
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backoff_escalates_across_disconnects() {
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) = source_with_mock_transports();
+
+        // Four transports that fail immediately (handle dropped).
+        for _ in 0..4 {
+            let (transport, handle) = MockTransport::pair();
+            drop(handle);
+            mock_tx.send(transport).await.unwrap();
+        }
+
+        let source_handle = tokio::spawn(source.run());
+
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
+                500.0,
+            )))
+            .await
+            .unwrap();
+
+        // 1st disconnect: nominal 1s, jittered to [0.5s, 1.0s).
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::ClearJobs));
+
+        // Advancing 0.4s is below the minimum (0.5s); no reconnect yet.
+        time::advance(Duration::from_millis(400)).await;
+        tokio::task::yield_now().await;
+        assert!(
+            event_rx.try_recv().is_err(),
+            "reconnected too soon after 1st disconnect",
+        );
+
+        // Advance past the maximum (1.0s total) to trigger reconnect.
+        time::advance(Duration::from_millis(600)).await;
+
+        // 2nd disconnect: nominal 2s, jittered to [1.0s, 2.0s).
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::ClearJobs));
+
+        // Advancing 0.9s is below the minimum (1.0s); no reconnect yet.
+        time::advance(Duration::from_millis(900)).await;
+        tokio::task::yield_now().await;
+        assert!(
+            event_rx.try_recv().is_err(),
+            "reconnected too soon after 2nd disconnect",
+        );
+
+        // Advance past the maximum (2.0s total).
+        time::advance(Duration::from_millis(1100)).await;
+
+        // 3rd disconnect: nominal 4s, jittered to [2.0s, 4.0s).
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::ClearJobs));
+
+        // Advancing 1.9s is below the minimum (2.0s); proves escalation.
+        time::advance(Duration::from_millis(1900)).await;
+        tokio::task::yield_now().await;
+        assert!(
+            event_rx.try_recv().is_err(),
+            "reconnected too soon after 3rd disconnect",
+        );
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fake_clock_drives_the_backoff_sequence() {
+        let start = ::time::macros::datetime!(2026-03-01 12:00:00 UTC);
+        let clock = FakeClock::new(start);
+        let (source, mut event_rx, command_tx, mock_tx, shutdown) = source_with_mock_transports();
+        let source = source.with_clock(clock.clone());
+
+        for _ in 0..3 {
+            let (transport, handle) = MockTransport::pair();
+            drop(handle);
+            mock_tx.send(transport).await.unwrap();
+        }
+        let source_handle = tokio::spawn(source.run());
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
+                500.0,
+            )))
+            .await
+            .unwrap();
+
+        // Each step waits out the longest jittered delay, so every
+        // reconnect lands at a point the clock alone decides.
+        for max_delay_ms in [1_000, 2_000] {
+            let event = event_rx.recv().await.unwrap();
+            assert!(matches!(event, SourceEvent::ClearJobs));
+            clock
+                .advance(Duration::from_millis(max_delay_ms / 2 - 1))
+                .await;
+            tokio::task::yield_now().await;
+            assert!(event_rx.try_recv().is_err(), "reconnected early");
+            clock
+                .advance(Duration::from_millis(max_delay_ms / 2 + 1))
+                .await;
+        }
+        let event = event_rx.recv().await.unwrap();
+        assert!(matches!(event, SourceEvent::ClearJobs));
+        assert_eq!(clock.now_utc(), start + Duration::from_secs(3));
+
+        shutdown.cancel();
+        source_handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn daily_counts_follow_the_sources_clock() {
+        let clock = FakeClock::new(::time::macros::datetime!(2026-03-01 23:59:59 UTC));
+        let mut source = throttle_test_source().with_clock(clock.clone());
+        let stats = source.stats();
+        let accept = ClientEvent::ShareAccepted {
+            job_id: "job-1".into(),
+            nonce: 0x11,
+        };
+
+        source.handle_client_event(accept.clone()).await.unwrap();
+        source.handle_client_event(accept.clone()).await.unwrap();
+        assert_eq!(stats.borrow().accepted_today.get(clock.now_utc()), 2);
+        assert_eq!(stats.borrow().shares_accepted, 2);
+
+        // Past midnight the day's count starts over; the lifetime total
+        // carries on.
+        clock.advance(Duration::from_secs(2)).await;
+        source.handle_client_event(accept).await.unwrap();
+        assert_eq!(stats.borrow().accepted_today.get(clock.now_utc()), 1);
+        assert_eq!(stats.borrow().shares_accepted, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fatal_error_stops_retrying() {
+        let (source, _event_rx, command_tx, mock_tx, _shutdown) = source_with_mock_transports();
+
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+
+        let source_handle = tokio::spawn(source.run());
+
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
+                500.0,
+            )))
+            .await
+            .unwrap();
+
+        do_configure_and_subscribe(&mut handle).await;
+
+        // mining.authorize -- reject
         let msg = handle.recv().await;
         handle.send(JsonRpcMessage::Response {
             id: msg.id().unwrap(),
@@ -1758,6 +3748,110 @@ mod tests {
         assert!(result.is_err(), "expected fatal error, got Ok");
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_max_failed_attempts() {
+        let (mut source, mut event_rx, command_tx, mock_tx, _shutdown) =
+            source_with_mock_transports();
+        source.config.max_failed_attempts = Some(3);
+        let stats = source.stats();
+
+        // Transports that fail immediately, more than the cap allows.
+        for _ in 0..4 {
+            let (transport, handle) = MockTransport::pair();
+            drop(handle);
+            mock_tx.send(transport).await.unwrap();
+        }
+
+        let source_handle = tokio::spawn(source.run());
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
+                500.0,
+            )))
+            .await
+            .unwrap();
+
+        for attempt in 1..=3 {
+            let event = event_rx.recv().await.unwrap();
+            assert!(
+                matches!(event, SourceEvent::ClearJobs),
+                "attempt {attempt}: expected ClearJobs, got {event:?}",
+            );
+            if attempt < 3 {
+                // Still retrying after backoff.
+                assert_eq!(stats.borrow().failure, None, "attempt {attempt}");
+                time::advance(Duration::from_secs(60)).await;
+            }
+        }
+
+        let result = source_handle.await.unwrap();
+        let error = result.expect_err("expected the source to give up");
+        assert!(
+            error.to_string().contains("3 failed connection attempts"),
+            "{error}"
+        );
+        assert_eq!(
+            stats.borrow().failure.as_deref(),
+            Some("gave up after 3 failed connection attempts")
+        );
+        // No fourth attempt: the source is gone.
+        assert!(event_rx.recv().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rejected_authorization_fails_fast_and_is_reported() {
+        let (source, mut event_rx, command_tx, mock_tx, _shutdown) = source_with_mock_transports();
+        let stats = source.stats();
+
+        let (transport, mut handle) = MockTransport::pair();
+        mock_tx.send(transport).await.unwrap();
+        // Would be used by a reconnect.
+        let (spare, mut spare_handle) = MockTransport::pair();
+        mock_tx.send(spare).await.unwrap();
+
+        let source_handle = tokio::spawn(source.run());
+        command_tx
+            .send(SourceCommand::UpdateHashRate(HashRate::from_gigahashes(
+                500.0,
+            )))
+            .await
+            .unwrap();
+
+        do_configure_and_subscribe(&mut handle).await;
+        let msg = handle.recv().await;
+        assert_eq!(msg.method(), Some("mining.authorize"));
+        handle.send(JsonRpcMessage::Response {
+            id: msg.id().unwrap(),
+            result: Some(json!(false)),
+            error: None,
+        });
+
+        let error = source_handle
+            .await
+            .unwrap()
+            .expect_err("expected the source to stop");
+        assert!(
+            matches!(
+                error.downcast_ref(),
+                Some(StratumError::AuthorizationFailed(_))
+            ),
+            "{error}"
+        );
+        assert_eq!(
+            stats.borrow().failure.as_deref(),
+            Some("authentication failed")
+        );
+
+        // No backoff, no second connection: the source cleared its work
+        // and went away.
+        assert!(matches!(
+            event_rx.recv().await,
+            Some(SourceEvent::ClearJobs)
+        ));
+        assert!(event_rx.recv().await.is_none());
+        time::advance(Duration::from_secs(600)).await;
+        assert!(spare_handle.try_recv().is_none());
+    }
+
     #[tokio::test(start_paused = true)]
     async fn shutdown_during_backoff() {
         let (source, mut event_rx, command_tx, mock_tx, shutdown) = source_with_mock_transports();
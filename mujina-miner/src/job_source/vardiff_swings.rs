@@ -0,0 +1,116 @@
+//! Spotting a pool whose vardiff won't settle.
+//!
+//! A pool's vardiff retargets the share difficulty from the shares it
+//! sees. Tuned badly it overshoots both ways, and every swing costs work:
+//! a difficulty far too low floods the pool with shares, one far too high
+//! leaves long gaps in which luck decides the reported hashrate. A ramp in
+//! one direction, as after connecting, is vardiff doing its job. What
+//! marks oscillation is repeated reversal, so a swing counts only when it
+//! changes the difficulty by [`LARGE_SWING`] or more against the
+//! direction of the swing before, and the pool is flagged while
+//! [`MAX_REVERSALS`] such reversals fall within [`WINDOW`].
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Factor by which the difficulty must move to count as a swing.
+pub const LARGE_SWING: f64 = 2.0;
+
+/// Span over which reversals are counted.
+pub const WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Reversals within the window at which the pool counts as oscillating.
+pub const MAX_REVERSALS: usize = 3;
+
+/// Tracks one connection's `mining.set_difficulty` history.
+#[derive(Debug, Default)]
+pub struct SwingTracker {
+    /// Difficulty the last swing was measured from.
+    settled: Option<f64>,
+    /// Direction of the last large swing, `true` for up.
+    last_up: Option<bool>,
+    /// When each reversal within the window happened, oldest first.
+    reversals: VecDeque<Instant>,
+}
+
+impl SwingTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a difficulty the pool set at `now`. Returns whether the pool
+    /// is oscillating.
+    pub fn observe(&mut self, now: Instant, difficulty: f64) -> bool {
+        while self
+            .reversals
+            .front()
+            .is_some_and(|&at| now.duration_since(at) > WINDOW)
+        {
+            self.reversals.pop_front();
+        }
+        if !(difficulty.is_finite() && difficulty > 0.0) {
+            return self.oscillating();
+        }
+        let Some(settled) = self.settled else {
+            self.settled = Some(difficulty);
+            return false;
+        };
+        let ratio = difficulty / settled;
+        if ratio >= LARGE_SWING || ratio <= 1.0 / LARGE_SWING {
+            let up = ratio > 1.0;
+            if self.last_up.is_some_and(|last| last != up) {
+                self.reversals.push_back(now);
+            }
+            self.last_up = Some(up);
+            self.settled = Some(difficulty);
+        }
+        self.oscillating()
+    }
+
+    fn oscillating(&self) -> bool {
+        self.reversals.len() >= MAX_REVERSALS
+    }
+
+    /// Reversals within the window, for logging.
+    pub fn reversals(&self) -> usize {
+        self.reversals.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seesawing_difficulty_is_flagged_and_ramps_are_not() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // Ramping up after connecting, then small corrections.
+        let mut steady = SwingTracker::new();
+        for (i, difficulty) in [512.0, 1024.0, 4096.0, 16384.0, 20000.0, 14000.0, 18000.0]
+            .into_iter()
+            .enumerate()
+        {
+            assert!(
+                !steady.observe(at(i as u64 * 30), difficulty),
+                "{difficulty}"
+            );
+        }
+        assert_eq!(steady.reversals(), 0);
+
+        // Swinging by 4x each way every 30 s.
+        let mut seesaw = SwingTracker::new();
+        let flags: Vec<bool> = [1000.0, 4000.0, 1000.0, 4000.0, 1000.0, 4000.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, difficulty)| seesaw.observe(at(i as u64 * 30), difficulty))
+            .collect();
+        assert_eq!(flags, [false, false, false, false, true, true]);
+
+        // Once it settles for the window, the reversals age out.
+        assert!(!seesaw.observe(at(150) + WINDOW, 4000.0));
+    }
+}
@@ -3,16 +3,25 @@ pub mod api_client;
 pub mod asic;
 pub mod backplane;
 pub mod board;
+mod burn_in;
+mod cgminer_api;
+pub mod clock;
 pub mod config;
+#[cfg(feature = "cpu-miner")]
 pub mod cpu_miner;
 pub mod daemon;
 pub mod env_help;
+pub mod host_load;
 pub mod hw_trait;
 pub mod job_source;
 pub mod mgmt_protocol;
+pub mod network;
+pub mod payout;
 pub mod peripheral;
 pub mod scheduler;
+mod stats_csv;
 pub mod stratum_v1;
+mod summary_log;
 pub mod tracing;
 pub mod transport;
 pub mod types;
@@ -2,18 +2,83 @@
 //!
 //! This module provides a control channel abstraction that handles
 //! packet ID management and request/response correlation.
+//!
+//! A channel whose stream fails or closes, as when a board's USB cable is
+//! pulled, marks its link lost ([`ControlChannel::link_lost()`]) so the
+//! board can be re-created instead of treating every failed poll as a
+//! hardware fault. An unreadable frame doesn't count: the link is still
+//! there.
+//!
+//! A channel out of step with the firmware does count. An exchange
+//! fails on an unexpected response when it reads an unreadable frame,
+//! or a response to no outstanding request (a late reply to a request
+//! that timed out is expected), and never gets its own. After
+//! [`DESYNC_LIMIT`] such exchanges in a row the link is marked lost,
+//! so the board is re-created and its protocol state starts afresh.
 
 use futures::SinkExt;
+use std::collections::HashSet;
 use std::io;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::Mutex;
 use tokio::time;
-use tokio_serial::SerialStream;
 use tokio_stream::StreamExt;
 use tokio_util::codec::{FramedRead, FramedWrite};
 
-use super::{ControlCodec, Packet, Response, ResponseFormat};
+use super::{
+    ControlCodec, DeviceVersion, ErrorCode, Packet, Page, Response, ResponseError, ResponseFormat,
+    UnsupportedProtocol,
+};
+use crate::hw_trait::HwError;
+use crate::tracing::prelude::*;
+
+/// How long to wait for a response when not told otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Exchanges in a row failing on an unexpected response before the link
+/// is given up as desynced.
+pub const DESYNC_LIMIT: u32 = 5;
+
+/// Errors from a control channel exchange.
+#[derive(Debug, thiserror::Error)]
+pub enum ControlChannelError {
+    /// The serial link failed or closed, or sent an unreadable frame.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    /// No response arrived in time.
+    #[error("Control command timeout after {}ms", .0.as_millis())]
+    Timeout(Duration),
+
+    /// An I2C passthrough did not complete in time, timed out either by
+    /// the firmware or waiting for its response. The device answered its
+    /// address but held the bus, typically by stretching the clock; a
+    /// missing device is a NACK instead.
+    #[error("I2C transaction timed out after {}ms", .0.as_millis())]
+    I2cTimeout(Duration),
+
+    /// The firmware reported an error.
+    #[error("Control protocol error: {0:?}")]
+    Protocol(ResponseError),
+}
+
+impl From<ControlChannelError> for HwError {
+    fn from(e: ControlChannelError) -> Self {
+        match e {
+            ControlChannelError::Io(e) => HwError::Io(e),
+            ControlChannelError::Timeout(_) | ControlChannelError::I2cTimeout(_) => {
+                HwError::Timeout
+            }
+            e @ ControlChannelError::Protocol(_) => HwError::Io(io::Error::other(e)),
+        }
+    }
+}
+
+type Reader = FramedRead<Box<dyn AsyncRead + Send + Unpin>, ControlCodec>;
+type Writer = FramedWrite<Box<dyn AsyncWrite + Send + Unpin>, ControlCodec>;
 
 /// Control channel for bitaxe-raw protocol communication.
 ///
@@ -22,77 +87,196 @@ use super::{ControlCodec, Packet, Response, ResponseFormat};
 #[derive(Clone)]
 pub struct ControlChannel {
     inner: Arc<Mutex<ControlChannelInner>>,
+    /// Set once the stream fails or closes.
+    link_lost: Arc<AtomicBool>,
 }
 
 struct ControlChannelInner {
-    writer: FramedWrite<tokio::io::WriteHalf<SerialStream>, ControlCodec>,
-    reader: FramedRead<tokio::io::ReadHalf<SerialStream>, ControlCodec>,
+    writer: Writer,
+    reader: Reader,
     next_id: u8,
+    /// IDs of requests that timed out, whose replies may still turn up.
+    late: HashSet<u8>,
+    /// Whether the last read failed, so the reader's next end of stream
+    /// is its reset after the error rather than the stream closing.
+    read_failed: bool,
+    /// Consecutive exchanges that failed on an unexpected response.
+    unexpected: u32,
 }
 
 impl ControlChannel {
-    /// Create a new control channel from a serial stream.
+    /// Create a new control channel over a serial stream, or anything
+    /// else carrying the protocol. [`ControlTransport`] opens the ones
+    /// boards use.
+    ///
+    /// [`ControlTransport`]: super::transport::ControlTransport
     ///
     /// The `format` parameter selects the response framing and error
     /// signaling variant. See [`ResponseFormat`] for details.
-    pub fn new(stream: SerialStream, format: ResponseFormat) -> Self {
+    pub fn new<S>(stream: S, format: ResponseFormat) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
         let (reader, writer) = tokio::io::split(stream);
+        let reader: Box<dyn AsyncRead + Send + Unpin> = Box::new(reader);
+        let writer: Box<dyn AsyncWrite + Send + Unpin> = Box::new(writer);
         Self {
             inner: Arc::new(Mutex::new(ControlChannelInner {
                 writer: FramedWrite::new(writer, ControlCodec::new(format)),
                 reader: FramedRead::new(reader, ControlCodec::new(format)),
                 next_id: 0,
+                late: HashSet::new(),
+                read_failed: false,
+                unexpected: 0,
             })),
+            link_lost: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Whether the stream has failed or closed, so no exchange can succeed
+    /// until the channel is opened again.
+    pub fn link_lost(&self) -> bool {
+        self.link_lost.load(Ordering::Relaxed)
+    }
+
+    /// Mark the link lost if `e` is the stream failing rather than a
+    /// frame that couldn't be decoded.
+    fn note_io_error(&self, e: &io::Error) {
+        if e.kind() != io::ErrorKind::InvalidData && !self.link_lost.swap(true, Ordering::Relaxed) {
+            warn!(error = %e, "Control link lost");
         }
     }
 
-    /// Send a raw packet and wait for response.
-    pub async fn send_packet(&self, mut packet: Packet) -> io::Result<Response> {
+    /// Count an exchange that failed on an unexpected response, giving
+    /// the link up once they reach [`DESYNC_LIMIT`] in a row.
+    fn note_unexpected(&self, inner: &mut ControlChannelInner, what: &str) {
+        inner.unexpected += 1;
+        if inner.unexpected >= DESYNC_LIMIT && !self.link_lost.swap(true, Ordering::Relaxed) {
+            warn!(
+                exchanges = inner.unexpected,
+                last = what,
+                "Control protocol out of step with board, treating link as lost"
+            );
+        }
+    }
+
+    /// Create a control channel speaking the protocol of firmware
+    /// `version`, or refuse when this host doesn't know that protocol.
+    ///
+    /// Nothing is written to `stream` on refusal.
+    pub fn negotiate<S>(stream: S, version: &DeviceVersion) -> Result<Self, UnsupportedProtocol>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let format = version.response_format()?;
+        debug!(version = %version, format = ?format, "Control protocol negotiated");
+        Ok(Self::new(stream, format))
+    }
+
+    /// Send a raw packet and wait up to [`DEFAULT_TIMEOUT`] for its
+    /// response.
+    pub async fn send_packet(&self, packet: Packet) -> Result<Response, ControlChannelError> {
+        self.send_packet_with_timeout(packet, DEFAULT_TIMEOUT).await
+    }
+
+    /// Send a raw packet and wait up to `timeout` for its response.
+    ///
+    /// A response that arrives after its request timed out is discarded
+    /// when it turns up, so it can't be taken for a later request's.
+    pub async fn send_packet_with_timeout(
+        &self,
+        mut packet: Packet,
+        timeout: Duration,
+    ) -> Result<Response, ControlChannelError> {
         let mut inner = self.inner.lock().await;
 
         // Assign packet ID
         packet.id = inner.next_id;
         inner.next_id = inner.next_id.wrapping_add(1);
         let expected_id = packet.id;
+        let i2c = packet.page == Page::I2C;
+        // A reply still owed under this ID, 256 requests ago, is now
+        // indistinguishable from this one's.
+        inner.late.remove(&expected_id);
 
         // Send the packet (logging happens in encoder)
-        inner.writer.send(packet).await?;
+        inner
+            .writer
+            .send(packet)
+            .await
+            .inspect_err(|e| self.note_io_error(e))?;
 
         // Wait for response with matching ID
-        let timeout = Duration::from_secs(1);
-        let response = time::timeout(timeout, async {
-            match inner.reader.next().await {
-                Some(Ok(resp)) => {
-                    if resp.id != expected_id {
+        let mut unexpected_id = None;
+        let received = time::timeout(timeout, async {
+            loop {
+                match inner.reader.next().await {
+                    Some(Ok(resp)) if resp.id == expected_id => return Ok(resp),
+                    Some(Ok(resp)) if inner.late.remove(&resp.id) => {
+                        debug!(
+                            expected_id,
+                            id = resp.id,
+                            "Discarding late control response"
+                        );
+                    }
+                    Some(Ok(resp)) => {
+                        debug!(
+                            expected_id,
+                            id = resp.id,
+                            "Discarding unexpected control response"
+                        );
+                        unexpected_id = Some(resp.id);
+                    }
+                    Some(Err(e)) => {
+                        inner.read_failed = true;
+                        return Err(e);
+                    }
+                    None if std::mem::take(&mut inner.read_failed) => {}
+                    None => {
                         return Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            format!(
-                                "Response ID mismatch: expected {}, got {}",
-                                expected_id, resp.id
-                            ),
+                            io::ErrorKind::UnexpectedEof,
+                            "Control stream closed",
                         ));
                     }
-                    Ok(resp)
                 }
-                Some(Err(e)) => Err(e),
-                None => Err(io::Error::new(
-                    io::ErrorKind::UnexpectedEof,
-                    "Control stream closed",
-                )),
             }
         })
-        .await
-        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "Control command timeout"))??;
+        .await;
+
+        let response = match received {
+            Ok(Ok(response)) => {
+                inner.unexpected = 0;
+                response
+            }
+            Ok(Err(e)) => {
+                if e.kind() == io::ErrorKind::InvalidData {
+                    self.note_unexpected(&mut inner, &e.to_string());
+                }
+                self.note_io_error(&e);
+                return Err(e.into());
+            }
+            Err(_) => {
+                inner.late.insert(expected_id);
+                if let Some(id) = unexpected_id {
+                    self.note_unexpected(&mut inner, &format!("response to ID {id}"));
+                }
+                return Err(if i2c {
+                    ControlChannelError::I2cTimeout(timeout)
+                } else {
+                    ControlChannelError::Timeout(timeout)
+                });
+            }
+        };
 
         // Check for protocol errors
-        if let Some(error) = response.error() {
-            return Err(io::Error::other(format!(
-                "Control protocol error: {:?}",
-                error
-            )));
+        match response.error {
+            None => Ok(response),
+            Some(ResponseError {
+                code: ErrorCode::Timeout,
+                ..
+            }) if i2c => Err(ControlChannelError::I2cTimeout(timeout)),
+            Some(error) => Err(ControlChannelError::Protocol(error)),
         }
-
-        Ok(response)
     }
 
     /// Send a packet without waiting for a response.
@@ -105,6 +289,109 @@ impl ControlChannel {
         packet.id = inner.next_id;
         inner.next_id = inner.next_id.wrapping_add(1);
 
-        inner.writer.send(packet).await
+        inner
+            .writer
+            .send(packet)
+            .await
+            .inspect_err(|e| self.note_io_error(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    use super::*;
+    use crate::mgmt_protocol::bitaxe_raw::ResponseFormat;
+
+    /// v1 firmware that answers the first `in_step` requests properly,
+    /// then each with `reply(id)`.
+    async fn firmware(
+        mut stream: impl AsyncRead + AsyncWrite + Unpin,
+        in_step: usize,
+        reply: impl Fn(u8) -> Vec<u8>,
+    ) {
+        for answered in 0.. {
+            let mut len = [0u8; 2];
+            if stream.read_exact(&mut len).await.is_err() {
+                return;
+            }
+            let mut request = vec![0u8; u16::from_le_bytes(len) as usize - 2];
+            stream.read_exact(&mut request).await.unwrap();
+            let id = request[0];
+            let response = if answered < in_step {
+                vec![4, 0, id, 0x00]
+            } else {
+                reply(id)
+            };
+            stream.write_all(&response).await.unwrap();
+        }
+    }
+
+    async fn exchange(channel: &ControlChannel) -> Result<Response, ControlChannelError> {
+        let packet = Packet::new(Page::GPIO, 0, vec![]);
+        channel
+            .send_packet_with_timeout(packet, Duration::from_millis(50))
+            .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn repeated_unexpected_responses_give_up_the_link() {
+        // Replies off by one, as if the firmware had slipped a request:
+        // each request sees only the next one's response.
+        let (host, device) = tokio::io::duplex(256);
+        tokio::spawn(firmware(device, 1, |id| {
+            vec![4, 0, id.wrapping_add(1), 0x00]
+        }));
+        let channel = ControlChannel::new(host, ResponseFormat::V1);
+
+        exchange(&channel).await.unwrap();
+        for _ in 1..DESYNC_LIMIT {
+            let result = exchange(&channel).await;
+            assert!(
+                matches!(result, Err(ControlChannelError::Timeout(_))),
+                "{result:?}"
+            );
+            assert!(!channel.link_lost());
+        }
+        assert!(exchange(&channel).await.is_err());
+        assert!(channel.link_lost());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unreadable_frames_count_but_one_good_exchange_resets() {
+        // A frame too short to hold even an ID and a status byte.
+        let garbage = |_| vec![3, 0, 0xee];
+        let (host, device) = tokio::io::duplex(256);
+        tokio::spawn(firmware(device, 0, garbage));
+        let channel = ControlChannel::new(host, ResponseFormat::V1);
+
+        // Each reads as bad data, not the stream closing.
+        for _ in 1..DESYNC_LIMIT {
+            let result = exchange(&channel).await;
+            assert!(
+                matches!(&result, Err(ControlChannelError::Io(e)) if e.kind() == io::ErrorKind::InvalidData),
+                "{result:?}"
+            );
+        }
+        assert!(!channel.link_lost());
+        assert!(exchange(&channel).await.is_err());
+        assert!(channel.link_lost());
+
+        // Unreadable frames between good exchanges never add up.
+        let (host, device) = tokio::io::duplex(256);
+        let answered = std::sync::atomic::AtomicUsize::new(0);
+        tokio::spawn(firmware(device, 0, move |id| {
+            if answered.fetch_add(1, Ordering::Relaxed).is_multiple_of(2) {
+                garbage(id)
+            } else {
+                vec![4, 0, id, 0x00]
+            }
+        }));
+        let channel = ControlChannel::new(host, ResponseFormat::V1);
+        for _ in 0..DESYNC_LIMIT * 2 {
+            let _ = exchange(&channel).await;
+        }
+        assert!(!channel.link_lost());
     }
 }
@@ -1,22 +1,72 @@
 //! I2C implementation using bitaxe-raw control protocol.
+//!
+//! Each transaction is passed through to the firmware, which runs it on
+//! the bus and replies. A device may hold the clock low while it prepares
+//! a reply; a transaction not answered within the passthrough timeout
+//! (`MUJINA_I2C_TIMEOUT_MS`, default 1000) fails with
+//! [`I2cError::Timeout`], as distinct from the [`I2cError::NoAck`] of a
+//! device that isn't there.
+
+use std::env;
+use std::time::Duration;
 
 use async_trait::async_trait;
 
-use super::channel::ControlChannel;
+use super::channel::{self, ControlChannel, ControlChannelError};
 use super::{I2CCommand, Packet, Page};
 use crate::hw_trait::i2c::{I2c, I2cError};
 use crate::hw_trait::{HwError, Result};
+use crate::tracing::prelude::*;
 
 /// I2C bus implementation using bitaxe-raw control protocol.
 #[derive(Clone)]
 pub struct BitaxeRawI2c {
     channel: ControlChannel,
+    timeout: Duration,
 }
 
 impl BitaxeRawI2c {
     /// Create a new I2C bus using the given control channel.
     pub fn new(channel: ControlChannel) -> Self {
-        Self { channel }
+        Self {
+            channel,
+            timeout: channel::DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Wait up to `timeout` for each transaction to complete.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send a transaction with device address `addr`, naming it `op` in
+    /// errors.
+    async fn transact(&self, packet: Packet, addr: u8, op: &str) -> Result<Vec<u8>> {
+        match self
+            .channel
+            .send_packet_with_timeout(packet, self.timeout)
+            .await
+        {
+            Ok(response) => Ok(response.data),
+            Err(ControlChannelError::I2cTimeout(_)) => Err(HwError::I2c(I2cError::Timeout(addr))),
+            Err(e) => Err(HwError::I2c(I2cError::Other(format!("{op} failed: {e}")))),
+        }
+    }
+}
+
+/// The passthrough timeout from `MUJINA_I2C_TIMEOUT_MS`, warning and
+/// keeping the default when it is invalid.
+pub fn timeout_from_env() -> Duration {
+    match env::var("MUJINA_I2C_TIMEOUT_MS") {
+        Err(_) => channel::DEFAULT_TIMEOUT,
+        Ok(val) => match val.parse::<u64>() {
+            Ok(ms) if ms > 0 => Duration::from_millis(ms),
+            _ => {
+                warn!(value = %val, "Invalid MUJINA_I2C_TIMEOUT_MS, using default");
+                channel::DEFAULT_TIMEOUT
+            }
+        },
     }
 }
 
@@ -29,11 +79,7 @@ impl I2c for BitaxeRawI2c {
             [vec![addr], data.to_vec()].concat(),
         );
 
-        self.channel
-            .send_packet(packet)
-            .await
-            .map_err(|e| HwError::I2c(I2cError::Other(format!("Write failed: {}", e))))?;
-
+        self.transact(packet, addr, "Write").await?;
         Ok(())
     }
 
@@ -44,21 +90,17 @@ impl I2c for BitaxeRawI2c {
             vec![addr, buffer.len() as u8],
         );
 
-        let response = self
-            .channel
-            .send_packet(packet)
-            .await
-            .map_err(|e| HwError::I2c(I2cError::Other(format!("Read failed: {}", e))))?;
+        let data = self.transact(packet, addr, "Read").await?;
 
-        if response.data.len() != buffer.len() {
+        if data.len() != buffer.len() {
             return Err(HwError::I2c(I2cError::Other(format!(
                 "Expected {} bytes, got {}",
                 buffer.len(),
-                response.data.len()
+                data.len()
             ))));
         }
 
-        buffer.copy_from_slice(&response.data);
+        buffer.copy_from_slice(&data);
         Ok(())
     }
 
@@ -69,36 +111,143 @@ impl I2c for BitaxeRawI2c {
 
         let packet = Packet::new(Page::I2C, I2CCommand::WriteRead as u8, data);
 
-        let response = self
-            .channel
-            .send_packet(packet)
-            .await
-            .map_err(|e| HwError::I2c(I2cError::Other(format!("WriteRead failed: {}", e))))?;
+        let data = self.transact(packet, addr, "WriteRead").await?;
 
-        if response.data.len() != read.len() {
+        if data.len() != read.len() {
             return Err(HwError::I2c(I2cError::Other(format!(
                 "Expected {} bytes, got {}",
                 read.len(),
-                response.data.len()
+                data.len()
             ))));
         }
 
-        read.copy_from_slice(&response.data);
+        read.copy_from_slice(&data);
         Ok(())
     }
 
     async fn set_frequency(&mut self, hz: u32) -> Result<()> {
-        let packet = Packet::new(
-            Page::I2C,
-            I2CCommand::SetFrequency as u8,
-            hz.to_le_bytes().to_vec(),
-        );
-
         self.channel
-            .send_packet(packet)
+            .send_packet_with_timeout(set_frequency_packet(hz), self.timeout)
             .await
             .map_err(|e| HwError::I2c(I2cError::Other(format!("SetFrequency failed: {}", e))))?;
 
         Ok(())
     }
 }
+
+/// The setup command selecting the bus clock, as a little-endian Hz value.
+fn set_frequency_packet(hz: u32) -> Packet {
+    Packet::new(
+        Page::I2C,
+        I2CCommand::SetFrequency as u8,
+        hz.to_le_bytes().to_vec(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+    use tokio::time;
+
+    use super::super::ResponseFormat;
+    use super::*;
+    use crate::hw_trait::i2c::I2cSpeed;
+
+    /// Serve the far end of `stream` as firmware would, answering each
+    /// request after `delay` with `reply` bytes, or with an error status
+    /// if `status` is nonzero.
+    async fn device(mut stream: DuplexStream, delay: Duration, status: u8, reply: Vec<u8>) {
+        loop {
+            let mut len = [0u8; 2];
+            if stream.read_exact(&mut len).await.is_err() {
+                return;
+            }
+            let mut request = vec![0u8; u16::from_le_bytes(len) as usize - 2];
+            stream.read_exact(&mut request).await.unwrap();
+            time::sleep(delay).await;
+
+            let data: &[u8] = if status == 0 { &reply } else { &[] };
+            let total = (4 + data.len()) as u16;
+            let mut response = total.to_le_bytes().to_vec();
+            response.extend([request[0], status]);
+            response.extend_from_slice(data);
+            stream.write_all(&response).await.unwrap();
+        }
+    }
+
+    /// A bus whose only device holds the clock for `delay` on every
+    /// transaction.
+    fn stretching_bus(delay: Duration, status: u8) -> ControlChannel {
+        let (host, firmware) = tokio::io::duplex(256);
+        tokio::spawn(device(firmware, delay, status, vec![0x12, 0x34]));
+        ControlChannel::new(host, ResponseFormat::V1)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clock_stretching_past_the_timeout_is_a_timeout_not_a_nack() {
+        let stretch = Duration::from_millis(1500);
+        let mut buf = [0u8; 2];
+
+        let mut i2c = BitaxeRawI2c::new(stretching_bus(stretch, 0));
+        let result = i2c.write_read(0x4c, &[0x00], &mut buf).await;
+        assert!(
+            matches!(result, Err(HwError::I2c(I2cError::Timeout(0x4c)))),
+            "{result:?}"
+        );
+
+        // The late reply to the first transaction is discarded, not taken
+        // for the second's, which queues behind it on the device.
+        let mut i2c = i2c.with_timeout(Duration::from_secs(3));
+        i2c.write_read(0x4c, &[0x00], &mut buf).await.unwrap();
+        assert_eq!(buf, [0x12, 0x34]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn firmware_timeout_on_the_bus_is_an_i2c_timeout() {
+        let channel = stretching_bus(Duration::ZERO, 0x10);
+        let packet = Packet::new(Page::I2C, I2CCommand::Read as u8, vec![0x4c, 2]);
+        let result = channel.send_packet(packet).await;
+        assert!(
+            matches!(result, Err(ControlChannelError::I2cTimeout(_))),
+            "{result:?}"
+        );
+
+        let mut buf = [0u8; 2];
+        let result = BitaxeRawI2c::new(channel).read(0x4c, &mut buf).await;
+        assert!(
+            matches!(result, Err(HwError::I2c(I2cError::Timeout(0x4c)))),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn timeout_from_env_falls_back_on_invalid_values() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_I2C_TIMEOUT_MS", "2500") };
+        assert_eq!(timeout_from_env(), Duration::from_millis(2500));
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_I2C_TIMEOUT_MS", "0") };
+        assert_eq!(timeout_from_env(), channel::DEFAULT_TIMEOUT);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_I2C_TIMEOUT_MS") };
+        assert_eq!(timeout_from_env(), channel::DEFAULT_TIMEOUT);
+    }
+
+    #[test]
+    fn set_frequency_packet_encodes_speed() {
+        let encoded = set_frequency_packet(I2cSpeed::Fast.hz()).encode();
+
+        assert_eq!(encoded[0], 0x0a); // length low: 6 header + 4 data
+        assert_eq!(encoded[1], 0x00); // length high
+        assert_eq!(encoded[4], 0x05); // I2C page
+        assert_eq!(encoded[5], 0x10); // SetFrequency command
+        assert_eq!(&encoded[6..], &400_000u32.to_le_bytes()); // 0x00061a80
+
+        let encoded = set_frequency_packet(I2cSpeed::Standard.hz()).encode();
+        assert_eq!(&encoded[6..], &100_000u32.to_le_bytes());
+    }
+}
@@ -31,13 +31,15 @@ pub mod gpio;
 pub mod i2c;
 pub mod led;
 pub mod system;
+pub mod transport;
 mod version;
 
-pub use version::DeviceVersion;
+pub use version::{DeviceVersion, NEWEST_PROTOCOL_MINOR, UnsupportedFirmware, UnsupportedProtocol};
 
 use crate::tracing::prelude::*;
 use bytes::{BufMut, BytesMut};
-use std::{fmt, io};
+use std::sync::LazyLock;
+use std::{env, fmt, io};
 use tokio_util::codec::{Decoder, Encoder};
 
 /// Wrapper for formatting byte slices as space-separated hex.
@@ -55,6 +57,24 @@ impl fmt::Display for HexBytes<'_> {
     }
 }
 
+/// Unbroken hex, for whole frames where spacing would double the width.
+struct CompactHex<'a>(&'a [u8]);
+
+impl fmt::Display for CompactHex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether raw frames are traced, from `MUJINA_TRACE_CONTROL_FRAMES`.
+///
+/// Read once; set to any value to enable.
+static TRACE_FRAMES: LazyLock<bool> =
+    LazyLock::new(|| env::var_os("MUJINA_TRACE_CONTROL_FRAMES").is_some());
+
 /// Response frame format version.
 ///
 /// The bitaxe-raw protocol has two response formats that differ in
@@ -317,9 +337,13 @@ impl Response {
 }
 
 /// Tokio codec for the control protocol
+///
+/// Decoded packets are always traced. Raw frames are traced as well when
+/// frame tracing is on, which is verbose enough to be off by default.
 pub struct ControlCodec {
     format: ResponseFormat,
     max_length: usize,
+    trace_frames: bool,
 }
 
 impl ControlCodec {
@@ -327,6 +351,19 @@ impl ControlCodec {
         Self {
             format,
             max_length: 4096,
+            trace_frames: *TRACE_FRAMES,
+        }
+    }
+
+    /// Override whether raw frames are traced.
+    pub fn with_frame_tracing(mut self, enabled: bool) -> Self {
+        self.trace_frames = enabled;
+        self
+    }
+
+    fn trace_frame(&self, dir: &'static str, frame: &[u8]) {
+        if self.trace_frames {
+            trace!(dir, len = frame.len(), frame = %CompactHex(frame), "Control frame");
         }
     }
 }
@@ -368,11 +405,11 @@ impl Decoder for ControlCodec {
             ResponseFormat::V1 => Response::parse_v1(response_data)?,
         };
 
+        self.trace_frame("rx", &packet_data);
         trace!(
             id = response.id,
             status = if response.error.is_some() { "ERR" } else { "OK" },
             data = %HexBytes(&response.data),
-            frame = %HexBytes(&packet_data),
             "RX control"
         );
 
@@ -391,12 +428,12 @@ impl Encoder<Packet> for ControlCodec {
                 format!("Packet too large: {} bytes", encoded.len()),
             ));
         }
+        self.trace_frame("tx", &encoded);
         trace!(
             id = item.id,
             page = ?item.page,
             cmd = %format!("0x{:02x}", item.command),
             data = %HexBytes(&item.data),
-            frame = %HexBytes(&encoded),
             "TX control"
         );
         dst.extend_from_slice(&encoded);
@@ -408,6 +445,43 @@ impl Encoder<Packet> for ControlCodec {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn frame_tracing_logs_compact_hex_with_direction() {
+        let output = crate::tracing::capture_logs(async {
+            let mut codec = ControlCodec::new(ResponseFormat::V0).with_frame_tracing(true);
+
+            let mut packet = Packet::new(Page::GPIO, 0x02, vec![0x01]);
+            packet.id = 0x2a;
+            codec.encode(packet, &mut BytesMut::new()).unwrap();
+
+            let mut rx = BytesMut::from(&[0x01, 0x00, 0x2a, 0x01][..]);
+            codec.decode(&mut rx).unwrap().unwrap();
+        })
+        .await;
+
+        let frames: Vec<_> = output
+            .lines()
+            .filter(|line| line.contains("Control frame"))
+            .collect();
+        assert_eq!(frames.len(), 2, "{output}");
+        assert!(frames[0].contains("dir=\"tx\""), "{}", frames[0]);
+        assert!(frames[0].contains("frame=07002a00060201"), "{}", frames[0]);
+        assert!(frames[1].contains("dir=\"rx\""), "{}", frames[1]);
+        assert!(frames[1].contains("frame=01002a01"), "{}", frames[1]);
+    }
+
+    #[tokio::test]
+    async fn frame_tracing_off_logs_no_frames() {
+        let output = crate::tracing::capture_logs(async {
+            let mut codec = ControlCodec::new(ResponseFormat::V0).with_frame_tracing(false);
+            let packet = Packet::new(Page::GPIO, 0x02, vec![0x01]);
+            codec.encode(packet, &mut BytesMut::new()).unwrap();
+        })
+        .await;
+        assert!(output.contains("TX control"), "{output}");
+        assert!(!output.contains("Control frame"), "{output}");
+    }
+
     #[test]
     fn v0_response_parsing() {
         let response = Response::parse_v0(&[0x42, 0x01]).unwrap();
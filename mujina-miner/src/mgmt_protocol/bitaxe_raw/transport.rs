@@ -0,0 +1,371 @@
+//! Byte streams a control channel runs over.
+//!
+//! [`ControlChannel`] takes any `AsyncRead + AsyncWrite` stream, so the
+//! protocol logic doesn't know how its bytes travel. Boards on USB reach
+//! their firmware through a CDC-ACM serial port. A board behind a network
+//! bridge, such as ser2net forwarding the port in raw mode, is reached
+//! over TCP. [`ControlTransport`] names those two and opens them; tests
+//! run the protocol over an in-memory [`tokio::io::duplex`], scripting the
+//! firmware's side through a [`MockFirmware`].
+
+use std::io;
+
+use thiserror::Error;
+use tokio::net::TcpStream;
+use tokio_serial::SerialPortBuilderExt;
+
+use super::channel::ControlChannel;
+use super::{DeviceVersion, ResponseFormat, UnsupportedProtocol};
+
+/// Why a control channel couldn't be connected.
+#[derive(Debug, Error)]
+pub enum ConnectError {
+    /// The firmware's protocol is one this host doesn't speak.
+    #[error(transparent)]
+    Unsupported(#[from] UnsupportedProtocol),
+
+    /// The transport failed to open.
+    #[error("failed to open control transport: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Where a board's control channel is reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlTransport {
+    /// A serial port, typically the first of a USB board's CDC-ACM pair.
+    Serial { path: String, baud_rate: u32 },
+
+    /// A TCP connection to a bridge forwarding the serial port's bytes
+    /// unchanged, as `host:port`.
+    Tcp { addr: String },
+}
+
+impl ControlTransport {
+    /// Baud rate bitaxe-raw firmware runs its control port at.
+    pub const SERIAL_BAUD_RATE: u32 = 115200;
+
+    /// The serial port at `path`, at the firmware's baud rate.
+    pub fn serial(path: impl Into<String>) -> Self {
+        Self::Serial {
+            path: path.into(),
+            baud_rate: Self::SERIAL_BAUD_RATE,
+        }
+    }
+
+    /// Open the transport and start a control channel speaking the
+    /// protocol of firmware `version`. Firmware whose protocol this host
+    /// doesn't know is refused before the transport is opened.
+    pub async fn connect(&self, version: &DeviceVersion) -> Result<ControlChannel, ConnectError> {
+        let format = version.response_format()?;
+        Ok(self.open(format).await?)
+    }
+
+    /// Open the transport and start a control channel over it in `format`.
+    pub async fn open(&self, format: ResponseFormat) -> io::Result<ControlChannel> {
+        match self {
+            Self::Serial { path, baud_rate } => {
+                let port = tokio_serial::new(path, *baud_rate)
+                    .open_native_async()
+                    .map_err(io::Error::from)?;
+                Ok(ControlChannel::new(port, format))
+            }
+            Self::Tcp { addr } => {
+                let stream = TcpStream::connect(addr).await?;
+                // Requests are a few bytes each and wait on their reply.
+                stream.set_nodelay(true)?;
+                Ok(ControlChannel::new(stream, format))
+            }
+        }
+    }
+}
+
+/// In-memory stand-in for a board's firmware, for tests.
+///
+/// Backed by [`tokio::io::duplex`] rather than a port, so it works with
+/// `tokio::time::pause()`. Create a pair with [`MockFirmware::pair()`]; the
+/// channel is the host's side, the firmware is the test's side. Requests
+/// come out exactly as they went on the wire, and nothing is answered
+/// until the test replies.
+#[cfg(test)]
+pub(crate) struct MockFirmware {
+    stream: tokio::io::DuplexStream,
+    format: ResponseFormat,
+}
+
+#[cfg(test)]
+impl MockFirmware {
+    /// Create a linked (channel, firmware) pair, the firmware answering in
+    /// `format`.
+    pub fn pair(format: ResponseFormat) -> (ControlChannel, Self) {
+        let (host, firmware) = Self::link(format);
+        (ControlChannel::new(host, format), firmware)
+    }
+
+    /// Create a linked (stream, firmware) pair, for building the channel
+    /// some other way.
+    pub fn link(format: ResponseFormat) -> (tokio::io::DuplexStream, Self) {
+        let (host, device) = tokio::io::duplex(256);
+        let firmware = Self {
+            stream: device,
+            format,
+        };
+        (host, firmware)
+    }
+
+    /// Receive the next request frame the host wrote, length prefix
+    /// included.
+    pub async fn recv_frame(&mut self) -> Vec<u8> {
+        use tokio::io::AsyncReadExt;
+
+        let mut frame = vec![0u8; 2];
+        self.stream
+            .read_exact(&mut frame)
+            .await
+            .expect("channel closed");
+        let total = u16::from_le_bytes([frame[0], frame[1]]) as usize;
+        frame.resize(total, 0);
+        self.stream
+            .read_exact(&mut frame[2..])
+            .await
+            .expect("channel closed");
+        frame
+    }
+
+    /// Answer request `id` successfully with `data`, framed in the
+    /// firmware's format.
+    pub async fn reply(&mut self, id: u8, data: &[u8]) {
+        let mut response = match self.format {
+            ResponseFormat::V0 => {
+                let mut response = (data.len() as u16).to_le_bytes().to_vec();
+                response.push(id);
+                response
+            }
+            ResponseFormat::V1 => {
+                let mut response = ((4 + data.len()) as u16).to_le_bytes().to_vec();
+                response.extend([id, 0x00]);
+                response
+            }
+        };
+        response.extend(data);
+        self.send_raw(&response).await;
+    }
+
+    /// Wait for the host to hang up, asserting it never wrote anything.
+    pub async fn expect_silence(&mut self) {
+        use tokio::io::AsyncReadExt;
+
+        let mut written = Vec::new();
+        self.stream
+            .read_to_end(&mut written)
+            .await
+            .expect("read failed");
+        assert!(written.is_empty(), "host wrote {written:02x?}");
+    }
+
+    /// Write raw bytes to the host, for malformed or split responses.
+    pub async fn send_raw(&mut self, bytes: &[u8]) {
+        use tokio::io::AsyncWriteExt;
+
+        self.stream.write_all(bytes).await.expect("channel closed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::super::gpio::BitaxeRawGpioController;
+    use super::super::i2c::BitaxeRawI2c;
+    use super::super::{I2CCommand, Page};
+    use super::*;
+    use crate::hw_trait::gpio::{Gpio, GpioPin, PinValue};
+    use crate::hw_trait::i2c::I2c;
+
+    /// Serve the far end of `stream` as v1 firmware would, with GPIO pins
+    /// that hold what is written and a single I2C device whose registers
+    /// hold what is written.
+    async fn firmware(mut stream: impl AsyncRead + AsyncWrite + Unpin) {
+        let mut pins = [0u8; 16];
+        let mut registers = [0u8; 256];
+        loop {
+            let mut len = [0u8; 2];
+            if stream.read_exact(&mut len).await.is_err() {
+                return;
+            }
+            let mut request = vec![0u8; u16::from_le_bytes(len) as usize - 2];
+            stream.read_exact(&mut request).await.unwrap();
+            let [id, _bus, page, command, ref data @ ..] = request[..] else {
+                panic!("short request: {request:02x?}");
+            };
+
+            let reply = match (page, data) {
+                (p, [value]) if p == Page::GPIO as u8 => {
+                    pins[command as usize] = *value;
+                    vec![*value]
+                }
+                (p, []) if p == Page::GPIO as u8 => vec![pins[command as usize]],
+                (p, [_addr, reg, values @ ..])
+                    if p == Page::I2C as u8 && command == I2CCommand::Write as u8 =>
+                {
+                    let start = *reg as usize;
+                    registers[start..start + values.len()].copy_from_slice(values);
+                    vec![]
+                }
+                (p, [_addr, reg, count])
+                    if p == Page::I2C as u8 && command == I2CCommand::WriteRead as u8 =>
+                {
+                    let start = *reg as usize;
+                    registers[start..start + *count as usize].to_vec()
+                }
+                (p, _) if p == Page::I2C as u8 => vec![],
+                _ => panic!("unexpected request: {request:02x?}"),
+            };
+
+            let total = (4 + reply.len()) as u16;
+            let mut response = total.to_le_bytes().to_vec();
+            response.extend([id, 0x00]);
+            response.extend(reply);
+            stream.write_all(&response).await.unwrap();
+        }
+    }
+
+    /// Drive GPIO and I2C through `channel`, as a board's startup does.
+    async fn exercise(channel: ControlChannel) {
+        let mut gpio = BitaxeRawGpioController::new(channel.clone());
+        let mut reset = gpio.pin(0).await.unwrap();
+        reset.write(PinValue::High).await.unwrap();
+        assert_eq!(reset.read().await.unwrap(), PinValue::High);
+        reset.write(PinValue::Low).await.unwrap();
+        assert_eq!(reset.read().await.unwrap(), PinValue::Low);
+
+        let mut i2c = BitaxeRawI2c::new(channel);
+        i2c.set_frequency(400_000).await.unwrap();
+        i2c.write(0x4c, &[0x10, 0xab, 0xcd]).await.unwrap();
+        let mut read = [0u8; 2];
+        i2c.write_read(0x4c, &[0x10], &mut read).await.unwrap();
+        assert_eq!(read, [0xab, 0xcd]);
+    }
+
+    #[tokio::test]
+    async fn protocol_runs_over_an_in_memory_transport() {
+        let (host, device) = tokio::io::duplex(256);
+        tokio::spawn(firmware(device));
+        exercise(ControlChannel::new(host, ResponseFormat::V1)).await;
+    }
+
+    #[tokio::test]
+    async fn protocol_runs_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            firmware(stream).await;
+        });
+
+        let transport = ControlTransport::Tcp { addr };
+        exercise(transport.open(ResponseFormat::V1).await.unwrap()).await;
+    }
+
+    #[tokio::test]
+    async fn gpio_write_goes_out_as_one_exact_frame() {
+        let (channel, mut firmware) = MockFirmware::pair(ResponseFormat::V1);
+        let mut gpio = BitaxeRawGpioController::new(channel);
+        let mut reset = gpio.pin(0).await.unwrap();
+        let write = tokio::spawn(async move { reset.write(PinValue::High).await });
+
+        let frame = firmware.recv_frame().await;
+        // len 7, id, bus 0, GPIO page, pin 0, high
+        assert_eq!(frame, [0x07, 0x00, frame[2], 0x00, 0x06, 0x00, 0x01]);
+        firmware.reply(frame[2], &[0x01]).await;
+        write.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn i2c_read_goes_out_as_one_exact_frame_in_either_format() {
+        for format in [ResponseFormat::V0, ResponseFormat::V1] {
+            let (channel, mut firmware) = MockFirmware::pair(format);
+            let mut i2c = BitaxeRawI2c::new(channel);
+            let read = tokio::spawn(async move {
+                let mut buf = [0u8; 2];
+                i2c.read(0x4c, &mut buf).await.map(|()| buf)
+            });
+
+            let frame = firmware.recv_frame().await;
+            // len 8, id, bus 0, I2C page, read, address, count
+            assert_eq!(
+                frame,
+                [0x08, 0x00, frame[2], 0x00, 0x05, 0x30, 0x4c, 0x02],
+                "{format:?}"
+            );
+            firmware.reply(frame[2], &[0x12, 0x34]).await;
+            assert_eq!(read.await.unwrap().unwrap(), [0x12, 0x34], "{format:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn hung_up_firmware_marks_the_link_lost() {
+        let (channel, mut firmware) = MockFirmware::pair(ResponseFormat::V1);
+        let mut gpio = BitaxeRawGpioController::new(channel.clone());
+        let mut reset = gpio.pin(0).await.unwrap();
+
+        let read = tokio::spawn(async move { (reset.read().await, reset) });
+        let frame = firmware.recv_frame().await;
+        firmware.send_raw(&[0x04, 0x00, frame[2], 0xee]).await;
+        let (result, mut reset) = read.await.unwrap();
+        // A bad reply is the firmware's fault, not the link's.
+        assert!(result.is_err());
+        assert!(!channel.link_lost());
+
+        // The cable comes out.
+        drop(firmware);
+        assert!(reset.read().await.is_err());
+        assert!(channel.link_lost());
+    }
+
+    #[tokio::test]
+    async fn supported_firmware_is_spoken_to_in_its_format() {
+        // Firmware 1.0 answers in v1.
+        let version = DeviceVersion::from_bcd(0x0510);
+        let (host, mut firmware) = MockFirmware::link(version.response_format().unwrap());
+        let channel = ControlChannel::negotiate(host, &version).unwrap();
+
+        let mut gpio = BitaxeRawGpioController::new(channel);
+        let mut reset = gpio.pin(0).await.unwrap();
+        let read = tokio::spawn(async move { reset.read().await });
+        let frame = firmware.recv_frame().await;
+        firmware.reply(frame[2], &[0x01]).await;
+        assert_eq!(read.await.unwrap().unwrap(), PinValue::High);
+    }
+
+    #[tokio::test]
+    async fn newer_protocol_firmware_is_refused_before_any_frame() {
+        let version = DeviceVersion::from_bcd(0x0520);
+        let (host, mut firmware) = MockFirmware::link(ResponseFormat::V1);
+        let Err(err) = ControlChannel::negotiate(host, &version) else {
+            panic!("firmware {version} accepted");
+        };
+        assert_eq!(err.found, version);
+        firmware.expect_silence().await;
+
+        // The transport isn't even opened.
+        let transport = ControlTransport::serial("/dev/nonexistent");
+        assert!(matches!(
+            transport.connect(&version).await,
+            Err(ConnectError::Unsupported(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unreachable_tcp_bridge_is_an_error() {
+        // Bound then dropped, so nothing is listening.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let transport = ControlTransport::Tcp { addr };
+        assert!(transport.open(ResponseFormat::V1).await.is_err());
+    }
+}
@@ -1,7 +1,25 @@
 //! Device version from bitaxe-raw's bcdDevice encoding.
+//!
+//! The firmware has no control-channel command for its version; it
+//! reports it in the USB device descriptor, which the host reads at
+//! enumeration before the control channel is open. That descriptor is the
+//! protocol handshake: [`DeviceVersion::response_format`] picks the framing
+//! the firmware speaks, or refuses firmware whose protocol is newer than
+//! this host knows, before a single frame is sent.
 
 use std::fmt;
 
+use thiserror::Error;
+
+use super::ResponseFormat;
+
+/// Newest firmware minor version whose control protocol this host speaks.
+///
+/// The firmware bumps its minor version when the control protocol changes
+/// (minor 1 brought the v1 response format) and only its patch version
+/// otherwise.
+pub const NEWEST_PROTOCOL_MINOR: u8 = 1;
+
 /// Device version decoded from the bitaxe-raw bcdDevice convention.
 ///
 /// USB bcdDevice is a vendor-defined release number. The bitaxe-raw
@@ -40,6 +58,60 @@ impl DeviceVersion {
     pub fn firmware_patch(&self) -> u8 {
         self.firmware_patch
     }
+
+    /// Check the firmware is at least `minimum` (`(minor, patch)`).
+    ///
+    /// Only firmware fields are compared; boards pick their minimum per
+    /// hardware, so the revision is not considered.
+    pub fn require_firmware(&self, minimum: (u8, u8)) -> Result<(), UnsupportedFirmware> {
+        if (self.firmware_minor, self.firmware_patch) >= minimum {
+            Ok(())
+        } else {
+            Err(UnsupportedFirmware {
+                found: *self,
+                minimum,
+            })
+        }
+    }
+
+    /// The response format this firmware answers in.
+    ///
+    /// Minor 0 answers in v0, minors up to [`NEWEST_PROTOCOL_MINOR`] in
+    /// v1. A later minor may frame its responses differently, so it is
+    /// refused rather than guessed at.
+    pub fn response_format(&self) -> Result<ResponseFormat, UnsupportedProtocol> {
+        match self.firmware_minor {
+            0 => Ok(ResponseFormat::V0),
+            minor if minor <= NEWEST_PROTOCOL_MINOR => Ok(ResponseFormat::V1),
+            _ => Err(UnsupportedProtocol { found: *self }),
+        }
+    }
+}
+
+/// Board firmware older than the host supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error(
+    "firmware {}.{} is older than the minimum supported {}.{}",
+    found.firmware_minor,
+    found.firmware_patch,
+    minimum.0,
+    minimum.1
+)]
+pub struct UnsupportedFirmware {
+    pub found: DeviceVersion,
+    pub minimum: (u8, u8),
+}
+
+/// Board firmware speaking a control protocol newer than the host's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error(
+    "firmware {}.{} speaks a newer control protocol than this host \
+     supports (newest known is {NEWEST_PROTOCOL_MINOR}.x); update mujina",
+    found.firmware_minor,
+    found.firmware_patch
+)]
+pub struct UnsupportedProtocol {
+    pub found: DeviceVersion,
 }
 
 impl fmt::Display for DeviceVersion {
@@ -74,6 +146,48 @@ mod tests {
         assert_eq!(v.firmware_patch(), 0x0A);
     }
 
+    #[test]
+    fn require_firmware() {
+        let minimum = (1, 2);
+        for bcd in [0x0512, 0x0513, 0x0520, 0x0590] {
+            assert_eq!(
+                DeviceVersion::from_bcd(bcd).require_firmware(minimum),
+                Ok(()),
+                "{bcd:#06x}"
+            );
+        }
+
+        // A newer hardware revision does not make up for old firmware.
+        let old = DeviceVersion::from_bcd(0xFF11);
+        let err = old.require_firmware(minimum).unwrap_err();
+        assert_eq!(err.found, old);
+        assert_eq!(
+            err.to_string(),
+            "firmware 1.1 is older than the minimum supported 1.2"
+        );
+        assert!(
+            DeviceVersion::from_bcd(0x0509)
+                .require_firmware(minimum)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn response_format_follows_the_firmware_minor() {
+        let format = |bcd| DeviceVersion::from_bcd(bcd).response_format();
+        assert_eq!(format(0x0509), Ok(ResponseFormat::V0));
+        assert_eq!(format(0x0510), Ok(ResponseFormat::V1));
+        assert_eq!(format(0xFF1F), Ok(ResponseFormat::V1));
+
+        let err = format(0x0520).unwrap_err();
+        assert_eq!(err.found, DeviceVersion::from_bcd(0x0520));
+        assert_eq!(
+            err.to_string(),
+            "firmware 2.0 speaks a newer control protocol than this host \
+             supports (newest known is 1.x); update mujina"
+        );
+    }
+
     #[test]
     fn display() {
         let v = DeviceVersion::from_bcd(0x0510);
@@ -9,3 +9,4 @@ pub mod bitaxe_raw;
 // Re-export commonly used types
 pub use bitaxe_raw::channel::ControlChannel;
 pub use bitaxe_raw::gpio::{BitaxeRawGpioController, BitaxeRawGpioPin};
+pub use bitaxe_raw::transport::ControlTransport;
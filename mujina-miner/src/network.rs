@@ -0,0 +1,116 @@
+//! Which Bitcoin network is being mined.
+//!
+//! Mainnet is the default. Testnet, signet and regtest are for trying the
+//! solo path without real coins at stake. The network decides which payout
+//! addresses are valid and the easiest block target a job can carry.
+//!
+//! Share difficulty is unaffected: Stratum pools and bitcoind both measure
+//! difficulty against mainnet's difficulty-1 target on every network, so
+//! [`Difficulty`](crate::types::Difficulty) keeps that base. A regtest
+//! block is simply far below difficulty 1.
+
+use std::env;
+
+use bitcoin::{Network, Target};
+
+use crate::tracing::prelude::*;
+
+/// Read the network from `MUJINA_NETWORK`, warning and falling back to
+/// mainnet on unknown values.
+pub fn from_env() -> Network {
+    let Ok(value) = env::var("MUJINA_NETWORK") else {
+        return Network::Bitcoin;
+    };
+    from_name(&value).unwrap_or_else(|| {
+        warn!(value = %value, "Invalid MUJINA_NETWORK, using mainnet");
+        Network::Bitcoin
+    })
+}
+
+/// Look up a network by the names users and bitcoind give it.
+pub fn from_name(name: &str) -> Option<Network> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "mainnet" | "main" | "bitcoin" => Some(Network::Bitcoin),
+        "testnet" | "test" | "testnet3" => Some(Network::Testnet),
+        "testnet4" => Some(Network::Testnet4),
+        "signet" => Some(Network::Signet),
+        "regtest" => Some(Network::Regtest),
+        _ => None,
+    }
+}
+
+/// Name a network the way users know it.
+pub fn name(network: Network) -> &'static str {
+    match network {
+        Network::Bitcoin => "mainnet",
+        Network::Testnet => "testnet",
+        Network::Testnet4 => "testnet4",
+        Network::Signet => "signet",
+        Network::Regtest => "regtest",
+    }
+}
+
+/// Whether `network` can require a block at `target`.
+///
+/// A target easier than the network's limit means the job comes from some
+/// other network, so a block found on it would be worthless here.
+pub fn allows_target(network: Network, target: Target) -> bool {
+    target <= network.params().max_attainable_target
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::CompactTarget;
+    use serial_test::serial;
+
+    use super::*;
+
+    /// Regtest's minimum-difficulty bits.
+    const REGTEST_BITS: u32 = 0x207fffff;
+
+    #[test]
+    fn names_round_trip() {
+        for network in [
+            Network::Bitcoin,
+            Network::Testnet,
+            Network::Testnet4,
+            Network::Signet,
+            Network::Regtest,
+        ] {
+            assert_eq!(from_name(name(network)), Some(network));
+        }
+        assert_eq!(from_name("main"), Some(Network::Bitcoin));
+        assert_eq!(from_name("Test"), Some(Network::Testnet));
+        assert_eq!(from_name("liquid"), None);
+    }
+
+    #[test]
+    fn regtest_targets_only_fit_regtest() {
+        let trivial = Target::from_compact(CompactTarget::from_consensus(REGTEST_BITS));
+        assert!(allows_target(Network::Regtest, trivial));
+        for network in [Network::Bitcoin, Network::Testnet, Network::Signet] {
+            assert!(!allows_target(network, trivial), "{network}");
+        }
+
+        // Difficulty 1 is within every network's limit.
+        for network in [Network::Bitcoin, Network::Testnet, Network::Regtest] {
+            assert!(allows_target(network, Target::MAX), "{network}");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn network_from_env() {
+        let var = "MUJINA_NETWORK";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(from_env(), Network::Bitcoin);
+            env::set_var(var, "regtest");
+            assert_eq!(from_env(), Network::Regtest);
+            env::set_var(var, "moonnet");
+            assert_eq!(from_env(), Network::Bitcoin);
+            env::remove_var(var);
+        }
+    }
+}
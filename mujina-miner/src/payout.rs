@@ -0,0 +1,146 @@
+//! Solo-mining payout address validation.
+//!
+//! A solo pool pays a found block to the address given as the worker
+//! username. A typo there loses the reward for good, and nothing fails
+//! until a block is found, so the address is checked when the daemon
+//! starts: it must parse as a Bitcoin address and belong to the network
+//! being mined.
+
+use std::str::FromStr;
+
+use bitcoin::address::{NetworkUnchecked, ParseError};
+use bitcoin::{Address, Network};
+use thiserror::Error;
+
+use crate::network;
+
+/// Why a configured payout address was refused.
+#[derive(Debug, Error)]
+pub enum PayoutAddressError {
+    /// Not a Bitcoin address at all.
+    #[error("payout address {address:?} is not a valid Bitcoin address: {source}")]
+    Malformed {
+        address: String,
+        #[source]
+        source: ParseError,
+    },
+
+    /// A valid address, but for another network.
+    #[error(
+        "payout address {address} is not a {} address; a block found \
+         with it could not be spent",
+        network::name(*network)
+    )]
+    WrongNetwork { address: String, network: Network },
+}
+
+/// Parse `text` as an address that can receive coins on `network`.
+///
+/// Testnet and signet share address formats, so an address valid for one
+/// is accepted for the other.
+pub fn parse_payout_address(text: &str, network: Network) -> Result<Address, PayoutAddressError> {
+    let address = text.trim();
+    let unchecked = Address::<NetworkUnchecked>::from_str(address).map_err(|source| {
+        PayoutAddressError::Malformed {
+            address: address.to_string(),
+            source,
+        }
+    })?;
+    unchecked
+        .require_network(network)
+        .map_err(|_| PayoutAddressError::WrongNetwork {
+            address: address.to_string(),
+            network,
+        })
+}
+
+/// Extract and check the payout address from a solo pool username.
+///
+/// Solo pools take `address` or `address.worker`; the worker suffix only
+/// names the miner in the pool's stats.
+pub fn payout_address_from_username(
+    username: &str,
+    network: Network,
+) -> Result<Address, PayoutAddressError> {
+    let address = username.split_once('.').map_or(username, |(a, _)| a);
+    parse_payout_address(address, network)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAINNET: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+    const MAINNET_P2PKH: &str = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2";
+    const TESTNET: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    #[test]
+    fn accepts_addresses_for_the_configured_network() {
+        for address in [MAINNET, MAINNET_P2PKH] {
+            let parsed = parse_payout_address(address, Network::Bitcoin).unwrap();
+            assert_eq!(parsed.to_string(), address);
+        }
+        let parsed = parse_payout_address(TESTNET, Network::Testnet).unwrap();
+        assert_eq!(parsed.to_string(), TESTNET);
+    }
+
+    #[test]
+    fn rejects_network_mismatch() {
+        let err = parse_payout_address(TESTNET, Network::Bitcoin).unwrap_err();
+        assert!(matches!(
+            err,
+            PayoutAddressError::WrongNetwork {
+                network: Network::Bitcoin,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "payout address {TESTNET} is not a mainnet address; a block \
+                 found with it could not be spent"
+            )
+        );
+
+        let err = parse_payout_address(MAINNET, Network::Testnet).unwrap_err();
+        assert!(matches!(err, PayoutAddressError::WrongNetwork { .. }));
+    }
+
+    #[test]
+    fn rejects_malformed_address() {
+        // One character off: the bech32 checksum catches it.
+        let typo = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdp";
+        for address in [typo, "mujina-testing", ""] {
+            let err = parse_payout_address(address, Network::Bitcoin).unwrap_err();
+            assert!(
+                matches!(err, PayoutAddressError::Malformed { .. }),
+                "{address:?}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn configured_network_decides_validity() {
+        let regtest = network::from_name("regtest").unwrap();
+        let address = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+        assert!(parse_payout_address(address, regtest).is_ok());
+        for other in [Network::Bitcoin, Network::Testnet] {
+            assert!(matches!(
+                parse_payout_address(address, other),
+                Err(PayoutAddressError::WrongNetwork { .. })
+            ));
+        }
+
+        // Legacy testnet addresses are shared with regtest.
+        let legacy = "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn";
+        assert!(parse_payout_address(legacy, regtest).is_ok());
+        assert!(parse_payout_address(legacy, Network::Testnet).is_ok());
+    }
+
+    #[test]
+    fn worker_suffix_is_ignored() {
+        let username = format!("{MAINNET}.bitaxe1");
+        let parsed = payout_address_from_username(&username, Network::Bitcoin).unwrap();
+        assert_eq!(parsed.to_string(), MAINNET);
+    }
+}
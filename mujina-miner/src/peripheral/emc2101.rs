@@ -123,6 +123,10 @@ impl<I: I2c> Emc2101<I> {
     /// EMC2101 uses 6-bit PWM duty cycle (0-63 = 0-100%)
     const PWM_MAX: u8 = 63;
 
+    // Diode fault readings of the external channel (datasheet section 6.5)
+    const FAULT_OPEN_CIRCUIT: u16 = 0x3F8;
+    const FAULT_SHORT: u16 = 0x3FF;
+
     /// Create a new EMC2101 driver with default address
     pub fn new(i2c: I) -> Self {
         Self {
@@ -214,21 +218,13 @@ impl<I: I2c> Emc2101<I> {
     /// Read external temperature in Celsius
     /// This is typically connected to the ASIC's temperature diode
     pub async fn get_external_temperature(&mut self) -> Result<f32> {
-        let high = self.read_register(regs::EXTERNAL_TEMP_HIGH).await?;
-        let low = self.read_register(regs::EXTERNAL_TEMP_LOW).await?;
+        let raw = self.read_external_raw().await?;
 
-        // Temperature is in 11-bit format with 0.125 degC resolution
-        // High byte is integer part, low byte bits 7-5 are fractional
-        const FRACTION_BITS: u8 = 3;
-        const FRACTION_SHIFT: u8 = 5;
-        let raw = ((high as u16) << FRACTION_BITS) | ((low as u16) >> FRACTION_SHIFT);
-
-        // Diode fault detection (datasheet section 6.5)
-        const FAULT_OPEN_CIRCUIT: u16 = 0x3F8;
-        const FAULT_SHORT: u16 = 0x3FF;
         match raw {
-            FAULT_OPEN_CIRCUIT => return Err(HwError::Other("external diode open circuit".into())),
-            FAULT_SHORT => return Err(HwError::Other("external diode short".into())),
+            Self::FAULT_OPEN_CIRCUIT => {
+                return Err(HwError::Other("external diode open circuit".into()));
+            }
+            Self::FAULT_SHORT => return Err(HwError::Other("external diode short".into())),
             _ => {}
         }
 
@@ -247,6 +243,12 @@ impl<I: I2c> Emc2101<I> {
         Ok(temp)
     }
 
+    /// Whether a diode is connected to the external channel. An absent
+    /// one reads as an open circuit; a shorted one is present but broken.
+    pub async fn has_external_diode(&mut self) -> Result<bool> {
+        Ok(self.read_external_raw().await? != Self::FAULT_OPEN_CIRCUIT)
+    }
+
     /// Read internal temperature in Celsius
     pub async fn get_internal_temperature(&mut self) -> Result<f32> {
         let raw = self.read_register(regs::INTERNAL_TEMP).await?;
@@ -297,6 +299,18 @@ impl<I: I2c> Emc2101<I> {
 
     // Helper methods for register access
 
+    /// Read the external channel's raw 11-bit reading.
+    async fn read_external_raw(&mut self) -> Result<u16> {
+        let high = self.read_register(regs::EXTERNAL_TEMP_HIGH).await?;
+        let low = self.read_register(regs::EXTERNAL_TEMP_LOW).await?;
+
+        // Temperature is in 11-bit format with 0.125 degC resolution
+        // High byte is integer part, low byte bits 7-5 are fractional
+        const FRACTION_BITS: u8 = 3;
+        const FRACTION_SHIFT: u8 = 5;
+        Ok(((high as u16) << FRACTION_BITS) | ((low as u16) >> FRACTION_SHIFT))
+    }
+
     async fn read_register(&mut self, reg: u8) -> Result<u8> {
         let mut buf = [0u8; 1];
         self.i2c.write_read(self.address, &[reg], &mut buf).await?;
@@ -307,3 +321,75 @@ impl<I: I2c> Emc2101<I> {
         self.i2c.write(self.address, &[reg, value]).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+
+    use super::*;
+
+    /// A fan controller's registers.
+    #[derive(Clone)]
+    struct Registers(Arc<Mutex<[u8; 256]>>);
+
+    impl Registers {
+        fn with(values: &[(u8, u8)]) -> Self {
+            let mut registers = [0; 256];
+            for &(reg, value) in values {
+                registers[usize::from(reg)] = value;
+            }
+            Self(Arc::new(Mutex::new(registers)))
+        }
+    }
+
+    #[async_trait]
+    impl I2c for Registers {
+        async fn write(&mut self, _addr: u8, data: &[u8]) -> Result<()> {
+            if let [reg, value] = *data {
+                self.0.lock().unwrap()[usize::from(reg)] = value;
+            }
+            Ok(())
+        }
+
+        async fn read(&mut self, _addr: u8, _buffer: &mut [u8]) -> Result<()> {
+            Ok(())
+        }
+
+        async fn write_read(&mut self, _addr: u8, write: &[u8], read: &mut [u8]) -> Result<()> {
+            read[0] = self.0.lock().unwrap()[usize::from(write[0])];
+            Ok(())
+        }
+
+        async fn set_frequency(&mut self, _hz: u32) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_diode_reads_as_absent() {
+        // 0x3F8, the open-circuit code, split across the two registers.
+        let open = [
+            (regs::EXTERNAL_TEMP_HIGH, 0x7F),
+            (regs::EXTERNAL_TEMP_LOW, 0x00),
+        ];
+        let mut fan = Emc2101::new(Registers::with(&open));
+        assert!(!fan.has_external_diode().await.unwrap());
+        assert!(fan.get_external_temperature().await.is_err());
+
+        // A diode at 45.5 C, and a shorted one, which is there but broken.
+        let mut fan = Emc2101::new(Registers::with(&[
+            (regs::EXTERNAL_TEMP_HIGH, 45),
+            (regs::EXTERNAL_TEMP_LOW, 0x80),
+        ]));
+        assert!(fan.has_external_diode().await.unwrap());
+        assert_eq!(fan.get_external_temperature().await.unwrap(), 45.5);
+        let mut fan = Emc2101::new(Registers::with(&[
+            (regs::EXTERNAL_TEMP_HIGH, 0x7F),
+            (regs::EXTERNAL_TEMP_LOW, 0xE0),
+        ]));
+        assert!(fan.has_external_diode().await.unwrap());
+        assert!(fan.get_external_temperature().await.is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! Blink code LED animation.
+//!
+//! Counts out a number of short pulses, then pauses, and repeats, like
+//! POST beep codes. Phase is derived from wall-clock time relative to a
+//! fixed epoch, as in blink, so the count isn't garbled by an interrupt.
+//! Rather than ticking, the task sleeps until the next edge.
+
+use std::time::Duration;
+
+use crate::tracing::prelude::*;
+use tokio::sync::oneshot;
+use tokio::time::{self, Instant};
+
+use super::{AnimationHandle, Resume, StopMode};
+use crate::hw_trait::rgb_led::{RgbColor, RgbLed};
+
+/// A count of pulses, on and then off for [`PULSE`](Self::PULSE) each,
+/// followed by a further [`PAUSE`](Self::PAUSE) dark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlinkCode {
+    pub pulses: u8,
+}
+
+impl BlinkCode {
+    /// Time each pulse spends on, and then off.
+    pub const PULSE: Duration = Duration::from_millis(250);
+
+    /// Extra time dark after the last pulse, so counts stand apart.
+    pub const PAUSE: Duration = Duration::from_millis(1500);
+
+    /// Length of one count and its pause.
+    pub fn cycle(&self) -> Duration {
+        Self::PULSE * 2 * u32::from(self.pulses) + Self::PAUSE
+    }
+
+    /// Whether the LED is lit `at` into a cycle, and for how much longer.
+    fn state_at(&self, at: Duration) -> (bool, Duration) {
+        let pulses_end = Self::PULSE * 2 * u32::from(self.pulses);
+        if at >= pulses_end {
+            return (false, self.cycle() - at);
+        }
+        let into_pulse =
+            Duration::from_nanos((at.as_nanos() % (Self::PULSE * 2).as_nanos()) as u64);
+        if into_pulse < Self::PULSE {
+            (true, Self::PULSE - into_pulse)
+        } else if at + Self::PULSE * 2 - into_pulse >= pulses_end {
+            // The last pulse's off time runs into the pause.
+            (false, self.cycle() - at)
+        } else {
+            (false, Self::PULSE * 2 - into_pulse)
+        }
+    }
+}
+
+/// Start a blink code animation.
+pub fn blink_code(led: Box<dyn RgbLed>, color: RgbColor, code: BlinkCode) -> AnimationHandle {
+    spawn_blink_code(led, color, code, Instant::now())
+}
+
+fn spawn_blink_code(
+    mut led: Box<dyn RgbLed>,
+    color: RgbColor,
+    code: BlinkCode,
+    epoch: Instant,
+) -> AnimationHandle {
+    let (cancel_tx, mut cancel_rx) = oneshot::channel();
+    let cycle = code.cycle();
+
+    let task = tokio::spawn(async move {
+        let mut finishing = false;
+
+        loop {
+            let now = Instant::now();
+            let into_cycle = Duration::from_nanos(
+                (now.duration_since(epoch).as_nanos() % cycle.as_nanos()) as u64,
+            );
+            let (on, remaining) = code.state_at(into_cycle);
+            let brightness = if on { 1.0 } else { 0.0 };
+            if let Err(e) = led.set(color, brightness).await {
+                warn!(error = %e, "LED blink code write failed");
+            }
+            let cycle_ends = into_cycle + remaining >= cycle;
+
+            tokio::select! {
+                _ = time::sleep_until(now + remaining) => {
+                    if finishing && cycle_ends {
+                        break;
+                    }
+                }
+                mode = &mut cancel_rx, if !finishing => match mode {
+                    Ok(StopMode::FinishCycle) => finishing = true,
+                    Ok(StopMode::Immediate) | Err(_) => break,
+                },
+            }
+        }
+
+        let state = BlinkCodeState { color, code, epoch };
+        (led as Box<dyn RgbLed>, Box::new(state) as Box<dyn Resume>)
+    });
+
+    AnimationHandle::new(cancel_tx, task)
+}
+
+/// State needed to resume a blink code animation.
+#[derive(Debug, Clone)]
+struct BlinkCodeState {
+    color: RgbColor,
+    code: BlinkCode,
+    epoch: Instant,
+}
+
+impl Resume for BlinkCodeState {
+    fn resume(self: Box<Self>, led: Box<dyn RgbLed>) -> AnimationHandle {
+        spawn_blink_code(led, self.color, self.code, self.epoch)
+    }
+}
@@ -3,7 +3,8 @@
 //! Provides composable async animations for RGB LEDs. Animations come
 //! in two flavors:
 //!
-//! - Looping (breathe, blink, hold): run indefinitely until cancelled.
+//! - Looping (breathe, blink, blink_code, hold): run indefinitely until
+//!   cancelled.
 //! - One-shot (flash): run for a bounded duration then complete on their own.
 //!
 //! Both return an [`AnimationHandle`] with a uniform API. An
@@ -12,6 +13,7 @@
 //! animation after the one-shot completes.
 
 pub mod blink;
+pub mod blink_code;
 pub mod breathe;
 pub mod flash;
 pub mod hold;
@@ -23,6 +25,7 @@ use tokio::task::JoinHandle;
 use crate::hw_trait::rgb_led::RgbLed;
 
 pub use blink::blink;
+pub use blink_code::{BlinkCode, blink_code};
 pub use breathe::{breathe, breathe_brightness};
 pub use flash::flash;
 pub use hold::hold;
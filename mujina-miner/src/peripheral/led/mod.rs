@@ -5,4 +5,4 @@ pub mod calibrated;
 pub mod status_led;
 
 pub use calibrated::{CalibratedLed, ColorProfile};
-pub use status_led::{Status, StatusLed};
+pub use status_led::{ErrorCode, Status, StatusLed};
@@ -3,12 +3,29 @@
 //! Translates high-level board status into LED colors and animations.
 //! The board owns a [`StatusLed`] and calls its methods directly
 //! as status changes occur.
+//!
+//! # Error codes
+//!
+//! A board that knows what went wrong shows [`Status::Error`] rather than
+//! the plain fault blink, so an operator at the rack can tell the cause
+//! without a screen. Each class is a count of short red pulses, a quarter
+//! second on and a quarter off, followed by a pause of a second and a
+//! half before the count repeats:
+//!
+//! | Pulses | Error class                 | Meaning                               |
+//! |--------|-----------------------------|---------------------------------------|
+//! | 2      | [`ErrorCode::Overtemp`]     | board too hot                         |
+//! | 3      | [`ErrorCode::PoolDown`]     | no pool to mine for                   |
+//! | 4      | [`ErrorCode::CommsError`]   | chips or control link not answering   |
+//!
+//! A single-pulse code is left out on purpose: repeated, it would look
+//! like the even [`Status::Fault`] blink.
 
 use std::time::Duration;
 
 use crate::tracing::prelude::*;
 
-use super::animation::{self, AnimationHandle};
+use super::animation::{self, AnimationHandle, BlinkCode};
 use crate::hw_trait::rgb_led::{RgbColor, RgbLed};
 
 /// High-level board status for LED indication.
@@ -19,6 +36,27 @@ pub enum Status {
     Hashing,
     Fault,
     Identify,
+    Error(ErrorCode),
+}
+
+/// A class of error with its own blink code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Overtemp,
+    PoolDown,
+    CommsError,
+}
+
+impl ErrorCode {
+    /// The blink code this class is shown with.
+    pub fn blink_code(self) -> BlinkCode {
+        let pulses = match self {
+            Self::Overtemp => 2,
+            Self::PoolDown => 3,
+            Self::CommsError => 4,
+        };
+        BlinkCode { pulses }
+    }
 }
 
 /// Indicates board status using an RGB LED.
@@ -83,6 +121,7 @@ impl StatusLed {
             Status::Identify => animation::blink(led, RgbColor::BLUE, IDENTIFY_BLINK_PHASE),
             Status::Idle => animation::hold(led, RgbColor::WHITE, 1.0),
             Status::Fault => animation::blink(led, RgbColor::RED, FAULT_BLINK_PHASE),
+            Status::Error(code) => animation::blink_code(led, RgbColor::RED, code.blink_code()),
         });
     }
 }
@@ -169,22 +208,74 @@ mod tests {
         // Let it breathe some more after flash
         tokio::time::sleep(Duration::from_secs(1)).await;
 
-        let writes = writes.lock().unwrap();
-
-        // Should see white (breathing), then orange (flash), then white again (resumed)
-        let flash_idx = writes
-            .iter()
-            .position(|(c, _)| *c == RgbColor::ORANGE)
-            .expect("expected an orange flash write");
+        {
+            let writes = writes.lock().unwrap();
 
-        assert!(
-            writes[flash_idx + 1..]
+            // Should see white (breathing), then orange (flash), then white again (resumed)
+            let flash_idx = writes
                 .iter()
-                .any(|(c, _)| *c == RgbColor::WHITE),
-            "expected white writes after flash (breathing resumed)",
-        );
+                .position(|(c, _)| *c == RgbColor::ORANGE)
+                .expect("expected an orange flash write");
+
+            assert!(
+                writes[flash_idx + 1..]
+                    .iter()
+                    .any(|(c, _)| *c == RgbColor::WHITE),
+                "expected white writes after flash (breathing resumed)",
+            );
+        }
 
-        drop(writes);
         status_led.off().await;
     }
+
+    /// Records when each write happened, relative to `start`.
+    struct TimedLed {
+        start: tokio::time::Instant,
+        writes: Arc<Mutex<Vec<(Duration, f32)>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RgbLed for TimedLed {
+        async fn set(&mut self, color: RgbColor, brightness: f32) -> hw_trait::Result<()> {
+            assert_eq!(color, RgbColor::RED);
+            let at = self.start.elapsed();
+            self.writes.lock().unwrap().push((at, brightness));
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn error_classes_blink_their_codes() {
+        let ms = Duration::from_millis;
+
+        for (code, pulses) in [
+            (ErrorCode::Overtemp, 2),
+            (ErrorCode::PoolDown, 3),
+            (ErrorCode::CommsError, 4),
+        ] {
+            assert_eq!(code.blink_code(), BlinkCode { pulses });
+
+            let writes = Arc::new(Mutex::new(Vec::new()));
+            let led = TimedLed {
+                start: tokio::time::Instant::now(),
+                writes: writes.clone(),
+            };
+            let mut status_led = StatusLed::new(Box::new(led), Status::Error(code));
+
+            // Two full counts, to see the pause between them.
+            let cycle = code.blink_code().cycle();
+            assert_eq!(cycle, ms(500) * u32::from(pulses) + ms(1500));
+            tokio::time::sleep(cycle * 2 - ms(1)).await;
+
+            let mut expected = Vec::new();
+            for start in [Duration::ZERO, cycle] {
+                for pulse in 0..u32::from(pulses) {
+                    expected.push((start + ms(500) * pulse, 1.0));
+                    expected.push((start + ms(500) * pulse + ms(250), 0.0));
+                }
+            }
+            assert_eq!(*writes.lock().unwrap(), expected, "{code:?}");
+            status_led.off().await;
+        }
+    }
 }
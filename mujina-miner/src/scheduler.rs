@@ -31,26 +31,34 @@
 //! functionality is added, after which the functionality is refactored out to
 //! where it belongs.
 
+use bitcoin::BlockHash;
 use slotmap::SlotMap;
-use std::collections::HashSet;
-use std::sync::Arc;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::env;
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
 use tokio::sync::{mpsc, watch};
-
+use tokio::time::Instant;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::{StreamExt, StreamMap};
 use tokio_util::sync::CancellationToken;
 
-use crate::api::commands::SchedulerCommand;
-use crate::api_client::types::{MinerTelemetry, SourceTelemetry};
+use crate::api::commands::{PromoteError, SchedulerCommand};
+use crate::api_client::types::{
+    LatencyBucket, LoopTiming, MinerTelemetry, SchedulerState, SchedulerThreadState, SourceJob,
+    SourceTelemetry, UnsubmittedShares,
+};
 use crate::asic::hash_thread::{HashTask, HashThread, HashThreadEvent, Share};
+use crate::clock::{self, Clock};
+use crate::job_source::stale_ratio::{StaleCounts, StaleFailoverPolicy, StaleWindow};
 use crate::job_source::{
-    JobTemplate, MerkleRootKind, Share as SourceShare, SourceCommand, SourceEvent,
+    Extranonce2Range, JobTemplate, MerkleRootKind, Share as SourceShare, SourceCommand,
+    SourceEvent, SourceStats, header,
 };
 use crate::tracing::prelude::*;
 use crate::types::{
-    AlarmStatus, DebouncedAlarm, Difficulty, HashRate, HashrateEstimator, ShareRate, Target,
-    expected_time_to_share_from_target,
+    AlarmStatus, DebouncedAlarm, Difficulty, HashRate, HashrateEstimator, LatencyHistogram,
+    ShareRate, Target, expected_time_to_share_from_target, time_to_block,
 };
 
 /// Unique identifier for a job source, assigned by the scheduler.
@@ -112,6 +120,84 @@ struct TaskEntry {
 
     /// Thread this task was assigned to
     thread_id: ThreadId,
+
+    /// Hashes of shares already seen on this task, for dropping repeats
+    recent_shares: RecentShares,
+}
+
+/// Bounded memory of share hashes seen on one task.
+///
+/// A task is one job's non-overlapping slice of work on one thread, so a
+/// repeated hash means the board reported the same nonce twice (firmware
+/// bug, glitch). Submitting it would only earn a duplicate-share reject.
+/// Only the newest hashes are kept, so memory stays fixed however long the
+/// job runs; a repeat older than that is too rare to matter.
+#[derive(Debug, Default)]
+struct RecentShares {
+    order: VecDeque<BlockHash>,
+    seen: HashSet<BlockHash>,
+}
+
+impl RecentShares {
+    const CAPACITY: usize = 256;
+
+    /// Record a hash. Returns `false` if it was already seen.
+    fn insert(&mut self, hash: BlockHash) -> bool {
+        if !self.seen.insert(hash) {
+            return false;
+        }
+        self.order.push_back(hash);
+        if self.order.len() > Self::CAPACITY
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.seen.remove(&oldest);
+        }
+        true
+    }
+}
+
+/// Shares submitted from boards with none accepted yet, awaiting the
+/// pool's answer.
+///
+/// A source reports an accepted share only by job and nonce, so this is
+/// how the acceptance is traced back to a board, once, for the startup
+/// confirmation. Boards drop out as they are confirmed; while one never
+/// is, only the newest submissions are kept.
+#[derive(Debug, Default)]
+struct UnconfirmedShares {
+    /// `(source, job id, nonce, board)`, oldest first.
+    order: VecDeque<(SourceId, String, u32, String)>,
+    /// Boards with a share accepted since startup.
+    confirmed: BTreeSet<String>,
+}
+
+impl UnconfirmedShares {
+    const CAPACITY: usize = 256;
+
+    /// Note a share submitted from `board`, unless already confirmed.
+    fn submitted(&mut self, source_id: SourceId, job_id: &str, nonce: u32, board: &str) {
+        if self.confirmed.contains(board) {
+            return;
+        }
+        self.order
+            .push_back((source_id, job_id.to_string(), nonce, board.to_string()));
+        if self.order.len() > Self::CAPACITY {
+            self.order.pop_front();
+        }
+    }
+
+    /// Match an accepted share to its board. Returns the board if this is
+    /// its first accepted share.
+    fn accepted(&mut self, source_id: SourceId, job_id: &str, nonce: u32) -> Option<String> {
+        let index = self
+            .order
+            .iter()
+            .position(|(s, j, n, _)| *s == source_id && j == job_id && *n == nonce)?;
+        let (_, _, _, board) = self.order.remove(index)?;
+        self.order.retain(|(_, _, _, b)| *b != board);
+        self.confirmed.insert(board.clone());
+        Some(board)
+    }
 }
 
 /// Registration message for adding a job source to the scheduler.
@@ -131,12 +217,22 @@ pub struct SourceRegistration {
 
     /// Command sender for this source (SubmitShare, etc.)
     pub command_tx: mpsc::Sender<SourceCommand>,
+
+    /// Connection measurements, for sources that publish them.
+    pub stats_rx: Option<watch::Receiver<SourceStats>>,
+
+    /// Named pool this source serves, `None` for the default pool. Only
+    /// the boards assigned to it get its jobs; see [`PoolAssignment`].
+    pub pool: Option<String>,
 }
 
 /// Item the backplane sends to the scheduler on the thread-registration channel.
 pub enum ThreadRegistration {
-    /// A new hash thread to schedule.
-    Thread(Box<dyn HashThread>),
+    /// A new hash thread to schedule, and the name of its board.
+    Thread {
+        thread: Box<dyn HashThread>,
+        board: String,
+    },
 
     /// Initial enumeration across all transports is complete.
     ///
@@ -145,6 +241,368 @@ pub enum ThreadRegistration {
     InitialEnumerationComplete,
 }
 
+/// What the miner is optimizing for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MiningMode {
+    /// Steady share flow for a pool's payout accounting.
+    #[default]
+    Pool,
+
+    /// Solo mining with little hashrate, where a block is a lottery win and
+    /// shares only matter as a progress signal. Every hash should be one
+    /// no other thread is trying, and the best share and any block found are
+    /// called out in the log.
+    Lottery,
+}
+
+impl MiningMode {
+    /// Read the mode from `MUJINA_MINING_MODE` (`pool` or `lottery`),
+    /// warning and falling back to `pool` on other values.
+    pub fn from_env() -> Self {
+        match env::var("MUJINA_MINING_MODE").as_deref() {
+            Err(_) | Ok("pool") => Self::Pool,
+            Ok("lottery") => Self::Lottery,
+            Ok(other) => {
+                warn!(value = %other, "Invalid MUJINA_MINING_MODE, using pool");
+                Self::Pool
+            }
+        }
+    }
+}
+
+/// How long every pool may be unreachable before the outage action is taken,
+/// when not configured.
+pub const DEFAULT_POOL_OUTAGE_GRACE: Duration = Duration::from_secs(5 * 60);
+
+/// What to do with the hash threads while no pool is reachable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoolOutageAction {
+    /// Leave the threads as they are. Work resumes as soon as a pool sends
+    /// a job; nothing found in the meantime can be submitted.
+    #[default]
+    Keep,
+
+    /// Idle every thread once the grace period runs out, to stop spending
+    /// power on work no pool will accept.
+    Idle,
+}
+
+/// What to do about a sustained loss of every pool, and after how long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolOutagePolicy {
+    pub action: PoolOutageAction,
+    pub grace: Duration,
+}
+
+impl Default for PoolOutagePolicy {
+    fn default() -> Self {
+        Self {
+            action: PoolOutageAction::default(),
+            grace: DEFAULT_POOL_OUTAGE_GRACE,
+        }
+    }
+}
+
+impl PoolOutagePolicy {
+    /// Read the action from `MUJINA_POOL_OUTAGE_ACTION` (`keep` or `idle`)
+    /// and the grace period from `MUJINA_POOL_OUTAGE_GRACE_SECS`, warning
+    /// and using the default on invalid values.
+    pub fn from_env() -> Self {
+        let action = match env::var("MUJINA_POOL_OUTAGE_ACTION").as_deref() {
+            Err(_) | Ok("keep") => PoolOutageAction::Keep,
+            Ok("idle") => PoolOutageAction::Idle,
+            Ok(other) => {
+                warn!(value = %other, "Invalid MUJINA_POOL_OUTAGE_ACTION, using keep");
+                PoolOutageAction::Keep
+            }
+        };
+        let grace = match env::var("MUJINA_POOL_OUTAGE_GRACE_SECS") {
+            Err(_) => DEFAULT_POOL_OUTAGE_GRACE,
+            Ok(value) => match value.parse::<u64>() {
+                Ok(secs) => Duration::from_secs(secs),
+                Err(_) => {
+                    warn!(
+                        value = %value,
+                        default_secs = DEFAULT_POOL_OUTAGE_GRACE.as_secs(),
+                        "Invalid MUJINA_POOL_OUTAGE_GRACE_SECS, using default"
+                    );
+                    DEFAULT_POOL_OUTAGE_GRACE
+                }
+            },
+        };
+        Self { action, grace }
+    }
+}
+
+/// What to do when a source's share difficulty stays too high for the
+/// hashrate to find shares at a useful rate.
+///
+/// A slow board at a difficulty meant for a farm can go an hour or more
+/// between shares; from the pool's side it looks dead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HighDifficultyAction {
+    /// Log a warning and leave the difficulty to the pool.
+    #[default]
+    Warn,
+
+    /// Also ask the source for a difficulty the hashrate can meet.
+    Suggest,
+}
+
+impl HighDifficultyAction {
+    /// Read the action from `MUJINA_HIGH_DIFFICULTY_ACTION` (`warn` or
+    /// `suggest`), warning and falling back to `warn` on other values.
+    pub fn from_env() -> Self {
+        match env::var("MUJINA_HIGH_DIFFICULTY_ACTION").as_deref() {
+            Err(_) | Ok("warn") => Self::Warn,
+            Ok("suggest") => Self::Suggest,
+            Ok(other) => {
+                warn!(value = %other, "Invalid MUJINA_HIGH_DIFFICULTY_ACTION, using warn");
+                Self::Warn
+            }
+        }
+    }
+}
+
+/// Interval between telemetry snapshots when not configured.
+pub const DEFAULT_TELEMETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Interval between telemetry snapshots while the host is loaded, when
+/// not configured.
+pub const DEFAULT_LOADED_TELEMETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the scheduler publishes its telemetry snapshot.
+///
+/// Building a snapshot walks every source and thread, which on a small,
+/// busy host is time the hot path could use. While the host is loaded it
+/// is built at the coarser `loaded_interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TelemetryCadence {
+    /// Interval between snapshots. `None` publishes none, so telemetry
+    /// only changes when an API command answers with a fresh one.
+    pub interval: Option<Duration>,
+    /// Interval between snapshots while the host is loaded. Never finer
+    /// than `interval`.
+    pub loaded_interval: Duration,
+}
+
+impl Default for TelemetryCadence {
+    fn default() -> Self {
+        Self {
+            interval: Some(DEFAULT_TELEMETRY_INTERVAL),
+            loaded_interval: DEFAULT_LOADED_TELEMETRY_INTERVAL,
+        }
+    }
+}
+
+impl TelemetryCadence {
+    /// Read the interval from `MUJINA_TELEMETRY_INTERVAL_SECS` (0
+    /// disables telemetry) and the loaded interval from
+    /// `MUJINA_TELEMETRY_LOADED_INTERVAL_SECS`, warning and using the
+    /// default on invalid values.
+    pub fn from_env() -> Self {
+        let mut cadence = Self::default();
+        if let Ok(value) = env::var("MUJINA_TELEMETRY_INTERVAL_SECS") {
+            match value.parse::<u64>() {
+                Ok(0) => cadence.interval = None,
+                Ok(secs) => cadence.interval = Some(Duration::from_secs(secs)),
+                Err(_) => {
+                    warn!(value = %value, "Invalid MUJINA_TELEMETRY_INTERVAL_SECS, using default")
+                }
+            }
+        }
+        if let Ok(value) = env::var("MUJINA_TELEMETRY_LOADED_INTERVAL_SECS") {
+            match value.parse::<u64>() {
+                Ok(secs) if secs > 0 => cadence.loaded_interval = Duration::from_secs(secs),
+                _ => warn!(
+                    value = %value,
+                    "Invalid MUJINA_TELEMETRY_LOADED_INTERVAL_SECS, using default"
+                ),
+            }
+        }
+        cadence
+    }
+
+    /// Time until the next snapshot after one taken now, or `None` when
+    /// telemetry is disabled.
+    fn next(&self, host_loaded: bool) -> Option<Duration> {
+        let interval = self.interval?;
+        Some(if host_loaded {
+            interval.max(self.loaded_interval)
+        } else {
+            interval
+        })
+    }
+}
+
+/// Whether each task's work space is logged, from
+/// `MUJINA_TRACE_WORK_ASSIGNMENT`.
+///
+/// Read once; set to any value to enable.
+static TRACE_ASSIGNMENTS: LazyLock<bool> =
+    LazyLock::new(|| env::var_os("MUJINA_TRACE_WORK_ASSIGNMENT").is_some());
+
+/// Shares a thread's measured hashrate needs before it is trusted, from
+/// `MUJINA_HASHRATE_MIN_SHARES`.
+///
+/// Read once.
+static HASHRATE_MIN_SHARES: LazyLock<usize> =
+    LazyLock::new(HashrateEstimator::min_samples_from_env);
+
+/// Whether the time each scheduler loop iteration takes is recorded, from
+/// `MUJINA_SCHEDULER_LOOP_TIMING`.
+///
+/// Read once; set to any value to enable.
+static LOOP_TIMING: LazyLock<bool> =
+    LazyLock::new(|| env::var_os("MUJINA_SCHEDULER_LOOP_TIMING").is_some());
+
+/// Loop iterations taking longer than this are logged when loop timing is
+/// on. Anything near it holds up every board's shares and new work.
+const SLOW_ITERATION: Duration = Duration::from_millis(100);
+
+/// How much of each share found the scheduler logs, from
+/// `MUJINA_SHARE_LOG_FORMAT`.
+///
+/// Read once.
+static SHARE_LOG_FORMAT: LazyLock<ShareLogFormat> = LazyLock::new(ShareLogFormat::from_env);
+
+/// Detail of the debug record logged for each share found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShareLogFormat {
+    /// Source, job, nonce, hash and difficulties.
+    #[default]
+    Compact,
+
+    /// Also the rolled fields and the full block header, enough to
+    /// recompute the hash by hand.
+    Extended,
+}
+
+impl ShareLogFormat {
+    /// Read the format from `MUJINA_SHARE_LOG_FORMAT` (`compact` or
+    /// `extended`), warning and falling back to compact on other values.
+    pub fn from_env() -> Self {
+        match env::var("MUJINA_SHARE_LOG_FORMAT").as_deref() {
+            Err(_) | Ok("compact") => Self::Compact,
+            Ok("extended") => Self::Extended,
+            Ok(other) => {
+                warn!(value = %other, "Invalid MUJINA_SHARE_LOG_FORMAT, using compact");
+                Self::Compact
+            }
+        }
+    }
+
+    /// Log a share found on `template` for `source`.
+    fn log(self, source: &str, template: &JobTemplate, share: &Share) {
+        let share_difficulty = Difficulty::from_hash(&share.hash);
+        let threshold = Difficulty::from_target(template.share_target);
+        let nonce = format!("{:#x}", share.nonce);
+        if self == Self::Compact {
+            debug!(
+                source = %source,
+                job_id = %template.id,
+                nonce = %nonce,
+                hash = %share.hash,
+                share_difficulty = %share_difficulty,
+                threshold = %threshold,
+                "Share found"
+            );
+            return;
+        }
+
+        let merkle_root = match (&template.merkle_root, share.extranonce2) {
+            (MerkleRootKind::Fixed(root), _) => Some(*root),
+            (MerkleRootKind::Computed(_), Some(en2)) => template.compute_merkle_root(&en2).ok(),
+            (MerkleRootKind::Computed(_), None) => None,
+        };
+        let header = merkle_root
+            .map(|root| header::build(template, root, share.version, share.ntime, share.nonce));
+        debug!(
+            source = %source,
+            job_id = %template.id,
+            nonce = %nonce,
+            hash = %share.hash,
+            share_difficulty = %share_difficulty,
+            threshold = %threshold,
+            version = format!("{:08x}", share.version.to_consensus()),
+            ntime = share.ntime,
+            extranonce2 = share.extranonce2.map(Vec::<u8>::from).map(hex::encode),
+            prev_blockhash = %template.prev_blockhash,
+            nbits = format!("{:08x}", template.bits.to_consensus()),
+            merkle_root = merkle_root.map(|root| root.to_string()),
+            header = header.map(|h| hex::encode(header::to_bytes(&h))),
+            "Share found"
+        );
+    }
+}
+
+/// Which named pool each board mines on.
+///
+/// Boards are named as in telemetry, such as `bitaxe-<serial>`. A board
+/// without an assignment mines on the primary pool: the default pool
+/// unless another has been promoted through the API.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolAssignment {
+    boards: HashMap<String, String>,
+    primary: Option<String>,
+}
+
+impl PoolAssignment {
+    /// Assign each board to a pool, given `(board, pool)` pairs.
+    pub fn new(boards: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self {
+            boards: boards.into_iter().collect(),
+            primary: None,
+        }
+    }
+
+    /// Read `MUJINA_BOARD_POOLS`, a comma-separated list of `board=pool`
+    /// pairs, warning about and skipping malformed entries.
+    pub fn from_env() -> Self {
+        let Ok(value) = env::var("MUJINA_BOARD_POOLS") else {
+            return Self::default();
+        };
+        Self::new(
+            value
+                .split(',')
+                .filter(|e| !e.trim().is_empty())
+                .filter_map(|entry| match entry.split_once('=') {
+                    Some((board, pool)) if !board.trim().is_empty() && !pool.trim().is_empty() => {
+                        Some((board.trim().to_string(), pool.trim().to_string()))
+                    }
+                    _ => {
+                        warn!(entry = %entry, "Invalid MUJINA_BOARD_POOLS entry, ignoring");
+                        None
+                    }
+                }),
+        )
+    }
+
+    /// The pool `board` mines on, `None` for the default pool.
+    pub fn pool_for(&self, board: &str) -> Option<&str> {
+        self.boards
+            .get(board)
+            .or(self.primary.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Make `pool` the one unassigned boards mine on, `None` for the
+    /// default pool. Returns the primary pool it replaces.
+    pub fn promote(&mut self, pool: Option<String>) -> Option<String> {
+        std::mem::replace(&mut self.primary, pool)
+    }
+
+    /// The pool unassigned boards mine on, `None` for the default pool.
+    pub fn primary(&self) -> Option<&str> {
+        self.primary.as_deref()
+    }
+
+    /// Every pool some board is assigned to.
+    pub fn pools(&self) -> impl Iterator<Item = &str> {
+        self.boards.values().map(String::as_str)
+    }
+}
+
 /// Internal scheduler tracking for a registered source.
 #[derive(Debug)]
 struct SourceEntry {
@@ -160,8 +618,17 @@ struct SourceEntry {
     /// Last job received from this source (for assigning to newly-arriving threads)
     last_job: Option<Arc<JobTemplate>>,
 
+    /// Connection measurements published by the source, if any.
+    stats_rx: Option<watch::Receiver<SourceStats>>,
+
     /// Debounced alarm for high-difficulty warnings.
     difficulty_alarm: DebouncedAlarm,
+
+    /// EN2 slices of the last job handed out, each assigned to one thread.
+    en2_slices: Vec<Extranonce2Range>,
+
+    /// Named pool the source serves, `None` for the default pool.
+    pool: Option<String>,
 }
 
 /// Whether to update alongside existing work or replace it.
@@ -178,9 +645,15 @@ struct ThreadEntry {
     thread: Box<dyn HashThread>,
     hashrate: HashrateEstimator,
 
+    /// Board the thread runs on, for its pool assignment.
+    board: String,
+
     /// Hashrate the thread declared via `ExpectedHashRate`, `None` until its
     /// first report.
     expected: Option<HashRate>,
+
+    /// Share target of the task last assigned, `None` until the first.
+    share_target: Option<Target>,
 }
 
 /// Core scheduler state.
@@ -209,10 +682,53 @@ struct Scheduler {
 
     /// Mining paused
     paused: bool,
+
+    /// What the miner is optimizing for
+    mode: MiningMode,
+
+    /// Tracks loss of every pool against the outage policy
+    outage: OutageMonitor,
+
+    /// Response to a share difficulty too high for the hashrate
+    high_difficulty: HighDifficultyAction,
+
+    /// Which pool each board mines on
+    assignment: PoolAssignment,
+
+    /// When to leave a primary pool whose shares go stale, if ever
+    stale_failover: Option<StaleFailoverPolicy>,
+
+    /// Each pool's recent stale ratio, keyed as in `assignment`
+    stale_windows: HashMap<Option<String>, StaleWindow>,
+
+    /// Whether the primary pool's stale ratio has been warned about with
+    /// nowhere better to go
+    stale_stuck_warned: bool,
+
+    /// Shares awaiting the first acceptance from their board
+    unconfirmed: UnconfirmedShares,
+
+    /// How often telemetry snapshots are published
+    telemetry: TelemetryCadence,
+
+    /// Whether the host is loaded, for the coarser telemetry cadence
+    host_loaded: watch::Receiver<bool>,
+
+    /// Time of day for daily share counts
+    clock: Arc<dyn Clock>,
+
+    /// Log the work space handed to each board with every task
+    trace_assignments: bool,
+
+    /// Detail logged for each share found
+    share_log: ShareLogFormat,
+
+    /// Time taken by each loop iteration, when recorded
+    loop_timing: Option<LatencyHistogram>,
 }
 
 impl Scheduler {
-    fn new() -> Self {
+    fn new(mode: MiningMode) -> Self {
         Self {
             sources: SlotMap::new(),
             threads: SlotMap::new(),
@@ -221,9 +737,121 @@ impl Scheduler {
             last_thread_count: 0,
             startup_gate: StartupGate::new(),
             paused: false,
+            mode,
+            outage: OutageMonitor::new(PoolOutagePolicy::default()),
+            high_difficulty: HighDifficultyAction::default(),
+            assignment: PoolAssignment::default(),
+            stale_failover: None,
+            stale_windows: HashMap::new(),
+            stale_stuck_warned: false,
+            unconfirmed: UnconfirmedShares::default(),
+            telemetry: TelemetryCadence::default(),
+            host_loaded: watch::channel(false).1,
+            clock: clock::system(),
+            trace_assignments: *TRACE_ASSIGNMENTS,
+            share_log: *SHARE_LOG_FORMAT,
+            loop_timing: LOOP_TIMING.then(LatencyHistogram::new),
+        }
+    }
+
+    /// Whether any source has work to hand out.
+    ///
+    /// With no sources registered there is nothing to lose, so that counts
+    /// as reachable.
+    fn pools_reachable(&self) -> bool {
+        self.sources.is_empty() || self.sources.values().any(|s| s.last_job.is_some())
+    }
+
+    /// Start or end the outage clock from the sources' current state.
+    fn observe_pool_outage(&mut self, now: Instant) {
+        let reachable = self.pools_reachable();
+        if self.outage.observe(reachable, now) {
+            info!("Pool reachable again, resuming hash threads");
         }
     }
 
+    /// Idle every thread for a pool outage that outlasted its grace period.
+    async fn idle_for_pool_outage(&mut self, share_channels: &mut ShareStream) {
+        warn!(
+            grace_secs = self.outage.policy.grace.as_secs(),
+            threads = self.threads.len(),
+            "All pools unreachable past the grace period, idling hash threads"
+        );
+        self.remove_tasks_where(share_channels, |_| true);
+        for entry in self.threads.values_mut() {
+            if let Err(e) = entry.thread.go_idle().await {
+                warn!(thread = %entry.thread.name(), error = %e, "Failed to idle thread");
+            }
+        }
+        self.outage.record_idled();
+    }
+
+    /// Sample each pool's stale ratio, and move unassigned boards off the
+    /// primary pool if its ratio stayed over the policy's maximum for a
+    /// full window.
+    ///
+    /// The boards go to the first other pool, in the order the pools were
+    /// configured, that has work and isn't known to be over the maximum
+    /// itself.
+    async fn check_stale_failover(&mut self, now: Instant, share_channels: &mut ShareStream) {
+        let Some(policy) = self.stale_failover else {
+            return;
+        };
+        let mut pools: Vec<(Option<String>, StaleCounts, bool)> = Vec::new();
+        for source in self.sources.values() {
+            let counts = source
+                .stats_rx
+                .as_ref()
+                .map(|rx| StaleCounts::from_stats(&rx.borrow()))
+                .unwrap_or_default();
+            let has_job = source.last_job.is_some();
+            match pools.iter_mut().find(|(pool, ..)| *pool == source.pool) {
+                Some((_, total, any_job)) => {
+                    *total += counts;
+                    *any_job |= has_job;
+                }
+                None => pools.push((source.pool.clone(), counts, has_job)),
+            }
+        }
+        for (pool, counts, _) in &pools {
+            self.stale_windows
+                .entry(pool.clone())
+                .or_insert_with(|| StaleWindow::new(policy.window))
+                .observe(now, *counts);
+        }
+
+        let primary = self.assignment.primary().map(str::to_string);
+        let ratio_of = |pool: &Option<String>| self.stale_windows.get(pool)?.ratio();
+        let Some(ratio) = ratio_of(&primary).filter(|&r| r > policy.max_ratio) else {
+            self.stale_stuck_warned = false;
+            return;
+        };
+        let next = pools
+            .iter()
+            .filter(|(pool, _, has_job)| *pool != primary && *has_job)
+            .find(|(pool, ..)| ratio_of(pool).is_none_or(|r| r <= policy.max_ratio))
+            .map(|(pool, ..)| pool.clone());
+        let stale_pct = format!("{:.1}", ratio * 100.0);
+        let Some(next) = next else {
+            if !std::mem::replace(&mut self.stale_stuck_warned, true) {
+                warn!(
+                    pool = primary.as_deref().unwrap_or("default"),
+                    stale_pct, "Primary pool's shares going stale, but no better pool to move to"
+                );
+            }
+            return;
+        };
+        warn!(
+            from = primary.as_deref().unwrap_or("default"),
+            to = next.as_deref().unwrap_or("default"),
+            stale_pct,
+            window_secs = policy.window.as_secs(),
+            "Primary pool's shares going stale, failing over"
+        );
+        // The pool is known to serve this source; promotion can't fail.
+        let _ = self.promote_pool(next, share_channels).await;
+    }
+
     /// Aggregate measured hashrate from per-thread estimators.
     ///
     /// Returns the truth: zero if no shares have been recorded yet.
@@ -246,41 +874,148 @@ impl Scheduler {
             .sum()
     }
 
-    /// Expected hashrate allocated to one source.
-    ///
-    /// Degenerate today: the source gets the full aggregate. The signature
-    /// takes a source so a proportional split across sources is a localized
-    /// change here.
-    fn allocated_hashrate(&self, _source_id: SourceId) -> HashRate {
-        self.expected_hashrate()
+    /// Expected hashrate allocated to one source: that of the threads on
+    /// boards assigned to its pool. Sources serving the same pool are each
+    /// told the pool's full rate.
+    fn allocated_hashrate(&self, source_id: SourceId) -> HashRate {
+        self.threads
+            .values()
+            .filter(|entry| self.serves(source_id, entry))
+            .filter_map(|entry| entry.expected)
+            .sum()
+    }
+
+    /// Whether `source_id` hands its jobs to the thread: the thread's
+    /// board is assigned to the source's pool.
+    fn serves(&self, source_id: SourceId, thread: &ThreadEntry) -> bool {
+        self.sources
+            .get(source_id)
+            .is_some_and(|source| source.pool.as_deref() == self.assignment.pool_for(&thread.board))
     }
 
-    /// Threads eligible for work: those that have reported an expected hashrate.
-    fn eligible_thread_ids(&self) -> impl Iterator<Item = ThreadId> + '_ {
+    /// Threads eligible for work from a source: those that have reported an
+    /// expected hashrate, on boards assigned to the source's pool.
+    fn eligible_thread_ids(&self, source_id: SourceId) -> impl Iterator<Item = ThreadId> + '_ {
         self.threads
             .iter()
-            .filter(|(_, entry)| entry.expected.is_some())
+            .filter(move |(_, entry)| entry.expected.is_some() && self.serves(source_id, entry))
             .map(|(id, _)| id)
     }
 
+    /// Shares found but not submitted, by reason, across the scheduler,
+    /// every thread, and every source, since the last reset.
+    fn unsubmitted_shares(&self) -> UnsubmittedShares {
+        let live = self.live_counts();
+        let base = &self.stats.live_at_reset;
+        // A retiring thread's counts move into the scheduler's, so the
+        // base comes off the sum of both.
+        UnsubmittedShares {
+            stale: (self.stats.stale_shares + live.flushed_shares + live.stale_avoided)
+                .saturating_sub(base.flushed_shares + base.stale_avoided),
+            below_target: self.stats.below_target_shares,
+            duplicate: self.stats.duplicate_shares,
+            hardware_error: (self.stats.retired_hardware_errors + live.hardware_errors)
+                .saturating_sub(base.hardware_errors),
+            queue_dropped: live.queue_dropped.saturating_sub(base.queue_dropped),
+        }
+    }
+
+    /// Counts kept by the threads and sources rather than the scheduler.
+    fn live_counts(&self) -> LiveCounts {
+        let mut counts = LiveCounts::default();
+        for entry in self.threads.values() {
+            let status = entry.thread.status();
+            counts.hardware_errors += status.hardware_errors;
+            counts.flushed_shares += status.flushed_shares;
+        }
+        for rx in self.sources.values().filter_map(|s| s.stats_rx.as_ref()) {
+            let stats = rx.borrow();
+            counts.stale_avoided += stats.shares_stale_avoided;
+            counts.queue_dropped += stats.shares_dropped;
+        }
+        counts
+    }
+
     /// Build a [`MinerTelemetry`] snapshot from current scheduler state.
     ///
     /// The scheduler contributes aggregate stats and source info. Board
     /// and thread details come from the backplane, not the scheduler, so
-    /// `boards` is left empty here.
+    /// `boards` is left empty here; which boards are confirmed goes in
+    /// `confirmed_boards` for the API to mark them.
     fn compute_miner_telemetry(&mut self) -> MinerTelemetry {
+        let now = self.clock.now_utc();
+        let hashrate = self.measured_hashrate();
+        let network_target = self
+            .sources
+            .values()
+            .find_map(|s| s.last_job.as_ref())
+            .map(|j| Target::from_compact(j.bits));
         MinerTelemetry {
             uptime_secs: self.stats.start_time.elapsed().as_secs(),
-            hashrate: u64::from(self.measured_hashrate()),
+            hashrate: u64::from(hashrate),
+            network_difficulty: network_target.map(|t| Difficulty::from_target(t).as_f64()),
+            expected_time_to_block_secs: network_target
+                .and_then(|t| time_to_block(hashrate, t))
+                .map(|d| d.as_secs_f64()),
             shares_submitted: self.stats.shares_submitted,
+            duplicate_shares: self.stats.duplicate_shares,
+            unsubmitted_shares: self.unsubmitted_shares(),
+            best_share_difficulty: self.stats.best_share.map(Difficulty::as_f64),
+            blocks_found: self.stats.blocks_found,
             paused: self.paused,
+            // Filled in by the API from the registry's energy meter.
+            energy_kwh: 0.0,
             boards: vec![],
+            confirmed_boards: self.unconfirmed.confirmed.clone(),
             sources: self
                 .sources
                 .values()
                 .map(|s| SourceTelemetry {
                     name: s.name.clone(),
                     url: s.url.clone(),
+                    connected: s.last_job.is_some(),
+                    time_to_first_job_secs: s
+                        .stats_rx
+                        .as_ref()
+                        .and_then(|rx| rx.borrow().time_to_first_job)
+                        .map(|d| d.as_secs_f64()),
+                    shares_dropped: s
+                        .stats_rx
+                        .as_ref()
+                        .map_or(0, |rx| rx.borrow().shares_dropped),
+                    shares_stale_avoided: s
+                        .stats_rx
+                        .as_ref()
+                        .map_or(0, |rx| rx.borrow().shares_stale_avoided),
+                    shares_accepted: s
+                        .stats_rx
+                        .as_ref()
+                        .map_or(0, |rx| rx.borrow().shares_accepted),
+                    shares_rejected: s
+                        .stats_rx
+                        .as_ref()
+                        .map_or(0, |rx| rx.borrow().shares_rejected),
+                    shares_accepted_today: s
+                        .stats_rx
+                        .as_ref()
+                        .map_or(0, |rx| rx.borrow().accepted_today.get(now)),
+                    shares_rejected_today: s
+                        .stats_rx
+                        .as_ref()
+                        .map_or(0, |rx| rx.borrow().rejected_today.get(now)),
+                    failure: s
+                        .stats_rx
+                        .as_ref()
+                        .and_then(|rx| rx.borrow().failure.clone()),
+                    difficulty_oscillating: s
+                        .stats_rx
+                        .as_ref()
+                        .is_some_and(|rx| rx.borrow().difficulty_oscillating),
+                    network_blocks_seen: s
+                        .stats_rx
+                        .as_ref()
+                        .map_or(0, |rx| rx.borrow().network_blocks_seen),
+                    reconnects: s.stats_rx.as_ref().map_or(0, |rx| rx.borrow().reconnects),
                     difficulty: s.last_job.as_ref().map(|j| {
                         let d = Difficulty::from_target(j.share_target).as_f64();
                         if d >= 10.0 { d.round() } else { d }
@@ -290,6 +1025,63 @@ impl Scheduler {
         }
     }
 
+    /// Count a loop iteration that took `took` from wake-up, when loop
+    /// timing is on, logging it when slow.
+    fn record_iteration(&mut self, took: Duration) {
+        let Some(histogram) = self.loop_timing.as_mut() else {
+            return;
+        };
+        histogram.record(took);
+        if took > SLOW_ITERATION {
+            warn!(took_ms = took.as_millis(), "Slow scheduler loop iteration");
+        }
+    }
+
+    /// Snapshot the per-thread inputs and outputs of share target selection,
+    /// for debugging through the API.
+    fn debug_state(&mut self) -> SchedulerState {
+        let threads = self
+            .threads
+            .values_mut()
+            .map(|entry| {
+                let measured = entry.hashrate.settled_hashrate();
+                let hashrate = measured.or(entry.expected).unwrap_or_default();
+                let bound = |rate: ShareRate| {
+                    (!hashrate.is_zero()).then(|| rate.to_difficulty(hashrate).as_f64())
+                };
+                SchedulerThreadState {
+                    name: entry.thread.name().to_string(),
+                    expected_hashrate: entry.expected.map(u64::from),
+                    measured_hashrate: measured.map(u64::from),
+                    hashrate_shares: entry.hashrate.sample_count(),
+                    share_difficulty: entry
+                        .share_target
+                        .map(|target| Difficulty::from_target(target).as_f64()),
+                    min_difficulty: bound(FLOOD_CAP_RATE),
+                    max_difficulty: bound(MEASUREMENT_SHARE_RATE),
+                }
+            })
+            .collect();
+        let micros = |d: Duration| u64::try_from(d.as_micros()).unwrap_or(u64::MAX);
+        let loop_timing = self.loop_timing.as_ref().map(|histogram| LoopTiming {
+            iterations: histogram.count(),
+            mean_us: micros(histogram.mean()),
+            max_us: micros(histogram.max()),
+            buckets: histogram
+                .buckets()
+                .map(|(bound, count)| LatencyBucket {
+                    le_us: bound.map(micros),
+                    count,
+                })
+                .collect(),
+        });
+        SchedulerState {
+            paused: self.paused,
+            threads,
+            loop_timing,
+        }
+    }
+
     /// Compute the per-thread scheduler target for HashTask.
     ///
     /// Clamps the source's pool difficulty between a measurement floor
@@ -304,7 +1096,7 @@ impl Scheduler {
         let measurement_target = MEASUREMENT_SHARE_RATE.to_target(hashrate);
         let flood_cap_target = FLOOD_CAP_RATE.to_target(hashrate);
 
-        source_target.clamp(measurement_target, flood_cap_target)
+        clamp_target(source_target, measurement_target, flood_cap_target)
     }
 
     /// Pairs every source's command sender with its current hashrate allocation.
@@ -358,18 +1150,32 @@ impl Scheduler {
             url: registration.url,
             command_tx: registration.command_tx,
             last_job: None,
+            stats_rx: registration.stats_rx,
             difficulty_alarm: DebouncedAlarm::new(HIGH_DIFFICULTY_DEBOUNCE),
+            en2_slices: Vec::new(),
+            pool: registration.pool.clone(),
         });
         source_events.insert(source_id, ReceiverStream::new(registration.event_rx));
-        debug!(source_id = ?source_id, name = %registration.name, "Source registered");
+        debug!(
+            source_id = ?source_id,
+            name = %registration.name,
+            pool = registration.pool.as_deref().unwrap_or("default"),
+            "Source registered"
+        );
 
-        // Hashrate is not yet split across sources: each is told the full
-        // aggregate, which over-suggests difficulty to every pool but the one
-        // that should get the whole hashrate.
-        if self.sources.len() > 1 {
+        // Hashrate is not split across sources serving the same pool: each
+        // is told the pool's full rate, which over-suggests difficulty to
+        // every one of them but the one that should get it all.
+        let same_pool = self
+            .sources
+            .values()
+            .filter(|s| s.pool == registration.pool)
+            .count();
+        if same_pool > 1 {
             warn!(
-                sources = self.sources.len(),
-                "Multiple sources active, but hashrate is not split across them"
+                sources = same_pool,
+                pool = registration.pool.as_deref().unwrap_or("default"),
+                "Multiple sources serve one pool, but hashrate is not split across them"
             );
         }
 
@@ -440,6 +1246,13 @@ impl Scheduler {
                         "Share difficulty too high for hashrate \
                          (expected > 5 min between shares)"
                     );
+                    if self.high_difficulty == HighDifficultyAction::Suggest {
+                        info!(source = %source_name, "Asking source for a lower difficulty");
+                        if let Err(e) = source.command_tx.send(SourceCommand::LowerDifficulty).await
+                        {
+                            warn!(source = %source_name, error = %e, "Failed to ask for lower difficulty");
+                        }
+                    }
                 }
                 AlarmStatus::Resolved => {
                     info!(
@@ -461,14 +1274,23 @@ impl Scheduler {
         // TODO: A thread that becomes eligible later is handed the full EN2
         // range on its first report, overlapping these slices until the next
         // job re-splits.
-        let eligible: Vec<ThreadId> = self.eligible_thread_ids().collect();
+        let eligible: Vec<ThreadId> = self.eligible_thread_ids(source_id).collect();
         if eligible.is_empty() {
             debug!(source = %source_name, "No eligible threads yet, job cached for later");
             return;
         }
-        let en2_slices = full_en2_range
-            .split(eligible.len())
-            .expect("Failed to split EN2 range among threads");
+        let en2_slices = partition_en2(&full_en2_range, eligible.len());
+        if en2_slices.len() < eligible.len() {
+            warn!(
+                source = %source_name,
+                threads = eligible.len(),
+                extranonce2_values = full_en2_range.len(),
+                "Extranonce2 space too small for every thread, some left idle"
+            );
+        }
+        if let Some(source) = self.sources.get_mut(source_id) {
+            source.en2_slices = en2_slices.clone();
+        }
 
         for (thread_id, en2_range) in eligible.into_iter().zip(en2_slices) {
             let starting_en2 = en2_range.iter().next();
@@ -495,6 +1317,9 @@ impl Scheduler {
                 ntime: template.time,
                 share_tx,
             };
+            if self.trace_assignments {
+                trace_assignment(&source_name, &entry.board, entry.thread.name(), &hash_task);
+            }
 
             let result = match mode {
                 AssignMode::Update => entry.thread.update_task(hash_task).await,
@@ -504,10 +1329,12 @@ impl Scheduler {
             if let Err(e) = result {
                 error!(thread = %entry.thread.name(), error = %e, "Failed to assign task");
             } else {
+                entry.share_target = Some(share_target);
                 let task_id = self.tasks.insert(TaskEntry {
                     source_id,
                     template: template.clone(),
                     thread_id,
+                    recent_shares: RecentShares::default(),
                 });
                 share_channels.insert(task_id, ReceiverStream::new(share_rx));
             }
@@ -526,6 +1353,7 @@ impl Scheduler {
         // Clear cached job so newly-arriving threads don't get stale work
         if let Some(source) = self.sources.get_mut(source_id) {
             source.last_job = None;
+            source.en2_slices.clear();
         }
 
         // Remove tasks for this source (channels close, stale shares fail)
@@ -535,29 +1363,67 @@ impl Scheduler {
     /// Handle a share arriving from a task's channel.
     async fn handle_share(&mut self, task_id: TaskId, share: Share) {
         // Look up task context for routing
-        let Some(task_entry) = self.tasks.get(task_id) else {
+        let Some(task_entry) = self.tasks.get_mut(task_id) else {
             // Task was removed (ReplaceJob/ClearJobs) but share arrived
             // before channel closed. This is normal; just drop the share.
+            self.stats.stale_shares += 1;
             trace!(task_id = ?task_id, "Share for removed task (dropped)");
             return;
         };
 
+        // Drop a repeated nonce before it counts as work or reaches the pool
+        if !task_entry.recent_shares.insert(share.hash) {
+            self.stats.duplicate_shares += 1;
+            warn!(
+                thread = %self.threads.get(task_entry.thread_id).map(|t| t.thread.name()).unwrap_or("unknown"),
+                job_id = %task_entry.template.id,
+                nonce = format!("{:#x}", share.nonce),
+                "Duplicate share from board (dropped)"
+            );
+            return;
+        }
+        let task_entry = &self.tasks[task_id];
+
         // Extract fields for logging (share may be consumed on submission)
         let nonce = share.nonce;
         let hash = share.hash;
         let share_difficulty = Difficulty::from_hash(&hash);
         let threshold = Difficulty::from_target(task_entry.template.share_target);
 
-        debug!(
-            source = %self.sources.get(task_entry.source_id).map(|s| s.name.as_str()).unwrap_or("unknown"),
-            job_id = %task_entry.template.id,
-            nonce = format!("{:#x}", nonce),
-            hash = %hash,
-            share_difficulty = %share_difficulty,
-            threshold = %threshold,
-            "Share found"
+        self.share_log.log(
+            self.sources
+                .get(task_entry.source_id)
+                .map_or("unknown", |s| s.name.as_str()),
+            &task_entry.template,
+            &share,
         );
 
+        if self
+            .stats
+            .best_share
+            .is_none_or(|best| share_difficulty > best)
+        {
+            self.stats.best_share = Some(share_difficulty);
+            if self.mode == MiningMode::Lottery {
+                info!(
+                    difficulty = %share_difficulty,
+                    network_difficulty = %Difficulty::from_target(task_entry.template.target()),
+                    "New best share."
+                );
+            }
+        }
+
+        if task_entry.template.target().is_met_by(hash) {
+            self.stats.blocks_found += 1;
+            info!(
+                source = %self.sources.get(task_entry.source_id).map(|s| s.name.as_str()).unwrap_or("unknown"),
+                job_id = %task_entry.template.id,
+                hash = %hash,
+                difficulty = %share_difficulty,
+                "BLOCK FOUND! Submitting to source."
+            );
+        }
+
         // Feed share work to per-thread hashrate estimator
         if let Some(entry) = self.threads.get_mut(task_entry.thread_id) {
             entry.hashrate.record(share.expected_work);
@@ -569,7 +1435,15 @@ impl Scheduler {
 
             // Submit share to originating source
             if let Some(source) = self.sources.get(task_entry.source_id) {
-                let source_share = SourceShare::from((share, task_entry.template.id.clone()));
+                let source_share = SourceShare::from((share, task_entry.template.as_ref()));
+                if let Some(entry) = self.threads.get(task_entry.thread_id) {
+                    self.unconfirmed.submitted(
+                        task_entry.source_id,
+                        &source_share.job_id,
+                        source_share.nonce,
+                        &entry.board,
+                    );
+                }
 
                 if let Err(e) = source
                     .command_tx
@@ -588,6 +1462,7 @@ impl Scheduler {
                 error!(source_id = ?task_entry.source_id, "Share for unknown source");
             }
         } else {
+            self.stats.below_target_shares += 1;
             trace!(
                 source = %self.sources.get(task_entry.source_id).map(|s| s.name.as_str()).unwrap_or("unknown"),
                 job_id = %task_entry.template.id,
@@ -599,9 +1474,21 @@ impl Scheduler {
         }
     }
 
-    /// Handle an event from a hash thread.
-    async fn handle_thread_event(
-        &mut self,
+    /// Handle a share the source reports accepted, confirming its board
+    /// is mining if it is the board's first.
+    fn handle_share_accepted(&mut self, source_id: SourceId, job_id: &str, nonce: u32) {
+        if let Some(board) = self.unconfirmed.accepted(source_id, job_id, nonce) {
+            info!(
+                board = %board,
+                source = %self.sources.get(source_id).map_or("unknown", |s| s.name.as_str()),
+                "Mining confirmed: first share accepted from board."
+            );
+        }
+    }
+
+    /// Handle an event from a hash thread.
+    async fn handle_thread_event(
+        &mut self,
         thread_id: ThreadId,
         event: HashThreadEvent,
         share_channels: &mut ShareStream,
@@ -675,6 +1562,7 @@ impl Scheduler {
     async fn handle_new_thread(
         &mut self,
         mut thread: Box<dyn HashThread>,
+        board: String,
         thread_events: &mut ThreadEventStream,
     ) {
         let event_rx = thread
@@ -682,10 +1570,18 @@ impl Scheduler {
             .expect("Thread missing event receiver");
 
         let thread_name = thread.name().to_string();
+        debug!(
+            thread = %thread_name,
+            board = %board,
+            pool = self.assignment.pool_for(&board).unwrap_or("default"),
+            "Thread assigned to pool"
+        );
         let thread_id = self.threads.insert(ThreadEntry {
             thread,
-            hashrate: HashrateEstimator::new(HASHRATE_WINDOW),
+            board,
+            hashrate: HashrateEstimator::with_min_samples(HASHRATE_WINDOW, *HASHRATE_MIN_SHARES),
             expected: None,
+            share_target: None,
         });
         self.startup_gate.record_registered();
         thread_events.insert(thread_id, ReceiverStream::new(event_rx));
@@ -706,37 +1602,49 @@ impl Scheduler {
 
     /// Assign each source's cached job to a newly-eligible thread.
     ///
-    /// Called from the thread's first ExpectedHashRate report. The thread takes
-    /// the full EN2 range for each source, overlapping the other threads until
-    /// the next job resplits the range.
+    /// Called from the thread's first ExpectedHashRate report. In pool mode
+    /// the thread takes the full EN2 range for each source, overlapping the
+    /// other threads until the next job resplits the range. In lottery mode
+    /// it takes a share of another thread's slice instead; see
+    /// [`take_unsearched_slice`].
     async fn assign_cached_jobs_to_thread(
         &mut self,
         thread_id: ThreadId,
         thread_name: &str,
         share_channels: &mut ShareStream,
     ) {
-        let thread_hashrate = {
+        let (thread_hashrate, pool) = {
             let entry = self
                 .threads
                 .get_mut(thread_id)
                 .expect("thread present for cached-job assignment");
-            entry
+            let hashrate = entry
                 .hashrate
                 .settled_hashrate()
                 .or(entry.expected)
-                .unwrap_or_default()
+                .unwrap_or_default();
+            (hashrate, self.assignment.pool_for(&entry.board))
         };
 
-        for (source_id, source) in self.sources.iter() {
+        let mode = self.mode;
+        for (source_id, source) in self.sources.iter_mut() {
+            if source.pool.as_deref() != pool {
+                continue;
+            }
             let Some(template) = &source.last_job else {
                 continue;
             };
 
-            // Extract full EN2 range (new thread overlaps with others)
             let full_en2_range = match &template.merkle_root {
                 MerkleRootKind::Computed(t) => t.extranonce2_range.clone(),
                 MerkleRootKind::Fixed(_) => continue,
             };
+            let en2_range = match mode {
+                MiningMode::Pool => full_en2_range,
+                MiningMode::Lottery => {
+                    take_unsearched_slice(&mut source.en2_slices).unwrap_or(full_en2_range)
+                }
+            };
 
             let share_target =
                 Self::compute_scheduler_target(thread_hashrate, template.share_target);
@@ -744,8 +1652,8 @@ impl Scheduler {
             let (share_tx, share_rx) = mpsc::channel(32);
             let hash_task = HashTask {
                 template: template.clone(),
-                en2_range: Some(full_en2_range.clone()),
-                en2: full_en2_range.iter().next(),
+                en2: en2_range.iter().next(),
+                en2_range: Some(en2_range),
                 share_target,
                 ntime: template.time,
                 share_tx,
@@ -755,13 +1663,18 @@ impl Scheduler {
                 .threads
                 .get_mut(thread_id)
                 .expect("Just inserted thread");
+            if self.trace_assignments {
+                trace_assignment(&source.name, &entry.board, thread_name, &hash_task);
+            }
             if let Err(e) = entry.thread.update_task(hash_task).await {
                 error!(thread = %thread_name, error = %e, "Failed to assign cached job");
             } else {
+                entry.share_target = Some(share_target);
                 let task_id = self.tasks.insert(TaskEntry {
                     source_id,
                     template: template.clone(),
                     thread_id,
+                    recent_shares: RecentShares::default(),
                 });
                 share_channels.insert(task_id, ReceiverStream::new(share_rx));
                 debug!(
@@ -791,9 +1704,19 @@ impl Scheduler {
             "Thread count changed"
         );
 
-        // Remove threads that no longer have active event streams
+        // Remove threads that no longer have active event streams, keeping
+        // their hardware error counts in the totals
         let active_thread_ids: HashSet<_> = thread_events.keys().collect();
-        self.threads.retain(|id, _| active_thread_ids.contains(&id));
+        let stats = &mut self.stats;
+        self.threads.retain(|id, entry| {
+            let active = active_thread_ids.contains(&id);
+            if !active {
+                let status = entry.thread.status();
+                stats.retired_hardware_errors += status.hardware_errors;
+                stats.stale_shares += status.flushed_shares;
+            }
+            active
+        });
 
         // Remove tasks for disconnected threads
         self.remove_tasks_where(share_channels, |e| {
@@ -809,10 +1732,11 @@ impl Scheduler {
     ///
     /// Publishes an updated state snapshot before replying so the API
     /// handler's subsequent `borrow()` sees the new value.
-    fn handle_api_command(
+    async fn handle_api_command(
         &mut self,
         cmd: SchedulerCommand,
         miner_telemetry_tx: &watch::Sender<MinerTelemetry>,
+        share_channels: &mut ShareStream,
     ) {
         match cmd {
             SchedulerCommand::PauseMining { reply } => {
@@ -827,7 +1751,143 @@ impl Scheduler {
                 let _ = miner_telemetry_tx.send(self.compute_miner_telemetry());
                 let _ = reply.send(Ok(()));
             }
+            SchedulerCommand::GetState { reply } => {
+                let _ = reply.send(self.debug_state());
+            }
+            SchedulerCommand::GetJob { source, reply } => {
+                let job = self
+                    .sources
+                    .values()
+                    .find(|s| s.name == source)
+                    .and_then(|s| s.last_job.as_deref())
+                    .map(SourceJob::from);
+                let _ = reply.send(job);
+            }
+            SchedulerCommand::PromotePool { pool, reply } => {
+                let pool = (pool != "default").then_some(pool);
+                let _ = reply.send(self.promote_pool(pool, share_channels).await);
+            }
+            SchedulerCommand::ResetStats { reply } => {
+                self.reset_stats();
+                let _ = miner_telemetry_tx.send(self.compute_miner_telemetry());
+                let _ = reply.send(());
+            }
+            SchedulerCommand::Retarget { reply } => {
+                self.retarget(share_channels).await;
+                let _ = reply.send(self.debug_state());
+            }
+        }
+    }
+
+    /// Give every thread a share target computed from its current
+    /// hashrate, logging each one's difficulty before and after.
+    ///
+    /// Targets are otherwise only computed as a job is handed out, so a
+    /// board whose estimate has settled keeps the target it had until its
+    /// pool sends the next one. Each source's cached job is split again as
+    /// an update, so shares from the work before still count; a thread
+    /// starts its slice of the job over, which can repeat a share the pool
+    /// has already seen.
+    async fn retarget(&mut self, share_channels: &mut ShareStream) {
+        let difficulty = |entry: &ThreadEntry| {
+            entry
+                .share_target
+                .map(|target| Difficulty::from_target(target).as_f64())
+        };
+        let before: HashMap<ThreadId, Option<f64>> = self
+            .threads
+            .iter()
+            .map(|(id, entry)| (id, difficulty(entry)))
+            .collect();
+
+        let jobs: Vec<(SourceId, JobTemplate)> = self
+            .sources
+            .iter()
+            .filter_map(|(id, s)| Some((id, JobTemplate::clone(s.last_job.as_ref()?))))
+            .collect();
+        for (source_id, job) in jobs {
+            self.assign_job_to_threads(AssignMode::Update, source_id, job, share_channels)
+                .await;
+        }
+        self.broadcast_hashrate_change().await;
+
+        for (id, entry) in &self.threads {
+            info!(
+                thread = %entry.thread.name(),
+                from = ?before.get(&id).copied().flatten(),
+                to = ?difficulty(entry),
+                "Share target recomputed"
+            );
+        }
+    }
+
+    /// Zero the runtime counters, for comparing runs without a restart.
+    ///
+    /// Counts the scheduler keeps are cleared; those threads and sources
+    /// keep are remembered as they stand and reported relative to that.
+    /// Uptime, best share and blocks found are lifetime figures and stay,
+    /// as do the sources' accepted and rejected counts, which track the
+    /// pool's own books.
+    fn reset_stats(&mut self) {
+        let shares = self.stats.shares_submitted;
+        let unsubmitted = self.unsubmitted_shares();
+        let live = self.live_counts();
+        let stats = &mut self.stats;
+        stats.shares_submitted = 0;
+        stats.duplicate_shares = 0;
+        stats.stale_shares = 0;
+        stats.below_target_shares = 0;
+        stats.retired_hardware_errors = 0;
+        stats.live_at_reset = live;
+        for entry in self.threads.values_mut() {
+            entry.hashrate.clear();
         }
+        info!(
+            shares_submitted = shares,
+            stale = unsubmitted.stale,
+            hardware_errors = unsubmitted.hardware_error,
+            "Runtime stats reset via API"
+        );
+    }
+
+    /// Switch unassigned boards to `pool`, `None` for the default pool.
+    ///
+    /// The cached jobs of the old and new primary pools are split again
+    /// among the threads each now serves, so the moved threads drop the
+    /// old pool's work at once and take the new pool's, or idle until it
+    /// sends a job.
+    async fn promote_pool(
+        &mut self,
+        pool: Option<String>,
+        share_channels: &mut ShareStream,
+    ) -> Result<(), PromoteError> {
+        if !self.sources.values().any(|s| s.pool == pool) {
+            return Err(PromoteError::UnknownPool(
+                pool.unwrap_or_else(|| "default".into()),
+            ));
+        }
+        let previous = self.assignment.promote(pool.clone());
+        if previous == pool {
+            return Ok(());
+        }
+        info!(
+            from = previous.as_deref().unwrap_or("default"),
+            to = pool.as_deref().unwrap_or("default"),
+            "Primary pool changed"
+        );
+
+        let jobs: Vec<(SourceId, JobTemplate)> = self
+            .sources
+            .iter()
+            .filter(|(_, s)| s.pool == previous || s.pool == pool)
+            .filter_map(|(id, s)| Some((id, JobTemplate::clone(s.last_job.as_ref()?))))
+            .collect();
+        for (source_id, job) in jobs {
+            self.assign_job_to_threads(AssignMode::Replace, source_id, job, share_channels)
+                .await;
+        }
+        self.broadcast_hashrate_change().await;
+        Ok(())
     }
 
     /// Main scheduler loop.
@@ -849,23 +1909,29 @@ impl Scheduler {
         status_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
         let mut first_status_tick = true;
 
-        // Create interval for periodic API telemetry publishing
-        let mut telemetry_interval = tokio::time::interval(Duration::from_secs(10));
-        telemetry_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        // First API telemetry snapshot right away, unless disabled
+        let mut next_telemetry = self.telemetry.interval.map(|_| Instant::now());
 
         // Deadline for the startup-gate fallback, set when the enumeration-
         // complete signal arrives without immediately opening the gate.
         let mut gate_deadline: Option<tokio::time::Instant> = None;
 
+        // When the event now being handled woke the loop; each branch sets
+        // it first, so loop timing leaves out the wait.
+        let mut woke;
+
         while !running.is_cancelled() {
+            let outage_deadline = self.outage.deadline();
             tokio::select! {
                 // Source registration
                 Some(registration) = source_reg_rx.recv() => {
+                    woke = Instant::now();
                     self.handle_source_registration(registration, &mut source_events).await;
                 }
 
                 // Source events
                 Some((source_id, event)) = source_events.next() => {
+                    woke = Instant::now();
                     let source_name = self.sources.get(source_id)
                         .map(|s| s.name.as_str())
                         .unwrap_or("unknown");
@@ -902,24 +1968,31 @@ impl Scheduler {
                         SourceEvent::ClearJobs => {
                             self.handle_clear_jobs(source_id, &mut share_channels);
                         }
+
+                        SourceEvent::ShareAccepted { job_id, nonce } => {
+                            self.handle_share_accepted(source_id, &job_id, nonce);
+                        }
                     }
                 }
 
                 // Share channels (from tasks)
                 Some((task_id, share)) = share_channels.next() => {
+                    woke = Instant::now();
                     self.handle_share(task_id, share).await;
                 }
 
                 // Thread events
                 Some((thread_id, event)) = thread_events.next() => {
+                    woke = Instant::now();
                     self.handle_thread_event(thread_id, event, &mut share_channels).await;
                 }
 
                 // Thread registration from backplane
                 Some(registration) = thread_rx.recv() => {
+                    woke = Instant::now();
                     match registration {
-                        ThreadRegistration::Thread(thread) => {
-                            self.handle_new_thread(thread, &mut thread_events).await;
+                        ThreadRegistration::Thread { thread, board } => {
+                            self.handle_new_thread(thread, board, &mut thread_events).await;
                         }
                         ThreadRegistration::InitialEnumerationComplete => {
                             self.startup_gate.record_enumeration_complete();
@@ -941,30 +2014,57 @@ impl Scheduler {
                         None => std::future::pending().await,
                     }
                 }, if self.startup_gate.is_holding() => {
+                    woke = Instant::now();
                     self.startup_gate.record_timeout();
                     debug!("Startup gate opened by fallback timeout; not every thread reported");
                     self.broadcast_hashrate_change().await;
                     gate_deadline = None;
                 }
 
+                // Pool outage: idle the threads once the grace period runs
+                // out with every pool still unreachable.
+                _ = async {
+                    match outage_deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    woke = Instant::now();
+                    self.idle_for_pool_outage(&mut share_channels).await;
+                }
+
                 // Periodic status logging
                 _ = status_interval.tick() => {
+                    woke = Instant::now();
                     if first_status_tick {
                         first_status_tick = false;
                     } else {
                         let hashrate = self.measured_hashrate();
                         self.stats.log_summary(hashrate);
                     }
+                    self.check_stale_failover(Instant::now(), &mut share_channels)
+                        .await;
                 }
 
                 // API commands
                 Some(cmd) = cmd_rx.recv() => {
-                    self.handle_api_command(cmd, &miner_telemetry_tx);
+                    woke = Instant::now();
+                    self.handle_api_command(cmd, &miner_telemetry_tx, &mut share_channels)
+                        .await;
                 }
 
-                // Periodic state publishing
-                _ = telemetry_interval.tick() => {
+                // Periodic state publishing, less often while the host is
+                // loaded
+                _ = async {
+                    match next_telemetry {
+                        Some(at) => tokio::time::sleep_until(at).await,
+                        None => std::future::pending().await,
+                    }
+                }, if next_telemetry.is_some() => {
+                    woke = Instant::now();
                     let _ = miner_telemetry_tx.send(self.compute_miner_telemetry());
+                    let loaded = *self.host_loaded.borrow();
+                    next_telemetry = self.telemetry.next(loaded).map(|d| Instant::now() + d);
                 }
 
                 // Shutdown
@@ -977,6 +2077,9 @@ impl Scheduler {
             // Detect thread disconnections (StreamMap silently removes ended streams)
             self.handle_thread_disconnections(&thread_events, &mut share_channels)
                 .await;
+
+            self.observe_pool_outage(Instant::now());
+            self.record_iteration(woke.elapsed());
         }
 
         // Log final statistics
@@ -1010,6 +2113,23 @@ const HIGH_DIFFICULTY_THRESHOLD: Duration = Duration::from_secs(300); // 5 minut
 /// hashrate changes from board hotplug.
 const HIGH_DIFFICULTY_DEBOUNCE: Duration = Duration::from_secs(30);
 
+/// Clamp `target` so it is no harder than `floor` and no easier than `cap`.
+///
+/// `floor` should never be harder than `cap`, as both come from the same
+/// hashrate. Should that break, `Ord::clamp` would panic and take the
+/// scheduler down with it. Instead the error is logged and the harder
+/// bound wins, keeping share traffic bounded.
+fn clamp_target(target: Target, floor: Target, cap: Target) -> Target {
+    if floor > cap {
+        error!(
+            floor = %Difficulty::from_target(floor),
+            cap = %Difficulty::from_target(cap),
+            "Scheduler target bounds inverted; using flood cap"
+        );
+    }
+    target.max(floor).min(cap)
+}
+
 /// Check whether job difficulty is unreasonably high for our hashrate.
 fn is_difficulty_too_high(job: &JobTemplate, hashrate: HashRate) -> bool {
     if hashrate.is_zero() {
@@ -1020,15 +2140,86 @@ fn is_difficulty_too_high(job: &JobTemplate, hashrate: HashRate) -> bool {
     time_to_share > HIGH_DIFFICULTY_THRESHOLD
 }
 
+/// Log the part of the search space `task` hands `thread` on `board`.
+///
+/// Every task covers the full nonce range, which the board's chips
+/// divide between themselves, and the job's full version-rolling mask;
+/// boards are kept apart by extranonce2 alone.
+fn trace_assignment(source: &str, board: &str, thread: &str, task: &HashTask) {
+    let Some(en2) = &task.en2_range else { return };
+    let version_mask = task
+        .template
+        .version
+        .gp_bits_mask()
+        .apply_to_version(bitcoin::block::Version::from_consensus(0))
+        .to_consensus();
+    debug!(
+        source,
+        board,
+        thread,
+        job_id = %task.template.id,
+        %en2,
+        nonces = "00000000..=ffffffff",
+        version_mask = %format_args!("{version_mask:08x}"),
+        "Work assigned"
+    );
+}
+
+/// Split `range` into one slice per thread, or one per value when the
+/// pool's space has fewer values than there are threads.
+///
+/// Slices never reach outside `range`, so no thread rolls an extranonce2
+/// the pool didn't assign.
+fn partition_en2(range: &Extranonce2Range, threads: usize) -> Vec<Extranonce2Range> {
+    let count = usize::try_from(range.len()).map_or(threads, |len| threads.min(len));
+    range.split(count).unwrap_or_default()
+}
+
+/// Carve EN2 work no thread is searching yet out of the slices in use.
+///
+/// Each thread walks its slice upward from the bottom, and EN2 spaces are
+/// far larger than a thread gets through in one job, so the top half of a
+/// slice is still untouched. The widest slice is halved: its thread keeps
+/// the bottom, where it is searching, and the top is returned for a new
+/// thread. Returns `None` when no slice can be split.
+fn take_unsearched_slice(slices: &mut Vec<Extranonce2Range>) -> Option<Extranonce2Range> {
+    let (widest, _) = slices
+        .iter()
+        .enumerate()
+        .filter(|(_, slice)| slice.len() > 1)
+        .max_by_key(|(i, slice)| (slice.len(), std::cmp::Reverse(*i)))?;
+    let mut halves = slices[widest].split(2)?;
+    let upper = halves.pop()?;
+    slices[widest] = halves.pop()?;
+    slices.push(upper.clone());
+    Some(upper)
+}
+
 /// Run the scheduler task, receiving hash threads and job sources.
+#[expect(clippy::too_many_arguments)]
 pub async fn task(
     running: CancellationToken,
     thread_rx: mpsc::Receiver<ThreadRegistration>,
     source_reg_rx: mpsc::Receiver<SourceRegistration>,
     miner_telemetry_tx: watch::Sender<MinerTelemetry>,
     cmd_rx: mpsc::Receiver<SchedulerCommand>,
+    mode: MiningMode,
+    outage: PoolOutagePolicy,
+    high_difficulty: HighDifficultyAction,
+    assignment: PoolAssignment,
+    stale_failover: Option<StaleFailoverPolicy>,
+    telemetry: TelemetryCadence,
+    host_loaded: watch::Receiver<bool>,
+    clock: Arc<dyn Clock>,
 ) {
-    let mut scheduler = Scheduler::new();
+    let mut scheduler = Scheduler::new(mode);
+    scheduler.outage = OutageMonitor::new(outage);
+    scheduler.high_difficulty = high_difficulty;
+    scheduler.assignment = assignment;
+    scheduler.stale_failover = stale_failover;
+    scheduler.telemetry = telemetry;
+    scheduler.host_loaded = host_loaded;
+    scheduler.clock = clock;
     scheduler
         .run(
             running,
@@ -1132,18 +2323,97 @@ impl StartupGate {
     }
 }
 
+/// Tracks how long every pool has been unreachable.
+///
+/// Driven by the caller's clock so the outage logic can be tested without
+/// waiting out a grace period.
+#[derive(Debug)]
+struct OutageMonitor {
+    policy: PoolOutagePolicy,
+    /// When the current outage began, `None` while a pool is reachable.
+    since: Option<Instant>,
+    /// Whether the threads were idled for the current outage.
+    idled: bool,
+}
+
+impl OutageMonitor {
+    fn new(policy: PoolOutagePolicy) -> Self {
+        Self {
+            policy,
+            since: None,
+            idled: false,
+        }
+    }
+
+    /// Record whether any pool is reachable at `now`.
+    ///
+    /// Returns true when this ends an outage the threads were idled for.
+    fn observe(&mut self, reachable: bool, now: Instant) -> bool {
+        if reachable {
+            self.since = None;
+            std::mem::take(&mut self.idled)
+        } else {
+            self.since.get_or_insert(now);
+            false
+        }
+    }
+
+    /// When to idle the threads, if an outage is running and the policy
+    /// calls for it.
+    fn deadline(&self) -> Option<Instant> {
+        match (self.policy.action, self.since) {
+            (PoolOutageAction::Idle, Some(since)) if !self.idled => Some(since + self.policy.grace),
+            _ => None,
+        }
+    }
+
+    /// Record that the threads were idled for the current outage.
+    fn record_idled(&mut self) {
+        self.idled = true;
+    }
+}
+
 /// Mining statistics tracker.
 #[derive(Debug)]
 struct MiningStats {
-    start_time: std::time::Instant,
+    start_time: Instant,
     shares_submitted: u64,
+    duplicate_shares: u64,
+    /// Shares for tasks removed before the share arrived.
+    stale_shares: u64,
+    /// Shares below their source's threshold.
+    below_target_shares: u64,
+    /// Hardware errors of threads that have since gone away.
+    retired_hardware_errors: u64,
+    best_share: Option<Difficulty>,
+    blocks_found: u64,
+    /// Counts held by threads and sources at the last stats reset, which
+    /// theirs are reported relative to.
+    live_at_reset: LiveCounts,
+}
+
+/// Share and error counts kept outside the scheduler, by the threads and
+/// sources that see them.
+#[derive(Debug, Default)]
+struct LiveCounts {
+    hardware_errors: u64,
+    flushed_shares: u64,
+    stale_avoided: u64,
+    queue_dropped: u64,
 }
 
 impl Default for MiningStats {
     fn default() -> Self {
         Self {
-            start_time: std::time::Instant::now(),
+            start_time: Instant::now(),
             shares_submitted: 0,
+            duplicate_shares: 0,
+            stale_shares: 0,
+            below_target_shares: 0,
+            retired_hardware_errors: 0,
+            best_share: None,
+            blocks_found: 0,
+            live_at_reset: LiveCounts::default(),
         }
     }
 }
@@ -1170,7 +2440,1361 @@ impl MiningStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::asic::hash_thread::{HashThreadCapabilities, HashThreadStatus};
+    use crate::job_source::{
+        GeneralPurposeBits, MerkleRootKind, MerkleRootTemplate, VersionTemplate,
+    };
     use crate::types::Difficulty;
+    use bitcoin::block::Version;
+    use bitcoin::hashes::Hash;
+    use bitcoin::pow::CompactTarget;
+
+    fn test_template(id: &str, difficulty: u64) -> Arc<JobTemplate> {
+        Arc::new(JobTemplate {
+            id: id.to_string(),
+            prev_blockhash: BlockHash::all_zeros(),
+            version: VersionTemplate::new(
+                Version::from_consensus(0x20000000),
+                GeneralPurposeBits::none(),
+            )
+            .unwrap(),
+            bits: CompactTarget::from_consensus(0x1d00ffff),
+            share_target: Difficulty::from(difficulty).to_target(),
+            time: 0,
+            max_time: 0,
+            merkle_root: MerkleRootKind::Fixed(bitcoin::TxMerkleNode::all_zeros()),
+        })
+    }
+
+    /// A share whose hash sits exactly at `difficulty`.
+    fn share_at(difficulty: u64) -> Share {
+        Share {
+            nonce: 0,
+            hash: BlockHash::from_byte_array(
+                Difficulty::from(difficulty).to_target().to_le_bytes(),
+            ),
+            version: Version::from_consensus(0x20000000),
+            ntime: 0,
+            extranonce2: None,
+            expected_work: Difficulty::from(1).to_target().to_work(),
+        }
+    }
+
+    /// Seconds into a two-minute run at which `scheduler` publishes
+    /// telemetry, with the host loaded or not.
+    async fn telemetry_times(mut scheduler: Scheduler, loaded: bool) -> Vec<u64> {
+        let (_load_tx, load_rx) = watch::channel(loaded);
+        scheduler.host_loaded = load_rx;
+        let (_thread_tx, thread_rx) = mpsc::channel(1);
+        let (_source_tx, source_rx) = mpsc::channel(1);
+        let (_cmd_tx, cmd_rx) = mpsc::channel(1);
+        let (telemetry_tx, mut telemetry_rx) = watch::channel(MinerTelemetry::default());
+        let running = CancellationToken::new();
+        let run = tokio::spawn({
+            let running = running.clone();
+            async move {
+                scheduler
+                    .run(running, thread_rx, source_rx, telemetry_tx, cmd_rx)
+                    .await
+            }
+        });
+
+        let start = Instant::now();
+        let mut times = Vec::new();
+        let _ = tokio::time::timeout(Duration::from_secs(125), async {
+            while telemetry_rx.changed().await.is_ok() {
+                times.push(start.elapsed().as_secs());
+            }
+        })
+        .await;
+        running.cancel();
+        run.await.unwrap();
+        times
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn telemetry_backs_off_under_load_and_can_be_disabled() {
+        let normal = telemetry_times(Scheduler::new(MiningMode::default()), false).await;
+        assert_eq!(normal, (0..=120).step_by(10).collect::<Vec<_>>());
+
+        let loaded = telemetry_times(Scheduler::new(MiningMode::default()), true).await;
+        assert_eq!(loaded, [0, 60, 120]);
+
+        let mut disabled = Scheduler::new(MiningMode::default());
+        disabled.telemetry.interval = None;
+        assert!(telemetry_times(disabled, true).await.is_empty());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn telemetry_cadence_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_TELEMETRY_INTERVAL_SECS", "5");
+            env::set_var("MUJINA_TELEMETRY_LOADED_INTERVAL_SECS", "30");
+        }
+        let cadence = TelemetryCadence::from_env();
+        assert_eq!(cadence.next(false), Some(Duration::from_secs(5)));
+        assert_eq!(cadence.next(true), Some(Duration::from_secs(30)));
+
+        // The loaded cadence never publishes more often.
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::set_var("MUJINA_TELEMETRY_INTERVAL_SECS", "90") };
+        assert_eq!(
+            TelemetryCadence::from_env().next(true),
+            Some(Duration::from_secs(90))
+        );
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var("MUJINA_TELEMETRY_INTERVAL_SECS", "0");
+            env::set_var("MUJINA_TELEMETRY_LOADED_INTERVAL_SECS", "0");
+        }
+        let cadence = TelemetryCadence::from_env();
+        assert_eq!(cadence.next(true), None);
+        assert_eq!(cadence.loaded_interval, DEFAULT_LOADED_TELEMETRY_INTERVAL);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_TELEMETRY_INTERVAL_SECS");
+            env::remove_var("MUJINA_TELEMETRY_LOADED_INTERVAL_SECS");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn loop_timing_records_iterations_and_catches_a_slow_one() {
+        let (mut scheduler, source_id, mut command_rx) = scheduler_with_source();
+        scheduler.loop_timing = Some(LatencyHistogram::new());
+        // Fill the source's command channel, so the hashrate broadcast at
+        // the end of enumeration blocks until the test drains it.
+        let command_tx = scheduler.sources[source_id].command_tx.clone();
+        while command_tx
+            .try_send(SourceCommand::UpdateHashRate(HashRate::default()))
+            .is_ok()
+        {}
+
+        let (thread_tx, thread_rx) = mpsc::channel(1);
+        let (_source_tx, source_rx) = mpsc::channel(1);
+        let (cmd_tx, cmd_rx) = mpsc::channel(1);
+        let running = CancellationToken::new();
+        let run = tokio::spawn({
+            let running = running.clone();
+            let (telemetry_tx, _) = watch::channel(MinerTelemetry::default());
+            async move {
+                scheduler
+                    .run(running, thread_rx, source_rx, telemetry_tx, cmd_rx)
+                    .await
+            }
+        });
+        let loop_timing = || async {
+            let (reply, state) = tokio::sync::oneshot::channel();
+            cmd_tx
+                .send(SchedulerCommand::GetState { reply })
+                .await
+                .unwrap();
+            state.await.unwrap().loop_timing.unwrap()
+        };
+
+        // Quick iterations land in the fastest bucket.
+        loop_timing().await;
+        let fast = loop_timing().await;
+        assert!(fast.iterations >= 1);
+        assert_eq!(fast.buckets[0].count, fast.iterations);
+        assert!(fast.max_us <= 100);
+
+        let logs = crate::tracing::capture_logs(async {
+            thread_tx
+                .send(ThreadRegistration::InitialEnumerationComplete)
+                .await
+                .unwrap();
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            while command_rx.try_recv().is_ok() {}
+            // Handled once the slow iteration has been recorded.
+            loop_timing().await;
+        })
+        .await;
+        let slow = loop_timing().await;
+        let (last, rest) = slow.buckets.split_last().unwrap();
+        assert_eq!((last.le_us, last.count), (None, 1));
+        assert!(rest.iter().all(|b| b.le_us == Some(100) || b.count == 0));
+        assert!(slow.max_us >= 2_000_000);
+        assert!(logs.contains("Slow scheduler loop iteration"), "{logs}");
+
+        running.cancel();
+        run.await.unwrap();
+    }
+
+    /// A scheduler with one registered source, and that source's command
+    /// receiver.
+    fn scheduler_with_source() -> (Scheduler, SourceId, mpsc::Receiver<SourceCommand>) {
+        let mut scheduler = Scheduler::new(MiningMode::default());
+        let (command_tx, command_rx) = mpsc::channel(10);
+        let source_id = scheduler.sources.insert(SourceEntry {
+            name: "pool".into(),
+            url: None,
+            command_tx,
+            last_job: None,
+            stats_rx: None,
+            difficulty_alarm: DebouncedAlarm::new(HIGH_DIFFICULTY_DEBOUNCE),
+            en2_slices: Vec::new(),
+            pool: None,
+        });
+        (scheduler, source_id, command_rx)
+    }
+
+    fn insert_task(
+        scheduler: &mut Scheduler,
+        source_id: SourceId,
+        template: Arc<JobTemplate>,
+    ) -> TaskId {
+        scheduler.tasks.insert(TaskEntry {
+            source_id,
+            template,
+            thread_id: ThreadId::default(),
+            recent_shares: RecentShares::default(),
+        })
+    }
+
+    /// A thread that accepts work and never hashes.
+    struct StubThread {
+        name: String,
+        capabilities: HashThreadCapabilities,
+        active: bool,
+        hardware_errors: u64,
+    }
+
+    #[async_trait::async_trait]
+    impl HashThread for StubThread {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn capabilities(&self) -> &HashThreadCapabilities {
+            &self.capabilities
+        }
+
+        async fn configure(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn update_task(&mut self, _: HashTask) -> anyhow::Result<Option<HashTask>> {
+            self.active = true;
+            Ok(None)
+        }
+
+        async fn replace_task(&mut self, _: HashTask) -> anyhow::Result<Option<HashTask>> {
+            self.active = true;
+            Ok(None)
+        }
+
+        async fn go_idle(&mut self) -> anyhow::Result<Option<HashTask>> {
+            self.active = false;
+            Ok(None)
+        }
+
+        fn take_event_receiver(&mut self) -> Option<mpsc::Receiver<HashThreadEvent>> {
+            None
+        }
+
+        fn status(&self) -> HashThreadStatus {
+            HashThreadStatus {
+                is_active: self.active,
+                hardware_errors: self.hardware_errors,
+                ..Default::default()
+            }
+        }
+    }
+
+    fn insert_thread(scheduler: &mut Scheduler, name: &str, expected: Option<HashRate>) {
+        scheduler.threads.insert(ThreadEntry {
+            thread: Box::new(StubThread {
+                name: name.into(),
+                capabilities: HashThreadCapabilities::default(),
+                active: false,
+                hardware_errors: 0,
+            }),
+            hashrate: HashrateEstimator::new(HASHRATE_WINDOW),
+            board: format!("board-{name}"),
+            expected,
+            share_target: None,
+        });
+    }
+
+    #[tokio::test]
+    async fn debug_state_reports_target_bounds_and_assignment() {
+        let (mut scheduler, source_id, _command_rx) = scheduler_with_source();
+        let hashrate = HashRate::from_terahashes(1.0);
+        insert_thread(&mut scheduler, "declared", Some(hashrate));
+        insert_thread(&mut scheduler, "silent", None);
+        scheduler.paused = true;
+
+        // A pool difficulty far below the flood cap gets raised to it.
+        let mut share_channels = ShareStream::new();
+        let mut template = (*test_template("job", 1)).clone();
+        template.merkle_root = MerkleRootKind::Computed(MerkleRootTemplate {
+            coinbase1: Vec::new(),
+            extranonce1: Vec::new(),
+            extranonce2_range: Extranonce2Range::new(4).unwrap(),
+            coinbase2: Vec::new(),
+            merkle_branches: Vec::new(),
+            cache: Default::default(),
+        });
+        scheduler
+            .assign_job_to_threads(
+                AssignMode::Replace,
+                source_id,
+                template,
+                &mut share_channels,
+            )
+            .await;
+
+        let state = scheduler.debug_state();
+        assert!(state.paused);
+        let declared = state.threads.iter().find(|t| t.name == "declared").unwrap();
+        let cap = FLOOD_CAP_RATE.to_difficulty(hashrate).as_f64();
+        let floor = MEASUREMENT_SHARE_RATE.to_difficulty(hashrate).as_f64();
+        assert_eq!(declared.expected_hashrate, Some(u64::from(hashrate)));
+        assert_eq!(declared.measured_hashrate, None);
+        assert_eq!(declared.min_difficulty, Some(cap));
+        assert_eq!(declared.max_difficulty, Some(floor));
+        assert!(cap < floor);
+        assert_eq!(declared.share_difficulty, Some(cap));
+
+        // Not eligible for work, and no hashrate to bound a target by.
+        let silent = state.threads.iter().find(|t| t.name == "silent").unwrap();
+        assert_eq!(silent.expected_hashrate, None);
+        assert_eq!(silent.share_difficulty, None);
+        assert_eq!(silent.min_difficulty, None);
+        assert_eq!(silent.max_difficulty, None);
+    }
+
+    #[tokio::test]
+    async fn retarget_recomputes_share_targets_now() {
+        let (mut scheduler, source_id, mut command_rx) = scheduler_with_source();
+        let hashrate = HashRate::from_terahashes(1.0);
+        insert_thread(&mut scheduler, "bitaxe", Some(hashrate));
+        let mut share_channels = ShareStream::new();
+        scheduler
+            .assign_job_to_threads(
+                AssignMode::Replace,
+                source_id,
+                computed_template("job"),
+                &mut share_channels,
+            )
+            .await;
+        let cap = |hashrate| Some(FLOOD_CAP_RATE.to_difficulty(hashrate).as_f64());
+        assert_eq!(
+            scheduler.debug_state().threads[0].share_difficulty,
+            cap(hashrate)
+        );
+
+        // The board turns out faster; without a new job its target stays.
+        let faster = HashRate::from_terahashes(4.0);
+        scheduler.threads.values_mut().next().unwrap().expected = Some(faster);
+        assert_eq!(
+            scheduler.debug_state().threads[0].share_difficulty,
+            cap(hashrate)
+        );
+
+        let (telemetry_tx, _telemetry_rx) = watch::channel(MinerTelemetry::default());
+        let (reply, retargeted) = tokio::sync::oneshot::channel();
+        scheduler
+            .handle_api_command(
+                SchedulerCommand::Retarget { reply },
+                &telemetry_tx,
+                &mut share_channels,
+            )
+            .await;
+        let state = retargeted.await.unwrap();
+        assert_eq!(state.threads[0].share_difficulty, cap(faster));
+
+        // The job went out again alongside the old one, and the source
+        // heard the new hashrate.
+        assert_eq!(scheduler.tasks.len(), 2);
+        assert!(matches!(
+            command_rx.try_recv(),
+            Ok(SourceCommand::UpdateHashRate(rate)) if rate == faster
+        ));
+    }
+
+    /// A job the scheduler can split across threads.
+    fn computed_template(id: &str) -> JobTemplate {
+        let mut template = (*test_template(id, 1)).clone();
+        template.merkle_root = MerkleRootKind::Computed(MerkleRootTemplate {
+            coinbase1: Vec::new(),
+            extranonce1: Vec::new(),
+            extranonce2_range: Extranonce2Range::new(4).unwrap(),
+            coinbase2: Vec::new(),
+            merkle_branches: Vec::new(),
+            cache: Default::default(),
+        });
+        template
+    }
+
+    #[tokio::test]
+    async fn share_log_formats_carry_their_fields() {
+        use crate::job_source::test_blocks::block_881423 as block;
+
+        // Block 881,423 as a pool job, and its winning share.
+        let mut template = (*test_template("job-7", 1)).clone();
+        template.prev_blockhash = *block::PREV_BLOCKHASH;
+        template.bits = *block::BITS;
+        template.merkle_root = MerkleRootKind::Computed(MerkleRootTemplate {
+            coinbase1: block::coinbase1_bytes().to_vec(),
+            extranonce1: block::extranonce1_bytes().to_vec(),
+            extranonce2_range: Extranonce2Range::new(block::EXTRANONCE2.size()).unwrap(),
+            coinbase2: block::coinbase2_bytes().to_vec(),
+            merkle_branches: block::MERKLE_BRANCHES.clone(),
+            cache: Default::default(),
+        });
+        let share = Share {
+            nonce: block::NONCE,
+            hash: *block::BLOCK_HASH,
+            version: *block::VERSION,
+            ntime: block::TIME,
+            extranonce2: Some(*block::EXTRANONCE2),
+            expected_work: Difficulty::from(1).to_target().to_work(),
+        };
+        let common = [
+            "source=pool".to_string(),
+            "job_id=job-7".to_string(),
+            format!("nonce={:#x}", block::NONCE),
+            format!("hash={}", *block::BLOCK_HASH),
+            format!(
+                "share_difficulty={}",
+                Difficulty::from_hash(&block::BLOCK_HASH)
+            ),
+            "threshold=1".to_string(),
+        ];
+
+        let compact = crate::tracing::capture_logs(async {
+            ShareLogFormat::Compact.log("pool", &template, &share)
+        })
+        .await;
+        assert_eq!(compact.lines().count(), 1, "{compact}");
+        for field in &common {
+            assert!(compact.contains(field), "{field} missing from {compact}");
+        }
+        assert!(!compact.contains("header="), "{compact}");
+
+        // The extended record carries the header the hash came from.
+        let extended = crate::tracing::capture_logs(async {
+            ShareLogFormat::Extended.log("pool", &template, &share)
+        })
+        .await;
+        assert_eq!(extended.lines().count(), 1, "{extended}");
+        let rolled = [
+            format!("version=\"{:08x}\"", block::VERSION.to_consensus()),
+            format!("ntime={}", block::TIME),
+            format!(
+                "extranonce2=\"{}\"",
+                hex::encode(block::extranonce2_bytes())
+            ),
+            format!("prev_blockhash={}", *block::PREV_BLOCKHASH),
+            format!("nbits=\"{:08x}\"", block::BITS.to_consensus()),
+            format!("merkle_root=\"{}\"", *block::MERKLE_ROOT),
+            format!("header=\"{}\"", hex::encode(block::HEADER_BYTES)),
+        ];
+        for field in common.iter().chain(&rolled) {
+            assert!(extended.contains(field), "{field} missing from {extended}");
+        }
+    }
+
+    fn threads_active(scheduler: &Scheduler) -> Vec<bool> {
+        scheduler
+            .threads
+            .values()
+            .map(|t| t.thread.status().is_active)
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn absurd_difficulty_for_a_slow_thread_asks_for_a_lower_one() {
+        let (mut scheduler, source_id, mut command_rx) = scheduler_with_source();
+        scheduler.high_difficulty = HighDifficultyAction::Suggest;
+        insert_thread(&mut scheduler, "slow", Some(HashRate::from_gigahashes(1.0)));
+        let mut share_channels = ShareStream::new();
+
+        // Difficulty 1T at 1 GH/s is a share every 136 years. The first
+        // job only starts the debounce.
+        let mut template = computed_template("job-1");
+        template.share_target = Difficulty::from(1_000_000_000_000).to_target();
+        scheduler
+            .assign_job_to_threads(
+                AssignMode::Replace,
+                source_id,
+                template.clone(),
+                &mut share_channels,
+            )
+            .await;
+        assert!(command_rx.try_recv().is_err());
+
+        // Still too high past the debounce: the source is asked to lower
+        // it, and the thread keeps its work meanwhile.
+        tokio::time::advance(HIGH_DIFFICULTY_DEBOUNCE).await;
+        template.id = "job-2".into();
+        scheduler
+            .assign_job_to_threads(
+                AssignMode::Replace,
+                source_id,
+                template.clone(),
+                &mut share_channels,
+            )
+            .await;
+        assert!(matches!(
+            command_rx.try_recv(),
+            Ok(SourceCommand::LowerDifficulty)
+        ));
+        assert_eq!(threads_active(&scheduler), [true]);
+
+        // Once, not on every job while the pool catches up.
+        template.id = "job-3".into();
+        scheduler
+            .assign_job_to_threads(
+                AssignMode::Replace,
+                source_id,
+                template,
+                &mut share_channels,
+            )
+            .await;
+        assert!(command_rx.try_recv().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn high_difficulty_only_warns_by_default() {
+        let (mut scheduler, source_id, mut command_rx) = scheduler_with_source();
+        insert_thread(&mut scheduler, "slow", Some(HashRate::from_gigahashes(1.0)));
+        let mut share_channels = ShareStream::new();
+        let mut template = computed_template("job");
+        template.share_target = Difficulty::from(1_000_000_000_000).to_target();
+        for _ in 0..2 {
+            scheduler
+                .assign_job_to_threads(
+                    AssignMode::Replace,
+                    source_id,
+                    template.clone(),
+                    &mut share_channels,
+                )
+                .await;
+            tokio::time::advance(HIGH_DIFFICULTY_DEBOUNCE).await;
+        }
+        assert!(command_rx.try_recv().is_err());
+        assert_eq!(threads_active(&scheduler), [true]);
+    }
+
+    #[tokio::test]
+    async fn pool_outage_idles_threads_past_grace_and_resumes_on_recovery() {
+        let (mut scheduler, source_id, _command_rx) = scheduler_with_source();
+        let grace = Duration::from_secs(60);
+        scheduler.outage = OutageMonitor::new(PoolOutagePolicy {
+            action: PoolOutageAction::Idle,
+            grace,
+        });
+        insert_thread(&mut scheduler, "t0", Some(HashRate::from_terahashes(1.0)));
+        let mut share_channels = ShareStream::new();
+        let start = Instant::now();
+
+        scheduler
+            .assign_job_to_threads(
+                AssignMode::Replace,
+                source_id,
+                computed_template("job1"),
+                &mut share_channels,
+            )
+            .await;
+        scheduler.observe_pool_outage(start);
+        assert_eq!(threads_active(&scheduler), [true]);
+        assert_eq!(scheduler.outage.deadline(), None);
+
+        // The only pool drops. The outage clock starts, but the threads are
+        // left alone until the grace period is over.
+        scheduler.handle_clear_jobs(source_id, &mut share_channels);
+        scheduler.observe_pool_outage(start);
+        scheduler.observe_pool_outage(start + grace / 2);
+        assert_eq!(scheduler.outage.deadline(), Some(start + grace));
+        assert_eq!(threads_active(&scheduler), [true]);
+
+        // Past the deadline the run loop idles every thread, once.
+        scheduler.idle_for_pool_outage(&mut share_channels).await;
+        scheduler.observe_pool_outage(start + grace * 2);
+        assert_eq!(threads_active(&scheduler), [false]);
+        assert_eq!(scheduler.outage.deadline(), None);
+        assert!(scheduler.tasks.is_empty());
+
+        // The pool comes back: its job puts the thread back to work and the
+        // outage is over.
+        scheduler
+            .assign_job_to_threads(
+                AssignMode::Replace,
+                source_id,
+                computed_template("job2"),
+                &mut share_channels,
+            )
+            .await;
+        scheduler.observe_pool_outage(start + grace * 3);
+        assert_eq!(threads_active(&scheduler), [true]);
+        assert!(!scheduler.outage.idled);
+        assert_eq!(scheduler.outage.since, None);
+    }
+
+    #[test]
+    fn outage_monitor_restarts_the_clock_after_recovery() {
+        let grace = Duration::from_secs(60);
+        let mut monitor = OutageMonitor::new(PoolOutagePolicy {
+            action: PoolOutageAction::Idle,
+            grace,
+        });
+        let start = Instant::now();
+
+        assert!(!monitor.observe(false, start));
+        assert!(!monitor.observe(true, start + grace / 2));
+        assert_eq!(monitor.deadline(), None);
+
+        // A recovery inside the grace period resets it for the next outage.
+        let later = start + grace;
+        monitor.observe(false, later);
+        assert_eq!(monitor.deadline(), Some(later + grace));
+        monitor.record_idled();
+        assert!(monitor.observe(true, later + grace * 2));
+    }
+
+    #[test]
+    fn outage_monitor_keep_never_acts() {
+        let mut monitor = OutageMonitor::new(PoolOutagePolicy::default());
+        monitor.observe(false, Instant::now());
+        assert_eq!(monitor.deadline(), None);
+    }
+
+    #[test]
+    fn target_clamp_survives_inverted_bounds() {
+        let easy = Difficulty::from(10_u64).to_target();
+        let normal = Difficulty::from(100_u64).to_target();
+        let hard = Difficulty::from(1000_u64).to_target();
+
+        assert_eq!(clamp_target(normal, hard, easy), normal);
+        assert_eq!(clamp_target(Target::MAX, hard, easy), easy);
+        assert_eq!(clamp_target(Target::ZERO, hard, easy), hard);
+
+        // Inverted: the harder bound wins rather than panicking.
+        for target in [Target::MAX, normal, Target::ZERO] {
+            assert_eq!(clamp_target(target, easy, hard), hard);
+        }
+    }
+
+    #[tokio::test]
+    async fn in_flight_shares_use_difficulty_at_issue_time() {
+        let (mut scheduler, source_id, mut command_rx) = scheduler_with_source();
+
+        // Work issued at difficulty 100; the pool then raises difficulty
+        // to 1000 and a new job carries the new value.
+        let old_task = insert_task(&mut scheduler, source_id, test_template("old", 100));
+        let new_task = insert_task(&mut scheduler, source_id, test_template("new", 1000));
+
+        // A difficulty-500 share from the old work still meets the
+        // difficulty it was issued under and is submitted.
+        scheduler.handle_share(old_task, share_at(500)).await;
+        let Ok(SourceCommand::SubmitShare(share)) = command_rx.try_recv() else {
+            panic!("in-flight share should be submitted");
+        };
+        assert_eq!(share.job_id, "old");
+
+        // The same share on new work is below the new difficulty.
+        scheduler.handle_share(new_task, share_at(500)).await;
+        assert!(command_rx.try_recv().is_err());
+
+        // An easier-than-issued share on old work is withheld, so the new
+        // difficulty is not loosening the old snapshot either.
+        scheduler.handle_share(old_task, share_at(50)).await;
+        assert!(command_rx.try_recv().is_err());
+        assert_eq!(scheduler.stats.shares_submitted, 1);
+    }
+
+    #[tokio::test]
+    async fn first_accepted_share_confirms_each_board_once() {
+        let (mut scheduler, source_id, _command_rx) = scheduler_with_source();
+        insert_thread(&mut scheduler, "a", None);
+        insert_thread(&mut scheduler, "b", None);
+        let thread = |scheduler: &Scheduler, board: &str| {
+            scheduler
+                .threads
+                .iter()
+                .find(|(_, entry)| entry.board == board)
+                .map(|(id, _)| id)
+                .unwrap()
+        };
+        let mut task_on = |board| {
+            let thread_id = thread(&scheduler, board);
+            let task = insert_task(&mut scheduler, source_id, test_template("job", 1));
+            scheduler.tasks[task].thread_id = thread_id;
+            task
+        };
+        let (task_a, task_b) = (task_on("board-a"), task_on("board-b"));
+        let share = |nonce, difficulty| Share {
+            nonce,
+            ..share_at(difficulty)
+        };
+
+        let logs = crate::tracing::capture_logs(async {
+            scheduler.handle_share(task_a, share(1, 500)).await;
+            scheduler.handle_share(task_a, share(2, 600)).await;
+            scheduler.handle_share(task_b, share(3, 700)).await;
+            assert!(scheduler.unconfirmed.confirmed.is_empty());
+
+            // Either of board a's shares confirms it; the other, accepted
+            // later, does nothing more.
+            scheduler.handle_share_accepted(source_id, "job", 2);
+            scheduler.handle_share_accepted(source_id, "job", 1);
+            assert_eq!(
+                scheduler.compute_miner_telemetry().confirmed_boards,
+                BTreeSet::from(["board-a".to_string()])
+            );
+
+            // An acceptance nothing was submitted for confirms no board.
+            scheduler.handle_share_accepted(source_id, "job", 9);
+            scheduler.handle_share_accepted(source_id, "job", 3);
+
+            // Shares after the first aren't even tracked.
+            scheduler.handle_share(task_a, share(4, 800)).await;
+            scheduler.handle_share_accepted(source_id, "job", 4);
+            assert!(scheduler.unconfirmed.order.is_empty());
+        })
+        .await;
+
+        assert_eq!(logs.matches("Mining confirmed").count(), 2, "{logs}");
+        assert_eq!(logs.matches("board=board-a").count(), 1, "{logs}");
+        assert_eq!(logs.matches("board=board-b").count(), 1, "{logs}");
+        assert_eq!(
+            scheduler.compute_miner_telemetry().confirmed_boards,
+            BTreeSet::from(["board-a".to_string(), "board-b".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn share_exactly_at_the_threshold_is_submitted() {
+        use crate::u256::U256;
+
+        let (mut scheduler, source_id, mut command_rx) = scheduler_with_source();
+        let task = insert_task(&mut scheduler, source_id, test_template("job", 1000));
+        let at = U256::from_le_bytes(Difficulty::from(1000).to_target().to_le_bytes());
+        let (mut below, mut above) = (at, at);
+        below -= U256::from(1);
+        above += U256::from(1);
+
+        for (nonce, value, submitted) in [(1, below, true), (2, at, true), (3, above, false)] {
+            let share = Share {
+                nonce,
+                hash: BlockHash::from_byte_array(value.to_le_bytes()),
+                ..share_at(1000)
+            };
+            scheduler.handle_share(task, share).await;
+            assert_eq!(command_rx.try_recv().is_ok(), submitted, "nonce {nonce}");
+        }
+        assert_eq!(scheduler.unsubmitted_shares().below_target, 1);
+    }
+
+    #[tokio::test]
+    async fn duplicate_share_dropped_and_counted() {
+        let (mut scheduler, source_id, mut command_rx) = scheduler_with_source();
+        let task = insert_task(&mut scheduler, source_id, test_template("job", 1));
+
+        scheduler.handle_share(task, share_at(500)).await;
+        assert!(matches!(
+            command_rx.try_recv(),
+            Ok(SourceCommand::SubmitShare(_))
+        ));
+
+        // The board reports the same nonce again: dropped, not submitted.
+        scheduler.handle_share(task, share_at(500)).await;
+        assert!(command_rx.try_recv().is_err());
+
+        // A new share on the same job still goes through.
+        scheduler.handle_share(task, share_at(600)).await;
+        assert!(matches!(
+            command_rx.try_recv(),
+            Ok(SourceCommand::SubmitShare(_))
+        ));
+
+        assert_eq!(scheduler.stats.shares_submitted, 2);
+        assert_eq!(scheduler.stats.duplicate_shares, 1);
+        assert_eq!(scheduler.compute_miner_telemetry().duplicate_shares, 1);
+    }
+
+    #[tokio::test]
+    async fn every_unsubmitted_share_is_counted_under_its_reason() {
+        let (mut scheduler, source_id, mut command_rx) = scheduler_with_source();
+        let (stats_tx, stats_rx) = watch::channel(SourceStats::default());
+        scheduler.sources[source_id].stats_rx = Some(stats_rx);
+        let unsubmitted = |s: &Scheduler| s.unsubmitted_shares();
+        assert_eq!(unsubmitted(&scheduler), UnsubmittedShares::default());
+
+        // Below the source's threshold.
+        let task = insert_task(&mut scheduler, source_id, test_template("job", 1000));
+        scheduler.handle_share(task, share_at(500)).await;
+        assert_eq!(unsubmitted(&scheduler).below_target, 1);
+
+        // The same nonce twice: the first is submitted.
+        scheduler.handle_share(task, share_at(2000)).await;
+        scheduler.handle_share(task, share_at(2000)).await;
+        assert!(command_rx.try_recv().is_ok());
+        assert_eq!(unsubmitted(&scheduler).duplicate, 1);
+
+        // On work that was replaced before the share arrived.
+        scheduler.tasks.remove(task);
+        scheduler.handle_share(task, share_at(2000)).await;
+        assert_eq!(unsubmitted(&scheduler).stale, 1);
+
+        // Withheld by the source as too old, or dropped from its queue.
+        stats_tx.send_modify(|s| {
+            s.shares_stale_avoided = 2;
+            s.shares_dropped = 3;
+        });
+        assert_eq!(unsubmitted(&scheduler).stale, 3);
+        assert_eq!(unsubmitted(&scheduler).queue_dropped, 3);
+
+        // Failed validation on the host, still counted once the thread
+        // has gone.
+        scheduler.threads.insert(ThreadEntry {
+            thread: Box::new(StubThread {
+                name: "bad-chip".into(),
+                capabilities: HashThreadCapabilities::default(),
+                active: true,
+                hardware_errors: 4,
+            }),
+            hashrate: HashrateEstimator::new(HASHRATE_WINDOW),
+            board: "board-bad-chip".into(),
+            expected: None,
+            share_target: None,
+        });
+        assert_eq!(unsubmitted(&scheduler).hardware_error, 4);
+        scheduler.last_thread_count = 1;
+        scheduler
+            .handle_thread_disconnections(&ThreadEventStream::new(), &mut ShareStream::new())
+            .await;
+        assert!(scheduler.threads.is_empty());
+
+        assert_eq!(
+            scheduler.compute_miner_telemetry().unsubmitted_shares,
+            UnsubmittedShares {
+                stale: 3,
+                below_target: 1,
+                duplicate: 1,
+                hardware_error: 4,
+                queue_dropped: 3,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn resetting_stats_zeroes_runtime_counters_and_keeps_lifetime_ones() {
+        let (mut scheduler, source_id, _command_rx) = scheduler_with_source();
+        let (stats_tx, stats_rx) = watch::channel(SourceStats {
+            shares_accepted: 7,
+            shares_stale_avoided: 2,
+            shares_dropped: 1,
+            ..Default::default()
+        });
+        scheduler.sources[source_id].stats_rx = Some(stats_rx);
+        let thread_id = scheduler.threads.insert(ThreadEntry {
+            thread: Box::new(StubThread {
+                name: "chip".into(),
+                capabilities: HashThreadCapabilities::default(),
+                active: true,
+                hardware_errors: 4,
+            }),
+            hashrate: HashrateEstimator::new(HASHRATE_WINDOW),
+            board: "board-chip".into(),
+            expected: None,
+            share_target: None,
+        });
+        scheduler.threads[thread_id]
+            .hashrate
+            .record(share_at(1).expected_work);
+
+        // A submitted share, a duplicate and one below target. The
+        // template's network target is difficulty 1, so both the new
+        // shares are blocks too.
+        let task = insert_task(&mut scheduler, source_id, test_template("job", 100));
+        scheduler.handle_share(task, share_at(500)).await;
+        scheduler.handle_share(task, share_at(500)).await;
+        scheduler.handle_share(task, share_at(50)).await;
+        let before = scheduler.compute_miner_telemetry();
+        assert_eq!(before.shares_submitted, 1);
+        assert_eq!(before.blocks_found, 2);
+        assert_ne!(before.unsubmitted_shares, UnsubmittedShares::default());
+
+        let (telemetry_tx, telemetry_rx) = watch::channel(MinerTelemetry::default());
+        let (reply, reset) = tokio::sync::oneshot::channel();
+        scheduler
+            .handle_api_command(
+                SchedulerCommand::ResetStats { reply },
+                &telemetry_tx,
+                &mut ShareStream::new(),
+            )
+            .await;
+        reset.await.unwrap();
+
+        let after = telemetry_rx.borrow().clone();
+        assert_eq!(after.shares_submitted, 0);
+        assert_eq!(after.duplicate_shares, 0);
+        assert_eq!(after.unsubmitted_shares, UnsubmittedShares::default());
+        assert!(!scheduler.threads[thread_id].hashrate.has_samples());
+        assert_eq!(after.blocks_found, 2);
+        assert_eq!(after.best_share_difficulty, before.best_share_difficulty);
+        assert_eq!(after.sources[0].shares_accepted, 7);
+
+        // Counting starts again from zero, a retiring thread included.
+        stats_tx.send_modify(|s| s.shares_dropped += 2);
+        scheduler.handle_share(task, share_at(600)).await;
+        assert_eq!(scheduler.compute_miner_telemetry().shares_submitted, 1);
+        scheduler.last_thread_count = 1;
+        scheduler
+            .handle_thread_disconnections(&ThreadEventStream::new(), &mut ShareStream::new())
+            .await;
+        assert_eq!(
+            scheduler.unsubmitted_shares(),
+            UnsubmittedShares {
+                queue_dropped: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    /// A template whose network target is mainnet-scale, far above any
+    /// test share.
+    fn mainnet_template(id: &str, difficulty: u64) -> Arc<JobTemplate> {
+        let mut template = (*test_template(id, difficulty)).clone();
+        template.bits = CompactTarget::from_consensus(0x1703a30c);
+        Arc::new(template)
+    }
+
+    #[tokio::test]
+    async fn block_solving_share_is_submitted_and_counted() {
+        let (mut scheduler, source_id, mut command_rx) = scheduler_with_source();
+        let ordinary = insert_task(&mut scheduler, source_id, mainnet_template("job", 100));
+        scheduler.handle_share(ordinary, share_at(500)).await;
+        let Ok(SourceCommand::SubmitShare(share)) = command_rx.try_recv() else {
+            panic!("share should be submitted");
+        };
+        assert!(!share.solves_block);
+        assert_eq!(scheduler.stats.blocks_found, 0);
+
+        // test_template's network target is difficulty 1, so any share
+        // meeting the pool's difficulty also solves the block.
+        let block = insert_task(&mut scheduler, source_id, test_template("block", 100));
+        scheduler.handle_share(block, share_at(500)).await;
+        let Ok(SourceCommand::SubmitShare(share)) = command_rx.try_recv() else {
+            panic!("block solution should be submitted");
+        };
+        assert!(share.solves_block);
+        assert_eq!(share.job_id, "block");
+        assert_eq!(scheduler.compute_miner_telemetry().blocks_found, 1);
+    }
+
+    #[tokio::test]
+    async fn best_share_tracks_highest_difficulty() {
+        let mut scheduler = Scheduler::new(MiningMode::Lottery);
+        let (command_tx, _command_rx) = mpsc::channel(10);
+        let source_id = scheduler.sources.insert(SourceEntry {
+            name: "solo".into(),
+            url: None,
+            command_tx,
+            last_job: None,
+            stats_rx: None,
+            difficulty_alarm: DebouncedAlarm::new(HIGH_DIFFICULTY_DEBOUNCE),
+            en2_slices: Vec::new(),
+            pool: None,
+        });
+        let task = insert_task(&mut scheduler, source_id, mainnet_template("job", 1));
+        assert_eq!(
+            scheduler.compute_miner_telemetry().best_share_difficulty,
+            None
+        );
+
+        for difficulty in [500, 2000, 700] {
+            scheduler.handle_share(task, share_at(difficulty)).await;
+        }
+        assert_eq!(
+            scheduler.compute_miner_telemetry().best_share_difficulty,
+            Some(2000.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn boards_mine_and_submit_on_their_assigned_pools() {
+        let (mut scheduler, main_id, mut main_rx) = scheduler_with_source();
+        let (command_tx, mut test_rx) = mpsc::channel(10);
+        let test_id = scheduler.sources.insert(SourceEntry {
+            name: "test-pool".into(),
+            url: None,
+            command_tx,
+            last_job: None,
+            stats_rx: None,
+            difficulty_alarm: DebouncedAlarm::new(HIGH_DIFFICULTY_DEBOUNCE),
+            en2_slices: Vec::new(),
+            pool: Some("test".into()),
+        });
+        scheduler.assignment = PoolAssignment::new([("board-b".into(), "test".into())]);
+        let hashrate = HashRate::from_terahashes(1.0);
+        insert_thread(&mut scheduler, "a", Some(hashrate));
+        insert_thread(&mut scheduler, "b", Some(hashrate));
+        let mut share_channels = ShareStream::new();
+
+        for (source_id, job_id) in [(main_id, "main-job"), (test_id, "test-job")] {
+            scheduler
+                .assign_job_to_threads(
+                    AssignMode::Replace,
+                    source_id,
+                    computed_template(job_id),
+                    &mut share_channels,
+                )
+                .await;
+        }
+
+        // Board a is unassigned and takes the default pool's job only;
+        // board b takes the test pool's. Each pool hears of its own
+        // board's hashrate.
+        let work: Vec<(String, String)> = scheduler
+            .tasks
+            .values()
+            .map(|task| {
+                let thread = scheduler.threads[task.thread_id].thread.name().to_string();
+                (thread, task.template.id.clone())
+            })
+            .collect();
+        assert_eq!(
+            work,
+            [
+                ("a".to_string(), "main-job".to_string()),
+                ("b".to_string(), "test-job".to_string())
+            ]
+        );
+        assert_eq!(scheduler.allocated_hashrate(main_id), hashrate);
+        assert_eq!(scheduler.allocated_hashrate(test_id), hashrate);
+
+        // Each board's shares go to its own pool.
+        let tasks: Vec<TaskId> = scheduler.tasks.keys().collect();
+        for task in tasks {
+            scheduler.handle_share(task, share_at(500)).await;
+        }
+        for (command_rx, job_id) in [(&mut main_rx, "main-job"), (&mut test_rx, "test-job")] {
+            let Ok(SourceCommand::SubmitShare(share)) = command_rx.try_recv() else {
+                panic!("no share submitted for {job_id}");
+            };
+            assert_eq!(share.job_id, job_id);
+            assert!(command_rx.try_recv().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn promoting_a_pool_moves_unassigned_boards_onto_it() {
+        let (mut scheduler, main_id, _main_rx) = scheduler_with_source();
+        let (command_tx, _backup_rx) = mpsc::channel(10);
+        let backup_id = scheduler.sources.insert(SourceEntry {
+            name: "backup-pool".into(),
+            url: None,
+            command_tx,
+            last_job: None,
+            stats_rx: None,
+            difficulty_alarm: DebouncedAlarm::new(HIGH_DIFFICULTY_DEBOUNCE),
+            en2_slices: Vec::new(),
+            pool: Some("backup".into()),
+        });
+        let hashrate = HashRate::from_terahashes(1.0);
+        insert_thread(&mut scheduler, "a", Some(hashrate));
+        insert_thread(&mut scheduler, "b", Some(hashrate));
+        let mut share_channels = ShareStream::new();
+        for (source_id, job_id) in [(main_id, "main-job"), (backup_id, "backup-job")] {
+            scheduler
+                .assign_job_to_threads(
+                    AssignMode::Replace,
+                    source_id,
+                    computed_template(job_id),
+                    &mut share_channels,
+                )
+                .await;
+        }
+        let jobs = |scheduler: &Scheduler| -> Vec<String> {
+            scheduler
+                .tasks
+                .values()
+                .map(|task| task.template.id.clone())
+                .collect()
+        };
+        assert_eq!(jobs(&scheduler), ["main-job", "main-job"]);
+
+        // A pool no source serves is refused and nothing moves.
+        assert_eq!(
+            scheduler
+                .promote_pool(Some("spare".into()), &mut share_channels)
+                .await,
+            Err(PromoteError::UnknownPool("spare".into()))
+        );
+        assert_eq!(jobs(&scheduler), ["main-job", "main-job"]);
+
+        // Both boards switch to the promoted pool's job, and back.
+        scheduler
+            .promote_pool(Some("backup".into()), &mut share_channels)
+            .await
+            .unwrap();
+        assert_eq!(jobs(&scheduler), ["backup-job", "backup-job"]);
+        assert_eq!(scheduler.allocated_hashrate(main_id), HashRate::default());
+        assert_eq!(scheduler.allocated_hashrate(backup_id), hashrate + hashrate);
+
+        scheduler
+            .promote_pool(None, &mut share_channels)
+            .await
+            .unwrap();
+        assert_eq!(jobs(&scheduler), ["main-job", "main-job"]);
+    }
+
+    #[tokio::test]
+    async fn primary_pool_whose_shares_go_stale_is_failed_over() {
+        let (mut scheduler, main_id, _main_rx) = scheduler_with_source();
+        let window = Duration::from_secs(600);
+        scheduler.stale_failover = Some(StaleFailoverPolicy {
+            max_ratio: 0.1,
+            window,
+        });
+        let (main_stats, stats_rx) = watch::channel(SourceStats::default());
+        scheduler.sources[main_id].stats_rx = Some(stats_rx);
+        let mut others = Vec::new();
+        for pool in ["worse", "backup"] {
+            let (command_tx, command_rx) = mpsc::channel(10);
+            let (stats_tx, stats_rx) = watch::channel(SourceStats::default());
+            let id = scheduler.sources.insert(SourceEntry {
+                name: format!("{pool}-pool"),
+                url: None,
+                command_tx,
+                last_job: None,
+                stats_rx: Some(stats_rx),
+                difficulty_alarm: DebouncedAlarm::new(HIGH_DIFFICULTY_DEBOUNCE),
+                en2_slices: Vec::new(),
+                pool: Some(pool.into()),
+            });
+            others.push((id, stats_tx, command_rx));
+        }
+        insert_thread(&mut scheduler, "a", Some(HashRate::from_terahashes(1.0)));
+        let mut share_channels = ShareStream::new();
+        for (source_id, job_id) in [
+            (main_id, "main-job"),
+            (others[0].0, "worse-job"),
+            (others[1].0, "backup-job"),
+        ] {
+            scheduler
+                .assign_job_to_threads(
+                    AssignMode::Replace,
+                    source_id,
+                    computed_template(job_id),
+                    &mut share_channels,
+                )
+                .await;
+        }
+        let jobs = |scheduler: &Scheduler| -> Vec<String> {
+            scheduler
+                .tasks
+                .values()
+                .map(|task| task.template.id.clone())
+                .collect()
+        };
+        let set = |stats: &watch::Sender<SourceStats>, accepted, stale_avoided| {
+            stats.send_modify(|s| {
+                s.shares_accepted = accepted;
+                s.shares_stale_avoided = stale_avoided;
+            });
+        };
+
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        scheduler
+            .check_stale_failover(at(0), &mut share_channels)
+            .await;
+
+        // A few stale shares over a full window are within the limit.
+        set(&main_stats, 90, 5);
+        scheduler
+            .check_stale_failover(at(300), &mut share_channels)
+            .await;
+        set(&main_stats, 180, 10);
+        scheduler
+            .check_stale_failover(at(600), &mut share_channels)
+            .await;
+        assert_eq!(jobs(&scheduler), ["main-job"]);
+
+        // Then a third of the last window's shares go stale. The boards
+        // skip the pool assigned boards find just as stale, and move to
+        // the next.
+        set(&others[0].1, 20, 20);
+        set(&main_stats, 200, 60);
+        scheduler
+            .check_stale_failover(at(900), &mut share_channels)
+            .await;
+        assert_eq!(scheduler.assignment.primary(), Some("backup"));
+        assert_eq!(jobs(&scheduler), ["backup-job"]);
+
+        // The new pool has no window of its own yet, so it stays.
+        scheduler
+            .check_stale_failover(at(1200), &mut share_channels)
+            .await;
+        assert_eq!(scheduler.assignment.primary(), Some("backup"));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn pool_assignment_from_env() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::set_var(
+                "MUJINA_BOARD_POOLS",
+                "bitaxe-1=test, emberone00-2 = test,bogus",
+            )
+        };
+        let assignment = PoolAssignment::from_env();
+        assert_eq!(assignment.pool_for("bitaxe-1"), Some("test"));
+        assert_eq!(assignment.pool_for("emberone00-2"), Some("test"));
+        assert_eq!(assignment.pool_for("bitaxe-3"), None);
+        assert_eq!(assignment.boards.len(), 2);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { env::remove_var("MUJINA_BOARD_POOLS") };
+        assert_eq!(PoolAssignment::from_env(), PoolAssignment::default());
+    }
+
+    #[test]
+    fn unsearched_slices_cover_distinct_work() {
+        let full = Extranonce2Range::new(4).unwrap();
+        let mut slices = full.split(2).unwrap();
+        let starts: Vec<u64> = slices.iter().map(|s| s.min).collect();
+
+        // Two threads join after the job was split between the first two.
+        let third = take_unsearched_slice(&mut slices).unwrap();
+        let fourth = take_unsearched_slice(&mut slices).unwrap();
+        assert_eq!(slices.len(), 4);
+        assert!(slices.contains(&third) && slices.contains(&fourth));
+
+        // No two threads share an EN2 value, and together they still
+        // cover the whole range.
+        let mut sorted = slices.clone();
+        sorted.sort_by_key(|s| s.min);
+        assert_eq!(sorted.first().unwrap().min, full.min);
+        assert_eq!(sorted.last().unwrap().max, full.max);
+        for pair in sorted.windows(2) {
+            assert_eq!(pair[0].max + 1, pair[1].min, "{pair:?}");
+        }
+
+        // The original threads keep the bottom of their slices, where they
+        // have been searching.
+        for start in starts {
+            for taken in [&third, &fourth] {
+                assert!(!(taken.min..=taken.max).contains(&start), "{taken:?}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn traced_assignments_partition_the_space_between_boards() {
+        let (mut scheduler, source_id, _command_rx) = scheduler_with_source();
+        scheduler.trace_assignments = true;
+        for name in ["a", "b", "c"] {
+            insert_thread(&mut scheduler, name, Some(HashRate::from_terahashes(1.0)));
+        }
+
+        let output = crate::tracing::capture_logs(async {
+            scheduler
+                .assign_job_to_threads(
+                    AssignMode::Replace,
+                    source_id,
+                    computed_template("job"),
+                    &mut ShareStream::new(),
+                )
+                .await;
+        })
+        .await;
+
+        let field = |line: &str, name: &str| {
+            line.split_whitespace()
+                .find_map(|f| f.strip_prefix(&format!("{name}=")))
+                .map(|v| v.trim_matches('"').to_string())
+                .unwrap_or_else(|| panic!("no {name} in {line}"))
+        };
+        let mut logged: Vec<(String, Extranonce2Range)> = output
+            .lines()
+            .filter(|line| line.contains("Work assigned"))
+            .map(|line| {
+                assert_eq!(field(line, "nonces"), "00000000..=ffffffff");
+                let en2 = field(line, "en2");
+                let (min, max) = en2.split_once("..=").unwrap();
+                let range = Extranonce2Range::new_range(
+                    u64::from_str_radix(min, 16).unwrap(),
+                    u64::from_str_radix(max, 16).unwrap(),
+                    4,
+                )
+                .unwrap();
+                (field(line, "board"), range)
+            })
+            .collect();
+        logged.sort_by_key(|(_, range)| range.min);
+
+        // One range per board, disjoint, together covering the space.
+        let boards: HashSet<_> = logged.iter().map(|(board, _)| board.as_str()).collect();
+        assert_eq!(boards, HashSet::from(["board-a", "board-b", "board-c"]));
+        assert_eq!(logged.first().unwrap().1.min, 0);
+        assert_eq!(logged.last().unwrap().1.max, u64::from(u32::MAX));
+        for pair in logged.windows(2) {
+            assert_eq!(pair[0].1.max + 1, pair[1].1.min, "{output}");
+        }
+
+        // And they are the slices the scheduler keeps for the source.
+        let ranges: Vec<_> = logged.into_iter().map(|(_, range)| range).collect();
+        assert_eq!(ranges, scheduler.sources[source_id].en2_slices);
+    }
+
+    #[test]
+    fn partitions_stay_within_the_negotiated_space() {
+        for (size, threads) in [(1, 3), (1, 300), (2, 7), (4, 16), (8, 5)] {
+            let full = Extranonce2Range::new(size).unwrap();
+            let slices = partition_en2(&full, threads);
+            assert_eq!(
+                slices.len() as u64,
+                (threads as u64).min(full.len()),
+                "size {size}"
+            );
+            assert_eq!(slices.first().unwrap().min, full.min);
+            assert_eq!(slices.last().unwrap().max, full.max);
+            for pair in slices.windows(2) {
+                assert_eq!(pair[0].max + 1, pair[1].min, "{pair:?}");
+            }
+            assert!(slices.iter().all(|s| s.size == size));
+        }
+        assert!(partition_en2(&Extranonce2Range::new(4).unwrap(), 0).is_empty());
+    }
+
+    #[test]
+    fn unsearched_slice_needs_a_splittable_slice() {
+        assert!(take_unsearched_slice(&mut Vec::new()).is_none());
+        let mut single = vec![Extranonce2Range::new_range(7, 7, 1).unwrap()];
+        assert!(take_unsearched_slice(&mut single).is_none());
+        assert_eq!(single.len(), 1);
+    }
+
+    #[test]
+    fn recent_shares_stay_bounded() {
+        let mut recent = RecentShares::default();
+        let hash = |i: u32| {
+            let mut bytes = [0u8; 32];
+            bytes[..4].copy_from_slice(&i.to_le_bytes());
+            BlockHash::from_byte_array(bytes)
+        };
+
+        for i in 0..10_000 {
+            assert!(recent.insert(hash(i)));
+        }
+        assert_eq!(recent.order.len(), RecentShares::CAPACITY);
+        assert_eq!(recent.seen.len(), RecentShares::CAPACITY);
+
+        // Recent hashes are still caught; long-evicted ones are forgotten.
+        assert!(!recent.insert(hash(9_999)));
+        assert!(recent.insert(hash(0)));
+    }
 
     #[test]
     fn scheduler_target_zero_hashrate_passthrough() {
@@ -1237,6 +3861,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn scheduler_target_bounds_never_invert() {
+        // From sub-hash-per-second up to far beyond any real fleet, the
+        // measurement floor must stay at or below the flood ceiling in
+        // difficulty terms, or `clamp` would panic.
+        for hashrate in [
+            HashRate::from(1),
+            HashRate::from(9),
+            HashRate::from(11),
+            HashRate::from_megahashes(1.0),
+            HashRate::from_terahashes(1.0),
+            HashRate::from_terahashes(1_000_000.0),
+            HashRate::from(u64::MAX),
+        ] {
+            let floor = MEASUREMENT_SHARE_RATE.to_target(hashrate);
+            let ceiling = FLOOD_CAP_RATE.to_target(hashrate);
+            assert!(floor <= ceiling, "bounds inverted at {hashrate}");
+
+            let result = Scheduler::compute_scheduler_target(hashrate, Target::MAX);
+            assert!(result >= floor && result <= ceiling);
+        }
+    }
+
     #[test]
     fn startup_gate_opens_on_completion_when_all_reported() {
         let mut gate = StartupGate::new();
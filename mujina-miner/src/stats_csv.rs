@@ -0,0 +1,428 @@
+//! Periodic stats appended to a CSV file.
+//!
+//! For graphing a miner's history without running a metrics stack: at a
+//! fixed interval the daemon appends one `fleet` row and one row per
+//! board to a CSV file, which any spreadsheet or plotting tool can read.
+//!
+//! | Column            | Fleet row                          | Board row        |
+//! |-------------------|------------------------------------|------------------|
+//! | `timestamp`       | RFC 3339, UTC, whole seconds       | same             |
+//! | `scope`           | `fleet`                            | board name       |
+//! | `hashrate_hs`     | total hashrate, H/s                | board's, H/s     |
+//! | `temperature_c`   | hottest sensor, Celsius            | board's hottest  |
+//! | `power_w`         | total power, W                     | board's power    |
+//! | `difficulty`      | highest current source difficulty  | empty            |
+//! | `shares_accepted` | lifetime total over sources        | empty            |
+//! | `shares_rejected` | lifetime total over sources        | empty            |
+//!
+//! Values that aren't known are left empty. The file is size-limited:
+//! when the next append would take it past the limit it is renamed to
+//! `<path>.1`, replacing any older one, and a fresh file is started, so at
+//! most two files' worth of history is kept.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write as _};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use crate::api_client::summary::{board_power_w, fleet_summary};
+use crate::api_client::types::{BoardTelemetry, MinerTelemetry};
+use crate::clock::Clock;
+use crate::tracing::prelude::*;
+
+/// First line of every file.
+pub(crate) const HEADER: &str = "timestamp,scope,hashrate_hs,temperature_c,power_w,\
+                                 difficulty,shares_accepted,shares_rejected";
+
+/// Interval between rows when not configured.
+pub(crate) const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Size a file may grow to before rotating, when not configured.
+pub(crate) const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Where and how often to write stats.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CsvConfig {
+    pub path: PathBuf,
+    pub interval: Duration,
+    /// Size at which the file rotates; `None` lets it grow without limit.
+    pub max_bytes: Option<u64>,
+}
+
+/// Read the CSV settings from the environment.
+///
+/// Returns `None` unless `MUJINA_STATS_CSV` names a file. The interval
+/// comes from `MUJINA_STATS_CSV_INTERVAL_SECS` and the size limit, in
+/// megabytes, from `MUJINA_STATS_CSV_MAX_MB`, where 0 means unlimited.
+/// Invalid values warn and use the default.
+pub(crate) fn config_from_env() -> Option<CsvConfig> {
+    let path = env::var_os("MUJINA_STATS_CSV").filter(|p| !p.is_empty())?;
+
+    let interval = match env::var("MUJINA_STATS_CSV_INTERVAL_SECS") {
+        Err(_) => DEFAULT_INTERVAL,
+        Ok(value) => match value.parse::<u64>() {
+            Ok(secs) if secs > 0 => Duration::from_secs(secs),
+            _ => {
+                warn!(
+                    value = %value,
+                    default_secs = DEFAULT_INTERVAL.as_secs(),
+                    "Invalid MUJINA_STATS_CSV_INTERVAL_SECS, using default"
+                );
+                DEFAULT_INTERVAL
+            }
+        },
+    };
+
+    let max_bytes = match env::var("MUJINA_STATS_CSV_MAX_MB") {
+        Err(_) => Some(DEFAULT_MAX_BYTES),
+        Ok(value) => match value.parse::<u64>() {
+            Ok(0) => None,
+            Ok(mb) => Some(mb.saturating_mul(1024 * 1024)),
+            Err(_) => {
+                warn!(value = %value, "Invalid MUJINA_STATS_CSV_MAX_MB, using default");
+                Some(DEFAULT_MAX_BYTES)
+            }
+        },
+    };
+
+    Some(CsvConfig {
+        path: PathBuf::from(path),
+        interval,
+        max_bytes,
+    })
+}
+
+/// The rows for one snapshot taken at `at`: the fleet first, then each
+/// board in order.
+pub(crate) fn rows(at: OffsetDateTime, telemetry: &MinerTelemetry) -> Vec<String> {
+    let timestamp = at
+        .to_offset(time::UtcOffset::UTC)
+        .replace_nanosecond(0)
+        .ok()
+        .and_then(|t| t.format(&Rfc3339).ok())
+        .unwrap_or_default();
+
+    let fleet = fleet_summary(telemetry);
+    let difficulty = telemetry
+        .sources
+        .iter()
+        .filter_map(|s| s.difficulty)
+        .reduce(f64::max);
+    let mut rows = vec![row(
+        &timestamp,
+        "fleet",
+        fleet.hashrate.into(),
+        fleet.hottest.map(|h| h.temperature.as_degrees_c()),
+        fleet.power_w,
+        difficulty,
+        Some((fleet.shares_accepted, fleet.shares_rejected)),
+    )];
+
+    for board in &telemetry.boards {
+        rows.push(row(
+            &timestamp,
+            &board.name,
+            board.threads.iter().map(|t| t.hashrate).sum(),
+            hottest(board),
+            board_power_w(board),
+            None,
+            None,
+        ));
+    }
+    rows
+}
+
+fn hottest(board: &BoardTelemetry) -> Option<f32> {
+    board
+        .temperatures
+        .iter()
+        .filter_map(|s| s.temperature)
+        .map(|t| t.as_degrees_c())
+        .reduce(f32::max)
+}
+
+fn row(
+    timestamp: &str,
+    scope: &str,
+    hashrate: u64,
+    temperature_c: Option<f32>,
+    power_w: Option<f32>,
+    difficulty: Option<f64>,
+    shares: Option<(u64, u64)>,
+) -> String {
+    let mut line = format!("{timestamp},{},{hashrate},", field(scope));
+    if let Some(t) = temperature_c {
+        let _ = write!(line, "{t:.1}");
+    }
+    line.push(',');
+    if let Some(p) = power_w {
+        let _ = write!(line, "{p:.1}");
+    }
+    line.push(',');
+    if let Some(d) = difficulty {
+        let _ = write!(line, "{d}");
+    }
+    line.push(',');
+    if let Some((accepted, rejected)) = shares {
+        let _ = write!(line, "{accepted},{rejected}");
+    } else {
+        line.push(',');
+    }
+    line
+}
+
+/// Quote a field if it holds a separator, quote or line break.
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Appends rows to the CSV file, writing the header to each new file and
+/// rotating at the size limit.
+#[derive(Debug)]
+pub(crate) struct CsvWriter {
+    path: PathBuf,
+    max_bytes: Option<u64>,
+}
+
+impl CsvWriter {
+    pub(crate) fn new(path: PathBuf, max_bytes: Option<u64>) -> Self {
+        Self { path, max_bytes }
+    }
+
+    /// Path the current file is renamed to on rotation.
+    pub(crate) fn rotated_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".1");
+        path.into()
+    }
+
+    /// Append `rows`, each followed by a newline.
+    pub(crate) fn append(&self, rows: &[String]) -> io::Result<()> {
+        let mut text = String::new();
+        for row in rows {
+            text.push_str(row);
+            text.push('\n');
+        }
+
+        let mut size = match fs::metadata(&self.path) {
+            Ok(meta) => meta.len(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+        if let Some(max) = self.max_bytes
+            && size > 0
+            && size + text.len() as u64 > max
+        {
+            fs::rename(&self.path, self.rotated_path())?;
+            size = 0;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        if size == 0 {
+            writeln!(file, "{HEADER}")?;
+        }
+        file.write_all(text.as_bytes())
+    }
+}
+
+/// Append a snapshot's rows every `config.interval` until shutdown.
+///
+/// `snapshot` is called once per interval for the current telemetry.
+/// Write failures are logged and retried at the next interval.
+pub(crate) async fn task(
+    config: CsvConfig,
+    shutdown: CancellationToken,
+    clock: Arc<dyn Clock>,
+    snapshot: impl Fn() -> MinerTelemetry,
+) {
+    let writer = CsvWriter::new(config.path, config.max_bytes);
+    let mut tick = tokio::time::interval_at(Instant::now() + config.interval, config.interval);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    info!(path = %writer.path.display(), "Writing stats to CSV.");
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = tick.tick() => {
+                let rows = rows(clock.now_utc(), &snapshot());
+                if let Err(e) = writer.append(&rows) {
+                    warn!(path = %writer.path.display(), error = %e, "Failed to write stats CSV");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use time::macros::datetime;
+
+    use super::*;
+    use crate::api_client::types::{
+        PowerMeasurement, SourceTelemetry, TemperatureSensor, ThreadTelemetry,
+    };
+    use crate::types::Temperature;
+
+    fn telemetry() -> MinerTelemetry {
+        MinerTelemetry {
+            boards: vec![
+                BoardTelemetry {
+                    name: "bitaxe-e2f56f9b".into(),
+                    threads: vec![ThreadTelemetry {
+                        name: "t0".into(),
+                        hashrate: 1_200_000_000_000,
+                        is_active: true,
+                        fault: None,
+                    }],
+                    temperatures: vec![TemperatureSensor {
+                        name: "asic".into(),
+                        temperature: Some(Temperature::from_celsius(61.4)),
+                    }],
+                    powers: vec![PowerMeasurement {
+                        name: "core".into(),
+                        voltage_v: Some(1.15),
+                        current_a: Some(13.0),
+                        power_w: Some(15.0),
+                    }],
+                    ..Default::default()
+                },
+                BoardTelemetry {
+                    name: "cpu-0".into(),
+                    ..Default::default()
+                },
+            ],
+            sources: vec![SourceTelemetry {
+                difficulty: Some(2048.0),
+                shares_accepted: 120,
+                shares_rejected: 3,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// A path under the system temp dir unique to this test.
+    fn temp_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("mujina-{}-{name}.csv", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn writes_header_and_rows() {
+        let path = temp_path("rows");
+        let writer = CsvWriter::new(path.clone(), None);
+        let at = datetime!(2026-03-01 12:00:00.75 UTC);
+
+        writer.append(&rows(at, &telemetry())).unwrap();
+        writer.append(&rows(at, &telemetry())).unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "timestamp,scope,hashrate_hs,temperature_c,power_w,difficulty,\
+             shares_accepted,shares_rejected"
+        );
+        assert_eq!(
+            lines[1],
+            "2026-03-01T12:00:00Z,fleet,1200000000000,61.4,15.0,2048,120,3"
+        );
+        assert_eq!(
+            lines[2],
+            "2026-03-01T12:00:00Z,bitaxe-e2f56f9b,1200000000000,61.4,15.0,,,"
+        );
+        assert_eq!(lines[3], "2026-03-01T12:00:00Z,cpu-0,0,,,,,");
+        // The second append adds rows without repeating the header.
+        assert_eq!(lines.len(), 7);
+        for line in &lines {
+            assert_eq!(line.split(',').count(), 8, "{line}");
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rotates_at_the_size_limit() {
+        let path = temp_path("rotate");
+        let rows = rows(datetime!(2026-03-01 12:00 UTC), &telemetry());
+        let batch = rows.iter().map(|r| r.len() as u64 + 1).sum::<u64>();
+        let limit = HEADER.len() as u64 + 1 + 2 * batch;
+        let writer = CsvWriter::new(path.clone(), Some(limit));
+
+        writer.append(&rows).unwrap();
+        writer.append(&rows).unwrap();
+        assert!(!writer.rotated_path().exists());
+
+        // A third batch would pass the limit, so it starts a new file.
+        writer.append(&rows).unwrap();
+        let old = fs::read_to_string(writer.rotated_path()).unwrap();
+        let new = fs::read_to_string(&path).unwrap();
+        assert_eq!(old.lines().count(), 1 + 2 * rows.len());
+        assert_eq!(new.lines().count(), 1 + rows.len());
+        assert!(new.starts_with(HEADER));
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(writer.rotated_path()).unwrap();
+    }
+
+    #[test]
+    fn quotes_fields_that_need_it() {
+        assert_eq!(field("board-1"), "board-1");
+        assert_eq!(field("a,b"), "\"a,b\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    #[serial]
+    fn config_env_parsing() {
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var("MUJINA_STATS_CSV");
+            env::remove_var("MUJINA_STATS_CSV_INTERVAL_SECS");
+            env::remove_var("MUJINA_STATS_CSV_MAX_MB");
+            assert_eq!(config_from_env(), None);
+
+            env::set_var("MUJINA_STATS_CSV", "/var/log/mujina.csv");
+            assert_eq!(
+                config_from_env(),
+                Some(CsvConfig {
+                    path: "/var/log/mujina.csv".into(),
+                    interval: DEFAULT_INTERVAL,
+                    max_bytes: Some(DEFAULT_MAX_BYTES),
+                })
+            );
+
+            env::set_var("MUJINA_STATS_CSV_INTERVAL_SECS", "10");
+            env::set_var("MUJINA_STATS_CSV_MAX_MB", "0");
+            let config = config_from_env().unwrap();
+            assert_eq!(config.interval, Duration::from_secs(10));
+            assert_eq!(config.max_bytes, None);
+
+            env::set_var("MUJINA_STATS_CSV_INTERVAL_SECS", "0");
+            env::set_var("MUJINA_STATS_CSV_MAX_MB", "lots");
+            let config = config_from_env().unwrap();
+            assert_eq!(config.interval, DEFAULT_INTERVAL);
+            assert_eq!(config.max_bytes, Some(DEFAULT_MAX_BYTES));
+
+            env::remove_var("MUJINA_STATS_CSV");
+            env::remove_var("MUJINA_STATS_CSV_INTERVAL_SECS");
+            env::remove_var("MUJINA_STATS_CSV_MAX_MB");
+        }
+    }
+}
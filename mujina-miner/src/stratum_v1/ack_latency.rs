@@ -0,0 +1,197 @@
+//! Share acknowledgement latency tracking.
+//!
+//! A pool that takes seconds to answer `mining.submit` is overloaded or far
+//! away, and an operator may want to switch. One slow ack means nothing, a
+//! GC pause or a retransmit, so the monitor only flags a pool whose acks
+//! are slow most of the time over a window of recent submits. The
+//! latencies themselves go into a [`LatencyHistogram`] with
+//! [`ACK_BUCKET_BOUNDS`], whose mean and maximum are logged with each
+//! change.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::client::SUBMIT_TIMEOUT;
+use crate::types::LatencyHistogram;
+
+/// Histogram bucket bounds for ack latencies, from a nearby pool's tens of
+/// milliseconds up to [`SUBMIT_TIMEOUT`], when a submit is given up on.
+pub(crate) const ACK_BUCKET_BOUNDS: [Duration; 6] = [
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+    SUBMIT_TIMEOUT,
+];
+
+/// Ack latencies, and whether each of the recent ones met an SLA.
+#[derive(Debug)]
+pub(crate) struct AckLatencyMonitor {
+    sla: Duration,
+    latencies: LatencyHistogram,
+    /// Whether each ack in the window was slower than the SLA, oldest
+    /// first.
+    recent: VecDeque<bool>,
+    violating: bool,
+}
+
+impl AckLatencyMonitor {
+    /// Acks considered per decision.
+    const WINDOW: usize = 10;
+
+    /// Slow acks in a full window that mark a sustained violation.
+    const TRIP: usize = 8;
+
+    /// Slow acks in the window at or below which the violation has cleared.
+    const CLEAR: usize = Self::WINDOW / 2;
+
+    pub(crate) fn new(sla: Duration) -> Self {
+        Self {
+            sla,
+            latencies: LatencyHistogram::with_bounds(&ACK_BUCKET_BOUNDS),
+            recent: VecDeque::with_capacity(Self::WINDOW),
+            violating: false,
+        }
+    }
+
+    /// Record one ack latency and report a change in SLA state.
+    ///
+    /// Returns [`AckSlaChange::Violated`] once when acks start consistently
+    /// exceeding the SLA, and [`AckSlaChange::Recovered`] once when they
+    /// stop. The gap between the trip and clear levels keeps a pool hovering
+    /// at the boundary from flapping.
+    pub(crate) fn record(&mut self, latency: Duration) -> Option<AckSlaChange> {
+        self.latencies.record(latency);
+        if self.recent.len() == Self::WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(latency > self.sla);
+
+        let slow = self.slow_count();
+        if !self.violating && self.recent.len() == Self::WINDOW && slow >= Self::TRIP {
+            self.violating = true;
+            Some(AckSlaChange::Violated)
+        } else if self.violating && slow <= Self::CLEAR {
+            self.violating = false;
+            Some(AckSlaChange::Recovered)
+        } else {
+            None
+        }
+    }
+
+    /// The configured SLA.
+    pub(crate) fn sla(&self) -> Duration {
+        self.sla
+    }
+
+    /// Every ack latency recorded.
+    pub(crate) fn latencies(&self) -> &LatencyHistogram {
+        &self.latencies
+    }
+
+    /// Acks in the window slower than the SLA.
+    pub(crate) fn slow_count(&self) -> usize {
+        self.recent.iter().filter(|&&slow| slow).count()
+    }
+}
+
+/// Transition reported by [`AckLatencyMonitor::record`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AckSlaChange {
+    Violated,
+    Recovered,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLA: Duration = Duration::from_secs(2);
+    const FAST: Duration = Duration::from_millis(100);
+    const SLOW: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn single_slow_ack_does_not_warn() {
+        let mut monitor = AckLatencyMonitor::new(SLA);
+        for _ in 0..20 {
+            assert_eq!(monitor.record(FAST), None);
+        }
+        assert_eq!(monitor.record(SLOW), None);
+        for _ in 0..20 {
+            assert_eq!(monitor.record(FAST), None);
+        }
+    }
+
+    #[test]
+    fn sustained_slow_acks_warn_once() {
+        let mut monitor = AckLatencyMonitor::new(SLA);
+        let changes: Vec<_> = (0..30).filter_map(|_| monitor.record(SLOW)).collect();
+        assert_eq!(changes, [AckSlaChange::Violated]);
+    }
+
+    #[test]
+    fn warns_only_once_window_is_full() {
+        let mut monitor = AckLatencyMonitor::new(SLA);
+        for _ in 0..AckLatencyMonitor::WINDOW - 1 {
+            assert_eq!(monitor.record(SLOW), None);
+        }
+        assert_eq!(monitor.record(SLOW), Some(AckSlaChange::Violated));
+    }
+
+    #[test]
+    fn intermittent_slow_acks_stay_quiet() {
+        // Every other ack slow: half the window, well under the trip level.
+        let mut monitor = AckLatencyMonitor::new(SLA);
+        for i in 0..50 {
+            let latency = if i % 2 == 0 { SLOW } else { FAST };
+            assert_eq!(monitor.record(latency), None);
+        }
+    }
+
+    #[test]
+    fn recovery_needs_mostly_fast_acks() {
+        let mut monitor = AckLatencyMonitor::new(SLA);
+        for _ in 0..AckLatencyMonitor::WINDOW {
+            monitor.record(SLOW);
+        }
+
+        // A few fast acks are not enough to clear.
+        for _ in 0..AckLatencyMonitor::CLEAR - 1 {
+            assert_eq!(monitor.record(FAST), None);
+        }
+        assert_eq!(monitor.record(FAST), Some(AckSlaChange::Recovered));
+
+        // And a renewed violation warns again.
+        let changes: Vec<_> = (0..20).filter_map(|_| monitor.record(SLOW)).collect();
+        assert_eq!(changes, [AckSlaChange::Violated]);
+    }
+
+    #[test]
+    fn ack_at_sla_is_within_it() {
+        let mut monitor = AckLatencyMonitor::new(SLA);
+        for _ in 0..20 {
+            assert_eq!(monitor.record(SLA), None);
+        }
+        assert_eq!(monitor.slow_count(), 0);
+    }
+
+    #[test]
+    fn every_ack_is_counted_in_the_histogram() {
+        let mut monitor = AckLatencyMonitor::new(SLA);
+        for _ in 0..AckLatencyMonitor::WINDOW {
+            monitor.record(FAST);
+        }
+        monitor.record(SLOW);
+
+        // The window forgets the oldest ack; the histogram keeps them all.
+        let latencies = monitor.latencies();
+        assert_eq!(latencies.count(), AckLatencyMonitor::WINDOW as u64 + 1);
+        assert_eq!(latencies.max(), SLOW);
+        assert_eq!(monitor.slow_count(), 1);
+
+        // Seconds-long acks are told apart, not lumped past 1 s.
+        let counts: Vec<u64> = latencies.buckets().map(|(_, n)| n).collect();
+        assert_eq!(counts, [0, 10, 0, 1, 0, 0, 0]);
+    }
+}
@@ -3,15 +3,27 @@
 //! This module contains the main client that manages the connection lifecycle,
 //! protocol state, and event emission.
 
+use std::collections::HashMap;
 use std::time::Duration;
 
-use super::connection::{Connection, Transport};
+use super::ack_latency::{AckLatencyMonitor, AckSlaChange};
+use super::connection::{Connection, SocketOptions, Transport};
 use super::error::{StratumError, StratumResult};
 use super::messages::{ClientCommand, ClientEvent, JsonRpcMessage, SubmitParams};
 use crate::tracing::prelude::*;
+use crate::types::{DayBoundary, Difficulty, Network};
 use tokio::sync::mpsc;
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 
+/// Largest extranonce2 size accepted from a pool, the most an
+/// [`Extranonce2`](crate::job_source::Extranonce2) can hold.
+const MAX_EXTRANONCE2_SIZE: u64 = 8;
+
+/// Largest extranonce1 accepted from a pool. Pools use 4 to 8 bytes;
+/// anything near this is a broken response rather than a real assignment.
+const MAX_EXTRANONCE1_SIZE: usize = 32;
+
 /// Pool connection configuration.
 #[derive(Debug, Clone)]
 pub struct PoolConfig {
@@ -26,6 +38,250 @@ pub struct PoolConfig {
 
     /// User agent string
     pub user_agent: String,
+
+    /// How to choose the `mining.suggest_difficulty` value
+    pub suggest_difficulty: SuggestDifficulty,
+
+    /// Local minimum share difficulty. Shares below it are not submitted
+    /// even if the pool's difficulty is lower.
+    pub min_difficulty: Option<f64>,
+
+    /// Window over which non-clean job updates are coalesced. Zero forwards
+    /// every job as it arrives. Clean jobs are never held.
+    pub job_debounce: Duration,
+
+    /// Job notifications taken in per second. Past it, jobs that leave old
+    /// work valid are dropped, so a flooding pool can't keep the boards
+    /// switching work; clean jobs always apply. `None` takes every job.
+    pub max_jobs_per_sec: Option<u32>,
+
+    /// Submit acknowledgement latency beyond which the pool counts as slow.
+    /// A warning is logged when most recent acks exceed it; `None` disables
+    /// the check.
+    pub ack_sla: Option<Duration>,
+    /// Difficulty an accepted share must reach to be logged on its own.
+    /// Shares below it are only counted; `None` logs every share at debug.
+    pub log_share_difficulty: Option<Difficulty>,
+
+    /// Network the pool is expected to be mining. Jobs with a block target
+    /// this network doesn't allow are warned about.
+    pub network: Network,
+
+    /// Shares that may await the pool's answer at once. Zero sends each
+    /// share only after the previous one is acknowledged; more lets shares
+    /// found in quick succession go out without queueing behind a round
+    /// trip, for pools close enough that the wait dominates.
+    pub submit_ahead: usize,
+
+    /// Window over which shares sent ahead are gathered into one write.
+    /// Zero writes each share as it comes. A share that solves a block is
+    /// never held; it goes out at once with anything already gathered.
+    /// Has no effect without [`PoolConfig::submit_ahead`], since shares
+    /// then wait on each other's answers anyway.
+    pub submit_batch: Duration,
+
+    /// Times a share the pool never answered is sent again before it is
+    /// given up on. Whichever send the pool answers first decides the
+    /// share, so one the pool took twice still counts once. Zero gives up
+    /// after the first timeout.
+    pub submit_retries: u32,
+
+    /// Where the day-scoped share counts in telemetry restart.
+    pub day_boundary: DayBoundary,
+
+    /// Age after which a replaced job's shares are assumed stale and
+    /// withheld rather than sent to be rejected. The latest job never
+    /// expires, and block solutions are always sent. `None` submits
+    /// shares on any job.
+    pub max_job_age: Option<Duration>,
+
+    /// Consecutive failed connection attempts after which the pool is
+    /// given up on. An attempt fails when the connection can't be made or
+    /// drops before it has been stable for a minute. `None` retries
+    /// forever.
+    pub max_failed_attempts: Option<u32>,
+
+    /// How far past a job's ntime shares may roll it. Capped at
+    /// [`PoolConfig::MAX_NTIME_ROLL`]; zero disables rolling.
+    pub ntime_roll: Duration,
+
+    /// How long shutdown waits for the pool to take shares still queued
+    /// or awaiting its answer. Whatever is left after it is dropped.
+    pub shutdown_flush: Duration,
+
+    /// Recent jobs remembered per connection. Shares on a job that has
+    /// been forgotten are treated as stale and withheld; block solutions
+    /// are always sent.
+    pub job_history: usize,
+
+    /// Outages shorter than this are brief reconnects: logged at debug
+    /// and not counted as disruptions. Zero counts every reconnect.
+    pub reconnect_grace: Duration,
+}
+
+impl PoolConfig {
+    /// Default for [`PoolConfig::user_agent`]: the software and its
+    /// version, so pool operators can tell which miners are mujina.
+    pub const DEFAULT_USER_AGENT: &str = concat!("mujina-miner/", env!("CARGO_PKG_VERSION"));
+
+    /// Default for [`PoolConfig::job_debounce`].
+    pub const DEFAULT_JOB_DEBOUNCE: Duration = Duration::from_millis(500);
+
+    /// Default for [`PoolConfig::max_jobs_per_sec`], far above what any
+    /// working pool sends.
+    pub const DEFAULT_MAX_JOBS_PER_SEC: u32 = 10;
+
+    /// Default for [`PoolConfig::ack_sla`].
+    pub const DEFAULT_ACK_SLA: Duration = Duration::from_secs(3);
+
+    /// Furthest ntime may be rolled past a job's. Stratum has no way for
+    /// a pool to state its window; ckpool, which many pools run, rejects
+    /// shares more than 7000 seconds ahead of the job.
+    pub const MAX_NTIME_ROLL: Duration = Duration::from_secs(7000);
+
+    /// Default for [`PoolConfig::shutdown_flush`].
+    pub const DEFAULT_SHUTDOWN_FLUSH: Duration = Duration::from_secs(5);
+
+    /// Default for [`PoolConfig::job_history`]. At a notify every 30
+    /// seconds or so this covers half an hour, far longer than a pool
+    /// keeps accepting shares on a job.
+    pub const DEFAULT_JOB_HISTORY: usize = 64;
+
+    /// Default for [`PoolConfig::reconnect_grace`]. The first retry waits
+    /// half a second to a second, so a pool that takes the connection back
+    /// at once falls within it.
+    pub const DEFAULT_RECONNECT_GRACE: Duration = Duration::from_secs(1);
+
+    /// Read the settings for the pool at `url` from the `MUJINA_POOL_*`
+    /// variables, warning about any that don't parse and using the
+    /// default for them. [`PoolConfig::network`] is left at its default,
+    /// for the caller to set.
+    pub fn from_env(url: String) -> Self {
+        let default = Self::default();
+        let millis = |val: &str| val.parse().ok().map(Duration::from_millis);
+        let secs = |val: &str| val.parse().ok().map(Duration::from_secs);
+        let nonzero = |d: Duration| (!d.is_zero()).then_some(d);
+        Self {
+            url,
+            username: std::env::var("MUJINA_POOL_USER")
+                .unwrap_or_else(|_| "mujina-testing".to_string()),
+            password: std::env::var("MUJINA_POOL_PASS").unwrap_or_else(|_| "x".to_string()),
+            user_agent: std::env::var("MUJINA_POOL_USER_AGENT")
+                .ok()
+                .filter(|agent| !agent.is_empty())
+                .unwrap_or(default.user_agent),
+            suggest_difficulty: SuggestDifficulty::from_env(),
+            min_difficulty: env_setting("MUJINA_POOL_MIN_DIFFICULTY", None, "ignoring", |val| {
+                let d = val.parse::<f64>().ok()?;
+                (d.is_finite() && d > 0.0).then_some(Some(d))
+            }),
+            job_debounce: env_setting(
+                "MUJINA_POOL_JOB_DEBOUNCE_MS",
+                default.job_debounce,
+                "using default",
+                millis,
+            ),
+            max_jobs_per_sec: env_setting(
+                "MUJINA_POOL_MAX_JOBS_PER_SEC",
+                default.max_jobs_per_sec,
+                "using default",
+                |val| {
+                    val.parse::<u32>()
+                        .ok()
+                        .map(|jobs| (jobs > 0).then_some(jobs))
+                },
+            ),
+            ack_sla: env_setting(
+                "MUJINA_POOL_ACK_SLA_MS",
+                default.ack_sla,
+                "using default",
+                |val| millis(val).map(nonzero),
+            ),
+            log_share_difficulty: env_setting(
+                "MUJINA_LOG_SHARE_DIFFICULTY",
+                None,
+                "logging every share",
+                |val| Difficulty::from_si(val).map(Some),
+            ),
+            network: default.network,
+            submit_ahead: env_setting(
+                "MUJINA_POOL_SUBMIT_AHEAD",
+                default.submit_ahead,
+                "waiting for each answer",
+                |val| val.parse().ok(),
+            ),
+            submit_batch: env_setting(
+                "MUJINA_POOL_SUBMIT_BATCH_MS",
+                default.submit_batch,
+                "writing each share",
+                millis,
+            ),
+            submit_retries: env_setting(
+                "MUJINA_POOL_SUBMIT_RETRIES",
+                default.submit_retries,
+                "not retrying",
+                |val| val.parse().ok(),
+            ),
+            day_boundary: DayBoundary::from_env(),
+            max_job_age: env_setting("MUJINA_POOL_MAX_JOB_AGE_SECS", None, "ignoring", |val| {
+                secs(val).map(nonzero)
+            }),
+            max_failed_attempts: env_setting(
+                "MUJINA_POOL_MAX_ATTEMPTS",
+                None,
+                "retrying forever",
+                |val| val.parse::<u32>().ok().map(|n| (n > 0).then_some(n)),
+            ),
+            ntime_roll: env_setting(
+                "MUJINA_POOL_NTIME_ROLL_SECS",
+                default.ntime_roll,
+                "using default",
+                |val| {
+                    let roll = secs(val)?;
+                    if roll > Self::MAX_NTIME_ROLL {
+                        warn!(value = %val, "MUJINA_POOL_NTIME_ROLL_SECS beyond the pool window, capping");
+                    }
+                    Some(roll.min(Self::MAX_NTIME_ROLL))
+                },
+            ),
+            shutdown_flush: env_setting(
+                "MUJINA_POOL_FLUSH_GRACE_MS",
+                default.shutdown_flush,
+                "using default",
+                millis,
+            ),
+            job_history: env_setting(
+                "MUJINA_POOL_JOB_HISTORY",
+                default.job_history,
+                "using default",
+                |val| val.parse().ok().filter(|&jobs| jobs > 0),
+            ),
+            reconnect_grace: env_setting(
+                "MUJINA_POOL_RECONNECT_GRACE_MS",
+                default.reconnect_grace,
+                "using default",
+                millis,
+            ),
+        }
+    }
+}
+
+/// Read the variable `name` with `parse`, giving `default` when it is
+/// unset. A value `parse` rejects is warned about, with `instead` saying
+/// what happens in its place, and gives `default` too.
+pub(crate) fn env_setting<T>(
+    name: &str,
+    default: T,
+    instead: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> T {
+    let Ok(val) = std::env::var(name) else {
+        return default;
+    };
+    parse(&val).unwrap_or_else(|| {
+        warn!(value = %val, "Invalid {name}, {instead}");
+        default
+    })
 }
 
 impl Default for PoolConfig {
@@ -34,7 +290,68 @@ impl Default for PoolConfig {
             url: String::new(),
             username: String::new(),
             password: String::new(),
-            user_agent: "mujina-miner/0.1.0-alpha".to_string(),
+            user_agent: Self::DEFAULT_USER_AGENT.to_string(),
+            suggest_difficulty: SuggestDifficulty::default(),
+            min_difficulty: None,
+            job_debounce: Self::DEFAULT_JOB_DEBOUNCE,
+            max_jobs_per_sec: Some(Self::DEFAULT_MAX_JOBS_PER_SEC),
+            ack_sla: Some(Self::DEFAULT_ACK_SLA),
+            log_share_difficulty: None,
+            network: Network::Bitcoin,
+            submit_ahead: 0,
+            submit_batch: Duration::ZERO,
+            submit_retries: 0,
+            day_boundary: DayBoundary::UTC,
+            max_job_age: None,
+            max_failed_attempts: None,
+            ntime_roll: Self::MAX_NTIME_ROLL,
+            shutdown_flush: Self::DEFAULT_SHUTDOWN_FLUSH,
+            job_history: Self::DEFAULT_JOB_HISTORY,
+            reconnect_grace: Self::DEFAULT_RECONNECT_GRACE,
+        }
+    }
+}
+
+/// Policy for sending `mining.suggest_difficulty` to the pool.
+///
+/// The suggestion is a hint. Pools that ignore it leave the session on
+/// their own difficulty, which is no error.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SuggestDifficulty {
+    /// Derive from expected hashrate on connect, re-suggesting as it
+    /// changes.
+    #[default]
+    Auto,
+
+    /// Suggest this difficulty on connect and never re-suggest.
+    Fixed(f64),
+
+    /// Never suggest; mine at whatever difficulty the pool sets.
+    Off,
+}
+
+impl SuggestDifficulty {
+    /// Parse from `MUJINA_POOL_SUGGEST_DIFFICULTY`.
+    ///
+    /// Accepts `auto`, `off`, or a positive difficulty. Unset or invalid
+    /// values fall back to `Auto`.
+    pub fn from_env() -> Self {
+        let Ok(val) = std::env::var("MUJINA_POOL_SUGGEST_DIFFICULTY") else {
+            return Self::Auto;
+        };
+        match val.trim().to_ascii_lowercase().as_str() {
+            "auto" => Self::Auto,
+            "off" => Self::Off,
+            other => match other.parse::<f64>() {
+                Ok(d) if d.is_finite() && d > 0.0 => Self::Fixed(d),
+                _ => {
+                    warn!(
+                        value = %val,
+                        "Invalid MUJINA_POOL_SUGGEST_DIFFICULTY, using auto"
+                    );
+                    Self::Auto
+                }
+            },
         }
     }
 }
@@ -69,6 +386,53 @@ pub struct StratumV1Client {
     /// Initial difficulty to suggest during the handshake (before the main
     /// event loop). Subsequent re-suggestions arrive via `ClientCommand`.
     initial_suggest_difficulty: Option<f64>,
+
+    /// Submit ack latency check, when an SLA is configured.
+    ack_latency: Option<AckLatencyMonitor>,
+
+    /// Shares submitted ahead, by request ID, until the pool answers.
+    pending_submits: HashMap<u64, PendingSubmit>,
+
+    /// Earlier sends of shares sent again, by request ID, with the ID of
+    /// their latest send, so a late answer to one still decides the share.
+    resent: HashMap<u64, u64>,
+
+    /// Set once the command channel closes: the client stops when the
+    /// last share sent ahead is answered.
+    draining: bool,
+
+    /// Shares gathered for the next batched write.
+    unsent: Vec<UnsentSubmit>,
+
+    /// When the gathered shares are written, if any are waiting.
+    batch_deadline: Option<Instant>,
+}
+
+/// How long the pool has to answer a share submission.
+pub(super) const SUBMIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A share sent ahead, awaiting the pool's answer.
+#[derive(Debug)]
+struct PendingSubmit {
+    job_id: String,
+    nonce: u32,
+    sent_at: Instant,
+    /// The `mining.submit` parameters, to send again.
+    params: serde_json::Value,
+    /// Sends left before the share is given up on.
+    retries_left: u32,
+    /// Request IDs of the earlier sends, first first.
+    earlier: Vec<u64>,
+}
+
+/// A share sent ahead, gathered for a batched write.
+#[derive(Debug)]
+struct UnsentSubmit {
+    id: u64,
+    msg: JsonRpcMessage,
+    params: serde_json::Value,
+    job_id: String,
+    nonce: u32,
 }
 
 /// Protocol state after successful subscription.
@@ -96,6 +460,7 @@ impl StratumV1Client {
         shutdown: CancellationToken,
     ) -> Self {
         Self {
+            ack_latency: config.ack_sla.map(AckLatencyMonitor::new),
             config,
             event_tx,
             command_rx: None,
@@ -103,6 +468,11 @@ impl StratumV1Client {
             next_id: 1,
             state: None,
             initial_suggest_difficulty: None,
+            pending_submits: HashMap::new(),
+            resent: HashMap::new(),
+            draining: false,
+            unsent: Vec::new(),
+            batch_deadline: None,
         }
     }
 
@@ -115,6 +485,7 @@ impl StratumV1Client {
         initial_suggest_difficulty: Option<f64>,
     ) -> Self {
         Self {
+            ack_latency: config.ack_sla.map(AckLatencyMonitor::new),
             config,
             event_tx,
             command_rx: Some(command_rx),
@@ -122,6 +493,11 @@ impl StratumV1Client {
             next_id: 1,
             state: None,
             initial_suggest_difficulty,
+            pending_submits: HashMap::new(),
+            resent: HashMap::new(),
+            draining: false,
+            unsent: Vec::new(),
+            batch_deadline: None,
         }
     }
 
@@ -147,14 +523,25 @@ impl StratumV1Client {
         params: serde_json::Value,
         timeout_dur: Duration,
     ) -> StratumResult<JsonRpcMessage> {
-        use tokio::time::timeout;
-
         let id = self.next_id();
 
         // Send request
         let msg = JsonRpcMessage::request(id, method, params);
         conn.write_message(&msg).await?;
 
+        self.await_response(conn, &[id], timeout_dur).await
+    }
+
+    /// Wait for the response to any of the requests `ids`, handling
+    /// notifications along the way. Times out after `timeout_dur`.
+    async fn await_response(
+        &mut self,
+        conn: &mut dyn Transport,
+        ids: &[u64],
+        timeout_dur: Duration,
+    ) -> StratumResult<JsonRpcMessage> {
+        use tokio::time::timeout;
+
         // Loop until we get our response, handling notifications along the way
         timeout(timeout_dur, async {
             loop {
@@ -164,7 +551,7 @@ impl StratumV1Client {
                         let msg = result?.ok_or(StratumError::Disconnected)?;
 
                         match msg {
-                            JsonRpcMessage::Response { id: resp_id, .. } if resp_id == id => {
+                            JsonRpcMessage::Response { id: resp_id, .. } if ids.contains(&resp_id) => {
                                 // This is our response
                                 return Ok(msg);
                             }
@@ -331,9 +718,28 @@ impl StratumV1Client {
                     StratumError::InvalidMessage("extranonce1 not a string".to_string())
                 })?;
 
+                if extranonce1.len() % 2 != 0
+                    || extranonce1.len() / 2 > MAX_EXTRANONCE1_SIZE
+                    || !extranonce1.bytes().all(|b| b.is_ascii_hexdigit())
+                {
+                    return Err(StratumError::InvalidMessage(format!(
+                        "extranonce1 {extranonce1:?} is not hex of at most \
+                         {MAX_EXTRANONCE1_SIZE} bytes"
+                    )));
+                }
+
                 let extranonce2_size = arr[2].as_u64().ok_or_else(|| {
                     StratumError::InvalidMessage("extranonce2_size not a number".to_string())
-                })? as usize;
+                })?;
+                // The size bounds every extranonce2 we roll, so one we
+                // can't represent would have us submit work the pool
+                // never assigned.
+                if !(1..=MAX_EXTRANONCE2_SIZE).contains(&extranonce2_size) {
+                    return Err(StratumError::InvalidMessage(format!(
+                        "extranonce2_size {extranonce2_size} outside 1-{MAX_EXTRANONCE2_SIZE} bytes"
+                    )));
+                }
+                let extranonce2_size = extranonce2_size as usize;
 
                 self.state = Some(ProtocolState {
                     extranonce1: extranonce1.to_string(),
@@ -464,17 +870,189 @@ impl StratumV1Client {
         let nonce = params.nonce;
 
         // Convert to Stratum JSON format
-        let submit_json = params.to_stratum_json();
-        let response = self
-            .send_request(
-                conn,
-                "mining.submit",
-                Value::Array(submit_json),
-                Duration::from_secs(30),
-            )
-            .await?;
+        let submit_json = Value::Array(params.to_stratum_json());
+        // Every send of the share, so an answer to an earlier one that
+        // comes in late still counts.
+        let mut ids = Vec::new();
+        let mut retries_left = self.config.submit_retries;
+        let (sent_at, response) = loop {
+            let id = self.next_id();
+            ids.push(id);
+            let msg = JsonRpcMessage::request(id, "mining.submit", submit_json.clone());
+            conn.write_message(&msg).await?;
+            let sent_at = Instant::now();
+            match self.await_response(conn, &ids, SUBMIT_TIMEOUT).await {
+                Ok(response) => break (sent_at, response),
+                Err(StratumError::Timeout) if retries_left > 0 => {
+                    retries_left -= 1;
+                    warn!(
+                        pool = %self.config.url,
+                        job_id = %job_id,
+                        retries_left,
+                        "No response to share, sending it again"
+                    );
+                }
+                Err(StratumError::Timeout) => {
+                    self.event_tx
+                        .send(ClientEvent::ShareUnanswered { job_id, nonce })
+                        .await
+                        .ok();
+                    return Err(StratumError::Timeout);
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        self.record_ack_latency(sent_at.elapsed());
+        let resent = response.id().is_some_and(|id| id != ids[0]);
+        self.handle_submit_response(job_id, nonce, response, resent)
+            .await
+    }
 
-        // Parse response and emit appropriate event
+    /// Submit a share without waiting for the pool's answer.
+    ///
+    /// The answer is matched by request ID in the main loop, which emits
+    /// the same events as [`submit`](Self::submit). Shares reach here only
+    /// after the scheduler has checked them against the share target, so
+    /// sending ahead never sends anything [`submit`](Self::submit) wouldn't.
+    ///
+    /// With [`PoolConfig::submit_batch`] set, the share is gathered and
+    /// written with the others arriving within the window, unless it
+    /// solves a block or fills the submit-ahead window.
+    async fn submit_ahead(
+        &mut self,
+        conn: &mut dyn Transport,
+        params: SubmitParams,
+        solves_block: bool,
+    ) -> StratumResult<()> {
+        let id = self.next_id();
+        let submit_json = serde_json::Value::Array(params.to_stratum_json());
+        let msg = JsonRpcMessage::request(id, "mining.submit", submit_json.clone());
+        self.unsent.push(UnsentSubmit {
+            id,
+            msg,
+            params: submit_json,
+            job_id: params.job_id,
+            nonce: params.nonce,
+        });
+        let window = self.config.submit_batch;
+        let full = self.pending_submits.len() + self.unsent.len() >= self.config.submit_ahead;
+        if window.is_zero() || solves_block || full {
+            return self.flush_submits(conn).await;
+        }
+        self.batch_deadline
+            .get_or_insert_with(|| Instant::now() + window);
+        Ok(())
+    }
+
+    /// Write the gathered shares in one go and start awaiting their
+    /// answers.
+    async fn flush_submits(&mut self, conn: &mut dyn Transport) -> StratumResult<()> {
+        self.batch_deadline = None;
+        let unsent = std::mem::take(&mut self.unsent);
+        match unsent.as_slice() {
+            [] => return Ok(()),
+            [one] => conn.write_message(&one.msg).await?,
+            _ => {
+                let msgs: Vec<_> = unsent.iter().map(|submit| submit.msg.clone()).collect();
+                trace!(pool = %self.config.url, shares = msgs.len(), "Writing share batch");
+                conn.write_messages(&msgs).await?;
+            }
+        }
+        let sent_at = Instant::now();
+        for submit in unsent {
+            self.pending_submits.insert(
+                submit.id,
+                PendingSubmit {
+                    job_id: submit.job_id,
+                    nonce: submit.nonce,
+                    sent_at,
+                    params: submit.params,
+                    retries_left: self.config.submit_retries,
+                    earlier: Vec::new(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// When the oldest share sent ahead runs out of time to be answered.
+    fn submit_deadline(&self) -> Instant {
+        self.pending_submits
+            .values()
+            .map(|pending| pending.sent_at)
+            .min()
+            .unwrap_or_else(Instant::now)
+            + SUBMIT_TIMEOUT
+    }
+
+    /// Send shares sent ahead that the pool never answered again, or give
+    /// up on them once out of retries.
+    async fn expire_pending_submits(&mut self, conn: &mut dyn Transport) {
+        let now = Instant::now();
+        let expired: Vec<u64> = self
+            .pending_submits
+            .iter()
+            .filter(|(_, pending)| now.duration_since(pending.sent_at) >= SUBMIT_TIMEOUT)
+            .map(|(&id, _)| id)
+            .collect();
+        for id in expired {
+            let Some(mut pending) = self.pending_submits.remove(&id) else {
+                continue;
+            };
+            if pending.retries_left > 0 {
+                let resend_id = self.next_id();
+                let msg =
+                    JsonRpcMessage::request(resend_id, "mining.submit", pending.params.clone());
+                match conn.write_message(&msg).await {
+                    Ok(()) => {
+                        pending.retries_left -= 1;
+                        warn!(
+                            pool = %self.config.url,
+                            job_id = %pending.job_id,
+                            retries_left = pending.retries_left,
+                            "No response to share, sending it again"
+                        );
+                        pending.earlier.push(id);
+                        for earlier in &pending.earlier {
+                            self.resent.insert(*earlier, resend_id);
+                        }
+                        pending.sent_at = Instant::now();
+                        self.pending_submits.insert(resend_id, pending);
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!(pool = %self.config.url, error = %e, "Failed to send share again");
+                    }
+                }
+            }
+            for earlier in &pending.earlier {
+                self.resent.remove(earlier);
+            }
+            warn!(
+                pool = %self.config.url,
+                job_id = %pending.job_id,
+                "Failed to submit share: no response from pool"
+            );
+            self.event_tx
+                .send(ClientEvent::ShareUnanswered {
+                    job_id: pending.job_id,
+                    nonce: pending.nonce,
+                })
+                .await
+                .ok();
+        }
+    }
+
+    /// Emit ShareAccepted or ShareRejected for the pool's answer to a
+    /// submit. A share sent again that the pool calls a duplicate was
+    /// taken the first time, and counts as accepted.
+    async fn handle_submit_response(
+        &mut self,
+        job_id: String,
+        nonce: u32,
+        response: JsonRpcMessage,
+        resent: bool,
+    ) -> StratumResult<bool> {
         match response {
             JsonRpcMessage::Response {
                 result: Some(result),
@@ -492,6 +1070,7 @@ impl StratumV1Client {
                     self.event_tx
                         .send(ClientEvent::ShareRejected {
                             job_id,
+                            nonce,
                             reason: "Pool returned false".to_string(),
                         })
                         .await
@@ -513,9 +1092,19 @@ impl StratumV1Client {
                     format!("{:?}", error)
                 };
 
+                if resent && reason.to_ascii_lowercase().contains("duplicate") {
+                    debug!(job_id = %job_id, "Share sent again was a duplicate, so the pool took it");
+                    self.event_tx
+                        .send(ClientEvent::ShareAccepted { job_id, nonce })
+                        .await
+                        .map_err(|_| StratumError::Disconnected)?;
+                    return Ok(true);
+                }
+
                 self.event_tx
                     .send(ClientEvent::ShareRejected {
                         job_id,
+                        nonce,
                         reason: reason.clone(),
                     })
                     .await
@@ -529,6 +1118,36 @@ impl StratumV1Client {
         }
     }
 
+    /// Feed one submit round trip to the SLA check, logging when the pool
+    /// becomes or stops being consistently slow.
+    fn record_ack_latency(&mut self, latency: Duration) {
+        let Some(monitor) = &mut self.ack_latency else {
+            return;
+        };
+        let change = monitor.record(latency);
+        let slow_acks = monitor.slow_count();
+        let mean_ms = monitor.latencies().mean().as_millis() as u64;
+        let max_ms = monitor.latencies().max().as_millis() as u64;
+        match change {
+            Some(AckSlaChange::Violated) => warn!(
+                pool = %self.config.url,
+                sla_ms = monitor.sla().as_millis() as u64,
+                slow_acks,
+                mean_ms,
+                max_ms,
+                "Pool is consistently slow to acknowledge shares; consider another pool"
+            ),
+            Some(AckSlaChange::Recovered) => info!(
+                pool = %self.config.url,
+                slow_acks,
+                mean_ms,
+                max_ms,
+                "Pool share acknowledgements back within SLA"
+            ),
+            None => {}
+        }
+    }
+
     /// Handle a notification from the pool.
     async fn handle_notification(
         &mut self,
@@ -651,7 +1270,7 @@ impl StratumV1Client {
     /// Establishes a TCP connection then delegates to
     /// [`run_with_transport`](Self::run_with_transport).
     pub async fn run(self) -> StratumResult<()> {
-        let conn = Connection::connect(&self.config.url).await?;
+        let conn = Connection::connect(&self.config.url, None, &SocketOptions::default()).await?;
         self.run_with_transport(conn).await
     }
 
@@ -659,7 +1278,8 @@ impl StratumV1Client {
     ///
     /// Performs the Stratum handshake (configure, subscribe, authorize),
     /// then enters the main event loop to handle notifications and
-    /// submit shares.
+    /// submit shares. Returns on shutdown, or once the command channel
+    /// has closed and every share sent ahead has been answered.
     pub(crate) async fn run_with_transport(
         mut self,
         mut conn: impl Transport,
@@ -699,6 +1319,10 @@ impl StratumV1Client {
         // Authorize
         self.authorize(&mut conn).await?;
         debug!("Authorized");
+        self.event_tx
+            .send(ClientEvent::Authorized)
+            .await
+            .map_err(|_| StratumError::Disconnected)?;
 
         // Suggest difficulty after authorize. The source drops jobs
         // until the pool responds with a matching set_difficulty, so
@@ -729,11 +1353,31 @@ impl StratumV1Client {
                                         }
                                     }
                                 }
-                                JsonRpcMessage::Response { id, .. } => {
-                                    // Response to a request we sent
-                                    // During main loop, we handle responses inline in submit()
-                                    // This would be a stray response - log and ignore
-                                    debug!(msg_id = %id, "Received unexpected response in main loop");
+                                response @ JsonRpcMessage::Response { id, .. } => {
+                                    // Answers to shares sent ahead are matched here;
+                                    // other responses are handled inline by the
+                                    // request that sent them, so this one is stray.
+                                    // A late answer to an earlier send of a share
+                                    // decides it as well as one to the latest.
+                                    let latest = self.resent.get(&id).copied().unwrap_or(id);
+                                    let Some(pending) = self.pending_submits.remove(&latest) else {
+                                        debug!(msg_id = %id, "Received unexpected response in main loop");
+                                        continue;
+                                    };
+                                    for earlier in &pending.earlier {
+                                        self.resent.remove(earlier);
+                                    }
+                                    self.record_ack_latency(pending.sent_at.elapsed());
+                                    let resent = pending.earlier.first().is_some_and(|&first| first != id);
+                                    if let Err(e) = self
+                                        .handle_submit_response(pending.job_id, pending.nonce, response, resent)
+                                        .await
+                                    {
+                                        warn!(pool = %self.config.url, error = %e, "Failed to submit share");
+                                    }
+                                    if self.draining && self.pending_submits.is_empty() {
+                                        return Ok(());
+                                    }
                                 }
                                 JsonRpcMessage::Request { id: Some(_), method, .. } => {
                                     // Request with ID from server (unusual, but handle it)
@@ -759,17 +1403,60 @@ impl StratumV1Client {
                     }
                 }
 
-                // Commands from external code (if command channel exists)
-                Some(cmd) = async {
+                // Shares sent ahead that the pool sat on too long
+                _ = tokio::time::sleep_until(self.submit_deadline()),
+                    if !self.pending_submits.is_empty() =>
+                {
+                    self.expire_pending_submits(&mut conn).await;
+                    if self.draining && self.pending_submits.is_empty() {
+                        return Ok(());
+                    }
+                }
+
+                // Gathered shares whose batching window has closed
+                _ = async {
+                    match self.batch_deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                }, if self.batch_deadline.is_some() => {
+                    if let Err(e) = self.flush_submits(&mut conn).await {
+                        warn!(pool = %self.config.url, error = %e, "Failed to submit share");
+                    }
+                }
+
+                // Commands from external code (if command channel exists).
+                // Held off while the submit-ahead window is full, so a
+                // slow pool backs shares up into the source's queue as
+                // it would without sending ahead.
+                cmd = async {
                     match &mut self.command_rx {
                         Some(rx) => rx.recv().await,
                         None => std::future::pending().await,
                     }
-                } => {
+                }, if self.pending_submits.len() + self.unsent.len() < self.config.submit_ahead.max(1) => {
+                    let Some(cmd) = cmd else {
+                        // The source is done submitting. Stop once the
+                        // pool has answered what was sent ahead.
+                        if let Err(e) = self.flush_submits(&mut conn).await {
+                            warn!(pool = %self.config.url, error = %e, "Failed to submit share");
+                        }
+                        if self.pending_submits.is_empty() {
+                            return Ok(());
+                        }
+                        self.command_rx = None;
+                        self.draining = true;
+                        continue;
+                    };
                     match cmd {
-                        ClientCommand::SubmitShare(params) => {
+                        ClientCommand::SubmitShare { params, solves_block } => {
                             debug!(pool = %self.config.url, job_id = %params.job_id, "Submitting share");
-                            if let Err(e) = self.submit(&mut conn, params).await {
+                            let result = if self.config.submit_ahead > 0 {
+                                self.submit_ahead(&mut conn, params, solves_block).await
+                            } else {
+                                self.submit(&mut conn, params).await.map(|_| ())
+                            };
+                            if let Err(e) = result {
                                 warn!(pool = %self.config.url, error = %e, "Failed to submit share");
                             }
                             // Acceptance/rejection emitted via ShareAccepted/ShareRejected events
@@ -948,6 +1635,7 @@ mod tests {
             username: username.to_string(),
             password: "x".to_string(),
             user_agent: "mujina-miner/0.1.0-test".to_string(),
+            ..Default::default()
         };
 
         println!("\n=== Connecting to {} ===", pool_url);
@@ -1110,6 +1798,7 @@ mod tests {
             username: "test".to_string(),
             password: "x".to_string(),
             user_agent: "test".to_string(),
+            ..Default::default()
         };
 
         let client = StratumV1Client::new(config, event_tx, shutdown);
@@ -1335,7 +2024,7 @@ mod tests {
         // Verify ShareRejected event was emitted with reason
         let event = event_rx.try_recv().expect("Expected ShareRejected event");
         match event {
-            ClientEvent::ShareRejected { job_id, reason } => {
+            ClientEvent::ShareRejected { job_id, reason, .. } => {
                 assert_eq!(job_id, "job456");
                 assert_eq!(reason, "Low difficulty share");
             }
@@ -1379,11 +2068,541 @@ mod tests {
         // Verify ShareRejected event was emitted
         let event = event_rx.try_recv().expect("Expected ShareRejected event");
         match event {
-            ClientEvent::ShareRejected { job_id, reason } => {
+            ClientEvent::ShareRejected { job_id, reason, .. } => {
                 assert_eq!(job_id, "job789");
                 assert_eq!(reason, "Pool returned false");
             }
             _ => panic!("Expected ShareRejected, got {:?}", event),
         }
     }
+
+    /// Run a client with `submit_ahead` through the handshake, returning
+    /// its command sender, event receiver and the pool side of the
+    /// connection.
+    async fn client_in_main_loop(
+        submit_ahead: usize,
+    ) -> (
+        mpsc::Sender<ClientCommand>,
+        mpsc::Receiver<ClientEvent>,
+        super::super::connection::MockTransportHandle,
+        CancellationToken,
+    ) {
+        client_with_config_in_main_loop(PoolConfig {
+            url: "test:3333".to_string(),
+            username: "test".to_string(),
+            submit_ahead,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// [`client_in_main_loop`] for a client with `config`.
+    async fn client_with_config_in_main_loop(
+        config: PoolConfig,
+    ) -> (
+        mpsc::Sender<ClientCommand>,
+        mpsc::Receiver<ClientEvent>,
+        super::super::connection::MockTransportHandle,
+        CancellationToken,
+    ) {
+        use super::super::connection::MockTransport;
+        use serde_json::json;
+
+        let (event_tx, event_rx) = mpsc::channel(64);
+        let (command_tx, command_rx) = mpsc::channel(64);
+        let shutdown = CancellationToken::new();
+        let client =
+            StratumV1Client::with_commands(config, event_tx, command_rx, shutdown.clone(), None);
+        let (transport, mut handle) = MockTransport::pair();
+        tokio::spawn(client.run_with_transport(transport));
+
+        for (method, result) in [
+            ("mining.configure", json!({"version-rolling": false})),
+            ("mining.subscribe", json!([[], "aabb", 4])),
+            ("mining.authorize", json!(true)),
+        ] {
+            let msg = handle.recv().await;
+            assert_eq!(msg.method(), Some(method));
+            handle.send(JsonRpcMessage::Response {
+                id: msg.id().unwrap(),
+                result: Some(result),
+                error: None,
+            });
+        }
+        (command_tx, event_rx, handle, shutdown)
+    }
+
+    fn submit_command(nonce: u32) -> ClientCommand {
+        ClientCommand::SubmitShare {
+            params: SubmitParams {
+                username: "test".to_string(),
+                job_id: "job1".to_string(),
+                extranonce2: vec![0; 4],
+                ntime: 0x12345678,
+                nonce,
+                version_bits: None,
+            },
+            solves_block: false,
+        }
+    }
+
+    /// Wait for the next share verdict, skipping handshake events.
+    async fn next_verdict(event_rx: &mut mpsc::Receiver<ClientEvent>) -> ClientEvent {
+        loop {
+            match event_rx.recv().await.expect("client stopped") {
+                event @ (ClientEvent::ShareAccepted { .. } | ClientEvent::ShareRejected { .. }) => {
+                    return event;
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    /// Run a client through configure and a subscribe answered with
+    /// `result`, returning its task and event receiver.
+    async fn client_subscribed_with(
+        result: serde_json::Value,
+    ) -> (
+        tokio::task::JoinHandle<StratumResult<()>>,
+        mpsc::Receiver<ClientEvent>,
+        super::super::connection::MockTransportHandle,
+    ) {
+        use super::super::connection::MockTransport;
+        use serde_json::json;
+
+        let (event_tx, event_rx) = mpsc::channel(64);
+        let (_command_tx, command_rx) = mpsc::channel(64);
+        let config = PoolConfig {
+            url: "test:3333".to_string(),
+            username: "test".to_string(),
+            ..Default::default()
+        };
+        let client = StratumV1Client::with_commands(
+            config,
+            event_tx,
+            command_rx,
+            CancellationToken::new(),
+            None,
+        );
+        let (transport, mut handle) = MockTransport::pair();
+        let run = tokio::spawn(client.run_with_transport(transport));
+
+        for (method, result) in [
+            ("mining.configure", json!({"version-rolling": false})),
+            ("mining.subscribe", result),
+        ] {
+            let msg = handle.recv().await;
+            assert_eq!(msg.method(), Some(method));
+            handle.send(JsonRpcMessage::Response {
+                id: msg.id().unwrap(),
+                result: Some(result),
+                error: None,
+            });
+        }
+        (run, event_rx, handle)
+    }
+
+    #[tokio::test]
+    async fn subscribe_identifies_the_miner_by_its_user_agent() {
+        use super::super::connection::MockTransport;
+        use serde_json::json;
+
+        assert_eq!(
+            PoolConfig::default().user_agent,
+            format!("mujina-miner/{}", env!("CARGO_PKG_VERSION"))
+        );
+        for user_agent in [PoolConfig::DEFAULT_USER_AGENT, "rack-3/1.0"] {
+            let (event_tx, _event_rx) = mpsc::channel(64);
+            let config = PoolConfig {
+                url: "test:3333".to_string(),
+                username: "test".to_string(),
+                user_agent: user_agent.to_string(),
+                ..Default::default()
+            };
+            let client = StratumV1Client::new(config, event_tx, CancellationToken::new());
+            let (transport, mut handle) = MockTransport::pair();
+            tokio::spawn(client.run_with_transport(transport));
+
+            let configure = handle.recv().await;
+            handle.send(JsonRpcMessage::Response {
+                id: configure.id().unwrap(),
+                result: Some(json!({"version-rolling": false})),
+                error: None,
+            });
+            let JsonRpcMessage::Request { method, params, .. } = handle.recv().await else {
+                panic!("expected mining.subscribe");
+            };
+            assert_eq!(method, "mining.subscribe");
+            assert_eq!(params, json!([user_agent]));
+        }
+    }
+
+    #[tokio::test]
+    async fn subscribe_reports_the_negotiated_extranonce_sizes() {
+        let (_run, mut event_rx, _handle) =
+            client_subscribed_with(serde_json::json!([[], "08000002", 2])).await;
+
+        loop {
+            match event_rx.recv().await.expect("client stopped") {
+                ClientEvent::Subscribed {
+                    extranonce1,
+                    extranonce2_size,
+                } => {
+                    assert_eq!(extranonce1, [0x08, 0x00, 0x00, 0x02]);
+                    assert_eq!(extranonce2_size, 2);
+                    break;
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn subscribe_rejects_implausible_extranonce_sizes() {
+        use serde_json::json;
+
+        for result in [
+            json!([[], "aabb", 255]),
+            json!([[], "aabb", 0]),
+            json!([[], "aabb", 9]),
+            json!([[], "xyz", 4]),
+            json!([[], "aa".repeat(MAX_EXTRANONCE1_SIZE + 1), 4]),
+        ] {
+            let (run, mut event_rx, _handle) = client_subscribed_with(result.clone()).await;
+            let outcome = run.await.unwrap();
+            assert!(
+                matches!(outcome, Err(StratumError::InvalidMessage(_))),
+                "{result}: {outcome:?}"
+            );
+            while let Ok(event) = event_rx.try_recv() {
+                assert!(
+                    !matches!(event, ClientEvent::Subscribed { .. }),
+                    "{result}: subscribed anyway"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn submit_ahead_sends_shares_before_earlier_ones_are_answered() {
+        use serde_json::json;
+
+        let (command_tx, mut event_rx, mut handle, shutdown) = client_in_main_loop(4).await;
+        for nonce in [1, 2, 3] {
+            command_tx.send(submit_command(nonce)).await.unwrap();
+        }
+
+        // All three reach the pool with none answered yet.
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let msg = handle.recv().await;
+            assert_eq!(msg.method(), Some("mining.submit"));
+            ids.push(msg.id().unwrap());
+        }
+
+        // Answers out of order still land on the right shares.
+        handle.send(JsonRpcMessage::Response {
+            id: ids[1],
+            result: None,
+            error: Some(json!([23, "Low difficulty share", null])),
+        });
+        for &id in [ids[2], ids[0]].iter() {
+            handle.send(JsonRpcMessage::Response {
+                id,
+                result: Some(json!(true)),
+                error: None,
+            });
+        }
+        let mut accepted = Vec::new();
+        for _ in 0..3 {
+            match next_verdict(&mut event_rx).await {
+                ClientEvent::ShareAccepted { nonce, .. } => accepted.push(nonce),
+                ClientEvent::ShareRejected { nonce, reason, .. } => {
+                    assert_eq!(nonce, 2);
+                    assert_eq!(reason, "Low difficulty share");
+                }
+                _ => unreachable!(),
+            }
+        }
+        assert_eq!(accepted, [3, 1]);
+        shutdown.cancel();
+    }
+
+    #[tokio::test]
+    async fn submit_ahead_waits_when_the_window_is_full() {
+        use serde_json::json;
+
+        let (command_tx, mut event_rx, mut handle, shutdown) = client_in_main_loop(2).await;
+        for nonce in [1, 2, 3] {
+            command_tx.send(submit_command(nonce)).await.unwrap();
+        }
+        let first = handle.recv().await.id().unwrap();
+        handle.recv().await;
+        tokio::task::yield_now().await;
+        assert!(handle.try_recv().is_none(), "third share sent early");
+
+        handle.send(JsonRpcMessage::Response {
+            id: first,
+            result: Some(json!(true)),
+            error: None,
+        });
+        assert!(matches!(
+            next_verdict(&mut event_rx).await,
+            ClientEvent::ShareAccepted { nonce: 1, .. }
+        ));
+        assert_eq!(handle.recv().await.method(), Some("mining.submit"));
+        shutdown.cancel();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unanswered_shares_sent_ahead_expire() {
+        let (command_tx, mut event_rx, mut handle, shutdown) = client_in_main_loop(1).await;
+        command_tx.send(submit_command(1)).await.unwrap();
+        handle.recv().await;
+
+        // The pool never answers; after the timeout the window opens again.
+        tokio::time::sleep(SUBMIT_TIMEOUT).await;
+        command_tx.send(submit_command(2)).await.unwrap();
+        assert_eq!(handle.recv().await.method(), Some("mining.submit"));
+        assert!(
+            timeout(Duration::from_millis(10), next_verdict(&mut event_rx))
+                .await
+                .is_err(),
+            "expired share reported"
+        );
+        shutdown.cancel();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unanswered_shares_are_sent_again_and_counted_once() {
+        use serde_json::json;
+
+        let accept = |id| JsonRpcMessage::Response {
+            id,
+            result: Some(json!(true)),
+            error: None,
+        };
+        let duplicate = |id| JsonRpcMessage::Response {
+            id,
+            result: None,
+            error: Some(json!([22, "Duplicate share", null])),
+        };
+        // Waiting on each answer and sending ahead, with the late answer
+        // to the first send arriving before the answer to the second and
+        // after it.
+        for submit_ahead in [0, 2] {
+            for original_first in [true, false] {
+                let (command_tx, mut event_rx, mut handle, shutdown) =
+                    client_with_config_in_main_loop(PoolConfig {
+                        url: "test:3333".to_string(),
+                        username: "test".to_string(),
+                        submit_ahead,
+                        submit_retries: 1,
+                        ..Default::default()
+                    })
+                    .await;
+                command_tx.send(submit_command(1)).await.unwrap();
+                let original = handle.recv().await;
+
+                // The pool drops the answer; the share goes out again.
+                tokio::time::sleep(SUBMIT_TIMEOUT).await;
+                let resent = handle.recv().await;
+                assert_eq!(submitted_nonce(&resent), 1);
+                let (original, resent) = (original.id().unwrap(), resent.id().unwrap());
+                assert_ne!(original, resent);
+
+                // The pool took the first send after all, so it calls the
+                // second a duplicate. Either way round the share counts
+                // once, as accepted.
+                if original_first {
+                    handle.send(accept(original));
+                    handle.send(duplicate(resent));
+                } else {
+                    handle.send(duplicate(resent));
+                    handle.send(accept(original));
+                }
+                assert!(matches!(
+                    next_verdict(&mut event_rx).await,
+                    ClientEvent::ShareAccepted { nonce: 1, .. }
+                ));
+                assert!(
+                    timeout(Duration::from_millis(10), next_verdict(&mut event_rx))
+                        .await
+                        .is_err(),
+                    "share counted twice (submit_ahead {submit_ahead})"
+                );
+                shutdown.cancel();
+            }
+        }
+
+        // Out of retries, the share is given up on.
+        let (command_tx, mut event_rx, mut handle, shutdown) =
+            client_with_config_in_main_loop(PoolConfig {
+                url: "test:3333".to_string(),
+                username: "test".to_string(),
+                submit_retries: 1,
+                ..Default::default()
+            })
+            .await;
+        command_tx.send(submit_command(1)).await.unwrap();
+        handle.recv().await;
+        tokio::time::sleep(SUBMIT_TIMEOUT).await;
+        handle.recv().await;
+        tokio::time::sleep(SUBMIT_TIMEOUT).await;
+        loop {
+            match event_rx.recv().await.expect("client stopped") {
+                ClientEvent::ShareUnanswered { nonce, .. } => {
+                    assert_eq!(nonce, 1);
+                    break;
+                }
+                _ => continue,
+            }
+        }
+        assert!(handle.try_recv().is_none(), "sent a third time");
+        shutdown.cancel();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shares_close_together_go_out_in_one_write_but_blocks_never_wait() {
+        let (command_tx, _event_rx, mut handle, shutdown) =
+            client_with_config_in_main_loop(PoolConfig {
+                url: "test:3333".to_string(),
+                username: "test".to_string(),
+                submit_ahead: 8,
+                submit_batch: Duration::from_millis(20),
+                ..Default::default()
+            })
+            .await;
+        let handshake_writes = handle.writes();
+
+        for nonce in [1, 2, 3] {
+            command_tx.send(submit_command(nonce)).await.unwrap();
+        }
+        tokio::task::yield_now().await;
+        assert!(
+            handle.try_recv().is_none(),
+            "share written before the window closed"
+        );
+
+        // The window closes and all three leave together.
+        for nonce in [1, 2, 3] {
+            assert_eq!(submitted_nonce(&handle.recv().await), nonce);
+        }
+        assert_eq!(handle.writes(), handshake_writes + 1);
+
+        // A block solution doesn't wait for the window: it goes at once,
+        // taking the share gathered before it along.
+        command_tx.send(submit_command(4)).await.unwrap();
+        let ClientCommand::SubmitShare { params, .. } = submit_command(5) else {
+            unreachable!()
+        };
+        command_tx
+            .send(ClientCommand::SubmitShare {
+                params,
+                solves_block: true,
+            })
+            .await
+            .unwrap();
+        let start = tokio::time::Instant::now();
+        assert_eq!(submitted_nonce(&handle.recv().await), 4);
+        assert_eq!(submitted_nonce(&handle.recv().await), 5);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert_eq!(handle.writes(), handshake_writes + 2);
+        shutdown.cancel();
+    }
+
+    /// The nonce of a `mining.submit` the client wrote.
+    fn submitted_nonce(msg: &JsonRpcMessage) -> u32 {
+        let JsonRpcMessage::Request { method, params, .. } = msg else {
+            panic!("not a request: {msg:?}");
+        };
+        assert_eq!(method, "mining.submit");
+        u32::from_str_radix(params[4].as_str().unwrap(), 16).unwrap()
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn suggest_difficulty_from_env() {
+        let cases = [
+            (None, SuggestDifficulty::Auto),
+            (Some("auto"), SuggestDifficulty::Auto),
+            (Some("OFF"), SuggestDifficulty::Off),
+            (Some("2048"), SuggestDifficulty::Fixed(2048.0)),
+            (Some("0.5"), SuggestDifficulty::Fixed(0.5)),
+            (Some("-1"), SuggestDifficulty::Auto),
+            (Some("lots"), SuggestDifficulty::Auto),
+        ];
+        for (value, expected) in cases {
+            // SAFETY: Test runs serially, no concurrent env access
+            unsafe {
+                match value {
+                    Some(v) => std::env::set_var("MUJINA_POOL_SUGGEST_DIFFICULTY", v),
+                    None => std::env::remove_var("MUJINA_POOL_SUGGEST_DIFFICULTY"),
+                }
+            }
+            assert_eq!(SuggestDifficulty::from_env(), expected, "value {value:?}");
+        }
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe { std::env::remove_var("MUJINA_POOL_SUGGEST_DIFFICULTY") };
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn pool_config_from_env() {
+        let vars = [
+            ("MUJINA_POOL_USER", "worker.1"),
+            ("MUJINA_POOL_MIN_DIFFICULTY", "-3"),
+            ("MUJINA_POOL_JOB_DEBOUNCE_MS", "250"),
+            ("MUJINA_POOL_MAX_JOBS_PER_SEC", "0"),
+            ("MUJINA_POOL_ACK_SLA_MS", "soon"),
+            ("MUJINA_LOG_SHARE_DIFFICULTY", "1.5M"),
+            ("MUJINA_POOL_SUBMIT_AHEAD", "4"),
+            ("MUJINA_POOL_MAX_JOB_AGE_SECS", "0"),
+            ("MUJINA_POOL_MAX_ATTEMPTS", "5"),
+            ("MUJINA_POOL_NTIME_ROLL_SECS", "9000"),
+            ("MUJINA_POOL_JOB_HISTORY", "0"),
+        ];
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            for (name, _) in vars {
+                std::env::remove_var(name);
+            }
+        }
+        let config = PoolConfig::from_env("stratum+tcp://pool:3333".into());
+        assert_eq!(config.url, "stratum+tcp://pool:3333");
+        assert_eq!(config.username, "mujina-testing");
+        assert_eq!(config.password, "x");
+        assert_eq!(config.job_debounce, PoolConfig::DEFAULT_JOB_DEBOUNCE);
+        assert_eq!(config.ack_sla, Some(PoolConfig::DEFAULT_ACK_SLA));
+        assert_eq!(config.ntime_roll, PoolConfig::MAX_NTIME_ROLL);
+        assert_eq!(config.max_failed_attempts, None);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            for (name, value) in vars {
+                std::env::set_var(name, value);
+            }
+        }
+        let config = PoolConfig::from_env("stratum+tcp://pool:3333".into());
+        assert_eq!(config.username, "worker.1");
+        assert_eq!(config.job_debounce, Duration::from_millis(250));
+        assert_eq!(config.submit_ahead, 4);
+        assert_eq!(config.max_failed_attempts, Some(5));
+        assert!(config.log_share_difficulty.is_some());
+        // Zero turns a limit off.
+        assert_eq!(config.max_jobs_per_sec, None);
+        assert_eq!(config.max_job_age, None);
+        // Invalid values fall back to the default; ntime roll is capped.
+        assert_eq!(config.min_difficulty, None);
+        assert_eq!(config.ack_sla, Some(PoolConfig::DEFAULT_ACK_SLA));
+        assert_eq!(config.job_history, PoolConfig::DEFAULT_JOB_HISTORY);
+        assert_eq!(config.ntime_roll, PoolConfig::MAX_NTIME_ROLL);
+
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            for (name, _) in vars {
+                std::env::remove_var(name);
+            }
+        }
+    }
 }
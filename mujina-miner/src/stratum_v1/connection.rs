@@ -4,15 +4,28 @@
 //! wrapper around tokio's TCP stream that handles buffered reading and writing
 //! of complete JSON-RPC messages. The [`Transport`] trait abstracts message
 //! I/O, allowing channel-based mocks for deterministic testing.
+//!
+//! Each pool connection gets [`SocketOptions`] once it is up. Nagle's
+//! algorithm is off by default, since it can hold a share back waiting to
+//! be coalesced with data that never comes, and TCP keepalive can be
+//! tuned to notice a dead path sooner than the OS would.
+
+use std::env;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
 
 use async_trait::async_trait;
+use socket2::{SockRef, TcpKeepalive};
 
 use super::error::{StratumError, StratumResult};
 use super::messages::JsonRpcMessage;
+#[cfg(feature = "socks5")]
+use super::socks5::Socks5Proxy;
 use crate::tracing::prelude::*;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
-use tokio::net::TcpStream;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpSocket, TcpStream};
 
 /// Message-level I/O for Stratum protocol.
 ///
@@ -27,6 +40,120 @@ pub trait Transport: Send {
 
     /// Write a JSON-RPC message.
     async fn write_message(&mut self, msg: &JsonRpcMessage) -> StratumResult<()>;
+
+    /// Write several messages as one write where the transport can.
+    async fn write_messages(&mut self, msgs: &[JsonRpcMessage]) -> StratumResult<()> {
+        for msg in msgs {
+            self.write_message(msg).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Longest line read from a pool unless configured otherwise. Far beyond
+/// any real `mining.notify`, even with a large coinbase.
+pub const DEFAULT_MAX_LINE: usize = 256 * 1024;
+
+/// Oversized lines in a row after which the connection is given up.
+const MAX_OVERSIZED_LINES: u32 = 3;
+
+/// TCP keepalive probing of an idle connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keepalive {
+    /// Quiet time before the first probe.
+    pub idle: Duration,
+    /// Time between unanswered probes.
+    pub interval: Duration,
+    /// Unanswered probes before the connection is dropped.
+    pub count: u32,
+}
+
+impl Keepalive {
+    /// Parse `idle,interval,count`, the first two in seconds.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.split(',').map(str::trim);
+        let mut secs = || {
+            parts
+                .next()?
+                .parse::<u64>()
+                .ok()
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs)
+        };
+        let (idle, interval) = (secs()?, secs()?);
+        let count = parts.next()?.parse::<u32>().ok().filter(|&n| n > 0)?;
+        parts.next().is_none().then_some(Self {
+            idle,
+            interval,
+            count,
+        })
+    }
+}
+
+/// Socket options set on each pool connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// Set `TCP_NODELAY`, sending each write at once.
+    pub nodelay: bool,
+    /// Keepalive probing, `None` to leave the OS default.
+    pub keepalive: Option<Keepalive>,
+}
+
+impl Default for SocketOptions {
+    fn default() -> Self {
+        Self {
+            nodelay: true,
+            keepalive: None,
+        }
+    }
+}
+
+impl SocketOptions {
+    /// Read `MUJINA_POOL_NAGLE`, set to any value to leave Nagle's
+    /// algorithm on, and `MUJINA_POOL_TCP_KEEPALIVE`, warning and
+    /// leaving keepalive alone when it can't be parsed.
+    pub fn from_env() -> Self {
+        let keepalive = env::var("MUJINA_POOL_TCP_KEEPALIVE").ok().and_then(|val| {
+            let keepalive = Keepalive::parse(&val);
+            if keepalive.is_none() {
+                warn!(value = %val, "Invalid MUJINA_POOL_TCP_KEEPALIVE, using the OS default");
+            }
+            keepalive
+        });
+        Self {
+            nodelay: env::var_os("MUJINA_POOL_NAGLE").is_none(),
+            keepalive,
+        }
+    }
+
+    /// Set the options on `socket`.
+    pub fn apply(&self, socket: &impl TcpTuning) -> io::Result<()> {
+        socket.set_nodelay(self.nodelay)?;
+        if let Some(keepalive) = &self.keepalive {
+            socket.set_keepalive(keepalive)?;
+        }
+        Ok(())
+    }
+}
+
+/// A socket [`SocketOptions`] can be set on.
+pub trait TcpTuning {
+    fn set_nodelay(&self, nodelay: bool) -> io::Result<()>;
+    fn set_keepalive(&self, keepalive: &Keepalive) -> io::Result<()>;
+}
+
+impl TcpTuning for TcpStream {
+    fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        TcpStream::set_nodelay(self, nodelay)
+    }
+
+    fn set_keepalive(&self, keepalive: &Keepalive) -> io::Result<()> {
+        let params = TcpKeepalive::new()
+            .with_time(keepalive.idle)
+            .with_interval(keepalive.interval)
+            .with_retries(keepalive.count);
+        SockRef::from(self).set_tcp_keepalive(&params)
+    }
 }
 
 /// Buffered TCP connection for Stratum protocol.
@@ -34,6 +161,11 @@ pub trait Transport: Send {
 /// Wraps a TCP stream with buffered readers/writers optimized for
 /// line-delimited JSON messages. Messages are automatically serialized
 /// and deserialized, with newlines added/stripped.
+///
+/// Lines longer than the connection's maximum are skipped as they arrive
+/// rather than buffered, so a broken or hostile pool can't make the miner
+/// hold an unbounded line in memory. A few in a row end the connection,
+/// so the client reconnects.
 pub struct Connection {
     /// Buffered reader for incoming messages
     reader: BufReader<OwnedReadHalf>,
@@ -41,8 +173,19 @@ pub struct Connection {
     /// Buffered writer for outgoing messages
     writer: BufWriter<OwnedWriteHalf>,
 
-    /// Line buffer for reading messages
-    line_buf: String,
+    /// The line read so far. Kept across calls, so a read cancelled
+    /// part-way through a line loses nothing.
+    line_buf: Vec<u8>,
+
+    /// Longest line accepted, in bytes.
+    max_line: usize,
+
+    /// Bytes of the current line skipped for being too long, `None` while
+    /// it is within bounds.
+    skipping: Option<usize>,
+
+    /// Oversized lines since the last good one.
+    oversized: u32,
 }
 
 impl Connection {
@@ -54,7 +197,58 @@ impl Connection {
         Self {
             reader: BufReader::new(read_half),
             writer: BufWriter::new(write_half),
-            line_buf: String::with_capacity(4096),
+            line_buf: Vec::with_capacity(4096),
+            max_line: DEFAULT_MAX_LINE,
+            skipping: None,
+            oversized: 0,
+        }
+    }
+
+    /// Skip lines longer than `bytes` instead of [`DEFAULT_MAX_LINE`].
+    pub fn with_max_line(mut self, bytes: usize) -> Self {
+        self.max_line = bytes;
+        self
+    }
+
+    /// Read up to the end of the next line within bounds into `line_buf`.
+    /// Returns `false` at EOF.
+    async fn read_line(&mut self) -> StratumResult<bool> {
+        loop {
+            let available = self.reader.fill_buf().await?;
+            if available.is_empty() {
+                return Ok(false);
+            }
+            let newline = available.iter().position(|&b| b == b'\n');
+            let chunk = &available[..newline.unwrap_or(available.len())];
+            match &mut self.skipping {
+                Some(skipped) => *skipped += chunk.len(),
+                None if self.line_buf.len() + chunk.len() > self.max_line => {
+                    self.skipping = Some(self.line_buf.len() + chunk.len());
+                    self.line_buf.clear();
+                }
+                None => self.line_buf.extend_from_slice(chunk),
+            }
+            let used = newline.map_or(available.len(), |at| at + 1);
+            self.reader.consume(used);
+            if newline.is_none() {
+                continue;
+            }
+
+            let Some(skipped) = self.skipping.take() else {
+                return Ok(true);
+            };
+            self.oversized += 1;
+            warn!(
+                bytes = skipped,
+                max_bytes = self.max_line,
+                "Oversized line from pool (dropped)"
+            );
+            if self.oversized >= MAX_OVERSIZED_LINES {
+                return Err(StratumError::OversizedLines {
+                    count: self.oversized,
+                    max: self.max_line,
+                });
+            }
         }
     }
 
@@ -62,56 +256,139 @@ impl Connection {
     ///
     /// Parses the URL, establishes TCP connection, and wraps it in a buffered
     /// connection. Supports both `stratum+tcp://` and plain `tcp://` schemes.
-    pub async fn connect(url: &str) -> StratumResult<Self> {
-        // Parse URL
-        let url = url
-            .strip_prefix("stratum+tcp://")
-            .or_else(|| url.strip_prefix("tcp://"))
-            .unwrap_or(url);
+    /// With `bind`, the socket is bound to that local IP address first, so
+    /// the connection leaves from it. `options` are set once it is up.
+    pub async fn connect(
+        url: &str,
+        bind: Option<&str>,
+        options: &SocketOptions,
+    ) -> StratumResult<Self> {
+        let url = strip_scheme(url);
 
-        debug!(url = %url, "Connecting to pool");
+        debug!(url = %url, bind = ?bind, "Connecting to pool");
 
-        // Connect
-        let stream = TcpStream::connect(url)
-            .await
-            .map_err(|e| StratumError::ConnectionFailed(e.to_string()))?;
+        let stream = Self::open(url, bind, options).await?;
+
+        debug!("Connected to pool");
+
+        Ok(Self::new(stream))
+    }
+
+    /// Connect to a Stratum pool through a SOCKS5 proxy.
+    ///
+    /// As [`Connection::connect`], but the TCP connection goes to the proxy,
+    /// which is asked to connect on to the pool.
+    #[cfg(feature = "socks5")]
+    pub async fn connect_via(
+        proxy: &Socks5Proxy,
+        url: &str,
+        bind: Option<&str>,
+        options: &SocketOptions,
+    ) -> StratumResult<Self> {
+        let url = strip_scheme(url);
+
+        debug!(url = %url, proxy = %proxy.addr, bind = ?bind, "Connecting to pool through proxy");
+
+        let mut stream = Self::open(&proxy.addr, bind, options).await?;
+        proxy.handshake(&mut stream, url).await?;
 
         debug!("Connected to pool");
 
         Ok(Self::new(stream))
     }
+
+    /// Open a TCP connection to `addr`, from `bind` when set, with
+    /// `options` set on it. Options that can't be set are warned about;
+    /// the connection works without them.
+    async fn open(
+        addr: &str,
+        bind: Option<&str>,
+        options: &SocketOptions,
+    ) -> StratumResult<TcpStream> {
+        let stream = match bind {
+            Some(bind) => Self::connect_from(addr, bind).await,
+            None => TcpStream::connect(addr)
+                .await
+                .map_err(|e| StratumError::ConnectionFailed(e.to_string())),
+        }?;
+        if let Err(e) = options.apply(&stream) {
+            warn!(error = %e, ?options, "Failed to set pool socket options");
+        }
+        Ok(stream)
+    }
+
+    /// Connect to `url` from the local address `bind`, trying the pool's
+    /// addresses of the same family in turn.
+    async fn connect_from(url: &str, bind: &str) -> StratumResult<TcpStream> {
+        let local: IpAddr = bind.parse().map_err(|_| {
+            StratumError::InvalidBindAddress(format!("{bind} is not an IP address"))
+        })?;
+        let remotes: Vec<SocketAddr> = tokio::net::lookup_host(url)
+            .await
+            .map_err(|e| StratumError::ConnectionFailed(e.to_string()))?
+            .filter(|addr| addr.is_ipv4() == local.is_ipv4())
+            .collect();
+        if remotes.is_empty() {
+            return Err(StratumError::ConnectionFailed(format!(
+                "{url} has no address reachable from {local}"
+            )));
+        }
+
+        let mut last_error = None;
+        for remote in remotes {
+            let socket = if local.is_ipv4() {
+                TcpSocket::new_v4()
+            } else {
+                TcpSocket::new_v6()
+            }?;
+            socket.bind(SocketAddr::new(local, 0)).map_err(|e| {
+                StratumError::ConnectionFailed(format!("can't bind to {local}: {e}"))
+            })?;
+            match socket.connect(remote).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(StratumError::ConnectionFailed(
+            last_error.map_or_else(String::new, |e| e.to_string()),
+        ))
+    }
 }
 
 #[async_trait]
 impl Transport for Connection {
     async fn read_message(&mut self) -> StratumResult<Option<JsonRpcMessage>> {
         loop {
-            self.line_buf.clear();
-
-            let n = self
-                .reader
-                .read_line(&mut self.line_buf)
-                .await
-                .map_err(StratumError::Io)?;
-
-            if n == 0 {
+            if !self.read_line().await? {
                 // EOF - connection closed
                 return Ok(None);
             }
+            self.oversized = 0;
 
-            let line = self.line_buf.trim();
-            if line.is_empty() {
+            let line = std::str::from_utf8(&self.line_buf)
+                .map(str::trim)
+                .map_err(|e| StratumError::InvalidMessage(format!("Line not UTF-8: {e}")));
+            let line = match line {
                 // Empty line, skip and read next
-                continue;
-            }
+                Ok("") => {
+                    self.line_buf.clear();
+                    continue;
+                }
+                Ok(line) => line,
+                Err(e) => {
+                    self.line_buf.clear();
+                    return Err(e);
+                }
+            };
 
             trace!(rx = %line, "Received message");
 
             let msg = serde_json::from_str(line).map_err(|e| {
                 StratumError::InvalidMessage(format!("Failed to parse JSON: {}, line: {}", e, line))
-            })?;
+            });
+            self.line_buf.clear();
 
-            return Ok(Some(msg));
+            return msg.map(Some);
         }
     }
 
@@ -125,6 +402,20 @@ impl Transport for Connection {
 
         Ok(())
     }
+
+    async fn write_messages(&mut self, msgs: &[JsonRpcMessage]) -> StratumResult<()> {
+        // One flush for the lot, so the batch leaves in as few segments
+        // as the buffer allows.
+        for msg in msgs {
+            let json = serde_json::to_string(msg)?;
+            trace!(tx = %json, "Sending message");
+            self.writer.write_all(json.as_bytes()).await?;
+            self.writer.write_all(b"\n").await?;
+        }
+        self.writer.flush().await?;
+
+        Ok(())
+    }
 }
 
 /// Forwarding impl so `Box<dyn Transport>` satisfies `impl Transport`.
@@ -141,6 +432,10 @@ impl Transport for Box<dyn Transport> {
     async fn write_message(&mut self, msg: &JsonRpcMessage) -> StratumResult<()> {
         (**self).write_message(msg).await
     }
+
+    async fn write_messages(&mut self, msgs: &[JsonRpcMessage]) -> StratumResult<()> {
+        (**self).write_messages(msgs).await
+    }
 }
 
 /// Factory for creating transport connections.
@@ -153,22 +448,76 @@ pub trait Connector: Send {
     async fn connect(&mut self) -> StratumResult<Box<dyn Transport>>;
 }
 
+/// `host:port` from a pool URL with or without its scheme.
+fn strip_scheme(url: &str) -> &str {
+    url.strip_prefix("stratum+tcp://")
+        .or_else(|| url.strip_prefix("tcp://"))
+        .unwrap_or(url)
+}
+
 /// Connects to a Stratum pool over TCP.
 pub struct TcpConnector {
     url: String,
+    bind: Option<String>,
+    max_line: usize,
+    options: SocketOptions,
+    #[cfg(feature = "socks5")]
+    proxy: Option<Socks5Proxy>,
 }
 
 impl TcpConnector {
     pub fn new(url: String) -> Self {
-        Self { url }
+        Self {
+            url,
+            bind: None,
+            max_line: DEFAULT_MAX_LINE,
+            options: SocketOptions::default(),
+            #[cfg(feature = "socks5")]
+            proxy: None,
+        }
+    }
+
+    /// Connect from the local IP address `bind` rather than whichever the
+    /// routing table picks. An address that isn't an IP address fails the
+    /// first connection attempt as fatal.
+    pub fn with_bind_address(mut self, bind: String) -> Self {
+        self.bind = Some(bind);
+        self
+    }
+
+    /// Skip lines from the pool longer than `bytes`; see [`Connection`].
+    pub fn with_max_line(mut self, bytes: usize) -> Self {
+        self.max_line = bytes;
+        self
+    }
+
+    /// Set `options` on each connection instead of the defaults.
+    pub fn with_socket_options(mut self, options: SocketOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Reach the pool through a SOCKS5 proxy. A bind address then applies
+    /// to the connection to the proxy.
+    #[cfg(feature = "socks5")]
+    pub fn with_proxy(mut self, proxy: Socks5Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
     }
 }
 
 #[async_trait]
 impl Connector for TcpConnector {
     async fn connect(&mut self) -> StratumResult<Box<dyn Transport>> {
-        let conn = Connection::connect(&self.url).await?;
-        Ok(Box::new(conn))
+        #[cfg(feature = "socks5")]
+        if let Some(proxy) = &self.proxy {
+            let conn =
+                Connection::connect_via(proxy, &self.url, self.bind.as_deref(), &self.options)
+                    .await?;
+            return Ok(Box::new(conn.with_max_line(self.max_line)));
+        }
+        let conn = Connection::connect(&self.url, self.bind.as_deref(), &self.options).await?;
+        Ok(Box::new(conn.with_max_line(self.max_line)))
     }
 }
 
@@ -182,6 +531,7 @@ impl Connector for TcpConnector {
 pub(crate) struct MockTransport {
     rx: tokio::sync::mpsc::UnboundedReceiver<JsonRpcMessage>,
     tx: tokio::sync::mpsc::UnboundedSender<JsonRpcMessage>,
+    writes: std::sync::Arc<std::sync::atomic::AtomicUsize>,
 }
 
 /// Test-side handle for a [`MockTransport`].
@@ -192,6 +542,7 @@ pub(crate) struct MockTransport {
 pub(crate) struct MockTransportHandle {
     tx: tokio::sync::mpsc::UnboundedSender<JsonRpcMessage>,
     rx: tokio::sync::mpsc::UnboundedReceiver<JsonRpcMessage>,
+    writes: std::sync::Arc<std::sync::atomic::AtomicUsize>,
 }
 
 #[cfg(test)]
@@ -201,13 +552,16 @@ impl MockTransport {
         let (client_tx, handle_rx) = tokio::sync::mpsc::unbounded_channel();
         let (handle_tx, client_rx) = tokio::sync::mpsc::unbounded_channel();
 
+        let writes = std::sync::Arc::default();
         let transport = MockTransport {
             rx: client_rx,
             tx: client_tx,
+            writes: std::sync::Arc::clone(&writes),
         };
         let handle = MockTransportHandle {
             tx: handle_tx,
             rx: handle_rx,
+            writes,
         };
         (transport, handle)
     }
@@ -224,10 +578,23 @@ impl Transport for MockTransport {
     }
 
     async fn write_message(&mut self, msg: &JsonRpcMessage) -> StratumResult<()> {
+        self.writes
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         self.tx
             .send(msg.clone())
             .map_err(|_| StratumError::Disconnected)
     }
+
+    async fn write_messages(&mut self, msgs: &[JsonRpcMessage]) -> StratumResult<()> {
+        self.writes
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        for msg in msgs {
+            self.tx
+                .send(msg.clone())
+                .map_err(|_| StratumError::Disconnected)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -241,6 +608,16 @@ impl MockTransportHandle {
     pub async fn recv(&mut self) -> JsonRpcMessage {
         self.rx.recv().await.expect("transport dropped")
     }
+
+    /// Writes the client has made, counting a batch as one.
+    pub fn writes(&self) -> usize {
+        self.writes.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Take a message the client already wrote, if any.
+    pub fn try_recv(&mut self) -> Option<JsonRpcMessage> {
+        self.rx.try_recv().ok()
+    }
 }
 
 /// Connector that pulls pre-built transports from a channel.
@@ -308,4 +685,131 @@ mod tests {
         assert_eq!(response.id(), Some(1));
         assert_eq!(response.method(), Some("test.method"));
     }
+
+    #[tokio::test]
+    async fn oversized_lines_are_skipped_without_being_buffered() {
+        const MAX: usize = 1024;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let pool = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let garbage = vec![b'x'; 64 * MAX];
+            let good = br#"{"id":1,"result":true,"error":null}"#;
+            for lines in [1, MAX_OVERSIZED_LINES] {
+                for _ in 0..lines {
+                    socket.write_all(&garbage).await.unwrap();
+                    socket.write_all(b"\n").await.unwrap();
+                }
+                socket.write_all(good).await.unwrap();
+                socket.write_all(b"\n").await.unwrap();
+            }
+        });
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut conn = Connection::new(stream).with_max_line(MAX);
+
+        // One oversized line is skipped and the next one parses.
+        let msg = conn.read_message().await.unwrap().unwrap();
+        assert_eq!(msg.id(), Some(1));
+        assert!(conn.line_buf.capacity() <= 4 * MAX);
+
+        // Several in a row end the connection.
+        let Err(e) = conn.read_message().await else {
+            panic!("oversized lines tolerated");
+        };
+        assert!(matches!(e, StratumError::OversizedLines { .. }));
+        assert!(!e.is_fatal());
+        assert!(conn.line_buf.capacity() <= 4 * MAX);
+        pool.await.unwrap();
+    }
+
+    /// Records the options set on it.
+    #[derive(Default)]
+    struct RecordingSocket {
+        nodelay: std::cell::Cell<Option<bool>>,
+        keepalive: std::cell::Cell<Option<Keepalive>>,
+    }
+
+    impl TcpTuning for RecordingSocket {
+        fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+            self.nodelay.set(Some(nodelay));
+            Ok(())
+        }
+
+        fn set_keepalive(&self, keepalive: &Keepalive) -> io::Result<()> {
+            self.keepalive.set(Some(*keepalive));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn socket_options_are_set_on_connections() {
+        let keepalive = Keepalive::parse("60, 10, 3").unwrap();
+        assert_eq!(
+            keepalive,
+            Keepalive {
+                idle: Duration::from_secs(60),
+                interval: Duration::from_secs(10),
+                count: 3,
+            }
+        );
+        for invalid in ["60,10", "60,10,3,1", "0,10,3", "60,10,0", "a,b,c"] {
+            assert_eq!(Keepalive::parse(invalid), None, "{invalid}");
+        }
+
+        // By default only Nagle's algorithm is turned off.
+        let socket = RecordingSocket::default();
+        SocketOptions::default().apply(&socket).unwrap();
+        assert_eq!(socket.nodelay.get(), Some(true));
+        assert_eq!(socket.keepalive.get(), None);
+        let options = SocketOptions {
+            nodelay: false,
+            keepalive: Some(keepalive),
+        };
+        let socket = RecordingSocket::default();
+        options.apply(&socket).unwrap();
+        assert_eq!(socket.nodelay.get(), Some(false));
+        assert_eq!(socket.keepalive.get(), Some(keepalive));
+
+        // And they reach the socket of a real connection.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let options = SocketOptions {
+            nodelay: true,
+            keepalive: Some(keepalive),
+        };
+        let stream = Connection::open(&addr.to_string(), None, &options)
+            .await
+            .unwrap();
+        let socket = SockRef::from(&stream);
+        assert!(socket.tcp_nodelay().unwrap());
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.tcp_keepalive_time().unwrap(), keepalive.idle);
+        assert_eq!(socket.tcp_keepalive_interval().unwrap(), keepalive.interval);
+        assert_eq!(socket.tcp_keepalive_retries().unwrap(), keepalive.count);
+    }
+
+    #[tokio::test]
+    async fn connections_leave_from_the_bind_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("stratum+tcp://{}", listener.local_addr().unwrap());
+
+        // Any 127/8 address is local on Linux, so binding to one other
+        // than the listener's shows the bind took effect.
+        let mut connector = TcpConnector::new(url.clone()).with_bind_address("127.0.0.2".into());
+        let (connected, accepted) = tokio::join!(connector.connect(), listener.accept());
+        connected.unwrap();
+        let (_, peer) = accepted.unwrap();
+        assert_eq!(peer.ip(), "127.0.0.2".parse::<IpAddr>().unwrap());
+
+        let mut connector = TcpConnector::new(url).with_bind_address("eth0".into());
+        let Err(e) = connector.connect().await else {
+            panic!("connected from an invalid bind address");
+        };
+        assert!(e.is_fatal());
+        assert_eq!(
+            e.to_string(),
+            "Invalid bind address: eth0 is not an IP address"
+        );
+    }
 }
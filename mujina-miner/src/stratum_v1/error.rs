@@ -45,6 +45,18 @@ pub enum StratumError {
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
 
+    /// Local address to connect from is not an IP address
+    #[error("Invalid bind address: {0}")]
+    InvalidBindAddress(String),
+
+    /// SOCKS5 proxy refused or failed the connection
+    #[error("Proxy failed: {0}")]
+    ProxyFailed(String),
+
+    /// Pool kept sending lines over the maximum length
+    #[error("Pool sent {count} lines in a row over {max} bytes")]
+    OversizedLines { count: u32, max: usize },
+
     /// Connection lost
     #[error("Connection lost")]
     Disconnected,
@@ -57,14 +69,17 @@ pub enum StratumError {
 impl StratumError {
     /// Whether this error is unrecoverable and should not be retried.
     ///
-    /// Authorization failures and invalid URLs are fatal---wrong
-    /// credentials and malformed addresses won't fix themselves.
+    /// Authorization failures and invalid URLs or bind addresses are
+    /// fatal---wrong credentials and malformed addresses won't fix
+    /// themselves.
     /// Everything else (network errors, timeouts, subscription
     /// failures from overloaded pools) may resolve on retry.
     pub fn is_fatal(&self) -> bool {
         matches!(
             self,
-            StratumError::AuthorizationFailed(_) | StratumError::InvalidUrl(_)
+            StratumError::AuthorizationFailed(_)
+                | StratumError::InvalidUrl(_)
+                | StratumError::InvalidBindAddress(_)
         )
     }
 }
@@ -9,6 +9,7 @@ use bitcoin::hashes::Hash;
 use bitcoin::{BlockHash, CompactTarget, TxMerkleNode};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use thiserror::Error;
 
 /// Events emitted by the Stratum client.
 ///
@@ -34,6 +35,9 @@ pub enum ClientEvent {
         extranonce2_size: usize,
     },
 
+    /// Worker authorized by pool (result of mining.authorize)
+    Authorized,
+
     /// New mining job received from pool
     NewJob(JobNotification),
 
@@ -58,10 +62,20 @@ pub enum ClientEvent {
     ShareRejected {
         /// Job ID that was rejected
         job_id: String,
+        /// Nonce that was rejected
+        nonce: u32,
         /// Rejection reason from pool
         reason: String,
     },
 
+    /// Pool never answered a submitted share in time
+    ShareUnanswered {
+        /// Job ID of the share
+        job_id: String,
+        /// Nonce of the share
+        nonce: u32,
+    },
+
     /// Disconnected from pool
     Disconnected,
 
@@ -74,8 +88,12 @@ pub enum ClientEvent {
 /// External code (typically job source) sends commands to request actions.
 #[derive(Debug, Clone)]
 pub enum ClientCommand {
-    /// Submit a share to the pool
-    SubmitShare(SubmitParams),
+    /// Submit a share to the pool. A share that solves a block is never
+    /// held back for batching.
+    SubmitShare {
+        params: SubmitParams,
+        solves_block: bool,
+    },
 
     /// Suggest a new difficulty to the pool. Fractional below 1 (for very
     /// slow workers like the CPU miner); sent as an integer at or above 1.
@@ -117,57 +135,75 @@ pub struct JobNotification {
     pub clean_jobs: bool,
 }
 
+/// Why a `mining.notify` couldn't be parsed.
+///
+/// Fields are named as in the protocol, merkle branches by index such as
+/// `merkle_branches[2]`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NotifyError {
+    /// Fewer params than the nine the protocol defines
+    #[error("mining.notify has {0} params, expected 9")]
+    TooFewParams(usize),
+
+    /// A param of the wrong JSON type
+    #[error("{field} is not {expected}")]
+    WrongType {
+        field: String,
+        expected: &'static str,
+    },
+
+    /// A string param that isn't hex
+    #[error("{field} is not hex")]
+    NotHex { field: String },
+
+    /// A fixed-size param of the wrong length, in hex digits
+    #[error("{field} is {len} hex digits, expected {expected}")]
+    WrongLength {
+        field: String,
+        len: usize,
+        expected: usize,
+    },
+}
+
 impl JobNotification {
     /// Parse from Stratum JSON array parameters.
     ///
     /// Converts hex strings from the pool protocol into typed Bitcoin structures.
     /// Uses manual parsing for better error context than serde tuple structs.
-    pub fn from_stratum_params(params: &[Value]) -> Result<Self, String> {
+    /// Hashes must be 64 hex digits, and version, nbits and ntime 8, as
+    /// the protocol sends them; anything else is a [`NotifyError`].
+    pub fn from_stratum_params(params: &[Value]) -> Result<Self, NotifyError> {
         if params.len() < 9 {
-            return Err("mining.notify params too short".to_string());
+            return Err(NotifyError::TooFewParams(params.len()));
         }
 
-        // Parse job_id
-        let job_id = params[0].as_str().ok_or("job_id not a string")?.to_string();
-
-        // Parse prev_hash (hex string, little-endian in Stratum)
-        let prev_hash_str = params[1].as_str().ok_or("prev_hash not a string")?;
-        let prev_hash = parse_block_hash(prev_hash_str)?;
+        let job_id = str_param(&params[0], "job_id")?.to_string();
 
-        // Parse coinbase1 and coinbase2
-        let coinbase1_str = params[2].as_str().ok_or("coinbase1 not a string")?;
-        let coinbase1 = hex::decode(coinbase1_str).map_err(|e| format!("coinbase1 hex: {}", e))?;
+        // prev_hash (hex string, word-swapped in Stratum)
+        let prev_hash = parse_block_hash(str_param(&params[1], "prev_hash")?)?;
 
-        let coinbase2_str = params[3].as_str().ok_or("coinbase2 not a string")?;
-        let coinbase2 = hex::decode(coinbase2_str).map_err(|e| format!("coinbase2 hex: {}", e))?;
+        let coinbase1 = decode_hex(str_param(&params[2], "coinbase1")?, "coinbase1")?;
+        let coinbase2 = decode_hex(str_param(&params[3], "coinbase2")?, "coinbase2")?;
 
-        // Parse merkle branches
-        let branches_json = params[4].as_array().ok_or("merkle_branches not an array")?;
-        let mut merkle_branches = Vec::new();
-        for branch in branches_json {
-            let branch_str = branch.as_str().ok_or("merkle branch not a string")?;
-            let node = parse_merkle_node(branch_str)?;
-            merkle_branches.push(node);
+        let branches_json = params[4].as_array().ok_or_else(|| NotifyError::WrongType {
+            field: "merkle_branches".into(),
+            expected: "an array",
+        })?;
+        let mut merkle_branches = Vec::with_capacity(branches_json.len());
+        for (i, branch) in branches_json.iter().enumerate() {
+            let field = format!("merkle_branches[{i}]");
+            merkle_branches.push(parse_merkle_node(str_param(branch, &field)?, &field)?);
         }
 
-        // Parse version (hex string, big-endian)
-        let version_str = params[5].as_str().ok_or("version not a string")?;
-        let version_u32 =
-            u32::from_str_radix(version_str, 16).map_err(|e| format!("version hex: {}", e))?;
-        let version = Version::from_consensus(version_u32 as i32);
+        // version, nbits and ntime (hex strings, big-endian)
+        let version = Version::from_consensus(parse_u32(&params[5], "version")? as i32);
+        let nbits = CompactTarget::from_consensus(parse_u32(&params[6], "nbits")?);
+        let ntime = parse_u32(&params[7], "ntime")?;
 
-        // Parse nbits (hex string)
-        let nbits_str = params[6].as_str().ok_or("nbits not a string")?;
-        let nbits_u32 =
-            u32::from_str_radix(nbits_str, 16).map_err(|e| format!("nbits hex: {}", e))?;
-        let nbits = CompactTarget::from_consensus(nbits_u32);
-
-        // Parse ntime (hex string)
-        let ntime_str = params[7].as_str().ok_or("ntime not a string")?;
-        let ntime = u32::from_str_radix(ntime_str, 16).map_err(|e| format!("ntime hex: {}", e))?;
-
-        // Parse clean_jobs
-        let clean_jobs = params[8].as_bool().ok_or("clean_jobs not a bool")?;
+        let clean_jobs = params[8].as_bool().ok_or_else(|| NotifyError::WrongType {
+            field: "clean_jobs".into(),
+            expected: "a bool",
+        })?;
 
         Ok(Self {
             job_id,
@@ -183,6 +219,40 @@ impl JobNotification {
     }
 }
 
+fn str_param<'a>(value: &'a Value, field: &str) -> Result<&'a str, NotifyError> {
+    value.as_str().ok_or_else(|| NotifyError::WrongType {
+        field: field.into(),
+        expected: "a string",
+    })
+}
+
+fn decode_hex(hex: &str, field: &str) -> Result<Vec<u8>, NotifyError> {
+    hex::decode(hex).map_err(|_| NotifyError::NotHex {
+        field: field.into(),
+    })
+}
+
+/// Decode exactly `N` bytes of hex.
+fn decode_fixed<const N: usize>(hex: &str, field: &str) -> Result<[u8; N], NotifyError> {
+    if hex.len() != N * 2 {
+        return Err(NotifyError::WrongLength {
+            field: field.into(),
+            len: hex.len(),
+            expected: N * 2,
+        });
+    }
+    let mut bytes = [0u8; N];
+    hex::decode_to_slice(hex, &mut bytes).map_err(|_| NotifyError::NotHex {
+        field: field.into(),
+    })?;
+    Ok(bytes)
+}
+
+/// A big-endian 32-bit field, sent as 8 hex digits.
+fn parse_u32(value: &Value, field: &str) -> Result<u32, NotifyError> {
+    decode_fixed::<4>(str_param(value, field)?, field).map(u32::from_be_bytes)
+}
+
 /// Parse a block hash from Stratum hex string.
 ///
 /// # Stratum v1's "Goofy" Block Hash Encoding
@@ -237,27 +307,20 @@ impl JobNotification {
 ///
 /// - Real capture in `test_data::esp_miner_job::notify::PREV_BLOCKHASH_STRING`
 /// - Discussion: https://github.com/slushpool/stratumprotocol/issues/9
-fn parse_block_hash(hex: &str) -> Result<BlockHash, String> {
-    let mut bytes = hex::decode(hex).map_err(|e| format!("block hash hex: {}", e))?;
-    if bytes.len() != 32 {
-        return Err(format!("block hash wrong length: {}", bytes.len()));
-    }
+fn parse_block_hash(hex: &str) -> Result<BlockHash, NotifyError> {
+    let mut bytes = decode_fixed::<32>(hex, "prev_hash")?;
 
     // Stratum's "word-swap" encoding: reverse bytes within each 4-byte word
     for chunk in bytes.chunks_mut(4) {
         chunk.reverse();
     }
 
-    BlockHash::from_slice(&bytes).map_err(|e| format!("block hash parse: {}", e))
+    Ok(BlockHash::from_byte_array(bytes))
 }
 
 /// Parse a merkle node from Stratum hex string.
-fn parse_merkle_node(hex: &str) -> Result<TxMerkleNode, String> {
-    let bytes = hex::decode(hex).map_err(|e| format!("merkle node hex: {}", e))?;
-    if bytes.len() != 32 {
-        return Err(format!("merkle node wrong length: {}", bytes.len()));
-    }
-    TxMerkleNode::from_slice(&bytes).map_err(|e| format!("merkle node parse: {}", e))
+fn parse_merkle_node(hex: &str, field: &str) -> Result<TxMerkleNode, NotifyError> {
+    decode_fixed::<32>(hex, field).map(TxMerkleNode::from_byte_array)
 }
 
 /// Parameters for submitting a share to the pool.
@@ -517,12 +580,24 @@ mod tests {
     fn test_parse_invalid_block_hash() {
         // Too short
         let result = parse_block_hash("deadbeef");
-        assert!(result.is_err());
+        assert_eq!(
+            result,
+            Err(NotifyError::WrongLength {
+                field: "prev_hash".into(),
+                len: 8,
+                expected: 64
+            })
+        );
 
         // Invalid hex
         let result =
             parse_block_hash("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz");
-        assert!(result.is_err());
+        assert_eq!(
+            result,
+            Err(NotifyError::NotHex {
+                field: "prev_hash".into()
+            })
+        );
     }
 
     #[test]
@@ -591,6 +666,85 @@ mod tests {
         assert!(!job.clean_jobs);
     }
 
+    #[test]
+    fn test_job_notification_rejects_malformed_params() {
+        let valid = json!([
+            "job1",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "aa",
+            "bb",
+            ["1111111111111111111111111111111111111111111111111111111111111111"],
+            "20000000",
+            "1d00ffff",
+            "5a5a5a5a",
+            false
+        ]);
+        let params = valid.as_array().unwrap();
+        assert!(JobNotification::from_stratum_params(params).is_ok());
+
+        let malformed = |index: usize, value: Value| {
+            let mut params = params.clone();
+            params[index] = value;
+            JobNotification::from_stratum_params(&params).unwrap_err()
+        };
+        let wrong_length = |field: &str, len, expected| NotifyError::WrongLength {
+            field: field.into(),
+            len,
+            expected,
+        };
+
+        assert_eq!(
+            JobNotification::from_stratum_params(&params[..8]).unwrap_err(),
+            NotifyError::TooFewParams(8)
+        );
+        assert_eq!(
+            malformed(0, json!(7)),
+            NotifyError::WrongType {
+                field: "job_id".into(),
+                expected: "a string"
+            }
+        );
+        assert_eq!(
+            malformed(1, json!("00000000")),
+            wrong_length("prev_hash", 8, 64)
+        );
+        assert_eq!(
+            malformed(2, json!("0g")),
+            NotifyError::NotHex {
+                field: "coinbase1".into()
+            }
+        );
+        assert_eq!(
+            malformed(4, json!(["aa", "11"])),
+            wrong_length("merkle_branches[0]", 2, 64)
+        );
+        assert_eq!(
+            malformed(5, json!("2000000")),
+            wrong_length("version", 7, 8)
+        );
+        assert_eq!(
+            malformed(6, json!("1d00ffff00")),
+            wrong_length("nbits", 10, 8)
+        );
+        assert_eq!(
+            malformed(7, json!("5a5a5a5z")),
+            NotifyError::NotHex {
+                field: "ntime".into()
+            }
+        );
+        assert_eq!(
+            malformed(8, json!("true")),
+            NotifyError::WrongType {
+                field: "clean_jobs".into(),
+                expected: "a bool"
+            }
+        );
+        assert_eq!(
+            malformed(5, json!("2000000")).to_string(),
+            "version is 7 hex digits, expected 8"
+        );
+    }
+
     /// Rosetta stone test: JobNotification parser produces correct Bitcoin types.
     ///
     /// Parses the raw JSON through our actual JobNotification parser and validates
@@ -42,16 +42,26 @@
 //! }
 //! ```
 
+mod ack_latency;
 mod client;
 mod connection;
 mod error;
 mod messages;
+#[cfg(feature = "socks5")]
+mod socks5;
+#[cfg(test)]
+pub(crate) mod test_pool;
 
-pub use client::{PoolConfig, StratumV1Client};
-pub use connection::{Connector, TcpConnector, Transport};
+pub(crate) use client::env_setting;
+pub use client::{PoolConfig, StratumV1Client, SuggestDifficulty};
+pub use connection::{
+    Connector, DEFAULT_MAX_LINE, Keepalive, SocketOptions, TcpConnector, TcpTuning, Transport,
+};
 #[cfg(test)]
 pub(crate) use connection::{MockConnector, MockTransport, MockTransportHandle};
 pub use error::{StratumError, StratumResult};
 #[cfg(test)]
 pub(crate) use messages::JsonRpcMessage;
-pub use messages::{ClientCommand, ClientEvent, JobNotification, SubmitParams};
+pub use messages::{ClientCommand, ClientEvent, JobNotification, NotifyError, SubmitParams};
+#[cfg(feature = "socks5")]
+pub use socks5::Socks5Proxy;
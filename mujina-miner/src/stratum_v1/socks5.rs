@@ -0,0 +1,302 @@
+//! Reaching the pool through a SOCKS5 proxy.
+//!
+//! For networks that only let traffic out through a proxy, and for Tor.
+//! The pool's host name is handed to the proxy unresolved, so a Tor proxy
+//! resolves it and `.onion` pools work. Username and password
+//! authentication (RFC 1929) is offered when credentials are configured.
+//! Only the CONNECT command of RFC 1928 is used.
+
+use std::net::IpAddr;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::error::{StratumError, StratumResult};
+
+const VERSION: u8 = 5;
+const NO_AUTH: u8 = 0x00;
+const USER_PASS: u8 = 0x02;
+const NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+
+/// A SOCKS5 proxy to connect through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Socks5Proxy {
+    /// Proxy address as `host:port`.
+    pub addr: String,
+    /// Username and password, if the proxy wants them.
+    pub auth: Option<(String, String)>,
+}
+
+impl Socks5Proxy {
+    /// Parse `socks5://[user:pass@]host:port`. The `socks5h://` spelling
+    /// is taken as the same thing, since names are always resolved by the
+    /// proxy.
+    pub fn parse(url: &str) -> StratumResult<Self> {
+        let rest = url
+            .strip_prefix("socks5://")
+            .or_else(|| url.strip_prefix("socks5h://"))
+            .ok_or_else(|| StratumError::InvalidUrl(format!("{url} is not a socks5:// URL")))?;
+        let (auth, addr) = match rest.rsplit_once('@') {
+            Some((userinfo, addr)) => {
+                let (user, pass) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+                (Some((user.to_string(), pass.to_string())), addr)
+            }
+            None => (None, rest),
+        };
+        if let Some((user, pass)) = &auth
+            && (user.is_empty() || user.len() > 255 || pass.len() > 255)
+        {
+            return Err(StratumError::InvalidUrl(format!(
+                "{url}: username must be 1-255 bytes and password at most 255"
+            )));
+        }
+        split_host_port(addr)?;
+        Ok(Self {
+            addr: addr.to_string(),
+            auth,
+        })
+    }
+
+    /// Over `stream`, already connected to the proxy, ask it to connect to
+    /// `target` (`host:port`). On success the stream carries the pool
+    /// connection.
+    pub async fn handshake<S>(&self, stream: &mut S, target: &str) -> StratumResult<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (host, port) = split_host_port(target)?;
+
+        let methods: &[u8] = match self.auth {
+            Some(_) => &[NO_AUTH, USER_PASS],
+            None => &[NO_AUTH],
+        };
+        let mut greeting = vec![VERSION, methods.len() as u8];
+        greeting.extend_from_slice(methods);
+        stream.write_all(&greeting).await?;
+
+        let mut choice = [0u8; 2];
+        stream.read_exact(&mut choice).await?;
+        if choice[0] != VERSION {
+            return Err(proxy_error(format!(
+                "not a SOCKS5 proxy (version {})",
+                choice[0]
+            )));
+        }
+        match choice[1] {
+            NO_AUTH => {}
+            USER_PASS => {
+                let Some((user, pass)) = &self.auth else {
+                    return Err(proxy_error("proxy asked for credentials we weren't given"));
+                };
+                let mut request = vec![0x01, user.len() as u8];
+                request.extend_from_slice(user.as_bytes());
+                request.push(pass.len() as u8);
+                request.extend_from_slice(pass.as_bytes());
+                stream.write_all(&request).await?;
+                let mut status = [0u8; 2];
+                stream.read_exact(&mut status).await?;
+                if status[1] != 0 {
+                    return Err(proxy_error("proxy refused the username and password"));
+                }
+            }
+            NO_ACCEPTABLE_METHOD => {
+                return Err(proxy_error(
+                    "proxy accepts none of our authentication methods",
+                ));
+            }
+            other => return Err(proxy_error(format!("proxy chose unknown method {other}"))),
+        }
+
+        let mut request = vec![VERSION, CONNECT, 0x00];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(ATYP_IPV4);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(ATYP_IPV6);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                request.push(ATYP_DOMAIN);
+                request.push(host.len() as u8);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0 {
+            return Err(proxy_error(format!(
+                "proxy couldn't reach {target}: {}",
+                reply_meaning(reply[1])
+            )));
+        }
+        // The address the proxy connected from, which we have no use for.
+        let bound_len = match reply[3] {
+            ATYP_IPV4 => 4,
+            ATYP_IPV6 => 16,
+            ATYP_DOMAIN => stream.read_u8().await? as usize,
+            other => return Err(proxy_error(format!("unknown address type {other}"))),
+        };
+        let mut bound = vec![0u8; bound_len + 2];
+        stream.read_exact(&mut bound).await?;
+        Ok(())
+    }
+}
+
+/// Split `host:port`, allowing a bracketed IPv6 host.
+fn split_host_port(addr: &str) -> StratumResult<(&str, u16)> {
+    let invalid = || StratumError::InvalidUrl(format!("{addr} is not host:port"));
+    let (host, port) = addr.rsplit_once(':').ok_or_else(invalid)?;
+    let host = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    let port = port.parse().map_err(|_| invalid())?;
+    if host.is_empty() || host.len() > 255 {
+        return Err(invalid());
+    }
+    Ok((host, port))
+}
+
+fn proxy_error(reason: impl Into<String>) -> StratumError {
+    StratumError::ProxyFailed(reason.into())
+}
+
+/// The RFC 1928 meaning of a CONNECT reply code.
+fn reply_meaning(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc;
+    use tokio_util::sync::CancellationToken;
+
+    use super::super::connection::{Connection, Connector, TcpConnector, Transport};
+    use super::super::messages::JsonRpcMessage;
+    use super::super::{ClientEvent, PoolConfig, StratumV1Client};
+    use super::*;
+
+    /// Play a SOCKS5 proxy wanting `alice:secret`, then the pool itself.
+    async fn proxy_then_pool(mut socket: TcpStream) {
+        let mut greeting = [0u8; 4];
+        socket.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(greeting, [VERSION, 2, NO_AUTH, USER_PASS]);
+        socket.write_all(&[VERSION, USER_PASS]).await.unwrap();
+
+        let mut auth = [0u8; 14];
+        socket.read_exact(&mut auth).await.unwrap();
+        assert_eq!(&auth, b"\x01\x05alice\x06secret");
+        socket.write_all(&[0x01, 0x00]).await.unwrap();
+
+        let mut connect = [0u8; 5];
+        socket.read_exact(&mut connect).await.unwrap();
+        assert_eq!(connect, [VERSION, CONNECT, 0, ATYP_DOMAIN, 12]);
+        let mut target = [0u8; 14];
+        socket.read_exact(&mut target).await.unwrap();
+        assert_eq!(&target[..12], b"pool.example");
+        assert_eq!(u16::from_be_bytes([target[12], target[13]]), 3333);
+        socket
+            .write_all(&[VERSION, 0, 0, ATYP_IPV4, 10, 0, 0, 1, 0x0d, 0x05])
+            .await
+            .unwrap();
+
+        let mut pool = Connection::new(socket);
+        for (method, result) in [
+            ("mining.configure", json!({"version-rolling": false})),
+            ("mining.subscribe", json!([[], "aabb", 4])),
+            ("mining.authorize", json!(true)),
+        ] {
+            let msg = pool.read_message().await.unwrap().unwrap();
+            assert_eq!(msg.method(), Some(method));
+            pool.write_message(&JsonRpcMessage::Response {
+                id: msg.id().unwrap(),
+                result: Some(result),
+                error: None,
+            })
+            .await
+            .unwrap();
+        }
+        // Hold the connection open until the client hangs up.
+        while let Ok(Some(_)) = pool.read_message().await {}
+    }
+
+    #[tokio::test]
+    async fn client_reaches_the_pool_through_the_proxy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = Socks5Proxy::parse(&format!(
+            "socks5://alice:secret@{}",
+            listener.local_addr().unwrap()
+        ))
+        .unwrap();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            proxy_then_pool(socket).await;
+        });
+
+        let url = "stratum+tcp://pool.example:3333".to_string();
+        let transport = TcpConnector::new(url.clone())
+            .with_proxy(proxy)
+            .connect()
+            .await
+            .unwrap();
+        let (event_tx, mut event_rx) = mpsc::channel(16);
+        let shutdown = CancellationToken::new();
+        let config = PoolConfig {
+            url,
+            username: "worker".into(),
+            ..Default::default()
+        };
+        let client = StratumV1Client::new(config, event_tx, shutdown.clone());
+        tokio::spawn(client.run_with_transport(transport));
+
+        loop {
+            match event_rx.recv().await.expect("client stopped") {
+                ClientEvent::Authorized => break,
+                _ => continue,
+            }
+        }
+        shutdown.cancel();
+    }
+
+    #[test]
+    fn proxy_urls() {
+        assert_eq!(
+            Socks5Proxy::parse("socks5h://127.0.0.1:9050").unwrap(),
+            Socks5Proxy {
+                addr: "127.0.0.1:9050".into(),
+                auth: None
+            }
+        );
+        assert_eq!(
+            Socks5Proxy::parse("socks5://u:p@[::1]:1080").unwrap().auth,
+            Some(("u".into(), "p".into()))
+        );
+        for bad in [
+            "http://proxy:8080",
+            "socks5://proxy",
+            "socks5://:pw@proxy:1080",
+        ] {
+            assert!(Socks5Proxy::parse(bad).unwrap_err().is_fatal(), "{bad}");
+        }
+    }
+}
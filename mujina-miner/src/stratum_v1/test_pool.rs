@@ -0,0 +1,530 @@
+//! A scripted Stratum v1 pool for tests.
+//!
+//! The client tests drive the protocol message by message over a
+//! [`MockTransport`](super::MockTransport), which is right for pinning down
+//! the client's behavior but never lets the whole miner run against a pool.
+//! [`TestPool`] is a real pool on a loopback port: it answers the handshake,
+//! sends whatever difficulty and jobs the test pushes, and judges each
+//! submit the way a pool would, rebuilding the header from the job and
+//! checking its hash against the difficulty. A test can also have it accept
+//! or reject every share regardless, and reads back what was submitted.
+//!
+//! It serves one connection at a time, the way a miner connects, and
+//! replays the current difficulty and job to each client as it authorizes.
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use bitcoin::block::{Header as BlockHeader, Version};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinHandle;
+
+use super::messages::{JobNotification, JsonRpcMessage, SubmitParams};
+use crate::job_source::{Extranonce2, Extranonce2Range, MerkleRootTemplate, header};
+use crate::tracing::prelude::*;
+use crate::types::Difficulty;
+
+/// How the pool sets up each client.
+#[derive(Debug, Clone)]
+pub(crate) struct TestPoolConfig {
+    pub extranonce1: Vec<u8>,
+    pub extranonce2_size: usize,
+    /// Version bits the pool lets clients roll, `None` to decline.
+    pub version_mask: Option<u32>,
+    /// Whether `mining.authorize` succeeds.
+    pub authorize: bool,
+}
+
+impl Default for TestPoolConfig {
+    fn default() -> Self {
+        Self {
+            extranonce1: vec![0x41, 0x28, 0x06, 0x4f],
+            extranonce2_size: 4,
+            version_mask: Some(0x1fffe000),
+            authorize: true,
+        }
+    }
+}
+
+/// What the pool answers a submit with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Verdict {
+    /// Accept shares that meet the difficulty, as a pool would.
+    #[default]
+    Check,
+    /// Accept every share of a known job.
+    Accept,
+    /// Reject every share.
+    Reject,
+}
+
+/// A share a client submitted, with how the pool judged it.
+#[derive(Debug, Clone)]
+pub(crate) struct Submit {
+    pub params: SubmitParams,
+    /// Difficulty of the share's hash, `None` when its job is unknown.
+    pub hash_difficulty: Option<Difficulty>,
+    pub accepted: bool,
+}
+
+/// Error codes as pools commonly send them.
+const JOB_NOT_FOUND: (i64, &str) = (21, "Job not found");
+const DUPLICATE: (i64, &str) = (22, "Duplicate share");
+const LOW_DIFFICULTY: (i64, &str) = (23, "Low difficulty share");
+const REJECTED: (i64, &str) = (20, "Rejected by test");
+
+/// A Stratum v1 pool listening on loopback.
+///
+/// Stops when dropped.
+pub(crate) struct TestPool {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    submits: mpsc::UnboundedReceiver<Submit>,
+    authorized: watch::Receiver<u32>,
+    task: JoinHandle<()>,
+}
+
+/// State the connections share with the test.
+struct Shared {
+    config: TestPoolConfig,
+    state: Mutex<State>,
+    push_tx: broadcast::Sender<JsonRpcMessage>,
+    submit_tx: mpsc::UnboundedSender<Submit>,
+    authorized_tx: watch::Sender<u32>,
+}
+
+#[derive(Default)]
+struct State {
+    difficulty: Option<f64>,
+    verdict: Verdict,
+    jobs: HashMap<String, JobNotification>,
+    /// Params of the latest job, replayed to each new client.
+    latest_job: Option<Value>,
+    seen: HashSet<ShareKey>,
+}
+
+/// What makes a share unique: its job, extranonce2, ntime, nonce and
+/// version bits.
+type ShareKey = (String, Vec<u8>, u32, u32, Option<u32>);
+
+impl TestPool {
+    /// Start a pool on a free loopback port.
+    pub async fn start(config: TestPoolConfig) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind test pool");
+        let addr = listener.local_addr().expect("test pool address");
+        let (push_tx, _) = broadcast::channel(64);
+        let (submit_tx, submits) = mpsc::unbounded_channel();
+        let (authorized_tx, authorized) = watch::channel(0);
+        let shared = Arc::new(Shared {
+            config,
+            state: Mutex::default(),
+            push_tx,
+            submit_tx,
+            authorized_tx,
+        });
+
+        let serving = Arc::clone(&shared);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, peer)) = listener.accept().await {
+                debug!(%peer, "Test pool client connected");
+                serving.serve(stream).await;
+                debug!(%peer, "Test pool client gone");
+            }
+        });
+
+        Self {
+            addr,
+            shared,
+            submits,
+            authorized,
+            task,
+        }
+    }
+
+    /// URL to point a client at the pool.
+    pub fn url(&self) -> String {
+        format!("stratum+tcp://{}", self.addr)
+    }
+
+    /// Wait until a client has authorized.
+    pub async fn authorized(&mut self) {
+        self.authorized
+            .wait_for(|&count| count > 0)
+            .await
+            .expect("test pool running");
+    }
+
+    /// Set the share difficulty, sending it to the client.
+    pub fn set_difficulty(&self, difficulty: f64) {
+        self.shared.state().difficulty = Some(difficulty);
+        self.shared.push(JsonRpcMessage::notification(
+            "mining.set_difficulty",
+            json!([difficulty]),
+        ));
+    }
+
+    /// Send a job, given as `mining.notify` params, to the client.
+    pub fn notify(&self, params: Value) {
+        let array = params.as_array().expect("notify params are an array");
+        let job = JobNotification::from_stratum_params(array).expect("valid notify params");
+        let mut state = self.shared.state();
+        if job.clean_jobs {
+            state.jobs.clear();
+        }
+        state.jobs.insert(job.job_id.clone(), job);
+        state.latest_job = Some(params.clone());
+        drop(state);
+        self.shared
+            .push(JsonRpcMessage::notification("mining.notify", params));
+    }
+
+    /// Answer submits from now on with `verdict`.
+    pub fn set_verdict(&self, verdict: Verdict) {
+        self.shared.state().verdict = verdict;
+    }
+
+    /// Wait for the next submit.
+    pub async fn next_submit(&mut self) -> Submit {
+        self.submits.recv().await.expect("test pool running")
+    }
+}
+
+impl Drop for TestPool {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Shared {
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("test pool state")
+    }
+
+    fn push(&self, msg: JsonRpcMessage) {
+        // No client connected is fine; it gets the state on authorizing.
+        let _ = self.push_tx.send(msg);
+    }
+
+    /// Serve one client until it disconnects.
+    async fn serve(&self, stream: TcpStream) {
+        let (read, mut write) = stream.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut pushes = self.push_tx.subscribe();
+        loop {
+            let replies = tokio::select! {
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => match serde_json::from_str(&line) {
+                        Ok(msg) => self.handle(msg),
+                        Err(e) => {
+                            warn!(error = %e, line, "Test pool got invalid JSON");
+                            continue;
+                        }
+                    },
+                    Ok(None) | Err(_) => return,
+                },
+                pushed = pushes.recv() => match pushed {
+                    Ok(msg) => vec![msg],
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+            };
+            for reply in replies {
+                let mut line = serde_json::to_vec(&reply).expect("serialize reply");
+                line.push(b'\n');
+                if write.write_all(&line).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Messages to send in answer to one from the client.
+    fn handle(&self, msg: JsonRpcMessage) -> Vec<JsonRpcMessage> {
+        let JsonRpcMessage::Request {
+            id: Some(id),
+            method,
+            params,
+        } = msg
+        else {
+            return Vec::new();
+        };
+        let reply = |result: Result<Value, (i64, &str)>| match result {
+            Ok(result) => JsonRpcMessage::Response {
+                id,
+                result: Some(result),
+                error: None,
+            },
+            Err((code, message)) => JsonRpcMessage::Response {
+                id,
+                result: None,
+                error: Some(json!([code, message, null])),
+            },
+        };
+
+        match method.as_str() {
+            "mining.configure" => vec![reply(Ok(match self.config.version_mask {
+                Some(mask) => json!({
+                    "version-rolling": true,
+                    "version-rolling.mask": format!("{mask:08x}"),
+                }),
+                None => json!({ "version-rolling": false }),
+            }))],
+            "mining.subscribe" => vec![reply(Ok(json!([
+                [["mining.notify", "1"]],
+                hex::encode(&self.config.extranonce1),
+                self.config.extranonce2_size,
+            ])))],
+            "mining.authorize" => {
+                let mut replies = vec![reply(Ok(json!(self.config.authorize)))];
+                if self.config.authorize {
+                    let state = self.state();
+                    if let Some(difficulty) = state.difficulty {
+                        replies.push(JsonRpcMessage::notification(
+                            "mining.set_difficulty",
+                            json!([difficulty]),
+                        ));
+                    }
+                    if let Some(job) = &state.latest_job {
+                        replies.push(JsonRpcMessage::notification("mining.notify", job.clone()));
+                    }
+                    self.authorized_tx.send_modify(|count| *count += 1);
+                }
+                replies
+            }
+            "mining.suggest_difficulty" | "mining.extranonce.subscribe" => {
+                vec![reply(Ok(json!(true)))]
+            }
+            "mining.submit" => {
+                let Some(params) = submit_params(&params) else {
+                    return vec![reply(Err((20, "Malformed submit")))];
+                };
+                let (hash_difficulty, verdict) = self.judge(&params);
+                let accepted = verdict.is_ok();
+                let _ = self.submit_tx.send(Submit {
+                    params,
+                    hash_difficulty,
+                    accepted,
+                });
+                vec![reply(verdict.map(|()| json!(true)))]
+            }
+            _ => vec![reply(Err((20, "Unknown method")))],
+        }
+    }
+
+    /// The hash difficulty of a share and whether to accept it.
+    fn judge(
+        &self,
+        submit: &SubmitParams,
+    ) -> (Option<Difficulty>, Result<(), (i64, &'static str)>) {
+        let mut state = self.state();
+        let Some(job) = state.jobs.get(&submit.job_id) else {
+            return (None, Err(JOB_NOT_FOUND));
+        };
+        let Some(hash_difficulty) = self.hash_difficulty(job, submit) else {
+            return (None, Err((20, "Malformed share")));
+        };
+        let verdict = match state.verdict {
+            Verdict::Reject => Err(REJECTED),
+            Verdict::Accept => Ok(()),
+            Verdict::Check => {
+                let difficulty = Difficulty::from_f64(state.difficulty.unwrap_or(1.0));
+                if hash_difficulty >= difficulty {
+                    Ok(())
+                } else {
+                    Err(LOW_DIFFICULTY)
+                }
+            }
+        };
+        let key: ShareKey = (
+            submit.job_id.clone(),
+            submit.extranonce2.clone(),
+            submit.ntime,
+            submit.nonce,
+            submit.version_bits,
+        );
+        let verdict = verdict.and_then(|()| {
+            if state.seen.insert(key) {
+                Ok(())
+            } else {
+                Err(DUPLICATE)
+            }
+        });
+        (Some(hash_difficulty), verdict)
+    }
+
+    /// Rebuild the share's header from its job and hash it.
+    fn hash_difficulty(&self, job: &JobNotification, submit: &SubmitParams) -> Option<Difficulty> {
+        let size = self.config.extranonce2_size;
+        if submit.extranonce2.len() != size {
+            return None;
+        }
+        let mut extranonce2 = [0u8; 8];
+        extranonce2[..size].copy_from_slice(&submit.extranonce2);
+        let extranonce2 = Extranonce2::new(u64::from_le_bytes(extranonce2), size as u8).ok()?;
+        let template = MerkleRootTemplate {
+            coinbase1: job.coinbase1.clone(),
+            extranonce1: self.config.extranonce1.clone(),
+            extranonce2_range: Extranonce2Range::new(size as u8).ok()?,
+            coinbase2: job.coinbase2.clone(),
+            merkle_branches: job.merkle_branches.clone(),
+            cache: Default::default(),
+        };
+        let mask = self.config.version_mask.unwrap_or(0);
+        let version = job.version.to_consensus() as u32;
+        let version = match submit.version_bits {
+            Some(bits) => (version & !mask) | (bits & mask),
+            None => version,
+        };
+        let header = BlockHeader {
+            version: Version::from_consensus(version as i32),
+            prev_blockhash: job.prev_hash,
+            merkle_root: template.compute_merkle_root(&extranonce2).ok()?,
+            time: submit.ntime,
+            bits: job.nbits,
+            nonce: submit.nonce,
+        };
+        Some(Difficulty::from_hash(&header::block_hash(&header)))
+    }
+}
+
+/// Parse `mining.submit` params.
+fn submit_params(params: &Value) -> Option<SubmitParams> {
+    let params = params.as_array()?;
+    let field = |i: usize| params.get(i)?.as_str();
+    let word = |i: usize| u32::from_str_radix(field(i)?, 16).ok();
+    Some(SubmitParams {
+        username: field(0)?.to_string(),
+        job_id: field(1)?.to_string(),
+        extranonce2: hex::decode(field(2)?).ok()?,
+        ntime: word(3)?,
+        nonce: word(4)?,
+        version_bits: match params.get(5) {
+            Some(_) => Some(word(5)?),
+            None => None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::super::connection::Connection;
+    use super::super::connection::Transport;
+    use super::*;
+    use crate::asic::bm13xx::test_data::esp_miner_job::{
+        POOL_SHARE_DIFFICULTY, STRATUM_EXTRANONCE1, STRATUM_EXTRANONCE2_SIZE, VERSION_MASK,
+    };
+    use crate::asic::bm13xx::test_data::stratum_json::{MINING_NOTIFY, MINING_SUBMIT};
+
+    /// Send a request and read up to its response, returning the response
+    /// and the notifications before it.
+    async fn request(
+        conn: &mut Connection,
+        id: u64,
+        method: &str,
+        params: Value,
+    ) -> (JsonRpcMessage, Vec<String>) {
+        conn.write_message(&JsonRpcMessage::request(id, method, params))
+            .await
+            .unwrap();
+        let mut notifications = Vec::new();
+        loop {
+            let msg = tokio::time::timeout(Duration::from_secs(5), conn.read_message())
+                .await
+                .expect("pool answered")
+                .unwrap()
+                .expect("pool still connected");
+            if msg.id() == Some(id) {
+                return (msg, notifications);
+            }
+            notifications.extend(msg.method().map(str::to_string));
+        }
+    }
+
+    fn error_code(msg: &JsonRpcMessage) -> Option<i64> {
+        match msg {
+            JsonRpcMessage::Response {
+                error: Some(error), ..
+            } => error[0].as_i64(),
+            _ => None,
+        }
+    }
+
+    /// The pool judges the captured esp-miner share as the real pool did.
+    #[tokio::test]
+    async fn pool_checks_shares_like_a_real_pool() {
+        let mut pool = TestPool::start(TestPoolConfig {
+            extranonce1: hex::decode(STRATUM_EXTRANONCE1).unwrap(),
+            extranonce2_size: STRATUM_EXTRANONCE2_SIZE,
+            version_mask: Some(VERSION_MASK),
+            authorize: true,
+        })
+        .await;
+        let notify: Value = serde_json::from_str(MINING_NOTIFY).unwrap();
+        pool.set_difficulty(POOL_SHARE_DIFFICULTY);
+        pool.notify(notify["params"].clone());
+
+        let stream = TcpStream::connect(pool.url().trim_start_matches("stratum+tcp://"))
+            .await
+            .unwrap();
+        let mut conn = Connection::new(stream);
+        let (configured, _) = request(&mut conn, 1, "mining.configure", json!([])).await;
+        let JsonRpcMessage::Response {
+            result: Some(configured),
+            ..
+        } = configured
+        else {
+            panic!("configure failed");
+        };
+        assert_eq!(configured["version-rolling.mask"], "1fffe000");
+        request(&mut conn, 2, "mining.subscribe", json!([])).await;
+        let (_, sent) = request(&mut conn, 3, "mining.authorize", json!(["w", "x"])).await;
+        pool.authorized().await;
+        // The difficulty and job come right after the authorize reply.
+        let (_, sent_after) = request(&mut conn, 4, "mining.suggest_difficulty", json!([1])).await;
+        assert_eq!(
+            [sent, sent_after].concat(),
+            ["mining.set_difficulty", "mining.notify"]
+        );
+
+        let submit: Value = serde_json::from_str(MINING_SUBMIT).unwrap();
+        let (accepted, _) = request(&mut conn, 5, "mining.submit", submit["params"].clone()).await;
+        assert_eq!(error_code(&accepted), None);
+        let share = pool.next_submit().await;
+        assert!(share.accepted);
+        assert!(share.hash_difficulty.unwrap().as_f64() >= POOL_SHARE_DIFFICULTY);
+        assert_eq!(share.params.job_id, "875b4b7");
+
+        // The same share again, a wrong nonce and an unknown job are each
+        // rejected for what they are.
+        let (again, _) = request(&mut conn, 6, "mining.submit", submit["params"].clone()).await;
+        assert_eq!(error_code(&again), Some(DUPLICATE.0));
+        assert!(!pool.next_submit().await.accepted);
+        let mut wrong = submit["params"].clone();
+        wrong[4] = json!("7552034d");
+        let (low, _) = request(&mut conn, 7, "mining.submit", wrong.clone()).await;
+        assert_eq!(error_code(&low), Some(LOW_DIFFICULTY.0));
+        assert!(!pool.next_submit().await.accepted);
+        let mut unknown = submit["params"].clone();
+        unknown[1] = json!("ffff");
+        let (stale, _) = request(&mut conn, 8, "mining.submit", unknown).await;
+        assert_eq!(error_code(&stale), Some(JOB_NOT_FOUND.0));
+
+        // On command, the pool accepts the low share and rejects all.
+        pool.set_verdict(Verdict::Accept);
+        let (forced, _) = request(&mut conn, 9, "mining.submit", wrong).await;
+        assert_eq!(error_code(&forced), None);
+        pool.set_verdict(Verdict::Reject);
+        let mut fresh = submit["params"].clone();
+        fresh[4] = json!("00000000");
+        let (rejected, _) = request(&mut conn, 10, "mining.submit", fresh).await;
+        assert_eq!(error_code(&rejected), Some(REJECTED.0));
+    }
+}
@@ -0,0 +1,296 @@
+//! Periodic fleet summary in the log.
+//!
+//! Per-event logs say what happened; this says how things are going. At a
+//! fixed interval the daemon logs one info line with fleet hashrate, shares
+//! accepted and rejected since the previous line, the reject ratio and the
+//! hottest temperature. The line has the same shape every time, so it is
+//! easy to grep and plot. The hashrate shown is smoothed across lines by
+//! `MUJINA_HASHRATE_SMOOTHING` so it doesn't flicker with share luck; the
+//! API reports the windowed estimate as measured.
+
+use std::env;
+use std::time::Duration;
+
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::api_client::summary::{FleetSummary, fleet_summary};
+use crate::api_client::types::MinerTelemetry;
+use crate::tracing::prelude::*;
+use crate::types::{HashRate, HashrateSmoother, TemperatureUnit};
+
+/// Interval between summaries when not configured.
+pub(crate) const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Read the summary interval from `MUJINA_SUMMARY_INTERVAL_SECS`.
+///
+/// Returns `None` when set to 0, which disables the summary.
+pub(crate) fn interval_from_env() -> Option<Duration> {
+    let Ok(value) = env::var("MUJINA_SUMMARY_INTERVAL_SECS") else {
+        return Some(DEFAULT_INTERVAL);
+    };
+    match value.parse::<u64>() {
+        Ok(0) => None,
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => {
+            warn!(
+                value = %value,
+                default_secs = DEFAULT_INTERVAL.as_secs(),
+                "Invalid MUJINA_SUMMARY_INTERVAL_SECS, using default"
+            );
+            Some(DEFAULT_INTERVAL)
+        }
+    }
+}
+
+/// Decides when a summary is due and what changed since the last one.
+///
+/// Time is passed in rather than read, so the cadence can be tested
+/// without a runtime.
+#[derive(Debug)]
+pub(crate) struct SummaryLogger {
+    interval: Duration,
+    next_due: Instant,
+    last_accepted: u64,
+    last_rejected: u64,
+    smoother: HashrateSmoother,
+}
+
+impl SummaryLogger {
+    /// Start a logger whose first summary is one interval after `now`.
+    pub(crate) fn new(interval: Duration, now: Instant) -> Self {
+        Self {
+            interval,
+            next_due: now + interval,
+            last_accepted: 0,
+            last_rejected: 0,
+            smoother: HashrateSmoother::new(1.0),
+        }
+    }
+
+    /// Smooth the shown hashrate with `smoother`. Unsmoothed otherwise.
+    pub(crate) fn with_smoothing(mut self, smoother: HashrateSmoother) -> Self {
+        self.smoother = smoother;
+        self
+    }
+
+    /// When the next summary is due.
+    pub(crate) fn next_due(&self) -> Instant {
+        self.next_due
+    }
+
+    /// Produce a summary if one is due at `now`.
+    ///
+    /// Missed intervals are skipped rather than replayed, so a stalled
+    /// runtime yields one summary covering the whole gap.
+    pub(crate) fn poll(
+        &mut self,
+        now: Instant,
+        telemetry: &MinerTelemetry,
+    ) -> Option<PeriodicSummary> {
+        if now < self.next_due {
+            return None;
+        }
+        while self.next_due <= now {
+            self.next_due += self.interval;
+        }
+
+        let fleet = fleet_summary(telemetry);
+        // Totals shrink when a source goes away; count that as no change
+        // rather than wrapping.
+        let accepted = fleet.shares_accepted.saturating_sub(self.last_accepted);
+        let rejected = fleet.shares_rejected.saturating_sub(self.last_rejected);
+        self.last_accepted = fleet.shares_accepted;
+        self.last_rejected = fleet.shares_rejected;
+
+        Some(PeriodicSummary {
+            shown_hashrate: self.smoother.update(fleet.hashrate),
+            fleet,
+            accepted,
+            rejected,
+        })
+    }
+}
+
+/// One summary line's worth of figures.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PeriodicSummary {
+    pub fleet: FleetSummary,
+
+    /// Shares accepted since the previous summary.
+    pub accepted: u64,
+
+    /// Shares rejected since the previous summary.
+    pub rejected: u64,
+
+    /// Fleet hashrate smoothed across summaries, for the log line.
+    pub shown_hashrate: HashRate,
+}
+
+impl PeriodicSummary {
+    /// Fraction of shares answered since the previous summary that were
+    /// rejected, `None` when none were answered.
+    pub(crate) fn reject_ratio(&self) -> Option<f64> {
+        let answered = self.accepted + self.rejected;
+        (answered > 0).then(|| self.rejected as f64 / answered as f64)
+    }
+
+    fn log(&self) {
+        info!(
+            hashrate = %self.shown_hashrate,
+            accepted = self.accepted,
+            rejected = self.rejected,
+            reject_pct = self.reject_ratio().map(|r| format!("{:.2}", r * 100.0)),
+            hottest = self
+                .fleet
+                .hottest
+                .as_ref()
+                .map(|h| h.temperature.display(TemperatureUnit::configured()).to_string()),
+            "Fleet summary."
+        );
+    }
+}
+
+/// Log a summary every `interval` until shutdown.
+///
+/// `snapshot` is called once per summary for the current telemetry.
+pub(crate) async fn task(
+    interval: Duration,
+    shutdown: CancellationToken,
+    snapshot: impl Fn() -> MinerTelemetry,
+) {
+    let mut logger =
+        SummaryLogger::new(interval, Instant::now()).with_smoothing(HashrateSmoother::from_env());
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = tokio::time::sleep_until(logger.next_due()) => {
+                if let Some(summary) = logger.poll(Instant::now(), &snapshot()) {
+                    summary.log();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+    use crate::api_client::types::{BoardTelemetry, SourceTelemetry, ThreadTelemetry};
+
+    const INTERVAL: Duration = Duration::from_secs(60);
+
+    fn telemetry(accepted: u64, rejected: u64) -> MinerTelemetry {
+        MinerTelemetry {
+            boards: vec![BoardTelemetry {
+                name: "board".into(),
+                threads: vec![ThreadTelemetry {
+                    name: "t0".into(),
+                    hashrate: 1_000_000_000,
+                    is_active: true,
+                    fault: None,
+                }],
+                ..Default::default()
+            }],
+            sources: vec![SourceTelemetry {
+                shares_accepted: accepted,
+                shares_rejected: rejected,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn emits_at_interval_with_deltas() {
+        let start = Instant::now();
+        let mut logger = SummaryLogger::new(INTERVAL, start);
+
+        // Nothing before the first interval elapses.
+        assert_eq!(logger.poll(start, &telemetry(5, 0)), None);
+        assert_eq!(
+            logger.poll(
+                start + INTERVAL - Duration::from_millis(1),
+                &telemetry(5, 0)
+            ),
+            None
+        );
+
+        let first = logger.poll(start + INTERVAL, &telemetry(10, 1)).unwrap();
+        assert_eq!((first.accepted, first.rejected), (10, 1));
+        assert_eq!(first.fleet.hashrate.as_gigahashes(), 1.0);
+
+        // Not again until the next interval.
+        assert_eq!(
+            logger.poll(
+                start + INTERVAL + Duration::from_secs(30),
+                &telemetry(20, 1)
+            ),
+            None
+        );
+
+        let second = logger
+            .poll(start + 2 * INTERVAL, &telemetry(28, 3))
+            .unwrap();
+        assert_eq!((second.accepted, second.rejected), (18, 2));
+        assert_eq!(second.reject_ratio(), Some(0.1));
+    }
+
+    #[test]
+    fn shown_hashrate_is_smoothed_across_summaries() {
+        let start = Instant::now();
+        let mut logger =
+            SummaryLogger::new(INTERVAL, start).with_smoothing(HashrateSmoother::new(0.5));
+        let mut doubled = telemetry(0, 0);
+        doubled.boards[0].threads[0].hashrate = 2_000_000_000;
+
+        let first = logger.poll(start + INTERVAL, &telemetry(0, 0)).unwrap();
+        assert_eq!(first.shown_hashrate.as_gigahashes(), 1.0);
+        let second = logger.poll(start + 2 * INTERVAL, &doubled).unwrap();
+        assert_eq!(second.fleet.hashrate.as_gigahashes(), 2.0);
+        assert_eq!(second.shown_hashrate.as_gigahashes(), 1.5);
+    }
+
+    #[test]
+    fn missed_intervals_are_skipped() {
+        let start = Instant::now();
+        let mut logger = SummaryLogger::new(INTERVAL, start);
+
+        let late = start + 3 * INTERVAL + Duration::from_secs(10);
+        let summary = logger.poll(late, &telemetry(30, 0)).unwrap();
+        assert_eq!(summary.accepted, 30);
+        assert_eq!(logger.next_due(), start + 4 * INTERVAL);
+        assert_eq!(logger.poll(late, &telemetry(30, 0)), None);
+    }
+
+    #[test]
+    fn shrinking_totals_count_as_no_change() {
+        let start = Instant::now();
+        let mut logger = SummaryLogger::new(INTERVAL, start);
+        logger.poll(start + INTERVAL, &telemetry(10, 2));
+
+        let summary = logger.poll(start + 2 * INTERVAL, &telemetry(0, 0)).unwrap();
+        assert_eq!((summary.accepted, summary.rejected), (0, 0));
+        assert_eq!(summary.reject_ratio(), None);
+    }
+
+    #[test]
+    #[serial]
+    fn interval_env_parsing() {
+        let var = "MUJINA_SUMMARY_INTERVAL_SECS";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(interval_from_env(), Some(DEFAULT_INTERVAL));
+            env::set_var(var, "15");
+            assert_eq!(interval_from_env(), Some(Duration::from_secs(15)));
+            env::set_var(var, "0");
+            assert_eq!(interval_from_env(), None);
+            env::set_var(var, "soon");
+            assert_eq!(interval_from_env(), Some(DEFAULT_INTERVAL));
+            env::remove_var(var);
+        }
+    }
+}
@@ -6,13 +6,28 @@
 //! The rest of the program can include `use crate::tracing::prelude::*`
 //! for convenient access to the `trace!()`, `debug!()`, `info!()`,
 //! `warn!()`, and `error!()` macros.
+//!
+//! On stdout, `MUJINA_LOG_FORMAT` picks between the default human format
+//! (colored, fields on a second line) and a compact one-line format for
+//! devices where log volume matters, such as an SBC logging to flash.
+//! Journald keeps events structured either way.
+//!
+//! Targets named in `MUJINA_LOG_DEDUP` have repeated identical warnings
+//! collapsed into periodic summaries; see the `dedup` module.
+//!
+//! The filter can be replaced while the program runs, through
+//! [`set_log_filter`], so a live problem can be traced without a restart
+//! losing the pool connection and whatever state led up to it.
 
 use std::fmt;
+use std::sync::OnceLock;
+use thiserror::Error;
 use time::OffsetDateTime;
 use tracing::field::{Field, Visit};
 use tracing::{Event, Level, Subscriber};
 use tracing_subscriber::{
-    filter::{EnvFilter, LevelFilter},
+    Registry,
+    filter::{EnvFilter, LevelFilter, ParseError},
     fmt::{
         FmtContext, FormatEvent, FormatFields,
         format::{DefaultFields, Writer as FmtWriter},
@@ -20,13 +35,54 @@ use tracing_subscriber::{
     },
     prelude::*,
     registry::LookupSpan,
+    reload,
 };
 
+mod dedup;
+
 pub mod prelude {
     #[allow(unused_imports)]
     pub use tracing::{debug, error, info, trace, warn};
 }
 
+/// In-memory log sink for tests.
+#[cfg(test)]
+#[derive(Clone, Default)]
+struct Buffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+#[cfg(test)]
+impl std::io::Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Run `f` with log output captured, returning what was logged. Tests
+/// use it to check what a user would see.
+#[cfg(test)]
+pub(crate) async fn capture_logs(f: impl Future<Output = ()>) -> String {
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    // The test runtime is single-threaded, so the thread-local default
+    // covers everything `f` does.
+    let guard = tracing::subscriber::set_default(subscriber);
+    f.await;
+    drop(guard);
+
+    let bytes = buffer.0.lock().unwrap().clone();
+    String::from_utf8(bytes).unwrap()
+}
+
 /// Initialize logging.
 ///
 /// If running under systemd, use journald; otherwise fall back to
@@ -37,6 +93,76 @@ pub fn init() {
     }
 }
 
+/// The installed filter, once [`init`] has run.
+static LOG_CONTROL: OnceLock<LogControl> = OnceLock::new();
+
+/// Why the log filter couldn't be changed.
+#[derive(Debug, Error)]
+pub enum LogFilterError {
+    #[error("invalid log filter: {0}")]
+    Invalid(#[from] ParseError),
+    #[error("log filter can't be changed: {0}")]
+    Unavailable(String),
+}
+
+/// Replaces the filter of a running subscriber.
+struct LogControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+    /// RUST_LOG as the program started with it, kept under each new
+    /// filter.
+    rust_log: Option<String>,
+}
+
+impl LogControl {
+    /// A filter layer built from the environment, and its control.
+    fn from_env() -> (reload::Layer<EnvFilter, Registry>, Self) {
+        let (layer, handle) = reload::Layer::new(build_env_filter());
+        let rust_log = std::env::var("RUST_LOG").ok();
+        (layer, Self { handle, rust_log })
+    }
+
+    fn filter(&self) -> Result<String, LogFilterError> {
+        self.handle
+            .with_current(|filter| filter.to_string())
+            .map_err(|e| LogFilterError::Unavailable(e.to_string()))
+    }
+
+    fn set(&self, mujina_log: &str) -> Result<String, LogFilterError> {
+        let filter: EnvFilter =
+            filter_string(self.rust_log.as_deref(), Some(mujina_log)).parse()?;
+        self.handle
+            .reload(filter)
+            .map_err(|e| LogFilterError::Unavailable(e.to_string()))?;
+        self.filter()
+    }
+
+    /// Make this the control [`log_filter`] and [`set_log_filter`] use.
+    fn install(self) {
+        let _ = LOG_CONTROL.set(self);
+    }
+}
+
+/// The log filter in effect, as `EnvFilter` directives.
+pub fn log_filter() -> Result<String, LogFilterError> {
+    control()?.filter()
+}
+
+/// Filter the log as if the program had been started with `MUJINA_LOG`
+/// set to `mujina_log`, returning the filter now in effect. RUST_LOG
+/// still applies beneath it; an empty `mujina_log` goes back to the
+/// defaults.
+pub fn set_log_filter(mujina_log: &str) -> Result<String, LogFilterError> {
+    let filter = control()?.set(mujina_log)?;
+    tracing::info!(mujina_log, %filter, "Log filter changed");
+    Ok(filter)
+}
+
+fn control() -> Result<&'static LogControl, LogFilterError> {
+    LOG_CONTROL
+        .get()
+        .ok_or_else(|| LogFilterError::Unavailable("logging not initialized".into()))
+}
+
 /// Default log filter: WARN for third-party crates, INFO for ours.
 const DEFAULT_LOG_FILTER: &str = "warn,mujina_miner=info";
 
@@ -121,10 +247,13 @@ mod journald {
         }
 
         if let Ok(layer) = tracing_journald::layer() {
+            let (filter, control) = super::LogControl::from_env();
             tracing_subscriber::registry()
-                .with(super::build_env_filter())
+                .with(filter)
+                .with(super::dedup::layer_from_env())
                 .with(layer)
                 .init();
+            control.install();
             true
         } else {
             error!("Failed to initialize journald logging, using stdout.");
@@ -183,29 +312,80 @@ mod journald {
     }
 }
 
-fn init_stdout() {
-    let env_filter = build_env_filter();
+/// How events are written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum LogFormat {
+    /// Colored, with structured fields on a second line.
+    #[default]
+    Human,
+    /// One uncolored line per event, for storage- or bandwidth-limited
+    /// devices.
+    Compact,
+}
 
-    tracing_subscriber::registry()
-        .with(env_filter)
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_timer(LocalTimer)
-                .with_target(true)
-                .fmt_fields(DefaultFields::new())
-                .event_format(CustomFormatter),
-        )
-        .init();
+impl LogFormat {
+    /// Read the format from `MUJINA_LOG_FORMAT` (`human` or `compact`),
+    /// falling back to `human` on other values.
+    ///
+    /// Logging isn't up yet when this runs, so an invalid value is
+    /// reported on stderr.
+    fn from_env() -> Self {
+        match std::env::var("MUJINA_LOG_FORMAT").as_deref() {
+            Err(_) | Ok("human") => Self::Human,
+            Ok("compact") => Self::Compact,
+            Ok(other) => {
+                eprintln!("Invalid MUJINA_LOG_FORMAT {other:?}, using human");
+                Self::Human
+            }
+        }
+    }
+}
+
+fn init_stdout() {
+    let (env_filter, control) = LogControl::from_env();
+    let layer = tracing_subscriber::fmt::layer()
+        .with_timer(LocalTimer)
+        .with_target(true)
+        .fmt_fields(DefaultFields::new());
+
+    match LogFormat::from_env() {
+        LogFormat::Human => tracing_subscriber::registry()
+            .with(env_filter)
+            .with(dedup::layer_from_env())
+            .with(layer.event_format(CustomFormatter))
+            .init(),
+        LogFormat::Compact => tracing_subscriber::registry()
+            .with(env_filter)
+            .with(dedup::layer_from_env())
+            .with(layer.event_format(CompactFormatter))
+            .init(),
+    }
+    control.install();
 }
 
 /// Custom event formatter that strips crate prefix, colors the target,
 /// and displays fields on a second line for readability.
 struct CustomFormatter;
 
+/// Event formatter writing each event as a single line:
+///
+/// ```text
+/// 14:03:07 W board::bitaxe "Fan stalled" fan=1 rpm=0
+/// ```
+///
+/// Time, one-letter level, target without the crate prefix, then the
+/// message and fields. The message and any field value containing
+/// whitespace, `"` or `=` are quoted with Rust string escapes, so a line
+/// splits back into its parts unambiguously. There is no color, padding
+/// or second line.
+struct CompactFormatter;
+
 /// Visitor that collects fields into a string buffer.
 struct FieldCollector {
     fields: Vec<(String, String)>,
     message: Option<String>,
+    /// Keep string values as written rather than Debug-escaped and quoted.
+    raw_strings: bool,
 }
 
 impl FieldCollector {
@@ -213,6 +393,15 @@ impl FieldCollector {
         Self {
             fields: Vec::new(),
             message: None,
+            raw_strings: false,
+        }
+    }
+
+    /// A collector whose field values are unquoted and unescaped.
+    fn raw() -> Self {
+        Self {
+            raw_strings: true,
+            ..Self::new()
         }
     }
 }
@@ -229,9 +418,23 @@ impl Visit for FieldCollector {
             } else {
                 formatted
             };
+            let cleaned = if self.raw_strings {
+                cleaned.trim_matches('"').to_string()
+            } else {
+                cleaned
+            };
             self.fields.push((field.name().to_string(), cleaned));
         }
     }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if self.raw_strings && field.name() != "message" {
+            self.fields
+                .push((field.name().to_string(), value.to_string()));
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
 }
 
 impl<S, N> FormatEvent<S, N> for CustomFormatter
@@ -265,27 +468,7 @@ where
         };
         write!(writer, "{}{}\x1b[0m ", level_color, level_text)?;
 
-        // Write target (module path) intelligently:
-        // - Strip "mujina_miner::" from our own code to reduce noise
-        // - For log compatibility layer, use log.target field if available
-        // - Keep full paths from dependencies (e.g., "mio::poll")
-        let target = event.metadata().target();
-        let short_target = if let Some(stripped) = target.strip_prefix("mujina_miner::") {
-            // Our code: strip the prefix
-            stripped.to_string()
-        } else if target == "log" {
-            // Log compatibility layer: extract real target from log.target field
-            visitor
-                .fields
-                .iter()
-                .find(|(k, _)| k == "log.target")
-                .map(|(_, v)| v.trim_matches('"').to_string())
-                .unwrap_or_else(|| target.to_string())
-        } else {
-            // Dependency code with full module path: use as-is
-            target.to_string()
-        };
-        write!(writer, "{}: ", short_target)?;
+        write!(writer, "{}: ", display_target(event, &visitor))?;
 
         // Write message (normal brightness)
         if let Some(ref msg) = visitor.message {
@@ -322,6 +505,79 @@ where
     }
 }
 
+impl<S, N> FormatEvent<S, N> for CompactFormatter
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: FmtWriter<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut visitor = FieldCollector::raw();
+        event.record(&mut visitor);
+
+        LocalTimer.format_time(&mut writer)?;
+        let level = match *event.metadata().level() {
+            Level::ERROR => 'E',
+            Level::WARN => 'W',
+            Level::INFO => 'I',
+            Level::DEBUG => 'D',
+            Level::TRACE => 'T',
+        };
+        write!(writer, " {} {} ", level, display_target(event, &visitor))?;
+
+        let message = visitor.message.as_deref().unwrap_or_default();
+        write_compact_value(&mut writer, message.trim_matches('"'))?;
+        for (key, value) in visitor
+            .fields
+            .iter()
+            .filter(|(k, _)| !k.starts_with("log."))
+        {
+            write!(writer, " {}=", key)?;
+            write_compact_value(&mut writer, value)?;
+        }
+
+        writeln!(writer)
+    }
+}
+
+/// Write `value` bare, or quoted if it would otherwise be ambiguous.
+fn write_compact_value(writer: &mut FmtWriter<'_>, value: &str) -> fmt::Result {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '=');
+    if needs_quotes {
+        write!(writer, "{:?}", value)
+    } else {
+        writer.write_str(value)
+    }
+}
+
+/// The event's target as the log shows it.
+///
+/// - Strip "mujina_miner::" from our own code to reduce noise
+/// - For log compatibility layer, use log.target field if available
+/// - Keep full paths from dependencies (e.g., "mio::poll")
+fn display_target(event: &Event<'_>, visitor: &FieldCollector) -> String {
+    let target = event.metadata().target();
+    if let Some(stripped) = target.strip_prefix("mujina_miner::") {
+        stripped.to_string()
+    } else if target == "log" {
+        visitor
+            .fields
+            .iter()
+            .find(|(k, _)| k == "log.target")
+            .map(|(_, v)| v.trim_matches('"').to_string())
+            .unwrap_or_else(|| target.to_string())
+    } else {
+        target.to_string()
+    }
+}
+
 // Provide our own timer that formats timestamps in local time and to the
 // nearest second. The default timer was in UTC and formatted timestamps as an
 // long, ugly string.
@@ -345,6 +601,164 @@ impl FormatTime for LocalTimer {
 mod tests {
     use super::*;
 
+    /// Render the events `f` logs with `format`.
+    fn render<F>(format: F, f: impl FnOnce()) -> String
+    where
+        F: for<'a> FormatEvent<tracing_subscriber::Registry, DefaultFields> + Send + Sync + 'static,
+    {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(move || writer.clone())
+                .fmt_fields(DefaultFields::new())
+                .event_format(format),
+        );
+        tracing::subscriber::with_default(subscriber, f);
+        let bytes = buffer.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn filter_changes_at_runtime() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let (handle_filter, handle) = reload::Layer::new(EnvFilter::new(DEFAULT_LOG_FILTER));
+        let control = LogControl {
+            handle,
+            rust_log: None,
+        };
+        let subscriber = tracing_subscriber::registry().with(handle_filter).with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || writer.clone()),
+        );
+        let logged = || String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("hidden at the default");
+            tracing::info!("shown at the default");
+
+            // Raised to debug, debug events appear from then on...
+            let filter = control.set("debug").unwrap();
+            assert_eq!(filter, "mujina_miner=debug,warn");
+            assert_eq!(control.filter().unwrap(), filter);
+            tracing::debug!("shown once raised");
+            tracing::trace!("still below the filter");
+
+            // ...and a module can be singled out, the rest lowered.
+            control.set("warn,tracing::tests=trace").unwrap();
+            tracing::info!(target: "mujina_miner::scheduler", "hidden once lowered");
+            tracing::trace!("shown for the module");
+
+            // A bad directive leaves the filter as it was.
+            assert!(matches!(
+                control.set("stratum_v1=loud"),
+                Err(LogFilterError::Invalid(_))
+            ));
+            tracing::trace!("still shown for the module");
+            control.set("").unwrap();
+            assert_eq!(
+                control.filter().unwrap(),
+                EnvFilter::new(DEFAULT_LOG_FILTER).to_string()
+            );
+        });
+
+        let logs = logged();
+        for shown in [
+            "shown at the default",
+            "shown once raised",
+            "shown for the module",
+            "still shown for the module",
+        ] {
+            assert!(logs.contains(shown), "{shown:?} missing from {logs}");
+        }
+        for hidden in [
+            "hidden at the default",
+            "still below the filter",
+            "hidden once lowered",
+        ] {
+            assert!(!logs.contains(hidden), "{hidden:?} in {logs}");
+        }
+    }
+
+    /// Split a compact line into its tokens, unquoting quoted ones.
+    fn split_compact(line: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut chars = line.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c == ' ' {
+                chars.next();
+                continue;
+            }
+            let mut token = String::new();
+            while let Some(c) = chars.next_if(|&c| c != ' ') {
+                if c != '"' {
+                    token.push(c);
+                    continue;
+                }
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => token.push(chars.next().unwrap()),
+                        c => token.push(c),
+                    }
+                }
+            }
+            tokens.push(token);
+        }
+        tokens
+    }
+
+    fn sample_event() {
+        tracing::warn!(
+            board = "bitaxe-1",
+            temperature_c = 71.5,
+            limit_c = 70,
+            "Temperature above limit"
+        );
+    }
+
+    #[test]
+    fn compact_format_is_smaller_and_parseable() {
+        let human = render(CustomFormatter, sample_event);
+        let compact = render(CompactFormatter, sample_event);
+
+        assert!(
+            compact.len() * 4 < human.len() * 3,
+            "compact {} bytes vs human {}:\n{compact}{human}",
+            compact.len(),
+            human.len()
+        );
+        assert_eq!(compact.lines().count(), 1, "{compact}");
+        assert!(!compact.contains('\x1b'), "{compact}");
+
+        let tokens = split_compact(compact.trim_end());
+        assert_eq!(tokens.len(), 7, "{tokens:?}");
+        assert_eq!(tokens[0].len(), "hh:mm:ss".len());
+        assert_eq!(
+            tokens[1..],
+            [
+                "W",
+                "tracing::tests",
+                "Temperature above limit",
+                "board=bitaxe-1",
+                "temperature_c=71.5",
+                "limit_c=70",
+            ]
+        );
+    }
+
+    #[test]
+    fn compact_values_with_spaces_or_quotes_round_trip() {
+        let compact = render(CompactFormatter, || {
+            tracing::info!(reason = "pool said \"bye\" and left", "");
+        });
+        let tokens = split_compact(compact.trim_end());
+        assert_eq!(tokens[3], "");
+        assert_eq!(tokens[4], "reason=pool said \"bye\" and left");
+    }
+
     /// Mujina's own directive within the built-in defaults.
     fn default_mujina_directive() -> &'static str {
         DEFAULT_LOG_FILTER
@@ -0,0 +1,333 @@
+//! Collapse bursts of identical warnings into periodic summaries.
+//!
+//! A flapping condition, such as an I2C device that NACKs now and then or
+//! a temperature hovering at a limit, can log the same warning thousands of
+//! times and bury everything else. For the targets named in
+//! `MUJINA_LOG_DEDUP`, the first of a run of identical warnings or errors
+//! is logged as usual and the repeats are counted instead. Once per window
+//! (`MUJINA_LOG_DEDUP_SECS`) a run that repeated is reported as one
+//! summary line, "N more occurrences in the last M s". A run that stopped
+//! repeating is forgotten, so its next occurrence is logged in full again.
+//!
+//! Events are identical when they come from the same callsite with the
+//! same field values. Targets are written as in `MUJINA_LOG`, without the
+//! crate prefix, and cover their submodules.
+
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::callsite::Identifier;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use super::prelude::*;
+
+/// Summary window when not configured.
+const DEFAULT_WINDOW: Duration = Duration::from_secs(60);
+
+/// How often the background thread looks for windows that have closed.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Build the layer from `MUJINA_LOG_DEDUP` and `MUJINA_LOG_DEDUP_SECS`, and
+/// start the thread that writes its summaries.
+///
+/// Returns `None` when no targets are named. Logging isn't up yet when this
+/// runs, so an invalid window is reported on stderr.
+pub(super) fn layer_from_env() -> Option<DedupLayer> {
+    let targets = std::env::var("MUJINA_LOG_DEDUP").ok()?;
+    let window = match std::env::var("MUJINA_LOG_DEDUP_SECS") {
+        Err(_) => DEFAULT_WINDOW,
+        Ok(value) => match value.parse::<u64>() {
+            Ok(secs) if secs > 0 => Duration::from_secs(secs),
+            _ => {
+                eprintln!(
+                    "Invalid MUJINA_LOG_DEDUP_SECS {value:?}, using {}",
+                    DEFAULT_WINDOW.as_secs()
+                );
+                DEFAULT_WINDOW
+            }
+        },
+    };
+    let layer = DedupLayer::new(&targets, window);
+    if layer.targets.is_empty() {
+        return None;
+    }
+
+    let flusher = layer.clone();
+    std::thread::Builder::new()
+        .name("log-dedup".into())
+        .spawn(move || {
+            loop {
+                std::thread::sleep(FLUSH_INTERVAL);
+                flusher.flush(Instant::now());
+            }
+        })
+        .ok()?;
+    Some(layer)
+}
+
+/// Layer that drops repeats of identical warnings on the configured targets
+/// and counts them for [`flush`](Self::flush) to summarize.
+#[derive(Clone)]
+pub(super) struct DedupLayer {
+    /// Full target paths covered, each including its submodules.
+    targets: Vec<String>,
+    window: Duration,
+    clock: Arc<dyn Fn() -> Instant + Send + Sync>,
+    runs: Arc<Mutex<HashMap<RunKey, Run>>>,
+}
+
+/// Identifies a run of identical events.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RunKey {
+    callsite: Identifier,
+    fields: String,
+}
+
+/// A run of identical events within the current window.
+#[derive(Debug)]
+struct Run {
+    started: Instant,
+    /// Occurrences dropped since `started`.
+    repeats: u64,
+    /// Where the events come from, as the log shows it.
+    target: String,
+    message: String,
+    /// The other fields, for the summary line.
+    fields: String,
+}
+
+impl DedupLayer {
+    /// Cover the comma-separated `targets`, summarizing every `window`.
+    pub(super) fn new(targets: &str, window: Duration) -> Self {
+        Self {
+            targets: super::elements(Some(targets))
+                .map(|t| format!("mujina_miner::{t}"))
+                .collect(),
+            window,
+            clock: Arc::new(Instant::now),
+            runs: Arc::default(),
+        }
+    }
+
+    /// Take the time from `clock` instead of the system clock.
+    #[cfg(test)]
+    fn with_clock(mut self, clock: impl Fn() -> Instant + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    fn covers(&self, target: &str) -> bool {
+        self.targets.iter().any(|t| {
+            target
+                .strip_prefix(t.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        })
+    }
+
+    /// Log a summary of each run whose window closed by `now` and repeated
+    /// in it, and forget runs that didn't repeat.
+    ///
+    /// Must be called outside of any event dispatch, since it logs.
+    pub(super) fn flush(&self, now: Instant) {
+        let mut summaries = Vec::new();
+        {
+            let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+            runs.retain(|_, run| {
+                if now.duration_since(run.started) < self.window {
+                    return true;
+                }
+                if run.repeats == 0 {
+                    return false;
+                }
+                summaries.push((
+                    run.target.clone(),
+                    run.message.clone(),
+                    run.fields.clone(),
+                    run.repeats,
+                ));
+                run.started = now;
+                run.repeats = 0;
+                true
+            });
+        }
+
+        for (target, message, fields, repeats) in summaries {
+            warn!(
+                source = %target,
+                fields = %fields,
+                "{repeats} more occurrences in the last {}s: {message}",
+                self.window.as_secs()
+            );
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for DedupLayer {
+    fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+        let metadata = event.metadata();
+        // Summaries come from this module and are never held back.
+        if *metadata.level() > Level::WARN
+            || metadata.target() == module_path!()
+            || !self.covers(metadata.target())
+        {
+            return true;
+        }
+
+        let mut visitor = IdentityVisitor::default();
+        event.record(&mut visitor);
+        let key = RunKey {
+            callsite: metadata.callsite(),
+            fields: format!("{}|{}", visitor.message, visitor.fields),
+        };
+
+        let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(run) = runs.get_mut(&key) {
+            run.repeats += 1;
+            return false;
+        }
+        let target = metadata.target();
+        runs.insert(
+            key,
+            Run {
+                started: (self.clock)(),
+                repeats: 0,
+                target: target
+                    .strip_prefix("mujina_miner::")
+                    .unwrap_or(target)
+                    .to_string(),
+                message: visitor.message,
+                fields: visitor.fields,
+            },
+        );
+        true
+    }
+}
+
+/// Renders an event's message and fields for comparison.
+#[derive(Default)]
+struct IdentityVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for IdentityVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            if !self.fields.is_empty() {
+                self.fields.push_str(", ");
+            }
+            let _ = write!(self.fields, "{}={value:?}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::prelude::*;
+
+    use super::*;
+    use crate::tracing::Buffer;
+
+    /// Warn the way a flaky I2C read would.
+    fn nack(bus: u8) {
+        warn!(bus, error = "NACK", "Fan controller read failed");
+    }
+
+    #[test]
+    fn repeated_warnings_collapse_into_summaries_per_window() {
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+        let now = Arc::new(Mutex::new(start));
+        let clock = now.clone();
+        let dedup =
+            DedupLayer::new("tracing::dedup", window).with_clock(move || *clock.lock().unwrap());
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(dedup.clone()).with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(move || writer.clone()),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            let at = |secs| {
+                let t = start + Duration::from_secs(secs);
+                *now.lock().unwrap() = t;
+                t
+            };
+
+            // One failure a second for five minutes, with the flusher
+            // running every second.
+            for secs in 0..300 {
+                nack(1);
+                dedup.flush(at(secs));
+            }
+            // A different field value is a different warning.
+            nack(2);
+
+            // The flapping stops. The last partial window is summarized,
+            // then the run is forgotten and logs in full again.
+            dedup.flush(at(360));
+            dedup.flush(at(420));
+            nack(1);
+            // Other levels and targets are never held back.
+            info!("Fan controller recovered");
+            info!("Fan controller recovered");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        let summaries: Vec<&str> = lines
+            .iter()
+            .copied()
+            .filter(|l| l.contains("more occurrences"))
+            .collect();
+        let originals = lines
+            .iter()
+            .filter(|l| l.contains("Fan controller read failed") && !l.contains("more"))
+            .count();
+
+        // Flushes at 60, 120, 180 and 240 s each close a full window; the 59
+        // repeats after that wait for 360 s. Bus 2 never repeated, so it has
+        // no summary.
+        assert_eq!(lines.len(), 10, "{output}");
+        assert_eq!(originals, 3, "{output}");
+        assert_eq!(summaries.len(), 5, "{output}");
+        for summary in &summaries[..4] {
+            assert!(
+                summary.contains("60 more occurrences in the last 60s: Fan controller read failed"),
+                "{summary}"
+            );
+            assert!(
+                summary.contains("source=tracing::dedup::tests"),
+                "{summary}"
+            );
+            assert!(summary.contains("bus=1"), "{summary}");
+        }
+        assert!(summaries[4].contains("59 more occurrences"), "{output}");
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|l| l.contains("Fan controller recovered"))
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn covers_named_targets_and_their_submodules() {
+        let dedup = DedupLayer::new("peripheral::tps546, board", DEFAULT_WINDOW);
+        assert!(dedup.covers("mujina_miner::peripheral::tps546"));
+        assert!(dedup.covers("mujina_miner::board::bitaxe"));
+        assert!(!dedup.covers("mujina_miner::boardwalk"));
+        assert!(!dedup.covers("mujina_miner::peripheral::emc2101"));
+        assert!(!dedup.covers("nusb"));
+    }
+}
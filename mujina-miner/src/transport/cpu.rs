@@ -1,13 +1,16 @@
 //! CPU miner virtual transport.
 //!
 //! Provides transport events for the CPU mining backend. Unlike USB transport,
-//! these events are synthesized at startup based on environment configuration
+//! these events are synthesized at startup based on the daemon's configuration
 //! rather than discovered from hardware.
 
+#[cfg(feature = "cpu-miner")]
+use crate::cpu_miner::CpuMinerConfig;
+
 /// Transport events for CPU miner virtual devices.
 #[derive(Debug)]
 pub enum TransportEvent {
-    /// A CPU miner "device" was connected (enabled via environment).
+    /// A CPU miner "device" was connected (enabled by configuration).
     CpuDeviceConnected(CpuDeviceInfo),
 
     /// A CPU miner "device" was disconnected.
@@ -20,9 +23,7 @@ pub struct CpuDeviceInfo {
     /// Unique identifier for this virtual device.
     pub device_id: String,
 
-    /// Number of mining threads to create.
-    pub thread_count: usize,
-
-    /// Target CPU duty cycle percentage (1-100).
-    pub duty_percent: u8,
+    /// How the board hashes.
+    #[cfg(feature = "cpu-miner")]
+    pub config: CpuMinerConfig,
 }
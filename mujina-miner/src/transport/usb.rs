@@ -54,7 +54,8 @@ mod platform {
 }
 
 /// Information about a discovered USB device.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(Default))]
 pub struct UsbDeviceInfo {
     /// USB vendor ID
     pub vid: u16,
@@ -245,18 +246,3 @@ fn build_device_info(device: &DeviceInfo) -> UsbDeviceInfo {
         device_path: platform::device_path(device),
     }
 }
-
-#[cfg(test)]
-impl Default for UsbDeviceInfo {
-    fn default() -> Self {
-        Self {
-            vid: 0,
-            pid: 0,
-            bcd_device: 0,
-            serial_number: None,
-            manufacturer: None,
-            product: None,
-            device_path: String::new(),
-        }
-    }
-}
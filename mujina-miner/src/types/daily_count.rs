@@ -0,0 +1,189 @@
+//! Counts that restart every day.
+//!
+//! Pool dashboards report shares per day, so share counts are kept for the
+//! current day next to the lifetime totals. A day runs from midnight to
+//! midnight at a fixed UTC offset, UTC unless configured otherwise.
+
+use std::env;
+
+use time::{Date, OffsetDateTime, UtcOffset};
+
+use crate::tracing::prelude::*;
+
+/// Where one stats day ends and the next begins: midnight at a fixed
+/// offset from UTC.
+///
+/// The offset is fixed, so a local day does not follow daylight saving
+/// changes; pick the offset the pool's dashboard uses, usually UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayBoundary(UtcOffset);
+
+impl Default for DayBoundary {
+    fn default() -> Self {
+        Self::UTC
+    }
+}
+
+impl DayBoundary {
+    /// Midnight UTC.
+    pub const UTC: Self = Self(UtcOffset::UTC);
+
+    /// Read the offset from `MUJINA_STATS_DAY_OFFSET`, warning and falling
+    /// back to UTC on invalid values.
+    pub fn from_env() -> Self {
+        let Ok(value) = env::var("MUJINA_STATS_DAY_OFFSET") else {
+            return Self::UTC;
+        };
+        Self::from_offset_str(&value).unwrap_or_else(|| {
+            warn!(value = %value, "Invalid MUJINA_STATS_DAY_OFFSET, using UTC");
+            Self::UTC
+        })
+    }
+
+    /// Parse an offset written as [`parse_utc_offset`] takes it.
+    pub fn from_offset_str(text: &str) -> Option<Self> {
+        parse_utc_offset(text).map(Self)
+    }
+
+    /// The stats day `at` falls in.
+    pub fn day_of(self, at: OffsetDateTime) -> Date {
+        at.to_offset(self.0).date()
+    }
+}
+
+/// Parse an offset from UTC written `+HH:MM`, `-HH:MM` or `+HH`.
+pub fn parse_utc_offset(text: &str) -> Option<UtcOffset> {
+    let text = text.trim();
+    let (sign, rest) = match text.as_bytes().first()? {
+        b'+' => (1, &text[1..]),
+        b'-' => (-1, &text[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let hours: i8 = hours.parse().ok().filter(|h| (0..24).contains(h))?;
+    let minutes: i8 = minutes.parse().ok().filter(|m| (0..60).contains(m))?;
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0).ok()
+}
+
+/// A count of events on the current stats day.
+///
+/// Counting on a new day starts over from zero, and reading on a day
+/// with no events yet gives zero, so a quiet start to the day never
+/// shows yesterday's count.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DailyCount {
+    boundary: DayBoundary,
+    day: Option<Date>,
+    count: u64,
+}
+
+impl DailyCount {
+    /// An empty count whose days end at `boundary`.
+    pub fn new(boundary: DayBoundary) -> Self {
+        Self {
+            boundary,
+            day: None,
+            count: 0,
+        }
+    }
+
+    /// Count one event at `at`.
+    pub fn increment(&mut self, at: OffsetDateTime) {
+        let day = self.boundary.day_of(at);
+        if self.day != Some(day) {
+            self.day = Some(day);
+            self.count = 0;
+        }
+        self.count += 1;
+    }
+
+    /// Events counted on the stats day containing `at`.
+    pub fn get(&self, at: OffsetDateTime) -> u64 {
+        if self.day == Some(self.boundary.day_of(at)) {
+            self.count
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use time::Duration;
+    use time::macros::{datetime, offset};
+
+    use super::*;
+
+    #[test]
+    fn rolls_over_exactly_at_midnight() {
+        let mut today = DailyCount::new(DayBoundary::UTC);
+        let midnight = datetime!(2026-03-01 00:00 UTC);
+
+        for at in [
+            midnight - Duration::hours(5),
+            midnight - Duration::nanoseconds(1),
+        ] {
+            today.increment(at);
+        }
+        assert_eq!(today.get(midnight - Duration::nanoseconds(1)), 2);
+
+        // Nothing has happened yet today.
+        assert_eq!(today.get(midnight), 0);
+
+        today.increment(midnight);
+        assert_eq!(today.get(midnight + Duration::hours(23)), 1);
+
+        // A day with no events reads zero, not the last day's count.
+        assert_eq!(today.get(midnight + Duration::days(2)), 0);
+    }
+
+    #[test]
+    fn offset_moves_the_boundary() {
+        let boundary = DayBoundary::from_offset_str("-05:00").unwrap();
+        let mut today = DailyCount::new(boundary);
+
+        // 03:00 UTC is still the previous evening at UTC-5.
+        today.increment(datetime!(2026-03-01 03:00 UTC));
+        assert_eq!(today.get(datetime!(2026-03-01 04:59 UTC)), 1);
+        assert_eq!(today.get(datetime!(2026-03-01 05:00 UTC)), 0);
+
+        // Times given in another offset land on the same day.
+        assert_eq!(today.get(datetime!(2026-02-28 23:59 -05:00)), 1);
+    }
+
+    #[test]
+    fn parses_offsets() {
+        assert_eq!(
+            DayBoundary::from_offset_str("+05:30"),
+            Some(DayBoundary(offset!(+05:30)))
+        );
+        assert_eq!(
+            DayBoundary::from_offset_str("-8"),
+            Some(DayBoundary(offset!(-08:00)))
+        );
+        assert_eq!(
+            DayBoundary::from_offset_str("+00:00"),
+            Some(DayBoundary::UTC)
+        );
+        for invalid in ["", "05:00", "+24:00", "+01:60", "+1:x", "UTC"] {
+            assert_eq!(DayBoundary::from_offset_str(invalid), None, "{invalid:?}");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn boundary_from_env() {
+        let var = "MUJINA_STATS_DAY_OFFSET";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(DayBoundary::from_env(), DayBoundary::UTC);
+            env::set_var(var, "+09:00");
+            assert_eq!(DayBoundary::from_env(), DayBoundary(offset!(+09:00)));
+            env::set_var(var, "Tokyo");
+            assert_eq!(DayBoundary::from_env(), DayBoundary::UTC);
+            env::remove_var(var);
+        }
+    }
+}
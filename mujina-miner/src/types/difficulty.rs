@@ -1,4 +1,27 @@
 //! Difficulty type with lossless 256-bit representation.
+//!
+//! Every conversion between difficulty, target, hash, displayed text and
+//! expected hash count goes through [`Difficulty`], so they can't drift
+//! apart; the consistency tests at the bottom hold them together across
+//! the whole range from sub-1 test difficulties to the hardest target.
+//!
+//! A share meets a target when its hash is at or below it, as in
+//! consensus, so a hash exactly at the target counts. Every check goes
+//! through [`Target::is_met_by`], or compares [`Difficulty`] values,
+//! whose order is the inverse of their targets'; never compare
+//! difficulties as `f64`, which rounds either way at the boundary.
+//!
+//! Hashes are counted two ways, and neither may be swapped for an
+//! approximation. Measured hashrate counts each share's exact work, the
+//! mean hashes to meet its target, `2^256 / (target + 1)`
+//! ([`Target::to_work`]). Planning from a difficulty, as share rates and
+//! the time to a share or block do, uses [`HASHES_PER_DIFFICULTY`],
+//! exactly `2^32` per unit of difficulty, the convention pools credit.
+//! The two differ by at most 1/65535, difficulty 1's target being
+//! `0xffff * 2^208` rather than `2^224`; a rounded constant such as
+//! 4.295e9 would put them apart by more than that.
+//!
+//! [`HASHES_PER_DIFFICULTY`]: Difficulty::HASHES_PER_DIFFICULTY
 
 use crate::u256::U256;
 use bitcoin::hash_types::BlockHash;
@@ -54,6 +77,17 @@ impl Difficulty {
         Self(Target::from(U256::from(Target::MAX) / value))
     }
 
+    /// Create from a share difficulty received or computed as f64,
+    /// rejecting values that aren't a difficulty.
+    ///
+    /// [`from_f64`](Self::from_f64) treats zero, negative, NaN and infinite
+    /// values as difficulty 1 so a misbehaving pool still yields a usable
+    /// target; that would display as a plausible "1". Returns `None` for
+    /// them instead, so a caller can show "?".
+    pub fn from_share_difficulty(value: f64) -> Option<Self> {
+        (value.is_finite() && value > 0.0).then(|| Self::from_f64(value))
+    }
+
     /// Get difficulty as f64.
     ///
     /// Exact to 12 significant digits for any difficulty a
@@ -68,6 +102,11 @@ impl Difficulty {
     /// digits to clean these up. Even very small values like
     /// 0.000_000_42 are unaffected; the rounding only discards
     /// noise past the last significant digit.
+    ///
+    /// [`Difficulty::MAX`], as from an all-zero hash, has no finite
+    /// difficulty and saturates to `f64::MAX`, which
+    /// [`from_share_difficulty`](Self::from_share_difficulty) maps back
+    /// to it. Display shows it as [`Self::UNBOUNDED`].
     pub fn as_f64(self) -> f64 {
         let raw = self.0.difficulty_float();
         if !raw.is_finite() {
@@ -90,6 +129,23 @@ impl Difficulty {
         }
     }
 
+    /// Mean hashes needed to meet this difficulty: `difficulty * 2^32`,
+    /// the convention pools and hashrate reporting use. Infinite for
+    /// [`Difficulty::MAX`].
+    ///
+    /// The exact mean for a target is `2^256 / (target + 1)` (its
+    /// [`Work`](bitcoin::Work)), which is larger by up to 1/65535, the
+    /// gap between difficulty 1's target `0xffff * 2^208` and `2^224`.
+    pub fn expected_hashes(self) -> f64 {
+        self.0.difficulty_float() * Self::HASHES_PER_DIFFICULTY
+    }
+
+    /// The difficulty at which a share takes `hashes` hashes on average,
+    /// the inverse of [`expected_hashes`](Self::expected_hashes).
+    pub fn from_expected_hashes(hashes: f64) -> Self {
+        Self::from_f64(hashes / Self::HASHES_PER_DIFFICULTY)
+    }
+
     /// Create difficulty from a target (lossless).
     pub fn from_target(target: Target) -> Self {
         Self(target)
@@ -107,6 +163,9 @@ impl Difficulty {
     /// The hash value directly represents the target that was met, so this
     /// conversion is lossless. Useful for determining what difficulty a
     /// found share represents.
+    ///
+    /// An all-zero hash meets every target and gives [`Difficulty::MAX`],
+    /// which displays as [`Self::UNBOUNDED`].
     pub fn from_hash(hash: &BlockHash) -> Self {
         let hash_u256 = U256::from_le_bytes(*hash.as_byte_array());
         if hash_u256 == U256::ZERO {
@@ -116,6 +175,96 @@ impl Difficulty {
         Self(Target::from(hash_u256))
     }
 
+    /// Parse a difficulty written the way [`Display`](fmt::Display) writes
+    /// it: a number with an optional SI suffix (K, M, G, T or P).
+    ///
+    /// Suffixes are case-insensitive, so "512k" and "1.5M" both parse.
+    /// Returns `None` for anything else, including zero and negatives.
+    pub fn from_si(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (number, scale) = match text.char_indices().last()? {
+            (i, c) if c.is_ascii_alphabetic() => {
+                let scale = match c.to_ascii_uppercase() {
+                    'K' => 1e3,
+                    'M' => 1e6,
+                    'G' => 1e9,
+                    'T' => 1e12,
+                    'P' => 1e15,
+                    _ => return None,
+                };
+                (&text[..i], scale)
+            }
+            _ => (text, 1.0),
+        };
+        Self::from_share_difficulty(number.trim_end().parse::<f64>().ok()? * scale)
+    }
+
+    /// Split into the value scaled for its SI suffix and the suffix, as
+    /// [`Display`](fmt::Display) shows them, e.g. `(1.5, "M")`.
+    ///
+    /// For laying out the number and suffix separately. The scaled value
+    /// is unrounded; Display then rounds it to at most three significant
+    /// digits. Difficulties below 1000 have an empty suffix.
+    ///
+    /// [`Difficulty::MAX`] has no finite difficulty and splits into
+    /// `(f64::INFINITY, "")`, which formats as [`Self::UNBOUNDED`].
+    pub fn si_parts(&self) -> (f64, &'static str) {
+        if self.is_unbounded() {
+            return (f64::INFINITY, "");
+        }
+        let value = self.as_f64();
+        let (unit, suffix) = Self::si_unit(value);
+        (value / unit, suffix)
+    }
+
+    /// The SI suffix `value` is shown with, and what it stands for.
+    fn si_unit(value: f64) -> (f64, &'static str) {
+        if value >= 1e15 {
+            (1e15, "P")
+        } else if value >= 1e12 {
+            (1e12, "T")
+        } else if value >= 1e9 {
+            (1e9, "G")
+        } else if value >= 1e6 {
+            (1e6, "M")
+        } else if value >= 1e3 {
+            (1e3, "K")
+        } else {
+            (1.0, "")
+        }
+    }
+
+    /// Format with a fixed number of significant digits, keeping the SI
+    /// suffix of [`Display`](fmt::Display).
+    ///
+    /// Unlike Display, trailing zeros are kept so columns of values line
+    /// up: 3 digits gives "1.50M", "12.3K" and "0.00125". Zero digits is
+    /// treated as one.
+    ///
+    /// The suffix is picked again after rounding, so rounding up carries
+    /// into the next unit: 999,960 gives "1.00M", not "1000K".
+    pub fn format_with_precision(&self, sig_figs: u32) -> String {
+        let (scaled, _) = self.si_parts();
+        if scaled.is_infinite() {
+            return Self::UNBOUNDED.to_string();
+        }
+        if scaled <= 0.0 || scaled.is_nan() {
+            return "0".to_string();
+        }
+        let decimals = |v: f64| (sig_figs.max(1) as i32 - 1 - v.log10().floor() as i32).max(0);
+        let round = |v: f64| {
+            let scale = 10f64.powi(decimals(v));
+            (v * scale).round() / scale
+        };
+        let value = self.as_f64();
+        let (unit, _) = Self::si_unit(value);
+        let rounded = round(value / unit) * unit;
+        let (unit, suffix) = Self::si_unit(rounded);
+        let scaled = rounded / unit;
+        let decimals = decimals(scaled) as usize;
+        format!("{scaled:.decimals$}{suffix}")
+    }
+
     /// Significant digits preserved by [`Self::as_f64()`] (and
     /// transitively by [`Self::as_u64()`]).
     ///
@@ -129,6 +278,24 @@ impl Difficulty {
     /// over that bound.
     const PRECISION_DIGITS: u32 = 12;
 
+    /// Hashes per unit of difficulty, exactly 2^32.
+    pub const HASHES_PER_DIFFICULTY: f64 = (1u64 << 32) as f64;
+
+    /// How [`Difficulty::MAX`] displays.
+    ///
+    /// A zero target makes `difficulty_float()` infinite. [`Self::as_f64`]
+    /// saturates that to `f64::MAX` for arithmetic, but shown through the
+    /// SI scaling it would print as a 294-digit "P" value, so display
+    /// uses this instead. It matches how Rust prints an infinite f64, and
+    /// [`from_si`](Self::from_si) rejects it like any other infinity.
+    pub const UNBOUNDED: &'static str = "inf";
+
+    /// Whether no finite difficulty corresponds to the target, i.e. it is
+    /// zero and no hash can meet it.
+    fn is_unbounded(&self) -> bool {
+        !self.0.difficulty_float().is_finite()
+    }
+
     /// Round an f64 to `digits` significant decimal digits.
     fn round_significant(value: f64, digits: u32) -> f64 {
         if value == 0.0 || !value.is_finite() {
@@ -165,6 +332,15 @@ impl PartialEq for Difficulty {
 
 impl Eq for Difficulty {}
 
+/// Difficulties order by their targets, so the comparison is exact and
+/// total: there is no NaN to special-case, since floats that aren't a
+/// difficulty never become one (see
+/// [`from_share_difficulty`](Difficulty::from_share_difficulty)). `max()`
+/// over shares finds the best one.
+///
+/// Two difficulties that display the same can still differ, because
+/// [`Display`](fmt::Display) rounds. Deciding whether a hash meets a
+/// target is done on [`Target`] itself, not by comparing difficulties.
 impl PartialOrd for Difficulty {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -181,11 +357,14 @@ impl Ord for Difficulty {
 
 impl fmt::Display for Difficulty {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_unbounded() {
+            return f.write_str(Self::UNBOUNDED);
+        }
         let value = self.as_f64();
 
         // Handle sub-1.0 difficulties with adaptive precision
         if value < 1.0 {
-            if value <= 0.0 {
+            if value <= 0.0 || value.is_nan() {
                 return write!(f, "0");
             }
             let magnitude = value.log10().floor() as i32;
@@ -196,28 +375,34 @@ impl fmt::Display for Difficulty {
         }
 
         // Format with SI suffixes (K, M, G, T, P)
-        let (scaled, suffix) = if value >= 1e15 {
-            (value / 1e15, "P")
-        } else if value >= 1e12 {
-            (value / 1e12, "T")
-        } else if value >= 1e9 {
-            (value / 1e9, "G")
-        } else if value >= 1e6 {
-            (value / 1e6, "M")
-        } else if value >= 1e3 {
-            (value / 1e3, "K")
-        } else {
-            (value, "")
+        let (scaled, suffix) = self.si_parts();
+
+        // Three significant digits: "112T", "11.2T", "1.12T". Whole
+        // numbers omit decimals ("1"). The digit count is decided on the
+        // rounded number, since rounding can carry it across a threshold:
+        // 99.96 shows as "100", not "100.0", and 9.996 as "10.0".
+        let decimals_for = |shown: f64| {
+            if shown >= 100.0 {
+                0
+            } else if shown >= 10.0 {
+                1
+            } else {
+                2
+            }
         };
-
-        // Round to appropriate precision; omit decimals for whole numbers
-        if scaled >= 100.0 || scaled.fract() == 0.0 {
-            write!(f, "{:.0}{}", scaled, suffix) // "112T" or "1"
-        } else if scaled >= 10.0 {
-            write!(f, "{:.1}{}", scaled, suffix) // "11.2T"
+        let mut decimals = if scaled.fract() == 0.0 {
+            0
         } else {
-            write!(f, "{:.2}{}", scaled, suffix) // "1.12T"
+            decimals_for(scaled)
+        };
+        let mut text = format!("{scaled:.decimals$}");
+        while let Ok(shown) = text.parse::<f64>()
+            && decimals_for(shown) < decimals
+        {
+            decimals = decimals_for(shown);
+            text = format!("{scaled:.decimals$}");
         }
+        write!(f, "{text}{suffix}")
     }
 }
 
@@ -226,6 +411,23 @@ mod tests {
     use super::*;
     use bitcoin::hashes::Hash;
 
+    #[test]
+    fn test_difficulty_from_share_difficulty() {
+        let diff = Difficulty::from_share_difficulty(2048.0).unwrap();
+        assert_eq!(diff, Difficulty::from(2048_u64));
+        assert_eq!(diff.to_string(), "2.05K");
+
+        let diff = Difficulty::from_share_difficulty(0.001).unwrap();
+        assert_eq!(diff.to_string(), "0.001");
+
+        for invalid in [0.0, -0.0, -512.0, f64::NAN, f64::INFINITY] {
+            assert!(
+                Difficulty::from_share_difficulty(invalid).is_none(),
+                "{invalid}"
+            );
+        }
+    }
+
     #[test]
     fn test_difficulty_as_u64() {
         let diff = Difficulty::from(1024_u64);
@@ -282,8 +484,23 @@ mod tests {
         let diff_a = Difficulty::from(500_u64);
         let diff_b = Difficulty::from(500_u64);
         assert_eq!(diff_a, diff_b);
-        assert!(!(diff_a > diff_b));
-        assert!(!(diff_a < diff_b));
+        assert!(diff_a <= diff_b);
+        assert!(diff_a >= diff_b);
+    }
+
+    #[test]
+    fn test_difficulty_sorting() {
+        let mut shares: Vec<Difficulty> = [2048.0, 0.5, 1.5e6, 1.0, 2049.0]
+            .into_iter()
+            .map(Difficulty::from_f64)
+            .collect();
+        shares.sort();
+        let sorted: Vec<String> = shares.iter().map(|d| d.to_string()).collect();
+        assert_eq!(sorted, ["0.5", "1", "2.05K", "2.05K", "1.50M"]);
+
+        // Equal on display, still ordered.
+        assert!(shares[2] < shares[3]);
+        assert_eq!(shares.iter().max(), Some(&Difficulty::from_f64(1.5e6)));
     }
 
     #[test]
@@ -338,6 +555,123 @@ mod tests {
         assert_eq!(diff.to_string(), "2.05K");
     }
 
+    #[test]
+    fn test_display_precision_follows_rounded_value() {
+        // Rounding carries these into the next digit count.
+        assert_eq!(Difficulty::from_f64(99.96e12).to_string(), "100T");
+        assert_eq!(Difficulty::from_f64(9.996e12).to_string(), "10.0T");
+        assert_eq!(Difficulty::from_f64(99.96).to_string(), "100");
+        assert_eq!(Difficulty::from_f64(9.996e3).to_string(), "10.0K");
+
+        // Just below, they keep their own.
+        assert_eq!(Difficulty::from_f64(99.94e12).to_string(), "99.9T");
+        assert_eq!(Difficulty::from_f64(9.994e12).to_string(), "9.99T");
+
+        // Exactly on the rounding edge, whichever way the stored value
+        // rounds, the number shown has three significant digits.
+        for (value, below, above) in [
+            (99.95e12, "99.9T", "100T"),
+            (9.995e12, "9.99T", "10.0T"),
+            (99.95e6, "99.9M", "100M"),
+            (9.995, "9.99", "10.0"),
+        ] {
+            let text = Difficulty::from_f64(value).to_string();
+            assert!(text == below || text == above, "{value}: {text:?}");
+        }
+    }
+
+    #[test]
+    fn test_si_parts_match_display() {
+        for value in [
+            0.25, 1.0, 999.0, 1000.0, 1234.5, 999_999.0, 1.5e6, 42e9, 112e12, 3.2e15,
+        ] {
+            let diff = Difficulty::from_f64(value);
+            let (scaled, suffix) = diff.si_parts();
+            let text = diff.to_string();
+            let number = text.strip_suffix(suffix).unwrap_or_else(|| {
+                panic!("{text:?} does not end in {suffix:?}");
+            });
+            assert!(
+                !number.ends_with(|c: char| c.is_ascii_alphabetic()),
+                "{text:?} has a suffix si_parts missed"
+            );
+
+            // Display only rounds what si_parts returns.
+            let shown: f64 = number.parse().unwrap();
+            assert!(
+                (shown - scaled).abs() <= 0.005 * scaled.max(1.0),
+                "{text:?} vs {scaled}"
+            );
+        }
+        assert_eq!(Difficulty::from(1_500_000_u64).si_parts(), (1.5, "M"));
+    }
+
+    #[test]
+    fn test_format_with_precision() {
+        let cases = [
+            (0.00123456, "0.00123", "0.0012346"),
+            (1.0, "1.00", "1.0000"),
+            (512.0, "512", "512.00"),
+            (1234.5, "1.23K", "1.2345K"),
+            (12_345.0, "12.3K", "12.345K"),
+            (1.5e6, "1.50M", "1.5000M"),
+            (112_233_445_566_778.0, "112T", "112.23T"),
+        ];
+        for (value, three, five) in cases {
+            let diff = Difficulty::from_f64(value);
+            assert_eq!(diff.format_with_precision(3), three, "{value}");
+            assert_eq!(diff.format_with_precision(5), five, "{value}");
+        }
+
+        // Precision beyond the integer part is never negative.
+        assert_eq!(
+            Difficulty::from(123_456_u64).format_with_precision(1),
+            "123K"
+        );
+        assert_eq!(Difficulty::from(2_u64).format_with_precision(0), "2");
+    }
+
+    #[test]
+    fn precision_rounding_carries_into_the_next_unit() {
+        let cases = [
+            (999_960.0, "1.00M"),
+            (999_400.0, "999K"),
+            (999.6, "1.00K"),
+            (9.996, "10.0"),
+            (99_960_000_000_000.0, "100T"),
+        ];
+        for (value, three) in cases {
+            let diff = Difficulty::from_f64(value);
+            assert_eq!(diff.format_with_precision(3), three, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_difficulty_from_si() {
+        assert_eq!(Difficulty::from_si("500"), Some(Difficulty::from(500_u64)));
+        assert_eq!(
+            Difficulty::from_si("512k"),
+            Some(Difficulty::from(512_000_u64))
+        );
+        assert_eq!(
+            Difficulty::from_si("1.5M"),
+            Some(Difficulty::from(1_500_000_u64))
+        );
+        assert_eq!(
+            Difficulty::from_si("2 G"),
+            Some(Difficulty::from(2_000_000_000_u64))
+        );
+        assert_eq!(Difficulty::from_si("0.5"), Some(Difficulty::from_f64(0.5)));
+
+        // Display output parses back to the same figure
+        let diff = Difficulty::from(11_200_000_000_000_u64);
+        assert_eq!(Difficulty::from_si(&diff.to_string()), Some(diff));
+
+        for bad in ["", "M", "1.5X", "-3K", "0", "lots"] {
+            assert_eq!(Difficulty::from_si(bad), None, "{bad:?}");
+        }
+    }
+
     #[test]
     fn test_difficulty_from_hash() {
         // Target::MAX gives difficulty 1
@@ -478,6 +812,51 @@ mod tests {
         assert_eq!(Difficulty::from(0_u64).as_f64(), 1.0);
     }
 
+    #[test]
+    fn test_display_edge_case_targets() {
+        // A zero target has no finite difficulty; it shows as the
+        // sentinel rather than f64::MAX scaled to petahashes.
+        let zero = Difficulty::from_target(Target::ZERO);
+        assert_eq!(zero.to_string(), Difficulty::UNBOUNDED);
+        assert_eq!(zero.format_with_precision(3), Difficulty::UNBOUNDED);
+        assert_eq!(zero.si_parts(), (f64::INFINITY, ""));
+        assert_eq!(Difficulty::from_si(&zero.to_string()), None);
+        assert_eq!(Difficulty::from_hash(&BlockHash::all_zeros()), zero);
+
+        // The smallest non-zero target is the hardest finite difficulty
+        // (~2.7e67); it is large but still a number with a suffix.
+        let one = Difficulty::from_target(Target::from_le_bytes({
+            let mut bytes = [0; 32];
+            bytes[0] = 1;
+            bytes
+        }));
+        let shown = one.to_string();
+        assert!(shown.ends_with('P'), "{shown}");
+        assert!(shown[..shown.len() - 1].parse::<f64>().unwrap().is_finite());
+        assert!(one.format_with_precision(3).ends_with('P'));
+
+        // The largest target is the easiest difficulty, well above zero.
+        let easiest = Difficulty::from_target(Target::from_le_bytes([0xff; 32]));
+        let shown = easiest.to_string();
+        assert!(shown.starts_with("0.0000000002"), "{shown}");
+        assert_ne!(easiest.format_with_precision(3), "0");
+        assert_eq!(Difficulty::from_target(Target::MAX).to_string(), "1");
+    }
+
+    #[test]
+    fn test_zero_hash_displays_as_unbounded() {
+        let zero_hash = Difficulty::from_hash(&BlockHash::all_zeros());
+        assert_eq!(format!("{zero_hash}"), "inf");
+        assert_eq!(zero_hash.format_with_precision(4), "inf");
+
+        // The API carries it as f64::MAX; clients reading that back, as
+        // the CLI and AxeOS endpoint do, show the same sentinel.
+        let carried = zero_hash.as_f64();
+        assert_eq!(carried, f64::MAX);
+        let shown = Difficulty::from_share_difficulty(carried).unwrap();
+        assert_eq!(shown.to_string(), Difficulty::UNBOUNDED);
+    }
+
     #[test]
     fn test_from_f64_extreme_values() {
         // Enormous values must not panic (divisor overflow guard)
@@ -496,4 +875,103 @@ mod tests {
         assert_eq!(Difficulty::from_f64(-1.0).to_target(), Target::MAX);
         assert_eq!(Difficulty::from_f64(0.0).to_target(), Target::MAX);
     }
+
+    /// Difficulties from 1e-9 to about 1e19, several per decade with
+    /// uneven mantissas, standing in for arbitrary inputs.
+    fn sweep() -> impl Iterator<Item = f64> {
+        (-9..=18).flat_map(|exp| {
+            [1.0, 1.234_567, 2.0, 3.162_277_66, 5.5, 7.999_999, 9.87]
+                .map(move |mantissa| mantissa * 10_f64.powi(exp))
+        })
+    }
+
+    fn relative_error(actual: f64, expected: f64) -> f64 {
+        (actual - expected).abs() / expected
+    }
+
+    #[test]
+    fn conversions_agree_across_the_range() {
+        for d in sweep() {
+            let diff = Difficulty::from_f64(d);
+            let back = diff.as_f64();
+            assert!(relative_error(back, d) < 1e-11, "{d} read back as {back}");
+
+            // Target and hash views of the same value are one difficulty.
+            let target = diff.to_target();
+            assert_eq!(Difficulty::from_target(target), diff, "{d}");
+            let hash = BlockHash::from_byte_array(target.to_le_bytes());
+            assert_eq!(Difficulty::from_hash(&hash), diff, "{d}");
+            assert!(target.is_met_by(hash), "{d}");
+
+            // Display rounds to three significant digits from 1 up, and
+            // keeps twelve below.
+            let shown = diff.to_string();
+            let parsed = Difficulty::from_si(&shown).unwrap().as_f64();
+            let tolerance = if d < 1.0 { 1e-11 } else { 5e-3 };
+            assert!(
+                relative_error(parsed, d) <= tolerance,
+                "{d} shown as {shown}, parsed as {parsed}"
+            );
+
+            let hashes = diff.expected_hashes();
+            let again = Difficulty::from_expected_hashes(hashes).as_f64();
+            assert!(relative_error(again, d) < 1e-11, "{d} via {hashes} hashes");
+
+            // Exact work stays within 1/65535 of the 2^32 convention. Below
+            // difficulty 1 work is a handful of hashes, too few for the
+            // integer division to compare.
+            if d >= 1.0 {
+                let work = U256::from_le_bytes(target.to_work().to_le_bytes()).to_f64_approx();
+                let gap = work / hashes - 1.0;
+                assert!((0.0..=1.0 / 65535.0 + 1e-9).contains(&gap), "{d}: {gap:e}");
+            }
+        }
+    }
+
+    #[test]
+    fn conversions_preserve_order() {
+        let diffs: Vec<_> = sweep().map(Difficulty::from_f64).collect();
+        for pair in diffs.windows(2) {
+            let [easier, harder] = pair else {
+                unreachable!()
+            };
+            assert!(easier < harder, "{easier} vs {harder}");
+            assert!(easier.to_target() > harder.to_target());
+            assert!(easier.expected_hashes() < harder.expected_hashes());
+            let shown = |d: &Difficulty| Difficulty::from_si(&d.to_string()).unwrap();
+            assert!(shown(easier) <= shown(harder), "{easier} vs {harder}");
+        }
+    }
+
+    #[test]
+    fn hash_counts_use_exact_constants() {
+        assert_eq!(Difficulty::HASHES_PER_DIFFICULTY, 2f64.powi(32));
+        assert_eq!(Difficulty::from(1).expected_hashes(), 4_294_967_296.0);
+
+        // Difficulty 1's exact work is 2^256 / (0xffff * 2^208 + 1), just
+        // under 2^48 / 0xffff: the 2^32 convention times 65536/65535.
+        let work = U256::from_le_bytes(Target::MAX.to_work().to_le_bytes()).to_f64_approx();
+        let ratio = work / Difficulty::HASHES_PER_DIFFICULTY;
+        assert!((ratio - 65536.0 / 65535.0).abs() < 1e-12, "{ratio}");
+    }
+
+    #[test]
+    fn hash_exactly_at_the_target_meets_it() {
+        for d in sweep() {
+            let target = Difficulty::from_f64(d).to_target();
+            let at = U256::from_le_bytes(target.to_le_bytes());
+            let (mut below, mut above) = (at, at);
+            below -= U256::from(1);
+            above += U256::from(1);
+            let hash = |value: U256| BlockHash::from_byte_array(value.to_le_bytes());
+            let required = Difficulty::from_target(target);
+
+            for (value, meets) in [(below, true), (at, true), (above, false)] {
+                let hash = hash(value);
+                assert_eq!(target.is_met_by(hash), meets, "{d}");
+                // The difficulty view agrees with the target view.
+                assert_eq!(Difficulty::from_hash(&hash) >= required, meets, "{d}");
+            }
+        }
+    }
 }
@@ -0,0 +1,155 @@
+//! Thousands separators for integers shown to people.
+//!
+//! A share count of 1234567 reads more easily as 1,234,567, but a script
+//! scraping the output may not expect the separator, so grouping is off
+//! unless `MUJINA_NUMBER_GROUPING` picks one. It applies only where a
+//! person reads the number: JSON fields, CSV columns and structured log
+//! fields stay plain, and SI-suffixed figures such as hashrate and
+//! difficulty are already short.
+
+use std::sync::LazyLock;
+use std::{env, fmt};
+
+use crate::tracing::prelude::*;
+
+/// Separator placed between groups of three digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberGrouping {
+    /// No separator: 1234567.
+    #[default]
+    Off,
+    /// 1,234,567.
+    Comma,
+    /// 1.234.567.
+    Period,
+    /// 1 234 567.
+    Space,
+}
+
+impl NumberGrouping {
+    /// The grouping chosen with `MUJINA_NUMBER_GROUPING`, read once per
+    /// process.
+    pub fn configured() -> Self {
+        static GROUPING: LazyLock<NumberGrouping> = LazyLock::new(NumberGrouping::from_env);
+        *GROUPING
+    }
+
+    /// Look up a grouping by name, in any case: `off`, `comma`, `period`
+    /// or `space`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "off" | "none" => Some(Self::Off),
+            "comma" => Some(Self::Comma),
+            "period" | "dot" => Some(Self::Period),
+            "space" => Some(Self::Space),
+            _ => None,
+        }
+    }
+
+    /// Read the grouping from `MUJINA_NUMBER_GROUPING`, warning and
+    /// leaving numbers ungrouped on an unrecognized value.
+    pub fn from_env() -> Self {
+        match env::var("MUJINA_NUMBER_GROUPING") {
+            Ok(value) => Self::from_name(&value).unwrap_or_else(|| {
+                warn!(value = %value, "Invalid MUJINA_NUMBER_GROUPING, not grouping");
+                Self::Off
+            }),
+            Err(_) => Self::Off,
+        }
+    }
+
+    fn separator(self) -> Option<char> {
+        match self {
+            Self::Off => None,
+            Self::Comma => Some(','),
+            Self::Period => Some('.'),
+            Self::Space => Some(' '),
+        }
+    }
+
+    /// Format `value` with this grouping.
+    pub fn display(self, value: u64) -> GroupedNumber {
+        GroupedNumber {
+            value,
+            grouping: self,
+        }
+    }
+}
+
+/// An integer formatted with a chosen grouping, from
+/// [`NumberGrouping::display`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupedNumber {
+    value: u64,
+    grouping: NumberGrouping,
+}
+
+impl fmt::Display for GroupedNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.value.to_string();
+        let Some(separator) = self.grouping.separator() else {
+            return f.write_str(&digits);
+        };
+        let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        f.write_str(&grouped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use super::*;
+
+    #[test]
+    fn groups_of_three_from_the_right() {
+        let cases = [
+            (0, "0", "0"),
+            (999, "999", "999"),
+            (1_000, "1000", "1,000"),
+            (65_536, "65536", "65,536"),
+            (1_234_567, "1234567", "1,234,567"),
+            (
+                u64::MAX,
+                "18446744073709551615",
+                "18,446,744,073,709,551,615",
+            ),
+        ];
+        for (value, plain, comma) in cases {
+            assert_eq!(NumberGrouping::Off.display(value).to_string(), plain);
+            assert_eq!(NumberGrouping::Comma.display(value).to_string(), comma);
+        }
+        assert_eq!(
+            NumberGrouping::Period.display(1_234_567).to_string(),
+            "1.234.567"
+        );
+        assert_eq!(
+            NumberGrouping::Space.display(1_234_567).to_string(),
+            "1 234 567"
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn grouping_from_env() {
+        let var = "MUJINA_NUMBER_GROUPING";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(NumberGrouping::from_env(), NumberGrouping::Off);
+            env::set_var(var, "Comma");
+            assert_eq!(NumberGrouping::from_env(), NumberGrouping::Comma);
+            env::set_var(var, "space");
+            assert_eq!(NumberGrouping::from_env(), NumberGrouping::Space);
+            env::set_var(var, "en_US");
+            assert_eq!(NumberGrouping::from_env(), NumberGrouping::Off);
+            env::remove_var(var);
+        }
+    }
+}
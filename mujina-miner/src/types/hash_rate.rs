@@ -5,8 +5,8 @@
 //! single miner's capability. Not intended for representing
 //! aggregate network hashrate.
 //!
-//! Arithmetic follows Rust's default overflow behavior: panics in
-//! debug builds, wraps in release builds.
+//! Addition saturates at `u64::MAX`, so summing rates across a fleet
+//! never wraps to a small number.
 
 use std::iter::Sum;
 use std::ops::Add;
@@ -102,7 +102,7 @@ impl Add for HashRate {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self {
-        Self(self.0 + rhs.0)
+        Self(self.0.saturating_add(rhs.0))
     }
 }
 
@@ -159,6 +159,14 @@ mod tests {
         assert_eq!(total, HashRate::from_megahashes(600.0));
     }
 
+    #[test]
+    fn sum_saturates_instead_of_wrapping() {
+        let near_max = HashRate::from(u64::MAX - 10);
+        assert_eq!(near_max + HashRate::from(5), HashRate::from(u64::MAX - 5));
+        let total: HashRate = [near_max, near_max, HashRate::from(100)].into_iter().sum();
+        assert_eq!(total, HashRate::from(u64::MAX));
+    }
+
     #[test]
     fn sum_empty_iterator() {
         let total: HashRate = std::iter::empty().sum();
@@ -1,23 +1,54 @@
 //! Windowed hashrate estimation from share work.
 //!
 //! Estimates hashrate by accumulating work from shares within a
-//! sliding time window. Each share records its expected work (from
-//! `Target::to_work()`), and the estimator divides total work by
-//! the span from the oldest sample to the current time.
+//! sliding time window. Each share records its expected work, the mean
+//! number of hashes to meet its target:
+//!
+//! ```text
+//! work     = 2^256 / (share_target + 1)      (Target::to_work)
+//! hashrate = sum(work) / (now - oldest share)
+//! ```
+//!
+//! This is exact, not the `difficulty * 2^32` convention, which is lower
+//! by up to 1/65535 (see [`Difficulty`](super::Difficulty)). Hash threads
+//! report each share with its target's work, so what they hash and what
+//! is reported agree.
 //!
 //! This gives an accurate estimate as soon as enough samples exist,
 //! without waiting for the full window to fill. If shares stop
 //! arriving, the span grows to include the silent period and the
 //! estimate declines naturally.
+//!
+//! Work is summed saturating. Shares against a near-zero target carry
+//! nearly 2^256 of work each, so a few could otherwise overflow even 256
+//! bits; a saturated window just reports the maximum hashrate until
+//! those samples leave it.
+//!
+//! All timestamps are monotonic [`Instant`]s, so a wall-clock step (NTP,
+//! manual change) cannot shrink or invert the span. A caller passing a
+//! time earlier than the recorded samples gets a zero estimate, not a
+//! negative span.
+//!
+//! A handful of shares says little: with share arrival a Poisson process,
+//! an estimate from n shares is off by about 1/sqrt(n). Until the window
+//! holds the minimum number of samples, [`HashrateEstimator::settled_hashrate`]
+//! withholds the estimate so callers keep a better one. The scheduler
+//! takes its minimum from `MUJINA_HASHRATE_MIN_SHARES`.
 
 use std::collections::VecDeque;
+use std::env;
 use std::time::{Duration, Instant};
 
 use bitcoin::pow::Work;
 
 use super::HashRate;
+use crate::tracing::prelude::*;
 use crate::u256::U256;
 
+/// Samples an estimate needs to settle unless configured otherwise,
+/// enough to be within about half of the true rate.
+pub const DEFAULT_MIN_SAMPLES: usize = 5;
+
 /// Windowed hashrate estimator.
 ///
 /// Tracks recent share work in a fixed-duration sliding window and
@@ -33,12 +64,33 @@ pub struct HashrateEstimator {
 impl HashrateEstimator {
     /// Create an estimator with the given measurement window.
     ///
-    /// Uses reasonable defaults: settled threshold of 5 samples,
-    /// capacity of `window_secs * 10` (assumes at most 10
-    /// samples/sec).
+    /// Uses reasonable defaults: settled threshold of
+    /// [`DEFAULT_MIN_SAMPLES`], capacity of `window_secs * 10` (assumes
+    /// at most 10 samples/sec).
     pub fn new(window: Duration) -> Self {
+        Self::with_min_samples(window, DEFAULT_MIN_SAMPLES)
+    }
+
+    /// Create an estimator that settles after `min_samples`, with the
+    /// default capacity.
+    pub fn with_min_samples(window: Duration, min_samples: usize) -> Self {
         let max_samples = window.as_secs() as usize * 10;
-        Self::with_limits(window, 5, max_samples)
+        Self::with_limits(window, min_samples, max_samples)
+    }
+
+    /// Read the settled threshold from `MUJINA_HASHRATE_MIN_SHARES`,
+    /// warning and using [`DEFAULT_MIN_SAMPLES`] on invalid values.
+    pub fn min_samples_from_env() -> usize {
+        let Ok(val) = env::var("MUJINA_HASHRATE_MIN_SHARES") else {
+            return DEFAULT_MIN_SAMPLES;
+        };
+        match val.parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                warn!(value = %val, "Invalid MUJINA_HASHRATE_MIN_SHARES, using default");
+                DEFAULT_MIN_SAMPLES
+            }
+        }
     }
 
     /// Create an estimator with explicit limits.
@@ -52,7 +104,8 @@ impl HashrateEstimator {
         }
     }
 
-    /// Record work from a share at the current time.
+    /// Record work from a share at the current time. `work` is the share
+    /// target's [`Target::to_work`](bitcoin::pow::Target::to_work).
     pub fn record(&mut self, work: Work) {
         self.record_at(Instant::now(), work);
     }
@@ -62,12 +115,12 @@ impl HashrateEstimator {
         let work = U256::from(work);
         self.prune_before(at.checked_sub(self.window).unwrap_or(at));
         self.samples.push_back((at, work));
-        self.total_work += work;
+        self.total_work = self.total_work.saturating_add(work);
 
         // Enforce capacity limit on top of time-based pruning
         while self.samples.len() > self.max_samples {
             if let Some((_, old_work)) = self.samples.pop_front() {
-                self.total_work -= old_work;
+                self.forget(old_work);
             }
         }
     }
@@ -110,14 +163,28 @@ impl HashrateEstimator {
         self.samples.len() >= self.min_samples
     }
 
+    /// Samples within the window as of the last record or estimate.
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
     /// Returns the measured hashrate if the estimator has settled,
     /// or `None` if not enough samples have been collected yet.
     pub fn settled_hashrate(&mut self) -> Option<HashRate> {
-        if self.is_settled() {
-            Some(self.hashrate())
-        } else {
-            None
-        }
+        self.settled_hashrate_at(Instant::now())
+    }
+
+    /// Like [`settled_hashrate`](Self::settled_hashrate), at the given
+    /// timestamp. Samples that have left the window don't count.
+    pub fn settled_hashrate_at(&mut self, now: Instant) -> Option<HashRate> {
+        let rate = self.hashrate_at(now);
+        self.is_settled().then_some(rate)
+    }
+
+    /// Forget every sample, as if newly created.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.total_work = U256::ZERO;
     }
 
     /// Remove samples older than `cutoff`, subtracting their work.
@@ -126,8 +193,21 @@ impl HashrateEstimator {
             if t >= cutoff {
                 break;
             }
-            self.total_work -= work;
             self.samples.pop_front();
+            self.forget(work);
+        }
+    }
+
+    /// Take a removed sample's work off the total.
+    fn forget(&mut self, work: U256) {
+        if self.total_work == U256::MAX {
+            // Saturated, so the total says nothing about what remains.
+            self.total_work = self
+                .samples
+                .iter()
+                .fold(U256::ZERO, |total, &(_, work)| total.saturating_add(work));
+        } else {
+            self.total_work -= work;
         }
     }
 }
@@ -167,6 +247,41 @@ mod tests {
         assert_eq!(u64::from(est.hashrate_at(now)), 0);
     }
 
+    #[test]
+    fn query_before_samples_is_zero() {
+        let mut est = HashrateEstimator::new(Duration::from_secs(100));
+        let base = Instant::now();
+        est.record_at(base + Duration::from_secs(10), work(1000));
+        est.record_at(base + Duration::from_secs(20), work(1000));
+
+        // As if time stepped back past every sample: the span saturates to
+        // zero rather than going negative.
+        assert_eq!(u64::from(est.hashrate_at(base)), 0);
+        assert_eq!(
+            u64::from(est.hashrate_at(base + Duration::from_secs(30))),
+            100
+        );
+    }
+
+    #[test]
+    fn out_of_order_sample_prunes_cleanly() {
+        let mut est = HashrateEstimator::new(Duration::from_secs(100));
+        let base = Instant::now();
+        est.record_at(base + Duration::from_secs(20), work(1000));
+        est.record_at(base + Duration::from_secs(10), work(1000));
+
+        let rate = u64::from(est.hashrate_at(base + Duration::from_secs(40)));
+        assert!(rate > 0);
+
+        // Once the window passes both samples, the running total drains
+        // to zero without underflow.
+        assert_eq!(
+            u64::from(est.hashrate_at(base + Duration::from_secs(500))),
+            0
+        );
+        assert!(!est.has_samples());
+    }
+
     #[test]
     fn single_sample_zero_span() {
         let mut est = HashrateEstimator::new(Duration::from_secs(100));
@@ -332,6 +447,78 @@ mod tests {
         assert!(est.is_settled());
     }
 
+    #[test]
+    fn near_max_work_saturates_without_wrapping() {
+        let mut est = HashrateEstimator::with_limits(Duration::from_secs(100), 1, 1000);
+        let base = Instant::now();
+        let at = |secs| base + Duration::from_secs(secs);
+        let huge = Work::from_le_bytes([0xff; 32]);
+
+        // Two shares of nearly 2^256 work each overflow 256 bits.
+        est.record_at(at(0), huge);
+        est.record_at(at(10), huge);
+        assert_eq!(u64::from(est.hashrate_at(at(20))), u64::MAX);
+
+        // One leaving the saturated window doesn't wrap the total either.
+        est.record_at(at(60), work(1000));
+        est.record_at(at(80), work(1000));
+        assert_eq!(u64::from(est.hashrate_at(at(105))), u64::MAX);
+
+        // Once both are gone the estimate is exact again.
+        assert_eq!(u64::from(est.hashrate_at(at(160))), 20);
+    }
+
+    #[test]
+    fn withholds_the_estimate_below_the_minimum_shares() {
+        let mut est = HashrateEstimator::with_min_samples(Duration::from_secs(100), 8);
+        let base = Instant::now();
+        let at = |secs| base + Duration::from_secs(secs);
+
+        // Seven shares: a rate can be computed but isn't reported.
+        for i in 0..7 {
+            est.record_at(at(i * 10), work(1000));
+        }
+        assert_eq!(est.sample_count(), 7);
+        assert_eq!(u64::from(est.hashrate_at(at(70))), 100);
+        assert_eq!(est.settled_hashrate_at(at(70)), None);
+
+        // The eighth settles it.
+        est.record_at(at(70), work(1000));
+        assert_eq!(
+            est.settled_hashrate_at(at(80)).map(u64::from),
+            Some(8000 / 80)
+        );
+
+        // Shares leaving the window unsettle it again, even with no new
+        // share recorded in between.
+        assert_eq!(est.settled_hashrate_at(at(105)), None);
+        assert_eq!(est.sample_count(), 7);
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn min_samples_from_env() {
+        let var = "MUJINA_HASHRATE_MIN_SHARES";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(
+                HashrateEstimator::min_samples_from_env(),
+                DEFAULT_MIN_SAMPLES
+            );
+            env::set_var(var, "20");
+            assert_eq!(HashrateEstimator::min_samples_from_env(), 20);
+            for invalid in ["0", "many"] {
+                env::set_var(var, invalid);
+                assert_eq!(
+                    HashrateEstimator::min_samples_from_env(),
+                    DEFAULT_MIN_SAMPLES
+                );
+            }
+            env::remove_var(var);
+        }
+    }
+
     #[test]
     fn settled_hashrate_none_before_settled() {
         let mut est = HashrateEstimator::with_limits(Duration::from_secs(100), 3, 1000);
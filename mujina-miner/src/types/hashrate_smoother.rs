@@ -0,0 +1,175 @@
+//! Smoothing for displayed hashrate.
+//!
+//! A windowed estimate moves with every share that enters or leaves the
+//! window, so a number printed from it flickers. For display only, an
+//! exponential moving average is run over successive estimates: each new
+//! one moves the shown value by a fixed fraction of the difference.
+//! Metrics and the API keep the windowed estimate itself, which is the
+//! better measurement; smoothing only lags it.
+
+use std::env;
+
+use super::HashRate;
+use crate::tracing::prelude::*;
+
+/// Weight given to each new estimate when not configured.
+pub const DEFAULT_FACTOR: f64 = 0.3;
+
+/// Exponential moving average over hashrate estimates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashrateSmoother {
+    factor: f64,
+    value: Option<f64>,
+}
+
+impl HashrateSmoother {
+    /// Smooth with `factor`, the weight in (0, 1] given to each new
+    /// estimate; 1 shows estimates unsmoothed. Out of range falls back to
+    /// [`DEFAULT_FACTOR`].
+    pub fn new(factor: f64) -> Self {
+        let factor = if factor > 0.0 && factor <= 1.0 {
+            factor
+        } else {
+            DEFAULT_FACTOR
+        };
+        Self {
+            factor,
+            value: None,
+        }
+    }
+
+    /// Smooth with the factor in `MUJINA_HASHRATE_SMOOTHING`, warning and
+    /// using the default when invalid.
+    pub fn from_env() -> Self {
+        let Ok(val) = env::var("MUJINA_HASHRATE_SMOOTHING") else {
+            return Self::new(DEFAULT_FACTOR);
+        };
+        match val.parse::<f64>() {
+            Ok(factor) if factor > 0.0 && factor <= 1.0 => Self::new(factor),
+            _ => {
+                warn!(value = %val, "Invalid MUJINA_HASHRATE_SMOOTHING, using default");
+                Self::new(DEFAULT_FACTOR)
+            }
+        }
+    }
+
+    /// The weight given to each new estimate.
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    /// Fold in a new estimate and return the value to display. The first
+    /// estimate is shown as it is.
+    pub fn update(&mut self, estimate: HashRate) -> HashRate {
+        let estimate = f64::from(estimate);
+        let value = match self.value {
+            Some(value) => value + self.factor * (estimate - value),
+            None => estimate,
+        };
+        self.value = Some(value);
+        HashRate::from(value.round() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serial_test::serial;
+
+    use super::*;
+    use crate::types::{HashrateEstimator, Work};
+
+    /// Work worth `n` hashes.
+    fn work(n: u64) -> Work {
+        let mut bytes = [0u8; 32];
+        bytes[..8].copy_from_slice(&n.to_le_bytes());
+        Work::from_le_bytes(bytes)
+    }
+
+    #[test]
+    fn smoothed_display_converges_and_jitters_less_than_the_window() {
+        const RATE: f64 = 1e9;
+        const SHARE_WORK: u64 = 2_000_000_000;
+
+        let start = Instant::now();
+        let mut estimator = HashrateEstimator::new(Duration::from_secs(60));
+        let mut smoother = HashrateSmoother::new(DEFAULT_FACTOR);
+
+        // Shares at Poisson arrivals averaging 2 s apart, from a fixed
+        // seed so the run is the same every time.
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut uniform = move || {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((seed >> 11) as f64 + 1.0) / (1u64 << 53) as f64
+        };
+        let mean_gap = SHARE_WORK as f64 / RATE;
+        let mut next_share = 0.0;
+
+        let (mut raw, mut shown) = (Vec::new(), Vec::new());
+        for tick in 1..=720 {
+            let now = tick as f64 * 5.0;
+            while next_share <= now {
+                estimator.record_at(
+                    start + Duration::from_secs_f64(next_share),
+                    work(SHARE_WORK),
+                );
+                next_share += -uniform().ln() * mean_gap;
+            }
+            let estimate = estimator.hashrate_at(start + Duration::from_secs_f64(now));
+            raw.push(f64::from(estimate));
+            shown.push(f64::from(smoother.update(estimate)));
+        }
+
+        // Past the first windows, both average near the true rate.
+        let settled = 60..raw.len();
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+        for values in [&raw[settled.clone()], &shown[settled.clone()]] {
+            let error = (mean(values) - RATE).abs() / RATE;
+            assert!(error < 0.1, "mean {} vs {RATE}", mean(values));
+        }
+
+        // The shown value moves much less from one display to the next.
+        let jitter = |values: &[f64]| {
+            values.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>() / values.len() as f64
+        };
+        let (raw_jitter, shown_jitter) = (jitter(&raw[settled.clone()]), jitter(&shown[settled]));
+        assert!(
+            shown_jitter < raw_jitter * 0.6,
+            "shown {shown_jitter} vs raw {raw_jitter}"
+        );
+    }
+
+    #[test]
+    fn first_estimate_is_shown_as_is_and_factor_one_disables() {
+        let mut smoother = HashrateSmoother::new(0.5);
+        assert_eq!(smoother.update(HashRate::from(100)), HashRate::from(100));
+        assert_eq!(smoother.update(HashRate::from(200)), HashRate::from(150));
+
+        let mut raw = HashrateSmoother::new(1.0);
+        raw.update(HashRate::from(100));
+        assert_eq!(raw.update(HashRate::from(200)), HashRate::from(200));
+    }
+
+    #[test]
+    #[serial]
+    fn factor_from_env() {
+        let var = "MUJINA_HASHRATE_SMOOTHING";
+        // SAFETY: Test runs serially, no concurrent env access
+        unsafe {
+            env::remove_var(var);
+            assert_eq!(HashrateSmoother::from_env().factor(), DEFAULT_FACTOR);
+            env::set_var(var, "0.1");
+            assert_eq!(HashrateSmoother::from_env().factor(), 0.1);
+            env::set_var(var, "1");
+            assert_eq!(HashrateSmoother::from_env().factor(), 1.0);
+            for bad in ["0", "1.5", "smooth"] {
+                env::set_var(var, bad);
+                assert_eq!(HashrateSmoother::from_env().factor(), DEFAULT_FACTOR);
+            }
+            env::remove_var(var);
+        }
+    }
+}
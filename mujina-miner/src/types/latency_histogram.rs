@@ -0,0 +1,135 @@
+//! Coarse histogram of how long something took.
+//!
+//! By default buckets grow by powers of ten, from 100 µs to 1 s with a
+//! last bucket for anything slower, which is enough to tell a loop that
+//! keeps up from one that now and then stalls for a whole I2C
+//! transaction. What is timed on a slower scale, such as a pool's answers
+//! to submits, brings bounds of its own. Counts are cumulative from
+//! creation.
+
+use std::time::Duration;
+
+/// Default upper bounds of the buckets, each holding durations up to and
+/// including it; past the last is one more bucket.
+pub const BUCKET_BOUNDS: [Duration; 5] = [
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+];
+
+/// Counts of durations by bucket, with their total and maximum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    bounds: &'static [Duration],
+    counts: Vec<u64>,
+    total: Duration,
+    max: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::with_bounds(&BUCKET_BOUNDS)
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A histogram with buckets up to each of `bounds`, which must
+    /// ascend, and one more past the last.
+    pub fn with_bounds(bounds: &'static [Duration]) -> Self {
+        debug_assert!(bounds.is_sorted(), "bucket bounds must ascend");
+        Self {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+        }
+    }
+
+    /// Count one duration.
+    pub fn record(&mut self, took: Duration) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| took <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket] = self.counts[bucket].saturating_add(1);
+        self.total = self.total.saturating_add(took);
+        self.max = self.max.max(took);
+    }
+
+    /// Durations recorded.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().fold(0, |sum, n| sum.saturating_add(*n))
+    }
+
+    /// Average of the durations recorded, zero before any.
+    pub fn mean(&self) -> Duration {
+        match self.count() {
+            0 => Duration::ZERO,
+            n => Duration::from_nanos((self.total.as_nanos() / u128::from(n)) as u64),
+        }
+    }
+
+    /// Longest duration recorded.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Each bucket's upper bound, `None` for the last, with its count.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        self.bounds
+            .iter()
+            .copied()
+            .map(Some)
+            .chain([None])
+            .zip(self.counts.iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_land_in_their_buckets() {
+        let mut histogram = LatencyHistogram::new();
+        assert_eq!(histogram.mean(), Duration::ZERO);
+        for took in [
+            Duration::from_micros(20),
+            Duration::from_micros(100),
+            Duration::from_micros(101),
+            Duration::from_millis(250),
+            Duration::from_secs(3),
+        ] {
+            histogram.record(took);
+        }
+
+        let counts: Vec<u64> = histogram.buckets().map(|(_, n)| n).collect();
+        assert_eq!(counts, [2, 1, 0, 0, 1, 1]);
+        assert_eq!(histogram.buckets().last(), Some((None, 1)));
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.max(), Duration::from_secs(3));
+        assert_eq!(histogram.mean(), Duration::from_nanos(650_044_200));
+    }
+
+    #[test]
+    fn buckets_follow_the_bounds_given() {
+        const BOUNDS: [Duration; 2] = [Duration::from_secs(1), Duration::from_secs(30)];
+        let mut histogram = LatencyHistogram::with_bounds(&BOUNDS);
+        for secs in [1, 3, 29, 31] {
+            histogram.record(Duration::from_secs(secs));
+        }
+
+        let buckets: Vec<_> = histogram.buckets().collect();
+        assert_eq!(
+            buckets,
+            [(Some(BOUNDS[0]), 1), (Some(BOUNDS[1]), 2), (None, 1)]
+        );
+    }
+}
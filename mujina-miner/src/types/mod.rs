@@ -5,32 +5,41 @@
 //! mining-specific types.
 
 mod bitcoin_impls;
+mod daily_count;
 mod debounced_alarm;
 mod difficulty;
+mod grouping;
 mod hash_rate;
 mod hashrate_estimator;
+mod hashrate_smoother;
+mod latency_histogram;
 mod share_rate;
 mod temperature;
+mod time_to_block;
 
 use std::time::Duration;
 
 // Re-export frequently used bitcoin types for convenience
 pub use bitcoin::block::Header as BlockHeader;
 pub use bitcoin::{Amount, BlockHash, Network, Target, Transaction, TxOut, Work};
+pub use daily_count::{DailyCount, DayBoundary, parse_utc_offset};
 pub use debounced_alarm::{AlarmStatus, DebouncedAlarm};
 pub use difficulty::Difficulty;
+pub use grouping::{GroupedNumber, NumberGrouping};
 pub use hash_rate::HashRate;
-pub use hashrate_estimator::HashrateEstimator;
+pub use hashrate_estimator::{DEFAULT_MIN_SAMPLES, HashrateEstimator};
+pub use hashrate_smoother::HashrateSmoother;
+pub use latency_histogram::{BUCKET_BOUNDS, LatencyHistogram};
 pub use share_rate::ShareRate;
-pub use temperature::Temperature;
+pub use temperature::{DisplayTemperature, Temperature, TemperatureUnit};
+pub use time_to_block::{HumanDuration, time_to_block};
 
 /// Calculate expected time between shares at given difficulty and hashrate.
 ///
 /// Returns the average time to find a share. Actual time varies due to
 /// randomness in hash mining.
 pub fn expected_time_to_share(difficulty: Difficulty, hashrate: HashRate) -> Duration {
-    let hashes_per_share = difficulty.as_f64() * (u32::MAX as f64 + 1.0);
-    let shares_per_sec = f64::from(hashrate) / hashes_per_share;
+    let shares_per_sec = f64::from(hashrate) / difficulty.expected_hashes();
     if shares_per_sec <= 0.0 {
         return Duration::MAX;
     }
@@ -38,15 +47,11 @@ pub fn expected_time_to_share(difficulty: Difficulty, hashrate: HashRate) -> Dur
 }
 
 /// Calculate expected time between shares from a Target and hashrate.
-///
-/// Uses rust-bitcoin's `Target::difficulty_float()` directly, avoiding
-/// intermediate Difficulty conversion.
 pub fn expected_time_to_share_from_target(target: Target, hashrate: HashRate) -> Duration {
     if hashrate.0 == 0 {
         return Duration::MAX;
     }
-    let difficulty_float = target.difficulty_float();
-    let hashes_per_share = difficulty_float * (u32::MAX as f64 + 1.0);
+    let hashes_per_share = Difficulty::from_target(target).expected_hashes();
     let shares_per_sec = hashrate.0 as f64 / hashes_per_share;
     if shares_per_sec <= 0.0 {
         return Duration::MAX;
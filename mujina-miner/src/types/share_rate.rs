@@ -67,9 +67,8 @@ impl ShareRate {
     /// Compute the target needed to achieve this share rate at the
     /// given hashrate.
     ///
-    /// A hash is valid if hash < target. With hashes uniform over
-    /// [0, 2^256), expected hashes per share = 2^256 / target, so
-    /// target = 2^256 / hashes_per_share.
+    /// The inverse of [`Difficulty::expected_hashes`], so the expected
+    /// time to a share at the returned target is this rate's interval.
     ///
     /// The returned target may exceed `Target::MAX` (Bitcoin
     /// difficulty-1, ~2^224) at low hashrates. This is fine for
@@ -80,7 +79,7 @@ impl ShareRate {
         if hashes_per_share <= 1.0 {
             Target::from(U256::MAX)
         } else {
-            Target::from(U256::MAX / hashes_per_share)
+            Difficulty::from_expected_hashes(hashes_per_share).to_target()
         }
     }
 
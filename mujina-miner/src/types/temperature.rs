@@ -3,7 +3,10 @@
 mod conversions;
 
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::sync::LazyLock;
+use std::{env, fmt};
+
+use crate::tracing::prelude::*;
 
 /// Temperature in degrees Celsius.
 ///
@@ -28,14 +31,87 @@ impl Temperature {
     pub fn as_degrees_c(self) -> f32 {
         self.0
     }
+
+    /// Return the temperature in degrees Fahrenheit.
+    pub fn as_degrees_f(self) -> f32 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+
+    /// Format in the given unit.
+    pub fn display(self, unit: TemperatureUnit) -> DisplayTemperature {
+        DisplayTemperature {
+            temperature: self,
+            unit,
+        }
+    }
 }
 
+/// Always Celsius. Human-facing output should go through
+/// [`Temperature::display`] so the operator's unit applies.
 impl fmt::Display for Temperature {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:.1} C", self.0)
     }
 }
 
+/// Unit temperatures are shown to people in.
+///
+/// Only formatting changes: values, thresholds, and machine-readable
+/// output (JSON fields) stay in Celsius.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    /// The unit chosen with `MUJINA_TEMP_UNIT`, read once per process.
+    pub fn configured() -> Self {
+        static UNIT: LazyLock<TemperatureUnit> = LazyLock::new(TemperatureUnit::from_env);
+        *UNIT
+    }
+
+    /// Look up a unit by name: `C`, `F`, or spelled out, in any case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "c" | "celsius" => Some(Self::Celsius),
+            "f" | "fahrenheit" => Some(Self::Fahrenheit),
+            _ => None,
+        }
+    }
+
+    /// Read the unit from `MUJINA_TEMP_UNIT`, warning and falling back to
+    /// Celsius on an unrecognized value.
+    pub fn from_env() -> Self {
+        match env::var("MUJINA_TEMP_UNIT") {
+            Ok(value) => Self::from_name(&value).unwrap_or_else(|| {
+                warn!(value = %value, "Invalid MUJINA_TEMP_UNIT, using Celsius");
+                Self::Celsius
+            }),
+            Err(_) => Self::Celsius,
+        }
+    }
+}
+
+/// A temperature formatted in a chosen unit, from [`Temperature::display`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayTemperature {
+    temperature: Temperature,
+    unit: TemperatureUnit,
+}
+
+impl fmt::Display for DisplayTemperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unit {
+            TemperatureUnit::Celsius => write!(f, "{:.1} C", self.temperature.as_degrees_c()),
+            TemperatureUnit::Fahrenheit => {
+                write!(f, "{:.1} F", self.temperature.as_degrees_f())
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -65,4 +141,42 @@ mod tests {
         assert_eq!(t.as_degrees_c(), -40.0);
         assert_eq!(t.to_string(), "-40.0 C");
     }
+
+    #[test]
+    fn fahrenheit_conversion() {
+        assert_eq!(Temperature::from_celsius(0.0).as_degrees_f(), 32.0);
+        assert_eq!(Temperature::from_celsius(100.0).as_degrees_f(), 212.0);
+        assert_eq!(Temperature::from_celsius(-40.0).as_degrees_f(), -40.0);
+    }
+
+    #[test]
+    fn display_in_each_unit() {
+        let t = Temperature::from_celsius(42.5);
+        assert_eq!(t.display(TemperatureUnit::Celsius).to_string(), "42.5 C");
+        assert_eq!(
+            t.display(TemperatureUnit::Fahrenheit).to_string(),
+            "108.5 F"
+        );
+        // The value itself is untouched by the display unit.
+        assert_eq!(t.as_degrees_c(), 42.5);
+    }
+
+    #[test]
+    fn unit_names_parse() {
+        for name in ["C", "c", "celsius", "Celsius"] {
+            assert_eq!(
+                TemperatureUnit::from_name(name),
+                Some(TemperatureUnit::Celsius),
+                "{name}"
+            );
+        }
+        for name in ["F", "f", "fahrenheit", "FAHRENHEIT"] {
+            assert_eq!(
+                TemperatureUnit::from_name(name),
+                Some(TemperatureUnit::Fahrenheit),
+                "{name}"
+            );
+        }
+        assert_eq!(TemperatureUnit::from_name("kelvin"), None);
+    }
 }
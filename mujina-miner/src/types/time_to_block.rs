@@ -0,0 +1,117 @@
+//! Expected time for a hashrate to find a block.
+//!
+//! Each hash meets the network target with probability `1 / (difficulty *
+//! 2^32)`, so the mean time to a block is `difficulty * 2^32 / hashrate`.
+//! For a solo miner this is typically years; it is a mean over a memoryless
+//! process, so the chance of a block in any given day doesn't grow with time
+//! already spent.
+
+use std::fmt;
+use std::time::Duration;
+
+use super::{Difficulty, HashRate, Target};
+
+/// Mean time for `hashrate` to find a block at `network_target`, or `None`
+/// when nothing is hashing. Saturates at [`Duration::MAX`].
+pub fn time_to_block(hashrate: HashRate, network_target: Target) -> Option<Duration> {
+    if hashrate.is_zero() {
+        return None;
+    }
+    let hashes = Difficulty::from_target(network_target).expected_hashes();
+    let secs = hashes / hashrate.0 as f64;
+    Some(Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX))
+}
+
+/// A long duration in the largest unit that keeps it above one, such as
+/// "3.2 days" or "41.7 years".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HumanDuration(pub Duration);
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MINUTE: f64 = 60.0;
+        const HOUR: f64 = 60.0 * MINUTE;
+        const DAY: f64 = 24.0 * HOUR;
+        const YEAR: f64 = 365.25 * DAY;
+
+        let secs = self.0.as_secs_f64();
+        let years = secs / YEAR;
+        if years >= 1e9 {
+            write!(f, "{:.1} billion years", years / 1e9)
+        } else if years >= 1e6 {
+            write!(f, "{:.1} million years", years / 1e6)
+        } else if years >= 1e3 {
+            write!(f, "{:.1} thousand years", years / 1e3)
+        } else if years >= 1.0 {
+            write!(f, "{years:.1} years")
+        } else if secs >= DAY {
+            write!(f, "{:.1} days", secs / DAY)
+        } else if secs >= HOUR {
+            write!(f, "{:.1} hours", secs / HOUR)
+        } else if secs >= MINUTE {
+            write!(f, "{:.1} minutes", secs / MINUTE)
+        } else {
+            write!(f, "{secs:.0} seconds")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Difficulty;
+
+    #[test]
+    fn time_to_block_at_hand_computed_points() {
+        // Difficulty 1 at 2^32 H/s: one block a second on average.
+        let target = Difficulty::from(1).to_target();
+        let secs = time_to_block(HashRate(1 << 32), target)
+            .unwrap()
+            .as_secs_f64();
+        assert!((secs - 1.0).abs() < 1e-6, "{secs}");
+
+        // A 1.2 TH/s Bitaxe against difficulty 100T:
+        // 1e14 * 2^32 / 1.2e12 = 357,913,941,333 s, about 11,342 years.
+        let target = Difficulty::from(100_000_000_000_000).to_target();
+        let estimate = time_to_block(HashRate::from_terahashes(1.2), target).unwrap();
+        let secs = estimate.as_secs_f64();
+        assert!((secs - 357_913_941_333.3).abs() / secs < 1e-6, "{secs}");
+        assert_eq!(HumanDuration(estimate).to_string(), "11.3 thousand years");
+
+        // 500 PH/s at the same difficulty: 858,993 s, about 9.9 days.
+        let estimate = time_to_block(HashRate::from_terahashes(500_000.0), target).unwrap();
+        assert!((estimate.as_secs_f64() - 858_993.46).abs() < 0.01);
+        assert_eq!(HumanDuration(estimate).to_string(), "9.9 days");
+
+        // 5 EH/s: 85,899 s, just under a day.
+        let estimate = time_to_block(HashRate::from_terahashes(5_000_000.0), target).unwrap();
+        assert!((estimate.as_secs_f64() - 85_899.35).abs() < 0.01);
+        assert_eq!(HumanDuration(estimate).to_string(), "23.9 hours");
+    }
+
+    #[test]
+    fn time_to_block_is_undefined_without_hashrate() {
+        let target = Difficulty::from(1).to_target();
+        assert_eq!(time_to_block(HashRate(0), target), None);
+
+        // A CPU miner against the hardest target saturates rather than
+        // overflowing.
+        assert_eq!(
+            time_to_block(HashRate(1), Difficulty::from(u64::MAX).to_target()),
+            Some(Duration::MAX)
+        );
+    }
+
+    #[test]
+    fn human_duration_picks_the_largest_unit() {
+        let shown = |secs: u64| HumanDuration(Duration::from_secs(secs)).to_string();
+        assert_eq!(shown(42), "42 seconds");
+        assert_eq!(shown(90), "1.5 minutes");
+        assert_eq!(shown(5_400), "1.5 hours");
+        assert_eq!(shown(3 * 86_400 + 4 * 3_600), "3.2 days");
+        assert_eq!(shown(31_557_600 * 41 + 22_090_320), "41.7 years");
+        assert_eq!(shown(31_557_600 * 2_500), "2.5 thousand years");
+        assert_eq!(shown(31_557_600 * 2_500_000), "2.5 million years");
+        assert_eq!(shown(u64::MAX), "584.5 billion years");
+    }
+}
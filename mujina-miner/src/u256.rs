@@ -68,6 +68,11 @@ impl U256 {
         mantissa * (2.0_f64).powi((start * 8) as i32)
     }
 
+    /// Add, saturating at [`U256::MAX`] instead of overflowing.
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
     /// Number of leading zero bits.
     fn leading_zeros(self) -> u32 {
         self.0.leading_zeros() as u32
@@ -208,6 +213,16 @@ impl From<u64> for U256 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn saturating_add_stops_at_max() {
+        let half: U256 = U256::MAX / 2u64;
+        let mut below_max = U256::MAX;
+        below_max -= U256::from(1);
+        assert_eq!(half.saturating_add(half), below_max);
+        assert_eq!(U256::MAX.saturating_add(U256::from(1)), U256::MAX);
+        assert_eq!(U256::from(2).saturating_add(U256::from(3)), U256::from(5));
+    }
+
     #[test]
     fn test_division_u256() {
         let a = U256::from_le_bytes({